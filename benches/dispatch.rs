@@ -0,0 +1,66 @@
+//! 分发开销基准：对比旧的"信号量 + 每目标spawn + JoinHandle列表"模式与 `run_bounded` 工作池
+//!
+//! 运行：cargo bench --bench dispatch
+use gxr::utils::pool::run_bounded;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{Mutex, Semaphore};
+
+const TARGETS: u32 = 100_000;
+const CONCURRENCY: usize = 200;
+
+/// 模拟探测：不产生网络流量，仅让出一次调度
+async fn stub_probe(ip: u32) -> u32 {
+    tokio::task::yield_now().await;
+    ip
+}
+
+/// 旧模式：分发循环中获取许可，为每个目标spawn任务并保存JoinHandle
+async fn legacy_dispatch(targets: Vec<u32>) -> usize {
+    let sem = Arc::new(Semaphore::new(CONCURRENCY));
+    let results = Arc::new(Mutex::new(Vec::with_capacity(targets.len())));
+    let mut handles = Vec::with_capacity(targets.len());
+
+    for ip in targets {
+        let permit = sem.clone().acquire_owned().await.unwrap();
+        let results = results.clone();
+        handles.push(tokio::spawn(async move {
+            let r = stub_probe(ip).await;
+            results.lock().await.push(r);
+            drop(permit);
+        }));
+    }
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    results.lock().await.len()
+}
+
+/// 新模式：有界工作池 + 结果通道
+async fn pooled_dispatch(targets: Vec<u32>) -> usize {
+    let mut results = Vec::with_capacity(targets.len());
+    run_bounded(targets, CONCURRENCY, stub_probe, |r| results.push(r)).await;
+    results.len()
+}
+
+fn main() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let targets: Vec<u32> = (0..TARGETS).collect();
+
+    for round in 1..=3 {
+        let start = Instant::now();
+        let n = rt.block_on(legacy_dispatch(targets.clone()));
+        let legacy = start.elapsed();
+
+        let start = Instant::now();
+        let m = rt.block_on(pooled_dispatch(targets.clone()));
+        let pooled = start.elapsed();
+
+        assert_eq!(n, m);
+        println!(
+            "第{}轮 {} 个目标: spawn模式 {:?}, 工作池 {:?}",
+            round, TARGETS, legacy, pooled
+        );
+    }
+}
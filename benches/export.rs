@@ -0,0 +1,99 @@
+//! 大结果集导出基准：端口扫描结果表导出为Excel的速度及峰值内存
+//!
+//! 目标：100万行在30秒内导出，导出期间峰值内存增长不超过200MB（峰值内存仅Linux下统计）。
+//! 未达到目标时以非零状态退出，可在CI中直接运行。
+//!
+//! 运行：cargo bench --bench export [-- 行数]
+use gxr::utils::blocking::BlockingStage;
+use gxr::utils::output::OutputKind;
+use gxr::utils::{ExcelExport, ExcelOptions};
+use std::time::{Duration, Instant};
+
+const ROWS: usize = 1_000_000;
+const TARGET_TIME: Duration = Duration::from_secs(30);
+const TARGET_MEMORY_MB: u64 = 200;
+
+/// 模拟的端口扫描结果
+struct Row {
+    ip: String,
+    port: u16,
+    status: &'static str,
+    banner: String,
+}
+
+/// 进程的峰值常驻内存（MB），无法统计时为 `None`
+fn peak_rss_mb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb / 1024)
+}
+
+fn main() {
+    let rows = std::env::args()
+        .skip(1)
+        .find_map(|a| a.parse().ok())
+        .unwrap_or(ROWS);
+    let data: Vec<Row> = (0..rows)
+        .map(|i| Row {
+            ip: format!("10.{}.{}.{}", i >> 16, (i >> 8) & 255, i & 255),
+            port: [22, 80, 443, 3389, 8080][i % 5],
+            status: if i % 3 == 0 { "开放" } else { "关闭" },
+            banner: if i % 3 == 0 {
+                format!("SSH-2.0-OpenSSH_8.{}", i % 10)
+            } else {
+                String::new()
+            },
+        })
+        .collect();
+    let root = std::env::temp_dir().join(format!("gxr_bench_export_{}", std::process::id()));
+    let options = ExcelOptions {
+        output_root: root.clone(),
+        quiet: true,
+        ..Default::default()
+    };
+    let kind = OutputKind {
+        subdir: "bench",
+        prefix: "export",
+    };
+
+    let baseline = peak_rss_mb();
+    let start = Instant::now();
+    let path = ExcelExport::prepare_with(
+        &data,
+        &["IP地址", "端口", "状态", "服务", "证据"],
+        |r, row| {
+            row.cell(&r.ip)
+                .cell(r.port)
+                .cell(r.status)
+                .cell(&r.banner)
+                .cell("");
+        },
+        kind,
+        &options,
+    )
+    .run()
+    .unwrap();
+    let elapsed = start.elapsed();
+    let growth = baseline
+        .zip(peak_rss_mb())
+        .map(|(b, p)| p.saturating_sub(b));
+    let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    std::fs::remove_dir_all(&root).ok();
+
+    println!(
+        "导出 {} 行耗时 {:?}（{:.0} 行/秒），文件 {:.1}MB，峰值内存增长 {}",
+        rows,
+        elapsed,
+        rows as f64 / elapsed.as_secs_f64(),
+        size as f64 / 1024.0 / 1024.0,
+        growth.map_or("未统计".to_string(), |mb| format!("{}MB", mb))
+    );
+    if rows >= ROWS && (elapsed > TARGET_TIME || growth.is_some_and(|mb| mb > TARGET_MEMORY_MB)) {
+        eprintln!(
+            "未达到目标：{} 行需在 {:?} 内导出，峰值内存增长不超过 {}MB",
+            ROWS, TARGET_TIME, TARGET_MEMORY_MB
+        );
+        std::process::exit(1);
+    }
+}
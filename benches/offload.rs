@@ -0,0 +1,127 @@
+//! 后处理卸载基准：导出Excel期间模拟探测的时延波动，对比在运行时工作线程中直接导出与 `run_stage` 卸载到阻塞线程池
+//!
+//! 同时进行的导出数与工作线程数相同（如守护进程中多个扫描同时结束），直接导出时工作线程全部被占用。
+//!
+//! 运行：cargo bench --bench offload
+use gxr::utils::blocking::{BlockingStage, run_stage};
+use gxr::utils::output::OutputKind;
+use gxr::utils::{ExcelExport, ExcelOptions};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+const ROWS: usize = 50_000;
+const PROBES: usize = 64;
+const WORKERS: usize = 2;
+const PROBE_INTERVAL: Duration = Duration::from_millis(2);
+
+/// 模拟探测：按固定间隔等待，记录每次实际等待超出预期的时长（微秒）
+async fn probe_jitter(done: Arc<AtomicBool>) -> Vec<u64> {
+    let mut samples = Vec::new();
+    while !done.load(Ordering::Relaxed) {
+        let start = Instant::now();
+        tokio::time::sleep(PROBE_INTERVAL).await;
+        let overshoot = start.elapsed().saturating_sub(PROBE_INTERVAL);
+        samples.push(overshoot.as_micros() as u64);
+    }
+    samples
+}
+
+/// 导出期间运行一组模拟探测，返回全部时延样本
+async fn measure(offload: bool, root: &std::path::Path) -> (Duration, Vec<u64>) {
+    let rows: Vec<String> = (0..ROWS)
+        .map(|i| format!("10.{}.{}.{}", i >> 16, (i >> 8) & 255, i & 255))
+        .collect();
+    let options = ExcelOptions {
+        output_root: root.to_path_buf(),
+        quiet: true,
+        ..Default::default()
+    };
+    let kind = OutputKind {
+        subdir: "bench",
+        prefix: "offload",
+    };
+    let stages: Vec<ExcelExport> = (0..WORKERS)
+        .map(|_| {
+            ExcelExport::prepare(
+                &rows,
+                &["IP", "状态"],
+                |r| vec![r.clone(), "开放".to_string()],
+                kind,
+                &options,
+            )
+        })
+        .collect();
+
+    let done = Arc::new(AtomicBool::new(false));
+    let probes: Vec<_> = (0..PROBES)
+        .map(|_| tokio::spawn(probe_jitter(done.clone())))
+        .collect();
+    let start = Instant::now();
+    let exports: Vec<_> = stages
+        .into_iter()
+        .map(|stage| {
+            tokio::spawn(async move {
+                if offload {
+                    run_stage(stage).await.unwrap()
+                } else {
+                    // 在工作线程中直接生成Excel（旧的做法）
+                    stage.run().unwrap()
+                }
+            })
+        })
+        .collect();
+    for export in exports {
+        export.await.unwrap();
+    }
+    let export = start.elapsed();
+    done.store(true, Ordering::Relaxed);
+    let mut samples = Vec::new();
+    for probe in probes {
+        samples.extend(probe.await.unwrap());
+    }
+    (export, samples)
+}
+
+/// 样本的 (p50, p99, 最大值, 标准差)，单位微秒
+fn describe(samples: &mut [u64]) -> (u64, u64, u64, f64) {
+    samples.sort_unstable();
+    let at = |q: f64| samples[((samples.len() - 1) as f64 * q) as usize];
+    let mean = samples.iter().sum::<u64>() as f64 / samples.len() as f64;
+    let var = samples
+        .iter()
+        .map(|&s| (s as f64 - mean).powi(2))
+        .sum::<f64>()
+        / samples.len() as f64;
+    (at(0.5), at(0.99), *samples.last().unwrap(), var.sqrt())
+}
+
+fn main() {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(WORKERS)
+        .enable_all()
+        .build()
+        .unwrap();
+    let root = std::env::temp_dir().join(format!("gxr_bench_offload_{}", std::process::id()));
+
+    for round in 1..=3 {
+        for (name, offload) in [("工作线程中导出", false), ("阻塞线程池导出", true)] {
+            let (export, mut samples) = rt.block_on(measure(offload, &root));
+            let (p50, p99, max, stddev) = describe(&mut samples);
+            println!(
+                "第{}轮 {}: 导出 {}×{} 行耗时 {:?}，探测时延超出 p50 {}µs, p99 {}µs, 最大 {}µs, 标准差 {:.0}µs（{} 个样本）",
+                round,
+                name,
+                WORKERS,
+                ROWS,
+                export,
+                p50,
+                p99,
+                max,
+                stddev,
+                samples.len()
+            );
+        }
+    }
+    std::fs::remove_dir_all(&root).ok();
+}
@@ -0,0 +1,148 @@
+// src/commands/assets.rs
+use crate::utils::assets::{AssetConfig, AssetDb, AssetRecord};
+use clap::{Parser, Subcommand};
+use std::error::Error;
+
+/// 资产库命令参数
+#[derive(Parser, Debug)]
+pub struct AssetsArgs {
+    #[command(subcommand)]
+    pub command: AssetsCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AssetsCommands {
+    /// 列出资产库中的主机
+    #[command(name = "list")]
+    List {
+        /// 按IP、别名、系统推测或标签值过滤（包含匹配）
+        #[arg(short, long, value_name = "TEXT")]
+        filter: Option<String>,
+
+        /// 只显示长期未响应的主机
+        #[arg(long)]
+        stale: bool,
+    },
+    /// 显示某个主机的完整记录
+    #[command(name = "show")]
+    Show {
+        /// IP地址或别名（如主机名）
+        host: String,
+    },
+    /// 从资产库删除主机
+    #[command(name = "forget")]
+    Forget {
+        /// IP地址或别名
+        #[arg(required = true)]
+        hosts: Vec<String>,
+    },
+}
+
+/// 主机是否与过滤文本匹配（IP、别名、系统推测或标签值包含该文本）
+fn matches(r: &AssetRecord, text: &str) -> bool {
+    r.ip.contains(text)
+        || r.aliases.iter().any(|a| a.contains(text))
+        || r.os_guess.as_deref().is_some_and(|o| o.contains(text))
+        || r.tags.values().any(|v| v.contains(text))
+}
+
+/// 执行资产库命令
+pub fn run(args: &AssetsArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let config = AssetConfig::load()?;
+    let path = config.path();
+    if !path.exists() {
+        println!(
+            "📭 资产库尚未创建: {}（在配置文件中设置 assets.enabled: true 后扫描会自动记录）",
+            path.display()
+        );
+        return Ok(());
+    }
+    let mut db = AssetDb::open(&path, config.stale_days)?;
+
+    match &args.command {
+        AssetsCommands::List { filter, stale } => {
+            let records: Vec<AssetRecord> = db
+                .list()?
+                .into_iter()
+                .filter(|r| filter.as_ref().is_none_or(|f| matches(r, f)))
+                .filter(|r| !stale || r.is_stale(db.stale_days()))
+                .collect();
+
+            if records.is_empty() {
+                println!("📭 没有匹配的资产");
+                return Ok(());
+            }
+
+            println!("🗂️  资产库（共 {} 个主机）:", records.len());
+            for r in records {
+                let mark = if r.is_stale(db.stale_days()) {
+                    " | 长期未响应"
+                } else {
+                    ""
+                };
+                println!(
+                    "   {} | {} | {} | {} | 最近响应 {}{}",
+                    r.ip,
+                    if r.aliases.is_empty() {
+                        "-".to_string()
+                    } else {
+                        r.aliases.join(",")
+                    },
+                    format_ports(&r.open_ports),
+                    r.os_guess.as_deref().unwrap_or("-"),
+                    short_time(&r.last_seen),
+                    mark
+                );
+            }
+        }
+        AssetsCommands::Show { host } => {
+            let r = db
+                .get(host)?
+                .ok_or_else(|| format!("资产库中没有该主机: {}", host))?;
+            println!("📄 资产 {}", r.ip);
+            if !r.aliases.is_empty() {
+                println!("   别名: {}", r.aliases.join(", "));
+            }
+            println!("   首次发现: {}", short_time(&r.first_seen));
+            println!("   最近响应: {}", short_time(&r.last_seen));
+            println!("   最近扫描: {}", short_time(&r.last_scanned));
+            println!("   开放端口: {}", format_ports(&r.open_ports));
+            if let Some(ref os) = r.os_guess {
+                println!("   系统推测: {}", os);
+            }
+            for (key, value) in &r.tags {
+                println!("   标签 {}: {}", key, value);
+            }
+            if r.is_stale(db.stale_days()) {
+                println!("   状态: 长期未响应（超过 {} 天）", db.stale_days());
+            }
+        }
+        AssetsCommands::Forget { hosts } => {
+            for host in hosts {
+                // 别名先解析为IP，删除主机时别名一并删除
+                let ip = db.get(host)?.map(|r| r.ip);
+                match ip {
+                    Some(ip) if db.forget(&ip)? => println!("🗑️  已删除: {}", ip),
+                    _ => println!("⚠️  资产库中没有该主机: {}", host),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn format_ports(ports: &[u16]) -> String {
+    if ports.is_empty() {
+        return "-".to_string();
+    }
+    ports
+        .iter()
+        .map(u16::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn short_time(time: &str) -> String {
+    time.get(..19).unwrap_or(time).replace('T', " ")
+}
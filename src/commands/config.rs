@@ -0,0 +1,204 @@
+// src/commands/config.rs
+use crate::utils::config_file;
+use crate::utils::secret::REDACTED;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Command, Parser, Subcommand};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// 配置命令参数
+#[derive(Parser, Debug)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// 显示全局设置的生效值及来源，并列出所有 GXTOOLS_* 环境变量
+    #[command(name = "show")]
+    Show,
+}
+
+/// 环境变量与其对应的命令行参数
+#[derive(Debug, Default, PartialEq)]
+pub struct EnvBinding {
+    /// 使用该变量的参数（如 "net ping --timeout"）
+    pub options: Vec<String>,
+    /// 当前环境中的值
+    pub value: Option<String>,
+}
+
+/// 执行配置命令
+///
+/// # 参数
+/// * `args` - 配置命令参数
+/// * `cli` - 完整的命令定义（用于收集环境变量）
+/// * `matches` - 本次解析结果（用于判断全局参数的来源）
+pub fn run(
+    args: &ConfigArgs,
+    cli: &Command,
+    matches: &ArgMatches,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match args.command {
+        ConfigCommands::Show => {
+            println!("⚙️  全局设置（优先级: 命令行 > 环境变量 > 默认值）:");
+            for arg in cli.get_arguments().filter(|a| a.is_global_set()) {
+                let id = arg.get_id().as_str();
+                let value = matches
+                    .get_raw(id)
+                    .map(|vals| {
+                        vals.map(|v| v.to_string_lossy().into_owned())
+                            .collect::<Vec<_>>()
+                            .join(",")
+                    })
+                    .unwrap_or_else(|| {
+                        if arg.get_action().takes_values() {
+                            "(未设置)".to_string()
+                        } else {
+                            "false".to_string()
+                        }
+                    });
+                let source = describe_source(matches.value_source(id), arg.get_env());
+                println!(
+                    "   --{:<12} = {:<16} （来源: {}）",
+                    arg.get_long().unwrap_or(id),
+                    value,
+                    source
+                );
+            }
+
+            let file = config_file();
+            println!(
+                "📄 配置文件: {}（{}）",
+                file.display(),
+                if file.exists() {
+                    "已存在"
+                } else {
+                    "不存在，使用默认值"
+                }
+            );
+
+            println!("📋 环境变量:");
+            for (name, binding) in collect_env_bindings(cli) {
+                let value = binding.value.as_deref().unwrap_or("(未设置)");
+                println!(
+                    "   {:<22} = {:<12} {}",
+                    name,
+                    value,
+                    binding.options.join(", ")
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 描述参数值的来源
+fn describe_source(source: Option<ValueSource>, env: Option<&std::ffi::OsStr>) -> String {
+    match source {
+        Some(ValueSource::CommandLine) => "命令行".to_string(),
+        Some(ValueSource::EnvVariable) => format!(
+            "环境变量 {}",
+            env.map(|e| e.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        ),
+        _ => "默认值".to_string(),
+    }
+}
+
+/// 递归收集命令树中所有参数绑定的环境变量
+///
+/// # 参数
+/// * `cli` - 根命令定义
+///
+/// # 返回
+/// * 按变量名排序的 变量名 -> 绑定信息
+pub fn collect_env_bindings(cli: &Command) -> BTreeMap<String, EnvBinding> {
+    let mut bindings = BTreeMap::new();
+    walk_env_bindings(cli, &[], &mut bindings);
+    bindings
+}
+
+fn walk_env_bindings(cmd: &Command, path: &[&str], bindings: &mut BTreeMap<String, EnvBinding>) {
+    for arg in cmd.get_arguments() {
+        let Some(env) = arg.get_env() else {
+            continue;
+        };
+        // 全局参数会传播到各子命令，只在定义处记录一次
+        if arg.is_global_set() && !path.is_empty() {
+            continue;
+        }
+        let name = env.to_string_lossy().into_owned();
+        let flag = format!("--{}", arg.get_long().unwrap_or(arg.get_id().as_str()));
+        let option = if path.is_empty() {
+            flag
+        } else {
+            format!("{} {}", path.join(" "), flag)
+        };
+
+        let binding = bindings.entry(name).or_insert_with(|| EnvBinding {
+            options: Vec::new(),
+            // 敏感参数（如访问令牌）只显示是否已设置
+            value: std::env::var(env).ok().map(|v| {
+                if arg.is_hide_env_values_set() {
+                    REDACTED.to_string()
+                } else {
+                    v
+                }
+            }),
+        });
+        binding.options.push(option);
+    }
+
+    for sub in cmd.get_subcommands() {
+        let mut sub_path = path.to_vec();
+        sub_path.push(sub.get_name());
+        walk_env_bindings(sub, &sub_path, bindings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+
+    #[test]
+    fn test_collect_env_bindings_walks_subcommands() {
+        let cli = Command::new("gxtools")
+            .arg(
+                Arg::new("lang")
+                    .long("lang")
+                    .global(true)
+                    .env("GXTOOLS_TEST_LANG"),
+            )
+            .subcommand(
+                Command::new("net").subcommand(
+                    Command::new("ping").arg(
+                        Arg::new("timeout")
+                            .long("timeout")
+                            .env("GXTOOLS_TEST_TIMEOUT"),
+                    ),
+                ),
+            )
+            .subcommand(
+                Command::new("pentest").subcommand(
+                    Command::new("portscan")
+                        .arg(
+                            Arg::new("timeout")
+                                .long("timeout")
+                                .env("GXTOOLS_TEST_TIMEOUT"),
+                        )
+                        .arg(Arg::new("full").long("full")),
+                ),
+            );
+
+        let bindings = collect_env_bindings(&cli);
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(bindings["GXTOOLS_TEST_LANG"].options, vec!["--lang"]);
+        assert_eq!(
+            bindings["GXTOOLS_TEST_TIMEOUT"].options,
+            vec!["net ping --timeout", "pentest portscan --timeout"]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+// src/commands/ctl.rs
+use crate::utils::console::Icon;
+use crate::utils::control::{self, ControlRequest};
+use crate::utils::format_duration;
+use clap::{Parser, Subcommand};
+use serde_json::Value;
+use std::error::Error;
+use std::time::Duration;
+
+/// 控制命令参数
+#[derive(Parser, Debug)]
+pub struct CtlArgs {
+    /// 运行ID（扫描开始时打印）或运行目录名
+    pub run_id: String,
+
+    #[command(subcommand)]
+    pub command: CtlCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CtlCommands {
+    /// 查询进度、统计及当前速率
+    #[command(name = "status")]
+    Status,
+    /// 列出已发现的结果（每行一个JSON对象）
+    #[command(name = "findings")]
+    Findings {
+        /// 只显示最近的N个
+        #[arg(short = 'n', long, default_value_t = 50, value_name = "N")]
+        limit: usize,
+    },
+    /// 调整速率限制（每秒探测数，0为不限）
+    #[command(name = "set-rate")]
+    SetRate {
+        /// 每秒探测数
+        rate: u32,
+    },
+    /// 暂停分发新的探测
+    #[command(name = "pause")]
+    Pause,
+    /// 恢复分发
+    #[command(name = "resume")]
+    Resume,
+    /// 停止扫描（已有结果照常导出）
+    #[command(name = "stop")]
+    Stop,
+}
+
+impl CtlCommands {
+    /// 对应的控制通道请求
+    fn request(&self) -> ControlRequest {
+        match *self {
+            Self::Status => ControlRequest::Status,
+            Self::Findings { limit } => ControlRequest::Findings { limit: Some(limit) },
+            Self::SetRate { rate } => ControlRequest::SetRate { rate },
+            Self::Pause => ControlRequest::Pause,
+            Self::Resume => ControlRequest::Resume,
+            Self::Stop => ControlRequest::Stop,
+        }
+    }
+}
+
+/// 执行控制命令
+///
+/// # 参数
+/// * `args` - 命令参数
+///
+/// # 返回
+/// * `Ok(())` - 请求已执行
+/// * `Err` - 找不到运行中的扫描或请求被拒绝
+pub async fn run(args: &CtlArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let response = control::send(&args.run_id, &args.command.request()).await?;
+    match args.command {
+        CtlCommands::Status => print_status(&response),
+        CtlCommands::Findings { .. } => {
+            let findings = response["findings"].as_array().cloned().unwrap_or_default();
+            for finding in &findings {
+                println!("{}", finding);
+            }
+            println!(
+                "{} 已发现 {} 个，显示最近 {} 个",
+                Icon::List,
+                response["total"],
+                findings.len()
+            );
+        }
+        CtlCommands::SetRate { .. } => match response["rate"].as_f64() {
+            Some(rate) => println!("{} 速率已调整为每秒 {:.0} 个探测", Icon::Ok, rate),
+            None => println!("{} 已取消速率限制", Icon::Ok),
+        },
+        CtlCommands::Pause => println!("{} 已暂停分发", Icon::Ok),
+        CtlCommands::Resume if response["paused"] == true => {
+            println!(
+                "{} 已取消手动暂停，当前处于扫描窗口外，窗口打开后继续",
+                Icon::Warn
+            )
+        }
+        CtlCommands::Resume => println!("{} 已恢复分发", Icon::Ok),
+        CtlCommands::Stop => println!("{} 已请求停止，进行中的探测将被放弃", Icon::Ok),
+    }
+    Ok(())
+}
+
+/// 打印状态应答
+fn print_status(status: &Value) {
+    let state = match status["state"].as_str() {
+        Some("paused") => "已暂停",
+        Some("stopping") => "正在停止",
+        _ => "运行中",
+    };
+    println!(
+        "{} {}（{}）: {}",
+        Icon::Stats,
+        status["run_id"].as_str().unwrap_or_default(),
+        status["module"].as_str().unwrap_or_default(),
+        state
+    );
+    let completed = status["progress"]["completed"].as_u64().unwrap_or(0);
+    let total = status["progress"]["total"].as_u64().unwrap_or(0);
+    println!(
+        "   进度: {} / {}（{:.1}%）",
+        completed,
+        total,
+        completed as f64 / total.max(1) as f64 * 100.0
+    );
+    let stats = &status["stats"];
+    if stats.is_object() {
+        println!(
+            "   成功 {}，失败 {}，出错 {}，未完成 {}",
+            stats["succeeded"], stats["failed"], stats["errored"], stats["cancelled"]
+        );
+    }
+    let secs = |key: &str| Duration::from_secs(status[key].as_u64().unwrap_or(0));
+    println!(
+        "   耗时: {}（其中暂停 {}）",
+        format_duration(secs("elapsed_secs")),
+        format_duration(secs("paused_secs"))
+    );
+    match status["rate"].as_f64() {
+        Some(rate) => println!("   速率限制: 每秒 {:.0} 个探测", rate),
+        None => println!("   速率限制: 不限"),
+    }
+    println!("   已发现: {} 个", status["findings"]);
+}
@@ -0,0 +1,381 @@
+// src/commands/history.rs
+use crate::utils::dns::DnsStats;
+use crate::utils::limits::EffectiveConcurrency;
+use crate::utils::meta::{self, RunMeta};
+use crate::utils::timing::Timing;
+use crate::utils::{format_duration, output_root};
+use chrono::{Local, NaiveDate};
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 历史记录文件名（位于输出根目录下）
+pub const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+/// 历史记录文件路径
+pub fn history_file() -> PathBuf {
+    output_root().join(HISTORY_FILE_NAME)
+}
+
+/// 历史记录命令参数
+#[derive(Parser, Debug)]
+pub struct HistoryArgs {
+    #[command(subcommand)]
+    pub command: HistoryCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryCommands {
+    /// 列出历史运行记录
+    #[command(name = "list")]
+    List {
+        /// 按模块过滤（如 ping、portscan）
+        #[arg(short, long, value_name = "MODULE")]
+        module: Option<String>,
+
+        /// 只显示该日期（含）之后的记录，格式：2024-01-01
+        #[arg(short, long, value_name = "DATE")]
+        since: Option<NaiveDate>,
+
+        /// 按运行元数据过滤（如 project=XX，值为包含匹配；可重复指定，需全部满足）
+        #[arg(long = "meta", value_name = "KEY=VALUE", value_parser = meta::parse_meta)]
+        meta: Vec<(String, String)>,
+    },
+    /// 显示某次运行的完整参数及输出文件
+    #[command(name = "show")]
+    Show {
+        /// 记录ID（支持前缀匹配）
+        id: String,
+    },
+    /// 使用系统默认程序打开某次运行的输出文件（没有输出文件时打开运行目录）
+    #[command(name = "open")]
+    Open {
+        /// 记录ID（支持前缀匹配）
+        id: String,
+    },
+}
+
+/// 单次运行的结果摘要，由各模块的 `run` 返回
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunSummary {
+    /// 任务总数（IP数或端口数）
+    pub total: usize,
+    /// 成功数（存活主机数或开放端口数）
+    pub succeeded: usize,
+    /// 输出文件路径
+    pub outputs: Vec<String>,
+    /// 实际生效的并发数
+    pub concurrency: Option<EffectiveConcurrency>,
+    /// 生效的时序参数
+    pub timing: Option<Timing>,
+}
+
+/// 历史运行记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// 记录ID
+    pub id: String,
+    /// 模块名称（如 "net ping"）
+    pub module: String,
+    /// 完整命令行
+    pub command_line: String,
+    /// 目标描述
+    pub targets: String,
+    /// 开始时间（RFC3339）
+    pub started_at: String,
+    /// 耗时（毫秒）
+    pub duration_ms: u64,
+    /// 任务总数
+    pub total: usize,
+    /// 成功数
+    pub succeeded: usize,
+    /// 输出文件路径
+    pub outputs: Vec<String>,
+    /// 退出状态（成功/失败）
+    pub exit_status: String,
+    /// 失败原因
+    pub error: Option<String>,
+    /// 实际生效的并发数及原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<EffectiveConcurrency>,
+    /// 运行目录（包含本次运行的全部产物及 manifest.json）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_dir: Option<String>,
+    /// DNS解析统计（本次运行做过解析时）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns: Option<DnsStats>,
+    /// 生效的时序参数（模板及各项取值）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<Timing>,
+    /// 运行元数据（项目名称、测试人员、授权文件编号等）
+    #[serde(default, skip_serializing_if = "RunMeta::is_empty")]
+    pub meta: RunMeta,
+}
+
+impl RunRecord {
+    /// 生成新的记录ID：时间戳 + 4位十六进制随机后缀
+    pub fn new_id() -> String {
+        let now = Local::now();
+        let suffix = (now.timestamp_subsec_nanos() ^ std::process::id()) & 0xffff;
+        format!("{}-{:04x}", now.format("%Y%m%d%H%M%S"), suffix)
+    }
+
+    /// 记录是否开始于指定日期（含）之后
+    fn is_since(&self, date: NaiveDate) -> bool {
+        chrono::DateTime::parse_from_rfc3339(&self.started_at)
+            .map(|t| t.date_naive() >= date)
+            .unwrap_or(false)
+    }
+}
+
+/// 追加一条历史记录（尽力而为，失败只打印警告，不影响扫描结果）
+///
+/// # 参数
+/// * `path` - 历史记录文件路径
+/// * `record` - 运行记录
+pub fn record_run(path: &Path, record: &RunRecord) {
+    if let Err(e) = append_record(path, record) {
+        eprintln!("⚠️  写入历史记录失败: {}", e);
+    }
+}
+
+fn append_record(path: &Path, record: &RunRecord) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let line = serde_json::to_string(record)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+/// 读取全部历史记录（跳过无法解析的行）
+///
+/// # 参数
+/// * `path` - 历史记录文件路径
+///
+/// # 返回
+/// * `Vec<RunRecord>` - 按写入顺序排列的记录，文件不存在时为空
+pub fn load_records(path: &Path) -> Vec<RunRecord> {
+    fs::read_to_string(path)
+        .map(|data| {
+            data.lines()
+                .filter(|l| !l.trim().is_empty())
+                .filter_map(|l| serde_json::from_str(l).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 按ID前缀查找唯一的历史记录
+fn find_record(records: &[RunRecord], id: &str) -> Result<RunRecord, Box<dyn Error + Send + Sync>> {
+    let matched: Vec<&RunRecord> = records.iter().filter(|r| r.id.starts_with(id)).collect();
+    match matched.len() {
+        0 => Err(format!("未找到历史记录: {}", id).into()),
+        1 => Ok(matched[0].clone()),
+        n => Err(format!("ID前缀 {} 匹配到 {} 条记录，请输入更完整的ID", id, n).into()),
+    }
+}
+
+/// 执行历史记录命令
+pub fn run(args: &HistoryArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let records = load_records(&history_file());
+
+    match &args.command {
+        HistoryCommands::List {
+            module,
+            since,
+            meta,
+        } => {
+            let filtered: Vec<&RunRecord> = records
+                .iter()
+                .filter(|r| {
+                    module
+                        .as_ref()
+                        .is_none_or(|m| r.module.contains(m.as_str()))
+                })
+                .filter(|r| since.is_none_or(|d| r.is_since(d)))
+                .filter(|r| {
+                    meta.iter()
+                        .all(|(k, v)| r.meta.get(k).is_some_and(|m| m.contains(v.as_str())))
+                })
+                .collect();
+
+            if filtered.is_empty() {
+                println!("📭 没有匹配的历史记录");
+                return Ok(());
+            }
+
+            println!("📜 历史记录（共 {} 条）:", filtered.len());
+            for r in filtered {
+                println!(
+                    "   {} | {} | {} | {} | {}/{} | {} | {}",
+                    r.id,
+                    r.started_at
+                        .get(..19)
+                        .unwrap_or(&r.started_at)
+                        .replace('T', " "),
+                    r.module,
+                    r.targets,
+                    r.succeeded,
+                    r.total,
+                    format_duration(Duration::from_millis(r.duration_ms)),
+                    r.exit_status
+                );
+            }
+        }
+        HistoryCommands::Show { id } => {
+            let r = find_record(&records, id)?;
+            println!("📄 运行记录 {}", r.id);
+            println!("   模块: {}", r.module);
+            println!("   命令: {}", r.command_line);
+            println!("   目标: {}", r.targets);
+            println!("   开始时间: {}", r.started_at);
+            println!(
+                "   耗时: {}",
+                format_duration(Duration::from_millis(r.duration_ms))
+            );
+            println!("   结果: {}/{}", r.succeeded, r.total);
+            if let Some(ref c) = r.concurrency {
+                println!("   并发: {}（{}）", c.value, c.reason);
+            }
+            if let Some(ref t) = r.timing {
+                println!("   时序: {}（{}）", t.template, t);
+            }
+            for (label, value) in meta::ordered(&r.meta) {
+                println!("   {}: {}", label, value);
+            }
+            println!("   状态: {}", r.exit_status);
+            if let Some(ref e) = r.error {
+                println!("   错误: {}", e);
+            }
+            if let Some(ref d) = r.dns {
+                println!(
+                    "   DNS: 解析 {} 次，命中缓存 {} 次，失败 {} 次",
+                    d.queries, d.cache_hits, d.failures
+                );
+            }
+            if let Some(ref dir) = r.run_dir {
+                let mark = if Path::new(dir).exists() {
+                    ""
+                } else {
+                    " (已不存在)"
+                };
+                println!("   运行目录: {}{}", dir, mark);
+            }
+            if r.outputs.is_empty() {
+                println!("   输出文件: 无");
+            } else {
+                println!("   输出文件:");
+                for output in &r.outputs {
+                    let mark = if Path::new(output).exists() {
+                        ""
+                    } else {
+                        " (已不存在)"
+                    };
+                    println!("     - {}{}", output, mark);
+                }
+            }
+        }
+        HistoryCommands::Open { id } => {
+            let r = find_record(&records, id)?;
+            let target = r
+                .outputs
+                .iter()
+                .chain(r.run_dir.iter())
+                .map(PathBuf::from)
+                .find(|p| p.exists())
+                .ok_or_else(|| format!("记录 {} 没有可打开的输出文件", r.id))?;
+            open_path(&target)?;
+            println!("📂 已打开: {}", target.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// 使用系统默认程序打开文件（或网址）
+pub fn open_path(path: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut c = std::process::Command::new("cmd");
+        c.args(["/C", "start", ""]);
+        c
+    } else if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    cmd.arg(path)
+        .spawn()
+        .map_err(|e| format!("无法打开 {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: &str, module: &str, started_at: &str) -> RunRecord {
+        RunRecord {
+            id: id.to_string(),
+            module: module.to_string(),
+            command_line: "gxtools net ping -t 192.168.1.1".to_string(),
+            targets: "192.168.1.1".to_string(),
+            started_at: started_at.to_string(),
+            duration_ms: 1500,
+            total: 1,
+            succeeded: 1,
+            outputs: vec![],
+            exit_status: "成功".to_string(),
+            error: None,
+            concurrency: None,
+            run_dir: None,
+            dns: None,
+            timing: None,
+            meta: RunMeta::new(),
+        }
+    }
+
+    #[test]
+    fn test_record_roundtrip() {
+        let path = std::env::temp_dir().join(format!("gxr_history_{}.jsonl", RunRecord::new_id()));
+        record_run(
+            &path,
+            &sample("a1", "net ping", "2024-01-02T10:00:00+08:00"),
+        );
+        record_run(
+            &path,
+            &sample("b2", "pentest portscan", "2024-01-05T10:00:00+08:00"),
+        );
+
+        let records = load_records(&path);
+        fs::remove_file(&path).ok();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].module, "pentest portscan");
+    }
+
+    #[test]
+    fn test_find_record_by_prefix() {
+        let records = vec![
+            sample("20240102-aaaa", "net ping", "2024-01-02T10:00:00+08:00"),
+            sample("20240102-aabb", "net ping", "2024-01-02T10:00:00+08:00"),
+        ];
+        assert_eq!(
+            find_record(&records, "20240102-aaa").unwrap().id,
+            "20240102-aaaa"
+        );
+        assert!(find_record(&records, "20240102-aa").is_err());
+        assert!(find_record(&records, "2023").is_err());
+    }
+
+    #[test]
+    fn test_is_since() {
+        let r = sample("a1", "net ping", "2024-01-02T10:00:00+08:00");
+        assert!(r.is_since(NaiveDate::from_ymd_opt(2024, 1, 2).unwrap()));
+        assert!(!r.is_since(NaiveDate::from_ymd_opt(2024, 1, 3).unwrap()));
+    }
+}
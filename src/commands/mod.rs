@@ -1,2 +1,14 @@
+pub mod assets;
+pub mod config;
+pub mod ctl;
+pub mod doctor;
+pub mod history;
 pub mod net;
 pub mod pentest;
+pub mod profile;
+pub mod report;
+pub mod resources;
+pub mod reverify;
+pub mod serve;
+pub mod template;
+pub mod update;
@@ -0,0 +1,648 @@
+// src/commands/net/arpscan.rs
+use crate::commands::history::RunSummary;
+use crate::utils::blocking::run_stage;
+use crate::utils::console::Icon;
+use crate::utils::context::ScanContext;
+use crate::utils::iface::{Interface, list_interfaces, mac_vendor};
+use crate::utils::output::OutputKind;
+use crate::utils::provenance::Provenance;
+use crate::utils::run_dir::{HOSTS_FILE_NAME, SummaryItem};
+use crate::utils::stats::Outcome;
+use crate::utils::targets::{TargetSourceArgs, collect_targets};
+use crate::utils::{ExcelExport, format_elapsed};
+use clap::Parser;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::io;
+use std::net::Ipv4Addr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 以太网帧中ARP的类型值
+const ETH_P_ARP: u16 = 0x0806;
+
+/// ARP请求帧长度（以太网首部14字节 + ARP报文28字节）
+const FRAME_LEN: usize = 42;
+
+/// 不在本地网段的目标在提示中最多列出的个数
+const MAX_LISTED_REMOTE: usize = 5;
+
+/// 没有权限发送ARP请求时的说明
+const PRIVILEGE_HINT: &str = "ARP扫描需要原始套接字权限：以root运行或授予 CAP_NET_RAW（sudo setcap cap_net_raw+ep <gxr路径>）";
+
+/// ARP扫描参数配置
+#[derive(Parser, Debug)]
+pub struct ArpScanArgs {
+    /// IP地址或网段（支持CIDR、范围、多个IP用逗号隔开），须在所选网卡的网段内
+    ///
+    /// 示例：
+    /// - CIDR: 192.168.1.0/24
+    /// - IP范围: 192.168.1.1-100
+    #[arg(
+        short,
+        long,
+        value_name = "TARGET",
+        required_unless_present_any = ["target_xlsx", "target_file"]
+    )]
+    pub target: Option<String>,
+
+    #[command(flatten)]
+    pub sources: TargetSourceArgs,
+
+    /// 发送ARP请求的网卡（默认选择网段内目标最多的网卡）
+    #[arg(short = 'i', long = "iface", value_name = "NAME")]
+    pub iface: Option<String>,
+
+    /// 每个目标的请求次数（只要有一次回复即判定为存活）
+    #[arg(short = 'n', long, default_value_t = 2, value_name = "COUNT")]
+    pub count: u32,
+
+    /// 每轮请求发完后等待回复的时间（秒）
+    #[arg(
+        short = 'T',
+        long,
+        env = "GXTOOLS_TIMEOUT",
+        default_value_t = 1,
+        value_name = "SECS"
+    )]
+    pub timeout: u64,
+
+    /// 每秒最多发送的请求数（0 表示不限）
+    #[arg(long, default_value_t = 1000, value_name = "PPS")]
+    pub rate: u32,
+
+    /// 是否打印详细结果到终端
+    #[arg(short = 'e', long, env = "GXTOOLS_ECHO")]
+    pub echo: bool,
+
+    /// 是否输出结果到Excel文件
+    #[arg(short = 'o', long, env = "GXTOOLS_OUTPUT")]
+    pub output: bool,
+}
+
+/// ARP扫描发现的一台主机
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ArpHost {
+    /// IP地址
+    pub ip: String,
+    /// MAC地址（小写冒号分隔）
+    pub mac: String,
+    /// 网卡厂商（按OUI识别，未收录时为空）
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub vendor: String,
+    /// 发送请求的网卡
+    pub interface: String,
+    /// 首次请求到收到回复的时间（毫秒，本机地址为空）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtt_ms: Option<f64>,
+    /// 观测时间及探测方式（`arp-request`，本机地址为 `local-interface`）
+    #[serde(flatten)]
+    pub observed: Provenance,
+}
+
+impl ArpHost {
+    fn new(ip: Ipv4Addr, mac: String, iface: &Interface) -> Self {
+        Self {
+            ip: ip.to_string(),
+            vendor: mac_vendor(&mac).unwrap_or_default().to_string(),
+            mac,
+            interface: iface.name.clone(),
+            rtt_ms: None,
+            observed: Provenance::now("arp-request"),
+        }
+    }
+}
+
+/// 选择发送ARP请求的网卡
+///
+/// # 参数
+/// * `interfaces` - 本机网卡
+/// * `targets` - 目标地址
+/// * `name` - 指定的网卡名称
+///
+/// # 返回
+/// * `Ok(&Interface)` - 指定的网卡，或网段内目标最多的网卡
+/// * `Err` - 指定的网卡不存在，或没有任何目标在本机网卡的网段内
+pub fn select_interface<'a>(
+    interfaces: &'a [Interface],
+    targets: &[Ipv4Addr],
+    name: Option<&str>,
+) -> Result<&'a Interface, Box<dyn Error + Send + Sync>> {
+    if let Some(name) = name {
+        return interfaces.iter().find(|i| i.name == name).ok_or_else(|| {
+            let names: Vec<&str> = interfaces.iter().map(|i| i.name.as_str()).collect();
+            format!("未找到网卡 {}（可用: {}）", name, names.join(", ")).into()
+        });
+    }
+    interfaces
+        .iter()
+        .map(|iface| (iface, targets.iter().filter(|ip| iface.contains(**ip)).count()))
+        .filter(|&(_, count)| count > 0)
+        .max_by_key(|&(_, count)| count)
+        .map(|(iface, _)| iface)
+        .ok_or_else(|| {
+            "目标不在任何本机网卡的网段内，ARP只能发现同一二层网段的主机（远程网段可用 net ping 探测）"
+                .into()
+        })
+}
+
+/// 按是否在网卡网段内划分目标（网卡自身地址不发送请求）
+///
+/// # 返回
+/// * `(网段内的目标, 网段外的目标)`
+pub fn split_targets(iface: &Interface, targets: &[Ipv4Addr]) -> (Vec<Ipv4Addr>, Vec<Ipv4Addr>) {
+    targets
+        .iter()
+        .filter(|ip| **ip != iface.addr)
+        .partition(|ip| iface.contains(**ip))
+}
+
+/// 解析冒号分隔的MAC地址
+fn parse_mac(mac: &str) -> Option<[u8; 6]> {
+    let bytes: Vec<u8> = mac
+        .split(':')
+        .map(|b| u8::from_str_radix(b, 16).ok())
+        .collect::<Option<_>>()?;
+    bytes.try_into().ok()
+}
+
+/// MAC地址的小写冒号分隔写法
+fn format_mac(mac: &[u8; 6]) -> String {
+    mac.iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// 构造广播的ARP请求帧
+fn request_frame(src_mac: [u8; 6], src_ip: Ipv4Addr, target: Ipv4Addr) -> [u8; FRAME_LEN] {
+    let mut frame = [0u8; FRAME_LEN];
+    frame[0..6].copy_from_slice(&[0xff; 6]);
+    frame[6..12].copy_from_slice(&src_mac);
+    frame[12..14].copy_from_slice(&ETH_P_ARP.to_be_bytes());
+    // 硬件类型以太网、协议类型IPv4、地址长度6/4、操作码1（请求）
+    frame[14..22].copy_from_slice(&[0, 1, 0x08, 0x00, 6, 4, 0, 1]);
+    frame[22..28].copy_from_slice(&src_mac);
+    frame[28..32].copy_from_slice(&src_ip.octets());
+    // 目标MAC未知，保持全0
+    frame[38..42].copy_from_slice(&target.octets());
+    frame
+}
+
+/// 解析发给本机的ARP回复
+///
+/// # 返回
+/// * `Some((发送方IP, 发送方MAC))` - 目标地址为 `local_ip` 的ARP回复
+fn parse_reply(frame: &[u8], local_ip: Ipv4Addr) -> Option<(Ipv4Addr, [u8; 6])> {
+    if frame.len() < FRAME_LEN
+        || frame[12..14] != ETH_P_ARP.to_be_bytes()
+        || frame[14..22] != [0, 1, 0x08, 0x00, 6, 4, 0, 2]
+    {
+        return None;
+    }
+    let sender: [u8; 4] = frame[28..32].try_into().ok()?;
+    let target: [u8; 4] = frame[38..42].try_into().ok()?;
+    if Ipv4Addr::from(target) != local_ip {
+        return None;
+    }
+    Some((Ipv4Addr::from(sender), frame[22..28].try_into().ok()?))
+}
+
+/// 绑定到网卡的ARP收发套接字（Linux的 `AF_PACKET`）
+#[cfg(target_os = "linux")]
+struct ArpSocket {
+    fd: tokio::io::unix::AsyncFd<std::os::fd::OwnedFd>,
+}
+
+#[cfg(target_os = "linux")]
+impl ArpSocket {
+    /// 创建套接字并绑定到网卡
+    fn open(iface: &str) -> io::Result<Self> {
+        use std::os::fd::FromRawFd;
+
+        let name = std::ffi::CString::new(iface)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "网卡名称无效"))?;
+        let protocol = ETH_P_ARP.to_be();
+        // SAFETY: 只调用套接字相关的系统接口，返回的描述符立即交给 OwnedFd 管理；
+        // sockaddr_ll 按内核要求填写后以其实际大小传入 bind
+        unsafe {
+            let index = libc::if_nametoindex(name.as_ptr());
+            if index == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let raw = libc::socket(
+                libc::AF_PACKET,
+                libc::SOCK_RAW | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+                i32::from(protocol),
+            );
+            if raw < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let fd = std::os::fd::OwnedFd::from_raw_fd(raw);
+            let mut addr: libc::sockaddr_ll = std::mem::zeroed();
+            addr.sll_family = libc::AF_PACKET as u16;
+            addr.sll_protocol = protocol;
+            addr.sll_ifindex = index as i32;
+            if libc::bind(
+                raw,
+                &addr as *const libc::sockaddr_ll as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+            ) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self {
+                fd: tokio::io::unix::AsyncFd::new(fd)?,
+            })
+        }
+    }
+
+    /// 发送一帧
+    async fn send(&self, frame: &[u8]) -> io::Result<()> {
+        use std::os::fd::AsRawFd;
+        loop {
+            let mut guard = self.fd.writable().await?;
+            // SAFETY: 缓冲区在调用期间有效，长度与指针一致
+            let sent = guard.try_io(|fd| {
+                let n =
+                    unsafe { libc::send(fd.as_raw_fd(), frame.as_ptr().cast(), frame.len(), 0) };
+                if n < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(())
+                }
+            });
+            if let Ok(result) = sent {
+                return result;
+            }
+        }
+    }
+
+    /// 接收一帧
+    async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        use std::os::fd::AsRawFd;
+        loop {
+            let mut guard = self.fd.readable().await?;
+            // SAFETY: 缓冲区在调用期间有效，长度与指针一致
+            let received = guard.try_io(|fd| {
+                let n =
+                    unsafe { libc::recv(fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0) };
+                if n < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(n as usize)
+                }
+            });
+            if let Ok(result) = received {
+                return result;
+            }
+        }
+    }
+}
+
+/// 其他系统暂不支持直接发送ARP请求
+#[cfg(not(target_os = "linux"))]
+struct ArpSocket;
+
+#[cfg(not(target_os = "linux"))]
+impl ArpSocket {
+    fn open(_iface: &str) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "当前系统暂不支持发送ARP请求（仅支持Linux），可改用 net map 通过系统ARP表发现本地主机",
+        ))
+    }
+
+    async fn send(&self, _frame: &[u8]) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    async fn recv(&self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+pub async fn run(args: &ArpScanArgs) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    run_with(args, &ScanContext::cli()).await
+}
+
+/// 向本地网段的目标发送ARP请求，按回复发现存活主机
+///
+/// 同一二层网段内的主机必须响应ARP，即使防火墙拦截了ICMP也能发现。
+/// 请求按轮发送，每轮只发给尚未回复的目标，发完后等待 `--timeout` 秒。
+///
+/// # 参数
+/// * `args` - ARP扫描参数
+/// * `ctx` - 扫描上下文
+///
+/// # 返回
+/// * `Ok(RunSummary)` - 扫描完成后的结果摘要
+/// * `Err` - 目标不在本地网段、没有权限或收发出错
+pub async fn run_with(
+    args: &ArpScanArgs,
+    ctx: &ScanContext,
+) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    let start = Instant::now();
+    let targets: Vec<Ipv4Addr> = collect_targets(args.target.as_deref(), &args.sources)
+        .await?
+        .ips()
+        .iter()
+        .filter_map(|ip| ip.parse().ok())
+        .collect();
+    if targets.is_empty() {
+        return Err("未解析到任何有效的IPv4地址".into());
+    }
+
+    let interfaces = list_interfaces().map_err(|e| format!("无法枚举本机网卡: {}", e))?;
+    let iface = select_interface(&interfaces, &targets, args.iface.as_deref())?;
+    let src_mac = iface
+        .mac
+        .as_deref()
+        .and_then(parse_mac)
+        .ok_or_else(|| format!("无法获取网卡 {} 的MAC地址", iface.name))?;
+    let (local, remote) = split_targets(iface, &targets);
+    if !remote.is_empty() {
+        let listed: Vec<String> = remote
+            .iter()
+            .take(MAX_LISTED_REMOTE)
+            .map(Ipv4Addr::to_string)
+            .collect();
+        println!(
+            "{} {} 个目标不在网卡 {} 的网段 {} 内，ARP无法到达，已跳过: {}{}（远程网段可用 net ping 探测）",
+            Icon::Warn,
+            remote.len(),
+            iface.name,
+            iface.cidr(),
+            listed.join(", "),
+            if remote.len() > MAX_LISTED_REMOTE {
+                " 等"
+            } else {
+                ""
+            }
+        );
+    }
+    if local.is_empty() {
+        return Err(format!("没有目标在网卡 {} 的网段 {} 内", iface.name, iface.cidr()).into());
+    }
+    let socket = ArpSocket::open(&iface.name).map_err(|e| match e.kind() {
+        io::ErrorKind::PermissionDenied => format!("{}（{}）", PRIVILEGE_HINT, e),
+        _ => format!("无法在网卡 {} 上发送ARP请求: {}", iface.name, e),
+    })?;
+
+    let count = args.count.max(1);
+    println!("{} 开始ARP扫描，共 {} 个目标IP", Icon::Scan, local.len());
+    println!(
+        "{} 配置: 网卡={}（{}，本机 {}）, 次数={}次, 等待={}秒, 速率={}",
+        Icon::Config,
+        iface.name,
+        iface.cidr(),
+        iface.addr,
+        count,
+        args.timeout,
+        match args.rate {
+            0 => "不限".to_string(),
+            pps => format!("{}/秒", pps),
+        }
+    );
+
+    let progress = ctx.new_progress(local.len() as u64);
+    let listener = ctx.interactive.then(|| ctx.pause.listen(&progress, true));
+    let wanted: HashSet<Ipv4Addr> = local.iter().copied().collect();
+    let first_sent: Mutex<HashMap<Ipv4Addr, Instant>> = Mutex::new(HashMap::new());
+    let found: Mutex<BTreeMap<Ipv4Addr, ArpHost>> = Mutex::new(BTreeMap::new());
+
+    let sender = async {
+        let gap = (args.rate > 0).then(|| Duration::from_secs(1) / args.rate);
+        for _ in 0..count {
+            // 全部回复后不再等待下一轮
+            if found.lock().unwrap().len() == local.len() {
+                break;
+            }
+            for &ip in &local {
+                if found.lock().unwrap().contains_key(&ip) {
+                    continue;
+                }
+                ctx.pause.wait().await;
+                first_sent.lock().unwrap().entry(ip).or_insert_with(|| {
+                    progress.stats().dispatch();
+                    Instant::now()
+                });
+                socket.send(&request_frame(src_mac, iface.addr, ip)).await?;
+                progress.stats().add_sent(FRAME_LEN as u64);
+                if let Some(gap) = gap {
+                    tokio::time::sleep(gap).await;
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(args.timeout)).await;
+        }
+        Ok::<_, io::Error>(())
+    };
+    let receiver = async {
+        let mut buf = [0u8; 1514];
+        loop {
+            let len = match socket.recv(&mut buf).await {
+                Ok(len) => len,
+                Err(e) => return Err::<(), io::Error>(e),
+            };
+            let Some((ip, mac)) = parse_reply(&buf[..len], iface.addr) else {
+                continue;
+            };
+            if !wanted.contains(&ip) || found.lock().unwrap().contains_key(&ip) {
+                continue;
+            }
+            let mut host = ArpHost::new(ip, format_mac(&mac), iface);
+            host.rtt_ms = first_sent
+                .lock()
+                .unwrap()
+                .get(&ip)
+                .map(|sent| sent.elapsed().as_secs_f64() * 1000.0);
+            if args.echo {
+                progress.println(format!(
+                    "  {} {} => {}{}",
+                    Icon::Ok,
+                    ip,
+                    host.mac,
+                    if host.vendor.is_empty() {
+                        String::new()
+                    } else {
+                        format!("（{}）", host.vendor)
+                    }
+                ));
+            }
+            progress.stats().add_received(len as u64);
+            ctx.emit(&host);
+            found.lock().unwrap().insert(ip, host);
+            progress.record(Outcome::Succeeded);
+        }
+    };
+    tokio::select! {
+        result = sender => result?,
+        result = receiver => result?,
+        _ = ctx.token().cancelled() => {}
+    }
+    drop(listener);
+
+    // 已发出请求但没有回复的记为失败，取消时记为未完成
+    let mut hosts: Vec<ArpHost> = found.into_inner().unwrap().into_values().collect();
+    let unanswered = first_sent.into_inner().unwrap().len() - hosts.len();
+    let outcome = if ctx.is_cancelled() {
+        progress.println(format!("{} 扫描已取消，以下为部分结果", Icon::Warn));
+        Outcome::Cancelled
+    } else {
+        Outcome::Failed
+    };
+    for _ in 0..unanswered {
+        progress.record(outcome);
+    }
+    progress.finish_with_message(format!("{} ARP扫描完成", Icon::Ok));
+
+    // 网卡自身地址在目标内时列为本机
+    if targets.contains(&iface.addr) {
+        let mut host = ArpHost::new(iface.addr, format_mac(&src_mac), iface);
+        host.observed = Provenance::now("local-interface");
+        hosts.push(host);
+        hosts.sort_by_key(|h| h.ip.parse::<Ipv4Addr>().ok());
+    }
+
+    let counts = progress.snapshot();
+    let mut summary: Vec<SummaryItem> = counts.summary_items("个地址");
+    summary.push(("存活".to_string(), format!("{} 台", hosts.len())));
+    summary.push((
+        "网卡".to_string(),
+        format!("{}（{}）", iface.name, iface.cidr()),
+    ));
+    if !remote.is_empty() {
+        summary.push((
+            "跳过".to_string(),
+            format!("{} 个（不在本地网段）", remote.len()),
+        ));
+    }
+    summary.push((
+        "耗时".to_string(),
+        format_elapsed(start.elapsed(), ctx.pause.paused_duration()),
+    ));
+
+    let mut outputs = Vec::new();
+    if args.output {
+        outputs.push(run_stage(export_results(&hosts, ctx)).await?);
+    }
+
+    println!("\n{} 扫描统计:", Icon::Stats);
+    for (name, value) in &summary {
+        println!("   {}: {}", name, value);
+    }
+
+    if let Some(run_dir) = ctx.run_dir() {
+        run_dir.write_json(HOSTS_FILE_NAME, "json", &hosts, hosts.len())?;
+        run_dir.write_summary(&summary)?;
+    }
+
+    Ok(RunSummary {
+        total: counts.completed as usize,
+        succeeded: hosts.len(),
+        outputs,
+        concurrency: None,
+        timing: None,
+    })
+}
+
+/// 导出主机清单到Excel
+fn export_results(hosts: &[ArpHost], ctx: &ScanContext) -> ExcelExport {
+    let headers = [
+        "IP地址",
+        "MAC地址",
+        "厂商",
+        "网卡",
+        "响应时间(ms)",
+        Provenance::HEADERS[0],
+        Provenance::HEADERS[1],
+    ];
+    ExcelExport::prepare(
+        hosts,
+        &headers,
+        |h| {
+            vec![
+                h.ip.clone(),
+                h.mac.clone(),
+                h.vendor.clone(),
+                h.interface.clone(),
+                h.rtt_ms.map(|t| format!("{:.1}", t)).unwrap_or_default(),
+                h.observed.observed_at.clone(),
+                h.observed.probe.clone(),
+            ]
+        },
+        OutputKind::ARPSCAN,
+        &ctx.excel_options(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iface(name: &str, addr: &str, prefix: u8) -> Interface {
+        Interface {
+            name: name.to_string(),
+            addr: addr.parse().unwrap(),
+            prefix,
+            mac: Some("00:0c:29:00:00:01".to_string()),
+        }
+    }
+
+    fn ips(list: &[&str]) -> Vec<Ipv4Addr> {
+        list.iter().map(|ip| ip.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_select_interface_and_split() {
+        let interfaces = [
+            iface("eth0", "10.0.0.5", 24),
+            iface("eth1", "192.168.1.5", 24),
+        ];
+        let targets = ips(&[
+            "192.168.1.1",
+            "192.168.1.5",
+            "192.168.1.9",
+            "10.0.0.1",
+            "8.8.8.8",
+        ]);
+        let chosen = select_interface(&interfaces, &targets, None).unwrap();
+        assert_eq!(chosen.name, "eth1");
+        assert_eq!(
+            select_interface(&interfaces, &targets, Some("eth0"))
+                .unwrap()
+                .name,
+            "eth0"
+        );
+        assert!(select_interface(&interfaces, &targets, Some("wlan0")).is_err());
+        assert!(select_interface(&interfaces, &ips(&["8.8.8.8"]), None).is_err());
+
+        // 网卡自身地址不发送请求，网段外的单独列出
+        let (local, remote) = split_targets(chosen, &targets);
+        assert_eq!(local, ips(&["192.168.1.1", "192.168.1.9"]));
+        assert_eq!(remote, ips(&["10.0.0.1", "8.8.8.8"]));
+    }
+
+    #[test]
+    fn test_request_and_reply_frames() {
+        let mac = parse_mac("00:0c:29:ab:cd:ef").unwrap();
+        let local: Ipv4Addr = "192.168.1.5".parse().unwrap();
+        let target: Ipv4Addr = "192.168.1.9".parse().unwrap();
+        let request = request_frame(mac, local, target);
+        assert_eq!(&request[0..6], &[0xff; 6]);
+        assert_eq!(&request[38..42], &target.octets());
+        // 请求不是回复
+        assert_eq!(parse_reply(&request, local), None);
+
+        // 目标的回复：发送方为目标，目标地址为本机
+        let peer = parse_mac("b8:27:eb:01:02:03").unwrap();
+        let mut reply = request_frame(peer, target, local);
+        reply[21] = 2;
+        assert_eq!(parse_reply(&reply, local), Some((target, peer)));
+        assert_eq!(parse_reply(&reply, target), None);
+        assert_eq!(parse_reply(&reply[..30], local), None);
+        assert_eq!(format_mac(&peer), "b8:27:eb:01:02:03");
+        assert_eq!(parse_mac("00:0c:29"), None);
+    }
+}
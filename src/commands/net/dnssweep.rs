@@ -0,0 +1,540 @@
+// src/commands/net/dnssweep.rs
+use crate::commands::history::RunSummary;
+use crate::utils::blocking::run_stage;
+use crate::utils::console::Icon;
+use crate::utils::context::ScanContext;
+use crate::utils::dns::{self, DnsConfig, DnsError, Resolver};
+use crate::utils::limits::{
+    ConcurrencySpec, ScanKind, effective_concurrency, parse_fixed_concurrency,
+};
+use crate::utils::output::OutputKind;
+use crate::utils::pool::run_tracked;
+use crate::utils::provenance::Provenance;
+use crate::utils::run_dir::{HOSTS_FILE_NAME, SummaryItem};
+use crate::utils::stats::Outcome;
+use crate::utils::{ExcelExport, format_elapsed};
+use clap::Parser;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// 泛解析检测时查询的随机名称个数
+const WILDCARD_PROBES: usize = 2;
+
+/// 内网主机名字典扫描参数配置
+#[derive(Parser, Debug)]
+pub struct DnsSweepArgs {
+    /// 要扫描的域名（多个用逗号隔开，如 corp.local,corp.example.com）
+    #[arg(
+        short,
+        long,
+        required = true,
+        value_delimiter = ',',
+        value_name = "DOMAIN"
+    )]
+    pub domain: Vec<String>,
+
+    /// 主机名字典（每行一个，如 gitlab、nas，# 之后为注释）
+    #[arg(short, long, value_name = "FILE")]
+    pub wordlist: PathBuf,
+
+    /// 使用的DNS服务器（多个用逗号隔开，如内网DNS 10.0.0.53），默认同 --dns-server 或系统配置
+    #[arg(long, value_delimiter = ',', value_name = "IP[:PORT]")]
+    pub resolver: Vec<String>,
+
+    /// 同时按系统配置的搜索域尝试字典中的短名
+    #[arg(long)]
+    pub short: bool,
+
+    /// 最大并发数（auto 表示根据文件描述符上限和任务数量自动选择）
+    #[arg(
+        short = 'c',
+        long,
+        env = "GXTOOLS_CONCURRENCY",
+        default_value = "50",
+        value_name = "NUM|auto",
+        value_parser = parse_fixed_concurrency
+    )]
+    pub concurrency: ConcurrencySpec,
+
+    /// 解析结果另存为目标文件（每行 `IP 主机名...`），可用 --target-file 导入 ping、portscan 等命令
+    #[arg(long, value_name = "FILE")]
+    pub alive_file: Option<PathBuf>,
+
+    /// 是否打印详细结果到终端
+    #[arg(short = 'e', long, env = "GXTOOLS_ECHO")]
+    pub echo: bool,
+
+    /// 是否输出结果到Excel文件
+    #[arg(short = 'o', long, env = "GXTOOLS_OUTPUT")]
+    pub output: bool,
+}
+
+/// 候选名称的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NameSource {
+    /// 字典中的名称加上指定的域名
+    Domain,
+    /// 字典中的短名加上系统搜索域（`--short`）
+    Search,
+}
+
+impl fmt::Display for NameSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NameSource::Domain => "指定域名",
+            NameSource::Search => "搜索域",
+        })
+    }
+}
+
+impl Serialize for NameSource {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// 待解析的候选名称
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    /// 完整名称（如 gitlab.corp.local）
+    pub name: String,
+    /// 所在域名
+    pub domain: String,
+    /// 来源
+    pub source: NameSource,
+}
+
+/// 解析成功的主机
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepHost {
+    /// 完整名称
+    pub name: String,
+    /// 解析得到的地址
+    pub ips: Vec<IpAddr>,
+    /// 所在域名
+    pub domain: String,
+    /// 来源
+    pub source: NameSource,
+    /// 观测时间及探测方式
+    #[serde(flatten)]
+    pub observed: Provenance,
+}
+
+/// 读取主机名字典：去掉注释、空行及重复项，统一为小写
+///
+/// # 参数
+/// * `path` - 字典文件
+///
+/// # 返回
+/// * `Ok((名称, 跳过的无效行数))`
+pub fn read_wordlist(path: &Path) -> Result<(Vec<String>, usize), Box<dyn Error + Send + Sync>> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("无法读取主机名字典 {}: {}", path.display(), e))?;
+    let mut seen = HashSet::new();
+    let mut words = Vec::new();
+    let mut invalid = 0;
+    for line in text.lines() {
+        let word = line
+            .split('#')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .trim_matches('.')
+            .to_ascii_lowercase();
+        if word.is_empty() {
+            continue;
+        }
+        let valid = word
+            .split('.')
+            .all(|label| !label.is_empty() && label.len() <= 63)
+            && word
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_');
+        if !valid {
+            invalid += 1;
+        } else if seen.insert(word.clone()) {
+            words.push(word);
+        }
+    }
+    Ok((words, invalid))
+}
+
+/// 组合字典与域名得到候选名称（同一名称只出现一次，指定域名优先）
+///
+/// # 参数
+/// * `words` - 字典中的名称
+/// * `domains` - 指定的域名
+/// * `search` - 系统搜索域（未开启 `--short` 时为空）
+pub fn candidates(words: &[String], domains: &[String], search: &[String]) -> Vec<Candidate> {
+    let mut seen = HashSet::new();
+    let sources = domains
+        .iter()
+        .map(|d| (d, NameSource::Domain))
+        .chain(search.iter().map(|d| (d, NameSource::Search)));
+    let mut out = Vec::new();
+    for (domain, source) in sources {
+        for word in words {
+            let name = format!("{}.{}", word, domain);
+            if seen.insert(name.clone()) {
+                out.push(Candidate {
+                    name,
+                    domain: domain.clone(),
+                    source,
+                });
+            }
+        }
+    }
+    out
+}
+
+/// 解析结果是否只是泛解析的地址
+///
+/// # 参数
+/// * `ips` - 候选名称解析得到的地址
+/// * `wildcard` - 该域名下随机名称解析得到的地址
+pub fn is_wildcard(ips: &[IpAddr], wildcard: &HashSet<IpAddr>) -> bool {
+    !wildcard.is_empty() && ips.iter().all(|ip| wildcard.contains(ip))
+}
+
+/// 随机的、几乎不可能存在的名称（用于检测泛解析）
+fn random_label(i: usize) -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let mixed =
+        (nanos ^ std::process::id() as u64 ^ (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    format!("gxr-wildcard-{:016x}", mixed)
+}
+
+/// 检测域名是否存在泛解析
+///
+/// # 返回
+/// * `HashSet<IpAddr>` - 随机名称解析得到的地址（没有泛解析时为空）
+async fn detect_wildcard(resolver: &Resolver, domain: &str) -> HashSet<IpAddr> {
+    let mut ips = HashSet::new();
+    for i in 0..WILDCARD_PROBES {
+        let name = format!("{}.{}", random_label(i), domain);
+        if let Ok(found) = resolver.lookup_ip(&name).await {
+            ips.extend(found);
+        }
+    }
+    ips
+}
+
+/// 解析结果按IP分组的目标文件内容（每行 `IP 主机名...`）
+pub fn alive_file_content(hosts: &[SweepHost]) -> String {
+    let mut by_ip: BTreeMap<IpAddr, Vec<&str>> = BTreeMap::new();
+    for host in hosts {
+        for ip in &host.ips {
+            by_ip.entry(*ip).or_default().push(&host.name);
+        }
+    }
+    let mut text = String::from("# net dnssweep 解析结果：IP 主机名...，可用 --target-file 导入\n");
+    for (ip, names) in by_ip {
+        text.push_str(&format!("{} {}\n", ip, names.join(" ")));
+    }
+    text
+}
+
+pub async fn run(args: &DnsSweepArgs) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    run_with(args, &ScanContext::cli()).await
+}
+
+/// 按字典猜测内网主机名并解析
+///
+/// 每个域名先用随机名称检测泛解析，只解析到泛解析地址的名称视为不存在。
+///
+/// # 参数
+/// * `args` - 扫描参数
+/// * `ctx` - 扫描上下文
+///
+/// # 返回
+/// * `Ok(RunSummary)` - 扫描完成后的结果摘要
+pub async fn run_with(
+    args: &DnsSweepArgs,
+    ctx: &ScanContext,
+) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    let start = Instant::now();
+    let (words, invalid) = read_wordlist(&args.wordlist)?;
+    if words.is_empty() {
+        return Err(format!("主机名字典 {} 中没有有效的名称", args.wordlist.display()).into());
+    }
+    if invalid > 0 {
+        println!("{} 跳过字典中 {} 行无效的名称", Icon::Warn, invalid);
+    }
+    let domains: Vec<String> = args
+        .domain
+        .iter()
+        .map(|d| d.trim().trim_matches('.').to_ascii_lowercase())
+        .filter(|d| !d.is_empty())
+        .collect();
+    let search = if args.short {
+        let search: Vec<String> = dns::search_domains()
+            .into_iter()
+            .filter(|d| !domains.contains(d))
+            .collect();
+        if search.is_empty() {
+            println!(
+                "{} 系统没有配置其他搜索域，--short 不增加候选名称",
+                Icon::Warn
+            );
+        }
+        search
+    } else {
+        Vec::new()
+    };
+
+    let resolver = if args.resolver.is_empty() {
+        dns::resolver().clone()
+    } else {
+        Resolver::from_config(DnsConfig {
+            servers: args.resolver.clone(),
+            upstream: None,
+            ..DnsConfig::load()?
+        })?
+    };
+
+    let mut wildcards: HashMap<String, HashSet<IpAddr>> = HashMap::new();
+    for domain in domains.iter().chain(&search) {
+        let ips = detect_wildcard(&resolver, domain).await;
+        if !ips.is_empty() {
+            let mut list: Vec<String> = ips.iter().map(IpAddr::to_string).collect();
+            list.sort();
+            println!(
+                "{} {} 存在泛解析（{}），只解析到这些地址的名称将被忽略",
+                Icon::Warn,
+                domain,
+                list.join(", ")
+            );
+        }
+        wildcards.insert(domain.clone(), ips);
+    }
+
+    let candidates = candidates(&words, &domains, &search);
+    let total = candidates.len();
+    let concurrency = effective_concurrency(args.concurrency, total, ScanKind::Connect);
+    let resolvers = if args.resolver.is_empty() {
+        "默认".to_string()
+    } else {
+        args.resolver.join(", ")
+    };
+    println!(
+        "{} 开始解析: {} 个名称 × {} 个域名 = {} 个候选",
+        Icon::Scan,
+        words.len(),
+        domains.len() + search.len(),
+        total
+    );
+    println!(
+        "{} 配置: DNS服务器={}, 并发={}（{}）",
+        Icon::Config,
+        resolvers,
+        concurrency.value,
+        concurrency.reason
+    );
+
+    let progress = ctx.new_progress(total as u64);
+    let listener = ctx.interactive.then(|| ctx.pause.listen(&progress, true));
+    let mut hosts: Vec<SweepHost> = Vec::new();
+    let mut wildcard_hits = 0;
+    run_tracked(
+        candidates.iter(),
+        concurrency.value,
+        &progress,
+        ctx.token(),
+        |candidate| {
+            let resolver = &resolver;
+            async move {
+                ctx.pause.wait().await;
+                (candidate, resolver.lookup_ip(&candidate.name).await)
+            }
+        },
+        |(candidate, result)| match result {
+            Ok(ips) if is_wildcard(&ips, &wildcards[&candidate.domain]) => {
+                wildcard_hits += 1;
+                Outcome::Failed
+            }
+            Ok(ips) => {
+                let host = SweepHost {
+                    name: candidate.name.clone(),
+                    ips,
+                    domain: candidate.domain.clone(),
+                    source: candidate.source,
+                    observed: Provenance::now("dns-lookup"),
+                };
+                if args.echo {
+                    let ips: Vec<String> = host.ips.iter().map(IpAddr::to_string).collect();
+                    progress.println(format!(
+                        "  {} {} => {}",
+                        Icon::Ok,
+                        host.name,
+                        ips.join(", ")
+                    ));
+                }
+                ctx.emit(&host);
+                hosts.push(host);
+                Outcome::Succeeded
+            }
+            Err(DnsError::NotFound { .. }) => Outcome::Failed,
+            Err(_) => Outcome::Errored,
+        },
+    )
+    .await;
+    drop(listener);
+    if ctx.is_cancelled() {
+        progress.println(format!("{} 扫描已取消，以下为部分结果", Icon::Warn));
+    }
+    progress.finish_with_message(format!("{} 主机名解析完成", Icon::Ok));
+    hosts.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let unique_ips: HashSet<IpAddr> = hosts.iter().flat_map(|h| h.ips.iter().copied()).collect();
+    let counts = progress.snapshot();
+    debug_assert_eq!(counts.succeeded as usize, hosts.len());
+    let mut summary: Vec<SummaryItem> = counts.summary_items("个名称");
+    summary.extend([
+        (
+            "解析成功".to_string(),
+            format!("{} 个名称，{} 个IP", counts.succeeded, unique_ips.len()),
+        ),
+        ("泛解析".to_string(), format!("{} 个", wildcard_hits)),
+        ("查询失败".to_string(), format!("{} 个", counts.errored)),
+        (
+            "耗时".to_string(),
+            format_elapsed(start.elapsed(), ctx.pause.paused_duration()),
+        ),
+    ]);
+    if counts.errored > 0 {
+        summary.insert(
+            summary.len() - 1,
+            (
+                "提示".to_string(),
+                "查询失败的名称可能存在，可检查DNS服务器后重试".to_string(),
+            ),
+        );
+    }
+
+    let mut outputs = Vec::new();
+    if let Some(ref path) = args.alive_file {
+        fs::write(path, alive_file_content(&hosts))
+            .map_err(|e| format!("无法写入目标文件 {}: {}", path.display(), e))?;
+        println!(
+            "{} 目标文件已保存至: {}（可用 --target-file 导入）",
+            Icon::Ok,
+            path.display()
+        );
+        outputs.push(path.display().to_string());
+    }
+    if args.output && !hosts.is_empty() {
+        outputs.push(run_stage(export_results(&hosts, ctx)).await?);
+    }
+
+    println!("\n{} 扫描统计:", Icon::Stats);
+    for (name, value) in &summary {
+        println!("   {}: {}", name, value);
+    }
+
+    if let Some(run_dir) = ctx.run_dir() {
+        run_dir.write_json(HOSTS_FILE_NAME, "json", &hosts, hosts.len())?;
+        run_dir.write_summary(&summary)?;
+    }
+
+    Ok(RunSummary {
+        total: counts.completed as usize,
+        succeeded: counts.succeeded as usize,
+        outputs,
+        concurrency: Some(concurrency),
+        timing: None,
+    })
+}
+
+/// 导出解析结果到Excel（每个 主机名-IP 一行，可用 --target-xlsx --column IP地址 导入）
+fn export_results(hosts: &[SweepHost], ctx: &ScanContext) -> ExcelExport {
+    let rows: Vec<(&SweepHost, IpAddr)> = hosts
+        .iter()
+        .flat_map(|h| h.ips.iter().map(move |ip| (h, *ip)))
+        .collect();
+    ExcelExport::prepare(
+        &rows,
+        &["主机名", "IP地址", "域名", "来源", "观测时间", "探测方式"],
+        |(host, ip)| {
+            vec![
+                host.name.clone(),
+                ip.to_string(),
+                host.domain.clone(),
+                host.source.to_string(),
+                host.observed.observed_at.clone(),
+                host.observed.probe.clone(),
+            ]
+        },
+        OutputKind::DNSSWEEP,
+        &ctx.excel_options(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates_and_wordlist() {
+        let path = std::env::temp_dir().join(format!("gxr_words_{}.txt", std::process::id()));
+        fs::write(&path, "GitLab\nnas # 群晖\n\ngitlab\nvpn.dev\nbad name\n").unwrap();
+        let (words, invalid) = read_wordlist(&path).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(words, ["gitlab", "nas", "vpn.dev"]);
+        assert_eq!(invalid, 1);
+
+        let domains = vec!["corp.local".to_string(), "corp.example.com".to_string()];
+        let search = vec!["corp.local".to_string(), "lab.corp".to_string()];
+        let list = candidates(&words, &domains, &search);
+        // 搜索域与指定域名重复时只保留一份，来源记为指定域名
+        assert_eq!(list.len(), 9);
+        assert_eq!(list[0].name, "gitlab.corp.local");
+        assert_eq!(list[2].name, "vpn.dev.corp.local");
+        assert!(
+            list.iter()
+                .filter(|c| c.domain == "corp.local")
+                .all(|c| c.source == NameSource::Domain)
+        );
+        assert_eq!(list[8].name, "vpn.dev.lab.corp");
+        assert_eq!(list[8].source, NameSource::Search);
+    }
+
+    #[test]
+    fn test_wildcard_filter_and_alive_file() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let wildcard: HashSet<IpAddr> = [ip("10.0.0.99")].into();
+        assert!(is_wildcard(&[ip("10.0.0.99")], &wildcard));
+        assert!(!is_wildcard(&[ip("10.0.0.99"), ip("10.0.0.5")], &wildcard));
+        assert!(!is_wildcard(&[ip("10.0.0.5")], &HashSet::new()));
+
+        let host = |name: &str, ips: &[&str]| SweepHost {
+            name: name.to_string(),
+            ips: ips.iter().map(|s| ip(s)).collect(),
+            domain: "corp.local".to_string(),
+            source: NameSource::Domain,
+            observed: Provenance::default(),
+        };
+        let hosts = [
+            host("gitlab.corp.local", &["10.0.0.5"]),
+            host("git.corp.local", &["10.0.0.5", "10.0.0.6"]),
+        ];
+        let text = alive_file_content(&hosts);
+        let lines: Vec<&str> = text.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            [
+                "10.0.0.5 gitlab.corp.local git.corp.local",
+                "10.0.0.6 git.corp.local"
+            ]
+        );
+    }
+}
@@ -0,0 +1,996 @@
+// src/commands/net/http.rs
+use crate::commands::history::RunSummary;
+use crate::utils::blocking::{BlockingStage, run_stage};
+use crate::utils::body_grep::{BodyGrep, GrepArgs, GrepMatch, decode_body, is_binary};
+use crate::utils::cluster::{
+    ClusterArgs, Fingerprint, assign_clusters, body_sha256, favicon_hash, simhash,
+};
+use crate::utils::console::Icon;
+use crate::utils::context::ScanContext;
+use crate::utils::http_pool::{HttpPool, HttpPoolArgs, TargetClient};
+use crate::utils::identity::DEFAULT_USER_AGENT;
+use crate::utils::limits::{
+    ConcurrencySpec, ScanKind, effective_concurrency, parse_fixed_concurrency,
+};
+use crate::utils::output::OutputKind;
+use crate::utils::pool::run_tracked;
+use crate::utils::provenance::Provenance;
+use crate::utils::quic::QuicProber;
+use crate::utils::run_dir::{PORTS_FILE_NAME, SummaryItem};
+use crate::utils::stats::Outcome;
+use crate::utils::targets::{TargetSourceArgs, collect_targets};
+use crate::utils::tls::negotiate_alpn;
+use crate::utils::{ExcelExport, format_elapsed, parse_ports_strict};
+use clap::Parser;
+use reqwest::Version;
+use reqwest::header::{ALT_SVC, CONTENT_TYPE, LOCATION, SERVER};
+use serde::Serialize;
+use std::error::Error;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+/// 默认探测的Web端口
+const DEFAULT_HTTP_PORTS: &str = "80,443,8080,8443";
+
+/// 优先按HTTPS访问的端口
+const HTTPS_PORTS: &[u16] = &[443, 8443, 9443];
+
+/// 标题的最大字符数
+const MAX_TITLE_CHARS: usize = 200;
+
+/// favicon的最大字节数
+const MAX_FAVICON_BYTES: usize = 256 * 1024;
+
+/// 协议名称（ALPN标识），按从新到旧排列
+const PROTOCOLS: &[&str] = &["h3", "h2", "http/1.1", "http/1.0"];
+
+/// Web服务探测参数配置
+#[derive(Parser, Debug)]
+pub struct HttpArgs {
+    /// 目标IP或IP段（支持CIDR、范围、多个IP用逗号隔开）
+    #[arg(
+        short,
+        long,
+        value_name = "TARGET",
+        required_unless_present_any = ["target_xlsx", "target_file"]
+    )]
+    pub target: Option<String>,
+
+    #[command(flatten)]
+    pub sources: TargetSourceArgs,
+
+    /// 探测的端口（443/8443/9443 先按HTTPS访问，其余先按HTTP，失败时换另一种）
+    #[arg(short, long, default_value = DEFAULT_HTTP_PORTS, value_name = "PORTS")]
+    pub ports: String,
+
+    /// 请求的路径
+    #[arg(long, default_value = "/", value_name = "PATH")]
+    pub path: String,
+
+    /// 最大并发数（auto 表示根据文件描述符上限和任务数量自动选择）
+    #[arg(
+        short = 'c',
+        long,
+        env = "GXTOOLS_CONCURRENCY",
+        default_value = "50",
+        value_name = "NUM|auto",
+        value_parser = parse_fixed_concurrency
+    )]
+    pub concurrency: ConcurrencySpec,
+
+    /// 单个请求的超时时间（秒）
+    #[arg(
+        short = 'T',
+        long,
+        env = "GXTOOLS_TIMEOUT",
+        default_value = "10",
+        value_name = "SECS"
+    )]
+    pub timeout: u64,
+
+    /// 最多读取的正文大小（KB），超出部分不读取也不查找
+    #[arg(long, default_value = "512", value_name = "KB")]
+    pub max_body: usize,
+
+    #[command(flatten)]
+    pub grep: GrepArgs,
+
+    #[command(flatten)]
+    pub cluster: ClusterArgs,
+
+    #[command(flatten)]
+    pub pool: HttpPoolArgs,
+
+    /// 额外尝试通过QUIC握手检测HTTP/3（HTTPS端口及通告了h3的服务）
+    #[arg(long)]
+    pub http3: bool,
+
+    /// 是否打印详细结果到终端
+    #[arg(short = 'e', long, env = "GXTOOLS_ECHO")]
+    pub echo: bool,
+
+    /// 是否输出结果到Excel文件
+    #[arg(short = 'o', long, env = "GXTOOLS_OUTPUT")]
+    pub output: bool,
+}
+
+/// 单个Web服务的探测结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HttpResult {
+    /// IP地址
+    pub ip: String,
+    /// 端口号
+    pub port: u16,
+    /// 实际访问的地址
+    pub url: String,
+    /// 状态码
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Server头
+    pub server: String,
+    /// 页面标题
+    pub title: String,
+    /// Content-Type头
+    pub content_type: String,
+    /// 跳转地址（Location头，不跟随跳转）
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub location: String,
+    /// 支持的最新HTTP版本（http/1.1、h2、h3），未能确认时为空
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub protocol: String,
+    /// Alt-Svc头（服务通告的其他协议，如h3）
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub alt_svc: String,
+    /// 读取的正文字节数
+    pub body_bytes: usize,
+    /// 正文超过 `--max-body` 被截断
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// 正文为二进制内容（不做查找）
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub binary: bool,
+    /// 正文的SHA-256（未读取正文时为空）
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub body_sha256: String,
+    /// 规范化页面的simhash
+    #[serde(skip)]
+    pub simhash: Option<u64>,
+    /// favicon的mmh3哈希（仅 `--cluster` 时获取）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favicon_hash: Option<i32>,
+    /// 集群编号（仅 `--cluster` 时，与其他服务内容相同或相似）
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub cluster: String,
+    /// 正文查找的匹配结果
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<GrepMatch>,
+    /// 请求失败的原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 观测时间及探测方式（如 `http-get:/actuator`）
+    #[serde(flatten)]
+    pub observed: Provenance,
+}
+
+/// 按端口决定的访问地址（先尝试的在前）
+fn candidate_urls(ip: &str, port: u16, path: &str) -> [String; 2] {
+    let host = if ip.contains(':') {
+        format!("[{}]", ip)
+    } else {
+        ip.to_string()
+    };
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    };
+    let http = format!("http://{}:{}{}", host, port, path);
+    let https = format!("https://{}:{}{}", host, port, path);
+    if HTTPS_PORTS.contains(&port) {
+        [https, http]
+    } else {
+        [http, https]
+    }
+}
+
+/// 访问单个端口，按需在正文中查找
+///
+/// # 参数
+/// * `client` - 该目标的HTTP客户端（HTTPS失败后改用HTTP等后续请求复用连接）
+/// * `ip` - IP地址
+/// * `port` - 端口号
+/// * `path` - 请求路径
+/// * `max_body` - 最多读取的正文字节数
+/// * `grep` - 查找模式（为 `None` 时不查找）
+pub async fn probe(
+    client: &TargetClient<'_>,
+    ip: &str,
+    port: u16,
+    path: &str,
+    max_body: usize,
+    grep: Option<&BodyGrep>,
+) -> HttpResult {
+    let mut result = HttpResult {
+        ip: ip.to_string(),
+        port,
+        url: String::new(),
+        status: None,
+        server: String::new(),
+        title: String::new(),
+        content_type: String::new(),
+        location: String::new(),
+        protocol: String::new(),
+        alt_svc: String::new(),
+        body_bytes: 0,
+        truncated: false,
+        binary: false,
+        body_sha256: String::new(),
+        simhash: None,
+        favicon_hash: None,
+        cluster: String::new(),
+        matches: Vec::new(),
+        error: None,
+        observed: Provenance::now(format!("http-get:{}", path)),
+    };
+
+    let mut last_error = String::new();
+    for url in candidate_urls(ip, port, path) {
+        let mut response = match client.get(&url).await {
+            Ok(response) => response,
+            Err(e) => {
+                last_error = describe_error(&e);
+                continue;
+            }
+        };
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v: &reqwest::header::HeaderValue| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        result.url = url;
+        result.observed = Provenance::now(format!("http-get:{}", path));
+        result.status = Some(response.status().as_u16());
+        result.server = header(SERVER);
+        result.content_type = header(CONTENT_TYPE);
+        result.location = header(LOCATION);
+        result.alt_svc = header(ALT_SVC);
+        result.protocol = match response.version() {
+            Version::HTTP_10 => "http/1.0",
+            Version::HTTP_2 => "h2",
+            Version::HTTP_3 => "h3",
+            _ => "http/1.1",
+        }
+        .to_string();
+
+        let content_type = Some(result.content_type.as_str()).filter(|ct| !ct.is_empty());
+        // 声明为二进制的内容不下载正文
+        if is_binary(content_type, &[]) {
+            result.binary = true;
+            return result;
+        }
+        let mut body = Vec::new();
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    let take = chunk.len().min(max_body - body.len());
+                    body.extend_from_slice(&chunk[..take]);
+                    if body.len() >= max_body {
+                        // 长度未知时无法确认后面是否还有数据，按截断处理
+                        result.truncated = take < chunk.len()
+                            || response
+                                .content_length()
+                                .is_none_or(|len| len > max_body as u64);
+                        break;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    result.error = Some(format!("读取正文失败: {}", describe_error(&e)));
+                    break;
+                }
+            }
+        }
+        result.body_bytes = body.len();
+        // 解码、摘要及正文查找在阻塞线程中进行
+        let analysis = BodyAnalysis {
+            content_type: content_type.map(str::to_string),
+            body,
+            grep: grep.cloned(),
+        };
+        match run_stage(analysis).await {
+            Ok(digest) => {
+                result.body_sha256 = digest.sha256;
+                result.binary = digest.binary;
+                result.title = digest.title;
+                result.simhash = digest.simhash;
+                result.matches = digest.matches;
+            }
+            Err(e) => result.error = Some(e.to_string()),
+        }
+        return result;
+    }
+    result.error = Some(last_error);
+    result
+}
+
+/// 响应正文的分析阶段：计算摘要、解码并提取标题、simhash及查找结果
+struct BodyAnalysis {
+    content_type: Option<String>,
+    body: Vec<u8>,
+    grep: Option<BodyGrep>,
+}
+
+/// 正文分析的结果
+#[derive(Debug, Default)]
+struct BodyDigest {
+    sha256: String,
+    binary: bool,
+    title: String,
+    simhash: Option<u64>,
+    matches: Vec<GrepMatch>,
+}
+
+impl BlockingStage for BodyAnalysis {
+    type Output = BodyDigest;
+    const NAME: &'static str = "正文分析";
+
+    fn run(self) -> Result<BodyDigest, Box<dyn Error + Send + Sync>> {
+        let mut digest = BodyDigest::default();
+        if !self.body.is_empty() {
+            digest.sha256 = body_sha256(&self.body);
+        }
+        let content_type = self.content_type.as_deref();
+        if is_binary(content_type, &self.body) {
+            digest.binary = true;
+            return Ok(digest);
+        }
+        let text = decode_body(content_type, &self.body);
+        digest.title = extract_title(&text);
+        digest.simhash = simhash(&text);
+        if let Some(grep) = self.grep {
+            digest.matches = grep.scan(&text);
+        }
+        Ok(digest)
+    }
+}
+
+/// 相似页面聚类阶段：比较全部响应的指纹，给出每个响应的集群编号
+struct Clustering {
+    fingerprints: Vec<Fingerprint>,
+    max_distance: u32,
+}
+
+impl BlockingStage for Clustering {
+    type Output = Vec<Option<String>>;
+    const NAME: &'static str = "页面聚类";
+
+    fn run(self) -> Result<Vec<Option<String>>, Box<dyn Error + Send + Sync>> {
+        Ok(assign_clusters(&self.fingerprints, self.max_distance))
+    }
+}
+
+/// 获取站点根目录下的favicon并计算哈希
+///
+/// # 返回
+/// * `None` - 请求失败、状态码非200、内容为空或为HTML页面（服务把所有路径都返回首页）
+pub async fn fetch_favicon(client: &TargetClient<'_>, url: &str) -> Option<i32> {
+    let mut url = reqwest::Url::parse(url).ok()?;
+    url.set_path("/favicon.ico");
+    url.set_query(None);
+    let mut response = client.get(url).await.ok()?;
+    if response.status() != reqwest::StatusCode::OK {
+        return None;
+    }
+    let mut icon = Vec::new();
+    while let Some(chunk) = response.chunk().await.ok()? {
+        icon.extend_from_slice(&chunk);
+        if icon.len() > MAX_FAVICON_BYTES {
+            return None;
+        }
+    }
+    let html = icon
+        .iter()
+        .skip_while(|b| b.is_ascii_whitespace())
+        .take(5)
+        .map(u8::to_ascii_lowercase)
+        .eq(b"<html".iter().copied())
+        || icon.starts_with(b"<!");
+    (!icon.is_empty() && !html).then(|| favicon_hash(&icon))
+}
+
+/// 集群的摘要：每个集群的服务数量及代表标题，按集群编号排列
+fn cluster_summary(results: &[HttpResult]) -> Vec<SummaryItem> {
+    let mut clusters: Vec<(&str, usize, &str)> = Vec::new();
+    for r in results.iter().filter(|r| !r.cluster.is_empty()) {
+        match clusters.iter_mut().find(|(id, _, _)| *id == r.cluster) {
+            Some((_, count, title)) => {
+                *count += 1;
+                if title.is_empty() {
+                    *title = &r.title;
+                }
+            }
+            None => clusters.push((&r.cluster, 1, &r.title)),
+        }
+    }
+    clusters.sort_by(|a, b| (a.0.len(), a.0).cmp(&(b.0.len(), b.0)));
+    clusters
+        .into_iter()
+        .map(|(id, count, title)| {
+            let value = if title.is_empty() {
+                format!("{}台", count)
+            } else {
+                format!("{}台, 标题 '{}'", count, title)
+            };
+            (format!("集群{}", id), value)
+        })
+        .collect()
+}
+
+/// 检测服务支持的更新的HTTP版本，检测失败时保留原结论
+///
+/// HTTPS服务以ALPN握手确认是否支持h2；请求失败的端口也做一次握手，
+/// 以便记录只支持h2的服务。开启 `--http3` 时对HTTPS服务及通告了h3的服务做QUIC握手。
+///
+/// # 参数
+/// * `result` - `probe` 的结果，确认的协议写入 `protocol`
+/// * `path` - 请求路径（只支持h2的服务用于补全访问地址）
+/// * `timeout` - 每次握手的超时
+/// * `quic` - QUIC探测器（为 `None` 时不检测HTTP/3）
+pub async fn detect_protocols(
+    result: &mut HttpResult,
+    path: &str,
+    timeout: Duration,
+    quic: Option<&QuicProber>,
+) {
+    let Ok(ip) = result.ip.parse::<IpAddr>() else {
+        return;
+    };
+    let tls = if result.status.is_some() {
+        result.url.starts_with("https://")
+    } else {
+        result.error.as_deref() != Some("请求超时")
+    };
+    if tls {
+        let addr = SocketAddr::new(ip, result.port);
+        let negotiated = tokio::time::timeout(timeout, async {
+            let stream = TcpStream::connect(addr).await?;
+            negotiate_alpn(stream, &result.ip, &[b"h2", b"http/1.1"]).await
+        })
+        .await;
+        if let Ok(Ok(Some(protocol))) = negotiated
+            && protocol == "h2"
+        {
+            if result.url.is_empty() {
+                result.url = candidate_urls(&result.ip, result.port, path)
+                    .into_iter()
+                    .find(|url| url.starts_with("https://"))
+                    .unwrap_or_default();
+            }
+            result.protocol = protocol;
+        }
+    }
+
+    let advertised = alt_svc_h3_port(&result.alt_svc);
+    if let Some(quic) = quic
+        && (result.url.starts_with("https://") || advertised.is_some())
+    {
+        let addr = SocketAddr::new(ip, advertised.unwrap_or(result.port));
+        if let Ok(Some(protocol)) = quic.handshake(addr, &result.ip, timeout).await
+            && protocol == "h3"
+        {
+            result.protocol = protocol;
+        }
+    }
+}
+
+/// Alt-Svc头中通告的HTTP/3端口
+///
+/// 如 `h3=":443"; ma=86400, h3-29=":443"` 返回443；未通告h3时返回 `None`。
+fn alt_svc_h3_port(alt_svc: &str) -> Option<u16> {
+    alt_svc.split(',').find_map(|entry| {
+        let (protocol, authority) = entry.split(';').next()?.trim().split_once('=')?;
+        if protocol != "h3" && !protocol.starts_with("h3-") {
+            return None;
+        }
+        authority
+            .trim()
+            .trim_matches('"')
+            .rsplit_once(':')?
+            .1
+            .parse()
+            .ok()
+    })
+}
+
+/// 请求错误的简短说明
+fn describe_error(e: &reqwest::Error) -> String {
+    if e.is_timeout() {
+        "请求超时".to_string()
+    } else if e.is_connect() {
+        "连接失败".to_string()
+    } else {
+        let mut message = e.to_string();
+        let mut source = e.source();
+        while let Some(inner) = source {
+            message = format!("{}: {}", message, inner);
+            source = inner.source();
+        }
+        message
+    }
+}
+
+/// 提取页面标题（空白合并为一个空格）
+pub fn extract_title(text: &str) -> String {
+    let lower = text.to_ascii_lowercase();
+    let Some(open) = lower.find("<title") else {
+        return String::new();
+    };
+    let Some(start) = lower[open..].find('>').map(|i| open + i + 1) else {
+        return String::new();
+    };
+    let end = lower[start..]
+        .find("</title")
+        .map_or(text.len(), |i| start + i);
+    text[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_TITLE_CHARS)
+        .collect()
+}
+
+pub async fn run(args: &HttpArgs) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    run_with(args, &ScanContext::cli()).await
+}
+
+/// 探测Web服务的状态码、标题、Server头，并按需在正文中查找关键字
+///
+/// # 参数
+/// * `args` - 探测参数
+/// * `ctx` - 扫描上下文
+///
+/// # 返回
+/// * `Ok(RunSummary)` - 探测完成后的结果摘要
+pub async fn run_with(
+    args: &HttpArgs,
+    ctx: &ScanContext,
+) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    let start = Instant::now();
+
+    let ports = parse_ports_strict(&args.ports)?;
+    let grep = BodyGrep::from_args(&args.grep)?;
+    let targets = collect_targets(args.target.as_deref(), &args.sources).await?;
+    let ips = targets.ips();
+
+    let total = ips.len() * ports.len();
+    let concurrency = effective_concurrency(args.concurrency, total, ScanKind::Connect);
+    let timeout = Duration::from_secs(args.timeout.max(1));
+    let pool = HttpPool::new(timeout, DEFAULT_USER_AGENT, &args.pool)?;
+    let max_body = args.max_body.max(1) * 1024;
+    let quic = if args.http3 {
+        match QuicProber::new(&[b"h3"]) {
+            Ok(quic) => Some(quic),
+            Err(e) => {
+                println!("{} 无法创建QUIC端点，跳过HTTP/3检测: {}", Icon::Warn, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    println!(
+        "{} 配置: 并发={}（{}）, 超时={}秒, 正文上限={}KB, 连接复用=每目标{}个（空闲{}秒）",
+        Icon::Config,
+        concurrency.value,
+        concurrency.reason,
+        args.timeout,
+        args.max_body,
+        args.pool.pool_size,
+        args.pool.pool_idle
+    );
+    if let Some(ref grep) = grep {
+        println!("{} 查找: {}", Icon::Config, grep.labels().join(", "));
+    }
+    println!(
+        "🌐 开始探测Web服务: {} 个IP × {} 个端口 = {} 个任务",
+        ips.len(),
+        ports.len(),
+        total
+    );
+
+    let progress = ctx.new_progress(total as u64);
+    let listener = ctx.interactive.then(|| ctx.pause.listen(&progress, true));
+    let tasks = ips
+        .iter()
+        .flat_map(|ip| ports.iter().map(move |&port| (ip.as_str(), port)));
+    let mut results: Vec<HttpResult> = Vec::new();
+    run_tracked(
+        tasks,
+        concurrency.value,
+        &progress,
+        ctx.token(),
+        |(ip, port)| {
+            let (pool, grep, quic) = (&pool, grep.as_ref(), quic.as_ref());
+            async move {
+                ctx.pause.wait().await;
+                // 该目标的连接在任务结束（含请求失败后放弃）时随客户端一起关闭
+                let client = pool.target(ip, ctx.throttle());
+                let mut result = probe(&client, ip, port, &args.path, max_body, grep).await;
+                detect_protocols(&mut result, &args.path, timeout, quic).await;
+                if args.cluster.cluster && result.status.is_some() {
+                    result.favicon_hash = fetch_favicon(&client, &result.url).await;
+                }
+                result
+            }
+        },
+        |result| {
+            progress.stats().add_received(result.body_bytes as u64);
+            if result.status.is_some() || !result.protocol.is_empty() {
+                if args.echo || !result.matches.is_empty() {
+                    progress.println(format!(
+                        "  🌐 {} [{}] {} {}",
+                        result.url,
+                        result
+                            .status
+                            .map_or_else(|| "-".to_string(), |s| s.to_string()),
+                        result.protocol,
+                        result.title
+                    ));
+                }
+                if let Some(ref grep) = grep {
+                    for m in &result.matches {
+                        progress.println(format!(
+                            "     🔎 {} ×{}: {}",
+                            m.label,
+                            m.count,
+                            grep.highlight(&m.label, &m.excerpts[0])
+                        ));
+                    }
+                }
+                ctx.emit(&result);
+                results.push(result);
+                Outcome::Succeeded
+            } else {
+                Outcome::Failed
+            }
+        },
+    )
+    .await;
+    drop(listener);
+    progress.finish_with_message("✅ Web服务探测完成");
+    results.sort_by(|a, b| (&a.ip, a.port).cmp(&(&b.ip, b.port)));
+    if args.cluster.cluster {
+        let fingerprints: Vec<Fingerprint> = results
+            .iter()
+            .map(|r| Fingerprint {
+                sha256: r.body_sha256.clone(),
+                simhash: r.simhash,
+                favicon: r.favicon_hash,
+            })
+            .collect();
+        let clusters = run_stage(Clustering {
+            fingerprints,
+            max_distance: args.cluster.cluster_distance,
+        })
+        .await?;
+        for (result, cluster) in results.iter_mut().zip(clusters) {
+            result.cluster = cluster.unwrap_or_default();
+        }
+    }
+
+    let counts = progress.snapshot();
+    debug_assert_eq!(counts.succeeded as usize, results.len());
+    let matched = results.iter().filter(|r| !r.matches.is_empty()).count();
+    let mut summary: Vec<SummaryItem> = counts.summary_items("个端口");
+    summary.push(("Web服务".to_string(), format!("{} 个", counts.succeeded)));
+    summary.extend(pool.stats().summary_item());
+    for protocol in PROTOCOLS {
+        let count = results.iter().filter(|r| r.protocol == *protocol).count();
+        if count > 0 {
+            summary.push((format!("协议 {}", protocol), format!("{} 个", count)));
+        }
+    }
+    let advertised = results
+        .iter()
+        .filter(|r| alt_svc_h3_port(&r.alt_svc).is_some())
+        .count();
+    if advertised > 0 {
+        summary.push(("通告h3".to_string(), format!("{} 个", advertised)));
+    }
+    if args.cluster.cluster {
+        let clustered = results.iter().filter(|r| !r.cluster.is_empty()).count();
+        summary.push((
+            "归入集群".to_string(),
+            format!(
+                "{} 个（其余 {} 个各不相同）",
+                clustered,
+                results.len() - clustered
+            ),
+        ));
+        summary.extend(cluster_summary(&results));
+    }
+    if let Some(ref grep) = grep {
+        summary.push(("命中".to_string(), format!("{} 个", matched)));
+        for label in grep.labels() {
+            let hits = results
+                .iter()
+                .filter(|r| r.matches.iter().any(|m| m.label == label))
+                .count();
+            summary.push((format!("查找 {}", label), format!("{} 个", hits)));
+        }
+    }
+    summary.push((
+        "耗时".to_string(),
+        format_elapsed(start.elapsed(), ctx.pause.paused_duration()),
+    ));
+
+    let mut outputs = Vec::new();
+    if args.output {
+        let labels = grep.as_ref().map(BodyGrep::labels).unwrap_or_default();
+        outputs
+            .push(run_stage(export_results(&results, &labels, args.cluster.cluster, ctx)).await?);
+    }
+
+    println!("\n{} 探测统计:", Icon::Stats);
+    for (name, value) in &summary {
+        println!("   {}: {}", name, value);
+    }
+
+    if let Some(run_dir) = ctx.run_dir() {
+        run_dir.write_json(PORTS_FILE_NAME, "json", &results, results.len())?;
+        run_dir.write_summary(&summary)?;
+    }
+
+    Ok(RunSummary {
+        total: counts.completed as usize,
+        succeeded: counts.succeeded as usize,
+        outputs,
+        concurrency: Some(concurrency),
+        timing: None,
+    })
+}
+
+/// 导出探测结果到Excel（每个查找模式一列，内容为匹配次数及第一条摘录；聚类时增加集群及favicon哈希列）
+fn export_results(
+    results: &[HttpResult],
+    labels: &[&str],
+    cluster: bool,
+    ctx: &ScanContext,
+) -> ExcelExport {
+    let mut headers: Vec<&str> = vec![
+        "IP地址",
+        "端口",
+        "地址",
+        "状态码",
+        "协议",
+        "标题",
+        "Server",
+        "Content-Type",
+        "跳转",
+        "Alt-Svc",
+        "正文字节",
+    ];
+    if cluster {
+        headers.extend_from_slice(&["集群", "favicon哈希"]);
+    }
+    headers.extend_from_slice(labels);
+    headers.extend(Provenance::HEADERS);
+    ExcelExport::prepare(
+        results,
+        &headers,
+        |r| {
+            let mut row = vec![
+                r.ip.clone(),
+                r.port.to_string(),
+                r.url.clone(),
+                r.status.map(|s| s.to_string()).unwrap_or_default(),
+                r.protocol.clone(),
+                r.title.clone(),
+                r.server.clone(),
+                r.content_type.clone(),
+                r.location.clone(),
+                r.alt_svc.clone(),
+                if r.binary {
+                    format!("{}（二进制）", r.body_bytes)
+                } else if r.truncated {
+                    format!("{}（已截断）", r.body_bytes)
+                } else {
+                    r.body_bytes.to_string()
+                },
+            ];
+            if cluster {
+                row.push(r.cluster.clone());
+                row.push(r.favicon_hash.map(|h| h.to_string()).unwrap_or_default());
+            }
+            row.extend(labels.iter().map(|label| {
+                r.matches
+                    .iter()
+                    .find(|m| m.label == *label)
+                    .map(|m| format!("{}次: {}", m.count, m.excerpts[0]))
+                    .unwrap_or_default()
+            }));
+            row.extend(r.observed.cells().map(String::from));
+            row
+        },
+        OutputKind::HTTP,
+        &ctx.excel_options(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::body_grep::GrepArgs;
+    use crate::utils::timing::Throttle;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 对每个连接返回同一个响应的本地服务
+    async fn serve(response: Vec<u8>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let response = response.clone();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    let _ = stream.read(&mut buf).await;
+                    let _ = stream.write_all(&response).await;
+                });
+            }
+        });
+        port
+    }
+
+    fn response(content_type: &str, body: &[u8]) -> Vec<u8> {
+        let mut out = format!(
+            "HTTP/1.1 200 OK\r\nServer: nginx\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            content_type,
+            body.len()
+        )
+        .into_bytes();
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn pool() -> HttpPool {
+        HttpPool::new(
+            Duration::from_secs(5),
+            DEFAULT_USER_AGENT,
+            &HttpPoolArgs::default(),
+        )
+        .unwrap()
+    }
+
+    fn grep(patterns: &[&str]) -> BodyGrep {
+        let args = GrepArgs {
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            grep_file: None,
+        };
+        BodyGrep::from_args(&args).unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_probe_decodes_and_greps_body() {
+        let (gbk, _, _) = encoding_rs::GBK.encode(
+            "<html><head><title>\n  某公司 门户 </title></head><body>版权所有 某公司<!-- c99shell --></body></html>",
+        );
+        let port = serve(response("text/html; charset=gbk", &gbk)).await;
+        let (pool, throttle) = (pool(), Throttle::default());
+        let client = pool.target("127.0.0.1", &throttle);
+        let g = grep(&["版权所有", "(?i)C99SHELL", "absent"]);
+
+        let result = probe(&client, "127.0.0.1", port, "/", 1024, Some(&g)).await;
+        assert_eq!(result.error, None);
+        assert_eq!(result.url, format!("http://127.0.0.1:{}/", port));
+        assert_eq!(result.status, Some(200));
+        assert_eq!(result.server, "nginx");
+        assert_eq!(result.title, "某公司 门户");
+        assert_eq!(result.protocol, "http/1.1");
+        assert!(!result.truncated && !result.binary);
+        let labels: Vec<&str> = result.matches.iter().map(|m| m.label.as_str()).collect();
+        assert_eq!(labels, ["版权所有", "(?i)C99SHELL"]);
+    }
+
+    #[tokio::test]
+    async fn test_probe_caps_body_and_skips_binary() {
+        let mut body = b"<html>".to_vec();
+        body.extend(std::iter::repeat_n(b'a', 4000));
+        body.extend_from_slice(b"marker");
+        let port = serve(response("text/html", &body)).await;
+        let (pool, throttle) = (pool(), Throttle::default());
+        let client = pool.target("127.0.0.1", &throttle);
+        let g = grep(&["marker"]);
+
+        let result = probe(&client, "127.0.0.1", port, "/", 1024, Some(&g)).await;
+        assert_eq!(result.body_bytes, 1024);
+        assert!(result.truncated);
+        assert!(result.matches.is_empty());
+
+        let port = serve(response("image/png", b"\x89PNG marker")).await;
+        let result = probe(&client, "127.0.0.1", port, "/", 1024, Some(&g)).await;
+        assert!(result.binary);
+        assert_eq!(result.body_bytes, 0);
+        assert!(result.matches.is_empty());
+    }
+
+    #[test]
+    fn test_candidate_urls_prefer_https_ports() {
+        assert_eq!(
+            candidate_urls("10.0.0.1", 8443, "admin"),
+            [
+                "https://10.0.0.1:8443/admin".to_string(),
+                "http://10.0.0.1:8443/admin".to_string()
+            ]
+        );
+        assert_eq!(candidate_urls("::1", 80, "/")[0], "http://[::1]:80/");
+        assert_eq!(
+            extract_title("<TITLE lang=en>Index of /</TITLE>"),
+            "Index of /"
+        );
+        assert_eq!(extract_title("<html>"), "");
+    }
+
+    #[tokio::test]
+    async fn test_favicon_hash_and_cluster_summary() {
+        let (pool, throttle) = (pool(), Throttle::default());
+        let client = pool.target("127.0.0.1", &throttle);
+        let port = serve(response("text/html", b"<html><title>x</title></html>")).await;
+        let url = format!("http://127.0.0.1:{}/", port);
+        // 所有路径都返回首页的服务没有favicon
+        assert_eq!(fetch_favicon(&client, &url).await, None);
+        let port = serve(response("image/x-icon", b"\x00\x00\x01\x00icon")).await;
+        let url = format!("http://127.0.0.1:{}/admin?x=1", port);
+        assert_eq!(
+            fetch_favicon(&client, &url).await,
+            Some(favicon_hash(b"\x00\x00\x01\x00icon"))
+        );
+
+        let mut result = probe(&client, "127.0.0.1", port, "/", 1024, None).await;
+        result.cluster = "A".to_string();
+        let mut untitled = result.clone();
+        untitled.title = "登录 - 统一认证".to_string();
+        let mut alone = result.clone();
+        alone.cluster.clear();
+        let mut second = result.clone();
+        second.cluster = "B".to_string();
+        assert_eq!(
+            cluster_summary(&[second.clone(), result, untitled, alone, second]),
+            [
+                (
+                    "集群A".to_string(),
+                    "2台, 标题 '登录 - 统一认证'".to_string()
+                ),
+                ("集群B".to_string(), "2台".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_alt_svc_and_fallback_detection() {
+        assert_eq!(
+            alt_svc_h3_port(r#"h3=":443"; ma=86400, h3-29=":443""#),
+            Some(443)
+        );
+        assert_eq!(
+            alt_svc_h3_port(r#"h2=":443", h3-29="cdn.example.com:8443""#),
+            Some(8443)
+        );
+        assert_eq!(alt_svc_h3_port(r#"h2=":443"; ma=60"#), None);
+        assert_eq!(alt_svc_h3_port("clear"), None);
+
+        // 纯HTTP服务上的TLS握手失败时保留原结论
+        let port = serve(response("text/html", b"<title>x</title>")).await;
+        let (pool, throttle) = (pool(), Throttle::default());
+        let client = pool.target("127.0.0.1", &throttle);
+        let mut result = probe(&client, "127.0.0.1", port, "/", 1024, None).await;
+        detect_protocols(&mut result, "/", Duration::from_secs(2), None).await;
+        assert_eq!(result.protocol, "http/1.1");
+        assert_eq!(result.url, format!("http://127.0.0.1:{}/", port));
+    }
+}
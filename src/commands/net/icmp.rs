@@ -0,0 +1,461 @@
+// src/commands/net/icmp.rs
+//! 原生ICMP回显探测
+//!
+//! 直接收发ICMP报文，不依赖系统ping程序：省去每次尝试创建进程的开销，
+//! 不需要解析各平台、各语言版本的ping输出，响应时间也精确到微秒。
+//!
+//! 需要能创建ICMP套接字：原始套接字要求root、`CAP_NET_RAW`（Linux）或管理员权限（Windows）；
+//! Linux下所在组在 `net.ipv4.ping_group_range` 内时也可使用非特权的ICMP套接字，
+//! 但收不到ICMP差错报文及TTL，目标不可达时只能记为超时。
+use crate::commands::net::ping::{FailureReason, PingOptions, Pinger, ProbeOutcome};
+use crate::utils::identity::{ICMP_MARKER_MAX_BYTES, identity};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::error::Error;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+
+/// 回显请求的附加数据长度（与Windows系统ping的默认包长一致）
+const PAYLOAD_LEN: usize = 32;
+
+/// 未指定ICMP标记时的附加数据（同Windows系统ping）
+const DEFAULT_PAYLOAD: &[u8] = b"abcdefghijklmnopqrstuvw";
+
+/// 接收缓冲区大小（足以容纳IP首部、差错报文及其引用的原始报文）
+const RECV_BUFFER: usize = 1500;
+
+/// ICMPv4回显请求/回复、目标不可达、超时的类型
+const ICMPV4_ECHO_REQUEST: u8 = 8;
+const ICMPV4_ECHO_REPLY: u8 = 0;
+const ICMPV4_UNREACHABLE: u8 = 3;
+const ICMPV4_TIME_EXCEEDED: u8 = 11;
+
+/// ICMPv6回显请求/回复、目标不可达、超时的类型
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+const ICMPV6_ECHO_REPLY: u8 = 129;
+const ICMPV6_UNREACHABLE: u8 = 1;
+const ICMPV6_TIME_EXCEEDED: u8 = 3;
+
+/// IPv6首部长度及其中ICMPv6的下一首部值
+const IPV6_HEADER_LEN: usize = 40;
+const IPV6_NEXT_ICMPV6: u8 = 58;
+
+/// 没有权限创建ICMP套接字时的说明
+const PRIVILEGE_HINT: &str = "原生ICMP需要原始套接字权限：Linux下以root运行、授予 CAP_NET_RAW（sudo setcap cap_net_raw+ep <gxr路径>）\
+     或将所在组加入 net.ipv4.ping_group_range，Windows下以管理员身份运行；也可以用 --engine system 改用系统ping";
+
+/// 下一个回显请求的序号（各探测共用，原始套接字会收到所有回复，靠序号区分）
+static NEXT_SEQUENCE: AtomicU16 = AtomicU16::new(1);
+
+/// ICMP套接字的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketKind {
+    /// 原始套接字（需要root、CAP_NET_RAW或管理员权限）
+    Raw,
+    /// 非特权ICMP套接字（Linux的 net.ipv4.ping_group_range、macOS）
+    Datagram,
+}
+
+/// 原生ICMP回显探测器
+///
+/// 每次尝试使用独立的套接字，按来源地址、标识符及序号匹配回复。
+#[derive(Debug, Clone)]
+pub struct IcmpPinger {
+    /// 套接字类型
+    pub kind: SocketKind,
+    /// 回显请求的标识符（非特权套接字下由内核改写）
+    identifier: u16,
+    /// 回显请求的附加数据
+    payload: Vec<u8>,
+}
+
+/// 收到的与本次请求相关的报文
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reply {
+    /// 回显回复及其TTL（只有原始IPv4套接字能取到TTL）
+    Echo { ttl: Option<u8> },
+    /// 引用本次请求的ICMP差错报文
+    Unreachable(FailureReason),
+}
+
+impl IcmpPinger {
+    /// 检查能否创建ICMP套接字并创建探测器（优先使用原始套接字）
+    ///
+    /// # 返回
+    /// * `Err` - 没有所需权限，错误信息中说明如何授权
+    pub fn open() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut last_error = None;
+        for kind in [SocketKind::Raw, SocketKind::Datagram] {
+            match open_socket(Domain::IPV4, kind) {
+                Ok(_) => {
+                    return Ok(Self {
+                        kind,
+                        identifier: std::process::id() as u16,
+                        payload: payload(identity().icmp_marker.as_deref()),
+                    });
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        let reason = last_error.map(|e| e.to_string()).unwrap_or_default();
+        Err(format!("无法创建ICMP套接字（{}）。{}", reason, PRIVILEGE_HINT).into())
+    }
+
+    /// 发送一次回显请求并等待回复
+    async fn echo(&self, target: IpAddr, limit: Duration) -> io::Result<ProbeOutcome> {
+        let domain = match target {
+            IpAddr::V4(_) => Domain::IPV4,
+            IpAddr::V6(_) => Domain::IPV6,
+        };
+        let socket = UdpSocket::from_std(open_socket(domain, self.kind)?.into())?;
+        let sequence = NEXT_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        let request = echo_request(target.is_ipv6(), self.identifier, sequence, &self.payload);
+        // 非特权套接字下标识符由内核改写，套接字只收到自己的回复，不再核对
+        let identifier = (self.kind == SocketKind::Raw).then_some(self.identifier);
+
+        let sent = Instant::now();
+        socket.send_to(&request, SocketAddr::new(target, 0)).await?;
+        let deadline = tokio::time::Instant::now() + limit;
+        let mut buf = [0u8; RECV_BUFFER];
+        loop {
+            let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await
+            else {
+                return Ok(ProbeOutcome::NoReply);
+            };
+            let (len, from) = received?;
+            match parse_reply(&buf[..len], target, from.ip(), identifier, sequence) {
+                Some(Reply::Echo { ttl }) => {
+                    return Ok(ProbeOutcome::Reply {
+                        response_time: Some(sent.elapsed().as_secs_f64() * 1000.0),
+                        ttl,
+                        tcp_port: None,
+                    });
+                }
+                Some(Reply::Unreachable(reason)) => return Ok(ProbeOutcome::Unreachable(reason)),
+                // 其他探测的回复或无关报文
+                None => continue,
+            }
+        }
+    }
+}
+
+impl Pinger for IcmpPinger {
+    /// 发送一次ICMP回显请求（超时时间至少1秒）
+    async fn probe(&self, ip: &str, opts: PingOptions) -> ProbeOutcome {
+        let Ok(target) = ip.parse::<IpAddr>() else {
+            return ProbeOutcome::Error(format!("无效的IP地址: {}", ip));
+        };
+        let limit = Duration::from_secs(opts.timeout_secs.max(1));
+        match self.echo(target, limit).await {
+            Ok(outcome) => outcome,
+            Err(e) => ProbeOutcome::Error(e.to_string()),
+        }
+    }
+}
+
+/// 创建非阻塞的ICMP套接字
+fn open_socket(domain: Domain, kind: SocketKind) -> io::Result<Socket> {
+    let ty = match kind {
+        SocketKind::Raw => Type::RAW,
+        SocketKind::Datagram => Type::DGRAM,
+    };
+    let protocol = if domain == Domain::IPV6 {
+        Protocol::ICMPV6
+    } else {
+        Protocol::ICMPV4
+    };
+    let socket = Socket::new(domain, ty, Some(protocol))?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+/// 回显请求的附加数据：ICMP标记（截取前 [`ICMP_MARKER_MAX_BYTES`] 字节）或默认内容循环填充
+fn payload(marker: Option<&str>) -> Vec<u8> {
+    let pattern = marker
+        .map(|m| &m.as_bytes()[..m.len().min(ICMP_MARKER_MAX_BYTES)])
+        .filter(|p| !p.is_empty())
+        .unwrap_or(DEFAULT_PAYLOAD);
+    pattern.iter().cycle().take(PAYLOAD_LEN).copied().collect()
+}
+
+/// 构造回显请求（ICMPv6的校验和包含伪首部，由内核计算）
+fn echo_request(v6: bool, identifier: u16, sequence: u16, payload: &[u8]) -> Vec<u8> {
+    let kind = if v6 {
+        ICMPV6_ECHO_REQUEST
+    } else {
+        ICMPV4_ECHO_REQUEST
+    };
+    let mut packet = vec![kind, 0, 0, 0];
+    packet.extend_from_slice(&identifier.to_be_bytes());
+    packet.extend_from_slice(&sequence.to_be_bytes());
+    packet.extend_from_slice(payload);
+    if !v6 {
+        let sum = checksum(&packet);
+        packet[2..4].copy_from_slice(&sum.to_be_bytes());
+    }
+    packet
+}
+
+/// 互联网校验和（RFC 1071）
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|pair| u32::from(u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)])))
+        .sum();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// 读取大端16位整数
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([
+        *data.get(offset)?,
+        *data.get(offset + 1)?,
+    ]))
+}
+
+/// 是否为本次请求（或其回复）的回显报文头
+fn echo_matches(icmp: &[u8], identifier: Option<u16>, sequence: u16) -> bool {
+    identifier.is_none_or(|id| read_u16(icmp, 4) == Some(id)) && read_u16(icmp, 6) == Some(sequence)
+}
+
+/// 解析收到的报文，只返回与本次请求相关的回复或差错
+///
+/// # 参数
+/// * `packet` - 收到的报文（原始IPv4套接字带IP首部）
+/// * `target` - 探测的目标
+/// * `from` - 报文的来源地址（差错报文来自沿途的路由器）
+/// * `identifier` - 需要核对的标识符，`None` 时不核对
+/// * `sequence` - 本次请求的序号
+fn parse_reply(
+    packet: &[u8],
+    target: IpAddr,
+    from: IpAddr,
+    identifier: Option<u16>,
+    sequence: u16,
+) -> Option<Reply> {
+    match target {
+        IpAddr::V4(target) => parse_v4(packet, target, from, identifier, sequence),
+        IpAddr::V6(target) => parse_v6(packet, target, from, identifier, sequence),
+    }
+}
+
+/// 解析ICMPv4报文
+fn parse_v4(
+    packet: &[u8],
+    target: Ipv4Addr,
+    from: IpAddr,
+    identifier: Option<u16>,
+    sequence: u16,
+) -> Option<Reply> {
+    // 带IP首部时首字节为版本号4；ICMP报文的首字节是类型，不会是0x4X
+    let (ttl, icmp) = match *packet.first()? {
+        b if b >> 4 == 4 => (
+            Some(*packet.get(8)?),
+            packet.get(usize::from(b & 0x0f) * 4..)?,
+        ),
+        _ => (None, packet),
+    };
+    let (kind, code) = (*icmp.first()?, *icmp.get(1)?);
+    match kind {
+        ICMPV4_ECHO_REPLY => (from == IpAddr::V4(target)
+            && echo_matches(icmp, identifier, sequence))
+        .then_some(Reply::Echo { ttl }),
+        ICMPV4_UNREACHABLE | ICMPV4_TIME_EXCEEDED => {
+            // 差错报文引用原始报文的IP首部及其后8字节
+            let quoted = icmp.get(8..)?;
+            let header_len = usize::from(quoted.first()? & 0x0f) * 4;
+            let dest: [u8; 4] = quoted.get(16..20)?.try_into().ok()?;
+            let original = quoted.get(header_len..)?;
+            (Ipv4Addr::from(dest) == target
+                && original.first() == Some(&ICMPV4_ECHO_REQUEST)
+                && echo_matches(original, identifier, sequence))
+            .then(|| Reply::Unreachable(v4_reason(kind, code)))
+        }
+        _ => None,
+    }
+}
+
+/// 解析ICMPv6报文（ICMPv6套接字收到的报文不带IPv6首部，也取不到跳数限制）
+fn parse_v6(
+    icmp: &[u8],
+    target: Ipv6Addr,
+    from: IpAddr,
+    identifier: Option<u16>,
+    sequence: u16,
+) -> Option<Reply> {
+    let (kind, code) = (*icmp.first()?, *icmp.get(1)?);
+    match kind {
+        ICMPV6_ECHO_REPLY => (from == IpAddr::V6(target)
+            && echo_matches(icmp, identifier, sequence))
+        .then_some(Reply::Echo { ttl: None }),
+        ICMPV6_UNREACHABLE | ICMPV6_TIME_EXCEEDED => {
+            let quoted = icmp.get(8..)?;
+            let dest: [u8; 16] = quoted.get(24..IPV6_HEADER_LEN)?.try_into().ok()?;
+            let original = quoted.get(IPV6_HEADER_LEN..)?;
+            (Ipv6Addr::from(dest) == target
+                && quoted.get(6) == Some(&IPV6_NEXT_ICMPV6)
+                && original.first() == Some(&ICMPV6_ECHO_REQUEST)
+                && echo_matches(original, identifier, sequence))
+            .then(|| Reply::Unreachable(v6_reason(kind, code)))
+        }
+        _ => None,
+    }
+}
+
+/// ICMPv4差错类型及代码对应的失败原因
+fn v4_reason(kind: u8, code: u8) -> FailureReason {
+    match (kind, code) {
+        (ICMPV4_TIME_EXCEEDED, _) => FailureReason::TtlExpired,
+        (_, 0) => FailureReason::NetUnreachable,
+        (_, 2 | 3) => FailureReason::PortUnreachable,
+        (_, 9 | 10 | 13) => FailureReason::AdminProhibited,
+        _ => FailureReason::HostUnreachable,
+    }
+}
+
+/// ICMPv6差错类型及代码对应的失败原因
+fn v6_reason(kind: u8, code: u8) -> FailureReason {
+    match (kind, code) {
+        (ICMPV6_TIME_EXCEEDED, _) => FailureReason::TtlExpired,
+        (_, 0) => FailureReason::NetUnreachable,
+        (_, 1 | 5 | 6) => FailureReason::AdminProhibited,
+        (_, 4) => FailureReason::PortUnreachable,
+        _ => FailureReason::HostUnreachable,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造带IPv4首部的报文
+    fn with_ipv4_header(ttl: u8, src: Ipv4Addr, dst: Ipv4Addr, icmp: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x45, 0, 0, 0, 0, 0, 0, 0, ttl, 1, 0, 0];
+        packet.extend_from_slice(&src.octets());
+        packet.extend_from_slice(&dst.octets());
+        packet.extend_from_slice(icmp);
+        packet
+    }
+
+    #[test]
+    fn test_echo_request_checksum_and_payload() {
+        let request = echo_request(false, 0x1234, 7, &payload(None));
+        assert_eq!(request.len(), 8 + PAYLOAD_LEN);
+        assert_eq!(&request[4..8], &[0x12, 0x34, 0, 7]);
+        // 含校验和重新计算结果为0
+        assert_eq!(checksum(&request), 0);
+        assert_eq!(&payload(Some("GX"))[..4], b"GXGX");
+        assert_eq!(
+            echo_request(true, 1, 1, &[])[..4],
+            [ICMPV6_ECHO_REQUEST, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_parse_v4_reply_and_errors() {
+        let target = Ipv4Addr::new(10, 0, 0, 5);
+        let local = Ipv4Addr::new(10, 0, 0, 1);
+        let mut reply = echo_request(false, 9, 42, b"x");
+        reply[0] = ICMPV4_ECHO_REPLY;
+
+        // 原始套接字带IP首部，TTL取自首部
+        let raw = with_ipv4_header(63, target, local, &reply);
+        assert_eq!(
+            parse_reply(&raw, target.into(), target.into(), Some(9), 42),
+            Some(Reply::Echo { ttl: Some(63) })
+        );
+        // 序号、标识符或来源不符的是其他探测的回复
+        assert_eq!(
+            parse_reply(&raw, target.into(), target.into(), Some(9), 43),
+            None
+        );
+        assert_eq!(
+            parse_reply(&raw, target.into(), target.into(), Some(8), 42),
+            None
+        );
+        assert_eq!(
+            parse_reply(&raw, target.into(), local.into(), Some(9), 42),
+            None
+        );
+        // 非特权套接字只有ICMP部分，不核对标识符
+        assert_eq!(
+            parse_reply(&reply, target.into(), target.into(), None, 42),
+            Some(Reply::Echo { ttl: None })
+        );
+
+        // 路由器返回的管理禁止，引用原始请求
+        let request = echo_request(false, 9, 42, b"x");
+        let mut error = vec![ICMPV4_UNREACHABLE, 13, 0, 0, 0, 0, 0, 0];
+        error.extend(with_ipv4_header(1, local, target, &request));
+        let router = Ipv4Addr::new(10, 0, 0, 254);
+        let raw = with_ipv4_header(255, router, local, &error);
+        assert_eq!(
+            parse_reply(&raw, target.into(), router.into(), Some(9), 42),
+            Some(Reply::Unreachable(FailureReason::AdminProhibited))
+        );
+        assert_eq!(
+            parse_reply(
+                &raw,
+                Ipv4Addr::new(10, 0, 0, 6).into(),
+                router.into(),
+                Some(9),
+                42
+            ),
+            None
+        );
+        assert_eq!(
+            v4_reason(ICMPV4_TIME_EXCEEDED, 0),
+            FailureReason::TtlExpired
+        );
+    }
+
+    #[test]
+    fn test_parse_v6_reply_and_errors() {
+        let target: Ipv6Addr = "2001:db8::5".parse().unwrap();
+        let mut reply = echo_request(true, 9, 42, b"x");
+        reply[0] = ICMPV6_ECHO_REPLY;
+        assert_eq!(
+            parse_reply(&reply, target.into(), target.into(), Some(9), 42),
+            Some(Reply::Echo { ttl: None })
+        );
+
+        let mut header = vec![0x60, 0, 0, 0, 0, 9, IPV6_NEXT_ICMPV6, 64];
+        header.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        header.extend_from_slice(&target.octets());
+        let mut error = vec![ICMPV6_UNREACHABLE, 4, 0, 0, 0, 0, 0, 0];
+        error.extend(header);
+        error.extend(echo_request(true, 9, 42, b"x"));
+        assert_eq!(
+            parse_reply(
+                &error,
+                target.into(),
+                Ipv6Addr::LOCALHOST.into(),
+                Some(9),
+                42
+            ),
+            Some(Reply::Unreachable(FailureReason::PortUnreachable))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_loopback_echo_when_permitted() {
+        // 没有权限创建ICMP套接字的环境跳过
+        let Ok(pinger) = IcmpPinger::open() else {
+            return;
+        };
+        let opts = PingOptions {
+            timeout_secs: 1,
+            count: 1,
+            stats: false,
+        };
+        match pinger.probe("127.0.0.1", opts).await {
+            ProbeOutcome::Reply { response_time, .. } => assert!(response_time.is_some()),
+            other => panic!("回环地址未回复: {:?}", other),
+        }
+    }
+}
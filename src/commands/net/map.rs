@@ -0,0 +1,796 @@
+// src/commands/net/map.rs
+use crate::commands::history::RunSummary;
+use crate::commands::net::ping::{PingOptions, PingResult, SystemPinger, ping_host, tcp_ping};
+use crate::utils::blocking::run_stage;
+use crate::utils::console::Icon;
+use crate::utils::context::ScanContext;
+use crate::utils::iface::{Interface, Neighbor, list_interfaces, mac_vendor, neighbor_table};
+use crate::utils::limits::{
+    ConcurrencySpec, ScanKind, effective_concurrency, parse_fixed_concurrency,
+};
+use crate::utils::output::OutputKind;
+use crate::utils::pool::run_tracked;
+use crate::utils::provenance::Provenance;
+use crate::utils::run_dir::{HOSTS_FILE_NAME, SummaryItem};
+use crate::utils::stats::Outcome;
+use crate::utils::targets::{TargetSourceArgs, collect_targets};
+use crate::utils::{
+    ExcelExport, ExcelSheet, config_file, format_elapsed, load_config_section, parse_ports_strict,
+    scope,
+};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+
+/// 配置文件中的段落名
+const CONFIG_SECTION: &str = "map";
+
+/// 默认的本地网段最大规模（前缀长度）
+const DEFAULT_MAX_LOCAL_PREFIX: u8 = 22;
+
+/// 远程主机ping不通时默认尝试的TCP端口
+const DEFAULT_TCP_PORTS: &str = "22,80,443,445,3389";
+
+/// 触发ARP解析时发送UDP报文的端口（discard）
+const ARP_NUDGE_PORT: u16 = 9;
+
+/// 远程主机汇总时的网段前缀长度
+const REMOTE_SUMMARY_PREFIX: u8 = 24;
+
+/// 网络测绘参数配置
+#[derive(Parser, Debug)]
+pub struct MapArgs {
+    /// 远程网段（支持CIDR、范围、多个用逗号隔开），与配置文件中的 map.remote 合并
+    #[arg(short, long, value_name = "TARGET")]
+    pub target: Option<String>,
+
+    /// 只扫描这些网卡（网卡名称，多个用逗号隔开）
+    #[arg(long = "iface", value_delimiter = ',', value_name = "NAME")]
+    pub include_ifaces: Vec<String>,
+
+    /// 不扫描这些网卡（网卡名称，多个用逗号隔开）
+    #[arg(long = "exclude-iface", value_delimiter = ',', value_name = "NAME")]
+    pub exclude_ifaces: Vec<String>,
+
+    /// 不扫描本地网段，只探测远程网段
+    #[arg(long)]
+    pub no_local: bool,
+
+    /// 本地网段的最大规模（前缀长度），更大的网段只扫描网卡地址所在的这一段
+    #[arg(long, default_value_t = DEFAULT_MAX_LOCAL_PREFIX, value_name = "PREFIX")]
+    pub max_local_prefix: u8,
+
+    /// 远程主机ping不通时尝试连接的TCP端口（连接成功或被拒绝均视为存活）
+    #[arg(long, default_value = DEFAULT_TCP_PORTS, value_name = "PORTS")]
+    pub tcp_ports: String,
+
+    /// 最大并发数（auto 表示根据文件描述符上限和任务数量自动选择）
+    #[arg(
+        short = 'c',
+        long,
+        env = "GXTOOLS_CONCURRENCY",
+        default_value = "100",
+        value_name = "NUM|auto",
+        value_parser = parse_fixed_concurrency
+    )]
+    pub concurrency: ConcurrencySpec,
+
+    /// 每次探测的超时时间（秒）
+    #[arg(
+        short = 'T',
+        long,
+        env = "GXTOOLS_TIMEOUT",
+        default_value = "1",
+        value_name = "SECS"
+    )]
+    pub timeout: u64,
+
+    /// 是否打印详细结果到终端
+    #[arg(short = 'e', long, env = "GXTOOLS_ECHO")]
+    pub echo: bool,
+
+    /// 是否输出结果到Excel文件
+    #[arg(short = 'o', long, env = "GXTOOLS_OUTPUT")]
+    pub output: bool,
+}
+
+/// 配置文件中的测绘参数
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MapConfig {
+    /// 每次测绘都要探测的远程网段
+    pub remote: Vec<String>,
+}
+
+/// 主机的发现方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Method {
+    /// 本机网卡地址
+    Local,
+    /// ARP表中有该主机的MAC
+    Arp,
+    /// ping有回复
+    Icmp,
+    /// TCP端口连接成功或被拒绝
+    Tcp,
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Method::Local => "本机",
+            Method::Arp => "ARP",
+            Method::Icmp => "ICMP",
+            Method::Tcp => "TCP",
+        })
+    }
+}
+
+impl Serialize for Method {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// 测绘发现的一台主机
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MapHost {
+    /// IP地址
+    pub ip: String,
+    /// 所属网段（本地网段为网卡网段，远程主机为所在的/24）
+    pub subnet: String,
+    /// 所在网卡（仅本地网段）
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub interface: String,
+    /// MAC地址（仅本地网段）
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub mac: String,
+    /// 网卡厂商
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub vendor: String,
+    /// 发现方式
+    pub methods: Vec<Method>,
+    /// ping响应时间（毫秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtt_ms: Option<f64>,
+    /// 响应的TCP端口
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tcp_port: Option<u16>,
+    /// 观测时间及探测方式（本机为 `local-interface`，仅ARP表中有的为 `arp-table`）
+    #[serde(flatten)]
+    pub observed: Provenance,
+}
+
+/// 一个网段的测绘统计
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentSummary {
+    /// 网段
+    pub subnet: String,
+    /// 网卡（远程网段为空）
+    pub interface: String,
+    /// 探测的地址数
+    pub probed: usize,
+    /// 存活数
+    pub alive: usize,
+    /// 各发现方式的主机数
+    pub methods: BTreeMap<Method, usize>,
+}
+
+/// 单个地址的探测结果
+#[derive(Debug, Clone)]
+pub struct ProbeRecord {
+    /// IP地址
+    pub ip: Ipv4Addr,
+    /// ping结果
+    pub ping: PingResult,
+    /// 响应的TCP端口（仅远程主机）
+    pub tcp_port: Option<u16>,
+    /// 观测时间及探测方式（TCP有响应时为该端口的连接，否则为ping）
+    pub observed: Provenance,
+}
+
+/// 要扫描的本地网段：按参数过滤网卡，过大的网段收窄到网卡地址所在的一段，同一网段只保留一次
+///
+/// # 参数
+/// * `interfaces` - 本机网卡
+/// * `include` - 只保留这些网卡（为空时不限）
+/// * `exclude` - 排除这些网卡
+/// * `max_prefix` - 网段的最大规模（前缀长度）
+pub fn local_segments(
+    interfaces: &[Interface],
+    include: &[String],
+    exclude: &[String],
+    max_prefix: u8,
+) -> Vec<Interface> {
+    let mut segments: Vec<Interface> = Vec::new();
+    for iface in interfaces {
+        if (!include.is_empty() && !include.contains(&iface.name)) || exclude.contains(&iface.name)
+        {
+            continue;
+        }
+        let segment = Interface {
+            prefix: iface.prefix.max(max_prefix.min(32)),
+            ..iface.clone()
+        };
+        if !segments.iter().any(|s| s.cidr() == segment.cidr()) {
+            segments.push(segment);
+        }
+    }
+    segments
+}
+
+/// 网段内要探测的地址（不含网络地址、广播地址及网卡自身地址）
+pub fn segment_hosts(segment: &Interface) -> Vec<Ipv4Addr> {
+    let network = u32::from(segment.network());
+    let size = 1u64 << (32 - u32::from(segment.prefix.min(32)));
+    let range = if size > 2 { 1..size - 1 } else { 0..size };
+    range
+        .map(|offset| Ipv4Addr::from(network + offset as u32))
+        .filter(|ip| *ip != segment.addr)
+        .collect()
+}
+
+/// 合并各方式的探测结果
+///
+/// 本地网段中ping有回复或ARP表中有MAC的地址记为存活，网卡自身地址记为"本机"；
+/// 远程地址ping有回复或TCP端口有响应的记为存活。
+///
+/// # 参数
+/// * `segments` - 本地网段
+/// * `records` - 各地址的探测结果
+/// * `neighbors` - 扫描结束后读取的ARP表
+///
+/// # 返回
+/// 存活主机，按IP排序
+pub fn merge_results(
+    segments: &[Interface],
+    records: &[ProbeRecord],
+    neighbors: &[Neighbor],
+) -> Vec<MapHost> {
+    let macs: HashMap<Ipv4Addr, &str> = neighbors.iter().map(|n| (n.ip, n.mac.as_str())).collect();
+    let mut hosts: BTreeMap<Ipv4Addr, MapHost> = BTreeMap::new();
+    let new_host = |ip: Ipv4Addr, mac: Option<&str>| {
+        let segment = segments.iter().find(|s| s.contains(ip));
+        let mac = mac.unwrap_or_default().to_string();
+        MapHost {
+            ip: ip.to_string(),
+            subnet: match segment {
+                Some(s) => s.cidr(),
+                None => remote_subnet(ip),
+            },
+            interface: segment.map(|s| s.name.clone()).unwrap_or_default(),
+            vendor: mac_vendor(&mac).unwrap_or_default().to_string(),
+            mac,
+            methods: Vec::new(),
+            rtt_ms: None,
+            tcp_port: None,
+            observed: Provenance::default(),
+        }
+    };
+
+    for segment in segments {
+        let mut host = new_host(segment.addr, segment.mac.as_deref());
+        host.methods.push(Method::Local);
+        host.observed = Provenance::now("local-interface");
+        hosts.insert(segment.addr, host);
+    }
+    for record in records {
+        let local = segments.iter().any(|s| s.contains(record.ip));
+        let mac = macs.get(&record.ip).copied().filter(|_| local);
+        let mut methods = Vec::new();
+        if mac.is_some() {
+            methods.push(Method::Arp);
+        }
+        if record.ping.is_success() {
+            methods.push(Method::Icmp);
+        }
+        if record.tcp_port.is_some() {
+            methods.push(Method::Tcp);
+        }
+        if methods.is_empty() {
+            continue;
+        }
+        let host = hosts
+            .entry(record.ip)
+            .or_insert_with(|| new_host(record.ip, mac));
+        host.observed = if record.ping.is_success() || record.tcp_port.is_some() {
+            record.observed.clone()
+        } else {
+            Provenance::now("arp-table")
+        };
+        host.methods.extend(methods);
+        host.rtt_ms = record.ping.response_time;
+        host.tcp_port = record.tcp_port;
+    }
+    hosts.into_values().collect()
+}
+
+/// 远程主机汇总时所在的网段
+fn remote_subnet(ip: Ipv4Addr) -> String {
+    let mask = u32::MAX << (32 - u32::from(REMOTE_SUMMARY_PREFIX));
+    format!(
+        "{}/{}",
+        Ipv4Addr::from(u32::from(ip) & mask),
+        REMOTE_SUMMARY_PREFIX
+    )
+}
+
+/// 按网段统计（本地网段在前，远程网段按/24汇总）
+///
+/// # 参数
+/// * `segments` - 本地网段
+/// * `records` - 各地址的探测结果（用于统计探测数）
+/// * `hosts` - 存活主机
+pub fn summarize_segments(
+    segments: &[Interface],
+    records: &[ProbeRecord],
+    hosts: &[MapHost],
+) -> Vec<SegmentSummary> {
+    let mut summaries: Vec<SegmentSummary> = segments
+        .iter()
+        .map(|s| SegmentSummary {
+            subnet: s.cidr(),
+            interface: s.name.clone(),
+            probed: 0,
+            alive: 0,
+            methods: BTreeMap::new(),
+        })
+        .collect();
+    let mut remote: BTreeMap<String, SegmentSummary> = BTreeMap::new();
+    for record in records {
+        let subnet = match segments.iter().find(|s| s.contains(record.ip)) {
+            Some(s) => s.cidr(),
+            None => remote_subnet(record.ip),
+        };
+        match summaries.iter_mut().find(|s| s.subnet == subnet) {
+            Some(summary) => summary.probed += 1,
+            None => {
+                remote
+                    .entry(subnet.clone())
+                    .or_insert_with(|| SegmentSummary {
+                        subnet,
+                        interface: String::new(),
+                        probed: 0,
+                        alive: 0,
+                        methods: BTreeMap::new(),
+                    })
+                    .probed += 1
+            }
+        }
+    }
+    summaries.extend(remote.into_values());
+    for host in hosts {
+        if let Some(summary) = summaries.iter_mut().find(|s| s.subnet == host.subnet) {
+            summary.alive += 1;
+            for method in &host.methods {
+                *summary.methods.entry(*method).or_default() += 1;
+            }
+        }
+    }
+    summaries
+}
+
+pub async fn run(args: &MapArgs) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    run_with(args, &ScanContext::cli()).await
+}
+
+/// 测绘本地网段及远程网段的存活主机
+///
+/// 本地网段逐个地址发送一个UDP报文触发ARP解析并ping，扫描结束后读取系统ARP表；
+/// 远程网段先ping，不通时再尝试连接几个常见TCP端口。
+///
+/// # 参数
+/// * `args` - 测绘参数
+/// * `ctx` - 扫描上下文
+///
+/// # 返回
+/// * `Ok(RunSummary)` - 测绘完成后的结果摘要
+pub async fn run_with(
+    args: &MapArgs,
+    ctx: &ScanContext,
+) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    let start = Instant::now();
+    let tcp_ports = parse_ports_strict(&args.tcp_ports)?;
+    let config: MapConfig = load_config_section(&config_file(), CONFIG_SECTION)?;
+
+    let segments = if args.no_local {
+        Vec::new()
+    } else {
+        match list_interfaces() {
+            Ok(interfaces) => local_segments(
+                &interfaces,
+                &args.include_ifaces,
+                &args.exclude_ifaces,
+                args.max_local_prefix,
+            ),
+            Err(e) => {
+                println!("{} 无法枚举本机网卡，跳过本地网段: {}", Icon::Warn, e);
+                Vec::new()
+            }
+        }
+    };
+    for segment in &segments {
+        println!(
+            "{} 本地网段: {}（{}，本机 {}）",
+            Icon::List,
+            segment.cidr(),
+            segment.name,
+            segment.addr
+        );
+    }
+    let mut local: Vec<String> = segments
+        .iter()
+        .flat_map(segment_hosts)
+        .map(|ip| ip.to_string())
+        .collect();
+    let excluded = scope::enforce_ips(&local)?;
+    local.retain(|ip| !excluded.contains(ip));
+
+    let mut remote_specs: Vec<String> = config.remote.clone();
+    remote_specs.extend(args.target.clone());
+    let local_set: HashSet<&str> = local.iter().map(String::as_str).collect();
+    let remote: Vec<String> = if remote_specs.is_empty() {
+        Vec::new()
+    } else {
+        collect_targets(Some(&remote_specs.join(",")), &TargetSourceArgs::default())
+            .await?
+            .ips()
+            .into_iter()
+            .filter(|ip| !local_set.contains(ip.as_str()) && ip.parse::<Ipv4Addr>().is_ok())
+            .collect()
+    };
+    if local.is_empty() && remote.is_empty() {
+        return Err(
+            "没有可探测的网段（未发现本地网段，也未通过 -t 或配置文件指定远程网段）".into(),
+        );
+    }
+
+    let total = local.len() + remote.len();
+    let concurrency = effective_concurrency(args.concurrency, total, ScanKind::Icmp);
+    let timeout = Duration::from_secs(args.timeout.max(1));
+    let opts = PingOptions {
+        timeout_secs: args.timeout.max(1),
+        count: 1,
+        stats: false,
+    };
+    println!(
+        "{} 配置: 并发={}（{}）, 超时={}秒, TCP端口={}",
+        Icon::Config,
+        concurrency.value,
+        concurrency.reason,
+        opts.timeout_secs,
+        args.tcp_ports
+    );
+    println!(
+        "{} 开始测绘: 本地 {} 个地址（ARP+ICMP），远程 {} 个地址（ICMP+TCP）",
+        Icon::Scan,
+        local.len(),
+        remote.len()
+    );
+
+    // 向本地地址发送UDP报文，系统会先做ARP解析，不需要ping程序或原始套接字
+    let nudge = UdpSocket::bind("0.0.0.0:0").await.ok();
+    let pinger = SystemPinger::default();
+    let progress = ctx.new_progress(total as u64);
+    let listener = ctx.interactive.then(|| ctx.pause.listen(&progress, true));
+    let tasks = local
+        .iter()
+        .map(|ip| (ip.as_str(), true))
+        .chain(remote.iter().map(|ip| (ip.as_str(), false)));
+    let mut records: Vec<ProbeRecord> = Vec::new();
+    run_tracked(
+        tasks,
+        concurrency.value,
+        &progress,
+        ctx.token(),
+        |(ip, local)| {
+            let (pinger, nudge, tcp_ports) = (&pinger, nudge.as_ref(), &tcp_ports);
+            async move {
+                ctx.pause.wait().await;
+                let addr: Ipv4Addr = ip.parse().expect("目标已按IPv4过滤");
+                if local && let Some(socket) = nudge {
+                    let _ = socket.send_to(&[0], (addr, ARP_NUDGE_PORT)).await;
+                }
+                let ping = ping_host(pinger, ip, opts, ctx.throttle()).await;
+                let tcp_port = if local || ping.is_success() {
+                    None
+                } else {
+                    tcp_ping(addr.into(), tcp_ports, timeout).await
+                };
+                let observed = match tcp_port {
+                    Some(port) => Provenance::now(format!("tcp-connect:{}", port)),
+                    None => ping.observed.clone(),
+                };
+                ProbeRecord {
+                    ip: addr,
+                    ping,
+                    tcp_port,
+                    observed,
+                }
+            }
+        },
+        |record| {
+            let alive = record.ping.is_success() || record.tcp_port.is_some();
+            if args.echo && alive {
+                progress.println(format!(
+                    "  ✅ {} {}",
+                    record.ip,
+                    record
+                        .tcp_port
+                        .map_or_else(|| "ICMP".to_string(), |p| format!("TCP {}", p))
+                ));
+            }
+            records.push(record);
+            if alive {
+                Outcome::Succeeded
+            } else {
+                Outcome::Failed
+            }
+        },
+    )
+    .await;
+    drop(listener);
+    progress.finish_with_message("✅ 网络测绘完成");
+
+    let neighbors = if segments.is_empty() {
+        Vec::new()
+    } else {
+        neighbor_table().await.unwrap_or_else(|e| {
+            println!(
+                "{} 无法读取ARP表，本地网段只按ping结果统计: {}",
+                Icon::Warn,
+                e
+            );
+            Vec::new()
+        })
+    };
+    let hosts = merge_results(&segments, &records, &neighbors);
+    let summaries = summarize_segments(&segments, &records, &hosts);
+    for host in &hosts {
+        ctx.emit(host);
+    }
+
+    // 本地网段的主机可能只出现在ARP表中，存活数以合并后的主机清单为准
+    let counts = progress.snapshot();
+    debug_assert_eq!(counts.completed as usize, records.len());
+    let mut summary: Vec<SummaryItem> = counts.summary_items("个地址");
+    summary.push(("存活".to_string(), format!("{} 台", hosts.len())));
+    for s in &summaries {
+        let name = if s.interface.is_empty() {
+            format!("网段 {}", s.subnet)
+        } else {
+            format!("网段 {}（{}）", s.subnet, s.interface)
+        };
+        let methods: Vec<String> = s
+            .methods
+            .iter()
+            .map(|(m, n)| format!("{} {}", m, n))
+            .collect();
+        summary.push((
+            name,
+            format!("存活 {}/{}（{}）", s.alive, s.probed, methods.join("，")),
+        ));
+    }
+    summary.push((
+        "耗时".to_string(),
+        format_elapsed(start.elapsed(), ctx.pause.paused_duration()),
+    ));
+
+    let mut outputs = Vec::new();
+    if args.output {
+        outputs.push(run_stage(export_results(&hosts, &summaries, ctx)).await?);
+    }
+
+    println!("\n📊 测绘统计:");
+    for (name, value) in &summary {
+        println!("   {}: {}", name, value);
+    }
+
+    if let Some(run_dir) = ctx.run_dir() {
+        run_dir.write_json(HOSTS_FILE_NAME, "json", &hosts, hosts.len())?;
+        run_dir.write_summary(&summary)?;
+    }
+
+    Ok(RunSummary {
+        total: counts.completed as usize,
+        succeeded: hosts.len(),
+        outputs,
+        concurrency: Some(concurrency),
+        timing: None,
+    })
+}
+
+/// 导出主机清单到Excel（附网段汇总工作表）
+fn export_results(
+    hosts: &[MapHost],
+    summaries: &[SegmentSummary],
+    ctx: &ScanContext,
+) -> ExcelExport {
+    let headers = [
+        "IP地址",
+        "网段",
+        "网卡",
+        "MAC地址",
+        "厂商",
+        "发现方式",
+        "响应时间(ms)",
+        "TCP端口",
+        Provenance::HEADERS[0],
+        Provenance::HEADERS[1],
+    ];
+    let count = |s: &SegmentSummary, m: Method| s.methods.get(&m).copied().unwrap_or(0).to_string();
+    let mut options = ctx.excel_options();
+    options.extra_sheets.push(ExcelSheet {
+        name: "网段汇总".to_string(),
+        headers: ["网段", "网卡", "探测数", "存活数", "ARP", "ICMP", "TCP"]
+            .iter()
+            .map(|h| h.to_string())
+            .collect(),
+        rows: summaries
+            .iter()
+            .map(|s| {
+                vec![
+                    s.subnet.clone(),
+                    s.interface.clone(),
+                    s.probed.to_string(),
+                    s.alive.to_string(),
+                    count(s, Method::Arp),
+                    count(s, Method::Icmp),
+                    count(s, Method::Tcp),
+                ]
+            })
+            .collect(),
+    });
+    ExcelExport::prepare(
+        hosts,
+        &headers,
+        |h| {
+            vec![
+                h.ip.clone(),
+                h.subnet.clone(),
+                h.interface.clone(),
+                h.mac.clone(),
+                h.vendor.clone(),
+                h.methods
+                    .iter()
+                    .map(Method::to_string)
+                    .collect::<Vec<_>>()
+                    .join("+"),
+                h.rtt_ms.map(|t| format!("{:.1}", t)).unwrap_or_default(),
+                h.tcp_port.map(|p| p.to_string()).unwrap_or_default(),
+                h.observed.observed_at.clone(),
+                h.observed.probe.clone(),
+            ]
+        },
+        OutputKind::MAP,
+        &options,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iface(name: &str, addr: &str, prefix: u8) -> Interface {
+        Interface {
+            name: name.to_string(),
+            addr: addr.parse().unwrap(),
+            prefix,
+            mac: Some("00:0c:29:00:00:01".to_string()),
+        }
+    }
+
+    fn record(ip: &str, alive: bool, tcp_port: Option<u16>) -> ProbeRecord {
+        let ping = if alive {
+            PingResult::success(ip.to_string(), Some(1.5), Some(64))
+        } else {
+            PingResult::failure(ip.to_string(), None)
+        };
+        let observed = match tcp_port {
+            Some(port) => Provenance::now(format!("tcp-connect:{}", port)),
+            None => ping.observed.clone(),
+        };
+        ProbeRecord {
+            ip: ip.parse().unwrap(),
+            ping,
+            tcp_port,
+            observed,
+        }
+    }
+
+    #[test]
+    fn test_local_segments_filter_and_narrow() {
+        let interfaces = [
+            iface("eth0", "10.20.30.40", 8),
+            iface("eth1", "192.168.1.5", 24),
+            iface("eth1:1", "192.168.1.6", 24),
+            iface("docker0", "172.17.0.1", 16),
+        ];
+        let segments = local_segments(&interfaces, &[], &["docker0".to_string()], 22);
+        let cidrs: Vec<String> = segments.iter().map(Interface::cidr).collect();
+        assert_eq!(cidrs, ["10.20.28.0/22", "192.168.1.0/24"]);
+        assert_eq!(segment_hosts(&segments[1]).len(), 253);
+
+        let only = local_segments(&interfaces, &["eth1".to_string()], &[], 22);
+        assert_eq!(only.len(), 1);
+        assert_eq!(
+            segment_hosts(&iface("p2p", "10.0.0.1", 31)),
+            [Ipv4Addr::new(10, 0, 0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_merge_and_summarize() {
+        let segments = [iface("eth0", "192.168.1.5", 24)];
+        let records = [
+            record("192.168.1.1", true, None),
+            record("192.168.1.2", false, None),
+            record("192.168.1.3", false, None),
+            record("10.0.0.1", false, Some(443)),
+            record("10.0.0.2", false, None),
+            record("10.0.1.9", true, None),
+        ];
+        let neighbors = [
+            Neighbor {
+                ip: "192.168.1.1".parse().unwrap(),
+                mac: "b8:27:eb:00:00:01".to_string(),
+            },
+            Neighbor {
+                ip: "192.168.1.2".parse().unwrap(),
+                mac: "00:50:56:00:00:02".to_string(),
+            },
+        ];
+        let hosts = merge_results(&segments, &records, &neighbors);
+        let rows: Vec<(&str, &str, Vec<Method>)> = hosts
+            .iter()
+            .map(|h| (h.ip.as_str(), h.vendor.as_str(), h.methods.clone()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("10.0.0.1", "", vec![Method::Tcp]),
+                ("10.0.1.9", "", vec![Method::Icmp]),
+                (
+                    "192.168.1.1",
+                    "Raspberry Pi",
+                    vec![Method::Arp, Method::Icmp]
+                ),
+                ("192.168.1.2", "VMware", vec![Method::Arp]),
+                ("192.168.1.5", "VMware", vec![Method::Local]),
+            ]
+        );
+        assert_eq!(hosts[0].subnet, "10.0.0.0/24");
+        assert_eq!(hosts[0].tcp_port, Some(443));
+        let probes: Vec<&str> = hosts.iter().map(|h| h.observed.probe.as_str()).collect();
+        assert_eq!(
+            probes,
+            [
+                "tcp-connect:443",
+                "icmp",
+                "icmp",
+                "arp-table",
+                "local-interface"
+            ]
+        );
+
+        let summaries = summarize_segments(&segments, &records, &hosts);
+        let counts: Vec<(&str, usize, usize)> = summaries
+            .iter()
+            .map(|s| (s.subnet.as_str(), s.probed, s.alive))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                ("192.168.1.0/24", 3, 3),
+                ("10.0.0.0/24", 2, 1),
+                ("10.0.1.0/24", 1, 1)
+            ]
+        );
+        assert_eq!(summaries[0].methods.get(&Method::Arp), Some(&2));
+    }
+}
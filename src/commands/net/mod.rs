@@ -1,2 +1,7 @@
+pub mod arpscan;
+pub mod dnssweep;
+pub mod http;
+pub mod icmp;
+pub mod map;
 pub mod ping;
 // pub mod trace;
\ No newline at end of file
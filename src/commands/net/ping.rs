@@ -1,14 +1,78 @@
 // src/commands/net/ping.rs
-use crate::utils::{ScanProgress, parse_targets, save_to_excel};
-use clap::Parser;
+use crate::commands::doctor::check_ping_program;
+use crate::commands::history::RunSummary;
+use crate::commands::net::icmp::IcmpPinger;
+use crate::commands::profile::ProfileOptions;
+use crate::commands::report::load_run_rows;
+use crate::utils::adaptive::AdaptiveController;
+use crate::utils::assets::{self, AssetArgs, AssetDb, ChangeCounts, Observation, change_notes};
+use crate::utils::blocking::{BlockingStage, run_stage};
+use crate::utils::console::Icon;
+use crate::utils::context::ScanContext;
+use crate::utils::dns;
+use crate::utils::dualstack::{self, DualStackHost, dual_stack_sheet};
+use crate::utils::finding::consolidate;
+use crate::utils::geo::{GeoArgs, GeoInfo, GeoLookup, asn_rollup_sheet};
+use crate::utils::identity::identity;
+use crate::utils::limits::{ConcurrencySpec, ScanKind, effective_concurrency};
+use crate::utils::output::OutputKind;
+use crate::utils::plan::{PlanArgs, ScanPlan};
+use crate::utils::pool::{run_tracked, run_tracked_streamed};
+use crate::utils::process::{CommandOutcome, output_with_timeout};
+use crate::utils::provenance::{Provenance, describe_staleness};
+use crate::utils::run_dir::{
+    CONCURRENCY_FILE_NAME, DUAL_STACK_FILE_NAME, FINDINGS_FILE_NAME, HOSTS_FILE_NAME, SummaryItem,
+    TARGET_OVERRIDES_FILE_NAME,
+};
+use crate::utils::sample::{RandomizeArgs, Sample, SampleArgs};
+use crate::utils::script::{
+    HookPoint, ScriptArgs, ScriptFetch, ScriptHook, ScriptRow, ScriptStage, print_derived,
+};
+use crate::utils::snapshot::{
+    ResultCollector, SnapshotArgs, SnapshotWriter, Snapshotter, snapshot_file_name,
+    write_json_snapshot,
+};
+use crate::utils::stats::{Outcome, ScanStats};
+use crate::utils::targets::{
+    Tags, TargetOverrides, TargetSourceArgs, alias_suffix, collect_targets, overrides_summary_item,
+    print_overrides, tag_keys,
+};
+use crate::utils::timing::{Throttle, Timing, TimingArgs, TimingDefaults};
+use crate::utils::transcript::{PING_KIND, Tape, TranscriptArgs, base64_bytes};
+use crate::utils::verify::{Verification, VerifyArgs, VerifyKind};
+use crate::utils::{
+    ExcelExport, ExcelOptions, RowWriter, ScanProgress, format_elapsed, ip_sort_key,
+    parse_ports_strict,
+};
+use clap::{Parser, ValueEnum};
+use futures::FutureExt;
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::{BTreeMap, HashSet};
 use std::error::Error;
-use std::sync::Arc;
-use std::time::Instant;
+use std::net::{IpAddr, SocketAddr};
+use std::panic::AssertUnwindSafe;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
 use tokio::process::Command;
-use tokio::sync::Semaphore;
+
+/// 系统ping程序
+pub const PING_PROGRAM: &str = "ping";
+
+/// ping进程硬性时限在 `--timeout` 之外的宽限时间
+const PING_GRACE: Duration = Duration::from_millis(500);
+
+/// 未收到回复时的重试间隔（Windows下加大间隔，避免请求过于密集）
+const RETRY_INTERVAL: Duration = if cfg!(target_os = "windows") {
+    Duration::from_millis(200)
+} else {
+    Duration::from_millis(100)
+};
 
 /// Ping扫描参数配置
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Serialize, Deserialize)]
 pub struct PingArgs {
     /// IP地址或网段（支持CIDR、范围、多个IP用逗号隔开）
     ///
@@ -17,57 +81,449 @@ pub struct PingArgs {
     /// - 多个IP: 192.168.1.1,192.168.1.2
     /// - IP范围: 192.168.1.1-10
     /// - CIDR: 192.168.1.0/24
-    #[arg(short, long, value_name = "TARGET")]
-    pub target: String,
+    /// - 按段通配: 192.168.*.*、10.0.1-5.1
+    /// - 标准输入: -（每行一个或逗号隔开，# 之后为注释，如 cat scope.txt | gxtools net ping -t -）
+    #[arg(
+        short,
+        long,
+        value_name = "TARGET",
+        required_unless_present_any = ["target_xlsx", "target_file", "profile"]
+    )]
+    pub target: Option<String>,
 
-    /// 超时时间（秒）
-    #[arg(short = 'T', long, default_value = "2", value_name = "SECS")]
-    pub timeout: u64,
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub sources: TargetSourceArgs,
 
-    /// 最大并发数
-    #[arg(short = 'c', long, default_value = "100", value_name = "NUM")]
-    pub concurrency: usize,
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub sample: SampleArgs,
 
-    /// 每个IP的ping次数（只要有一次成功即判定为存活）
-    #[arg(short = 'n', long, default_value = "3", value_name = "COUNT")]
-    pub count: u32,
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub order: RandomizeArgs,
+
+    /// 超时时间（秒），默认2秒，随 --timing 调整
+    #[arg(short = 'T', long, env = "GXTOOLS_TIMEOUT", value_name = "SECS")]
+    pub timeout: Option<u64>,
+
+    /// 最大并发数（auto 表示根据文件描述符上限和目标数量自动选择，auto-adaptive 表示按出错率在 --min 与 --max 之间自动调整），默认100，随 --timing 调整
+    #[arg(
+        short = 'c',
+        long,
+        env = "GXTOOLS_CONCURRENCY",
+        value_name = "NUM|auto|auto-adaptive"
+    )]
+    pub concurrency: Option<ConcurrencySpec>,
+
+    /// 每个IP的ping次数（只要有一次成功即判定为存活），默认3次，优先于 --retries
+    #[arg(short = 'n', long, env = "GXTOOLS_PING_COUNT", value_name = "COUNT")]
+    pub count: Option<u32>,
+
+    /// 探测引擎：icmp 为原生ICMP（需要 root、CAP_NET_RAW 或管理员权限），system 为调用系统ping程序；
+    /// 未指定时有权限则使用 icmp，否则回退到 system
+    #[arg(long, env = "GXTOOLS_PING_ENGINE", value_enum, value_name = "ENGINE")]
+    #[serde(default)]
+    pub engine: Option<PingEngine>,
+
+    /// 探测方式：icmp 为ICMP回显；tcp 为连接 --tcp-ports 中的端口，连接成功或被拒绝均视为存活，
+    /// 适用于过滤ICMP的网段；auto 为先ICMP，无回复的主机再尝试TCP
+    #[arg(
+        long,
+        env = "GXTOOLS_PING_METHOD",
+        value_enum,
+        default_value_t,
+        value_name = "METHOD"
+    )]
+    #[serde(default)]
+    pub method: PingMethod,
+
+    /// TCP探测的端口（--method tcp/auto 时使用）
+    #[arg(long, default_value = DEFAULT_TCP_PORTS, value_name = "PORTS")]
+    #[serde(default = "default_tcp_ports")]
+    pub tcp_ports: String,
+
+    /// 统计模式：每个IP发满 -n 次，统计丢包率及最小/平均/最大/标准差时延，用于测量链路质量
+    /// （默认有一次成功即停止，扫描更快）
+    #[arg(long, env = "GXTOOLS_PING_STATS")]
+    #[serde(default)]
+    pub stats: bool,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub timing: TimingArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub geo: GeoArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub script: ScriptArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub transcript: TranscriptArgs,
+
+    /// 只解析目标并打印扫描计划（含目标参数覆盖），不发送任何探测
+    #[arg(long)]
+    #[serde(skip)]
+    pub dry_run: bool,
+
+    #[command(flatten)]
+    #[serde(skip)]
+    pub plan: PlanArgs,
 
     /// 是否打印详细结果到终端
-    #[arg(short = 'e', long)]
+    #[arg(short = 'e', long, env = "GXTOOLS_ECHO")]
     pub echo: bool,
 
+    /// 详细结果中同时列出未存活的主机（默认只列出存活及收到差错报文的主机），用于留存覆盖范围
+    #[arg(long, env = "GXTOOLS_SHOW_FAILED", requires = "echo")]
+    #[serde(default)]
+    pub show_failed: bool,
+
+    /// Ping完成后反向解析（PTR）存活主机的主机名，结果增加主机名列
+    #[arg(long, env = "GXTOOLS_RESOLVE")]
+    #[serde(default)]
+    pub resolve: bool,
+
+    /// 反向解析的超时时间（秒）
+    #[arg(long, value_name = "SECS", default_value_t = 2, requires = "resolve")]
+    #[serde(default = "default_resolve_timeout")]
+    pub resolve_timeout: u64,
+
     /// 是否输出结果到Excel文件
-    #[arg(short = 'o', long)]
+    #[arg(short = 'o', long, env = "GXTOOLS_OUTPUT")]
     pub output: bool,
+
+    /// Excel中存活与未存活的主机分别写入两个工作表
+    #[arg(long, env = "GXTOOLS_SPLIT_SHEETS", requires = "output")]
+    #[serde(default)]
+    pub split_sheets: bool,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub snapshot: SnapshotArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub verify: VerifyArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub assets: AssetArgs,
+
+    #[command(flatten)]
+    #[serde(skip)]
+    pub profile_args: ProfileOptions,
 }
 
+/// Ping扫描在 normal 时序模板下的默认参数
+pub const TIMING_DEFAULTS: TimingDefaults = TimingDefaults {
+    concurrency: 100,
+    timeout_secs: 2,
+    retries: 2,
+};
+
 /// Ping扫描结果
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PingResult {
     /// IP地址
     pub ip: String,
-    /// 状态（成功/失败）
+    /// 状态（成功/失败/超时）
     pub status: String,
     /// 响应时间（毫秒，可选）
     pub response_time: Option<f64>,
+    /// 回复报文的TTL（可选）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u8>,
+    /// 有响应的TCP端口（TCP探测判定存活时）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tcp_port: Option<u16>,
+    /// 失败原因（ICMP差错类型或超时，无法执行ping时为空）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<FailureReason>,
+    /// 指向该IP的其他目标写法（如主机名、重叠的网段）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// 目标标签（来自 --tag-columns 及 --tag）
+    #[serde(skip_serializing_if = "Tags::is_empty")]
+    pub tags: Tags,
+    /// 地理位置及ASN（开启 --enrich-geo 时查询存活主机）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geo: Option<GeoInfo>,
+    /// 复核标注（开启 --verify 时）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+    /// 与资产库相比的变化（开启资产库时）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub asset_changes: Vec<String>,
+    /// 反向解析得到的主机名（开启 --resolve 时查询存活主机，没有PTR记录时为 -）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// 丢包及时延统计（开启 --stats 时）
+    #[serde(flatten)]
+    pub quality: Option<LinkQuality>,
+    /// 观测时间及探测方式
+    #[serde(flatten)]
+    pub observed: Provenance,
+    /// 得出结论用的尝试次数（成功前失败过的视为结果反复）
+    #[serde(skip)]
+    pub attempts: u32,
+}
+
+/// 统计模式下单个IP的丢包及时延
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinkQuality {
+    /// 发送的探测数
+    pub packets_sent: u32,
+    /// 收到回复的探测数
+    pub packets_received: u32,
+    /// 丢包率（百分比）
+    pub loss_pct: f64,
+    /// 最小响应时间（毫秒，没有时延数据时为空，下同）
+    pub rtt_min: Option<f64>,
+    /// 平均响应时间
+    pub rtt_avg: Option<f64>,
+    /// 最大响应时间
+    pub rtt_max: Option<f64>,
+    /// 响应时间的标准差
+    pub rtt_stddev: Option<f64>,
+}
+
+impl LinkQuality {
+    /// Excel中统计模式的列
+    pub const HEADERS: [&'static str; 7] = [
+        "发送",
+        "接收",
+        "丢包率(%)",
+        "最小(ms)",
+        "平均(ms)",
+        "最大(ms)",
+        "标准差(ms)",
+    ];
+
+    /// 根据各次探测汇总
+    ///
+    /// # 参数
+    /// * `sent` - 发送的探测数
+    /// * `received` - 收到回复的探测数
+    /// * `rtts` - 收到回复的响应时间（TCP探测等没有时延的回复不计入）
+    pub fn from_samples(sent: u32, received: u32, rtts: &[f64]) -> Self {
+        let loss_pct = if sent == 0 {
+            0.0
+        } else {
+            (sent - received) as f64 / sent as f64 * 100.0
+        };
+        let (rtt_min, rtt_avg, rtt_max, rtt_stddev) = if rtts.is_empty() {
+            (None, None, None, None)
+        } else {
+            let n = rtts.len() as f64;
+            let avg = rtts.iter().sum::<f64>() / n;
+            let variance = rtts.iter().map(|t| (t - avg).powi(2)).sum::<f64>() / n;
+            (
+                rtts.iter().copied().reduce(f64::min),
+                Some(avg),
+                rtts.iter().copied().reduce(f64::max),
+                Some(variance.sqrt()),
+            )
+        };
+        Self {
+            packets_sent: sent,
+            packets_received: received,
+            loss_pct,
+            rtt_min,
+            rtt_avg,
+            rtt_max,
+            rtt_stddev,
+        }
+    }
+
+    /// 终端显示的摘要，如 `loss=0% avg=1.2ms`
+    pub fn summary(&self) -> String {
+        match self.rtt_avg {
+            Some(avg) => format!("loss={:.0}% avg={:.1}ms", self.loss_pct, avg),
+            None => format!("loss={:.0}%", self.loss_pct),
+        }
+    }
+
+    /// Excel中统计模式各列的值（没有统计时为空）
+    pub fn cells(quality: Option<&Self>) -> [String; 7] {
+        let ms = |t: Option<f64>| {
+            t.map(|t| format!("{:.2}", t))
+                .unwrap_or_else(|| "-".to_string())
+        };
+        match quality {
+            Some(q) => [
+                q.packets_sent.to_string(),
+                q.packets_received.to_string(),
+                format!("{:.1}", q.loss_pct),
+                ms(q.rtt_min),
+                ms(q.rtt_avg),
+                ms(q.rtt_max),
+                ms(q.rtt_stddev),
+            ],
+            None => Default::default(),
+        }
+    }
+}
+
+/// Ping结果的探测方式
+const PROBE: &str = "icmp";
+
+/// TCP探测的探测方式（判定存活时后接有响应的端口）
+const TCP_PROBE: &str = "tcp-connect";
+
+/// TCP探测默认尝试的端口
+const DEFAULT_TCP_PORTS: &str = "80,443,22,445";
+
+fn default_tcp_ports() -> String {
+    DEFAULT_TCP_PORTS.to_string()
+}
+
+/// 反向解析的并发数
+const RESOLVE_CONCURRENCY: usize = 32;
+
+fn default_resolve_timeout() -> u64 {
+    2
+}
+
+/// Ping失败的原因
+///
+/// 目标不可达时路由器或防火墙会返回ICMP差错报文，
+/// 据此可以区分被防火墙拦截和主机确实不存在。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FailureReason {
+    /// 未在超时时间内收到任何回复
+    NoReply,
+    /// 网络不可达（ICMP类型3代码0）
+    NetUnreachable,
+    /// 主机不可达（ICMP类型3代码1）
+    HostUnreachable,
+    /// 端口/协议不可达（ICMP类型3代码2、3）
+    PortUnreachable,
+    /// 被管理策略禁止（ICMP类型3代码9、10、13，通常是防火墙）
+    AdminProhibited,
+    /// 传输中TTL过期（ICMP类型11）
+    TtlExpired,
+}
+
+impl std::fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            FailureReason::NoReply => "超时",
+            FailureReason::NetUnreachable => "网络不可达",
+            FailureReason::HostUnreachable => "主机不可达",
+            FailureReason::PortUnreachable => "端口不可达",
+            FailureReason::AdminProhibited => "管理禁止",
+            FailureReason::TtlExpired => "TTL过期",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Serialize for FailureReason {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// ping输出中的ICMP差错关键词（小写）-> 失败原因，按从具体到宽泛排列
+const UNREACHABLE_KEYWORDS: &[(&str, FailureReason)] = &[
+    // Linux: "Packet filtered"、"Destination Host Prohibited"；BSD/macOS: "administratively prohibited"
+    ("packet filtered", FailureReason::AdminProhibited),
+    ("prohibited", FailureReason::AdminProhibited),
+    ("管理性禁止", FailureReason::AdminProhibited),
+    ("管理禁止", FailureReason::AdminProhibited),
+    ("port unreachable", FailureReason::PortUnreachable),
+    ("protocol unreachable", FailureReason::PortUnreachable),
+    ("无法连到端口", FailureReason::PortUnreachable),
+    ("无法访问目标端口", FailureReason::PortUnreachable),
+    ("net unreachable", FailureReason::NetUnreachable),
+    ("无法访问目标网", FailureReason::NetUnreachable),
+    ("host unreachable", FailureReason::HostUnreachable),
+    ("无法访问目标主机", FailureReason::HostUnreachable),
+    ("time to live exceeded", FailureReason::TtlExpired),
+    ("ttl expired", FailureReason::TtlExpired),
+    ("传输中过期", FailureReason::TtlExpired),
+];
+
+impl ScriptRow for PingResult {
+    fn asset(&self) -> &str {
+        &self.ip
+    }
+
+    fn observed(&self) -> Option<&Provenance> {
+        Some(&self.observed)
+    }
+
+    fn fields_mut(&mut self) -> &mut Tags {
+        &mut self.tags
+    }
 }
 
 impl PingResult {
     /// 创建成功的ping结果
-    fn success(ip: String, response_time: Option<f64>) -> Self {
+    pub fn success(ip: String, response_time: Option<f64>, ttl: Option<u8>) -> Self {
         Self {
             ip,
             status: "成功".to_string(),
             response_time,
+            ttl,
+            tcp_port: None,
+            failure_reason: None,
+            aliases: Vec::new(),
+            tags: Tags::new(),
+            geo: None,
+            verification: None,
+            asset_changes: Vec::new(),
+            hostname: None,
+            quality: None,
+            observed: Provenance::now(PROBE),
+            attempts: 1,
         }
     }
 
     /// 创建失败的ping结果
-    fn failure(ip: String) -> Self {
+    pub fn failure(ip: String, reason: Option<FailureReason>) -> Self {
         Self {
             ip,
             status: "失败".to_string(),
             response_time: None,
+            ttl: None,
+            tcp_port: None,
+            failure_reason: reason,
+            aliases: Vec::new(),
+            tags: Tags::new(),
+            geo: None,
+            verification: None,
+            asset_changes: Vec::new(),
+            hostname: None,
+            quality: None,
+            observed: Provenance::now(PROBE),
+            attempts: 1,
+        }
+    }
+
+    /// 创建超时的ping结果（ping进程超过硬性时限被终止）
+    fn timeout(ip: String) -> Self {
+        Self {
+            ip,
+            status: "超时".to_string(),
+            response_time: None,
+            ttl: None,
+            tcp_port: None,
+            failure_reason: Some(FailureReason::NoReply),
+            aliases: Vec::new(),
+            tags: Tags::new(),
+            geo: None,
+            verification: None,
+            asset_changes: Vec::new(),
+            hostname: None,
+            quality: None,
+            observed: Provenance::now(PROBE),
+            attempts: 1,
         }
     }
 
@@ -75,6 +531,17 @@ impl PingResult {
     pub fn is_success(&self) -> bool {
         self.status == "成功"
     }
+
+    /// 计入扫描统计的结论：存活为成功，未回复或收到差错为失败，无法执行ping为出错
+    pub fn outcome(&self) -> Outcome {
+        if self.is_success() {
+            Outcome::Succeeded
+        } else if self.failure_reason.is_some() {
+            Outcome::Failed
+        } else {
+            Outcome::Errored
+        }
+    }
 }
 
 /// 执行Ping扫描
@@ -83,264 +550,1649 @@ impl PingResult {
 /// * `args` - Ping扫描参数
 ///
 /// # 返回
-/// * `Ok(())` - 扫描成功完成
+/// * `Ok(RunSummary)` - 扫描成功完成，返回结果摘要
 /// * `Err` - 扫描过程中发生错误
-pub async fn run(args: &PingArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+pub async fn run(args: &PingArgs) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    run_with(args, &ScanContext::cli()).await
+}
+
+/// 在指定上下文中执行Ping扫描（供守护进程等非命令行调用方使用）
+///
+/// # 参数
+/// * `args` - Ping扫描参数
+/// * `ctx` - 扫描上下文（进度、暂停、取消及结果推送）
+///
+/// # 返回
+/// 同 [`run`]
+pub async fn run_with(
+    args: &PingArgs,
+    ctx: &ScanContext,
+) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
     let start = Instant::now();
 
-    // 解析目标IP列表
-    let ip_list = parse_targets(&args.target)?;
-    let total_ips = ip_list.len();
+    // 开启抽样时只扫描样本，目标空间不展开
+    let sample = Sample::draw(args.target.as_deref(), &args.sample)?;
+    let sample_spec = sample.as_ref().map(Sample::spec);
+    // 解析目标，同一IP只探测一次
+    let target = sample_spec.as_deref().or(args.target.as_deref());
+    let mut targets = collect_targets(target, &args.sources).await?;
+    args.sample.apply_limit(&mut targets);
+    // 数据库在扫描前打开，缺失时尽早提示
+    let geo = GeoLookup::open(&args.geo);
+    let total_ips = targets.len();
 
     if total_ips == 0 {
         return Err("未解析到任何有效的IP地址".into());
     }
 
-    println!("🔍 开始Ping扫描，共 {} 个目标IP", total_ips);
+    // -n 是总次数，换算为重试次数
+    let timing = Timing::resolve(
+        &args.timing,
+        TIMING_DEFAULTS,
+        args.concurrency,
+        args.timeout,
+        args.count.map(|n| n.max(1) - 1),
+    );
+    let concurrency = effective_concurrency(timing.concurrency, total_ips, ScanKind::Icmp);
+    let tape = Tape::open(&args.transcript, "net ping")?;
+    let adaptive =
+        AdaptiveController::for_spec(timing.concurrency, concurrency.value, tape.is_replay());
+    let ctx = &ctx
+        .clone()
+        .with_throttle(Throttle::for_targets(&timing, &targets))
+        .with_adaptive(adaptive)
+        .with_target_overrides(&targets)
+        .with_tape(tape);
+    // 探测器在扫描前选定，指定原生ICMP但没有权限时尽早报错
+    let tcp = TcpPinger {
+        ports: parse_ports_strict(&args.tcp_ports)?,
+    };
+    if args.method != PingMethod::Icmp && !matches!(ctx.tape(), Tape::Live) {
+        return Err("录制及回放只支持ICMP探测，请改用 --method icmp".into());
+    }
+    let pinger = match args.method {
+        PingMethod::Tcp => EnginePinger::Tcp(tcp.clone()),
+        PingMethod::Icmp | PingMethod::Auto => EnginePinger::select(args.engine, ctx)?,
+    };
+    // 没有ping程序时每个目标都会失败，在发出探测前报错（回放及试运行时不执行ping）
+    if matches!(pinger, EnginePinger::System(_)) && !ctx.tape().is_replay() && !args.dry_run {
+        check_ping_program(PING_PROGRAM).await.require()?;
+    }
+    // 脚本在扫描前编译，出错时尽早报错；http_get 只能访问本次的目标，回放时不可用
+    let fetch = match ctx.tape() {
+        Tape::Replay(_) => None,
+        _ if args.script.script.is_none() => None,
+        _ => Some(ScriptFetch::new(ctx, &targets)?),
+    };
+    let script = ScriptHook::load(&args.script, "net ping", fetch)?;
+    // 资产库在扫描前打开，结构版本不兼容时尽早报错；回放的是旧结果，不更新资产库
+    let asset_db = match ctx.tape() {
+        Tape::Replay(_) => None,
+        _ => AssetDb::open_for_run(&args.assets)?,
+    };
+    // 复核基线在扫描前读取，运行目录无效时尽早报错
+    let baseline = match args.verify.verify_baseline {
+        Some(ref run) if args.verify.wants(VerifyKind::Baseline) => baseline_alive(run)?,
+        _ => HashSet::new(),
+    };
+
+    println!("{} 开始Ping扫描，共 {} 个目标IP", Icon::Scan, total_ips);
     println!(
-        "⚙️  配置: 超时={}秒, 重试={}次, 并发={}",
-        args.timeout, args.count, args.concurrency
+        "{} 配置: 引擎={}, 超时={}秒, 次数={}次, 并发={}（{}）",
+        Icon::Config,
+        pinger.name(),
+        timing.timeout_secs,
+        timing.retries + 1,
+        concurrency.value,
+        concurrency.reason
     );
+    match args.method {
+        PingMethod::Icmp => {}
+        PingMethod::Tcp => println!("{} TCP端口: {}", Icon::Config, args.tcp_ports),
+        PingMethod::Auto => println!(
+            "{} TCP端口: {}（ICMP无回复时尝试）",
+            Icon::Config,
+            args.tcp_ports
+        ),
+    }
+    if args.stats {
+        println!(
+            "{} 统计模式: 每个IP发送全部 {} 次探测，统计丢包及时延",
+            Icon::Config,
+            timing.retries + 1
+        );
+    }
+    println!("{} 时序: {}（{}）", Icon::Config, timing.template, timing);
+    let overrides = targets.override_specs();
+    print_overrides(&overrides);
+    // 签批的扫描计划：试运行时导出，执行前核对本次展开的范围
+    let approved = args.plan.check(&ScanPlan::new(
+        "net ping",
+        &targets,
+        None,
+        timing.retries + 1,
+        args,
+    ))?;
+    if args.dry_run {
+        println!("{} 试运行，未发送任何探测", Icon::Ok);
+        return Ok(RunSummary {
+            total: 0,
+            succeeded: 0,
+            outputs: Vec::new(),
+            concurrency: Some(concurrency),
+            timing: Some(timing),
+        });
+    }
+
+    // 创建进度条，扫描期间可按 p 或发送 SIGUSR1 暂停
+    let progress = ctx.new_progress(total_ips as u64);
+    let listener = ctx.interactive.then(|| ctx.pause.listen(&progress, true));
 
-    // 创建进度条
-    let progress = ScanProgress::new(total_ips as u64);
+    // 结果边扫描边累积，开启 --snapshot-every 时定期写中间结果
+    let collector = ResultCollector::new();
+    let snapshotter = Snapshotter::start(
+        &args.snapshot,
+        &collector,
+        ctx.run_dir().cloned(),
+        &progress,
+        snapshot_writer(args.output, args.split_sheets, ctx),
+    );
 
     // 执行并发ping扫描
-    let results = ping_concurrent_async(
-        ip_list,
-        args.timeout,
-        args.count,
-        args.concurrency,
+    let opts = PingOptions {
+        timeout_secs: timing.timeout_secs,
+        count: timing.retries + 1,
+        stats: args.stats,
+    };
+    // 目标按需取出，不再另外复制一份IP列表；打乱顺序时才需要完整列表
+    let shuffler = args.order.shuffler();
+    let ips: Box<dyn Iterator<Item = String> + Send + '_> = match shuffler {
+        Some(mut shuffler) => {
+            let mut ips = targets.ips();
+            shuffler.shuffle(&mut ips);
+            Box::new(ips.into_iter())
+        }
+        None => Box::new(targets.iter_ips()),
+    };
+    ping_concurrent_with(
+        &pinger,
+        ips,
+        opts,
+        concurrency.value,
         &progress,
+        ctx,
+        &collector,
     )
     .await?;
+    drop(listener);
+    // 结果按完成顺序到达，输出前按IP排序
+    let mut results = collector.into_vec();
+    results.sort_by_cached_key(|r| ip_sort_key(&r.ip));
+    if ctx.is_cancelled() {
+        progress.println(format!("{} 扫描已取消，以下为部分结果", Icon::Warn));
+    }
+    progress.finish_with_message(format!("{} Ping扫描完成", Icon::Ok));
+
+    // ICMP无回复的主机再尝试TCP，作为单独的阶段显示进度
+    let tcp_found = if args.method == PingMethod::Auto && !ctx.is_cancelled() {
+        Some(
+            tcp_fallback(
+                &tcp,
+                &mut results,
+                opts,
+                concurrency.value,
+                ctx,
+                progress.stats(),
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    // 复核可疑结果，作为第二个阶段显示进度
+    let verified = if args.verify.verify && !ctx.is_cancelled() {
+        let candidates = verify_candidates(&results, &args.verify, &baseline);
+        let opts = PingOptions {
+            timeout_secs: args
+                .verify
+                .timeout(Duration::from_secs(timing.timeout_secs))
+                .as_secs(),
+            count: args.verify.attempts(),
+            stats: false,
+        };
+        let verified = verify_results(
+            &pinger,
+            &mut results,
+            candidates,
+            opts,
+            concurrency.value,
+            ctx,
+            progress.stats(),
+        )
+        .await?;
+        Some(verified)
+    } else {
+        None
+    };
+    ctx.tape().finish()?;
+
+    // 反向解析在Ping阶段之后单独进行，不拖慢探测；回放时不发送查询
+    if args.resolve && !ctx.is_cancelled() && !ctx.tape().is_replay() {
+        resolve_hostnames(
+            dns::resolver(),
+            &mut results,
+            Duration::from_secs(args.resolve_timeout.max(1)),
+            ctx,
+        )
+        .await;
+    }
+
+    for result in &mut results {
+        result.aliases = targets.aliases(&result.ip);
+        result.tags = targets.tags(&result.ip);
+        if let Some(ref geo) = geo
+            && result.is_success()
+        {
+            result.geo = geo.lookup(&result.ip);
+        }
+    }
+
+    // 脚本处理每个主机的结果，返回的字段作为附加列导出
+    let mut derived = Vec::new();
+    let script = match script {
+        Some(hook) => {
+            let stage = ScriptStage::new(hook, HookPoint::HostResult, results, Vec::new());
+            let (hook, rows, findings) = run_stage(stage).await?;
+            results = rows;
+            derived = consolidate(findings);
+            Some(hook)
+        }
+        None => None,
+    };
+
+    // 与资产库比对并记录本次结果
+    let observations = results
+        .iter()
+        .map(|r| Observation {
+            ip: r.ip.clone(),
+            responding: r.is_success(),
+            aliases: r.aliases.clone(),
+            tags: r.tags.clone(),
+            ..Default::default()
+        })
+        .collect();
+    let asset_changes = assets::update(asset_db, observations, None, ctx.is_cancelled()).await?;
+    if let Some(ref changes) = asset_changes {
+        for result in &mut results {
+            result.asset_changes = change_notes(changes, &result.ip);
+        }
+    }
 
-    // 统计结果
-    let success_count = results.iter().filter(|r| r.is_success()).count();
-    let failure_count = total_ips - success_count;
+    // 同一主机名的IPv4、IPv6地址并列对照
+    let alive: HashSet<&str> = results
+        .iter()
+        .filter(|r| r.is_success())
+        .map(|r| r.ip.as_str())
+        .collect();
+    let dual_stack: Vec<DualStackHost> = dualstack::correlate(&targets, |ip| alive.contains(ip));
+
+    // 统计结果（取消时只统计已完成的部分），计数以工作池的统计为准，失败原因按结果行分类
+    let counts = progress.snapshot();
+    let stats = PingStats::from_results(&results);
+    debug_assert_eq!(counts.completed as usize, results.len());
+    debug_assert_eq!(counts.succeeded as usize, stats.alive);
 
     // 打印详细结果
     if args.echo {
-        progress.println("📋 扫描结果：".to_string());
+        progress.println(format!("{} 扫描结果：", Icon::List));
         for result in &results {
             if result.is_success() {
-                let time_info = result
-                    .response_time
-                    .map(|t| format!(" ({}ms)", t))
+                let time_info = match (&result.quality, result.response_time) {
+                    (Some(quality), _) => format!(" {}", quality.summary()),
+                    (None, Some(t)) => format!(" ({}ms)", t),
+                    (None, None) => String::new(),
+                };
+                let port_info = result
+                    .tcp_port
+                    .map(|port| format!("，TCP {} 有响应", port))
+                    .unwrap_or_default();
+                let name_info = result
+                    .hostname
+                    .as_ref()
+                    .map(|name| format!("，主机名 {}", name))
                     .unwrap_or_default();
-                progress.println(format!("  ✅ {} => 存活{}", result.ip, time_info));
+                progress.println(format!(
+                    "  {} {}{} => 存活{}{}{}",
+                    Icon::Ok,
+                    result.ip,
+                    alias_suffix(&result.aliases),
+                    time_info,
+                    port_info,
+                    name_info
+                ));
+            } else if let Some(reason) = result
+                .failure_reason
+                .filter(|r| args.show_failed || *r != FailureReason::NoReply)
+            {
+                // 默认只列出收到差错报文的目标，未回复的数量见统计
+                let loss_info = result
+                    .quality
+                    .as_ref()
+                    .map(|quality| format!(" {}", quality.summary()))
+                    .unwrap_or_default();
+                progress.println(format!(
+                    "  {} {}{} => {}{}",
+                    Icon::Fail,
+                    result.ip,
+                    alias_suffix(&result.aliases),
+                    reason,
+                    loss_info
+                ));
+            } else if args.show_failed {
+                progress.println(format!(
+                    "  {} {}{} => {}",
+                    Icon::Fail,
+                    result.ip,
+                    alias_suffix(&result.aliases),
+                    result.status
+                ));
             }
         }
     }
 
-    progress.finish_with_message("✅ Ping扫描完成");
+    // 抽样时按样本外推存活主机数
+    let estimates = sample
+        .as_ref()
+        .map(|sample| {
+            vec![(
+                "存活",
+                sample.estimate(counts.succeeded as usize, counts.completed as usize),
+            )]
+        })
+        .unwrap_or_default();
 
     // 保存到Excel
+    let mut outputs = Vec::new();
     if args.output {
-        save_to_excel(
-            &results,
-            &["IP地址", "状态", "响应时间(ms)"],
-            |item| {
-                vec![
-                    item.ip.clone(),
-                    item.status.clone(),
-                    item.response_time
-                        .map(|t| format!("{:.2}", t))
-                        .unwrap_or_else(|| "-".to_string()),
-                ]
-            },
-            "ping",
-            "ping",
-        )?;
+        let mut options = ctx.excel_options();
+        if let Some(ref sample) = sample {
+            options.extra_sheets.push(sample.sheet(&estimates));
+        }
+        if !dual_stack.is_empty() {
+            options.extra_sheets.push(dual_stack_sheet(&dual_stack));
+        }
+        outputs.push(run_stage(export_results(&results, options, args.split_sheets)).await?);
+    }
+    if let Some(snapshotter) = snapshotter {
+        let kept = snapshotter.finish(args.snapshot.keep_snapshots).await;
+        if kept > 0 {
+            println!("{} 已保留 {} 份中间结果", Icon::Ok, kept);
+        }
     }
 
     // 打印总结
     let elapsed = start.elapsed();
-    println!("\n📊 扫描统计:");
-    println!("   总计: {} 个IP", total_ips);
-    println!(
-        "   存活: {} 个 ({:.1}%)",
-        success_count,
-        (success_count as f64 / total_ips as f64) * 100.0
-    );
-    println!(
-        "   失败: {} 个 ({:.1}%)",
-        failure_count,
-        (failure_count as f64 / total_ips as f64) * 100.0
-    );
-    println!("   耗时: {:.2?}", elapsed);
+    let mut summary: Vec<SummaryItem> = vec![
+        ("总计".to_string(), format!("{} 个IP", counts.completed)),
+        (
+            "存活".to_string(),
+            format!(
+                "{} 个 ({:.1}%)",
+                counts.succeeded,
+                counts.percent(counts.succeeded)
+            ),
+        ),
+        (
+            "失败".to_string(),
+            format!(
+                "{} 个 ({:.1}%)",
+                counts.failed,
+                counts.percent(counts.failed)
+            ),
+        ),
+        (
+            "耗时".to_string(),
+            format_elapsed(elapsed, ctx.pause.paused_duration()),
+        ),
+    ];
+    if counts.errored > 0 {
+        summary.insert(
+            3,
+            (
+                "无法执行ping".to_string(),
+                format!(
+                    "{} 个 ({:.1}%)",
+                    counts.errored,
+                    counts.percent(counts.errored)
+                ),
+            ),
+        );
+    }
+    if counts.cancelled > 0 {
+        summary.push((
+            "未完成".to_string(),
+            format!("{} 个IP（探测中被取消）", counts.cancelled),
+        ));
+    }
+    if let Some((probed, found)) = tcp_found {
+        summary.push((
+            "TCP探测".to_string(),
+            format!("{} 个，判定存活 {} 个", probed, found),
+        ));
+    }
+    if let Some((checked, changed)) = verified {
+        summary.push((
+            "复核".to_string(),
+            format!("{} 个，结论改变 {} 个", checked, changed),
+        ));
+    }
+    if let Some(ref sample) = sample {
+        summary.extend(sample.summary_items(&estimates));
+    }
+    summary.extend(dualstack::summary_items(&dual_stack));
+    if let Some(ref changes) = asset_changes {
+        summary.push(ChangeCounts::count(changes).summary_item());
+    }
+    if let Some(ref script) = script {
+        summary.push(script.summary_item());
+    }
+    summary.extend(timing.summary_items());
+    summary.extend(ctx.adaptive().map(|a| a.summary_item()));
+    summary.extend(overrides_summary_item(&overrides));
+    summary.extend(approved);
+    // 失败按原因分别计数，插在"失败"之后
+    for (i, (reason, count)) in stats.reasons.iter().enumerate() {
+        summary.insert(
+            3 + i,
+            (
+                format!("  {}", reason),
+                format!("{} 个 ({:.1}%)", count, stats.percent(*count)),
+            ),
+        );
+    }
+    println!("\n{} 扫描统计:", Icon::Stats);
+    for (name, value) in &summary {
+        println!("   {}: {}", name, value);
+    }
+    print_derived(&derived);
+
+    // 结果及统计写入运行目录，供 `report view` 查看
+    if let Some(run_dir) = ctx.run_dir() {
+        run_dir.write_json(HOSTS_FILE_NAME, "json", &results, results.len())?;
+        if !dual_stack.is_empty() {
+            run_dir.write_json(DUAL_STACK_FILE_NAME, "json", &dual_stack, dual_stack.len())?;
+        }
+        if !derived.is_empty() {
+            let first_seen = chrono::Local::now().to_rfc3339();
+            let rows: Vec<_> = derived.iter().map(|f| f.to_vm(&first_seen)).collect();
+            run_dir.write_json(FINDINGS_FILE_NAME, "json", &rows, rows.len())?;
+        }
+        if !overrides.is_empty() {
+            run_dir.write_json(
+                TARGET_OVERRIDES_FILE_NAME,
+                "json",
+                &overrides,
+                overrides.len(),
+            )?;
+        }
+        if let Some(adaptive) = ctx.adaptive() {
+            let series = adaptive.series();
+            run_dir.write_json(CONCURRENCY_FILE_NAME, "json", &series, series.len())?;
+        }
+        run_dir.write_summary(&summary)?;
+    }
+
+    Ok(RunSummary {
+        total: counts.completed as usize,
+        succeeded: counts.succeeded as usize,
+        outputs,
+        concurrency: Some(concurrency),
+        timing: Some(timing),
+    })
+}
+
+/// 读取基线运行中存活的主机
+///
+/// # 参数
+/// * `run` - 基线运行目录（目录名或其前缀）
+fn baseline_alive(run: &str) -> Result<HashSet<String>, Box<dyn Error + Send + Sync>> {
+    let rows = load_run_rows(run, HOSTS_FILE_NAME)?;
+    let observed = rows.iter().filter_map(|r| r["observed_at"].as_str());
+    if let Some(staleness) = describe_staleness(observed, chrono::Local::now()) {
+        println!("{} 复核基线 {}: {}", Icon::Config, run, staleness);
+    }
+    Ok(rows
+        .iter()
+        .filter(|r| r["status"] == "成功")
+        .filter_map(|r| r["ip"].as_str().map(str::to_string))
+        .collect())
+}
+
+/// 挑选需要复核的主机
+///
+/// # 参数
+/// * `results` - 主扫描结果
+/// * `verify` - 复核参数
+/// * `baseline` - 基线中存活的主机
+///
+/// # 返回
+/// * `Vec<String>` - 基线中存活、本次失败的主机，以及重试后才成功的主机
+pub fn verify_candidates(
+    results: &[PingResult],
+    verify: &VerifyArgs,
+    baseline: &HashSet<String>,
+) -> Vec<String> {
+    results
+        .iter()
+        .filter(|r| {
+            (verify.wants(VerifyKind::Baseline) && !r.is_success() && baseline.contains(&r.ip))
+                || (verify.wants(VerifyKind::Flapped) && r.is_success() && r.attempts > 1)
+        })
+        .map(|r| r.ip.clone())
+        .collect()
+}
+
+/// 用复核参数重新探测部分主机，复核结果替换主扫描结果，其余结果标注为未复核
+///
+/// 复核使用与主扫描相同的探测流程，单独显示一个进度条，结果不再推送给上下文的接收方。
+///
+/// # 参数
+/// * `pinger` - 探测器
+/// * `results` - 主扫描结果
+/// * `candidates` - 需要复核的主机
+/// * `opts` - 复核的超时及尝试次数
+/// * `concurrency` - 最大并发数
+/// * `ctx` - 扫描上下文
+/// * `stats` - 主扫描的统计（复核替换的结果按新结论重新计数）
+///
+/// # 返回
+/// * `(复核数, 结论改变数)`
+pub async fn verify_results<P: Pinger>(
+    pinger: &P,
+    results: &mut [PingResult],
+    candidates: Vec<String>,
+    opts: PingOptions,
+    concurrency: usize,
+    ctx: &ScanContext,
+    stats: &ScanStats,
+) -> Result<(usize, usize), Box<dyn Error + Send + Sync>> {
+    for result in results.iter_mut() {
+        result.verification = Some(Verification::UNVERIFIED);
+    }
+    if candidates.is_empty() {
+        return Ok((0, 0));
+    }
+
+    let verify_ctx = ctx.without_results();
+    let progress = verify_ctx.new_progress(candidates.len() as u64);
+    progress.set_message("复核");
+    let collector = ResultCollector::new();
+    ping_concurrent_with(
+        pinger,
+        candidates,
+        opts,
+        concurrency,
+        &progress,
+        &verify_ctx,
+        &collector,
+    )
+    .await?;
+    progress.finish_with_message(format!("{} 复核完成", Icon::Ok));
+
+    let mut checked = 0;
+    let mut changed = 0;
+    for mut verified in collector.into_vec() {
+        let Some(result) = results.iter_mut().find(|r| r.ip == verified.ip) else {
+            continue;
+        };
+        let flipped = verified.is_success() != result.is_success();
+        stats.reclassify(result.outcome(), verified.outcome());
+        verified.verification = Some(Verification::verified(flipped));
+        *result = verified;
+        checked += 1;
+        changed += usize::from(flipped);
+    }
+    Ok((checked, changed))
+}
+
+/// 对ICMP无回复的主机再做TCP探测（`--method auto`），有响应的替换主扫描结果
+///
+/// 单独显示一个进度条，结果不再推送给上下文的接收方。
+/// TCP仍无响应的主机保留ICMP的结果（收到的差错类型比TCP超时更有参考价值）。
+///
+/// # 参数
+/// * `pinger` - TCP探测器
+/// * `results` - 主扫描结果
+/// * `opts` - 超时及尝试次数
+/// * `concurrency` - 最大并发数
+/// * `ctx` - 扫描上下文
+/// * `stats` - 主扫描的统计（改判存活的按新结论重新计数）
+///
+/// # 返回
+/// * `(TCP探测数, 改判存活数)`
+pub async fn tcp_fallback(
+    pinger: &TcpPinger,
+    results: &mut [PingResult],
+    opts: PingOptions,
+    concurrency: usize,
+    ctx: &ScanContext,
+    stats: &ScanStats,
+) -> Result<(usize, usize), Box<dyn Error + Send + Sync>> {
+    let candidates: Vec<String> = results
+        .iter()
+        .filter(|r| !r.is_success())
+        .map(|r| r.ip.clone())
+        .collect();
+    if candidates.is_empty() {
+        return Ok((0, 0));
+    }
+
+    let tcp_ctx = ctx.without_results();
+    let progress = tcp_ctx.new_progress(candidates.len() as u64);
+    progress.set_message("TCP探测");
+    let collector = ResultCollector::new();
+    ping_concurrent_with(
+        pinger,
+        candidates,
+        opts,
+        concurrency,
+        &progress,
+        &tcp_ctx,
+        &collector,
+    )
+    .await?;
+    progress.finish_with_message(format!("{} TCP探测完成", Icon::Ok));
+
+    let answered = collector.into_vec();
+    let probed = answered.len();
+    let mut found = 0;
+    for alive in answered.into_iter().filter(PingResult::is_success) {
+        let Some(result) = results.iter_mut().find(|r| r.ip == alive.ip) else {
+            continue;
+        };
+        stats.reclassify(result.outcome(), alive.outcome());
+        *result = alive;
+        found += 1;
+    }
+    Ok((probed, found))
+}
+
+/// Ping扫描统计
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PingStats {
+    /// 已完成的IP数
+    pub total: usize,
+    /// 存活数
+    pub alive: usize,
+    /// 失败数（含超时）
+    pub failed: usize,
+    /// 按原因统计的失败数（无法执行ping的不计入）
+    pub reasons: BTreeMap<FailureReason, usize>,
+}
+
+impl PingStats {
+    /// 统计已完成的结果
+    pub fn from_results(results: &[PingResult]) -> Self {
+        let alive = results.iter().filter(|r| r.is_success()).count();
+        let mut reasons = BTreeMap::new();
+        for reason in results.iter().filter_map(|r| r.failure_reason) {
+            *reasons.entry(reason).or_default() += 1;
+        }
+        Self {
+            total: results.len(),
+            alive,
+            failed: results.len() - alive,
+            reasons,
+        }
+    }
+
+    /// 数量占总数的百分比（没有结果时为0）
+    pub fn percent(&self, count: usize) -> f64 {
+        (count as f64 / self.total.max(1) as f64) * 100.0
+    }
+}
+
+/// 单次ping尝试的参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PingOptions {
+    /// 超时时间（秒）
+    pub timeout_secs: u64,
+    /// 每个IP的最多尝试次数
+    pub count: u32,
+    /// 是否发满全部次数并统计丢包及时延（--stats）
+    pub stats: bool,
+}
+
+impl PingOptions {
+    /// 应用目标行上的参数覆盖（系统ping的超时只支持整秒，向上取整）
+    pub fn for_target(self, overrides: &TargetOverrides) -> Self {
+        Self {
+            timeout_secs: overrides
+                .timeout_ms
+                .map_or(self.timeout_secs, |ms| ms.div_ceil(1000)),
+            count: overrides.count.unwrap_or(self.count),
+            stats: self.stats,
+        }
+    }
+}
+
+/// 单次ping尝试的结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProbeOutcome {
+    /// 收到回复
+    Reply {
+        /// 响应时间（毫秒）
+        response_time: Option<f64>,
+        /// 回复报文的TTL
+        ttl: Option<u8>,
+        /// 有响应的TCP端口（TCP探测时）
+        tcp_port: Option<u16>,
+    },
+    /// 未收到回复
+    NoReply,
+    /// 收到ICMP差错报文（目标不可达、TTL过期等）
+    Unreachable(FailureReason),
+    /// 超过硬性时限仍未结束
+    TimedOut,
+    /// 无法执行探测（如找不到ping程序）
+    Error(String),
+}
+
+/// 主机存活探测器
+///
+/// 每次调用只做一次尝试，重试、并发和取消由 [`ping_concurrent_with`] 统一处理，
+/// 测试时可替换为返回预设结果的实现。
+pub trait Pinger: Sync {
+    /// 对单个IP进行一次探测
+    fn probe(&self, ip: &str, opts: PingOptions) -> impl Future<Output = ProbeOutcome> + Send;
+
+    /// 结果中记录的探测方式
+    fn probe_name(&self) -> &'static str {
+        PROBE
+    }
+}
+
+/// Ping的探测引擎
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PingEngine {
+    /// 原生ICMP
+    Icmp,
+    /// 调用系统ping程序
+    System,
+}
+
+/// Ping的探测方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PingMethod {
+    /// ICMP回显
+    #[default]
+    Icmp,
+    /// TCP连接
+    Tcp,
+    /// 先ICMP，无回复的再TCP
+    Auto,
+}
+
+/// 按 `--engine`、`--method` 选定的探测器
+#[derive(Debug, Clone)]
+pub enum EnginePinger {
+    /// 调用系统ping程序
+    System(SystemPinger),
+    /// 原生ICMP
+    Icmp(IcmpPinger),
+    /// TCP连接
+    Tcp(TcpPinger),
+}
+
+impl EnginePinger {
+    /// 按引擎及上下文选择探测器
+    ///
+    /// 录制及回放以系统ping的输出为单位，此时总是使用系统ping。
+    ///
+    /// # 参数
+    /// * `engine` - 指定的引擎，`None` 时有权限则使用原生ICMP，否则使用系统ping
+    /// * `ctx` - 扫描上下文
+    ///
+    /// # 返回
+    /// * `Err` - 指定了原生ICMP但没有所需权限，或在录制、回放时指定了原生ICMP
+    pub fn select(
+        engine: Option<PingEngine>,
+        ctx: &ScanContext,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let system = SystemPinger::for_context(ctx);
+        if !matches!(system.tape, Tape::Live) {
+            if engine == Some(PingEngine::Icmp) {
+                return Err("录制及回放只支持系统ping，请改用 --engine system".into());
+            }
+            return Ok(Self::System(system));
+        }
+        match engine {
+            Some(PingEngine::System) => Ok(Self::System(system)),
+            Some(PingEngine::Icmp) => Ok(Self::Icmp(IcmpPinger::open()?)),
+            None => Ok(IcmpPinger::open().map_or(Self::System(system), Self::Icmp)),
+        }
+    }
+
+    /// 引擎名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::System(_) => "系统ping",
+            Self::Icmp(_) => "原生ICMP",
+            Self::Tcp(_) => "TCP连接",
+        }
+    }
+}
+
+impl Pinger for EnginePinger {
+    async fn probe(&self, ip: &str, opts: PingOptions) -> ProbeOutcome {
+        match self {
+            Self::System(pinger) => pinger.probe(ip, opts).await,
+            Self::Icmp(pinger) => pinger.probe(ip, opts).await,
+            Self::Tcp(pinger) => pinger.probe(ip, opts).await,
+        }
+    }
+
+    fn probe_name(&self) -> &'static str {
+        match self {
+            Self::Tcp(pinger) => pinger.probe_name(),
+            _ => PROBE,
+        }
+    }
+}
+
+/// 以TCP连接探测存活的探测器（用于过滤ICMP的网段）
+///
+/// 同时连接各端口，任一端口连接成功或被拒绝（收到RST说明主机在线）即判定存活。
+#[derive(Debug, Clone)]
+pub struct TcpPinger {
+    /// 尝试连接的端口
+    pub ports: Vec<u16>,
+}
+
+impl Pinger for TcpPinger {
+    /// 连接各端口一次（超时时间至少1秒），有响应时记录端口
+    ///
+    /// 要等所有端口都有结论才能选出靠前的端口，等待时间不代表往返时延，不记录响应时间。
+    async fn probe(&self, ip: &str, opts: PingOptions) -> ProbeOutcome {
+        let Ok(addr) = ip.parse::<IpAddr>() else {
+            return ProbeOutcome::Error(format!("无效的IP地址: {}", ip));
+        };
+        let limit = Duration::from_secs(opts.timeout_secs.max(1));
+        match tcp_ping(addr, &self.ports, limit).await {
+            Some(port) => ProbeOutcome::Reply {
+                response_time: None,
+                ttl: None,
+                tcp_port: Some(port),
+            },
+            None => ProbeOutcome::NoReply,
+        }
+    }
+
+    fn probe_name(&self) -> &'static str {
+        TCP_PROBE
+    }
+}
+
+/// TCP探测：任一端口连接成功或被拒绝即视为存活
+///
+/// # 返回
+/// * `Some(端口)` - 有响应的端口（多个端口有响应时取列表中靠前的）
+pub async fn tcp_ping(ip: IpAddr, ports: &[u16], timeout: Duration) -> Option<u16> {
+    let attempts = ports.iter().map(|&port| async move {
+        let addr = SocketAddr::new(ip, port);
+        match tokio::time::timeout(timeout, TcpStream::connect(addr)).await {
+            Ok(Ok(_)) => Some(port),
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => Some(port),
+            _ => None,
+        }
+    });
+    future::join_all(attempts)
+        .await
+        .into_iter()
+        .flatten()
+        .next()
+}
+
+/// 一次系统ping的原始输出（录制及回放的单位）
+///
+/// 只保存判断所需的原始内容，回放时按录制时的平台规则重新解析，
+/// Windows下录制的输出在其他平台回放时同样按Windows的规则判断。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum PingTranscript {
+    /// ping进程在时限内结束
+    Finished {
+        /// 是否为Windows的ping输出
+        windows: bool,
+        /// 退出码是否为0
+        success: bool,
+        /// 标准输出原文
+        #[serde(with = "base64_bytes")]
+        stdout: Vec<u8>,
+    },
+    /// 超过硬性时限，ping进程已被终止
+    TimedOut,
+    /// 无法执行ping程序
+    Error {
+        /// 错误信息
+        message: String,
+    },
+}
+
+impl PingTranscript {
+    /// 解析为探测结果
+    pub fn outcome(&self) -> ProbeOutcome {
+        match self {
+            Self::TimedOut => ProbeOutcome::TimedOut,
+            Self::Error { message } => ProbeOutcome::Error(message.clone()),
+            Self::Finished {
+                windows,
+                success,
+                stdout,
+            } => {
+                // 差错报文在Windows下也以"来自 ... 的回复"开头，需先于成功关键词判断
+                if let Some(reason) = extract_failure_reason(stdout) {
+                    return ProbeOutcome::Unreachable(reason);
+                }
+
+                // Windows下即使返回非0状态码，也可能包含有效响应（如TTL过期但能通）
+                let is_success = if *windows {
+                    // 1. GBK解码（中文版）/ UTF-8（英文版）都能兼容
+                    let (gbk_str, _, _) = encoding_rs::GBK.decode(stdout);
+                    let output_str = gbk_str.to_lowercase();
+                    
+                    // 2. 同时匹配中英文成功关键词，覆盖所有Windows版本
+                    let success_keywords = [
+                        // 中文关键词（适配Windows中文版）
+                        "回复", "来自", 
+                        // 英文关键词（适配Windows英文版）
+                        "reply from", "ttl=", "bytes=", 
+                        // 通用关键词（中英文都有）
+                        "time=" 
+                    ];
+                    
+                    // 只要包含任意一个关键词，就判定为成功
+                    success_keywords.iter().any(|kw| output_str.contains(kw))
+                } else {
+                    *success
+                };
+
+                if is_success {
+                    // 尝试提取响应时间和TTL
+                    ProbeOutcome::Reply {
+                        response_time: extract_response_time(stdout),
+                        ttl: extract_ttl(stdout),
+                        tcp_port: None,
+                    }
+                } else {
+                    ProbeOutcome::NoReply
+                }
+            }
+        }
+    }
+}
+
+/// 调用系统ping程序的探测器
+#[derive(Debug, Clone)]
+pub struct SystemPinger {
+    /// ping程序路径
+    pub program: String,
+    /// 录制或回放（回放时不执行ping程序）
+    pub tape: Tape,
+}
+
+impl Default for SystemPinger {
+    fn default() -> Self {
+        Self {
+            program: PING_PROGRAM.to_string(),
+            tape: Tape::Live,
+        }
+    }
+}
+
+impl SystemPinger {
+    /// 按上下文的录制或回放设置创建探测器
+    pub fn for_context(ctx: &ScanContext) -> Self {
+        Self {
+            tape: ctx.tape().clone(),
+            ..Default::default()
+        }
+    }
+
+    /// 执行一次系统ping并收集原始输出
+    ///
+    /// 受 `timeout_secs + PING_GRACE` 的硬性时限约束，
+    /// ping进程超时未退出时会被强制终止并记为超时。
+    async fn execute(&self, ip: &str, opts: PingOptions) -> PingTranscript {
+        // Windows下单次ping超时（毫秒），设置为总超时的1/2避免整体超时过长
+        let win_timeout_ms = (opts.timeout_secs * 500).to_string();
+        // Linux下的超时参数（秒）
+        let linux_timeout_secs = opts.timeout_secs.to_string();
+        // 单次尝试的硬性时限
+        let hard_limit = Duration::from_secs(opts.timeout_secs) + PING_GRACE;
+
+        // IPv6地址（主机名解析得到）需要显式指定地址族
+        let family = if ip.contains(':') { "-6" } else { "-4" };
+
+        let mut cmd = Command::new(&self.program);
+        if cfg!(target_os = "windows") {
+            // Windows平台: ping -n 1 -w timeout IP
+            cmd.args(["-n", "1", "-w", &win_timeout_ms, family, "-l", "32", ip]);
+        } else {
+            // Unix/Linux平台: ping -c 1 -W timeout [-p 填充模式] IP
+            cmd.args(["-c", "1", "-W", &linux_timeout_secs]);
+            if let Some(pattern) = identity().icmp_pattern() {
+                cmd.args(["-p", &pattern]);
+            }
+            cmd.arg(ip);
+        }
+        let output = output_with_timeout(&mut cmd, hard_limit).await;
+
+        // println!("\n===== 调试信息 [IP: {}, 尝试次数: {}] =====", ip, attempt);
+        // match &output {
+        //     Ok(out) => {
+        //         // 1. 打印命令退出码（Windows下Ping的退出码可能不准，但可以参考）
+        //         println!("退出码: {:?}", out.status.code());
+        //         // 2. 打印标准输出（stdout）—— Ping的主要输出内容
+        //         println!("标准输出（原始字节）: {:?}", out.stdout);
+        //         // 3. 尝试转成字符串（UTF-8），Windows下可能乱码，先看原始
+        //         let stdout_str = String::from_utf8_lossy(&out.stdout);
+        //         println!("标准输出（UTF-8解析）: {}", stdout_str);
+        //         // 4. Windows下尝试用GBK解码（解决中文乱码）
+        //         if cfg!(target_os = "windows") {
+        //             let (gbk_str, _, _) = encoding_rs::GBK.decode(&out.stdout);
+        //             println!("标准输出（GBK解码）: {}", gbk_str);
+        //         }
+        //         // 5. 打印标准错误（stderr）—— 排查命令执行错误
+        //         let stderr_str = String::from_utf8_lossy(&out.stderr);
+        //         println!("标准错误: {}", stderr_str);
+        //     }
+        //     Err(e) => {
+        //         // 命令执行失败（比如找不到ping命令、权限问题）
+        //         println!("命令执行失败: {}", e);
+        //     }
+        // }
+        // println!("===========================================\n");
+
+        match output {
+            // ping进程未在时限内退出，已被终止
+            Ok(CommandOutcome::TimedOut) => PingTranscript::TimedOut,
+            Ok(CommandOutcome::Finished(out)) => PingTranscript::Finished {
+                windows: cfg!(target_os = "windows"),
+                success: out.status.success(),
+                stdout: out.stdout,
+            },
+            Err(e) => PingTranscript::Error {
+                message: e.to_string(),
+            },
+        }
+    }
+}
+
+impl Pinger for SystemPinger {
+    /// 执行一次系统ping（录制时保存原始输出，回放时读取录制，录制中没有的按超时处理）
+    async fn probe(&self, ip: &str, opts: PingOptions) -> ProbeOutcome {
+        let transcript = match self.tape {
+            Tape::Replay(ref player) => player
+                .take(PING_KIND, ip, "")
+                .unwrap_or(PingTranscript::TimedOut),
+            ref tape => {
+                let transcript = self.execute(ip, opts).await;
+                if let Tape::Record(recorder) = tape {
+                    let mut saved = transcript.clone();
+                    if let PingTranscript::Finished { ref mut stdout, .. } = saved {
+                        *stdout = recorder.payload(ip, stdout);
+                    }
+                    recorder.append(PING_KIND, ip, "", &saved);
+                }
+                transcript
+            }
+        };
+        transcript.outcome()
+    }
+}
+
+/// 导出Ping结果到Excel
+///
+/// # 参数
+/// * `results` - Ping结果
+/// * `options` - 导出选项
+/// * `split` - 存活与未存活的主机分别写入两个工作表（--split-sheets）
+///
+/// # 返回
+/// * 待执行的导出阶段（由调用方放到阻塞线程池执行，见 [`run_stage`]）
+pub fn export_results(
+    results: &[PingResult],
+    mut options: ExcelOptions,
+    split: bool,
+) -> ExcelExport {
+    // 查询过地理位置时增加地理位置列及ASN汇总表
+    let has_geo = results.iter().any(|r| r.geo.is_some());
+    if has_geo {
+        options.extra_sheets.push(asn_rollup_sheet(
+            results
+                .iter()
+                .filter_map(|r| Some((r.ip.as_str(), r.geo.as_ref()?))),
+        ));
+    }
+    // 有目标被合并时增加别名列，反向解析过时增加主机名列
+    let has_aliases = results.iter().any(|r| !r.aliases.is_empty());
+    let has_hostnames = results.iter().any(|r| r.hostname.is_some());
+    // TCP探测判定存活时增加有响应的端口列
+    let has_tcp_ports = results.iter().any(|r| r.tcp_port.is_some());
+    // 统计模式时增加丢包及时延列
+    let has_quality = results.iter().any(|r| r.quality.is_some());
+    let mut headers = vec!["IP地址", "状态", "响应时间(ms)", "失败原因"];
+    if has_tcp_ports {
+        headers.push("TCP端口");
+    }
+    if has_quality {
+        headers.extend(LinkQuality::HEADERS);
+    }
+    if has_aliases {
+        headers.push("别名");
+    }
+    if has_hostnames {
+        headers.push("主机名");
+    }
+    // 每个标签一列
+    if has_geo {
+        headers.extend(GeoInfo::HEADERS);
+    }
+    let has_verification = results.iter().any(|r| r.verification.is_some());
+    if has_verification {
+        headers.extend(Verification::HEADERS);
+    }
+    let has_asset_changes = results.iter().any(|r| !r.asset_changes.is_empty());
+    if has_asset_changes {
+        headers.push("资产变化");
+    }
+    let keys = tag_keys(results.iter().map(|r| &r.tags));
+    headers.extend(keys.iter().map(String::as_str));
+    // 观测来源固定在最后，不影响按位置引用已有列的表格
+    headers.extend(Provenance::HEADERS);
+    let write_row = |item: &PingResult, row: &mut RowWriter| {
+        row.cell(&item.ip).cell(&item.status);
+        match item.response_time {
+            Some(t) => row.cell(format_args!("{:.2}", t)),
+            None => row.cell("-"),
+        };
+        match item.failure_reason {
+            Some(reason) => row.cell(reason),
+            None => row.cell(""),
+        };
+        if has_tcp_ports {
+            row.cell(item.tcp_port.map(|p| p.to_string()).unwrap_or_default());
+        }
+        if has_quality {
+            row.cells(LinkQuality::cells(item.quality.as_ref()));
+        }
+        if has_aliases {
+            row.cell(item.aliases.join(", "));
+        }
+        if has_hostnames {
+            row.cell(item.hostname.as_deref().unwrap_or(""));
+        }
+        if has_geo {
+            row.cells(GeoInfo::cells(item.geo.as_ref()));
+        }
+        if has_verification {
+            row.cells(Verification::cells(item.verification.as_ref()));
+        }
+        if has_asset_changes {
+            row.cell(item.asset_changes.join("; "));
+        }
+        row.cells(
+            keys.iter()
+                .map(|k| item.tags.get(k).map_or("", String::as_str)),
+        );
+        row.cells(item.observed.cells());
+    };
+    if !split {
+        return ExcelExport::prepare_with(results, &headers, write_row, OutputKind::PING, &options);
+    }
+    let (alive, dead): (Vec<&PingResult>, Vec<&PingResult>) =
+        results.iter().partition(|r| r.is_success());
+    ExcelExport::prepare_groups(
+        &[("存活", &headers, &alive), ("未存活", &headers, &dead)],
+        |item, row| write_row(item, row),
+        OutputKind::PING,
+        &options,
+    )
+}
 
-    Ok(())
+/// 中间结果的写入方式：JSON，开启 -o 时另写Excel
+fn snapshot_writer(excel: bool, split: bool, ctx: &ScanContext) -> SnapshotWriter<PingResult> {
+    let run_dir = ctx.run_dir().cloned();
+    let options = ctx.excel_options();
+    Box::new(move |rows, n| {
+        let mut files = vec![write_json_snapshot(
+            rows,
+            n,
+            OutputKind::PING,
+            run_dir.as_deref(),
+        )?];
+        if excel {
+            let options = ExcelOptions {
+                file_name: Some(snapshot_file_name(
+                    OutputKind::PING.prefix,
+                    n,
+                    "xlsx",
+                    run_dir.is_none(),
+                )),
+                quiet: true,
+                ..options.clone()
+            };
+            files.push(export_results(rows, options, split).run()?.into());
+        }
+        Ok(files)
+    })
+}
+
+/// 反向解析存活主机的主机名（PTR），作为单独的阶段显示进度
+///
+/// 每个查询有单独的超时，没有PTR记录、超时或查询失败的主机记为 `-`。
+///
+/// # 参数
+/// * `resolver` - DNS解析器
+/// * `results` - Ping结果，存活主机的 `hostname` 在此填入
+/// * `timeout` - 单个查询的超时时间
+/// * `ctx` - 扫描上下文（取消后不再发起新的查询）
+pub async fn resolve_hostnames(
+    resolver: &dns::Resolver,
+    results: &mut [PingResult],
+    timeout: Duration,
+    ctx: &ScanContext,
+) {
+    let alive: Vec<(usize, IpAddr)> = results
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.is_success())
+        .filter_map(|(i, r)| Some((i, r.ip.parse().ok()?)))
+        .collect();
+    if alive.is_empty() {
+        return;
+    }
+    let progress = ctx.new_progress(alive.len() as u64);
+    let mut names = Vec::with_capacity(alive.len());
+    run_tracked(
+        alive,
+        RESOLVE_CONCURRENCY,
+        &progress,
+        ctx.token(),
+        |(i, ip)| async move { (i, tokio::time::timeout(timeout, resolver.reverse(ip)).await) },
+        |(i, lookup)| {
+            let name = match lookup {
+                Ok(Ok(names)) => names.into_iter().next(),
+                _ => None,
+            };
+            let outcome = if name.is_some() {
+                Outcome::Succeeded
+            } else {
+                Outcome::Failed
+            };
+            names.push((i, name));
+            outcome
+        },
+    )
+    .await;
+    progress.finish_with_message(format!("{} 反向解析完成", Icon::Ok));
+    for (i, name) in names {
+        let name = name.map(|n| n.trim_end_matches('.').to_string());
+        results[i].hostname = Some(name.unwrap_or_else(|| "-".to_string()));
+    }
 }
 
 /// 并发执行Ping扫描
 ///
+/// 并发由工作池限制，结果按IP数值排序后返回（与传入顺序及完成顺序无关，便于对比两次扫描）
+///
 /// # 参数
-/// * `ips` - IP地址列表
+/// * `ips` - IP地址（按需取出，可传入 [`parse_targets_iter`](crate::utils::parse_targets_iter) 这样的惰性迭代器，
+///   进度条总数由调用方预先设置）
 /// * `timeout` - 超时时间（秒）
 /// * `count` - 每个IP的ping次数
 /// * `concurrency` - 最大并发数
 /// * `progress` - 进度条
+/// * `ctx` - 扫描上下文（暂停期间不再发起新的ping，取消后不再取出新目标，
+///   每个结果同时推送给上下文的接收方，开启录制或回放时同样生效）
 ///
 /// # 返回
-/// * `Ok(Vec<PingResult>)` - Ping结果列表
+/// * `Ok(Vec<PingResult>)` - Ping结果列表（按IP排序）
 /// * `Err` - 扫描失败
 pub async fn ping_concurrent_async(
-    ips: Vec<String>,
+    ips: impl IntoIterator<Item = String, IntoIter: Send>,
     timeout: u64,
     count: u32,
     concurrency: usize,
     progress: &ScanProgress,
+    ctx: &ScanContext,
 ) -> Result<Vec<PingResult>, Box<dyn Error + Send + Sync>> {
-    let sem = Arc::new(Semaphore::new(concurrency));
-    let results = Arc::new(tokio::sync::Mutex::new(Vec::with_capacity(ips.len())));
-    let mut handles = Vec::with_capacity(ips.len());
+    let opts = PingOptions {
+        timeout_secs: timeout,
+        count,
+        stats: false,
+    };
+    let results = ResultCollector::new();
+    ping_concurrent_with(
+        &SystemPinger::for_context(ctx),
+        ips,
+        opts,
+        concurrency,
+        progress,
+        ctx,
+        &results,
+    )
+    .await?;
+    let mut results = results.into_vec();
+    results.sort_by_cached_key(|r| ip_sort_key(&r.ip));
+    Ok(results)
+}
 
-    for ip in ips {
-        let permit = sem.clone().acquire_owned().await?;
-        let ip_clone = ip.clone();
-        let results_clone = Arc::clone(&results);
-        let progress_clone = progress.clone();
+/// 使用指定探测器并发执行Ping扫描
+///
+/// # 参数
+/// * `pinger` - 探测器
+/// * `opts` - 超时及尝试次数
+/// * `results` - 结果按完成顺序追加到这里（扫描期间可随时读取）
+///
+/// 其余参数同 [`ping_concurrent_async`]
+pub async fn ping_concurrent_with<P: Pinger>(
+    pinger: &P,
+    ips: impl IntoIterator<Item = String, IntoIter: Send>,
+    opts: PingOptions,
+    concurrency: usize,
+    progress: &ScanProgress,
+    ctx: &ScanContext,
+    results: &ResultCollector<PingResult>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (dispatch, stream) = ping_streamed(pinger, ips, opts, concurrency, progress, ctx);
+    let collector = stream.for_each(|result| {
+        results.push(result);
+        future::ready(())
+    });
+    tokio::join!(dispatch, collector);
 
-        let handle = tokio::spawn(async move {
-            let result = ping_ip_async(&ip_clone, timeout, count).await;
+    Ok(())
+}
 
-            // 将结果添加到结果列表
-            {
-                let mut results_guard = results_clone.lock().await;
-                results_guard.push(result);
+/// 使用指定探测器并发执行Ping扫描，结果以流的形式交给下一阶段
+///
+/// 与 [`ping_concurrent_with`] 相同（取消、暂停、推送结果及进度统计均一致），
+/// 但结果按完成顺序从返回的结果流中取出，下一阶段（如端口扫描）可以在某个主机
+/// 确认存活后立即开始，不必等全部主机探测完毕。被取消的探测不出现在结果流中。
+///
+/// 返回的分发任务与结果流须同时驱动，见 [`run_streamed`](crate::utils::pool::run_streamed)。
+///
+/// # 参数
+/// 同 [`ping_concurrent_with`]
+///
+/// # 返回
+/// * `(分发任务, 结果流)`
+pub fn ping_streamed<'a, P: Pinger>(
+    pinger: &'a P,
+    ips: impl IntoIterator<Item = String, IntoIter: Send + 'a>,
+    opts: PingOptions,
+    concurrency: usize,
+    progress: &'a ScanProgress,
+    ctx: &'a ScanContext,
+) -> (
+    impl Future<Output = ()> + 'a,
+    impl Stream<Item = PingResult> + 'a,
+) {
+    let (dispatch, results) = run_tracked_streamed(
+        stream::iter(ips),
+        concurrency,
+        progress,
+        ctx.token(),
+        move |ip| {
+            let host = ctx.host_token(&ip);
+            async move {
+                host.run_until_cancelled(async {
+                    ctx.pause.wait().await;
+                    let opts = opts.for_target(&ctx.target_overrides(&ip));
+                    // 单个目标的探测出现panic时只记为该目标出错，其他目标照常进行
+                    let probe = AssertUnwindSafe(ping_host(pinger, &ip, opts, ctx.throttle()));
+                    match probe.catch_unwind().await {
+                        Ok(result) => result,
+                        Err(panic) => {
+                            progress.println(format!(
+                                "{} {} 探测异常: {}",
+                                Icon::Warn,
+                                ip,
+                                panic_message(&*panic)
+                            ));
+                            PingResult::failure(ip.clone(), None)
+                        }
+                    }
+                })
+                .await
             }
+        },
+    );
+    let results = results.filter_map(move |result| {
+        let Some(result) = result.flatten() else {
+            progress.record(Outcome::Cancelled);
+            return future::ready(None);
+        };
+        let outcome = result.outcome();
+        ctx.emit_with_outcome(&result, outcome);
+        progress.record(outcome);
+        future::ready(Some(result))
+    });
 
-            progress_clone.inc(1);
-            drop(permit);
-        });
+    (dispatch, results)
+}
+
+/// panic携带的信息（非字符串时为固定提示）
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("未知错误")
+}
 
-        handles.push(handle);
+/// Ping单个IP地址
+///
+/// 会尝试ping指定次数，只要有一次成功即返回成功结果；
+/// 未收到回复时间隔一段时间再重试，探测无法执行时不再重试。
+/// 最后一次尝试超时的记为超时，否则记为失败，失败原因取最后一次尝试的结果。
+///
+/// # 参数
+/// * `pinger` - 探测器
+/// * `ip` - IP地址
+/// * `opts` - 超时及尝试次数
+/// * `throttle` - 探测节流（每次尝试前等待）
+///
+/// # 返回
+/// * `PingResult` - Ping结果
+pub async fn ping_host<P: Pinger>(
+    pinger: &P,
+    ip: &str,
+    opts: PingOptions,
+    throttle: &Throttle,
+) -> PingResult {
+    if opts.stats {
+        return ping_host_stats(pinger, ip, opts, throttle).await;
     }
+    let mut timed_out = false;
+    let mut reason = None;
+
+    for attempt in 1..=opts.count {
+        let permit = throttle.acquire(ip).await;
+        let outcome = pinger.probe(ip, opts).await;
+        drop(permit);
+        let failure = match outcome {
+            ProbeOutcome::Reply {
+                response_time,
+                ttl,
+                tcp_port,
+            } => {
+                let observed = match tcp_port {
+                    Some(port) => Provenance::now(format!("{}:{}", pinger.probe_name(), port)),
+                    None => Provenance::now(pinger.probe_name()),
+                };
+                return PingResult {
+                    attempts: attempt,
+                    tcp_port,
+                    observed,
+                    ..PingResult::success(ip.to_string(), response_time, ttl)
+                };
+            }
+            ProbeOutcome::TimedOut => {
+                // 已被终止，直接进行下一次尝试
+                timed_out = true;
+                continue;
+            }
+            ProbeOutcome::NoReply => FailureReason::NoReply,
+            ProbeOutcome::Unreachable(reason) => reason,
+            ProbeOutcome::Error(e) => {
+                eprintln!("{} 执行ping命令失败 {}: {}", Icon::Warn, ip, e);
+                reason = None;
+                break;
+            }
+        };
 
-    // 等待所有任务完成
-    for handle in handles {
-        if let Err(e) = handle.await {
-            eprintln!("⚠️  任务执行失败: {}", e);
+        // Ping失败，继续重试
+        timed_out = false;
+        reason = Some(failure);
+        if attempt < opts.count {
+            tokio::time::sleep(RETRY_INTERVAL).await;
         }
     }
 
-    let final_results = Arc::try_unwrap(results)
-        .expect("无法获取最终结果")
-        .into_inner();
-
-    Ok(final_results)
+    let result = if timed_out {
+        PingResult::timeout(ip.to_string())
+    } else {
+        PingResult::failure(ip.to_string(), reason)
+    };
+    PingResult {
+        observed: Provenance::now(pinger.probe_name()),
+        ..result
+    }
 }
 
-/// Ping单个IP地址
+/// 统计模式下Ping单个IP：发满全部次数，有一次回复即判定为存活
 ///
-/// 会尝试ping指定次数，只要有一次成功即返回成功结果
+/// 结果的响应时间为平均值，TTL、TCP端口取最后一次回复；尝试次数记为第一次回复时的次数，
+/// 与默认模式一样用于判断结果是否反复。
 ///
 /// # 参数
+/// * `pinger` - 探测器
 /// * `ip` - IP地址
-/// * `timeout_secs` - 超时时间（秒）
-/// * `count` - 最多尝试次数
+/// * `opts` - 超时及发送次数
+/// * `throttle` - 探测节流（每次尝试前等待）
 ///
 /// # 返回
-/// * `Ok(PingResult)` - Ping结果
-/// * `Err` - Ping失败
-async fn ping_ip_async(ip: &str, timeout_secs: u64, count: u32) -> PingResult {
-    // Windows下单次ping超时（毫秒），设置为总超时的1/2避免整体超时过长
-    let win_timeout_ms = (timeout_secs * 500).to_string();
-    // Linux下的超时参数（秒）
-    let linux_timeout_secs = timeout_secs.to_string();
-    // let timeout_str = format!("{}", timeout_secs * 1000);
-
-    for attempt in 1..=count {
-        let output = if cfg!(target_os = "windows") {
-            // Windows平台: ping -n 1 -w timeout IP
-            Command::new("ping")
-                .args(["-n", "1", "-w", &win_timeout_ms, "-4", "-l", "32", ip])
-                .output()
-                .await
-        } else {
-            // Unix/Linux平台: ping -c 1 -W timeout IP
-            Command::new("ping")
-                .args(["-c", "1", "-W", &linux_timeout_secs.to_string(), ip])
-                .output()
-                .await
-        };
-
-        // println!("\n===== 调试信息 [IP: {}, 尝试次数: {}] =====", ip, attempt);
-        // match &output {
-        //     Ok(out) => {
-        //         // 1. 打印命令退出码（Windows下Ping的退出码可能不准，但可以参考）
-        //         println!("退出码: {:?}", out.status.code());
-        //         // 2. 打印标准输出（stdout）—— Ping的主要输出内容
-        //         println!("标准输出（原始字节）: {:?}", out.stdout);
-        //         // 3. 尝试转成字符串（UTF-8），Windows下可能乱码，先看原始
-        //         let stdout_str = String::from_utf8_lossy(&out.stdout);
-        //         println!("标准输出（UTF-8解析）: {}", stdout_str);
-        //         // 4. Windows下尝试用GBK解码（解决中文乱码）
-        //         if cfg!(target_os = "windows") {
-        //             let (gbk_str, _, _) = encoding_rs::GBK.decode(&out.stdout);
-        //             println!("标准输出（GBK解码）: {}", gbk_str);
-        //         }
-        //         // 5. 打印标准错误（stderr）—— 排查命令执行错误
-        //         let stderr_str = String::from_utf8_lossy(&out.stderr);
-        //         println!("标准错误: {}", stderr_str);
-        //     }
-        //     Err(e) => {
-        //         // 命令执行失败（比如找不到ping命令、权限问题）
-        //         println!("命令执行失败: {}", e);
-        //     }
-        // }
-        // println!("===========================================\n");
-
-        match output {
-            Ok(out) => {
-                // Windows下即使返回非0状态码，也可能包含有效响应（如TTL过期但能通）
-                let is_success = if cfg!(target_os = "windows") {
-                    // 1. GBK解码（中文版）/ UTF-8（英文版）都能兼容
-                    let (gbk_str, _, _) = encoding_rs::GBK.decode(&out.stdout);
-                    let output_str = gbk_str.to_lowercase();
-                    
-                    // 2. 同时匹配中英文成功关键词，覆盖所有Windows版本
-                    let success_keywords = [
-                        // 中文关键词（适配Windows中文版）
-                        "回复", "来自", 
-                        // 英文关键词（适配Windows英文版）
-                        "reply from", "ttl=", "bytes=", 
-                        // 通用关键词（中英文都有）
-                        "time=" 
-                    ];
-                    
-                    // 只要包含任意一个关键词，就判定为成功
-                    success_keywords.iter().any(|kw| output_str.contains(kw))
-                } else {
-                    out.status.success()
-                };
+/// * `PingResult` - 带丢包及时延统计的Ping结果
+async fn ping_host_stats<P: Pinger>(
+    pinger: &P,
+    ip: &str,
+    opts: PingOptions,
+    throttle: &Throttle,
+) -> PingResult {
+    let mut sent = 0;
+    let mut received = 0;
+    let mut rtts = Vec::new();
+    let mut last_reply = None;
+    let mut first_reply = None;
+    let mut timed_out = false;
+    let mut reason = None;
 
-                if is_success {
-                    // 尝试提取响应时间
-                    let response_time = extract_response_time(&out.stdout);
-                    return PingResult::success(ip.to_string(), response_time);
-                } else {
-                    // Ping失败，继续重试
-                    if attempt < count {
-                        // Windows下增加重试间隔，避免请求过于密集
-                        let sleep_time = if cfg!(target_os = "windows") {
-                            tokio::time::Duration::from_millis(200)
-                        } else {
-                            tokio::time::Duration::from_millis(100)
-                        };
-                        tokio::time::sleep(sleep_time).await;
-                    }
-                }
+    for attempt in 1..=opts.count {
+        let permit = throttle.acquire(ip).await;
+        let outcome = pinger.probe(ip, opts).await;
+        drop(permit);
+        sent += 1;
+        match outcome {
+            ProbeOutcome::Reply {
+                response_time,
+                ttl,
+                tcp_port,
+            } => {
+                received += 1;
+                rtts.extend(response_time);
+                first_reply.get_or_insert(attempt);
+                last_reply = Some((ttl, tcp_port));
+            }
+            ProbeOutcome::TimedOut => {
+                timed_out = true;
+                continue;
+            }
+            ProbeOutcome::NoReply => {
+                timed_out = false;
+                reason = Some(FailureReason::NoReply);
+            }
+            ProbeOutcome::Unreachable(failure) => {
+                timed_out = false;
+                reason = Some(failure);
             }
-            Err(e) => {
-                eprintln!("⚠️  执行ping命令失败 {}: {}", ip, e);
+            ProbeOutcome::Error(e) => {
+                eprintln!("{} 执行ping命令失败 {}: {}", Icon::Warn, ip, e);
+                // 没有真正发出的探测不计入丢包
+                sent -= 1;
+                timed_out = false;
+                reason = None;
                 break;
             }
         }
+        if attempt < opts.count {
+            tokio::time::sleep(RETRY_INTERVAL).await;
+        }
+    }
+
+    let quality = LinkQuality::from_samples(sent, received, &rtts);
+    let result = match last_reply {
+        Some((ttl, tcp_port)) => {
+            let observed = match tcp_port {
+                Some(port) => Provenance::now(format!("{}:{}", pinger.probe_name(), port)),
+                None => Provenance::now(pinger.probe_name()),
+            };
+            PingResult {
+                attempts: first_reply.unwrap_or(1),
+                tcp_port,
+                observed,
+                ..PingResult::success(ip.to_string(), quality.rtt_avg, ttl)
+            }
+        }
+        None if timed_out => PingResult {
+            observed: Provenance::now(pinger.probe_name()),
+            ..PingResult::timeout(ip.to_string())
+        },
+        None => PingResult {
+            observed: Provenance::now(pinger.probe_name()),
+            ..PingResult::failure(ip.to_string(), reason)
+        },
+    };
+    PingResult {
+        quality: Some(quality),
+        ..result
     }
+}
 
-    PingResult::failure(ip.to_string())
+/// 从ping输出中识别ICMP差错报文的类型
+///
+/// 兼容Linux/BSD/macOS以及Windows中英文版的输出（Windows中文版为GBK编码）。
+///
+/// # 参数
+/// * `output` - ping命令的标准输出
+///
+/// # 返回
+/// * `Some(FailureReason)` - 收到的差错类型
+/// * `None` - 输出中没有差错报文
+fn extract_failure_reason(output: &[u8]) -> Option<FailureReason> {
+    let text = match std::str::from_utf8(output) {
+        Ok(text) => text.to_lowercase(),
+        Err(_) => encoding_rs::GBK.decode(output).0.to_lowercase(),
+    };
+    UNREACHABLE_KEYWORDS
+        .iter()
+        .find(|(keyword, _)| text.contains(keyword))
+        .map(|&(_, reason)| reason)
 }
 
 /// 从ping输出中提取响应时间
@@ -394,18 +2246,120 @@ fn extract_response_time(output: &[u8]) -> Option<f64> {
     None
 }
 
+/// 从ping输出中提取回复报文的TTL
+///
+/// # 参数
+/// * `output` - ping命令的标准输出
+///
+/// # 返回
+/// * `Some(u8)` - TTL值
+/// * `None` - 输出中没有TTL
+fn extract_ttl(output: &[u8]) -> Option<u8> {
+    let output_str = String::from_utf8_lossy(output).to_lowercase();
+    let pos = output_str.find("ttl=")? + "ttl=".len();
+    let digits: String = output_str[pos..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::dns::{DnsAnswer, DnsConfig, DnsError, DnsQuery, DnsRecords, DnsTransport};
+    use crate::utils::pool::run_bounded;
+    use crate::utils::targets::TargetSet;
+    use futures::future::BoxFuture;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 只有 10.0.0.1 有PTR记录的解析器
+    struct PtrTransport;
+
+    impl DnsTransport for PtrTransport {
+        fn query(&self, query: &DnsQuery) -> BoxFuture<'_, Result<DnsAnswer, DnsError>> {
+            let answer = match query {
+                DnsQuery::Ptr(ip) if ip.to_string() == "10.0.0.1" => Ok(DnsAnswer {
+                    records: DnsRecords::Names(vec!["gw.corp.local.".to_string()]),
+                    ttl: Duration::from_secs(60),
+                }),
+                _ => Err(DnsError::NotFound { negative_ttl: None }),
+            };
+            async move { answer }.boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ping_concurrent_async_returns_ip_order() {
+        let ips = ["127.0.0.10", "127.0.0.2", "127.0.0.1", "127.0.0.9"];
+        let ctx = background();
+        let progress = ctx.new_progress(ips.len() as u64);
+        let results = ping_concurrent_async(ips.map(String::from), 1, 1, 4, &progress, &ctx)
+            .await
+            .unwrap();
+        let order: Vec<&str> = results.iter().map(|r| r.ip.as_str()).collect();
+        // 按数值而非文本排序，与传入顺序无关
+        assert_eq!(order, ["127.0.0.1", "127.0.0.2", "127.0.0.9", "127.0.0.10"]);
+    }
+
+    /// 探测 10.0.0.2 时panic的探测器
+    struct PanickyPinger;
+
+    impl Pinger for PanickyPinger {
+        async fn probe(&self, ip: &str, _opts: PingOptions) -> ProbeOutcome {
+            if ip == "10.0.0.2" {
+                panic!("探测器故障");
+            }
+            ProbeOutcome::Reply {
+                response_time: Some(1.0),
+                ttl: None,
+                tcp_port: None,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_panicking_probe_only_fails_its_target() {
+        let ctx = background();
+        let results = scan(&PanickyPinger, ips(4), opts(1), 2, &ctx).await;
+        assert_eq!(results.len(), 4);
+        let failed: Vec<&PingResult> = results.iter().filter(|r| !r.is_success()).collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].ip, "10.0.0.2");
+        assert_eq!(failed[0].outcome(), Outcome::Errored);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_hostnames_marks_missing_ptr() {
+        let resolver = dns::Resolver::new(PtrTransport, DnsConfig::default());
+        let mut results = vec![
+            PingResult::success("10.0.0.1".to_string(), Some(1.0), None),
+            PingResult::success("10.0.0.2".to_string(), Some(1.0), None),
+            PingResult::failure("10.0.0.3".to_string(), None),
+        ];
+        resolve_hostnames(
+            &resolver,
+            &mut results,
+            Duration::from_secs(1),
+            &background(),
+        )
+        .await;
+        assert_eq!(results[0].hostname.as_deref(), Some("gw.corp.local"));
+        assert_eq!(results[1].hostname.as_deref(), Some("-"));
+        // 未存活的主机不查询
+        assert_eq!(results[2].hostname, None);
+    }
 
     #[test]
     fn test_ping_result_creation() {
-        let success = PingResult::success("192.168.1.1".to_string(), Some(10.5));
+        let success = PingResult::success("192.168.1.1".to_string(), Some(10.5), Some(64));
         assert!(success.is_success());
         assert_eq!(success.ip, "192.168.1.1");
         assert_eq!(success.response_time, Some(10.5));
 
-        let failure = PingResult::failure("192.168.1.2".to_string());
+        let failure = PingResult::failure("192.168.1.2".to_string(), None);
         assert!(!failure.is_success());
         assert_eq!(failure.ip, "192.168.1.2");
         assert_eq!(failure.response_time, None);
@@ -432,10 +2386,605 @@ mod tests {
         assert_eq!(time, Some(20.0));
     }
 
+    #[test]
+    fn test_extract_ttl() {
+        assert_eq!(
+            extract_ttl(b"Reply from 192.168.1.1: bytes=32 time=15ms TTL=128"),
+            Some(128)
+        );
+        assert_eq!(
+            extract_ttl(b"64 bytes from 10.0.0.1: icmp_seq=1 ttl=57 time=1.23 ms"),
+            Some(57)
+        );
+        assert_eq!(extract_ttl(b"Request timeout for icmp_seq 1"), None);
+    }
+
+    #[test]
+    fn test_extract_failure_reason() {
+        let cases: [(&[u8], Option<FailureReason>); 7] = [
+            (
+                b"From 10.0.0.1 icmp_seq=1 Destination Host Unreachable",
+                Some(FailureReason::HostUnreachable),
+            ),
+            (
+                b"From 10.0.0.1 icmp_seq=1 Packet filtered",
+                Some(FailureReason::AdminProhibited),
+            ),
+            (
+                b"Reply from 10.0.0.1: Destination net unreachable.",
+                Some(FailureReason::NetUnreachable),
+            ),
+            (
+                b"From 10.0.0.1 icmp_seq=1 Time to live exceeded",
+                Some(FailureReason::TtlExpired),
+            ),
+            (b"Request timed out.", None),
+            (
+                b"64 bytes from 10.0.0.1: icmp_seq=1 ttl=57 time=1.23 ms",
+                None,
+            ),
+            (b"", None),
+        ];
+        for (output, expected) in cases {
+            assert_eq!(extract_failure_reason(output), expected);
+        }
+
+        // Windows中文版输出为GBK编码
+        let (gbk, _, _) = encoding_rs::GBK.encode("来自 10.0.0.1 的回复: 无法访问目标主机。");
+        assert_eq!(
+            extract_failure_reason(&gbk),
+            Some(FailureReason::HostUnreachable)
+        );
+        let (gbk, _, _) = encoding_rs::GBK.encode("来自 10.0.0.1 的回复: 无法访问目标网。");
+        assert_eq!(
+            extract_failure_reason(&gbk),
+            Some(FailureReason::NetUnreachable)
+        );
+    }
+
     #[test]
     fn test_extract_response_time_none() {
         let output = b"Request timeout for icmp_seq 1";
         let time = extract_response_time(output);
         assert_eq!(time, None);
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hung_ping_is_killed_and_scan_completes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // 模拟一个永不退出的ping程序
+        let mock = std::env::temp_dir().join(format!("gxr_mock_ping_{}", std::process::id()));
+        std::fs::write(&mock, "#!/bin/sh\nexec sleep 1000\n").unwrap();
+        std::fs::set_permissions(&mock, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let program = mock.to_str().unwrap().to_string();
+
+        let start = Instant::now();
+        let pinger = SystemPinger {
+            program,
+            ..Default::default()
+        };
+        let opts = PingOptions {
+            timeout_secs: 0,
+            count: 2,
+            stats: false,
+        };
+        let throttle = Throttle::default();
+        let mut results = Vec::new();
+        run_bounded(
+            vec!["192.0.2.1", "192.0.2.2", "192.0.2.3"],
+            3,
+            |ip| ping_host(&pinger, ip, opts, &throttle),
+            |r| results.push(r),
+        )
+        .await;
+        std::fs::remove_file(&mock).ok();
+
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.status == "超时"));
+        // 每个IP两次尝试，每次 0s + PING_GRACE，并发执行
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    /// 按预设脚本返回结果的探测器，记录尝试次数和最大并发
+    #[derive(Default)]
+    struct ScriptedPinger {
+        /// IP -> 每次尝试的 (耗时, 结果)，超出脚本的尝试返回未回复
+        script: HashMap<String, Vec<(Duration, ProbeOutcome)>>,
+        /// 未在脚本中的IP的耗时
+        latency: Duration,
+        attempts: Mutex<HashMap<String, usize>>,
+        in_flight: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl ScriptedPinger {
+        fn with(mut self, ip: &str, steps: Vec<(u64, ProbeOutcome)>) -> Self {
+            let steps = steps
+                .into_iter()
+                .map(|(ms, outcome)| (Duration::from_millis(ms), outcome))
+                .collect();
+            self.script.insert(ip.to_string(), steps);
+            self
+        }
+
+        fn attempts(&self, ip: &str) -> usize {
+            self.attempts.lock().unwrap().get(ip).copied().unwrap_or(0)
+        }
+    }
+
+    impl Pinger for ScriptedPinger {
+        async fn probe(&self, ip: &str, _opts: PingOptions) -> ProbeOutcome {
+            let attempt = {
+                let mut attempts = self.attempts.lock().unwrap();
+                let n = attempts.entry(ip.to_string()).or_default();
+                *n += 1;
+                *n - 1
+            };
+            let (latency, outcome) = self
+                .script
+                .get(ip)
+                .and_then(|steps| steps.get(attempt).cloned())
+                .unwrap_or((self.latency, ProbeOutcome::NoReply));
+
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(latency).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            outcome
+        }
+    }
+
+    fn reply(ms: f64) -> ProbeOutcome {
+        ProbeOutcome::Reply {
+            response_time: Some(ms),
+            ttl: Some(64),
+            tcp_port: None,
+        }
+    }
+
+    fn opts(count: u32) -> PingOptions {
+        PingOptions {
+            timeout_secs: 1,
+            count,
+            stats: false,
+        }
+    }
+
+    fn ips(n: usize) -> Vec<String> {
+        (1..=n).map(|i| format!("10.0.0.{}", i)).collect()
+    }
+
+    async fn scan(
+        pinger: &impl Pinger,
+        ips: Vec<String>,
+        opts: PingOptions,
+        concurrency: usize,
+        ctx: &ScanContext,
+    ) -> Vec<PingResult> {
+        scan_tracked(pinger, ips, opts, concurrency, ctx).await.0
+    }
+
+    /// 同 `scan`，另外返回带扫描统计的进度条
+    async fn scan_tracked(
+        pinger: &impl Pinger,
+        ips: Vec<String>,
+        opts: PingOptions,
+        concurrency: usize,
+        ctx: &ScanContext,
+    ) -> (Vec<PingResult>, ScanProgress) {
+        let progress = ctx.new_progress(ips.len() as u64);
+        let results = ResultCollector::new();
+        ping_concurrent_with(pinger, ips, opts, concurrency, &progress, ctx, &results)
+            .await
+            .unwrap();
+        (results.into_vec(), progress)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stats_match_result_rows() {
+        let pinger = ScriptedPinger::default()
+            .with("10.0.0.1", vec![(10, reply(1.0))])
+            .with("10.0.0.2", vec![(10, reply(2.0))])
+            .with(
+                "10.0.0.3",
+                vec![(
+                    10,
+                    ProbeOutcome::Unreachable(FailureReason::HostUnreachable),
+                )],
+            )
+            .with(
+                "10.0.0.4",
+                vec![(10, ProbeOutcome::Error("没有ping程序".into()))],
+            )
+            .with("10.0.0.5", vec![(10, ProbeOutcome::TimedOut)]);
+        let (results, progress) = scan_tracked(&pinger, ips(8), opts(1), 3, &background()).await;
+
+        let stats = progress.snapshot();
+        let rows = PingStats::from_results(&results);
+        assert_eq!(stats.dispatched, 8);
+        assert_eq!(stats.completed as usize, results.len());
+        assert_eq!(progress.position(), stats.completed);
+        assert_eq!(stats.succeeded as usize, rows.alive);
+        assert_eq!(stats.failed as usize, rows.reasons.values().sum::<usize>());
+        assert_eq!(stats.errored, 1);
+        assert_eq!(
+            (stats.succeeded + stats.failed + stats.errored) as usize,
+            results.len()
+        );
+    }
+
+    #[test]
+    fn test_link_quality_from_samples() {
+        let quality = LinkQuality::from_samples(4, 3, &[1.0, 2.0, 3.0]);
+        assert_eq!(quality.loss_pct, 25.0);
+        assert_eq!(quality.rtt_min, Some(1.0));
+        assert_eq!(quality.rtt_avg, Some(2.0));
+        assert_eq!(quality.rtt_max, Some(3.0));
+        assert!((quality.rtt_stddev.unwrap() - (2.0f64 / 3.0).sqrt()).abs() < 1e-9);
+        assert_eq!(quality.summary(), "loss=25% avg=2.0ms");
+
+        let lost = LinkQuality::from_samples(3, 0, &[]);
+        assert_eq!(lost.loss_pct, 100.0);
+        assert_eq!(lost.rtt_avg, None);
+        assert_eq!(lost.summary(), "loss=100%");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stats_mode_sends_every_probe() {
+        let pinger = ScriptedPinger::default()
+            .with(
+                "10.0.0.1",
+                vec![
+                    (10, ProbeOutcome::NoReply),
+                    (10, reply(1.0)),
+                    (10, reply(3.0)),
+                    (10, ProbeOutcome::TimedOut),
+                ],
+            )
+            .with("10.0.0.2", vec![(10, reply(1.0))]);
+        let stats = PingOptions {
+            stats: true,
+            ..opts(4)
+        };
+        let mut results = scan(&pinger, ips(3), stats, 3, &background()).await;
+        results.sort_by(|a, b| a.ip.cmp(&b.ip));
+
+        // 有回复后仍发满全部次数
+        assert_eq!(pinger.attempts("10.0.0.1"), 4);
+        assert_eq!(pinger.attempts("10.0.0.2"), 4);
+        let quality = results[0].quality.as_ref().unwrap();
+        assert!(results[0].is_success());
+        assert_eq!((quality.packets_sent, quality.packets_received), (4, 2));
+        assert_eq!(quality.loss_pct, 50.0);
+        assert_eq!(results[0].response_time, Some(2.0));
+        assert_eq!(results[0].attempts, 2);
+        assert_eq!(results[1].quality.as_ref().unwrap().loss_pct, 75.0);
+        assert!(!results[2].is_success());
+        assert_eq!(results[2].quality.as_ref().unwrap().loss_pct, 100.0);
+
+        // 默认模式有一次成功即停止
+        let pinger = ScriptedPinger::default().with("10.0.0.1", vec![(10, reply(1.0))]);
+        let results = scan(&pinger, ips(1), opts(4), 1, &background()).await;
+        assert_eq!(pinger.attempts("10.0.0.1"), 1);
+        assert!(results[0].quality.is_none());
+    }
+
+    fn background() -> ScanContext {
+        ScanContext::background(tokio::sync::mpsc::unbounded_channel().0)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_concurrency_limit_is_respected() {
+        let pinger = ScriptedPinger {
+            latency: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let ctx = background();
+        let start = tokio::time::Instant::now();
+        let results = scan(&pinger, ips(20), opts(1), 4, &ctx).await;
+
+        assert_eq!(results.len(), 20);
+        assert_eq!(pinger.peak.load(Ordering::SeqCst), 4);
+        // 20个目标、每批4个，共5批
+        assert_eq!(start.elapsed(), Duration::from_millis(250));
+        assert_eq!(ctx.progress(), (20, 20));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_results_arrive_in_completion_order() {
+        let pinger = ScriptedPinger::default()
+            .with("10.0.0.1", vec![(300, reply(3.0))])
+            .with("10.0.0.2", vec![(100, reply(1.0))])
+            .with("10.0.0.3", vec![(200, reply(2.0))]);
+        let results = scan(&pinger, ips(3), opts(1), 3, &background()).await;
+
+        let order: Vec<&str> = results.iter().map(|r| r.ip.as_str()).collect();
+        assert_eq!(order, ["10.0.0.2", "10.0.0.3", "10.0.0.1"]);
+        assert!(results.iter().all(|r| r.is_success()));
+        assert_eq!(results[0].ttl, Some(64));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_aliases_of_one_host_are_probed_once() {
+        let mut targets = TargetSet::default();
+        targets.add("localhost", vec!["127.0.0.1".to_string()]);
+        for spec in ["127.0.0.1", "127.0.0.1/32"] {
+            targets.add(spec, crate::utils::parse_targets(spec).unwrap());
+        }
+
+        let pinger = ScriptedPinger::default().with("127.0.0.1", vec![(10, reply(0.1))]);
+        let mut results = scan(&pinger, targets.ips(), opts(1), 4, &background()).await;
+        for result in &mut results {
+            result.aliases = targets.aliases(&result.ip);
+        }
+
+        assert_eq!(pinger.attempts("127.0.0.1"), 1);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].aliases, ["localhost", "127.0.0.1/32"]);
+        let row = serde_json::to_value(&results[0]).unwrap();
+        assert_eq!(row["aliases"][0], "localhost");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_target_overrides_change_attempts() {
+        let mut targets = TargetSet::default();
+        for spec in ["10.0.0.1", "10.0.0.2"] {
+            targets.add(spec, vec![spec.to_string()]);
+        }
+        let overrides = TargetOverrides::parse(["count=4", "timeout=300ms"]).unwrap();
+        targets.set_overrides("10.0.0.2", overrides);
+        let ctx = background().with_target_overrides(&targets);
+
+        let pinger = ScriptedPinger::default();
+        let results = scan(&pinger, targets.ips(), opts(2), 2, &ctx).await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(pinger.attempts("10.0.0.1"), 2);
+        assert_eq!(pinger.attempts("10.0.0.2"), 4);
+        // 系统ping的超时向上取整到秒
+        assert_eq!(opts(2).for_target(&overrides).timeout_secs, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_behaviour() {
+        let pinger = ScriptedPinger::default()
+            // 第二次尝试成功
+            .with(
+                "10.0.0.1",
+                vec![(10, ProbeOutcome::NoReply), (10, reply(5.0))],
+            )
+            // 最后一次尝试超时记为超时
+            .with(
+                "10.0.0.2",
+                vec![(10, ProbeOutcome::NoReply), (10, ProbeOutcome::TimedOut)],
+            )
+            // 超时后又未回复记为失败
+            .with(
+                "10.0.0.3",
+                vec![(10, ProbeOutcome::TimedOut), (10, ProbeOutcome::NoReply)],
+            )
+            // 无法执行探测时不再重试
+            .with(
+                "10.0.0.4",
+                vec![(10, ProbeOutcome::Error("not found".to_string()))],
+            )
+            // 失败原因取最后一次尝试
+            .with(
+                "10.0.0.5",
+                vec![
+                    (
+                        10,
+                        ProbeOutcome::Unreachable(FailureReason::HostUnreachable),
+                    ),
+                    (
+                        10,
+                        ProbeOutcome::Unreachable(FailureReason::AdminProhibited),
+                    ),
+                ],
+            );
+        let results = scan(&pinger, ips(5), opts(2), 5, &background()).await;
+        let find = |ip: &str| results.iter().find(|r| r.ip == ip).unwrap();
+        let status = |ip: &str| find(ip).status.clone();
+
+        assert_eq!(status("10.0.0.1"), "成功");
+        assert_eq!(status("10.0.0.2"), "超时");
+        assert_eq!(status("10.0.0.3"), "失败");
+        assert_eq!(status("10.0.0.4"), "失败");
+        assert_eq!(pinger.attempts("10.0.0.1"), 2);
+        assert_eq!(pinger.attempts("10.0.0.2"), 2);
+        assert_eq!(pinger.attempts("10.0.0.4"), 1);
+        assert_eq!(status("10.0.0.5"), "失败");
+        assert_eq!(
+            find("10.0.0.3").failure_reason,
+            Some(FailureReason::NoReply)
+        );
+        assert_eq!(find("10.0.0.4").failure_reason, None);
+        assert_eq!(
+            find("10.0.0.5").failure_reason,
+            Some(FailureReason::AdminProhibited)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancel_stops_dispatch() {
+        let pinger = ScriptedPinger {
+            latency: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let ctx = background();
+        let ((results, progress), _) =
+            tokio::join!(scan_tracked(&pinger, ips(10), opts(1), 2, &ctx), async {
+                tokio::time::sleep(Duration::from_millis(250)).await;
+                ctx.cancel();
+            });
+
+        // 取消前已完成的结果保留，进行中的两个探测被放弃，之后不再取出新目标
+        assert_eq!(results.len(), 4);
+        assert_eq!(pinger.attempts.lock().unwrap().len(), 6);
+        let stats = progress.snapshot();
+        assert_eq!((stats.completed, stats.cancelled), (4, 2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancel_host_skips_only_that_host() {
+        let pinger = ScriptedPinger {
+            latency: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let ctx = background();
+        ctx.cancel_host("10.0.0.2");
+        let (results, progress) = scan_tracked(&pinger, ips(3), opts(1), 3, &ctx).await;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(progress.snapshot().cancelled, 1);
+        assert!(results.iter().all(|r| r.ip != "10.0.0.2"));
+        assert_eq!(pinger.attempts("10.0.0.2"), 0);
+        assert!(!ctx.is_cancelled());
+    }
+
+    #[test]
+    fn test_stats_math() {
+        let results = vec![
+            PingResult::success("10.0.0.1".to_string(), Some(1.0), None),
+            PingResult::failure("10.0.0.2".to_string(), Some(FailureReason::AdminProhibited)),
+            PingResult::failure("10.0.0.4".to_string(), Some(FailureReason::AdminProhibited)),
+            PingResult::timeout("10.0.0.3".to_string()),
+            PingResult::failure("10.0.0.5".to_string(), None),
+        ];
+        let stats = PingStats::from_results(&results[..3]);
+        assert_eq!(
+            stats,
+            PingStats {
+                total: 3,
+                alive: 1,
+                failed: 2,
+                reasons: BTreeMap::from([(FailureReason::AdminProhibited, 2)]),
+            }
+        );
+        let stats = PingStats::from_results(&results);
+        assert_eq!(stats.failed, 4);
+        assert_eq!(stats.reasons[&FailureReason::NoReply], 1);
+        assert_eq!(stats.reasons.values().sum::<usize>(), 3);
+        assert_eq!(stats.percent(stats.alive), 20.0);
+        assert_eq!(PingStats::from_results(&[]).percent(0), 0.0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_verify_pass_reprobes_suspicious_hosts() {
+        let pinger = ScriptedPinger::default()
+            // 10.0.0.1 基线中存活，主扫描丢包；10.0.0.2 重试后才成功，复核时不再回复
+            .with(
+                "10.0.0.2",
+                vec![(10, ProbeOutcome::NoReply), (10, reply(2.0))],
+            )
+            .with("10.0.0.3", vec![(10, reply(3.0))]);
+        let ctx = background();
+        let (mut results, progress) = scan_tracked(&pinger, ips(4), opts(2), 4, &ctx).await;
+        let stats = progress.stats();
+        results.sort_by(|a, b| a.ip.cmp(&b.ip));
+        assert!(!results[0].is_success());
+        assert_eq!(results[1].attempts, 2);
+
+        let verify = VerifyArgs {
+            verify: true,
+            ..Default::default()
+        };
+        let baseline = HashSet::from(["10.0.0.1".to_string(), "10.0.0.3".to_string()]);
+        let candidates = verify_candidates(&results, &verify, &baseline);
+        assert_eq!(candidates, ["10.0.0.1", "10.0.0.2"]);
+
+        // 复核时 10.0.0.1 回复，10.0.0.2 不再回复
+        let verify_pinger = ScriptedPinger::default().with("10.0.0.1", vec![(10, reply(1.0))]);
+        let (checked, changed) = verify_results(
+            &verify_pinger,
+            &mut results,
+            candidates,
+            opts(3),
+            4,
+            &ctx,
+            stats,
+        )
+        .await
+        .unwrap();
+        assert_eq!((checked, changed), (2, 2));
+        assert!(results[0].is_success());
+        assert_eq!(results[0].verification, Some(Verification::verified(true)));
+        assert!(!results[1].is_success());
+        assert_eq!(verify_pinger.attempts("10.0.0.2"), 3);
+        assert_eq!(results[2].verification, Some(Verification::UNVERIFIED));
+        assert_eq!(results[3].verification, Some(Verification::UNVERIFIED));
+        // 复核改变的结论同步到主扫描的统计
+        let alive = results.iter().filter(|r| r.is_success()).count();
+        assert_eq!(stats.snapshot().succeeded as usize, alive);
+    }
+
+    #[test]
+    fn test_engine_selection() {
+        let ctx = background();
+        let pinger = EnginePinger::select(Some(PingEngine::System), &ctx).unwrap();
+        assert_eq!(pinger.name(), "系统ping");
+        // 未指定时按权限选择，没有权限也不报错
+        let pinger = EnginePinger::select(None, &ctx).unwrap();
+        assert_eq!(
+            matches!(pinger, EnginePinger::Icmp(_)),
+            IcmpPinger::open().is_ok()
+        );
+        let args =
+            PingArgs::try_parse_from(["ping", "-t", "10.0.0.1", "--engine", "icmp"]).unwrap();
+        assert_eq!(args.engine, Some(PingEngine::Icmp));
+    }
+
+    /// 本机上一个监听中的端口及一个已关闭的端口
+    async fn loopback_ports() -> (tokio::net::TcpListener, u16, u16) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap().port();
+        // 绑定后立即释放的端口，连接会被拒绝
+        let closed = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        (listener, open, closed)
+    }
+
+    #[tokio::test]
+    async fn test_tcp_pinger_counts_refused_as_alive() {
+        let (_listener, open, closed) = loopback_ports().await;
+        let throttle = Throttle::default();
+        // 被拒绝（RST）同样说明主机在线，多个端口有响应时取列表中靠前的
+        let pinger = TcpPinger {
+            ports: vec![closed, open],
+        };
+        let result = ping_host(&pinger, "127.0.0.1", opts(1), &throttle).await;
+        assert!(result.is_success());
+        assert_eq!(result.tcp_port, Some(closed));
+        assert_eq!(result.observed.probe, format!("tcp-connect:{}", closed));
+
+        let pinger = TcpPinger { ports: vec![open] };
+        let result = ping_host(&pinger, "127.0.0.1", opts(1), &throttle).await;
+        assert_eq!(result.tcp_port, Some(open));
+    }
+
+    #[tokio::test]
+    async fn test_tcp_fallback_replaces_icmp_failures() {
+        let (_listener, open, _) = loopback_ports().await;
+        let ctx = background();
+        let icmp = ScriptedPinger::default().with("10.0.0.1", vec![(1, reply(1.0))]);
+        let ips = vec!["10.0.0.1".to_string(), "127.0.0.1".to_string()];
+        let (mut results, progress) = scan_tracked(&icmp, ips, opts(1), 4, &ctx).await;
+        results.sort_by_cached_key(|r| ip_sort_key(&r.ip));
+        assert_eq!(progress.snapshot().succeeded, 1);
+
+        let tcp = TcpPinger { ports: vec![open] };
+        let (probed, found) = tcp_fallback(&tcp, &mut results, opts(1), 4, &ctx, progress.stats())
+            .await
+            .unwrap();
+        assert_eq!((probed, found), (1, 1));
+        assert!(results.iter().all(PingResult::is_success));
+        assert_eq!(results[1].tcp_port, Some(open));
+        assert_eq!(results[0].observed.probe, "icmp");
+        // 改判存活的同步到主扫描的统计
+        assert_eq!(progress.snapshot().succeeded, 2);
+    }
 }
@@ -0,0 +1,1342 @@
+// src/commands/pentest/adinfo.rs
+use crate::commands::history::RunSummary;
+use crate::utils::blocking::run_stage;
+use crate::utils::console::Icon;
+use crate::utils::context::ScanContext;
+use crate::utils::dns::resolver;
+use crate::utils::finding::{
+    Finding, FindingFormat, FindingParams, Severity, consolidate, write_findings,
+};
+use crate::utils::limits::{
+    ConcurrencySpec, ScanKind, effective_concurrency, parse_fixed_concurrency,
+};
+use crate::utils::output::OutputKind;
+use crate::utils::pool::run_tracked;
+use crate::utils::provenance::Provenance;
+use crate::utils::run_dir::{FINDINGS_FILE_NAME, HOSTS_FILE_NAME, SummaryItem};
+use crate::utils::scope;
+use crate::utils::stats::Outcome;
+use crate::utils::targets::{TargetSourceArgs, collect_targets};
+use crate::utils::{ExcelExport, format_elapsed};
+use clap::Parser;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+/// 单条LDAP消息的最大长度
+const MAX_MESSAGE_BYTES: usize = 4 * 1024 * 1024;
+
+/// 一次查询最多接收的消息数（防止服务端无视条数限制）
+const MAX_SEARCH_MESSAGES: usize = 1000;
+
+/// 签名检查使用的不存在的账号（不会触发真实账号的锁定）
+const SIGNING_PROBE_USER: &str = "gxtools-signing-probe";
+
+/// 签名检查使用的密码
+const SIGNING_PROBE_PASSWORD: &str = "gxtools-invalid-password";
+
+/// 查询rootDSE时请求的属性
+const ROOT_DSE_ATTRIBUTES: &[&str] = &[
+    "dnsHostName",
+    "serverName",
+    "defaultNamingContext",
+    "rootDomainNamingContext",
+    "namingContexts",
+    "supportedSASLMechanisms",
+    "supportedLDAPVersion",
+    "domainFunctionality",
+    "forestFunctionality",
+    "domainControllerFunctionality",
+];
+
+/// 域信息收集参数配置
+#[derive(Parser, Debug)]
+pub struct AdInfoArgs {
+    /// 目标IP或IP段（支持CIDR、范围、多个IP用逗号隔开）
+    #[arg(
+        short,
+        long,
+        value_name = "TARGET",
+        required_unless_present_any = ["domain", "target_xlsx", "target_file"]
+    )]
+    pub targets: Option<String>,
+
+    #[command(flatten)]
+    pub sources: TargetSourceArgs,
+
+    /// 域名（通过 _ldap._tcp.dc._msdcs.<域名> SRV记录定位域控制器）
+    #[arg(short, long, value_name = "DOMAIN")]
+    pub domain: Option<String>,
+
+    /// 直接指定目标时使用的LDAP端口（SRV记录自带端口）
+    #[arg(short, long, default_value = "389", value_name = "PORT")]
+    pub port: u16,
+
+    /// 最大并发数（auto 表示根据文件描述符上限和任务数量自动选择）
+    #[arg(
+        short = 'c',
+        long,
+        env = "GXTOOLS_CONCURRENCY",
+        default_value = "10",
+        value_name = "NUM|auto",
+        value_parser = parse_fixed_concurrency
+    )]
+    pub concurrency: ConcurrencySpec,
+
+    /// 连接及每个请求的超时时间（秒）
+    #[arg(
+        short = 'T',
+        long,
+        env = "GXTOOLS_TIMEOUT",
+        default_value = "5",
+        value_name = "SECS"
+    )]
+    pub timeout: u64,
+
+    /// 匿名读取目录时最多取回的条目数
+    #[arg(long, default_value = "5", value_name = "NUM")]
+    pub sample: u32,
+
+    /// 不检查LDAP签名要求（该检查会用不存在的账号做一次简单绑定）
+    #[arg(long)]
+    pub no_signing_check: bool,
+
+    /// 另外导出的发现列表格式（多个格式用逗号隔开）
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "FORMAT")]
+    pub format: Vec<FindingFormat>,
+
+    /// 是否输出结果到Excel文件
+    #[arg(short = 'o', long, env = "GXTOOLS_OUTPUT")]
+    pub output: bool,
+}
+
+/// LDAP签名要求
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SigningStatus {
+    /// 明文简单绑定被拒绝（strongerAuthRequired）
+    Required,
+    /// 明文简单绑定被正常处理（凭据错误），服务端未强制签名
+    NotRequired,
+    /// 应答无法判断
+    Unknown,
+}
+
+impl SigningStatus {
+    /// 中文名称
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Required => "已强制",
+            Self::NotRequired => "未强制",
+            Self::Unknown => "无法判断",
+        }
+    }
+}
+
+/// 单个域控制器（LDAP服务）的检查结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DomainController {
+    /// IP地址
+    pub ip: String,
+    /// 端口号
+    pub port: u16,
+    /// SRV记录中的主机名（直接指定的目标为空）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub srv_host: Option<String>,
+    /// rootDSE中的 dnsHostName
+    pub dns_host_name: String,
+    /// 默认命名上下文（域的根DN）
+    pub default_naming_context: String,
+    /// 所有命名上下文
+    pub naming_contexts: Vec<String>,
+    /// 支持的SASL机制
+    pub sasl_mechanisms: Vec<String>,
+    /// 域功能级别
+    pub domain_level: String,
+    /// 林功能级别
+    pub forest_level: String,
+    /// 域控制器功能级别
+    pub dc_level: String,
+    /// 匿名绑定是否成功
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anonymous_bind: Option<bool>,
+    /// 匿名查询取回的条目数
+    pub anonymous_entries: usize,
+    /// 匿名查询取回的条目DN
+    pub anonymous_sample: Vec<String>,
+    /// 匿名查询的结果（如 `1 000004DC: LdapErr: ...`）
+    pub anonymous_result: String,
+    /// LDAP签名要求
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signing: Option<SigningStatus>,
+    /// 签名检查绑定的应答
+    pub signing_evidence: String,
+    /// 检查中断的原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 观测时间及探测方式（如 `ldap:389`）
+    #[serde(flatten)]
+    pub observed: Provenance,
+}
+
+impl DomainController {
+    fn new(ip: &str, port: u16, srv_host: Option<String>) -> Self {
+        Self {
+            ip: ip.to_string(),
+            port,
+            srv_host,
+            dns_host_name: String::new(),
+            default_naming_context: String::new(),
+            naming_contexts: Vec::new(),
+            sasl_mechanisms: Vec::new(),
+            domain_level: String::new(),
+            forest_level: String::new(),
+            dc_level: String::new(),
+            anonymous_bind: None,
+            anonymous_entries: 0,
+            anonymous_sample: Vec::new(),
+            anonymous_result: String::new(),
+            signing: None,
+            signing_evidence: String::new(),
+            error: None,
+            observed: Provenance::now(format!("ldap:{}", port)),
+        }
+    }
+
+    /// 检查结论：连接不上视为失败，连接后检查中断视为出错
+    pub fn outcome(&self) -> Outcome {
+        match self.error.as_deref() {
+            None => Outcome::Succeeded,
+            Some(e) if e.starts_with("连接") => Outcome::Failed,
+            Some(_) => Outcome::Errored,
+        }
+    }
+
+    /// 主机名（优先使用rootDSE中的 dnsHostName）
+    pub fn host_name(&self) -> &str {
+        if self.dns_host_name.is_empty() {
+            self.srv_host.as_deref().unwrap_or_default()
+        } else {
+            &self.dns_host_name
+        }
+    }
+}
+
+/// 域汇总信息
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DomainSummary {
+    /// 域名（由默认命名上下文换算）
+    pub domain: String,
+    /// 默认命名上下文
+    pub naming_context: String,
+    /// 域功能级别
+    pub domain_level: String,
+    /// 林功能级别
+    pub forest_level: String,
+    /// 该域的域控制器（主机名或IP）
+    pub controllers: Vec<String>,
+}
+
+/// LDAP操作的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LdapResult {
+    code: u32,
+    diagnostic: String,
+}
+
+impl LdapResult {
+    fn describe(&self) -> String {
+        format!("{} {}", self.code, self.diagnostic)
+            .trim()
+            .to_string()
+    }
+}
+
+/// 查询返回的条目
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LdapEntry {
+    dn: String,
+    attributes: BTreeMap<String, Vec<String>>,
+}
+
+impl LdapEntry {
+    fn first(&self, name: &str) -> String {
+        self.values(name).first().cloned().unwrap_or_default()
+    }
+
+    fn values(&self, name: &str) -> Vec<String> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.clone())
+            .unwrap_or_default()
+    }
+}
+
+// ---- BER编码（只实现LDAP请求与应答用到的部分） ----
+
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_ENUMERATED: u8 = 0x0a;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_BIND_REQUEST: u8 = 0x60;
+const TAG_BIND_RESPONSE: u8 = 0x61;
+const TAG_UNBIND_REQUEST: u8 = 0x42;
+const TAG_SEARCH_REQUEST: u8 = 0x63;
+const TAG_SEARCH_ENTRY: u8 = 0x64;
+const TAG_SEARCH_DONE: u8 = 0x65;
+const TAG_SIMPLE_AUTH: u8 = 0x80;
+const TAG_FILTER_PRESENT: u8 = 0x87;
+
+/// 编码一个TLV
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// 编码非负整数（INTEGER/ENUMERATED）
+fn integer(tag: u8, value: u32) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes[..3].iter().take_while(|&&b| b == 0).count();
+    let mut content = bytes[skip..].to_vec();
+    if content[0] & 0x80 != 0 {
+        content.insert(0, 0);
+    }
+    tlv(tag, &content)
+}
+
+fn octets(value: &str) -> Vec<u8> {
+    tlv(TAG_OCTET_STRING, value.as_bytes())
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// 解析一个TLV
+///
+/// # 返回
+/// * `(标签, 内容, 剩余字节)`
+fn parse_tlv(buf: &[u8]) -> io::Result<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = buf.split_first().ok_or_else(|| invalid("BER数据不完整"))?;
+    let (&first, rest) = rest.split_first().ok_or_else(|| invalid("BER数据不完整"))?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 || rest.len() < n {
+            return Err(invalid("不支持的BER长度"));
+        }
+        let len = rest[..n]
+            .iter()
+            .fold(0usize, |acc, &b| acc << 8 | b as usize);
+        (len, &rest[n..])
+    };
+    if rest.len() < len {
+        return Err(invalid("BER数据不完整"));
+    }
+    Ok((tag, &rest[..len], &rest[len..]))
+}
+
+fn parse_integer(content: &[u8]) -> u32 {
+    content.iter().fold(0u32, |acc, &b| acc << 8 | b as u32)
+}
+
+fn parse_string(content: &[u8]) -> String {
+    String::from_utf8_lossy(content).to_string()
+}
+
+/// 解析 LDAPResult（resultCode, matchedDN, diagnosticMessage）
+fn parse_result(content: &[u8]) -> io::Result<LdapResult> {
+    let (_, code, rest) = parse_tlv(content)?;
+    let (_, _matched, rest) = parse_tlv(rest)?;
+    let (_, diagnostic, _) = parse_tlv(rest)?;
+    Ok(LdapResult {
+        code: parse_integer(code),
+        diagnostic: parse_string(diagnostic)
+            .trim_end_matches('\0')
+            .trim()
+            .to_string(),
+    })
+}
+
+/// 解析 SearchResultEntry
+fn parse_entry(content: &[u8]) -> io::Result<LdapEntry> {
+    let (_, dn, rest) = parse_tlv(content)?;
+    let (_, mut list, _) = parse_tlv(rest)?;
+    let mut attributes = BTreeMap::new();
+    while !list.is_empty() {
+        let (_, attribute, next) = parse_tlv(list)?;
+        list = next;
+        let (_, name, rest) = parse_tlv(attribute)?;
+        let (_, mut set, _) = parse_tlv(rest)?;
+        let mut values = Vec::new();
+        while !set.is_empty() {
+            let (_, value, next) = parse_tlv(set)?;
+            values.push(parse_string(value));
+            set = next;
+        }
+        attributes.insert(parse_string(name), values);
+    }
+    Ok(LdapEntry {
+        dn: parse_string(dn),
+        attributes,
+    })
+}
+
+/// 只发送匿名/简单绑定和查询的LDAP客户端
+struct LdapClient<S> {
+    stream: S,
+    next_id: u32,
+    io_timeout: Duration,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> LdapClient<S> {
+    fn new(stream: S, io_timeout: Duration) -> Self {
+        Self {
+            stream,
+            next_id: 1,
+            io_timeout,
+        }
+    }
+
+    /// 发送一个请求，返回消息ID
+    async fn send(&mut self, op: Vec<u8>) -> io::Result<u32> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let message = tlv(TAG_SEQUENCE, &[integer(TAG_INTEGER, id), op].concat());
+        timeout(self.io_timeout, self.stream.write_all(&message))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "发送请求超时"))??;
+        Ok(id)
+    }
+
+    /// 读取下一个属于指定请求的应答
+    ///
+    /// # 返回
+    /// * `(操作标签, 操作内容)`
+    async fn receive(&mut self, id: u32) -> io::Result<(u8, Vec<u8>)> {
+        loop {
+            let message = timeout(self.io_timeout, read_message(&mut self.stream))
+                .await
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "等待应答超时"))??;
+            let (_, body, _) = parse_tlv(&message)?;
+            let (_, message_id, rest) = parse_tlv(body)?;
+            let (tag, op, _) = parse_tlv(rest)?;
+            let message_id = parse_integer(message_id);
+            if message_id == id {
+                return Ok((tag, op.to_vec()));
+            }
+            if message_id == 0 {
+                // 服务端主动断开的通知（Notice of Disconnection）
+                let reason = parse_result(op).map(|r| r.describe()).unwrap_or_default();
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    format!("服务端断开连接: {}", reason),
+                ));
+            }
+        }
+    }
+
+    /// 简单绑定（用户名和密码为空即匿名绑定）
+    async fn bind(&mut self, name: &str, password: &str) -> io::Result<LdapResult> {
+        let op = tlv(
+            TAG_BIND_REQUEST,
+            &[
+                integer(TAG_INTEGER, 3),
+                octets(name),
+                tlv(TAG_SIMPLE_AUTH, password.as_bytes()),
+            ]
+            .concat(),
+        );
+        let id = self.send(op).await?;
+        match self.receive(id).await? {
+            (TAG_BIND_RESPONSE, op) => parse_result(&op),
+            _ => Err(invalid("绑定应答类型不匹配")),
+        }
+    }
+
+    /// 按 `(objectClass=*)` 查询
+    ///
+    /// # 参数
+    /// * `base` - 查询的根DN（空字符串为rootDSE）
+    /// * `scope` - 0：仅根对象，1：下一级，2：整个子树
+    /// * `size_limit` - 条目数上限（0为不限制）
+    /// * `attributes` - 请求的属性
+    async fn search(
+        &mut self,
+        base: &str,
+        scope: u32,
+        size_limit: u32,
+        attributes: &[&str],
+    ) -> io::Result<(Vec<LdapEntry>, LdapResult)> {
+        let attributes: Vec<u8> = attributes.iter().flat_map(|a| octets(a)).collect();
+        let op = tlv(
+            TAG_SEARCH_REQUEST,
+            &[
+                octets(base),
+                integer(TAG_ENUMERATED, scope),
+                integer(TAG_ENUMERATED, 0),
+                integer(TAG_INTEGER, size_limit),
+                integer(TAG_INTEGER, self.io_timeout.as_secs() as u32),
+                tlv(TAG_BOOLEAN, &[0]),
+                tlv(TAG_FILTER_PRESENT, b"objectClass"),
+                tlv(TAG_SEQUENCE, &attributes),
+            ]
+            .concat(),
+        );
+        let id = self.send(op).await?;
+        let mut entries = Vec::new();
+        for _ in 0..MAX_SEARCH_MESSAGES {
+            match self.receive(id).await? {
+                (TAG_SEARCH_ENTRY, op) => entries.push(parse_entry(&op)?),
+                (TAG_SEARCH_DONE, op) => return Ok((entries, parse_result(&op)?)),
+                // 引用（SearchResultReference）等其他应答忽略
+                _ => {}
+            }
+        }
+        Err(invalid("查询应答过多"))
+    }
+
+    /// 结束会话（不等待应答）
+    async fn unbind(&mut self) {
+        let _ = self.send(tlv(TAG_UNBIND_REQUEST, &[])).await;
+    }
+}
+
+/// 读取一条完整的LDAP消息（含外层SEQUENCE）
+async fn read_message<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<Vec<u8>> {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).await?;
+    if header[0] != TAG_SEQUENCE {
+        return Err(invalid("不是LDAP消息"));
+    }
+    let mut message = header.to_vec();
+    let len = if header[1] < 0x80 {
+        header[1] as usize
+    } else {
+        let n = (header[1] & 0x7f) as usize;
+        if n == 0 || n > 4 {
+            return Err(invalid("不支持的BER长度"));
+        }
+        let mut bytes = vec![0u8; n];
+        stream.read_exact(&mut bytes).await?;
+        message.extend_from_slice(&bytes);
+        bytes.iter().fold(0usize, |acc, &b| acc << 8 | b as usize)
+    };
+    if len > MAX_MESSAGE_BYTES {
+        return Err(invalid("LDAP消息过大"));
+    }
+    let start = message.len();
+    message.resize(start + len, 0);
+    stream.read_exact(&mut message[start..]).await?;
+    Ok(message)
+}
+
+/// 功能级别编号对应的Windows版本
+///
+/// # 参数
+/// * `value` - rootDSE中的 `domainFunctionality` 等属性值
+pub fn functional_level_name(value: &str) -> String {
+    let name = match value.trim() {
+        "" => return String::new(),
+        "0" => "Windows 2000",
+        "1" => "Windows Server 2003 混合模式",
+        "2" => "Windows Server 2003",
+        "3" => "Windows Server 2008",
+        "4" => "Windows Server 2008 R2",
+        "5" => "Windows Server 2012",
+        "6" => "Windows Server 2012 R2",
+        "7" => "Windows Server 2016",
+        "10" => "Windows Server 2025",
+        other => return format!("未知级别 {}", other),
+    };
+    format!("{}（{}）", name, value.trim())
+}
+
+/// 把命名上下文换算为域名（`DC=corp,DC=local` → `corp.local`）
+pub fn naming_context_to_domain(dn: &str) -> String {
+    dn.split(',')
+        .filter_map(|rdn| {
+            let (key, value) = rdn.trim().split_once('=')?;
+            key.trim()
+                .eq_ignore_ascii_case("dc")
+                .then(|| value.trim().to_ascii_lowercase())
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// 在已建立的连接上检查LDAP服务：读取rootDSE、匿名绑定并尝试读取目录、检查签名要求
+///
+/// 不使用任何真实凭据；签名检查使用不存在的账号做一次简单绑定，
+/// 服务端在校验凭据之前以 strongerAuthRequired 拒绝即视为强制签名。
+///
+/// # 参数
+/// * `stream` - 已建立的连接
+/// * `dc` - 检查结果（输出）
+/// * `domain` - 已知的域名（用于签名检查的账号名）
+/// * `sample` - 匿名读取目录时最多取回的条目数
+/// * `signing_check` - 是否检查签名要求
+/// * `io_timeout` - 每个请求的超时
+pub async fn audit_stream<S>(
+    stream: S,
+    dc: &mut DomainController,
+    domain: Option<&str>,
+    sample: u32,
+    signing_check: bool,
+    io_timeout: Duration,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut client = LdapClient::new(stream, io_timeout);
+    if let Err(e) = audit_ldap(&mut client, dc, domain, sample, signing_check).await {
+        dc.error = Some(e.to_string());
+    }
+    client.unbind().await;
+}
+
+async fn audit_ldap<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut LdapClient<S>,
+    dc: &mut DomainController,
+    domain: Option<&str>,
+    sample: u32,
+    signing_check: bool,
+) -> io::Result<()> {
+    let (entries, result) = client.search("", 0, 1, ROOT_DSE_ATTRIBUTES).await?;
+    let Some(root) = entries.first() else {
+        return Err(io::Error::other(format!(
+            "无法读取rootDSE: {}",
+            result.describe()
+        )));
+    };
+    dc.dns_host_name = root.first("dnsHostName");
+    dc.naming_contexts = root.values("namingContexts");
+    dc.default_naming_context = root.first("defaultNamingContext");
+    if dc.default_naming_context.is_empty() {
+        // 非AD的目录服务没有默认命名上下文，取第一个命名上下文
+        dc.default_naming_context = dc.naming_contexts.first().cloned().unwrap_or_default();
+    }
+    dc.sasl_mechanisms = root.values("supportedSASLMechanisms");
+    dc.domain_level = functional_level_name(&root.first("domainFunctionality"));
+    dc.forest_level = functional_level_name(&root.first("forestFunctionality"));
+    dc.dc_level = functional_level_name(&root.first("domainControllerFunctionality"));
+
+    let bind = client.bind("", "").await?;
+    dc.anonymous_bind = Some(bind.code == 0);
+    if bind.code == 0 && !dc.default_naming_context.is_empty() {
+        let base = dc.default_naming_context.clone();
+        let (entries, result) = client.search(&base, 1, sample, &["1.1"]).await?;
+        dc.anonymous_entries = entries.len();
+        dc.anonymous_sample = entries.into_iter().map(|e| e.dn).collect();
+        dc.anonymous_result = result.describe();
+    } else {
+        dc.anonymous_result = bind.describe();
+    }
+
+    if signing_check {
+        let domain = domain
+            .map(str::to_string)
+            .unwrap_or_else(|| naming_context_to_domain(&dc.default_naming_context));
+        let user = if domain.is_empty() {
+            SIGNING_PROBE_USER.to_string()
+        } else {
+            format!("{}@{}", SIGNING_PROBE_USER, domain)
+        };
+        let result = client.bind(&user, SIGNING_PROBE_PASSWORD).await?;
+        dc.signing = Some(match result.code {
+            8 => SigningStatus::Required,
+            49 => SigningStatus::NotRequired,
+            _ => SigningStatus::Unknown,
+        });
+        dc.signing_evidence = result.describe();
+    }
+    Ok(())
+}
+
+/// 连接并检查单个LDAP服务，连接失败的原因记录在结果中
+///
+/// # 参数
+/// * `ip` / `port` - LDAP服务地址
+/// * `srv_host` - SRV记录中的主机名
+/// * `domain` - 已知的域名（用于签名检查的账号名）
+/// * `sample` - 匿名读取目录时最多取回的条目数
+/// * `signing_check` - 是否检查签名要求
+/// * `io_timeout` - 连接及每个请求的超时
+pub async fn check_controller(
+    ip: &str,
+    port: u16,
+    srv_host: Option<String>,
+    domain: Option<&str>,
+    sample: u32,
+    signing_check: bool,
+    io_timeout: Duration,
+) -> DomainController {
+    let mut dc = DomainController::new(ip, port, srv_host);
+    match timeout(io_timeout, TcpStream::connect((ip, port))).await {
+        Ok(Ok(stream)) => {
+            let audit = audit_stream(stream, &mut dc, domain, sample, signing_check, io_timeout);
+            if timeout(io_timeout * 8, audit).await.is_err() {
+                dc.error = Some("检查超时".to_string());
+            }
+        }
+        Ok(Err(e)) => dc.error = Some(format!("连接失败: {}", e)),
+        Err(_) => dc.error = Some("连接超时".to_string()),
+    }
+    dc
+}
+
+/// 通过SRV记录定位域控制器
+///
+/// # 返回
+/// * `Vec<(IP, 端口, 主机名)>` - 按SRV优先级排列
+async fn discover_controllers(
+    domain: &str,
+) -> Result<Vec<(String, u16, String)>, Box<dyn Error + Send + Sync>> {
+    let name = format!("_ldap._tcp.dc._msdcs.{}", domain.trim_end_matches('.'));
+    let records = resolver()
+        .lookup_srv(&name)
+        .await
+        .map_err(|e| format!("查询 {} 失败: {}", name, e))?;
+    let mut controllers = Vec::new();
+    for record in records {
+        match resolver().lookup_ip(&record.target).await {
+            Ok(ips) => {
+                for ip in ips {
+                    controllers.push((ip.to_string(), record.port, record.target.clone()));
+                }
+            }
+            Err(e) => eprintln!("{} 无法解析域控制器 {}: {}", Icon::Warn, record.target, e),
+        }
+    }
+    Ok(controllers)
+}
+
+/// 按命名上下文汇总域信息
+///
+/// # 参数
+/// * `controllers` - 检查结果
+pub fn summarize_domains(controllers: &[DomainController]) -> Vec<DomainSummary> {
+    let mut domains: BTreeMap<String, DomainSummary> = BTreeMap::new();
+    for dc in controllers
+        .iter()
+        .filter(|dc| !dc.default_naming_context.is_empty())
+    {
+        let summary = domains
+            .entry(dc.default_naming_context.to_ascii_lowercase())
+            .or_insert_with(|| DomainSummary {
+                domain: naming_context_to_domain(&dc.default_naming_context),
+                naming_context: dc.default_naming_context.clone(),
+                domain_level: String::new(),
+                forest_level: String::new(),
+                controllers: Vec::new(),
+            });
+        if summary.domain_level.is_empty() {
+            summary.domain_level = dc.domain_level.clone();
+        }
+        if summary.forest_level.is_empty() {
+            summary.forest_level = dc.forest_level.clone();
+        }
+        let name = match dc.host_name() {
+            "" => dc.ip.clone(),
+            host => host.to_string(),
+        };
+        if !summary.controllers.contains(&name) {
+            summary.controllers.push(name);
+        }
+    }
+    domains.into_values().collect()
+}
+
+/// 把检查结果映射为标准发现
+///
+/// # 参数
+/// * `controllers` - 检查结果
+pub fn adinfo_findings(controllers: &[DomainController]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for dc in controllers.iter().filter(|dc| dc.error.is_none()) {
+        let finding =
+            |check_id: &str, title: &str, severity, evidence: String, fix: &str| Finding {
+                module: "pentest adinfo".to_string(),
+                asset: dc.ip.clone(),
+                port: Some(dc.port),
+                protocol: "tcp".to_string(),
+                check_id: check_id.to_string(),
+                title: title.to_string(),
+                severity,
+                evidence,
+                remediation: fix.to_string(),
+                params: FindingParams::new(),
+                sources: Vec::new(),
+                observed: dc.observed.clone(),
+            };
+
+        let mut info = vec![format!(
+            "defaultNamingContext={}",
+            dc.default_naming_context
+        )];
+        if !dc.host_name().is_empty() {
+            info.insert(0, format!("dnsHostName={}", dc.host_name()));
+        }
+        if !dc.domain_level.is_empty() {
+            info.push(format!("域功能级别={}", dc.domain_level));
+        }
+        findings.push(finding(
+            "ldap-rootdse",
+            "LDAP rootDSE 可匿名读取",
+            Severity::Info,
+            info.join("; "),
+            "rootDSE按协议对匿名开放，确认LDAP服务只对需要的网段开放",
+        ));
+        if dc.anonymous_entries > 0 {
+            findings.push(finding(
+                "ldap-anonymous-read",
+                "LDAP 目录可匿名读取",
+                Severity::High,
+                format!(
+                    "匿名查询 {} 返回 {} 个条目: {}",
+                    dc.default_naming_context,
+                    dc.anonymous_entries,
+                    dc.anonymous_sample.join("; ")
+                ),
+                "关闭匿名访问（AD中检查 dsHeuristics 第7位及 ANONYMOUS LOGON 的目录权限）",
+            ));
+        }
+        if dc.signing == Some(SigningStatus::NotRequired) {
+            findings.push(finding(
+                "ldap-signing-not-required",
+                "LDAP 未强制签名（接受明文简单绑定）",
+                Severity::Medium,
+                format!("简单绑定应答: {}", dc.signing_evidence),
+                "将“域控制器: LDAP 服务器签名要求”设为“要求签名”，并启用LDAP通道绑定",
+            ));
+        }
+    }
+    findings
+}
+
+pub async fn run(args: &AdInfoArgs) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    run_with(args, &ScanContext::cli()).await
+}
+
+/// 定位域控制器并检查LDAP的匿名访问及签名要求（不使用凭据）
+///
+/// # 参数
+/// * `args` - 检查参数
+/// * `ctx` - 扫描上下文
+///
+/// # 返回
+/// * `Ok(RunSummary)` - 检查完成后的结果摘要
+pub async fn run_with(
+    args: &AdInfoArgs,
+    ctx: &ScanContext,
+) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    let start = Instant::now();
+    let started_at = chrono::Local::now();
+
+    // (IP, 端口, SRV主机名)，同一IP只检查一次，SRV记录优先
+    let mut tasks: Vec<(String, u16, Option<String>)> = Vec::new();
+    if let Some(ref domain) = args.domain {
+        println!("🔎 通过SRV记录定位 {} 的域控制器", domain);
+        let discovered = match discover_controllers(domain).await {
+            Ok(found) => found,
+            Err(e) if args.targets.is_some() || args.sources.has_files() => {
+                eprintln!("{} {}", Icon::Warn, e);
+                Vec::new()
+            }
+            Err(e) => return Err(e),
+        };
+        let ips: Vec<String> = discovered.iter().map(|(ip, _, _)| ip.clone()).collect();
+        let excluded = scope::enforce_ips(&ips)?;
+        for (ip, port, host) in discovered {
+            if !excluded.contains(&ip) && !tasks.iter().any(|(t, _, _)| *t == ip) {
+                println!("   {} {} → {}:{}", Icon::List, host, ip, port);
+                tasks.push((ip, port, Some(host)));
+            }
+        }
+    }
+    if args.targets.is_some() || args.sources.has_files() {
+        let targets = collect_targets(args.targets.as_deref(), &args.sources).await?;
+        for ip in targets.ips() {
+            if !tasks.iter().any(|(t, _, _)| *t == ip) {
+                tasks.push((ip, args.port, None));
+            }
+        }
+    }
+    if tasks.is_empty() {
+        return Err("没有找到可检查的域控制器".into());
+    }
+
+    let total = tasks.len();
+    let concurrency = effective_concurrency(args.concurrency, total, ScanKind::Connect);
+    println!(
+        "{} 配置: 并发={}（{}）, 超时={}秒, 签名检查={}",
+        Icon::Config,
+        concurrency.value,
+        concurrency.reason,
+        args.timeout,
+        if args.no_signing_check {
+            "关闭"
+        } else {
+            "开启"
+        }
+    );
+    println!("🏢 开始检查 {} 个LDAP服务", total);
+
+    let domain = args.domain.as_deref();
+    let io_timeout = Duration::from_secs(args.timeout.max(1));
+    let progress = ctx.new_progress(total as u64);
+    let listener = ctx.interactive.then(|| ctx.pause.listen(&progress, true));
+    let mut controllers: Vec<DomainController> = Vec::new();
+    run_tracked(
+        tasks,
+        concurrency.value,
+        &progress,
+        ctx.token(),
+        |(ip, port, host)| async move {
+            ctx.pause.wait().await;
+            let signing_check = !args.no_signing_check;
+            check_controller(
+                &ip,
+                port,
+                host,
+                domain,
+                args.sample,
+                signing_check,
+                io_timeout,
+            )
+            .await
+        },
+        |dc| {
+            let status = match dc.error {
+                Some(ref e) => e.clone(),
+                None => format!(
+                    "{} | 匿名条目={} | 签名={}",
+                    dc.default_naming_context,
+                    dc.anonymous_entries,
+                    dc.signing.map_or("未检查", SigningStatus::label)
+                ),
+            };
+            progress.println(format!("  🏢 {}:{} {}", dc.ip, dc.port, status));
+            ctx.emit(&dc);
+            let outcome = dc.outcome();
+            controllers.push(dc);
+            outcome
+        },
+    )
+    .await;
+    drop(listener);
+    progress.finish_with_message("✅ 域信息收集完成");
+    controllers.sort_by(|a, b| (&a.ip, a.port).cmp(&(&b.ip, b.port)));
+
+    let domains = summarize_domains(&controllers);
+    let findings = consolidate(adinfo_findings(&controllers));
+    let issues: Vec<&Finding> = findings
+        .iter()
+        .filter(|f| f.severity > Severity::Info)
+        .collect();
+
+    for d in &domains {
+        println!("\n🏢 域 {}（{}）", d.domain, d.naming_context);
+        println!("   域功能级别: {}", d.domain_level);
+        println!("   林功能级别: {}", d.forest_level);
+        println!("   域控制器: {}", d.controllers.join(", "));
+    }
+
+    let mut outputs = Vec::new();
+    if args.output {
+        outputs.push(run_stage(export_controllers(&controllers, ctx)).await?);
+    }
+    let first_seen = started_at.to_rfc3339();
+    for &format in &args.format {
+        let run_dir = ctx.run_dir().map(|run| run.as_ref());
+        let path = write_findings(&findings, format, &first_seen, OutputKind::ADINFO, run_dir)?;
+        println!("{} 结果已保存至: {}", Icon::Ok, path.display());
+        outputs.push(path.display().to_string());
+    }
+
+    let counts = progress.snapshot();
+    debug_assert_eq!(counts.completed as usize, controllers.len());
+    let count = |status: SigningStatus| {
+        controllers
+            .iter()
+            .filter(|dc| dc.signing == Some(status))
+            .count()
+    };
+    let mut summary: Vec<SummaryItem> = counts.summary_items("个LDAP服务");
+    summary.extend([
+        ("域".to_string(), format!("{} 个", domains.len())),
+        (
+            "可匿名读取".to_string(),
+            format!(
+                "{} 个",
+                controllers
+                    .iter()
+                    .filter(|dc| dc.anonymous_entries > 0)
+                    .count()
+            ),
+        ),
+        (
+            "未强制签名".to_string(),
+            format!("{} 个", count(SigningStatus::NotRequired)),
+        ),
+        (
+            "耗时".to_string(),
+            format_elapsed(start.elapsed(), ctx.pause.paused_duration()),
+        ),
+    ]);
+    println!("\n📊 检查统计:");
+    for (name, value) in &summary {
+        println!("   {}: {}", name, value);
+    }
+    if !issues.is_empty() {
+        println!("\n{} 发现的问题:", Icon::Warn);
+        for f in &issues {
+            println!(
+                "   [{}] {}:{} {}",
+                f.severity.label(),
+                f.asset,
+                f.port.unwrap_or_default(),
+                f.title
+            );
+        }
+    }
+
+    if let Some(run_dir) = ctx.run_dir() {
+        let rows: Vec<_> = findings.iter().map(|f| f.to_vm(&first_seen)).collect();
+        run_dir.write_json(HOSTS_FILE_NAME, "json", &controllers, controllers.len())?;
+        run_dir.write_json(FINDINGS_FILE_NAME, "json", &rows, rows.len())?;
+        run_dir.write_summary(&summary)?;
+    }
+
+    Ok(RunSummary {
+        total: counts.completed as usize,
+        succeeded: counts.succeeded as usize,
+        outputs,
+        concurrency: Some(concurrency),
+        timing: None,
+    })
+}
+
+/// 导出检查结果到Excel
+fn export_controllers(controllers: &[DomainController], ctx: &ScanContext) -> ExcelExport {
+    let headers = [
+        "IP地址",
+        "端口",
+        "主机名",
+        "域",
+        "默认命名上下文",
+        "域功能级别",
+        "林功能级别",
+        "DC功能级别",
+        "SASL机制",
+        "匿名绑定",
+        "匿名条目",
+        "匿名查询结果",
+        "LDAP签名",
+        "签名检查应答",
+        "错误",
+        Provenance::HEADERS[0],
+        Provenance::HEADERS[1],
+    ];
+    ExcelExport::prepare(
+        controllers,
+        &headers,
+        |dc| {
+            vec![
+                dc.ip.clone(),
+                dc.port.to_string(),
+                dc.host_name().to_string(),
+                naming_context_to_domain(&dc.default_naming_context),
+                dc.default_naming_context.clone(),
+                dc.domain_level.clone(),
+                dc.forest_level.clone(),
+                dc.dc_level.clone(),
+                dc.sasl_mechanisms.join(", "),
+                match dc.anonymous_bind {
+                    Some(true) => "成功",
+                    Some(false) => "失败",
+                    None => "",
+                }
+                .to_string(),
+                dc.anonymous_sample.join("; "),
+                dc.anonymous_result.clone(),
+                dc.signing
+                    .map(SigningStatus::label)
+                    .unwrap_or_default()
+                    .to_string(),
+                dc.signing_evidence.clone(),
+                dc.error.clone().unwrap_or_default(),
+                dc.observed.observed_at.clone(),
+                dc.observed.probe.clone(),
+            ]
+        },
+        OutputKind::ADINFO,
+        &ctx.excel_options(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::DuplexStream;
+
+    fn result_op(tag: u8, code: u32, diagnostic: &str) -> Vec<u8> {
+        tlv(
+            tag,
+            &[
+                integer(TAG_ENUMERATED, code),
+                octets(""),
+                octets(diagnostic),
+            ]
+            .concat(),
+        )
+    }
+
+    fn entry_op(dn: &str, attributes: &[(&str, &[&str])]) -> Vec<u8> {
+        let list: Vec<u8> = attributes
+            .iter()
+            .flat_map(|(name, values)| {
+                let set: Vec<u8> = values.iter().flat_map(|v| octets(v)).collect();
+                tlv(TAG_SEQUENCE, &[octets(name), tlv(0x31, &set)].concat())
+            })
+            .collect();
+        tlv(
+            TAG_SEARCH_ENTRY,
+            &[octets(dn), tlv(TAG_SEQUENCE, &list)].concat(),
+        )
+    }
+
+    type Script = fn(u8, &str) -> Vec<Vec<u8>>;
+    type Received = Arc<Mutex<Vec<(u8, String)>>>;
+
+    /// 按请求类型应答的模拟LDAP服务端，记录收到的请求（操作标签, 查询根DN或绑定名）
+    fn scripted(script: Script) -> (DuplexStream, Received) {
+        let (client, mut server) = tokio::io::duplex(64 * 1024);
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        tokio::spawn(async move {
+            while let Ok(message) = read_message(&mut server).await {
+                let (_, body, _) = parse_tlv(&message).unwrap();
+                let (_, id, rest) = parse_tlv(body).unwrap();
+                let (tag, op, _) = parse_tlv(rest).unwrap();
+                let target = match tag {
+                    TAG_SEARCH_REQUEST => parse_string(parse_tlv(op).unwrap().1),
+                    TAG_BIND_REQUEST => {
+                        let (_, _, rest) = parse_tlv(op).unwrap();
+                        parse_string(parse_tlv(rest).unwrap().1)
+                    }
+                    _ => String::new(),
+                };
+                log.lock().unwrap().push((tag, target.clone()));
+                for reply in script(tag, &target) {
+                    let message = tlv(
+                        TAG_SEQUENCE,
+                        &[integer(TAG_INTEGER, parse_integer(id)), reply].concat(),
+                    );
+                    if server.write_all(&message).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        (client, received)
+    }
+
+    fn root_dse() -> Vec<u8> {
+        entry_op(
+            "",
+            &[
+                ("dnsHostName", &["DC01.corp.local"]),
+                ("defaultNamingContext", &["DC=corp,DC=local"]),
+                (
+                    "namingContexts",
+                    &["DC=corp,DC=local", "CN=Configuration,DC=corp,DC=local"],
+                ),
+                (
+                    "supportedSASLMechanisms",
+                    &["GSSAPI", "GSS-SPNEGO", "EXTERNAL"],
+                ),
+                ("domainFunctionality", &["7"]),
+                ("forestFunctionality", &["7"]),
+                ("domainControllerFunctionality", &["10"]),
+            ],
+        )
+    }
+
+    async fn audit(stream: DuplexStream, signing_check: bool) -> DomainController {
+        let mut dc = DomainController::new("10.0.0.10", 389, Some("dc01.corp.local".into()));
+        audit_stream(
+            stream,
+            &mut dc,
+            None,
+            5,
+            signing_check,
+            Duration::from_secs(1),
+        )
+        .await;
+        dc
+    }
+
+    #[tokio::test]
+    async fn test_anonymous_readable_directory_without_signing() {
+        let (stream, received) = scripted(|tag, target| match (tag, target) {
+            (TAG_SEARCH_REQUEST, "") => vec![root_dse(), result_op(TAG_SEARCH_DONE, 0, "")],
+            (TAG_SEARCH_REQUEST, _) => vec![
+                entry_op("CN=Users,DC=corp,DC=local", &[]),
+                entry_op("CN=Computers,DC=corp,DC=local", &[]),
+                result_op(TAG_SEARCH_DONE, 0, ""),
+            ],
+            (TAG_BIND_REQUEST, "") => vec![result_op(TAG_BIND_RESPONSE, 0, "")],
+            (TAG_BIND_REQUEST, _) => vec![result_op(
+                TAG_BIND_RESPONSE,
+                49,
+                "80090308: LdapErr: DSID-0C090569, comment: AcceptSecurityContext error, data 52e, v4563\0",
+            )],
+            _ => Vec::new(),
+        });
+        let dc = audit(stream, true).await;
+
+        assert_eq!(dc.error, None);
+        assert_eq!(dc.dns_host_name, "DC01.corp.local");
+        assert_eq!(dc.default_naming_context, "DC=corp,DC=local");
+        assert_eq!(dc.naming_contexts.len(), 2);
+        assert_eq!(dc.sasl_mechanisms, ["GSSAPI", "GSS-SPNEGO", "EXTERNAL"]);
+        assert_eq!(dc.domain_level, "Windows Server 2016（7）");
+        assert_eq!(dc.dc_level, "Windows Server 2025（10）");
+        assert_eq!(dc.anonymous_bind, Some(true));
+        assert_eq!(dc.anonymous_entries, 2);
+        assert_eq!(dc.anonymous_sample[0], "CN=Users,DC=corp,DC=local");
+        assert_eq!(dc.signing, Some(SigningStatus::NotRequired));
+        assert!(dc.signing_evidence.starts_with("49 80090308"));
+        assert!(!dc.signing_evidence.ends_with('\0'));
+
+        let ids: Vec<(String, Severity)> = adinfo_findings(std::slice::from_ref(&dc))
+            .into_iter()
+            .map(|f| (f.check_id, f.severity))
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                ("ldap-rootdse".to_string(), Severity::Info),
+                ("ldap-anonymous-read".to_string(), Severity::High),
+                ("ldap-signing-not-required".to_string(), Severity::Medium),
+            ]
+        );
+
+        // 签名检查的账号名取自命名上下文，会话以解绑结束
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let received = received.lock().unwrap().clone();
+        assert_eq!(
+            received,
+            [
+                (TAG_SEARCH_REQUEST, String::new()),
+                (TAG_BIND_REQUEST, String::new()),
+                (TAG_SEARCH_REQUEST, "DC=corp,DC=local".to_string()),
+                (
+                    TAG_BIND_REQUEST,
+                    "gxtools-signing-probe@corp.local".to_string()
+                ),
+                (TAG_UNBIND_REQUEST, String::new()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hardened_controller_has_no_issues() {
+        let (stream, _) = scripted(|tag, target| match (tag, target) {
+            (TAG_SEARCH_REQUEST, "") => vec![root_dse(), result_op(TAG_SEARCH_DONE, 0, "")],
+            (TAG_SEARCH_REQUEST, _) => vec![result_op(
+                TAG_SEARCH_DONE,
+                1,
+                "000004DC: LdapErr: DSID-0C090A5C, comment: In order to perform this operation a successful bind must be completed on the connection., data 0, v4563",
+            )],
+            (TAG_BIND_REQUEST, "") => vec![result_op(TAG_BIND_RESPONSE, 0, "")],
+            (TAG_BIND_REQUEST, _) => vec![result_op(
+                TAG_BIND_RESPONSE,
+                8,
+                "00002028: LdapErr: DSID-0C09026B, comment: The server requires binds to turn on integrity checking if SSL\\TLS are not already active on the connection, data 0, v4563",
+            )],
+            _ => Vec::new(),
+        });
+        let dc = audit(stream, true).await;
+        assert_eq!(dc.anonymous_bind, Some(true));
+        assert_eq!(dc.anonymous_entries, 0);
+        assert!(dc.anonymous_result.starts_with("1 000004DC"));
+        assert_eq!(dc.signing, Some(SigningStatus::Required));
+        assert_eq!(adinfo_findings(&[dc]).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rootdse_refused_is_reported() {
+        let (stream, received) = scripted(|tag, _| match tag {
+            TAG_SEARCH_REQUEST => vec![result_op(TAG_SEARCH_DONE, 50, "insufficient access")],
+            _ => Vec::new(),
+        });
+        let dc = audit(stream, false).await;
+        assert_eq!(
+            dc.error.as_deref(),
+            Some("无法读取rootDSE: 50 insufficient access")
+        );
+        assert!(adinfo_findings(&[dc]).is_empty());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(received.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_ber_lengths_and_integers() {
+        assert_eq!(integer(TAG_INTEGER, 0), [0x02, 0x01, 0x00]);
+        assert_eq!(integer(TAG_INTEGER, 128), [0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(integer(TAG_INTEGER, 65_536), [0x02, 0x03, 0x01, 0x00, 0x00]);
+
+        for len in [0usize, 127, 128, 255, 256, 70_000] {
+            let content = vec![0x41; len];
+            let encoded = tlv(TAG_OCTET_STRING, &content);
+            let (tag, parsed, rest) = parse_tlv(&encoded).unwrap();
+            assert_eq!((tag, parsed.len(), rest.len()), (TAG_OCTET_STRING, len, 0));
+        }
+        assert!(parse_tlv(&[0x04, 0x05, 0x41]).is_err());
+    }
+
+    #[test]
+    fn test_domain_helpers() {
+        assert_eq!(naming_context_to_domain("DC=Corp, dc=Local"), "corp.local");
+        assert_eq!(naming_context_to_domain("o=example"), "");
+        assert_eq!(functional_level_name("3"), "Windows Server 2008（3）");
+        assert_eq!(functional_level_name("42"), "未知级别 42");
+
+        let mut a = DomainController::new("10.0.0.10", 389, Some("dc01.corp.local".into()));
+        a.default_naming_context = "DC=corp,DC=local".into();
+        a.domain_level = functional_level_name("7");
+        let mut b = DomainController::new("10.0.0.11", 389, None);
+        b.default_naming_context = "dc=CORP,dc=local".into();
+        b.dns_host_name = "dc02.corp.local".into();
+        let failed = DomainController::new("10.0.0.12", 389, None);
+
+        let domains = summarize_domains(&[a, b, failed]);
+        assert_eq!(domains.len(), 1);
+        assert_eq!(domains[0].domain, "corp.local");
+        assert_eq!(domains[0].domain_level, "Windows Server 2016（7）");
+        assert_eq!(
+            domains[0].controllers,
+            ["dc01.corp.local", "dc02.corp.local"]
+        );
+    }
+}
@@ -0,0 +1,546 @@
+use crate::commands::history::RunSummary;
+use crate::commands::pentest::portscan::{
+    PortProbeOptions, PortScanResult, TcpConnector, export_results, host_records, scan_ports_with,
+};
+use crate::commands::pentest::probes::ProbeRegistry;
+use crate::commands::resources;
+use crate::utils::blocking::run_stage;
+use crate::utils::console::Icon;
+use crate::utils::context::ScanContext;
+use crate::utils::format_elapsed;
+use crate::utils::limits::{
+    ConcurrencySpec, ScanKind, effective_concurrency, parse_fixed_concurrency,
+};
+use crate::utils::metrics::ScanMetrics;
+use crate::utils::output::OutputKind;
+use crate::utils::run_dir::{HOSTS_FILE_NAME, PORTS_FILE_NAME, SummaryItem};
+use crate::utils::scope;
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 结果补充探测参数配置
+#[derive(Parser, Debug)]
+pub struct EnrichArgs {
+    /// 已有的扫描结果（masscan JSON 或 nmap XML，按文件内容自动识别）
+    #[arg(short, long, value_name = "FILE")]
+    pub input: PathBuf,
+
+    /// 要执行的探测（多个用逗号隔开）
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "banner",
+        value_name = "PROBES"
+    )]
+    pub probes: Vec<EnrichProbe>,
+
+    /// 最大并发数（auto 表示根据文件描述符上限和任务数量自动选择）
+    #[arg(
+        short = 'c',
+        long,
+        env = "GXTOOLS_CONCURRENCY",
+        default_value = "200",
+        value_name = "NUM|auto",
+        value_parser = parse_fixed_concurrency
+    )]
+    pub concurrency: ConcurrencySpec,
+
+    /// 连接及读取超时时间（秒）
+    #[arg(
+        short = 'T',
+        long,
+        env = "GXTOOLS_TIMEOUT",
+        default_value = "3",
+        value_name = "SECS"
+    )]
+    pub timeout: u64,
+
+    /// 是否输出结果到Excel文件
+    #[arg(short = 'o', long, env = "GXTOOLS_OUTPUT")]
+    pub output: bool,
+}
+
+/// 补充探测类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EnrichProbe {
+    /// 读取banner并识别服务（SSH、MySQL、RDP、HTTP等）
+    Banner,
+}
+
+/// 导入结果的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// masscan -oJ
+    Masscan,
+    /// nmap -oX（也包括本工具 `--format nmap-xml` 的输出）
+    NmapXml,
+}
+
+impl ImportFormat {
+    /// 按文件内容识别格式
+    pub fn detect(content: &str) -> Option<Self> {
+        match content
+            .trim_start_matches('\u{feff}')
+            .trim_start()
+            .chars()
+            .next()?
+        {
+            '<' => Some(Self::NmapXml),
+            '[' | '{' => Some(Self::Masscan),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Masscan => "masscan JSON",
+            Self::NmapXml => "nmap XML",
+        }
+    }
+}
+
+/// 导入结果
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImportReport {
+    /// 开放的 (IP, 端口)，已排序去重
+    pub ports: Vec<(String, u16)>,
+    /// 因格式错误被跳过的记录数
+    pub skipped: usize,
+    /// 非开放或非TCP而忽略的端口数
+    pub ignored: usize,
+}
+
+impl ImportReport {
+    fn from_set(ports: BTreeSet<(IpAddr, u16)>, skipped: usize, ignored: usize) -> Self {
+        Self {
+            ports: ports
+                .into_iter()
+                .map(|(ip, port)| (ip.to_string(), port))
+                .collect(),
+            skipped,
+            ignored,
+        }
+    }
+
+    /// 涉及的主机数
+    pub fn host_count(&self) -> usize {
+        self.ports
+            .iter()
+            .map(|(ip, _)| ip)
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+}
+
+/// masscan 的一条记录
+#[derive(Debug, Deserialize)]
+struct MasscanRecord {
+    ip: IpAddr,
+    #[serde(default)]
+    ports: Vec<MasscanPort>,
+}
+
+/// masscan 记录中的端口
+#[derive(Debug, Deserialize)]
+struct MasscanPort {
+    port: u16,
+    #[serde(default = "default_proto")]
+    proto: String,
+    /// banner 记录没有 status 字段，出现即表示端口开放
+    #[serde(default = "default_status")]
+    status: String,
+}
+
+fn default_proto() -> String {
+    "tcp".to_string()
+}
+
+fn default_status() -> String {
+    "open".to_string()
+}
+
+/// 解析 masscan JSON
+///
+/// masscan 的输出每行一条记录，结尾常带多余的逗号或 `{finished: 1}` 之类的非标准行，
+/// 整体无法按JSON解析时逐行解析，无法解析的行计入跳过数。
+///
+/// # 参数
+/// * `content` - 文件内容
+pub fn parse_masscan(content: &str) -> ImportReport {
+    let values: Vec<serde_json::Value> = match serde_json::from_str(content) {
+        Ok(serde_json::Value::Array(values)) => values,
+        Ok(value) => vec![value],
+        Err(_) => {
+            let mut values = Vec::new();
+            let mut skipped = 0;
+            for line in content.lines() {
+                let line = line.trim().trim_end_matches(',');
+                if line.is_empty() || line == "[" || line == "]" {
+                    continue;
+                }
+                match serde_json::from_str(line) {
+                    Ok(value) => values.push(value),
+                    Err(_) => skipped += 1,
+                }
+            }
+            let mut report = collect_masscan(values);
+            report.skipped += skipped;
+            return report;
+        }
+    };
+    collect_masscan(values)
+}
+
+fn collect_masscan(values: Vec<serde_json::Value>) -> ImportReport {
+    let mut ports = BTreeSet::new();
+    let (mut skipped, mut ignored) = (0, 0);
+    for value in values {
+        let Ok(record) = serde_json::from_value::<MasscanRecord>(value) else {
+            skipped += 1;
+            continue;
+        };
+        for port in record.ports {
+            if port.proto.eq_ignore_ascii_case("tcp") && port.status == "open" {
+                ports.insert((record.ip, port.port));
+            } else {
+                ignored += 1;
+            }
+        }
+    }
+    ImportReport::from_set(ports, skipped, ignored)
+}
+
+/// 解析 nmap XML
+///
+/// 只取 TCP 开放端口；缺少地址的主机、端口号无效的端口计入跳过数。
+///
+/// # 参数
+/// * `content` - 文件内容
+///
+/// # 返回
+/// * `Err` - 文件不是有效的XML或根元素不是 `nmaprun`
+pub fn parse_nmap_xml(content: &str) -> Result<ImportReport, Box<dyn Error + Send + Sync>> {
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let doc = roxmltree::Document::parse_with_options(content, options)
+        .map_err(|e| format!("nmap XML 格式错误: {}", e))?;
+    let root = doc.root_element();
+    if !root.has_tag_name("nmaprun") {
+        return Err(format!("不是 nmap XML 结果（根元素为 {}）", root.tag_name().name()).into());
+    }
+
+    let mut ports = BTreeSet::new();
+    let (mut skipped, mut ignored) = (0, 0);
+    for host in root.children().filter(|n| n.has_tag_name("host")) {
+        let ip = host
+            .children()
+            .filter(|n| n.has_tag_name("address"))
+            .filter(|n| matches!(n.attribute("addrtype"), Some("ipv4" | "ipv6")))
+            .find_map(|n| n.attribute("addr")?.parse::<IpAddr>().ok());
+        let Some(ip) = ip else {
+            skipped += 1;
+            continue;
+        };
+        let port_nodes = host
+            .children()
+            .filter(|n| n.has_tag_name("ports"))
+            .flat_map(|n| n.children())
+            .filter(|n| n.has_tag_name("port"));
+        for node in port_nodes {
+            let Some(port) = node.attribute("portid").and_then(|p| p.parse::<u16>().ok()) else {
+                skipped += 1;
+                continue;
+            };
+            let open = node
+                .children()
+                .find(|n| n.has_tag_name("state"))
+                .and_then(|n| n.attribute("state"))
+                == Some("open");
+            if node.attribute("protocol") == Some("tcp") && open {
+                ports.insert((ip, port));
+            } else {
+                ignored += 1;
+            }
+        }
+    }
+    Ok(ImportReport::from_set(ports, skipped, ignored))
+}
+
+/// 读取并解析已有的扫描结果
+///
+/// # 参数
+/// * `path` - 文件路径
+pub fn import_results(
+    path: &Path,
+) -> Result<(ImportFormat, ImportReport), Box<dyn Error + Send + Sync>> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("无法读取 {}: {}", path.display(), e))?;
+    let format = ImportFormat::detect(&content).ok_or_else(|| {
+        format!(
+            "无法识别 {} 的格式（支持 masscan JSON 和 nmap XML）",
+            path.display()
+        )
+    })?;
+    let report = match format {
+        ImportFormat::Masscan => parse_masscan(&content),
+        ImportFormat::NmapXml => parse_nmap_xml(&content)?,
+    };
+    Ok((format, report))
+}
+
+pub async fn run(args: &EnrichArgs) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    run_with(args, &ScanContext::cli()).await
+}
+
+/// 对已有扫描结果中的开放端口执行补充探测（不重新扫描端口）
+///
+/// # 参数
+/// * `args` - 补充探测参数
+/// * `ctx` - 扫描上下文
+///
+/// # 返回
+/// * `Ok(RunSummary)` - 探测完成后的结果摘要
+pub async fn run_with(
+    args: &EnrichArgs,
+    ctx: &ScanContext,
+) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    let start = Instant::now();
+    let fps = resources::store()?.fingerprints().to_vec();
+
+    let (format, mut report) = import_results(&args.input)?;
+    println!(
+        "📥 已导入 {}: {} 个主机, {} 个开放端口",
+        format.name(),
+        report.host_count(),
+        report.ports.len()
+    );
+    if report.skipped > 0 {
+        println!("{} 跳过 {} 条无法解析的记录", Icon::Warn, report.skipped);
+    }
+    if report.ignored > 0 {
+        println!("   忽略 {} 个非开放或非TCP端口", report.ignored);
+    }
+    if report.ports.is_empty() {
+        return Err("导入结果中没有开放的TCP端口".into());
+    }
+    let hosts: Vec<String> = report
+        .ports
+        .iter()
+        .map(|(ip, _)| ip.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let excluded = scope::enforce_ips(&hosts)?;
+    report.ports.retain(|(ip, _)| !excluded.contains(ip));
+
+    let total = report.ports.len();
+    let concurrency = effective_concurrency(args.concurrency, total, ScanKind::Connect);
+    let probes: Vec<String> = args
+        .probes
+        .iter()
+        .map(|p| p.to_possible_value().unwrap().get_name().to_string())
+        .collect();
+    println!(
+        "⚙️  配置: 探测={}, 并发={}（{}）, 超时={}秒",
+        probes.join(","),
+        concurrency.value,
+        concurrency.reason,
+        args.timeout
+    );
+
+    let progress = ctx.new_progress(total as u64);
+    let listener = ctx.interactive.then(|| ctx.pause.listen(&progress, true));
+    let opts = PortProbeOptions {
+        concurrency: concurrency.value,
+        probe_timeout: Duration::from_secs(args.timeout.max(1)),
+        retries: 0,
+        egress: &[],
+        knocker: None,
+        retransmit: None,
+        probes: ProbeRegistry::standard(),
+    };
+    let tasks = report.ports.iter().map(|(ip, port)| (ip.as_str(), *port));
+    let mut results: Vec<PortScanResult> = Vec::with_capacity(total);
+    let mut metrics = ScanMetrics::new();
+    scan_ports_with(
+        &TcpConnector,
+        tasks,
+        &fps,
+        opts,
+        &progress,
+        ctx,
+        |result, timing| {
+            metrics.record(&result.ip, &timing);
+            results.push(result);
+        },
+    )
+    .await;
+    drop(listener);
+    progress.finish_with_message("✅ 补充探测完成");
+    results.sort_by(|a, b| (&a.ip, a.port).cmp(&(&b.ip, b.port)));
+
+    let counts = progress.snapshot();
+    let open_ports: Vec<&PortScanResult> = results.iter().filter(|r| r.is_open()).collect();
+    debug_assert_eq!(counts.succeeded as usize, open_ports.len());
+    let open_count = open_ports.len();
+    let identified = open_ports.iter().filter(|r| r.received_data()).count();
+
+    let mut outputs = Vec::new();
+    if args.output {
+        outputs.push(
+            run_stage(export_results(
+                &results,
+                OutputKind::ENRICH,
+                &BTreeMap::new(),
+                &metrics,
+                ctx.excel_options(),
+            ))
+            .await?,
+        );
+    }
+
+    let mut summary: Vec<SummaryItem> = vec![("导入".to_string(), format!("{} 个端口", total))];
+    summary.extend(counts.summary_items("个端口"));
+    summary.extend([
+        ("仍开放".to_string(), format!("{} 个", counts.succeeded)),
+        ("获取到应答".to_string(), format!("{} 个", identified)),
+        ("跳过记录".to_string(), report.skipped.to_string()),
+        (
+            "耗时".to_string(),
+            format_elapsed(start.elapsed(), ctx.pause.paused_duration()),
+        ),
+    ]);
+    println!("\n📊 探测统计:");
+    for (name, value) in &summary {
+        println!("   {}: {}", name, value);
+    }
+
+    if let Some(run_dir) = ctx.run_dir() {
+        let hosts = host_records(&results, &BTreeMap::new(), &BTreeMap::new());
+        run_dir.write_json(HOSTS_FILE_NAME, "json", &hosts, hosts.len())?;
+        run_dir.write_json(PORTS_FILE_NAME, "json", &open_ports, open_count)?;
+        run_dir.write_summary(&summary)?;
+    }
+
+    Ok(RunSummary {
+        total: counts.completed as usize,
+        succeeded: counts.succeeded as usize,
+        outputs,
+        concurrency: Some(concurrency),
+        timing: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::pentest::nmap_xml::{NmapRunInfo, render};
+    use crate::utils::provenance::Provenance;
+    use chrono::Local;
+
+    fn result(ip: &str, port: u16, status: &str) -> PortScanResult {
+        PortScanResult {
+            ip: ip.to_string(),
+            port,
+            status: status.to_string(),
+            banner: String::new(),
+            evidence: Vec::new(),
+            suspected_honeypot: false,
+            aliases: Vec::new(),
+            tags: Default::default(),
+            geo: None,
+            egress: None,
+            verification: None,
+            failure: None,
+            knocked: false,
+            asset_changes: Vec::new(),
+            stage: None,
+            probes: BTreeMap::new(),
+            observed: Provenance::default(),
+        }
+    }
+
+    #[test]
+    fn test_masscan_lines_with_bad_records_are_skipped() {
+        let content = r#"[
+{   "ip": "10.0.0.2",   "timestamp": "1700000000", "ports": [ {"port": 443, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] },
+{   "ip": "10.0.0.1",   "timestamp": "1700000000", "ports": [ {"port": 22, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] },
+{   "ip": "10.0.0.1",   "timestamp": "1700000001", "ports": [ {"port": 22, "proto": "tcp", "service": {"name": "ssh", "banner": "SSH-2.0-OpenSSH_8.9"} } ] },
+{   "ip": "10.0.0.3",   "timestamp": "1700000001", "ports": [ {"port": 53, "proto": "udp", "status": "open"} ] },
+{   "ip": "not-an-ip",  "ports": [ {"port": 80, "proto": "tcp", "status": "open"} ] },
+{   "ip": "10.0.0.4", "ports": [ {"port": 80
+{finished: 1}
+]
+"#;
+        assert_eq!(ImportFormat::detect(content), Some(ImportFormat::Masscan));
+        let report = parse_masscan(content);
+        assert_eq!(
+            report.ports,
+            [("10.0.0.1".to_string(), 22), ("10.0.0.2".to_string(), 443)]
+        );
+        assert_eq!(report.skipped, 3);
+        assert_eq!(report.ignored, 1);
+        assert_eq!(report.host_count(), 2);
+    }
+
+    #[test]
+    fn test_own_nmap_xml_round_trips() {
+        let mut web = result("10.0.0.1", 80, "开放");
+        web.banner = "HTTP/1.1 200 OK | nginx".to_string();
+        web.evidence = vec!["http-probe".to_string()];
+        let results = vec![
+            web,
+            result("10.0.0.1", 22, "关闭"),
+            result("10.0.0.1", 8443, "超时"),
+            result("10.0.0.2", 80, "关闭"),
+            result("fe80::1", 22, "开放"),
+        ];
+        let now = Local::now();
+        let xml = render(
+            &results,
+            &NmapRunInfo {
+                args: "gxr pentest portscan".to_string(),
+                start: now,
+                end: now,
+            },
+        );
+
+        assert_eq!(ImportFormat::detect(&xml), Some(ImportFormat::NmapXml));
+        let report = parse_nmap_xml(&xml).unwrap();
+        assert_eq!(
+            report.ports,
+            [("10.0.0.1".to_string(), 80), ("fe80::1".to_string(), 22)]
+        );
+        // open|filtered 不是确认开放的端口
+        assert_eq!(report.ignored, 1);
+        assert_eq!(report.skipped, 0);
+    }
+
+    #[test]
+    fn test_nmap_xml_bad_hosts_are_skipped() {
+        let xml = r#"<?xml version="1.0"?>
+<nmaprun scanner="nmap">
+<host><address addr="aa:bb:cc:dd:ee:ff" addrtype="mac"/></host>
+<host><address addr="10.0.0.5" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="99999"><state state="open"/></port>
+<port protocol="udp" portid="161"><state state="open"/></port>
+<port protocol="tcp" portid="3389"><state state="open"/></port>
+</ports></host>
+</nmaprun>"#;
+        let report = parse_nmap_xml(xml).unwrap();
+        assert_eq!(report.ports, [("10.0.0.5".to_string(), 3389)]);
+        assert_eq!(report.skipped, 2);
+        assert_eq!(report.ignored, 1);
+        assert!(parse_nmap_xml("<scan/>").is_err());
+    }
+}
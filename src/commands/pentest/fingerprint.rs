@@ -1,6 +1,4 @@
 use serde::Deserialize;
-use std::fs;
-use std::path::Path;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Probe {
@@ -18,18 +16,3 @@ pub struct Fingerprint {
     pub ports: Vec<u16>,    // 适用端口（为空则不限）
     pub probes: Vec<Probe>, // 该服务的探针集合
 }
-
-pub fn load_fingerprints<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<Fingerprint>> {
-    let data = fs::read_to_string(&path)?;
-    let ext = path
-        .as_ref()
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("");
-
-    if ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml") {
-        Ok(serde_yaml::from_str(&data)?)
-    } else {
-        Ok(serde_json::from_str(&data)?)
-    }
-}
@@ -0,0 +1,284 @@
+// src/commands/pentest/honeypot.rs
+use crate::commands::pentest::portscan::PortScanResult;
+use crate::utils::{config_file, load_config_section};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+
+/// 配置文件中的段落名
+pub const CONFIG_SECTION: &str = "honeypot";
+
+/// 连接后通常会主动发送banner的服务端口
+const BANNER_PORTS: &[u16] = &[21, 22, 23, 25, 110, 143, 465, 587, 993, 995, 3306, 5900];
+
+/// 检测参数（可在配置文件的 `honeypot` 段落中调整）
+///
+/// ```yaml
+/// honeypot:
+///   threshold: 1.0
+///   open_ports_limit: 30
+///   open_ports_weight: 0.6
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HoneypotConfig {
+    /// 判定阈值（各项得分之和达到该值时标记为疑似蜜罐）
+    pub threshold: f64,
+    /// 开放端口数达到该值视为异常
+    pub open_ports_limit: usize,
+    /// 开放端口占已扫描端口的比例达到该值视为异常（至少扫描20个端口时生效）
+    pub open_ratio_limit: f64,
+    /// 开放端口异常的得分
+    pub open_ports_weight: f64,
+    /// 相同banner出现在至少该数量的端口上视为异常
+    pub identical_banner_ports: usize,
+    /// 相同banner的得分
+    pub identical_banner_weight: f64,
+    /// 应有banner却无数据的端口达到该数量视为异常
+    pub silent_banner_ports: usize,
+    /// 应有banner却无数据的得分
+    pub silent_banner_weight: f64,
+}
+
+impl Default for HoneypotConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            open_ports_limit: 30,
+            open_ratio_limit: 0.5,
+            open_ports_weight: 0.6,
+            identical_banner_ports: 3,
+            identical_banner_weight: 0.5,
+            silent_banner_ports: 2,
+            silent_banner_weight: 0.5,
+        }
+    }
+}
+
+impl HoneypotConfig {
+    /// 从配置文件读取检测参数，未配置的项使用默认值
+    pub fn load() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        load_config_section(&config_file(), CONFIG_SECTION)
+    }
+}
+
+/// 单个主机的检测结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HostAssessment {
+    /// IP地址
+    pub ip: String,
+    /// 总得分
+    pub score: f64,
+    /// 命中的特征
+    pub reasons: Vec<String>,
+    /// 是否疑似蜜罐
+    pub suspected: bool,
+}
+
+/// 按主机检测疑似蜜罐 / tarpit
+///
+/// 按主机汇总结果并打分，得分达到阈值的主机标记为疑似蜜罐。
+/// 连接扫描无法获取 SYN-ACK 的窗口大小，因此只使用基于连接和应答内容的特征：
+/// - 开放端口数量异常多（LaBrea 类 tarpit 会接受所有端口的连接）
+/// - 多个不相关端口返回完全相同的banner（低交互蜜罐的常见特征）
+/// - 本应主动发送banner的服务连接成功却没有任何数据
+///
+/// # 参数
+/// * `results` - 端口扫描结果（含关闭端口，用于计算开放比例）
+/// * `config` - 检测参数
+///
+/// # 返回
+/// * IP -> 检测结果（只包含有开放端口的主机）
+pub fn assess_hosts(
+    results: &[PortScanResult],
+    config: &HoneypotConfig,
+) -> BTreeMap<String, HostAssessment> {
+    let mut by_host: HashMap<&str, Vec<&PortScanResult>> = HashMap::new();
+    for result in results {
+        by_host.entry(result.ip.as_str()).or_default().push(result);
+    }
+
+    by_host
+        .into_iter()
+        .filter_map(|(ip, host_results)| {
+            let open: Vec<&PortScanResult> = host_results
+                .iter()
+                .copied()
+                .filter(|r| r.is_open())
+                .collect();
+            if open.is_empty() {
+                return None;
+            }
+            Some((
+                ip.to_string(),
+                assess_host(ip, &open, host_results.len(), config),
+            ))
+        })
+        .collect()
+}
+
+fn assess_host(
+    ip: &str,
+    open: &[&PortScanResult],
+    scanned: usize,
+    config: &HoneypotConfig,
+) -> HostAssessment {
+    let mut score = 0.0;
+    let mut reasons = Vec::new();
+
+    let ratio = open.len() as f64 / scanned.max(1) as f64;
+    if open.len() >= config.open_ports_limit || (scanned >= 20 && ratio >= config.open_ratio_limit)
+    {
+        score += config.open_ports_weight;
+        reasons.push(format!("开放端口过多（{}/{}）", open.len(), scanned));
+    }
+
+    // 只比较真正收到数据的banner，按端口表标注的服务名不算
+    let mut banners: HashMap<&str, HashSet<u16>> = HashMap::new();
+    for r in open.iter().filter(|r| r.received_data()) {
+        banners.entry(r.banner.as_str()).or_default().insert(r.port);
+    }
+    if let Some((banner, ports)) = banners
+        .iter()
+        .filter(|(_, ports)| ports.len() >= config.identical_banner_ports)
+        .max_by_key(|(_, ports)| ports.len())
+    {
+        score += config.identical_banner_weight;
+        reasons.push(format!(
+            "{} 个端口返回相同banner「{}」",
+            ports.len(),
+            truncate(banner, 40)
+        ));
+    }
+
+    let mut silent: Vec<u16> = open
+        .iter()
+        .filter(|r| BANNER_PORTS.contains(&r.port) && !r.received_data())
+        .map(|r| r.port)
+        .collect();
+    if !silent.is_empty() && silent.len() >= config.silent_banner_ports {
+        silent.sort_unstable();
+        score += config.silent_banner_weight;
+        reasons.push(format!(
+            "应有banner的端口无响应数据（{}）",
+            silent
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        ));
+    }
+
+    HostAssessment {
+        ip: ip.to_string(),
+        score,
+        suspected: score >= config.threshold,
+        reasons,
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        format!("{}…", text.chars().take(max_chars).collect::<String>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::pentest::portscan::CONNECT_EVIDENCE;
+    use crate::utils::provenance::Provenance;
+
+    fn open(ip: &str, port: u16, banner: &str, evidence: &str) -> PortScanResult {
+        PortScanResult {
+            ip: ip.to_string(),
+            port,
+            status: "开放".to_string(),
+            banner: banner.to_string(),
+            evidence: vec![evidence.to_string()],
+            suspected_honeypot: false,
+            aliases: Vec::new(),
+            tags: Default::default(),
+            geo: None,
+            egress: None,
+            verification: None,
+            failure: None,
+            knocked: false,
+            asset_changes: Vec::new(),
+            stage: None,
+            probes: BTreeMap::new(),
+            observed: Provenance::default(),
+        }
+    }
+
+    fn closed(ip: &str, port: u16) -> PortScanResult {
+        PortScanResult {
+            ip: ip.to_string(),
+            port,
+            status: "关闭".to_string(),
+            banner: String::new(),
+            evidence: Vec::new(),
+            suspected_honeypot: false,
+            aliases: Vec::new(),
+            tags: Default::default(),
+            geo: None,
+            egress: None,
+            verification: None,
+            failure: None,
+            knocked: false,
+            asset_changes: Vec::new(),
+            stage: None,
+            probes: BTreeMap::new(),
+            observed: Provenance::default(),
+        }
+    }
+
+    #[test]
+    fn test_tarpit_accepting_everything_is_flagged() {
+        // 所有端口都能连接，且都不发送数据
+        let results: Vec<PortScanResult> = (1..=40)
+            .chain([110, 143])
+            .map(|p| open("10.0.0.9", p, "服务未知", CONNECT_EVIDENCE))
+            .collect();
+        let hosts = assess_hosts(&results, &HoneypotConfig::default());
+        let host = &hosts["10.0.0.9"];
+        assert!(host.suspected);
+        assert_eq!(host.reasons.len(), 2);
+    }
+
+    #[test]
+    fn test_identical_banners_and_normal_host() {
+        let mut results = vec![
+            open("10.0.0.5", 21, "220 Service ready", "initial-raw"),
+            open("10.0.0.5", 25, "220 Service ready", "initial-raw"),
+            open("10.0.0.5", 110, "220 Service ready", "initial-raw"),
+            open("10.0.0.5", 22, "SSH", CONNECT_EVIDENCE),
+            open("10.0.0.5", 3306, "MySQL", CONNECT_EVIDENCE),
+            // 普通主机：少量端口，banner各不相同
+            open("10.0.0.6", 22, "SSH-2.0-OpenSSH_9.6", "ssh-banner"),
+            open("10.0.0.6", 80, "nginx", "http-probe"),
+        ];
+        results.extend((1000..1020).map(|p| closed("10.0.0.6", p)));
+
+        let hosts = assess_hosts(&results, &HoneypotConfig::default());
+        assert!(hosts["10.0.0.5"].suspected);
+        assert!(!hosts["10.0.0.6"].suspected);
+        assert!(hosts["10.0.0.6"].reasons.is_empty());
+    }
+
+    #[test]
+    fn test_config_weights_override_defaults() {
+        let config: HoneypotConfig =
+            serde_yaml::from_str("threshold: 0.5\nopen_ports_limit: 3\n").unwrap();
+        assert_eq!(config.open_ports_limit, 3);
+        assert_eq!(config.identical_banner_ports, 3);
+
+        let results: Vec<PortScanResult> = [22, 80, 443]
+            .map(|p| open("10.0.0.7", p, &format!("svc{}", p), "initial-raw"))
+            .to_vec();
+        assert!(assess_hosts(&results, &config)["10.0.0.7"].suspected);
+        assert!(!assess_hosts(&results, &HoneypotConfig::default())["10.0.0.7"].suspected);
+    }
+}
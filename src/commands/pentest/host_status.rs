@@ -0,0 +1,385 @@
+// src/commands/pentest/host_status.rs
+use crate::commands::pentest::portscan::PortScanResult;
+use crate::utils::ExcelSheet;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io;
+
+/// 端口未开放时连接失败的方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectFailure {
+    /// 目标有应答（连接被拒绝，或连接成功但没有识别出服务）
+    Reached,
+    /// 连接超时，目标没有任何应答
+    Filtered,
+    /// 无路由或ARP解析失败（附系统错误信息）
+    Unreachable(String),
+    /// 其他连接错误（附系统错误信息）
+    Error(String),
+}
+
+impl ConnectFailure {
+    /// 根据探测过程中目标是否有应答及最后一次连接错误判断失败方式
+    ///
+    /// # 参数
+    /// * `reached` - 是否有连接成功或被拒绝
+    /// * `error` - 最后一次连接错误（被拒绝除外）
+    pub fn classify(reached: bool, error: Option<(io::ErrorKind, String)>) -> Self {
+        match error {
+            _ if reached => ConnectFailure::Reached,
+            None => ConnectFailure::Filtered,
+            Some((io::ErrorKind::HostUnreachable | io::ErrorKind::NetworkUnreachable, text)) => {
+                ConnectFailure::Unreachable(text)
+            }
+            Some((_, text)) => ConnectFailure::Error(text),
+        }
+    }
+}
+
+/// 主机的扫描结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HostStatus {
+    /// 至少一个端口开放或有应答
+    Scanned,
+    /// 无路由或ARP解析失败
+    Unreachable,
+    /// 全部端口都没有应答
+    Filtered,
+    /// 连接出错
+    Error,
+    /// 存活探测无响应，没有扫描端口
+    NotAlive,
+    /// 扫描取消前没有开始
+    Skipped,
+}
+
+impl fmt::Display for HostStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HostStatus::Scanned => "已扫描",
+            HostStatus::Unreachable => "不可达",
+            HostStatus::Filtered => "全部过滤",
+            HostStatus::Error => "错误",
+            HostStatus::NotAlive => "未存活",
+            HostStatus::Skipped => "未扫描",
+        })
+    }
+}
+
+impl Serialize for HostStatus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// 单个目标主机的扫描结论
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostOutcome {
+    /// IP地址
+    pub ip: String,
+    /// 结论
+    pub status: HostStatus,
+    /// 开放端口数
+    pub open: usize,
+    /// 有应答但未开放的端口数
+    pub closed: usize,
+    /// 无应答的端口数
+    pub filtered: usize,
+    /// 连接出错（含不可达）的端口数
+    pub failed: usize,
+    /// 说明（如系统错误信息）
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub detail: String,
+}
+
+impl HostOutcome {
+    /// 没有扫描端口的主机
+    fn unscanned(ip: &str, status: HostStatus, detail: &str) -> Self {
+        Self {
+            ip: ip.to_string(),
+            status,
+            open: 0,
+            closed: 0,
+            filtered: 0,
+            failed: 0,
+            detail: detail.to_string(),
+        }
+    }
+}
+
+/// 一个主机的端口结果统计
+#[derive(Debug, Default)]
+struct HostTally {
+    done: usize,
+    open: usize,
+    closed: usize,
+    filtered: usize,
+    failed: usize,
+    unreachable: Option<String>,
+    error: Option<String>,
+}
+
+impl HostTally {
+    fn add(&mut self, result: &PortScanResult) {
+        self.done += 1;
+        if result.is_open() {
+            self.open += 1;
+            return;
+        }
+        match result.failure {
+            Some(ConnectFailure::Reached) | None => self.closed += 1,
+            Some(ConnectFailure::Filtered) => self.filtered += 1,
+            Some(ConnectFailure::Unreachable(ref e)) => {
+                self.failed += 1;
+                self.unreachable.get_or_insert_with(|| e.clone());
+            }
+            Some(ConnectFailure::Error(ref e)) => {
+                self.failed += 1;
+                self.error.get_or_insert_with(|| e.clone());
+            }
+        }
+    }
+
+    /// 按端口结果得出主机结论（有应答优先，其次不可达、出错，都没有时为全部过滤）
+    fn outcome(&self, ip: &str, ports: usize) -> HostOutcome {
+        let (status, detail) = if self.open + self.closed > 0 {
+            let detail = if self.done < ports {
+                format!("扫描未完成（{}/{} 个端口）", self.done, ports)
+            } else if self.open == 0 {
+                "端口均关闭".to_string()
+            } else {
+                String::new()
+            };
+            (HostStatus::Scanned, detail)
+        } else if let Some(ref e) = self.unreachable {
+            (HostStatus::Unreachable, e.clone())
+        } else if let Some(ref e) = self.error {
+            (HostStatus::Error, e.clone())
+        } else {
+            (
+                HostStatus::Filtered,
+                format!("{} 个端口均无应答", self.filtered),
+            )
+        };
+        HostOutcome {
+            ip: ip.to_string(),
+            status,
+            open: self.open,
+            closed: self.closed,
+            filtered: self.filtered,
+            failed: self.failed,
+            detail,
+        }
+    }
+}
+
+/// 扫描过程中跟踪各主机的端口完成情况，主机的全部端口都有结果时得出结论
+#[derive(Debug)]
+pub struct HostTracker {
+    ports: usize,
+    tallies: HashMap<String, HostTally>,
+}
+
+impl HostTracker {
+    /// # 参数
+    /// * `ports` - 每个主机要扫描的端口数
+    pub fn new(ports: usize) -> Self {
+        Self {
+            ports,
+            tallies: HashMap::new(),
+        }
+    }
+
+    /// 记录一个端口结果
+    ///
+    /// # 返回
+    /// * `Some(HostOutcome)` - 该主机的全部端口都已完成（每个主机只返回一次）
+    pub fn record(&mut self, result: &PortScanResult) -> Option<HostOutcome> {
+        let tally = self.tallies.entry(result.ip.clone()).or_default();
+        tally.add(result);
+        (tally.done == self.ports).then(|| tally.outcome(&result.ip, self.ports))
+    }
+}
+
+/// 得出每个目标主机的结论（按目标顺序，没有扫描端口的主机也包含在内）
+///
+/// # 参数
+/// * `results` - 最终的端口结果（含复核）
+/// * `targets` - 全部目标IP
+/// * `scanned` - 实际扫描端口的IP（未做存活探测时为 `None`，即全部目标）
+/// * `ports` - 每个主机要扫描的端口数
+pub fn host_outcomes(
+    results: &[PortScanResult],
+    targets: impl IntoIterator<Item = String>,
+    scanned: Option<&[String]>,
+    ports: usize,
+) -> Vec<HostOutcome> {
+    let scanned: Option<HashSet<&str>> =
+        scanned.map(|ips| ips.iter().map(String::as_str).collect());
+    let mut tallies: HashMap<&str, HostTally> = HashMap::new();
+    for result in results {
+        tallies.entry(result.ip.as_str()).or_default().add(result);
+    }
+    targets
+        .into_iter()
+        .map(|ip| match tallies.get(ip.as_str()) {
+            Some(tally) => tally.outcome(&ip, ports),
+            None if scanned.as_ref().is_none_or(|s| s.contains(ip.as_str())) => {
+                HostOutcome::unscanned(&ip, HostStatus::Skipped, "扫描已取消")
+            }
+            None => HostOutcome::unscanned(&ip, HostStatus::NotAlive, "存活探测无响应，未扫描端口"),
+        })
+        .collect()
+}
+
+/// 按结论统计主机数（如 `已扫描 3，不可达 1`）
+pub fn status_counts(outcomes: &[HostOutcome]) -> String {
+    let mut counts: BTreeMap<HostStatus, usize> = BTreeMap::new();
+    for outcome in outcomes {
+        *counts.entry(outcome.status).or_default() += 1;
+    }
+    counts
+        .iter()
+        .map(|(status, n)| format!("{} {}", status, n))
+        .collect::<Vec<_>>()
+        .join("，")
+}
+
+/// 生成主机状态表（每个目标主机一行）
+pub fn host_status_sheet(outcomes: &[HostOutcome]) -> ExcelSheet {
+    ExcelSheet {
+        name: "主机状态".to_string(),
+        headers: ["IP地址", "状态", "开放", "关闭", "无应答", "出错", "说明"]
+            .map(String::from)
+            .to_vec(),
+        rows: outcomes
+            .iter()
+            .map(|o| {
+                vec![
+                    o.ip.clone(),
+                    o.status.to_string(),
+                    o.open.to_string(),
+                    o.closed.to_string(),
+                    o.filtered.to_string(),
+                    o.failed.to_string(),
+                    o.detail.clone(),
+                ]
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::provenance::Provenance;
+
+    fn result(ip: &str, port: u16, failure: Option<ConnectFailure>) -> PortScanResult {
+        PortScanResult {
+            ip: ip.to_string(),
+            port,
+            status: if failure.is_none() {
+                "开放"
+            } else {
+                "关闭"
+            }
+            .to_string(),
+            banner: String::new(),
+            evidence: Vec::new(),
+            suspected_honeypot: false,
+            aliases: Vec::new(),
+            tags: Default::default(),
+            geo: None,
+            egress: None,
+            verification: None,
+            failure,
+            knocked: false,
+            asset_changes: Vec::new(),
+            stage: None,
+            probes: BTreeMap::new(),
+            observed: Provenance::default(),
+        }
+    }
+
+    #[test]
+    fn test_classify_connect_failures() {
+        let refused = ConnectFailure::classify(true, None);
+        assert_eq!(refused, ConnectFailure::Reached);
+        assert_eq!(
+            ConnectFailure::classify(false, None),
+            ConnectFailure::Filtered
+        );
+        let no_route = (
+            io::ErrorKind::HostUnreachable,
+            "No route to host".to_string(),
+        );
+        assert_eq!(
+            ConnectFailure::classify(false, Some(no_route.clone())),
+            ConnectFailure::Unreachable("No route to host".to_string())
+        );
+        // 同一任务中有连接得到应答时以应答为准
+        assert_eq!(ConnectFailure::classify(true, Some(no_route)), refused);
+        assert_eq!(
+            ConnectFailure::classify(
+                false,
+                Some((io::ErrorKind::AddrNotAvailable, "bind".to_string()))
+            ),
+            ConnectFailure::Error("bind".to_string())
+        );
+    }
+
+    #[test]
+    fn test_every_target_gets_an_outcome() {
+        let unreachable = || Some(ConnectFailure::Unreachable("No route to host".to_string()));
+        let results = [
+            result("10.0.0.1", 22, None),
+            result("10.0.0.1", 80, Some(ConnectFailure::Reached)),
+            result("10.0.0.2", 22, Some(ConnectFailure::Reached)),
+            result("10.0.0.2", 80, Some(ConnectFailure::Filtered)),
+            result("10.0.0.3", 22, unreachable()),
+            result("10.0.0.3", 80, Some(ConnectFailure::Filtered)),
+            result("10.0.0.4", 22, Some(ConnectFailure::Filtered)),
+            result("10.0.0.4", 80, Some(ConnectFailure::Filtered)),
+            result(
+                "10.0.0.5",
+                22,
+                Some(ConnectFailure::Error("Permission denied".into())),
+            ),
+        ];
+        let targets: Vec<String> = (1..=7).map(|i| format!("10.0.0.{}", i)).collect();
+        let scanned = &targets[..6];
+        let outcomes = host_outcomes(&results, targets.clone(), Some(scanned), 2);
+        let rows: Vec<(&str, HostStatus, &str)> = outcomes
+            .iter()
+            .map(|o| (o.ip.as_str(), o.status, o.detail.as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("10.0.0.1", HostStatus::Scanned, ""),
+                ("10.0.0.2", HostStatus::Scanned, "端口均关闭"),
+                ("10.0.0.3", HostStatus::Unreachable, "No route to host"),
+                ("10.0.0.4", HostStatus::Filtered, "2 个端口均无应答"),
+                ("10.0.0.5", HostStatus::Error, "Permission denied"),
+                ("10.0.0.6", HostStatus::Skipped, "扫描已取消"),
+                (
+                    "10.0.0.7",
+                    HostStatus::NotAlive,
+                    "存活探测无响应，未扫描端口"
+                ),
+            ]
+        );
+        assert_eq!(
+            status_counts(&outcomes),
+            "已扫描 2，不可达 1，全部过滤 1，错误 1，未存活 1，未扫描 1"
+        );
+        assert_eq!(host_status_sheet(&outcomes).rows[2][5], "1");
+
+        let mut tracker = HostTracker::new(2);
+        assert_eq!(tracker.record(&results[4]), None);
+        let done = tracker.record(&results[5]).unwrap();
+        assert_eq!(done.status, HostStatus::Unreachable);
+        assert_eq!((done.filtered, done.failed), (1, 1));
+    }
+}
@@ -0,0 +1,1125 @@
+// src/commands/pentest/mail.rs
+use crate::commands::history::RunSummary;
+use crate::utils::blocking::run_stage;
+use crate::utils::console::Icon;
+use crate::utils::context::ScanContext;
+use crate::utils::finding::{
+    Finding, FindingFormat, FindingParams, Severity, consolidate, write_findings,
+};
+use crate::utils::limits::{
+    ConcurrencySpec, ScanKind, effective_concurrency, parse_fixed_concurrency,
+};
+use crate::utils::output::OutputKind;
+use crate::utils::pool::run_tracked;
+use crate::utils::provenance::Provenance;
+use crate::utils::run_dir::{FINDINGS_FILE_NAME, PORTS_FILE_NAME, SummaryItem};
+use crate::utils::stats::Outcome;
+use crate::utils::targets::{TargetSourceArgs, collect_targets};
+use crate::utils::tls::connect_insecure;
+use crate::utils::{ExcelExport, format_elapsed, parse_ports_strict};
+use clap::Parser;
+use serde::Serialize;
+use std::error::Error;
+use std::io;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+/// 默认检查的邮件服务端口
+const DEFAULT_MAIL_PORTS: &str = "25,110,143,465,587,993,995";
+
+/// 中继测试默认使用的外部发件人
+pub const DEFAULT_RELAY_FROM: &str = "gxtools-probe@example.com";
+
+/// 中继测试默认使用的外部收件人
+pub const DEFAULT_RELAY_TO: &str = "relay-check@example.net";
+
+/// 单行应答的最大长度，超过时截断
+const MAX_LINE_BYTES: usize = 4096;
+
+/// 单个应答最多读取的行数（防止服务端无限输出）
+const MAX_REPLY_LINES: usize = 200;
+
+/// EHLO/HELO 使用的主机名
+const HELO_NAME: &str = "gxtools.local";
+
+/// 邮件服务安全检查参数配置
+#[derive(Parser, Debug)]
+pub struct MailArgs {
+    /// 目标IP或IP段（支持CIDR、范围、多个IP用逗号隔开）
+    #[arg(
+        short,
+        long,
+        value_name = "TARGET",
+        required_unless_present_any = ["target_xlsx", "target_file"]
+    )]
+    pub targets: Option<String>,
+
+    #[command(flatten)]
+    pub sources: TargetSourceArgs,
+
+    /// 检查的端口（按端口判断协议：25/465/587 为SMTP，110/995 为POP3，143/993 为IMAP，465/993/995 连接即TLS）
+    #[arg(short, long, default_value = DEFAULT_MAIL_PORTS, value_name = "PORTS")]
+    pub ports: String,
+
+    /// 最大并发数（auto 表示根据文件描述符上限和任务数量自动选择）
+    #[arg(
+        short = 'c',
+        long,
+        env = "GXTOOLS_CONCURRENCY",
+        default_value = "50",
+        value_name = "NUM|auto",
+        value_parser = parse_fixed_concurrency
+    )]
+    pub concurrency: ConcurrencySpec,
+
+    /// 连接及每条应答的超时时间（秒）
+    #[arg(
+        short = 'T',
+        long,
+        env = "GXTOOLS_TIMEOUT",
+        default_value = "5",
+        value_name = "SECS"
+    )]
+    pub timeout: u64,
+
+    /// 不进行开放中继测试
+    #[arg(long)]
+    pub no_relay_test: bool,
+
+    /// 中继测试使用的外部发件人
+    #[arg(long, default_value = DEFAULT_RELAY_FROM, value_name = "ADDR")]
+    pub relay_from: String,
+
+    /// 中继测试使用的外部收件人（只发送 RCPT TO，不会发送邮件内容）
+    #[arg(long, default_value = DEFAULT_RELAY_TO, value_name = "ADDR")]
+    pub relay_to: String,
+
+    /// 另外导出的发现列表格式（多个格式用逗号隔开）
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "FORMAT")]
+    pub format: Vec<FindingFormat>,
+
+    /// 是否输出结果到Excel文件
+    #[arg(short = 'o', long, env = "GXTOOLS_OUTPUT")]
+    pub output: bool,
+}
+
+/// 邮件协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum MailProtocol {
+    /// SMTP（含提交端口）
+    Smtp,
+    /// POP3
+    Pop3,
+    /// IMAP
+    Imap,
+}
+
+impl MailProtocol {
+    /// 按端口判断协议及是否连接即TLS
+    pub fn for_port(port: u16) -> Option<(Self, bool)> {
+        match port {
+            25 | 587 => Some((Self::Smtp, false)),
+            465 => Some((Self::Smtp, true)),
+            110 => Some((Self::Pop3, false)),
+            995 => Some((Self::Pop3, true)),
+            143 => Some((Self::Imap, false)),
+            993 => Some((Self::Imap, true)),
+            _ => None,
+        }
+    }
+
+    /// 协议名称
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Smtp => "SMTP",
+            Self::Pop3 => "POP3",
+            Self::Imap => "IMAP",
+        }
+    }
+}
+
+/// 开放中继测试结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelayVerdict {
+    /// 外部收件人被拒绝
+    Rejected,
+    /// 外部收件人被接受（疑似开放中继）
+    Accepted,
+    /// 发件人即被拒绝等无法判断的情况
+    Inconclusive,
+}
+
+impl RelayVerdict {
+    /// 中文名称
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Rejected => "拒绝中继",
+            Self::Accepted => "接受中继",
+            Self::Inconclusive => "无法判断",
+        }
+    }
+}
+
+/// 开放中继测试结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RelayResult {
+    /// 结论
+    pub verdict: RelayVerdict,
+    /// 依据的应答（如 `RCPT TO:<x> => 554 5.7.1 Relay access denied`）
+    pub evidence: String,
+}
+
+/// 开放中继测试使用的外部地址
+#[derive(Debug, Clone)]
+pub struct RelayProbe {
+    /// 外部发件人
+    pub from: String,
+    /// 外部收件人
+    pub to: String,
+}
+
+impl RelayProbe {
+    /// 记录到开放中继发现中的参数（复查时使用相同的地址）
+    pub fn params(&self) -> FindingParams {
+        FindingParams::from([
+            ("relay_from".to_string(), self.from.clone()),
+            ("relay_to".to_string(), self.to.clone()),
+        ])
+    }
+
+    /// 按发现中记录的参数还原，缺少的参数使用默认地址
+    pub fn from_params(params: &FindingParams) -> Self {
+        let get = |key: &str, default: &str| {
+            params
+                .get(key)
+                .cloned()
+                .unwrap_or_else(|| default.to_string())
+        };
+        Self {
+            from: get("relay_from", DEFAULT_RELAY_FROM),
+            to: get("relay_to", DEFAULT_RELAY_TO),
+        }
+    }
+}
+
+/// 单个邮件服务的检查结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MailService {
+    /// IP地址
+    pub ip: String,
+    /// 端口号
+    pub port: u16,
+    /// 协议
+    pub protocol: MailProtocol,
+    /// 是否连接即TLS（465/993/995）
+    pub tls: bool,
+    /// 服务banner
+    pub banner: String,
+    /// 服务声明的扩展（EHLO/CAPA/CAPABILITY）
+    pub capabilities: Vec<String>,
+    /// 明文端口是否提供STARTTLS（TLS端口为空）
+    pub starttls: Option<bool>,
+    /// 加密之前允许的明文认证方式（如 PLAIN、LOGIN、USER）
+    pub plaintext_auth: Vec<String>,
+    /// 声明明文认证方式的扩展行
+    pub auth_evidence: Vec<String>,
+    /// 开放中继测试结果（仅SMTP）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relay: Option<RelayResult>,
+    /// 检查中断的原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 观测时间及探测方式（如 `smtp:25`、`imaps:993`）
+    #[serde(flatten)]
+    pub observed: Provenance,
+}
+
+impl MailService {
+    fn new(ip: &str, port: u16, protocol: MailProtocol, tls: bool) -> Self {
+        let scheme = format!(
+            "{}{}",
+            protocol.name().to_ascii_lowercase(),
+            if tls { "s" } else { "" }
+        );
+        Self {
+            ip: ip.to_string(),
+            port,
+            protocol,
+            tls,
+            banner: String::new(),
+            capabilities: Vec::new(),
+            starttls: None,
+            plaintext_auth: Vec::new(),
+            auth_evidence: Vec::new(),
+            relay: None,
+            error: None,
+            observed: Provenance::now(format!("{}:{}", scheme, port)),
+        }
+    }
+
+    /// 记录明文认证方式（去重）
+    fn allow_plaintext(&mut self, mechanism: &str, evidence: &str) {
+        if !self.plaintext_auth.iter().any(|m| m == mechanism) {
+            self.plaintext_auth.push(mechanism.to_string());
+        }
+        if !self.auth_evidence.iter().any(|e| e == evidence) {
+            self.auth_evidence.push(evidence.to_string());
+        }
+    }
+}
+
+/// 逐行收发的会话（每次读取单独计算超时）
+struct Session<S> {
+    conn: BufReader<S>,
+    io_timeout: Duration,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    fn new(stream: S, io_timeout: Duration) -> Self {
+        Self {
+            conn: BufReader::new(stream),
+            io_timeout,
+        }
+    }
+
+    /// 读取一行（不含换行符），连接关闭时返回错误
+    async fn line(&mut self) -> io::Result<String> {
+        let mut buf = Vec::new();
+        let read = timeout(self.io_timeout, async {
+            loop {
+                let available = self.conn.fill_buf().await?;
+                if available.is_empty() {
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "连接已关闭"));
+                }
+                let (chunk, done) = match available.iter().position(|&b| b == b'\n') {
+                    Some(i) => (&available[..=i], true),
+                    None => (available, false),
+                };
+                let take = chunk.len().min(MAX_LINE_BYTES.saturating_sub(buf.len()));
+                buf.extend_from_slice(&chunk[..take]);
+                let consumed = chunk.len();
+                self.conn.consume(consumed);
+                if done {
+                    return Ok(());
+                }
+            }
+        })
+        .await;
+        match read {
+            Ok(result) => result?,
+            Err(_) => return Err(io::Error::new(io::ErrorKind::TimedOut, "等待应答超时")),
+        }
+        Ok(String::from_utf8_lossy(&buf)
+            .trim_end_matches(['\r', '\n'])
+            .to_string())
+    }
+
+    /// 发送一条命令
+    async fn send(&mut self, command: &str) -> io::Result<()> {
+        let write = async {
+            self.conn
+                .write_all(format!("{}\r\n", command).as_bytes())
+                .await?;
+            self.conn.flush().await
+        };
+        timeout(self.io_timeout, write)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "发送命令超时"))?
+    }
+
+    /// 读取SMTP应答（多行应答以 `250-` 续行，以 `250 ` 结束）
+    ///
+    /// # 返回
+    /// * `(应答码, 各行去掉应答码后的文本)`
+    async fn smtp_reply(&mut self) -> io::Result<(u16, Vec<String>)> {
+        let mut lines = Vec::new();
+        for _ in 0..MAX_REPLY_LINES {
+            let line = self.line().await?;
+            let code = line
+                .get(..3)
+                .and_then(|c| c.parse::<u16>().ok())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("不是SMTP应答: {}", line),
+                    )
+                })?;
+            let last = line.as_bytes().get(3) != Some(&b'-');
+            lines.push(line.get(4..).unwrap_or_default().trim().to_string());
+            if last {
+                return Ok((code, lines));
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "SMTP应答行数过多",
+        ))
+    }
+
+    /// 读取以单独一行 `.` 结束的多行应答（POP3）
+    async fn dot_terminated(&mut self) -> io::Result<Vec<String>> {
+        let mut lines = Vec::new();
+        for _ in 0..MAX_REPLY_LINES {
+            let line = self.line().await?;
+            if line == "." {
+                return Ok(lines);
+            }
+            lines.push(line);
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "POP3应答行数过多",
+        ))
+    }
+
+    /// 读取到带指定标签的应答为止（IMAP）
+    ///
+    /// # 返回
+    /// * `(未带标签的应答, 带标签的应答)`
+    async fn tagged(&mut self, tag: &str) -> io::Result<(Vec<String>, String)> {
+        let prefix = format!("{} ", tag);
+        let mut untagged = Vec::new();
+        for _ in 0..MAX_REPLY_LINES {
+            let line = self.line().await?;
+            if let Some(rest) = line.strip_prefix(&prefix) {
+                return Ok((untagged, rest.to_string()));
+            }
+            untagged.push(line);
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "IMAP应答行数过多",
+        ))
+    }
+}
+
+/// 在已建立的连接上检查邮件服务，检查中断时原因记录在结果中
+///
+/// # 参数
+/// * `stream` - 已建立的连接（TLS端口为握手完成的连接）
+/// * `service` - 检查结果（输出）
+/// * `relay` - 开放中继测试使用的外部地址（为 `None` 时不测试，仅对SMTP生效）
+/// * `io_timeout` - 每条应答的超时
+pub async fn audit_stream<S>(
+    stream: S,
+    service: &mut MailService,
+    relay: Option<&RelayProbe>,
+    io_timeout: Duration,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut session = Session::new(stream, io_timeout);
+    let result = match service.protocol {
+        MailProtocol::Smtp => audit_smtp(&mut session, service, relay).await,
+        MailProtocol::Pop3 => audit_pop3(&mut session, service).await,
+        MailProtocol::Imap => audit_imap(&mut session, service).await,
+    };
+    if let Err(e) = result {
+        service.error = Some(e.to_string());
+    }
+}
+
+/// SMTP：读取banner，EHLO列出扩展，检查STARTTLS和明文认证，并进行保守的开放中继测试
+///
+/// 中继测试只发送 MAIL FROM 与 RCPT TO，随后 RSET，不会发送 DATA。
+async fn audit_smtp<S: AsyncRead + AsyncWrite + Unpin>(
+    session: &mut Session<S>,
+    service: &mut MailService,
+    relay: Option<&RelayProbe>,
+) -> io::Result<()> {
+    let (code, greeting) = session.smtp_reply().await?;
+    service.banner = greeting.join(" ");
+    if code != 220 {
+        return Err(io::Error::other(format!("服务未就绪: {}", code)));
+    }
+
+    session.send(&format!("EHLO {}", HELO_NAME)).await?;
+    let (code, lines) = session.smtp_reply().await?;
+    if code == 250 {
+        // 第一行是服务端的问候
+        service.capabilities = lines.into_iter().skip(1).collect();
+    } else {
+        session.send(&format!("HELO {}", HELO_NAME)).await?;
+        session.smtp_reply().await?;
+    }
+
+    if !service.tls {
+        service.starttls = Some(
+            service
+                .capabilities
+                .iter()
+                .any(|c| c.eq_ignore_ascii_case("STARTTLS")),
+        );
+        let auth_lines: Vec<String> = service
+            .capabilities
+            .iter()
+            .filter(|c| {
+                let upper = c.to_ascii_uppercase();
+                upper.starts_with("AUTH ") || upper.starts_with("AUTH=")
+            })
+            .cloned()
+            .collect();
+        for line in &auth_lines {
+            for mechanism in line[5..].split_whitespace() {
+                let mechanism = mechanism.to_ascii_uppercase();
+                if mechanism == "PLAIN" || mechanism == "LOGIN" {
+                    service.allow_plaintext(&mechanism, line);
+                }
+            }
+        }
+    }
+
+    if let Some(probe) = relay {
+        service.relay = Some(relay_test(session, probe).await?);
+    }
+    let _ = session.send("QUIT").await;
+    Ok(())
+}
+
+/// 开放中继测试：外部发件人发往外部收件人，收件人被接受即疑似开放中继
+async fn relay_test<S: AsyncRead + AsyncWrite + Unpin>(
+    session: &mut Session<S>,
+    probe: &RelayProbe,
+) -> io::Result<RelayResult> {
+    let mail_from = format!("MAIL FROM:<{}>", probe.from);
+    session.send(&mail_from).await?;
+    let (code, lines) = session.smtp_reply().await?;
+    if !(200..300).contains(&code) {
+        return Ok(RelayResult {
+            verdict: RelayVerdict::Inconclusive,
+            evidence: format!("{} => {} {}", mail_from, code, lines.join(" ")),
+        });
+    }
+
+    let rcpt_to = format!("RCPT TO:<{}>", probe.to);
+    session.send(&rcpt_to).await?;
+    let (code, lines) = session.smtp_reply().await?;
+    let verdict = if (200..300).contains(&code) {
+        RelayVerdict::Accepted
+    } else {
+        RelayVerdict::Rejected
+    };
+    // 撤销本次事务，绝不发送 DATA
+    session.send("RSET").await?;
+    let _ = session.smtp_reply().await;
+    Ok(RelayResult {
+        verdict,
+        evidence: format!("{} => {} {}", rcpt_to, code, lines.join(" ")),
+    })
+}
+
+/// POP3：读取banner，CAPA列出扩展，检查STLS和明文认证
+async fn audit_pop3<S: AsyncRead + AsyncWrite + Unpin>(
+    session: &mut Session<S>,
+    service: &mut MailService,
+) -> io::Result<()> {
+    let greeting = session.line().await?;
+    let Some(banner) = greeting.strip_prefix("+OK") else {
+        service.banner = greeting;
+        return Err(io::Error::other("服务未就绪"));
+    };
+    service.banner = banner.trim().to_string();
+
+    session.send("CAPA").await?;
+    if session.line().await?.starts_with("+OK") {
+        service.capabilities = session.dot_terminated().await?;
+    }
+
+    if !service.tls {
+        service.starttls = Some(
+            service
+                .capabilities
+                .iter()
+                .any(|c| c.eq_ignore_ascii_case("STLS")),
+        );
+        let capabilities = service.capabilities.clone();
+        for line in &capabilities {
+            let mut words = line.split_whitespace().map(str::to_ascii_uppercase);
+            match words.next().as_deref() {
+                Some("USER") => service.allow_plaintext("USER", line),
+                Some("SASL") => {
+                    for mechanism in words.filter(|m| m == "PLAIN" || m == "LOGIN") {
+                        service.allow_plaintext(&mechanism, line);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let _ = session.send("QUIT").await;
+    Ok(())
+}
+
+/// IMAP：读取banner，CAPABILITY列出扩展，检查STARTTLS和明文认证（LOGIN未被 LOGINDISABLED 禁用）
+async fn audit_imap<S: AsyncRead + AsyncWrite + Unpin>(
+    session: &mut Session<S>,
+    service: &mut MailService,
+) -> io::Result<()> {
+    let greeting = session.line().await?;
+    let Some(banner) = greeting.strip_prefix("* OK") else {
+        service.banner = greeting;
+        return Err(io::Error::other("服务未就绪"));
+    };
+    service.banner = banner.trim().to_string();
+
+    session.send("a1 CAPABILITY").await?;
+    let (untagged, _) = session.tagged("a1").await?;
+    let line = untagged
+        .iter()
+        .find_map(|l| l.strip_prefix("* CAPABILITY "))
+        .unwrap_or_default()
+        .to_string();
+    service.capabilities = line.split_whitespace().map(str::to_string).collect();
+
+    if !service.tls {
+        let has = |name: &str| {
+            service
+                .capabilities
+                .iter()
+                .any(|c| c.eq_ignore_ascii_case(name))
+        };
+        service.starttls = Some(has("STARTTLS"));
+        let evidence = format!("CAPABILITY {}", line);
+        let login_allowed = !has("LOGINDISABLED");
+        let plain = has("AUTH=PLAIN");
+        let auth_login = has("AUTH=LOGIN");
+        if login_allowed {
+            service.allow_plaintext("LOGIN", &format!("{}（未声明 LOGINDISABLED）", evidence));
+        }
+        if plain {
+            service.allow_plaintext("PLAIN", &evidence);
+        }
+        if auth_login {
+            service.allow_plaintext("LOGIN", &evidence);
+        }
+    }
+
+    let _ = session.send("a2 LOGOUT").await;
+    Ok(())
+}
+
+/// 连接并检查单个邮件服务
+///
+/// # 返回
+/// * `None` - 端口未连通
+pub async fn check_service(
+    ip: &str,
+    port: u16,
+    relay: Option<&RelayProbe>,
+    io_timeout: Duration,
+) -> Option<MailService> {
+    let (protocol, tls) = MailProtocol::for_port(port)?;
+    let stream = timeout(io_timeout, TcpStream::connect((ip, port)))
+        .await
+        .ok()?
+        .ok()?;
+    let mut service = MailService::new(ip, port, protocol, tls);
+    let relay = relay.filter(|_| protocol == MailProtocol::Smtp);
+    // 整个会话另有上限，防止逐行缓慢应答的服务端拖住工作槽位
+    let session_limit = io_timeout * 12;
+    let audit = async {
+        if tls {
+            match timeout(io_timeout, connect_insecure(stream, ip)).await {
+                Ok(Ok(stream)) => audit_stream(stream, &mut service, relay, io_timeout).await,
+                Ok(Err(e)) => service.error = Some(format!("TLS握手失败: {}", e)),
+                Err(_) => service.error = Some("TLS握手超时".to_string()),
+            }
+        } else {
+            audit_stream(stream, &mut service, relay, io_timeout).await;
+        }
+    };
+    if timeout(session_limit, audit).await.is_err() {
+        service.error = Some("检查超时".to_string());
+    }
+    Some(service)
+}
+
+/// 把检查结果映射为标准发现
+///
+/// # 参数
+/// * `services` - 检查结果
+/// * `probe` - 开放中继测试使用的外部地址（记录到开放中继发现中）
+pub fn mail_findings(services: &[MailService], probe: Option<&RelayProbe>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for s in services {
+        let protocol = s.protocol.name();
+        let finding =
+            |check_id: &str, title: String, severity, evidence: String, fix: &str| Finding {
+                module: "pentest mail".to_string(),
+                asset: s.ip.clone(),
+                port: Some(s.port),
+                protocol: "tcp".to_string(),
+                check_id: check_id.to_string(),
+                title,
+                severity,
+                evidence,
+                remediation: fix.to_string(),
+                params: FindingParams::new(),
+                sources: Vec::new(),
+                observed: s.observed.clone(),
+            };
+
+        findings.push(finding(
+            "mail-service",
+            format!("{} 服务 {}/tcp", protocol, s.port),
+            Severity::Info,
+            s.banner.clone(),
+            "确认该服务需要对外提供",
+        ));
+        if let Some(ref relay) = s.relay
+            && relay.verdict == RelayVerdict::Accepted
+        {
+            let mut open_relay = finding(
+                "smtp-open-relay",
+                "SMTP 疑似开放中继（接受外部发件人发往外部收件人）".to_string(),
+                Severity::High,
+                relay.evidence.clone(),
+                "只允许认证用户及内部网段中继，拒绝外部发件人投递到外部域",
+            );
+            open_relay.params = probe.map(RelayProbe::params).unwrap_or_default();
+            findings.push(open_relay);
+        }
+        if s.starttls == Some(false) {
+            let evidence = if s.capabilities.is_empty() {
+                "服务未返回扩展列表".to_string()
+            } else {
+                s.capabilities.join("; ")
+            };
+            findings.push(finding(
+                "mail-no-starttls",
+                format!("{} 明文端口未提供STARTTLS", protocol),
+                Severity::Medium,
+                evidence,
+                "启用STARTTLS（POP3为STLS），或只开放连接即TLS的端口",
+            ));
+        }
+        if !s.plaintext_auth.is_empty() {
+            findings.push(finding(
+                "mail-plaintext-auth",
+                format!(
+                    "{} 允许在加密前明文认证（{}）",
+                    protocol,
+                    s.plaintext_auth.join("/")
+                ),
+                Severity::Medium,
+                s.auth_evidence.join("; "),
+                "在建立TLS之前禁止明文认证（如 Postfix smtpd_tls_auth_only、Dovecot disable_plaintext_auth）",
+            ));
+        }
+    }
+    findings
+}
+
+pub async fn run(args: &MailArgs) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    run_with(args, &ScanContext::cli()).await
+}
+
+/// 检查邮件服务的banner、扩展、STARTTLS、明文认证及开放中继风险
+///
+/// # 参数
+/// * `args` - 检查参数
+/// * `ctx` - 扫描上下文
+///
+/// # 返回
+/// * `Ok(RunSummary)` - 检查完成后的结果摘要
+pub async fn run_with(
+    args: &MailArgs,
+    ctx: &ScanContext,
+) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    let start = Instant::now();
+    let started_at = chrono::Local::now();
+
+    let ports = parse_ports_strict(&args.ports)?;
+    if let Some(port) = ports.iter().find(|&&p| MailProtocol::for_port(p).is_none()) {
+        return Err(format!(
+            "端口 {} 不是已知的邮件服务端口（支持 {}）",
+            port, DEFAULT_MAIL_PORTS
+        )
+        .into());
+    }
+    let targets = collect_targets(args.targets.as_deref(), &args.sources).await?;
+    let ips = targets.ips();
+
+    let total = ips.len() * ports.len();
+    let concurrency = effective_concurrency(args.concurrency, total, ScanKind::Connect);
+    println!(
+        "{} 配置: 并发={}（{}）, 超时={}秒, 中继测试={}",
+        Icon::Config,
+        concurrency.value,
+        concurrency.reason,
+        args.timeout,
+        if args.no_relay_test {
+            "关闭"
+        } else {
+            "开启"
+        }
+    );
+    println!(
+        "📧 开始检查邮件服务: {} 个IP × {} 个端口 = {} 个任务",
+        ips.len(),
+        ports.len(),
+        total
+    );
+
+    let relay = (!args.no_relay_test).then(|| RelayProbe {
+        from: args.relay_from.clone(),
+        to: args.relay_to.clone(),
+    });
+    let io_timeout = Duration::from_secs(args.timeout.max(1));
+    let progress = ctx.new_progress(total as u64);
+    let listener = ctx.interactive.then(|| ctx.pause.listen(&progress, true));
+    let tasks = ips
+        .iter()
+        .flat_map(|ip| ports.iter().map(move |&port| (ip.as_str(), port)));
+    let mut services: Vec<MailService> = Vec::new();
+    run_tracked(
+        tasks,
+        concurrency.value,
+        &progress,
+        ctx.token(),
+        |(ip, port)| {
+            let relay = relay.as_ref();
+            async move {
+                ctx.pause.wait().await;
+                check_service(ip, port, relay, io_timeout).await
+            }
+        },
+        |service| {
+            if let Some(service) = service {
+                progress.println(format!(
+                    "  📧 {}:{} {} | {}",
+                    service.ip,
+                    service.port,
+                    service.protocol.name(),
+                    service.error.as_deref().unwrap_or(&service.banner)
+                ));
+                ctx.emit(&service);
+                let outcome = if service.error.is_some() {
+                    Outcome::Errored
+                } else {
+                    Outcome::Succeeded
+                };
+                services.push(service);
+                outcome
+            } else {
+                Outcome::Failed
+            }
+        },
+    )
+    .await;
+    drop(listener);
+    progress.finish_with_message("✅ 邮件服务检查完成");
+    services.sort_by(|a, b| (&a.ip, a.port).cmp(&(&b.ip, b.port)));
+
+    let findings = consolidate(mail_findings(&services, relay.as_ref()));
+    let issues: Vec<&Finding> = findings
+        .iter()
+        .filter(|f| f.severity > Severity::Info)
+        .collect();
+
+    let mut outputs = Vec::new();
+    if args.output {
+        outputs.push(run_stage(export_services(&services, ctx)).await?);
+    }
+    let first_seen = started_at.to_rfc3339();
+    for &format in &args.format {
+        let run_dir = ctx.run_dir().map(|run| run.as_ref());
+        let path = write_findings(&findings, format, &first_seen, OutputKind::MAIL, run_dir)?;
+        println!("{} 结果已保存至: {}", Icon::Ok, path.display());
+        outputs.push(path.display().to_string());
+    }
+
+    let relays = services
+        .iter()
+        .filter(|s| {
+            s.relay
+                .as_ref()
+                .is_some_and(|r| r.verdict == RelayVerdict::Accepted)
+        })
+        .count();
+    // 识别出协议但检查中途出错的服务同样计入邮件服务
+    let counts = progress.snapshot();
+    debug_assert_eq!((counts.succeeded + counts.errored) as usize, services.len());
+    let mut summary: Vec<SummaryItem> = counts.summary_items("个端口");
+    summary.extend([
+        ("邮件服务".to_string(), format!("{} 个", services.len())),
+        ("问题".to_string(), format!("{} 个", issues.len())),
+        ("疑似开放中继".to_string(), format!("{} 个", relays)),
+        (
+            "耗时".to_string(),
+            format_elapsed(start.elapsed(), ctx.pause.paused_duration()),
+        ),
+    ]);
+    println!("\n📊 检查统计:");
+    for (name, value) in &summary {
+        println!("   {}: {}", name, value);
+    }
+    if !issues.is_empty() {
+        println!("\n{} 发现的问题:", Icon::Warn);
+        for f in &issues {
+            println!(
+                "   [{}] {}:{} {}",
+                f.severity.label(),
+                f.asset,
+                f.port.unwrap_or_default(),
+                f.title
+            );
+        }
+    }
+
+    if let Some(run_dir) = ctx.run_dir() {
+        let rows: Vec<_> = findings.iter().map(|f| f.to_vm(&first_seen)).collect();
+        run_dir.write_json(PORTS_FILE_NAME, "json", &services, services.len())?;
+        run_dir.write_json(FINDINGS_FILE_NAME, "json", &rows, rows.len())?;
+        run_dir.write_summary(&summary)?;
+    }
+
+    Ok(RunSummary {
+        total: counts.completed as usize,
+        succeeded: services.len(),
+        outputs,
+        concurrency: Some(concurrency),
+        timing: None,
+    })
+}
+
+/// 导出检查结果到Excel
+fn export_services(services: &[MailService], ctx: &ScanContext) -> ExcelExport {
+    let headers = [
+        "IP地址",
+        "端口",
+        "协议",
+        "TLS",
+        "Banner",
+        "STARTTLS",
+        "明文认证",
+        "中继测试",
+        "扩展",
+        "错误",
+        Provenance::HEADERS[0],
+        Provenance::HEADERS[1],
+    ];
+    ExcelExport::prepare(
+        services,
+        &headers,
+        |s| {
+            vec![
+                s.ip.clone(),
+                s.port.to_string(),
+                s.protocol.name().to_string(),
+                if s.tls { "是" } else { "否" }.to_string(),
+                s.banner.clone(),
+                match s.starttls {
+                    Some(true) => "支持",
+                    Some(false) => "不支持",
+                    None => "",
+                }
+                .to_string(),
+                s.plaintext_auth.join(", "),
+                s.relay
+                    .as_ref()
+                    .map(|r| format!("{}: {}", r.verdict.label(), r.evidence))
+                    .unwrap_or_default(),
+                s.capabilities.join("; "),
+                s.error.clone().unwrap_or_default(),
+                s.observed.observed_at.clone(),
+                s.observed.probe.clone(),
+            ]
+        },
+        OutputKind::MAIL,
+        &ctx.excel_options(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncBufReadExt, DuplexStream};
+
+    /// 按命令前缀应答的模拟服务端，记录收到的命令
+    fn scripted(
+        greeting: &'static str,
+        replies: &'static [(&'static str, &'static str)],
+    ) -> (DuplexStream, Arc<Mutex<Vec<String>>>) {
+        let (client, server) = tokio::io::duplex(4096);
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        tokio::spawn(async move {
+            let mut server = BufReader::new(server);
+            let _ = server.write_all(greeting.as_bytes()).await;
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if server.read_line(&mut line).await.unwrap_or(0) == 0 {
+                    break;
+                }
+                let command = line.trim_end().to_string();
+                let upper = command.to_ascii_uppercase();
+                let reply = replies
+                    .iter()
+                    .find(|(prefix, _)| upper.starts_with(prefix))
+                    .map_or("500 unknown command\r\n", |(_, reply)| *reply);
+                log.lock().unwrap().push(command);
+                let _ = server.write_all(reply.as_bytes()).await;
+            }
+        });
+        (client, received)
+    }
+
+    fn probe() -> RelayProbe {
+        RelayProbe {
+            from: "a@example.com".to_string(),
+            to: "b@example.net".to_string(),
+        }
+    }
+
+    async fn audit(stream: DuplexStream, port: u16, relay: Option<&RelayProbe>) -> MailService {
+        let (protocol, tls) = MailProtocol::for_port(port).unwrap();
+        let mut service = MailService::new("10.0.0.1", port, protocol, tls);
+        audit_stream(stream, &mut service, relay, Duration::from_secs(1)).await;
+        service
+    }
+
+    fn check_ids(service: &MailService) -> Vec<(String, Severity)> {
+        mail_findings(std::slice::from_ref(service), Some(&probe()))
+            .into_iter()
+            .map(|f| (f.check_id, f.severity))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_smtp_open_relay_and_plaintext_auth() {
+        let (stream, received) = scripted(
+            "220-mx.corp.local ESMTP Postfix\r\n220 ready\r\n",
+            &[
+                (
+                    "EHLO",
+                    "250-mx.corp.local\r\n250-PIPELINING\r\n250-AUTH PLAIN LOGIN CRAM-MD5\r\n250 8BITMIME\r\n",
+                ),
+                ("MAIL FROM", "250 2.1.0 Ok\r\n"),
+                ("RCPT TO", "250 2.1.5 Ok\r\n"),
+                ("RSET", "250 2.0.0 Ok\r\n"),
+                ("QUIT", "221 Bye\r\n"),
+            ],
+        );
+        let service = audit(stream, 25, Some(&probe())).await;
+
+        assert_eq!(service.error, None);
+        assert_eq!(service.banner, "mx.corp.local ESMTP Postfix ready");
+        assert_eq!(
+            service.capabilities,
+            ["PIPELINING", "AUTH PLAIN LOGIN CRAM-MD5", "8BITMIME"]
+        );
+        assert_eq!(service.starttls, Some(false));
+        assert_eq!(service.plaintext_auth, ["PLAIN", "LOGIN"]);
+        assert_eq!(service.auth_evidence, ["AUTH PLAIN LOGIN CRAM-MD5"]);
+        let relay = service.relay.as_ref().unwrap();
+        assert_eq!(relay.verdict, RelayVerdict::Accepted);
+        assert_eq!(relay.evidence, "RCPT TO:<b@example.net> => 250 2.1.5 Ok");
+        assert_eq!(
+            check_ids(&service),
+            [
+                ("mail-service".to_string(), Severity::Info),
+                ("smtp-open-relay".to_string(), Severity::High),
+                ("mail-no-starttls".to_string(), Severity::Medium),
+                ("mail-plaintext-auth".to_string(), Severity::Medium),
+            ]
+        );
+
+        // 中继测试绝不发送邮件内容
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let commands = received.lock().unwrap().clone();
+        assert!(commands.iter().any(|c| c == "RSET"));
+        assert!(!commands.iter().any(|c| c.eq_ignore_ascii_case("DATA")));
+    }
+
+    #[tokio::test]
+    async fn test_smtp_relay_rejected_with_starttls() {
+        let (stream, _) = scripted(
+            "220 mx ESMTP\r\n",
+            &[
+                ("EHLO", "250-mx\r\n250-STARTTLS\r\n250 SIZE 10240000\r\n"),
+                ("MAIL FROM", "250 Ok\r\n"),
+                ("RCPT TO", "554 5.7.1 Relay access denied\r\n"),
+                ("RSET", "250 Ok\r\n"),
+            ],
+        );
+        let service = audit(stream, 587, Some(&probe())).await;
+        assert_eq!(service.starttls, Some(true));
+        assert!(service.plaintext_auth.is_empty());
+        let relay = service.relay.unwrap();
+        assert_eq!(relay.verdict, RelayVerdict::Rejected);
+        assert!(relay.evidence.contains("554 5.7.1 Relay access denied"));
+    }
+
+    #[tokio::test]
+    async fn test_pop3_without_stls() {
+        let (stream, _) = scripted(
+            "+OK Dovecot ready.\r\n",
+            &[
+                ("CAPA", "+OK\r\nTOP\r\nUSER\r\nSASL PLAIN\r\nUIDL\r\n.\r\n"),
+                ("QUIT", "+OK Logging out\r\n"),
+            ],
+        );
+        let service = audit(stream, 110, None).await;
+        assert_eq!(service.banner, "Dovecot ready.");
+        assert_eq!(service.starttls, Some(false));
+        assert_eq!(service.plaintext_auth, ["USER", "PLAIN"]);
+        assert_eq!(service.auth_evidence, ["USER", "SASL PLAIN"]);
+        assert_eq!(service.relay, None);
+    }
+
+    #[tokio::test]
+    async fn test_imap_login_disabled_before_tls() {
+        let (stream, received) = scripted(
+            "* OK [CAPABILITY IMAP4rev1] Dovecot ready.\r\n",
+            &[
+                (
+                    "A1 CAPABILITY",
+                    "* CAPABILITY IMAP4rev1 STARTTLS LOGINDISABLED AUTH=GSSAPI\r\na1 OK done\r\n",
+                ),
+                ("A2 LOGOUT", "* BYE\r\na2 OK\r\n"),
+            ],
+        );
+        let service = audit(stream, 143, None).await;
+        assert_eq!(service.banner, "[CAPABILITY IMAP4rev1] Dovecot ready.");
+        assert_eq!(service.starttls, Some(true));
+        assert!(service.plaintext_auth.is_empty());
+        assert_eq!(check_ids(&service).len(), 1);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(received.lock().unwrap()[0], "a1 CAPABILITY");
+    }
+
+    #[tokio::test]
+    async fn test_tls_port_is_not_flagged_for_plaintext() {
+        let (stream, _) = scripted(
+            "* OK ready\r\n",
+            &[(
+                "A1 CAPABILITY",
+                "* CAPABILITY IMAP4rev1 AUTH=PLAIN\r\na1 OK\r\n",
+            )],
+        );
+        let service = audit(stream, 993, None).await;
+        assert_eq!(service.starttls, None);
+        assert!(service.plaintext_auth.is_empty());
+        assert_eq!(check_ids(&service).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_non_mail_service_is_reported() {
+        let (stream, _) = scripted("SSH-2.0-OpenSSH_9.6\r\n", &[]);
+        let service = audit(stream, 25, None).await;
+        assert!(service.error.unwrap().contains("不是SMTP应答"));
+
+        // 服务端不应答时按超时中断
+        let (client, _server) = tokio::io::duplex(64);
+        let service = audit(client, 110, None).await;
+        assert_eq!(service.error.as_deref(), Some("等待应答超时"));
+    }
+}
@@ -1,3 +1,14 @@
+pub mod adinfo;
+pub mod enrich;
 pub mod fingerprint;
+pub mod honeypot;
+pub mod host_status;
+pub mod mail;
+pub mod nmap_xml;
+pub mod osguess;
 pub mod port_list;
 pub mod portscan;
+pub mod probes;
+pub mod retransmit;
+pub mod tui;
+pub mod udp_probes;
@@ -0,0 +1,520 @@
+use crate::commands::pentest::port_list::service_name;
+use crate::commands::pentest::portscan::PortScanResult;
+use crate::utils::output::OutputKind;
+use crate::utils::run_dir::RunDir;
+use crate::utils::salvage::salvage;
+use crate::utils::{compress_ports, output_file_path};
+use chrono::{DateTime, Local};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write;
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+/// nmap XML 结果的文件名（不含扩展名）
+pub const NMAP_XML_FILE_STEM: &str = "nmap";
+
+/// 生成 nmap XML 所需的运行信息
+#[derive(Debug, Clone)]
+pub struct NmapRunInfo {
+    /// 命令行参数
+    pub args: String,
+    /// 扫描开始时间
+    pub start: DateTime<Local>,
+    /// 扫描结束时间
+    pub end: DateTime<Local>,
+}
+
+/// 识别到的服务（对应 `<service>` 元素）
+#[derive(Debug, Clone, PartialEq)]
+struct Service {
+    name: String,
+    product: Option<String>,
+    version: Option<String>,
+    extrainfo: Option<String>,
+    /// 是否根据响应内容识别（否则只是按端口表推断）
+    probed: bool,
+}
+
+/// nmap 端口状态
+///
+/// 连接扫描中连接失败（被拒绝或超时）统一记为“关闭”，无法区分 closed 与 filtered；
+/// 探测超过硬性时限时连接已建立但应答异常缓慢，记为 open|filtered。
+fn port_state(result: &PortScanResult) -> &'static str {
+    match result.status.as_str() {
+        "开放" => "open",
+        "超时" => "open|filtered",
+        _ => "closed|filtered",
+    }
+}
+
+/// 根据识别证据和banner推断服务
+fn detect_service(result: &PortScanResult) -> Option<Service> {
+    let banner = result.banner.trim();
+    let probed = |name: &str| Service {
+        name: name.to_string(),
+        product: None,
+        version: None,
+        extrainfo: None,
+        probed: true,
+    };
+
+    for evidence in &result.evidence {
+        let service = match evidence.as_str() {
+            "ssh-banner" => {
+                // SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.1
+                let software = banner.splitn(3, '-').nth(2).unwrap_or("");
+                let (software, extra) = split_first_word(software);
+                let (product, version) = split_version(software, '_');
+                Service {
+                    product,
+                    version,
+                    extrainfo: extra,
+                    ..probed("ssh")
+                }
+            }
+            e if e.starts_with("mysql-handshake") => Service {
+                product: Some("MySQL".to_string()),
+                version: banner
+                    .strip_prefix("MySQL ")
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_string),
+                ..probed("mysql")
+            },
+            "rdp-response" => probed("ms-wbt-server"),
+            "http-probe" => {
+                // HTTP/1.1 200 OK | nginx/1.18.0 (Ubuntu)
+                let server = banner.split_once(" | ").map(|(_, s)| s).unwrap_or("");
+                let (software, extra) = split_first_word(server);
+                let (product, version) = split_version(software, '/');
+                Service {
+                    product,
+                    version,
+                    extrainfo: extra,
+                    ..probed("http")
+                }
+            }
+            _ => continue,
+        };
+        return Some(service);
+    }
+
+    // 只建立了连接或应答无法识别时按端口表推断
+    service_name(result.port).map(|name| Service {
+        name: name.to_ascii_lowercase(),
+        product: None,
+        version: None,
+        extrainfo: None,
+        probed: false,
+    })
+}
+
+/// 拆分首个单词及其余部分
+fn split_first_word(text: &str) -> (&str, Option<String>) {
+    match text.trim().split_once(' ') {
+        Some((first, rest)) if !rest.trim().is_empty() => (first, Some(rest.trim().to_string())),
+        Some((first, _)) => (first, None),
+        None => (text.trim(), None),
+    }
+}
+
+/// 拆分产品名称和版本（如 `OpenSSH_8.9p1`、`nginx/1.18.0`）
+fn split_version(software: &str, sep: char) -> (Option<String>, Option<String>) {
+    let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_string());
+    match software.split_once(sep) {
+        Some((product, version)) => (non_empty(product), non_empty(version)),
+        None => (non_empty(software), None),
+    }
+}
+
+/// 转义XML属性值
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' => out.push_str("&#10;"),
+            '\r' => out.push_str("&#13;"),
+            '\t' => out.push_str("&#9;"),
+            // XML 1.0 不允许其他控制字符
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// ctime 风格的时间（nmap 的 startstr/timestr 格式）
+fn ctime(time: &DateTime<Local>) -> String {
+    time.format("%a %b %e %H:%M:%S %Y").to_string()
+}
+
+/// 将端口扫描结果渲染为 nmap XML
+///
+/// 只有存在开放端口（或应答超时端口）的主机记为 up 并输出 `<host>`，
+/// 其余端口以 `<extraports>` 汇总。我们不掌握的信息（如 reason_ttl）直接省略。
+///
+/// # 参数
+/// * `results` - 扫描结果（全部端口）
+/// * `info` - 运行信息
+///
+/// # 返回
+/// * `String` - XML 文本
+pub fn render(results: &[PortScanResult], info: &NmapRunInfo) -> String {
+    let mut ports: Vec<u16> = results.iter().map(|r| r.port).collect();
+    ports.sort_unstable();
+    ports.dedup();
+
+    let mut hosts: BTreeMap<IpAddr, Vec<&PortScanResult>> = BTreeMap::new();
+    let mut unparsed: BTreeMap<&str, Vec<&PortScanResult>> = BTreeMap::new();
+    for r in results {
+        match r.ip.parse::<IpAddr>() {
+            Ok(ip) => hosts.entry(ip).or_default().push(r),
+            Err(_) => unparsed.entry(&r.ip).or_default().push(r),
+        }
+    }
+    let total = hosts.len() + unparsed.len();
+
+    let mut xml = String::new();
+    let _ = writeln!(xml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    let _ = writeln!(xml, "<!DOCTYPE nmaprun>");
+    let _ = writeln!(
+        xml,
+        "<nmaprun scanner=\"gxtools\" args=\"{}\" start=\"{}\" startstr=\"{}\" version=\"{}\" xmloutputversion=\"1.05\">",
+        escape(&info.args),
+        info.start.timestamp(),
+        ctime(&info.start),
+        env!("CARGO_PKG_VERSION"),
+    );
+    let _ = writeln!(
+        xml,
+        "<scaninfo type=\"connect\" protocol=\"tcp\" numservices=\"{}\" services=\"{}\"/>",
+        ports.len(),
+        compress_ports(&ports),
+    );
+    let _ = writeln!(xml, "<verbose level=\"0\"/>");
+    let _ = writeln!(xml, "<debugging level=\"0\"/>");
+
+    let mut up = 0;
+    for (ip, rows) in &hosts {
+        if !rows.iter().any(|r| port_state(r) != "closed|filtered") {
+            continue;
+        }
+        up += 1;
+        write_host(&mut xml, ip, rows);
+    }
+
+    let elapsed = (info.end - info.start).num_milliseconds().max(0) as f64 / 1000.0;
+    let _ = writeln!(xml, "<runstats>");
+    let _ = writeln!(
+        xml,
+        "<finished time=\"{}\" timestr=\"{}\" elapsed=\"{:.2}\" summary=\"{}\" exit=\"success\"/>",
+        info.end.timestamp(),
+        ctime(&info.end),
+        elapsed,
+        escape(&format!(
+            "gxtools done at {}; {} IP address{} ({} host{} up) scanned in {:.2} seconds",
+            ctime(&info.end),
+            total,
+            if total == 1 { "" } else { "es" },
+            up,
+            if up == 1 { "" } else { "s" },
+            elapsed
+        )),
+    );
+    let _ = writeln!(
+        xml,
+        "<hosts up=\"{}\" down=\"{}\" total=\"{}\"/>",
+        up,
+        total - up,
+        total
+    );
+    let _ = writeln!(xml, "</runstats>");
+    let _ = writeln!(xml, "</nmaprun>");
+    xml
+}
+
+/// 输出单个主机
+fn write_host(xml: &mut String, ip: &IpAddr, rows: &[&PortScanResult]) {
+    let addrtype = if ip.is_ipv4() { "ipv4" } else { "ipv6" };
+    let _ = writeln!(xml, "<host>");
+    let _ = writeln!(xml, "<status state=\"up\"/>");
+    let _ = writeln!(xml, "<address addr=\"{}\" addrtype=\"{}\"/>", ip, addrtype);
+
+    // 指向该IP的主机名写法（网段、范围等其他写法不是主机名）
+    let hostnames: Vec<&String> = rows[0]
+        .aliases
+        .iter()
+        .filter(|a| {
+            a.parse::<IpAddr>().is_err()
+                && !a.contains('/')
+                && a.chars().any(|c| c.is_ascii_alphabetic())
+        })
+        .collect();
+    if hostnames.is_empty() {
+        let _ = writeln!(xml, "<hostnames/>");
+    } else {
+        let _ = writeln!(xml, "<hostnames>");
+        for name in hostnames {
+            let _ = writeln!(xml, "<hostname name=\"{}\" type=\"user\"/>", escape(name));
+        }
+        let _ = writeln!(xml, "</hostnames>");
+    }
+
+    let _ = writeln!(xml, "<ports>");
+    let closed = rows
+        .iter()
+        .filter(|r| port_state(r) == "closed|filtered")
+        .count();
+    if closed > 0 {
+        let _ = writeln!(
+            xml,
+            "<extraports state=\"closed|filtered\" count=\"{}\"/>",
+            closed
+        );
+    }
+    let mut listed: Vec<&&PortScanResult> = rows
+        .iter()
+        .filter(|r| port_state(r) != "closed|filtered")
+        .collect();
+    listed.sort_by_key(|r| r.port);
+    for r in listed {
+        let _ = writeln!(xml, "<port protocol=\"tcp\" portid=\"{}\">", r.port);
+        let state = port_state(r);
+        if state == "open" {
+            let _ = writeln!(xml, "<state state=\"open\" reason=\"syn-ack\"/>");
+        } else {
+            let _ = writeln!(xml, "<state state=\"{}\"/>", state);
+        }
+        if let Some(service) = detect_service(r) {
+            let mut attrs = format!("name=\"{}\"", escape(&service.name));
+            for (key, value) in [
+                ("product", &service.product),
+                ("version", &service.version),
+                ("extrainfo", &service.extrainfo),
+            ] {
+                if let Some(value) = value {
+                    let _ = write!(attrs, " {}=\"{}\"", key, escape(value));
+                }
+            }
+            let (method, conf) = if service.probed {
+                ("probed", 10)
+            } else {
+                ("table", 3)
+            };
+            let _ = writeln!(
+                xml,
+                "<service {} method=\"{}\" conf=\"{}\"/>",
+                attrs, method, conf
+            );
+        }
+        let _ = writeln!(xml, "</port>");
+    }
+    let _ = writeln!(xml, "</ports>");
+    let _ = writeln!(xml, "</host>");
+}
+
+/// 写出 nmap XML 结果
+///
+/// # 参数
+/// * `results` - 扫描结果（全部端口）
+/// * `info` - 运行信息
+/// * `run_dir` - 本次运行的工作目录
+///
+/// # 返回
+/// * `Ok(PathBuf)` - 写入的文件路径（写入失败时为系统临时目录下的转存文件）
+pub fn write_nmap_xml(
+    results: &[PortScanResult],
+    info: &NmapRunInfo,
+    run_dir: Option<&RunDir>,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let content = render(results, info);
+    let written = output_file_path(NMAP_XML_FILE_STEM, "xml", OutputKind::PORTSCAN, run_dir)
+        .and_then(|path| {
+            fs::write(&path, &content)?;
+            Ok(path)
+        });
+    let path = match written {
+        Ok(path) => path,
+        Err(e) => {
+            let prefix = format!("{}_{}", OutputKind::PORTSCAN.prefix, NMAP_XML_FILE_STEM);
+            return salvage(&prefix, "xml", content.as_bytes(), &e.to_string());
+        }
+    };
+    if let Some(run) = run_dir {
+        let open = results.iter().filter(|r| r.is_open()).count();
+        run.record(&path, "xml", open)?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::pentest::portscan::CONNECT_EVIDENCE;
+    use crate::utils::provenance::Provenance;
+    use chrono::TimeZone;
+
+    fn result(
+        ip: &str,
+        port: u16,
+        status: &str,
+        banner: &str,
+        evidence: &[&str],
+    ) -> PortScanResult {
+        PortScanResult {
+            ip: ip.to_string(),
+            port,
+            status: status.to_string(),
+            banner: banner.to_string(),
+            evidence: evidence.iter().map(|e| e.to_string()).collect(),
+            suspected_honeypot: false,
+            aliases: Vec::new(),
+            tags: Default::default(),
+            geo: None,
+            egress: None,
+            verification: None,
+            failure: None,
+            knocked: false,
+            asset_changes: Vec::new(),
+            stage: None,
+            probes: BTreeMap::new(),
+            observed: Provenance::default(),
+        }
+    }
+
+    fn info() -> NmapRunInfo {
+        let start = Local.with_ymd_and_hms(2024, 1, 2, 10, 0, 0).unwrap();
+        NmapRunInfo {
+            args: "gxr pentest portscan -t 10.0.0.0/30 --format nmap-xml".to_string(),
+            start,
+            end: start + chrono::Duration::milliseconds(12_340),
+        }
+    }
+
+    #[test]
+    fn test_detect_service_from_banners() {
+        let ssh = result(
+            "10.0.0.1",
+            22,
+            "开放",
+            "SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.1",
+            &["ssh-banner"],
+        );
+        let service = detect_service(&ssh).unwrap();
+        assert_eq!(service.name, "ssh");
+        assert_eq!(service.product.as_deref(), Some("OpenSSH"));
+        assert_eq!(service.version.as_deref(), Some("8.9p1"));
+        assert_eq!(service.extrainfo.as_deref(), Some("Ubuntu-3ubuntu0.1"));
+
+        let http = result(
+            "10.0.0.1",
+            8080,
+            "开放",
+            "HTTP/1.1 200 OK | nginx/1.18.0 (Ubuntu)",
+            &["http-probe"],
+        );
+        let service = detect_service(&http).unwrap();
+        assert_eq!(service.name, "http");
+        assert_eq!(service.product.as_deref(), Some("nginx"));
+        assert_eq!(service.version.as_deref(), Some("1.18.0"));
+        assert!(service.probed);
+
+        // 只建立了连接：按端口表推断，不编造产品信息
+        let table = result("10.0.0.1", 3306, "开放", "MySQL", &[CONNECT_EVIDENCE]);
+        let service = detect_service(&table).unwrap();
+        assert_eq!(service.name, "mysql");
+        assert!(service.product.is_none());
+        assert!(!service.probed);
+    }
+
+    fn child<'a, 'input>(
+        node: roxmltree::Node<'a, 'input>,
+        name: &str,
+    ) -> roxmltree::Node<'a, 'input> {
+        node.children()
+            .find(|n| n.has_tag_name(name))
+            .unwrap_or_else(|| panic!("缺少 <{}>", name))
+    }
+
+    #[test]
+    fn test_render_is_valid_nmap_xml() {
+        let mut web = result(
+            "10.0.0.1",
+            80,
+            "开放",
+            "HTTP/1.0 200 OK | Apache \"<test>\" & co",
+            &["http-probe"],
+        );
+        web.aliases = vec!["web.example.com".to_string(), "10.0.0.0/30".to_string()];
+        let results = vec![
+            web,
+            result("10.0.0.1", 22, "关闭", "", &[]),
+            result("10.0.0.1", 443, "超时", "", &[]),
+            result("10.0.0.2", 22, "关闭", "", &[]),
+            result("10.0.0.2", 80, "关闭", "", &[]),
+            result("10.0.0.2", 443, "关闭", "", &[]),
+        ];
+        let xml = render(&results, &info());
+        // nmap 的输出带有 DOCTYPE 声明
+        let options = roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        };
+        let doc = roxmltree::Document::parse_with_options(&xml, options).unwrap();
+        let root = doc.root_element();
+        assert_eq!(root.tag_name().name(), "nmaprun");
+        assert_eq!(
+            root.attribute("start"),
+            Some(info().start.timestamp().to_string().as_str())
+        );
+
+        let scaninfo = child(root, "scaninfo");
+        assert_eq!(scaninfo.attribute("type"), Some("connect"));
+        assert_eq!(scaninfo.attribute("services"), Some("22,80,443"));
+
+        // 10.0.0.2 没有任何应答，不输出 <host>
+        let hosts: Vec<_> = root.children().filter(|n| n.has_tag_name("host")).collect();
+        assert_eq!(hosts.len(), 1);
+        let host = hosts[0];
+        assert_eq!(child(host, "address").attribute("addr"), Some("10.0.0.1"));
+        let names: Vec<_> = child(host, "hostnames")
+            .children()
+            .filter_map(|n| n.attribute("name"))
+            .collect();
+        assert_eq!(names, ["web.example.com"]);
+
+        let ports = child(host, "ports");
+        assert_eq!(child(ports, "extraports").attribute("count"), Some("1"));
+        let listed: Vec<_> = ports
+            .children()
+            .filter(|n| n.has_tag_name("port"))
+            .collect();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].attribute("portid"), Some("80"));
+        assert_eq!(child(listed[0], "state").attribute("state"), Some("open"));
+        assert!(child(listed[0], "state").attribute("reason_ttl").is_none());
+        assert_eq!(
+            child(listed[0], "service").attribute("extrainfo"),
+            Some("\"<test>\" & co")
+        );
+        assert_eq!(
+            child(listed[1], "state").attribute("state"),
+            Some("open|filtered")
+        );
+
+        let runstats = child(root, "runstats");
+        assert_eq!(
+            child(runstats, "finished").attribute("elapsed"),
+            Some("12.34")
+        );
+        let counts = child(runstats, "hosts");
+        assert_eq!(counts.attribute("up"), Some("1"));
+        assert_eq!(counts.attribute("down"), Some("1"));
+    }
+}
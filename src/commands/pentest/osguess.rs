@@ -0,0 +1,282 @@
+// src/commands/pentest/osguess.rs
+use serde::Serialize;
+use std::fmt;
+
+/// 置信度上限（粗略推测，不给出"确定"的结论）
+const MAX_CONFIDENCE: f64 = 0.9;
+
+/// 推测的操作系统类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum OsFamily {
+    Windows,
+    Linux,
+    Bsd,
+    MacOs,
+    /// 路由器、交换机、防火墙等
+    NetworkDevice,
+}
+
+impl fmt::Display for OsFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OsFamily::Windows => "Windows",
+            OsFamily::Linux => "Linux",
+            OsFamily::Bsd => "BSD",
+            OsFamily::MacOs => "macOS",
+            OsFamily::NetworkDevice => "网络设备",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 单个主机上收集到的可观测特征
+#[derive(Debug, Clone, Default)]
+pub struct OsSignals {
+    /// ICMP回复报文的TTL
+    pub ttl: Option<u8>,
+    /// SYN-ACK报文的窗口大小（仅在能获取原始报文时提供）
+    pub tcp_window: Option<u16>,
+    /// 开放端口
+    pub open_ports: Vec<u16>,
+    /// 各开放端口返回的banner
+    pub banners: Vec<String>,
+}
+
+/// 操作系统推测结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OsGuess {
+    /// 推测的系统类别
+    pub family: OsFamily,
+    /// 发行版或版本提示（如 Ubuntu、FreeBSD）
+    pub detail: Option<String>,
+    /// 置信度（0~1）
+    pub confidence: f64,
+    /// 推测依据
+    pub reasons: Vec<String>,
+}
+
+impl OsGuess {
+    /// 显示用名称，如 `Linux (Ubuntu)`
+    pub fn name(&self) -> String {
+        match self.detail {
+            Some(ref detail) => format!("{} ({})", self.family, detail),
+            None => self.family.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for OsGuess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "推测 {}（置信度 {:.0}%）",
+            self.name(),
+            self.confidence * 100.0
+        )
+    }
+}
+
+/// banner关键字 -> (系统类别, 发行版提示, 得分)
+const BANNER_HINTS: &[(&str, OsFamily, Option<&str>, f64)] = &[
+    ("microsoft-iis", OsFamily::Windows, None, 0.6),
+    ("microsoft-httpapi", OsFamily::Windows, None, 0.6),
+    ("openssh_for_windows", OsFamily::Windows, None, 0.6),
+    ("microsoft sql server", OsFamily::Windows, None, 0.4),
+    ("windows", OsFamily::Windows, None, 0.4),
+    ("ubuntu", OsFamily::Linux, Some("Ubuntu"), 0.6),
+    ("debian", OsFamily::Linux, Some("Debian"), 0.6),
+    ("raspbian", OsFamily::Linux, Some("Raspbian"), 0.6),
+    ("centos", OsFamily::Linux, Some("CentOS"), 0.6),
+    ("red hat", OsFamily::Linux, Some("RHEL"), 0.6),
+    (".el7", OsFamily::Linux, Some("RHEL/CentOS 7"), 0.5),
+    (".el8", OsFamily::Linux, Some("RHEL 8"), 0.5),
+    (".el9", OsFamily::Linux, Some("RHEL 9"), 0.5),
+    ("fedora", OsFamily::Linux, Some("Fedora"), 0.6),
+    ("alpine", OsFamily::Linux, Some("Alpine"), 0.5),
+    ("linux", OsFamily::Linux, None, 0.4),
+    ("freebsd", OsFamily::Bsd, Some("FreeBSD"), 0.6),
+    ("openbsd", OsFamily::Bsd, Some("OpenBSD"), 0.6),
+    ("netbsd", OsFamily::Bsd, Some("NetBSD"), 0.6),
+    ("darwin", OsFamily::MacOs, None, 0.5),
+    ("macos", OsFamily::MacOs, None, 0.5),
+    ("cisco", OsFamily::NetworkDevice, Some("Cisco"), 0.6),
+    ("mikrotik", OsFamily::NetworkDevice, Some("MikroTik"), 0.6),
+    ("routeros", OsFamily::NetworkDevice, Some("MikroTik"), 0.6),
+    ("huawei", OsFamily::NetworkDevice, Some("Huawei"), 0.5),
+    ("fortinet", OsFamily::NetworkDevice, Some("Fortinet"), 0.5),
+];
+
+/// 开放端口 -> (系统类别, 得分)
+const PORT_HINTS: &[(u16, OsFamily, f64)] = &[
+    (135, OsFamily::Windows, 0.3),
+    (139, OsFamily::Windows, 0.2),
+    (445, OsFamily::Windows, 0.3),
+    (3389, OsFamily::Windows, 0.3),
+    (5985, OsFamily::Windows, 0.3),
+    (548, OsFamily::MacOs, 0.3),
+    (62078, OsFamily::MacOs, 0.4),
+    (111, OsFamily::Linux, 0.1),
+    (2049, OsFamily::Linux, 0.1),
+];
+
+/// 单个类别的端口得分上限（避免多个同类端口叠加压过其他特征）
+const PORT_SCORE_LIMIT: f64 = 0.5;
+
+/// 根据已有的可观测特征粗略推测主机操作系统
+///
+/// 各特征按类别累加得分，得分最高的类别作为推测结果；
+/// 置信度同时考虑该类别得分的绝对值和在总得分中的占比，且不超过90%。
+/// 结果只是推测，特征可以被伪造或被中间设备改写（如NAT、负载均衡）。
+///
+/// # 参数
+/// * `signals` - 主机的可观测特征
+///
+/// # 返回
+/// * `Some(OsGuess)` - 推测结果
+/// * `None` - 没有可用的特征
+pub fn guess_os(signals: &OsSignals) -> Option<OsGuess> {
+    let mut scores: Vec<(OsFamily, f64)> = Vec::new();
+    let mut add = |family: OsFamily, score: f64| match scores.iter_mut().find(|(f, _)| *f == family)
+    {
+        Some(entry) => entry.1 += score,
+        None => scores.push((family, score)),
+    };
+    let mut reasons = Vec::new();
+    let mut details: Vec<(OsFamily, &str)> = Vec::new();
+
+    if let Some(ttl) = signals.ttl {
+        let (family, score, initial) = match ttl {
+            0..=64 => (OsFamily::Linux, 0.3, 64),
+            65..=128 => (OsFamily::Windows, 0.4, 128),
+            _ => (OsFamily::NetworkDevice, 0.4, 255),
+        };
+        add(family, score);
+        reasons.push(format!("TTL={}（初始值约{}）", ttl, initial));
+    }
+
+    if let Some(window) = signals.tcp_window {
+        let family = match window {
+            8192 | 64240 => Some(OsFamily::Windows),
+            5840 | 14600 | 29200 | 65160 => Some(OsFamily::Linux),
+            65535 => Some(OsFamily::Bsd),
+            4128 => Some(OsFamily::NetworkDevice),
+            _ => None,
+        };
+        if let Some(family) = family {
+            add(family, 0.3);
+            reasons.push(format!("TCP窗口={}", window));
+        }
+    }
+
+    let mut port_scores: Vec<(OsFamily, f64, Vec<u16>)> = Vec::new();
+    for &(port, family, score) in PORT_HINTS {
+        if !signals.open_ports.contains(&port) {
+            continue;
+        }
+        match port_scores.iter_mut().find(|(f, _, _)| *f == family) {
+            Some(entry) => {
+                entry.1 += score;
+                entry.2.push(port);
+            }
+            None => port_scores.push((family, score, vec![port])),
+        }
+    }
+    for (family, score, ports) in port_scores {
+        add(family, score.min(PORT_SCORE_LIMIT));
+        let ports: Vec<String> = ports.iter().map(|p| p.to_string()).collect();
+        reasons.push(format!("开放端口 {}", ports.join(",")));
+    }
+
+    for banner in &signals.banners {
+        let lower = banner.to_lowercase();
+        // 每个banner只取第一个命中的关键字，关键字按从具体到宽泛排列
+        if let Some(&(keyword, family, detail, score)) =
+            BANNER_HINTS.iter().find(|(k, ..)| lower.contains(k))
+        {
+            add(family, score);
+            reasons.push(format!("banner含「{}」", keyword));
+            if let Some(detail) = detail {
+                details.push((family, detail));
+            }
+        }
+    }
+
+    let total: f64 = scores.iter().map(|(_, s)| s).sum();
+    let (family, best) = scores
+        .iter()
+        .copied()
+        .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))?;
+
+    let confidence = ((best / total) * best.min(1.0)).min(MAX_CONFIDENCE);
+    let detail = details
+        .iter()
+        .find(|(f, _)| *f == family)
+        .map(|(_, d)| d.to_string());
+
+    Some(OsGuess {
+        family,
+        detail,
+        confidence,
+        reasons,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_signals_agree() {
+        let guess = guess_os(&OsSignals {
+            ttl: Some(127),
+            open_ports: vec![80, 135, 445, 3389],
+            banners: vec!["Microsoft-IIS/10.0".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(guess.family, OsFamily::Windows);
+        assert_eq!(guess.confidence, MAX_CONFIDENCE);
+        assert_eq!(guess.reasons.len(), 3);
+        assert!(guess.to_string().starts_with("推测 Windows"));
+    }
+
+    #[test]
+    fn test_openssh_banner_gives_distro() {
+        let guess = guess_os(&OsSignals {
+            ttl: Some(61),
+            open_ports: vec![22],
+            banners: vec!["SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.6".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(guess.name(), "Linux (Ubuntu)");
+        assert!(guess.confidence > 0.8);
+    }
+
+    #[test]
+    fn test_ttl_only_is_low_confidence() {
+        let guess = guess_os(&OsSignals {
+            ttl: Some(64),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(guess.family, OsFamily::Linux);
+        assert!(guess.confidence < 0.5);
+
+        assert!(guess_os(&OsSignals::default()).is_none());
+    }
+
+    #[test]
+    fn test_conflicting_signals_reduce_confidence() {
+        // Linux TTL + Windows端口 + SYN窗口，Windows得分更高但置信度下降
+        let guess = guess_os(&OsSignals {
+            ttl: Some(64),
+            tcp_window: Some(64240),
+            open_ports: vec![445, 3389],
+            banners: Vec::new(),
+        })
+        .unwrap();
+        assert_eq!(guess.family, OsFamily::Windows);
+        assert!(guess.confidence < 0.6);
+    }
+}
@@ -1,120 +1,209 @@
-use std::collections::HashMap;
-
-pub const DEFAULT_PORTS: &[u16] = &[
-    7, 19, 20, 21, 22, 23, 25, 53, 67, 68, 69, 80, 110, 111, 123, 135, 137, 138, 139, 143, 161,
-    162, 179, 389, 443, 445, 500, 514, 515, 520, 546, 547, 587, 631, 993, 995, 1080, 1433, 1521,
-    1723, 1883, 2049, 2082, 2083, 2181, 2375, 2379, 2483, 2484, 3306, 3389, 3690, 4369, 5000, 5001,
-    5222, 5223, 5432, 5672, 5800, 5900, 5984, 5985, 5986, 6000, 6379, 6443, 7001, 7002, 7199, 7443,
-    7777, 8000, 8009, 8080, 8081, 8443, 8500, 8600, 8765, 8888, 9000, 9042, 9090, 9200, 9300, 9418,
-    9999, 10000, 11211, 15672, 27017, 27018, 28017, 50070, 50075, 50470, 60020, 60030, 61616,
-    62078, 65535,5601,
+/// 端口表：(端口号, 服务名称, 是否纳入默认扫描)
+///
+/// 默认端口列表和服务名称查询均由此表派生，新增端口只需在此处添加一行。
+/// 表项必须按端口号升序排列且不重复（由单元测试保证）。
+pub const PORT_TABLE: &[(u16, &str, bool)] = &[
+    (7, "Echo", true),
+    (19, "Chargen", true),
+    (20, "FTP-Data", true),
+    (21, "FTP", true),
+    (22, "SSH", true),
+    (23, "Telnet", true),
+    (25, "SMTP", true),
+    (53, "DNS", true),
+    (67, "DHCP Server", true),
+    (68, "DHCP Client", true),
+    (69, "TFTP", true),
+    (80, "HTTP", true),
+    (88, "Kerberos", true),
+    (102, "Siemens S7", true),
+    (110, "POP3", true),
+    (111, "RPCbind", true),
+    (123, "NTP", true),
+    (135, "MS RPC", true),
+    (137, "NetBIOS Name Service", true),
+    (138, "NetBIOS Datagram Service", true),
+    (139, "NetBIOS Session Service", true),
+    (143, "IMAP", true),
+    (161, "SNMP", true),
+    (162, "SNMP Trap", true),
+    (179, "BGP", true),
+    (389, "LDAP", true),
+    (443, "HTTPS", true),
+    (445, "Microsoft-DS/SMB", true),
+    (465, "SMTPS", true),
+    (500, "ISAKMP/IKE", true),
+    (502, "Modbus", true),
+    (514, "Syslog", true),
+    (515, "LPD (Printer)", true),
+    (520, "RIP", true),
+    (546, "DHCPv6 Client", true),
+    (547, "DHCPv6 Server", true),
+    (587, "SMTP (Submission)", true),
+    (631, "IPP (Printer)", true),
+    (636, "LDAPS", true),
+    (873, "Rsync", true),
+    (993, "IMAPS", true),
+    (995, "POP3S", true),
+    (1080, "SOCKS Proxy", true),
+    (1099, "Java RMI", true),
+    (1433, "Microsoft SQL Server", true),
+    (1521, "Oracle Database", true),
+    (1723, "PPTP", true),
+    (1883, "MQTT", true),
+    (2049, "NFS", true),
+    (2082, "cPanel", true),
+    (2083, "cPanel (SSL)", true),
+    (2181, "Zookeeper", true),
+    (2222, "SSH Alt", true),
+    (2375, "Docker API", true),
+    (2376, "Docker API (TLS)", true),
+    (2379, "etcd", true),
+    (2483, "Oracle DB Listener (TCP)", true),
+    (2484, "Oracle DB Listener (SSL)", true),
+    (3000, "Grafana / HTTP Dev Server", true),
+    (3268, "LDAP Global Catalog", true),
+    (3306, "MySQL", true),
+    (3389, "RDP", true),
+    (3690, "Subversion (SVN)", true),
+    (4369, "Erlang Port Mapper", true),
+    (4443, "HTTPS Alt", true),
+    (4848, "GlassFish Admin", true),
+    (5000, "UPnP / Flask Dev Server", true),
+    (5001, "HTTP Alt", true),
+    (5060, "SIP", true),
+    (5222, "XMPP Client", true),
+    (5223, "XMPP Client (SSL)", true),
+    (5432, "PostgreSQL", true),
+    (5601, "Kibana", true),
+    (5672, "RabbitMQ/AMQP", true),
+    (5800, "VNC over HTTP", true),
+    (5900, "VNC", true),
+    (5984, "CouchDB", true),
+    (5985, "WinRM (HTTP)", true),
+    (5986, "WinRM (HTTPS)", true),
+    (6000, "X11", true),
+    (6379, "Redis", true),
+    (6443, "Kubernetes API", true),
+    (7001, "WebLogic Admin", true),
+    (7002, "WebLogic SSL", true),
+    (7199, "Cassandra JMX", true),
+    (7443, "HTTPS Alt", true),
+    (7474, "Neo4j", true),
+    (7777, "Oracle TNS", true),
+    (8000, "HTTP Alt (Dev Server)", true),
+    (8009, "AJP (Tomcat)", true),
+    (8080, "HTTP Alt (Proxy)", true),
+    (8081, "HTTP Alt", true),
+    (8088, "HTTP Alt (Hadoop YARN)", true),
+    (8089, "Splunk Management", true),
+    (8161, "ActiveMQ Web Console", true),
+    (8200, "Vault", true),
+    (8443, "HTTPS Alt", true),
+    (8500, "Consul", true),
+    (8600, "Consul DNS", true),
+    (8765, "WebSocket Test", true),
+    (8834, "Nessus", true),
+    (8888, "HTTP API / Jupyter", true),
+    (9000, "PHP-FPM / SonarQube", true),
+    (9042, "Cassandra CQL", true),
+    (9090, "Prometheus", true),
+    (9092, "Kafka", true),
+    (9100, "Node Exporter / JetDirect", true),
+    (9200, "Elasticsearch", true),
+    (9300, "Elasticsearch Internal", true),
+    (9418, "Git", true),
+    (9443, "HTTPS Alt", true),
+    (9999, "HBase / Debug Port", true),
+    (10000, "Webmin / Bacula", true),
+    (10250, "Kubelet API", true),
+    (11211, "Memcached", true),
+    (15672, "RabbitMQ Web UI", true),
+    (20000, "DNP3", true),
+    (27017, "MongoDB", true),
+    (27018, "MongoDB Alt", true),
+    (28017, "MongoDB Web Status", true),
+    (44818, "EtherNet/IP", true),
+    (50070, "Hadoop NameNode Web UI", true),
+    (50075, "Hadoop DataNode Web UI", true),
+    (50470, "Hadoop Secured NameNode", true),
+    (60020, "HBase Master", true),
+    (60030, "HBase RegionServer", true),
+    (61616, "Apache ActiveMQ", true),
+    (62078, "iTunes Sync Service", true),
+    (65535, "Reserved", true),
 ];
 
-lazy_static::lazy_static! {
-    pub static ref DEFAULT_PORT_BANNERS: HashMap<u16, &'static str> = {
-        let mut m = HashMap::new();
-        // 常见服务
-        m.insert(7, "Echo");
-        m.insert(19, "Chargen");
-        m.insert(20, "FTP-Data");
-        m.insert(21, "FTP");
-        m.insert(22, "SSH");
-        m.insert(23, "Telnet");
-        m.insert(25, "SMTP");
-        m.insert(53, "DNS");
-        m.insert(67, "DHCP Server");
-        m.insert(68, "DHCP Client");
-        m.insert(69, "TFTP");
-        m.insert(110, "POP3");
-        m.insert(111, "RPCbind");
-        m.insert(123, "NTP");
-        m.insert(135, "MS RPC");
-        m.insert(137, "NetBIOS Name Service");
-        m.insert(138, "NetBIOS Datagram Service");
-        m.insert(139, "NetBIOS Session Service");
-        m.insert(143, "IMAP");
-        m.insert(161, "SNMP");
-        m.insert(162, "SNMP Trap");
-        m.insert(179, "BGP");
-        m.insert(389, "LDAP");
-        m.insert(445, "Microsoft-DS/SMB");
-        m.insert(500, "ISAKMP/IKE");
-        m.insert(514, "Syslog");
-        m.insert(515, "LPD (Printer)");
-        m.insert(520, "RIP");
-        m.insert(546, "DHCPv6 Client");
-        m.insert(547, "DHCPv6 Server");
-        m.insert(587, "SMTP (submission)");
-        m.insert(631, "IPP (Printer)");
-        m.insert(993, "IMAPS");
-        m.insert(995, "POP3S");
-        m.insert(1080, "SOCKS Proxy");
-        m.insert(1433, "Microsoft SQL Server");
-        m.insert(1521, "Oracle Database");
-        m.insert(1723, "PPTP");
-        m.insert(1883, "MQTT");
-        m.insert(2049, "NFS");
-        m.insert(2082, "cPanel");
-        m.insert(2083, "cPanel (SSL)");
-        m.insert(2181, "Zookeeper");
-        m.insert(2375, "Docker API");
-        m.insert(2379, "etcd");
-        m.insert(2483, "Oracle DB Listener (TCP)");
-        m.insert(2484, "Oracle DB Listener (SSL)");
-        m.insert(3306, "MySQL");
-        m.insert(3389, "RDP");
-        m.insert(3690, "Subversion (SVN)");
-        m.insert(4369, "Erlang Port Mapper");
-        m.insert(5000, "UPnP / Flask Dev Server");
-        m.insert(5001, "HTTP Alternate");
-        m.insert(5222, "XMPP Client");
-        m.insert(5223, "XMPP Client SSL");
-        m.insert(5432, "PostgreSQL");
-        m.insert(5601, "Kibana");
-        m.insert(5672, "RabbitMQ/AMQP");
-        m.insert(5800, "VNC over HTTP");
-        m.insert(5900, "VNC");
-        m.insert(5984, "CouchDB");
-        m.insert(5985, "WinRM (HTTP)");
-        m.insert(5986, "WinRM (HTTPS)");
-        m.insert(6000, "X11");
-        m.insert(6379, "Redis");
-        m.insert(6443, "Kubernetes API");
-        m.insert(7001, "WebLogic Admin");
-        m.insert(7002, "WebLogic SSL");
-        m.insert(7199, "Cassandra JMX");
-        m.insert(7443, "HTTPS Alt");
-        m.insert(7777, "Oracle TNS");
-        m.insert(8000, "Common Dev Server");
-        m.insert(8009, "AJP (Tomcat)");
-        m.insert(8081, "HTTP-Alt2");
-        m.insert(8443, "HTTPS Alt");
-        m.insert(8500, "Consul");
-        m.insert(8600, "Consul DNS");
-        m.insert(8765, "WebSocket Test");
-        m.insert(8888, "HTTP API / Jupyter");
-        m.insert(9000, "PHP-FPM / SonarQube");
-        m.insert(9042, "Cassandra CQL");
-        m.insert(9090, "Prometheus");
-        m.insert(9200, "Elasticsearch");
-        m.insert(9300, "Elasticsearch Internal");
-        m.insert(9418, "Git");
-        m.insert(9999, "HBase / Debug Port");
-        m.insert(10000, "Webmin / Bacula");
-        m.insert(11211, "Memcached");
-        m.insert(15672, "RabbitMQ Web UI");
-        m.insert(27017, "MongoDB");
-        m.insert(27018, "MongoDB Alt");
-        m.insert(28017, "MongoDB Web Status");
-        m.insert(50070, "Hadoop NameNode Web UI");
-        m.insert(50075, "Hadoop DataNode Web UI");
-        m.insert(50470, "Hadoop Secured NameNode");
-        m.insert(60020, "HBase Master");
-        m.insert(60030, "HBase RegionServer");
-        m.insert(61616, "Apache ActiveMQ");
-        m.insert(62078, "iTunes Sync Service");
-        m.insert(65535, "Reserved");
+/// 默认扫描的端口数量
+const DEFAULT_PORT_COUNT: usize = {
+    let mut count = 0;
+    let mut i = 0;
+    while i < PORT_TABLE.len() {
+        if PORT_TABLE[i].2 {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+};
+
+/// 默认扫描的端口列表（由 [`PORT_TABLE`] 在编译期派生，升序）
+pub const DEFAULT_PORTS: &[u16] = &{
+    let mut ports = [0u16; DEFAULT_PORT_COUNT];
+    let mut n = 0;
+    let mut i = 0;
+    while i < PORT_TABLE.len() {
+        if PORT_TABLE[i].2 {
+            ports[n] = PORT_TABLE[i].0;
+            n += 1;
+        }
+        i += 1;
+    }
+    ports
+};
+
+/// 查询端口对应的常见服务名称
+///
+/// # 参数
+/// * `port` - 端口号
+///
+/// # 返回
+/// * `Some(&str)` - 服务名称
+/// * `None` - 端口不在端口表中
+pub fn service_name(port: u16) -> Option<&'static str> {
+    PORT_TABLE
+        .binary_search_by_key(&port, |&(p, _, _)| p)
+        .ok()
+        .map(|i| PORT_TABLE[i].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_port_table_sorted_and_unique() {
+        assert!(PORT_TABLE.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(DEFAULT_PORTS.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_every_default_port_has_label() {
+        for &port in DEFAULT_PORTS {
+            let name = service_name(port);
+            assert!(
+                name.is_some_and(|n| !n.trim().is_empty()),
+                "端口 {} 缺少服务名称",
+                port
+            );
+        }
+    }
 
-        m
-    };
+    #[test]
+    fn test_service_name_lookup() {
+        assert_eq!(service_name(8080), Some("HTTP Alt (Proxy)"));
+        assert_eq!(service_name(22), Some("SSH"));
+        assert_eq!(service_name(1), None);
+        assert!(DEFAULT_PORTS.contains(&5601));
+    }
 }
@@ -1,17 +1,87 @@
-use crate::commands::net::ping::ping_concurrent_async;
-use crate::commands::pentest::fingerprint::load_fingerprints;
+use crate::commands::doctor::check_ping_program;
+use crate::commands::history::RunSummary;
+use crate::commands::net::ping::{
+    PING_PROGRAM, PingOptions, PingResult, Pinger, SystemPinger, ping_concurrent_async,
+    ping_streamed,
+};
+use crate::commands::pentest::honeypot::{HoneypotConfig, HostAssessment, assess_hosts};
+use crate::commands::pentest::host_status::{
+    ConnectFailure, HostOutcome, HostStatus, HostTracker, host_outcomes, host_status_sheet,
+    status_counts,
+};
+use crate::commands::pentest::nmap_xml::{NmapRunInfo, write_nmap_xml};
+use crate::commands::pentest::osguess::{OsGuess, OsSignals, guess_os};
 use crate::commands::pentest::port_list::*;
-use crate::utils::{ScanProgress, parse_ports, parse_targets, save_to_excel};
-use clap::Parser;
-use futures::stream::{FuturesUnordered, StreamExt};
+use crate::commands::pentest::probes::{
+    self, ProbeConnect, ProbeRegistry, ProbeStream, ProbeValue, classify_banner, fallback_banner,
+};
+use crate::commands::pentest::retransmit::{HostRtt, ProbeStage, RetransmitArgs, RetransmitPlan};
+use crate::commands::pentest::tui::{self, TuiControl, TuiExit};
+use crate::commands::profile::ProfileOptions;
+use crate::commands::report::load_run_rows;
+use crate::commands::resources;
+use crate::utils::adaptive::AdaptiveController;
+use crate::utils::assets::{self, AssetArgs, AssetDb, ChangeCounts, Observation, change_notes};
+use crate::utils::blocking::{BlockingStage, run_stage};
+use crate::utils::console::Icon;
+use crate::utils::context::ScanContext;
+use crate::utils::dualstack::{self, dual_stack_sheet};
+use crate::utils::finding::{
+    Finding, FindingFormat, FindingParams, Severity, consolidate, write_findings,
+};
+use crate::utils::geo::{GeoArgs, GeoInfo, GeoLookup, asn_rollup_sheet};
+use crate::utils::knock::{KnockArgs, Knocker};
+use crate::utils::limits::{ConcurrencySpec, ScanKind, effective_concurrency};
+use crate::utils::metrics::{ProbeTiming, ScanMetrics};
+use crate::utils::output::OutputKind;
+use crate::utils::plan::{PlanArgs, ScanPlan};
+use crate::utils::pool::{run_streamed, run_tracked_streamed};
+use crate::utils::provenance::{Provenance, describe_staleness};
+use crate::utils::redact::redactor;
+use crate::utils::run_dir::{
+    CONCURRENCY_FILE_NAME, DUAL_STACK_FILE_NAME, FINDINGS_FILE_NAME, HOST_STATUS_FILE_NAME,
+    HOSTS_FILE_NAME, PORTS_FILE_NAME, SummaryItem, TARGET_OVERRIDES_FILE_NAME,
+};
+use crate::utils::sample::{Estimate, RandomizeArgs, Sample, SampleArgs};
+use crate::utils::script::{
+    HookPoint, ScriptArgs, ScriptFetch, ScriptHook, ScriptRow, ScriptStage, print_derived,
+};
+use crate::utils::snapshot::{
+    ResultCollector, SnapshotArgs, SnapshotWriter, Snapshotter, snapshot_file_name,
+    write_json_snapshot,
+};
+use crate::utils::stats::{Outcome, ScanStats};
+use crate::utils::targets::{
+    Tags, TargetSourceArgs, alias_suffix, collect_targets, overrides_summary_item, print_overrides,
+    tag_keys,
+};
+use crate::utils::timing::{Throttle, Timing, TimingArgs, TimingDefaults};
+use crate::utils::transcript::{Tape, TapeStream, TranscriptArgs};
+use crate::utils::verify::{Verification, VerifyArgs, VerifyKind};
+use crate::utils::{
+    ExcelExport, ExcelOptions, ExcelSheet, ScanProgress, format_duration, format_elapsed,
+    ip_sort_key, parse_ports_strict,
+};
+use clap::{Parser, ValueEnum};
+use futures::future;
+use futures::future::BoxFuture;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use indicatif::{MultiProgress, ProgressDrawTarget};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::error::Error;
-use std::sync::Arc;
+use std::future::Future;
+use std::io;
+use std::io::IsTerminal;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tokio::net::TcpStream;
-use tokio::sync::{Mutex, Semaphore};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+use tokio::net::{TcpSocket, TcpStream};
+use tokio::time::timeout;
 
 /// 端口扫描参数配置
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Serialize, Deserialize)]
 pub struct PortScanArgs {
     /// 目标IP或IP段（支持CIDR、范围、多个IP用逗号隔开）
     ///
@@ -20,56 +90,269 @@ pub struct PortScanArgs {
     /// - 多个IP: 192.168.1.1,192.168.1.2
     /// - IP范围: 192.168.1.1-10
     /// - CIDR: 192.168.1.0/24
-    #[arg(short, long, value_name = "TARGET")]
-    pub targets: String,
+    /// - 按段通配: 192.168.*.*、10.0.1-5.1
+    /// - 标准输入: -（每行一个或逗号隔开，# 之后为注释，如 cat scope.txt | gxtools pentest portscan -t -）
+    #[arg(
+        short,
+        long,
+        value_name = "TARGET",
+        required_unless_present_any = ["target_xlsx", "target_file", "profile"]
+    )]
+    pub targets: Option<String>,
 
-    /// 自定义端口列表（用逗号隔开，支持范围）
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub sources: TargetSourceArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub sample: SampleArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub order: RandomizeArgs,
+
+    /// 自定义端口列表（用逗号隔开，支持范围和排除项）
+    ///
+    /// 语法（先合并包含项，再减去排除项）：
+    /// - 单个端口: 22
+    /// - 端口范围: 8000-9000
+    /// - 开放式范围: 8000-（至65535）
+    /// - 全部端口: all（1-65535）
+    /// - 排除项: 以 ! 开头，如 !445、!135-139
     ///
-    /// 示例：22,80,443,8000-9000
-    #[arg(short, long, value_name = "PORTS")]
+    /// 示例：22,80,443,8000-9000 或 all,!135-139,!445
+    #[arg(short, long, env = "GXTOOLS_PORTS", value_name = "PORTS")]
     pub ports: Option<String>,
 
     /// 扫描全部端口（1-65535）
-    #[arg(long)]
+    #[arg(long, env = "GXTOOLS_FULL")]
     pub full: bool,
 
-    /// 最大并发数
-    #[arg(short = 'c', long, default_value = "200", value_name = "NUM")]
-    pub concurrency: usize,
+    /// 最大并发数（auto 表示根据文件描述符上限和任务数量自动选择，auto-adaptive 表示按出错率在 --min 与 --max 之间自动调整），默认200，随 --timing 调整
+    #[arg(
+        short = 'c',
+        long,
+        env = "GXTOOLS_CONCURRENCY",
+        value_name = "NUM|auto|auto-adaptive"
+    )]
+    pub concurrency: Option<ConcurrencySpec>,
+
+    /// 连接及读取超时时间（秒），每个探测阶段另有硬性时限兜底，默认3秒，随 --timing 调整
+    #[arg(short = 'T', long, env = "GXTOOLS_TIMEOUT", value_name = "SECS")]
+    pub timeout: Option<u64>,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub timing: TimingArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub retransmit: RetransmitArgs,
+
+    /// 本地出口地址（多网卡时使用，多个用逗号隔开）：经第一个出口不可达或超时的端口依次改用后续出口重试
+    #[arg(
+        long = "sources",
+        visible_alias = "source",
+        env = "GXTOOLS_SOURCES",
+        value_delimiter = ',',
+        value_name = "IP"
+    )]
+    #[serde(default)]
+    pub egress: Vec<IpAddr>,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub knock: KnockArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub geo: GeoArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub script: ScriptArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub transcript: TranscriptArgs,
 
     /// 是否输出结果到Excel文件
-    #[arg(short = 'o', long)]
+    #[arg(short = 'o', long, env = "GXTOOLS_OUTPUT")]
     pub output: bool,
 
+    /// 端口开放后执行的补充探测（多个用逗号隔开，可用 banner、tls、webinfo、favicon，none 表示不探测），默认 banner
+    #[arg(
+        long,
+        env = "GXTOOLS_PROBES",
+        value_delimiter = ',',
+        value_name = "PROBE"
+    )]
+    #[serde(default)]
+    pub probes: Vec<String>,
+
     /// 先进行主机存活探测（Ping扫描）
-    #[arg(long)]
+    #[arg(long, env = "GXTOOLS_LIVE")]
     pub live: bool,
+
+    /// 按主机检测疑似蜜罐/tarpit（检测参数可在配置文件的 honeypot 段落中调整）
+    #[arg(long, env = "GXTOOLS_DETECT_HONEYPOT")]
+    pub detect_honeypot: bool,
+
+    /// 根据TTL、开放端口和banner粗略推测主机操作系统（结果仅为推测）
+    #[arg(long, env = "GXTOOLS_OS_GUESS")]
+    pub os_guess: bool,
+
+    /// 打开交互界面实时浏览结果（需在终端中运行）
+    #[arg(long)]
+    pub tui: bool,
+
+    /// 只解析目标及端口并打印扫描计划（含目标参数覆盖），不发送任何探测
+    #[arg(long)]
+    #[serde(skip)]
+    pub dry_run: bool,
+
+    #[command(flatten)]
+    #[serde(skip)]
+    pub plan: PlanArgs,
+
+    /// 另外导出的结果格式（多个格式用逗号隔开）
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "FORMAT")]
+    pub format: Vec<OutputFormat>,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub snapshot: SnapshotArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub verify: VerifyArgs,
+
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub assets: AssetArgs,
+
+    #[command(flatten)]
+    #[serde(skip)]
+    pub profile_args: ProfileOptions,
+}
+
+/// 端口扫描的附加导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// 标准化发现列表（JSON），供漏洞管理平台导入
+    VmJson,
+    /// 标准化发现列表（CSV），供漏洞管理平台导入
+    VmCsv,
+    /// nmap XML，供只接受 nmap 结果的下游工具使用
+    NmapXml,
 }
 
+/// 端口扫描在 normal 时序模板下的默认参数
+pub const TIMING_DEFAULTS: TimingDefaults = TimingDefaults {
+    concurrency: 200,
+    timeout_secs: 3,
+    retries: 0,
+};
+
+/// 仅建立了TCP连接、没有收到任何数据时的证据标记
+pub const CONNECT_EVIDENCE: &str = "tcp-connect";
+
 /// 端口扫描结果
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PortScanResult {
     /// IP地址
     pub ip: String,
     /// 端口号
     pub port: u16,
-    /// 状态（开放/关闭）
+    /// 状态（开放/关闭/超时）
     pub status: String,
     /// 服务banner信息
     pub banner: String,
     /// 识别证据列表
     pub evidence: Vec<String>,
+    /// 所在主机是否疑似蜜罐（开启 --detect-honeypot 时在扫描结束后标记）
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub suspected_honeypot: bool,
+    /// 指向该IP的其他目标写法（如主机名、重叠的网段）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// 目标标签（来自 --tag-columns 及 --tag）
+    #[serde(skip_serializing_if = "Tags::is_empty")]
+    pub tags: Tags,
+    /// 地理位置及ASN（开启 --enrich-geo 时查询有开放端口的主机）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geo: Option<GeoInfo>,
+    /// 最终连通目标的本地出口地址（指定 --sources 时记录，各出口均不通时为空）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub egress: Option<IpAddr>,
+    /// 复核标注（开启 --verify 时）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+    /// 探测前是否对该主机敲过门（开启 --knock 时，说明平时被过滤的端口为何开放）
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub knocked: bool,
+    /// 所在主机与资产库相比的变化（开启资产库时）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub asset_changes: Vec<String>,
+    /// 得出结论的探测阶段（指定 --timeout-first 或 --timeout-retry 时）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage: Option<ProbeStage>,
+    /// 补充探测得到的值（字段名见 [`ProbeRegistry::builtin`] 中各探测声明的列）
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub probes: BTreeMap<String, ProbeValue>,
+    /// 观测时间及探测方式
+    #[serde(flatten)]
+    pub observed: Provenance,
+    /// 端口未开放时连接失败的方式（用于判断主机是否扫描到）
+    #[serde(skip)]
+    pub failure: Option<ConnectFailure>,
+}
+
+impl ScriptRow for PortScanResult {
+    fn asset(&self) -> &str {
+        &self.ip
+    }
+
+    fn port(&self) -> Option<u16> {
+        Some(self.port)
+    }
+
+    fn scripted(&self) -> bool {
+        self.is_open()
+    }
+
+    fn fields_mut(&mut self) -> &mut Tags {
+        &mut self.tags
+    }
+
+    fn observed(&self) -> Option<&Provenance> {
+        Some(&self.observed)
+    }
 }
 
 impl PortScanResult {
     /// 创建开放端口的结果
-    fn open(ip: String, port: u16, banner: String, evidence: Vec<String>) -> Self {
+    pub fn open(ip: String, port: u16, banner: String, evidence: Vec<String>) -> Self {
         Self {
             ip,
             port,
             status: "开放".to_string(),
             banner,
             evidence,
+            suspected_honeypot: false,
+            aliases: Vec::new(),
+            tags: Tags::new(),
+            geo: None,
+            egress: None,
+            verification: None,
+            failure: None,
+            knocked: false,
+            asset_changes: Vec::new(),
+            stage: None,
+            probes: BTreeMap::new(),
+            observed: Provenance::now(format!("tcp-connect:{}", port)),
         }
     }
 
@@ -81,6 +364,41 @@ impl PortScanResult {
             status: "关闭".to_string(),
             banner: String::new(),
             evidence: Vec::new(),
+            suspected_honeypot: false,
+            aliases: Vec::new(),
+            tags: Tags::new(),
+            geo: None,
+            egress: None,
+            verification: None,
+            failure: None,
+            knocked: false,
+            asset_changes: Vec::new(),
+            stage: None,
+            probes: BTreeMap::new(),
+            observed: Provenance::now(format!("tcp-connect:{}", port)),
+        }
+    }
+
+    /// 创建超时的结果（探测超过硬性时限被中止）
+    fn timeout(ip: String, port: u16) -> Self {
+        Self {
+            ip,
+            port,
+            status: "超时".to_string(),
+            banner: String::new(),
+            evidence: Vec::new(),
+            suspected_honeypot: false,
+            aliases: Vec::new(),
+            tags: Tags::new(),
+            geo: None,
+            egress: None,
+            verification: None,
+            failure: None,
+            knocked: false,
+            asset_changes: Vec::new(),
+            stage: None,
+            probes: BTreeMap::new(),
+            observed: Provenance::now(format!("tcp-connect:{}", port)),
         }
     }
 
@@ -88,239 +406,2759 @@ impl PortScanResult {
     pub fn is_open(&self) -> bool {
         self.status == "开放"
     }
-}
 
-pub async fn run(args: &PortScanArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let start = Instant::now();
+    /// 计入扫描统计的结论：开放为成功，关闭或被过滤为失败，探测超过硬性时限为出错
+    pub fn outcome(&self) -> Outcome {
+        match self.status.as_str() {
+            "开放" => Outcome::Succeeded,
+            "超时" => Outcome::Errored,
+            _ => Outcome::Failed,
+        }
+    }
 
-    // 加载指纹库
-    let fps = load_fingerprints("fingerprints.yaml")?;
+    /// 首探是否超时（探测超过硬性时限，或连接未得到目标任何应答），两段式探测时需要重探
+    pub fn timed_out(&self) -> bool {
+        self.status == "超时" || self.failure == Some(ConnectFailure::Filtered)
+    }
 
-    // 解析目标IP列表
-    let ips = parse_targets(&args.targets)?;
+    /// 是否从该端口收到过数据（仅建立连接不算）
+    pub fn received_data(&self) -> bool {
+        self.evidence.iter().any(|e| e != CONNECT_EVIDENCE)
+    }
+}
 
-    // 如果启用了存活探测，先进行Ping扫描
-    let live_ips = if args.live {
-        println!("🔍 开始主机存活探测...");
-        let ping_progress = ScanProgress::new(ips.len() as u64);
-        let ping_results = ping_concurrent_async(ips.clone(), 3, 2, 100, &ping_progress).await?;
+pub async fn run(args: &PortScanArgs) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    run_with(args, &ScanContext::cli()).await
+}
 
-        let alive: Vec<String> = ping_results
-            .into_iter()
-            .filter(|r| r.is_success())
-            .map(|r| r.ip)
-            .collect();
+/// 在指定上下文中执行端口扫描（供守护进程等非命令行调用方使用）
+///
+/// # 参数
+/// * `args` - 端口扫描参数
+/// * `ctx` - 扫描上下文（进度、暂停、取消及结果推送）
+///
+/// # 返回
+/// * `Ok(RunSummary)` - 扫描完成（或被取消）后的结果摘要
+/// * `Err` - 扫描过程中发生错误
+pub async fn run_with(
+    args: &PortScanArgs,
+    ctx: &ScanContext,
+) -> Result<RunSummary, Box<dyn Error + Send + Sync>> {
+    let start = Instant::now();
+    let started_at = chrono::Local::now();
 
-        println!("✅ 发现 {} 个存活主机", alive.len());
-        alive
+    if args.tui {
+        tui::ensure_tty()?;
+    }
+
+    // 加载指纹库，补充探测在发送任何流量前校验
+    let fps = resources::store()?.fingerprints().to_vec();
+    let probes = ProbeRegistry::builtin().select(&args.probes)?;
+    let honeypot_config = if args.detect_honeypot {
+        Some(HoneypotConfig::load()?)
     } else {
-        ips
+        None
     };
 
-    if live_ips.is_empty() {
-        return Err("没有有效的IP地址可供扫描".into());
-    }
+    // 开启抽样时只扫描样本，目标空间不展开
+    let sample = Sample::draw(args.targets.as_deref(), &args.sample)?;
+    let sample_spec = sample.as_ref().map(Sample::spec);
+    // 解析目标，同一IP只扫描一次
+    let target = sample_spec.as_deref().or(args.targets.as_deref());
+    let mut targets = collect_targets(target, &args.sources).await?;
+    args.sample.apply_limit(&mut targets);
+    // 数据库在扫描前打开，缺失时尽早提示
+    let geo = GeoLookup::open(&args.geo);
 
-    // 确定要扫描的端口列表
-    let ports: Vec<u16> = if args.full {
-        println!("⚠️  全端口扫描模式（1-65535）");
+    // 确定要扫描的端口列表（在发送任何探测流量之前校验）
+    let mut ports: Vec<u16> = if args.full {
+        println!("{} 全端口扫描模式（1-65535）", Icon::Warn);
         (1..=65535).collect()
     } else if let Some(ref port_str) = args.ports {
-        let parsed = parse_ports(port_str);
-        if parsed.is_empty() {
-            return Err("未解析到任何有效端口".into());
-        }
+        let parsed = parse_ports_strict(port_str)?;
+        println!("{} 端口解析完成: 共 {} 个端口", Icon::Config, parsed.len());
         parsed
     } else {
         DEFAULT_PORTS.to_vec()
     };
 
-    let total_tasks = (live_ips.len() * ports.len()) as u64;
+    // 时序参数对存活探测和端口扫描两个阶段都生效
+    let timing = Timing::resolve(
+        &args.timing,
+        TIMING_DEFAULTS,
+        args.concurrency,
+        args.timeout,
+        None,
+    );
+    let tape = Tape::open(&args.transcript, "pentest portscan")?;
+    let ctx = &ctx
+        .clone()
+        .with_throttle(Throttle::for_targets(&timing, &targets))
+        .with_target_overrides(&targets)
+        .with_tape(tape);
+    // 脚本在扫描前编译，出错时尽早报错；http_get 只能访问本次的目标，回放时不可用
+    let fetch = match ctx.tape() {
+        Tape::Replay(_) => None,
+        _ if args.script.script.is_none() => None,
+        _ => Some(ScriptFetch::new(ctx, &targets)?),
+    };
+    let script = ScriptHook::load(&args.script, "pentest portscan", fetch)?;
+    // 资产库在扫描前打开，结构版本不兼容时尽早报错；回放的是旧结果，不更新资产库
+    let asset_db = match ctx.tape() {
+        Tape::Replay(_) => None,
+        _ => AssetDb::open_for_run(&args.assets)?,
+    };
+    println!("{} 时序: {}（{}）", Icon::Config, timing.template, timing);
+    if !ctx.tape().is_replay() {
+        check_egress(&args.egress)?;
+    }
+    // 复核基线在扫描前读取，运行目录无效时尽早报错
+    let baseline = match args.verify.verify_baseline {
+        Some(ref run) if args.verify.wants(VerifyKind::Baseline) => baseline_open(run)?,
+        _ => HashSet::new(),
+    };
+    if !args.egress.is_empty() {
+        let egress: Vec<String> = args.egress.iter().map(IpAddr::to_string).collect();
+        println!("{} 出口: {}（依次尝试）", Icon::Config, egress.join(" → "));
+    }
+    // 辅助的ping阶段并发不超过端口扫描的并发
+    let ping_spec = match timing.concurrency {
+        ConcurrencySpec::Fixed(n) => ConcurrencySpec::Fixed(n.min(100)),
+        ConcurrencySpec::Auto | ConcurrencySpec::Adaptive { .. } => ConcurrencySpec::Fixed(100),
+    };
+
+    // 上下文中的暂停开关覆盖存活探测和端口扫描两个阶段
+    let pause = &ctx.pause;
+    let interactive = ctx.interactive;
+
+    // 存活探测得到的TTL（用于操作系统推测）
+    let mut ttls: HashMap<String, u8> = HashMap::new();
+
+    let mut metrics = ScanMetrics::new();
+    if args.live && !ctx.tape().is_replay() && !args.dry_run {
+        check_ping_program(PING_PROGRAM).await.require()?;
+    }
+    if targets.is_empty() {
+        return Err("没有有效的IP地址可供扫描".into());
+    }
+
+    // 开启存活探测时两个阶段流水线执行：主机确认存活后立即开始扫描其端口，
+    // 不必等全部主机探测完毕，端口扫描的任务总数随存活主机的发现逐步增加
+    let total_tasks = (targets.len() * ports.len()) as u64;
+    if args.live {
+        println!(
+            "{} 开始存活探测及端口扫描: {} 个IP，存活主机确认后立即扫描 {} 个端口",
+            Icon::Scan,
+            targets.len(),
+            ports.len()
+        );
+    } else {
+        println!(
+            "{} 开始端口扫描: {} 个IP × {} 个端口 = {} 个任务",
+            Icon::Scan,
+            targets.len(),
+            ports.len(),
+            total_tasks
+        );
+    }
+    let concurrency = effective_concurrency(
+        timing.concurrency,
+        total_tasks.try_into().unwrap_or(usize::MAX),
+        ScanKind::Connect,
+    );
+    // 自适应并发按端口扫描的并发定上限，存活探测与端口扫描共用节流器中的许可
+    let ctx = &ctx.clone().with_adaptive(AdaptiveController::for_spec(
+        timing.concurrency,
+        concurrency.value,
+        ctx.tape().is_replay(),
+    ));
     println!(
-        "🔍 开始端口扫描: {} 个IP × {} 个端口 = {} 个任务",
-        live_ips.len(),
-        ports.len(),
-        total_tasks
+        "{} 配置: 并发={}（{}）, 超时={}秒",
+        Icon::Config,
+        concurrency.value,
+        concurrency.reason,
+        timing.timeout_secs
+    );
+    let overrides = targets.override_specs();
+    print_overrides(&overrides);
+    // 签批的扫描计划：试运行时导出，执行前核对本次展开的范围
+    let approved = args.plan.check(&ScanPlan::new(
+        "pentest portscan",
+        &targets,
+        Some(&ports),
+        timing.retries + 1,
+        args,
+    ))?;
+    if args.dry_run {
+        println!("{} 试运行，未发送任何探测", Icon::Ok);
+        return Ok(RunSummary {
+            total: 0,
+            succeeded: 0,
+            outputs: Vec::new(),
+            concurrency: Some(concurrency),
+            timing: Some(timing),
+        });
+    }
+    let probe_timeout = Duration::from_secs(timing.timeout_secs.max(1));
+    // 打乱主机及端口的探测顺序（扫描计划已按原顺序核对），结果仍按IP排序；
+    // 不打乱时目标按需从集合中取出，不展开为IP列表
+    let shuffled_ips = args.order.shuffler().map(|mut shuffler| {
+        let mut ips = targets.ips();
+        shuffler.shuffle(&mut ips);
+        shuffler.shuffle(&mut ports);
+        ips
+    });
+    let shuffled = shuffled_ips.is_some();
+    let hosts = || -> Box<dyn Iterator<Item = String> + Send + '_> {
+        match shuffled_ips {
+            Some(ref ips) => Box::new(ips.iter().cloned()),
+            None => Box::new(targets.iter_ips()),
+        }
+    };
+
+    // 初始化进度条，存活探测的进度条与端口扫描的一同绘制；
+    // 存活探测结果只用于筛选目标，不推送给上下文的接收方
+    let ping_ctx = ctx.without_results();
+    let ping_progress = ping_ctx.new_progress(targets.len() as u64);
+    let progress = ctx.new_progress(if args.live { 0 } else { total_tasks });
+    if args.live {
+        let multi = if interactive && !args.tui {
+            MultiProgress::new()
+        } else {
+            MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+        };
+        ping_progress.attach(&multi);
+        progress.attach(&multi);
+    }
+
+    // 交互界面只消费结果副本，关闭界面不影响扫描
+    let (tui_tx, tui_handle) = if args.tui {
+        progress.set_hidden(true);
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let control = TuiControl {
+            pause: pause.clone(),
+            cancel: ctx.token().clone(),
+            progress: progress.clone(),
+            export: ctx.excel_options(),
+        };
+        let handle = tokio::task::spawn_blocking(move || tui::run(rx, control));
+        (Some(tx), Some(handle))
+    } else {
+        (None, None)
+    };
+    // 交互界面自行处理按键，此时只监听信号（暂停同时作用于存活探测）
+    let listener = interactive.then(|| pause.listen(&progress, !args.tui));
+
+    // 惰性生成 (IP, 端口) 任务，并发由工作池限制；
+    // 限制单主机并行数时按端口轮流扫描各主机，避免工作槽位都在等同一主机
+    let pinger = SystemPinger::for_context(&ping_ctx);
+    let pinged = ResultCollector::new();
+    let (live_stage, tasks) = if args.live {
+        let live = LiveStage {
+            pinger: &pinger,
+            opts: PingOptions {
+                timeout_secs: 3,
+                count: 2,
+                stats: false,
+            },
+            concurrency: effective_concurrency(ping_spec, targets.len(), ScanKind::Icmp).value,
+            progress: &ping_progress,
+            ctx: &ping_ctx,
+            results: &pinged,
+        };
+        let interleave = timing.host_parallelism.is_some();
+        let (stage, tasks) = live_port_tasks(live, hosts(), &ports, &progress, interleave);
+        (Some(stage), tasks)
+    } else if timing.host_parallelism.is_some() {
+        let tasks = ports
+            .iter()
+            .flat_map(|&port| hosts().map(move |ip| (Arc::<str>::from(ip), port)));
+        (None, stream::iter(tasks).boxed())
+    } else {
+        let tasks = hosts().flat_map(|ip| {
+            let ip = Arc::<str>::from(ip);
+            ports.iter().map(move |&port| (ip.clone(), port))
+        });
+        (None, stream::iter(tasks).boxed())
+    };
+
+    // 敲门后平时被过滤的端口会短暂开放，每个主机在第一次探测前敲门
+    let knocker = scan_knocker(args, ctx);
+    if let Some(ref knocker) = knocker {
+        println!(
+            "{} 敲门: {}（间隔 {}ms）",
+            Icon::Config,
+            knocker.sequence(),
+            args.knock.knock_delay
+        );
+    }
+    let retransmit = args.retransmit.plan(probe_timeout);
+    if let Some(plan) = retransmit {
+        let first = plan
+            .first
+            .map(format_duration)
+            .unwrap_or_else(|| "按主机往返时间推算".to_string());
+        println!(
+            "{} 两段式探测: 首探超时 {}，重探超时 {}",
+            Icon::Config,
+            first,
+            format_duration(plan.retry)
+        );
+    }
+    let names = probes.names();
+    println!(
+        "{} 补充探测: {}",
+        Icon::Config,
+        if names.is_empty() {
+            "无".to_string()
+        } else {
+            names.join(", ")
+        }
+    );
+    let opts = PortProbeOptions {
+        concurrency: concurrency.value,
+        probe_timeout,
+        retries: timing.retries,
+        egress: &args.egress,
+        knocker: knocker.as_ref(),
+        retransmit,
+        probes: &probes,
+    };
+    // 结果边扫描边累积，开启 --snapshot-every 时定期写中间结果
+    let collector = ResultCollector::new();
+    let snapshotter = Snapshotter::start(
+        &args.snapshot,
+        &collector,
+        ctx.run_dir().cloned(),
+        &progress,
+        snapshot_writer(args.output, ctx),
+    );
+    let phase = Instant::now();
+    // 存活探测与端口扫描同时进行，存活探测结束时记录其耗时
+    let live_phase = async {
+        live_stage?.await;
+        ping_progress.finish_with_message(format!("{} 存活探测完成", Icon::Ok));
+        Some(phase.elapsed())
+    };
+    // 超时重试后才得到应答的端口（复核时视为结果反复）
+    let mut flapped: HashSet<(String, u16)> = HashSet::new();
+    // 主机的全部端口完成时得出结论，不可达或出错的主机即时提示
+    let mut tracker = HostTracker::new(ports.len());
+    let connector = TapeConnector::new(TcpConnector, ctx.tape());
+    let port_phase = scan_ports_streamed(
+        &connector,
+        tasks,
+        &fps,
+        opts,
+        &progress,
+        ctx,
+        |result, timing| {
+            metrics.record(&result.ip, &timing);
+            if timing.timeouts > 0 && result.status != "超时" {
+                flapped.insert((result.ip.clone(), result.port));
+            }
+            if let Some(ref tx) = tui_tx {
+                let _ = tx.send(result.clone());
+            } else if let Some(outcome) = tracker.record(&result)
+                && matches!(outcome.status, HostStatus::Unreachable | HostStatus::Error)
+            {
+                progress.println(format!(
+                    "  {} {} {}: {}",
+                    Icon::Warn,
+                    outcome.ip,
+                    outcome.status,
+                    outcome.detail
+                ));
+            }
+            collector.push(result);
+        },
     );
-    println!("⚙️  配置: 并发={}", args.concurrency);
-
-    // 初始化结果存储和进度条
-    let results = Arc::new(Mutex::new(Vec::<PortScanResult>::with_capacity(
-        total_tasks as usize,
-    )));
-    let progress = ScanProgress::new(total_tasks);
-
-    // 并发控制信号量
-    let sem = Arc::new(Semaphore::new(args.concurrency));
-    let mut tasks = FuturesUnordered::new();
-
-    // 为每个IP和端口创建扫描任务
-    for ip in live_ips {
-        for &port in &ports {
-            let permit = sem.clone().acquire_owned().await?;
-            let ip_cloned = ip.clone();
-            let results_clone = results.clone();
-            let progress_clone = progress.clone();
-            let fps_clone = fps.clone();
-
-            tasks.push(tokio::spawn(async move {
-                let _permit = permit;
-
-                // 扫描单个端口
-                let result = scan_single_port(&ip_cloned, port, &fps_clone, &progress_clone).await;
-
-                // 保存结果
-                {
-                    let mut results_guard = results_clone.lock().await;
-                    results_guard.push(result);
+    let (live_elapsed, ()) = tokio::join!(live_phase, port_phase);
+    if let Some(elapsed) = live_elapsed {
+        metrics.record_phase("存活探测", elapsed);
+    }
+    metrics.record_phase("端口扫描", phase.elapsed());
+    drop(listener);
+    drop(tui_tx);
+    let mut final_results = collector.into_vec();
+    if shuffled {
+        final_results.sort_by_cached_key(|r| (ip_sort_key(&r.ip), r.port));
+    }
+
+    if let Some(handle) = tui_handle {
+        let exit = handle
+            .await
+            .map_err(|e| format!("交互界面异常退出: {}", e))??;
+        if exit == TuiExit::Aborted {
+            println!("{} 扫描已被用户中止，以下为部分结果", Icon::Warn);
+        }
+    } else if ctx.is_cancelled() {
+        println!("{} 扫描已取消，以下为部分结果", Icon::Warn);
+    }
+
+    progress.finish_with_message(format!("{} 端口扫描完成", Icon::Ok));
+
+    let live_ips: Option<Vec<String>> = if args.live {
+        let alive: Vec<String> = pinged
+            .into_vec()
+            .into_iter()
+            .filter(|r| r.is_success())
+            .map(|r| {
+                if let Some(ttl) = r.ttl {
+                    ttls.insert(r.ip.clone(), ttl);
                 }
+                r.ip
+            })
+            .collect();
+        println!("{} 发现 {} 个存活主机", Icon::Ok, alive.len());
+        if alive.is_empty() {
+            return Err("没有有效的IP地址可供扫描".into());
+        }
+        Some(alive)
+    } else {
+        None
+    };
 
-                progress_clone.inc(1);
-            }));
+    // 复核可疑结果，作为第二个阶段显示进度
+    let verified = if args.verify.verify && !ctx.is_cancelled() {
+        let phase = Instant::now();
+        let candidates = verify_candidates(&final_results, &args.verify, &flapped, &baseline);
+        // 复核时距敲门已有一段时间，重新敲门
+        let verify_knocker = scan_knocker(args, ctx);
+        let verify_opts = PortProbeOptions {
+            probe_timeout: args.verify.timeout(probe_timeout),
+            retries: args.verify.attempts() - 1,
+            knocker: verify_knocker.as_ref(),
+            retransmit: None,
+            ..opts
+        };
+        let verified = verify_results(
+            &TapeConnector::new(TcpConnector, ctx.tape()),
+            &mut final_results,
+            candidates,
+            &fps,
+            verify_opts,
+            ctx,
+            progress.stats(),
+        )
+        .await;
+        metrics.record_phase("复核", phase.elapsed());
+        Some(verified)
+    } else {
+        None
+    };
+
+    // 蜜罐检测、地理位置查询和系统推测计入补充识别阶段
+    let phase = Instant::now();
+    // 按主机检测疑似蜜罐，结果保留但单独标记
+    let assessments = honeypot_config
+        .as_ref()
+        .map(|config| assess_hosts(&final_results, config))
+        .unwrap_or_default();
+    for result in &mut final_results {
+        result.suspected_honeypot = assessments.get(&result.ip).is_some_and(|a| a.suspected);
+        result.aliases = targets.aliases(&result.ip);
+        result.tags = targets.tags(&result.ip);
+        if let Some(ref geo) = geo
+            && result.is_open()
+        {
+            result.geo = geo.lookup(&result.ip);
         }
     }
+    let suspected_hosts: Vec<&HostAssessment> =
+        assessments.values().filter(|a| a.suspected).collect();
+    let mut findings = {
+        let open_ports: Vec<&PortScanResult> =
+            final_results.iter().filter(|r| r.is_open()).collect();
+        findings(&open_ports, &suspected_hosts)
+    };
+
+    // 脚本处理每个开放端口及模块的发现，返回的字段作为附加列导出
+    let mut derived = Vec::new();
+    let script = match script {
+        Some(hook) => {
+            let stage =
+                ScriptStage::new(hook, HookPoint::PortResult, final_results, findings.clone());
+            let (hook, rows, extra) = run_stage(stage).await?;
+            final_results = rows;
+            findings.extend(extra.iter().cloned());
+            derived = extra;
+            Some(hook)
+        }
+        None => None,
+    };
+    // 脚本可能报告与模块相同的问题，合并后再导出
+    let findings = consolidate(findings);
+
+    // 按主机推测操作系统
+    let os_guesses = if args.os_guess && !ctx.is_cancelled() {
+        // 未做存活探测时补充探测有开放端口的主机，只为获取TTL
+        if !args.live {
+            let hosts: Vec<String> = group_open_ports(&final_results).into_keys().collect();
+            if !hosts.is_empty() {
+                let ping_ctx = ctx.without_results();
+                let ping_progress = ping_ctx.new_progress(hosts.len() as u64);
+                ping_progress.set_message("获取TTL用于系统推测");
+                let ping_concurrency =
+                    effective_concurrency(ping_spec, hosts.len(), ScanKind::Icmp);
+                let ping_results = ping_concurrent_async(
+                    hosts,
+                    timing.timeout_secs.max(1),
+                    1,
+                    ping_concurrency.value,
+                    &ping_progress,
+                    &ping_ctx,
+                )
+                .await?;
+                ping_progress.finish();
+                ttls.extend(
+                    ping_results
+                        .into_iter()
+                        .filter_map(|r| Some((r.ip, r.ttl?))),
+                );
+            }
+        }
+        guess_hosts(&final_results, &ttls)
+    } else {
+        BTreeMap::new()
+    };
 
-    // 等待所有任务完成
-    while tasks.next().await.is_some() {}
+    metrics.record_phase("补充识别", phase.elapsed());
+    ctx.tape().finish()?;
 
-    progress.finish_with_message("✅ 端口扫描完成");
+    // 每个目标都有结论，没有开放端口的主机也能说明原因
+    let outcomes = host_outcomes(
+        &final_results,
+        targets.iter_ips(),
+        live_ips.as_deref(),
+        ports.len(),
+    );
 
-    // 获取最终结果
-    let final_results = Arc::try_unwrap(results)
-        .expect("无法获取最终结果")
-        .into_inner();
+    // 与资产库比对并记录本次结果（有端口应答即视为有响应）
+    let mut open_by_host: BTreeMap<String, BTreeSet<u16>> = BTreeMap::new();
+    for r in final_results.iter().filter(|r| r.is_open()) {
+        open_by_host.entry(r.ip.clone()).or_default().insert(r.port);
+    }
+    let observations = outcomes
+        .iter()
+        .map(|o| Observation {
+            ip: o.ip.clone(),
+            responding: o.status == HostStatus::Scanned,
+            open_ports: open_by_host.remove(&o.ip).unwrap_or_default(),
+            os_guess: os_guesses.get(&o.ip).map(OsGuess::name),
+            aliases: targets.aliases(&o.ip),
+            tags: targets.tags(&o.ip),
+        })
+        .collect();
+    let scanned_ports = ports.iter().copied().collect();
+    let asset_changes = assets::update(
+        asset_db,
+        observations,
+        Some(scanned_ports),
+        ctx.is_cancelled(),
+    )
+    .await?;
+    if let Some(ref changes) = asset_changes {
+        for result in &mut final_results {
+            result.asset_changes = change_notes(changes, &result.ip);
+        }
+    }
 
     // 统计结果
     let open_ports: Vec<&PortScanResult> = final_results.iter().filter(|r| r.is_open()).collect();
+    // 同一主机名的IPv4、IPv6地址并列对照（有端口应答即视为可达）
+    let dual_stack = dualstack::correlate(&targets, |ip| {
+        outcomes
+            .iter()
+            .any(|o| o.ip == ip && o.status == HostStatus::Scanned)
+    });
 
-    let total_scanned = final_results.len();
-    let open_count = open_ports.len();
-    let closed_count = total_scanned - open_count;
+    // 计数以工作池的统计为准（已计入复核改变的结论）
+    let counts = progress.snapshot();
+    debug_assert_eq!(counts.completed as usize, final_results.len());
+    debug_assert_eq!(counts.succeeded as usize, open_ports.len());
+    let total_scanned = counts.completed as usize;
+    let open_count = counts.succeeded as usize;
+
+    let estimates = sample
+        .as_ref()
+        .map(|sample| sample_estimates(sample, &outcomes, args.live))
+        .unwrap_or_default();
 
     // 保存到Excel
+    let mut outputs = Vec::new();
     if args.output {
-        save_to_excel(
-            &final_results,
-            &["IP地址", "端口", "状态", "服务", "证据"],
-            |r| {
-                vec![
-                    r.ip.clone(),
-                    r.port.to_string(),
-                    r.status.clone(),
-                    r.banner.clone(),
-                    r.evidence.join("; "),
-                ]
-            },
-            "portscan",
-            "portscan",
-        )?;
+        let mut options = ctx.excel_options();
+        options.extra_sheets.push(host_status_sheet(&outcomes));
+        if !dual_stack.is_empty() {
+            options.extra_sheets.push(dual_stack_sheet(&dual_stack));
+        }
+        if let Some(ref sample) = sample {
+            options.extra_sheets.push(sample.sheet(&estimates));
+        }
+        outputs.push(
+            run_stage(export_results(
+                &final_results,
+                OutputKind::PORTSCAN,
+                &os_guesses,
+                &metrics,
+                options,
+            ))
+            .await?,
+        );
+    }
+    for format in &args.format {
+        // nmap XML以原始IP为主机键并带有完整命令行，无法脱敏
+        if *format == OutputFormat::NmapXml && redactor().is_some() {
+            println!("{} 已开启脱敏输出，跳过nmap XML格式", Icon::Warn);
+            continue;
+        }
+        let run_dir = ctx.run_dir().map(|run| run.as_ref());
+        let path = match format {
+            OutputFormat::VmJson | OutputFormat::VmCsv => {
+                let format = if *format == OutputFormat::VmJson {
+                    FindingFormat::VmJson
+                } else {
+                    FindingFormat::VmCsv
+                };
+                write_findings(
+                    &findings,
+                    format,
+                    &started_at.to_rfc3339(),
+                    OutputKind::PORTSCAN,
+                    run_dir,
+                )?
+            }
+            OutputFormat::NmapXml => {
+                let info = NmapRunInfo {
+                    args: std::env::args().collect::<Vec<_>>().join(" "),
+                    start: started_at,
+                    end: chrono::Local::now(),
+                };
+                write_nmap_xml(&final_results, &info, run_dir)?
+            }
+        };
+        println!("{} 结果已保存至: {}", Icon::Ok, path.display());
+        outputs.push(path.display().to_string());
+    }
+    if let Some(snapshotter) = snapshotter {
+        let kept = snapshotter.finish(args.snapshot.keep_snapshots).await;
+        if kept > 0 {
+            println!("{} 已保留 {} 份中间结果", Icon::Ok, kept);
+        }
     }
 
     // 打印总结
     let elapsed = start.elapsed();
-    println!("\n📊 扫描统计:");
-    println!("   总计: {} 个端口", total_scanned);
-    println!(
-        "   开放: {} 个 ({:.1}%)",
-        open_count,
-        (open_count as f64 / total_scanned as f64) * 100.0
-    );
-    println!(
-        "   关闭: {} 个 ({:.1}%)",
-        closed_count,
-        (closed_count as f64 / total_scanned as f64) * 100.0
-    );
-    println!("   耗时: {:.2?}", elapsed);
+    let mut summary: Vec<SummaryItem> = vec![
+        ("总计".to_string(), format!("{} 个端口", counts.completed)),
+        (
+            "开放".to_string(),
+            format!(
+                "{} 个 ({:.1}%)",
+                counts.succeeded,
+                counts.percent(counts.succeeded)
+            ),
+        ),
+        (
+            "关闭".to_string(),
+            format!(
+                "{} 个 ({:.1}%)",
+                counts.failed,
+                counts.percent(counts.failed)
+            ),
+        ),
+        (
+            "耗时".to_string(),
+            format_elapsed(elapsed, pause.paused_duration()),
+        ),
+    ];
+    if counts.errored > 0 {
+        summary.insert(
+            3,
+            (
+                "超时".to_string(),
+                format!(
+                    "{} 个 ({:.1}%)",
+                    counts.errored,
+                    counts.percent(counts.errored)
+                ),
+            ),
+        );
+    }
+    if counts.cancelled > 0 {
+        summary.push((
+            "未完成".to_string(),
+            format!("{} 个端口（探测中被取消）", counts.cancelled),
+        ));
+    }
+    summary.push(("主机状态".to_string(), status_counts(&outcomes)));
+    summary.extend(dualstack::summary_items(&dual_stack));
+    if let Some(ref changes) = asset_changes {
+        summary.push(ChangeCounts::count(changes).summary_item());
+    }
+    if let Some(ref sample) = sample {
+        summary.extend(sample.summary_items(&estimates));
+    }
+    if honeypot_config.is_some() {
+        summary.push((
+            "疑似蜜罐".to_string(),
+            format!("{} 个主机", suspected_hosts.len()),
+        ));
+    }
+    if let Some((checked, changed)) = verified {
+        summary.push((
+            "复核".to_string(),
+            format!("{} 个端口，结论改变 {} 个", checked, changed),
+        ));
+    }
+    if let Some(ref script) = script {
+        summary.push(script.summary_item());
+    }
+    if let Some(phases) = metrics.phase_summary() {
+        summary.push(("阶段耗时".to_string(), phases));
+    }
+    for (source, tried, reached) in egress_stats(&final_results, &args.egress) {
+        summary.push((
+            format!("出口 {}", source),
+            format!("连通 {} / 尝试 {}", reached, tried),
+        ));
+    }
+    summary.extend(timing.summary_items());
+    summary.extend(ctx.adaptive().map(|a| a.summary_item()));
+    summary.extend(overrides_summary_item(&overrides));
+    summary.extend(approved);
+    println!("\n{} 扫描统计:", Icon::Stats);
+    for (name, value) in &summary {
+        println!("   {}: {}", name, value);
+    }
+    print_slowest_hosts(&metrics, SLOWEST_HOSTS);
+    print_silent_hosts(&outcomes, SILENT_HOSTS);
+    print_derived(&derived);
+
+    // 结果及统计写入运行目录，供 `report view` 查看（只保留开放端口）
+    if let Some(run_dir) = ctx.run_dir() {
+        let hosts = host_records(&final_results, &os_guesses, &assessments);
+        run_dir.write_json(HOSTS_FILE_NAME, "json", &hosts, hosts.len())?;
+        run_dir.write_json(PORTS_FILE_NAME, "json", &open_ports, open_count)?;
+        run_dir.write_json(HOST_STATUS_FILE_NAME, "json", &outcomes, outcomes.len())?;
+        if !dual_stack.is_empty() {
+            run_dir.write_json(DUAL_STACK_FILE_NAME, "json", &dual_stack, dual_stack.len())?;
+        }
+        if honeypot_config.is_some() {
+            run_dir.write_json(
+                FINDINGS_FILE_NAME,
+                "json",
+                &suspected_hosts,
+                suspected_hosts.len(),
+            )?;
+        }
+        if !overrides.is_empty() {
+            run_dir.write_json(
+                TARGET_OVERRIDES_FILE_NAME,
+                "json",
+                &overrides,
+                overrides.len(),
+            )?;
+        }
+        if let Some(adaptive) = ctx.adaptive() {
+            let series = adaptive.series();
+            run_dir.write_json(CONCURRENCY_FILE_NAME, "json", &series, series.len())?;
+        }
+        run_dir.write_summary(&summary)?;
+    }
 
     // 按IP分组显示开放端口
     if open_count > 0 {
-        println!("\n🔓 开放端口详情:");
-        let mut grouped: std::collections::HashMap<String, Vec<&PortScanResult>> =
-            std::collections::HashMap::new();
-        for result in &open_ports {
-            grouped.entry(result.ip.clone()).or_default().push(result);
-        }
+        let grouped = group_open_ports(&final_results);
 
+        if grouped.len() > suspected_hosts.len() {
+            println!("\n{} 开放端口详情:", Icon::List);
+        }
         for (ip, ports) in grouped.iter() {
+            if assessments.get(ip).is_some_and(|a| a.suspected) {
+                continue;
+            }
             let port_list: Vec<String> = ports.iter().map(|p| p.port.to_string()).collect();
-            println!("   {} => [{}]", ip, port_list.join(", "));
+            let host = format!("{}{}", ip, alias_suffix(&ports[0].aliases));
+            match os_guesses.get(ip) {
+                Some(guess) => println!("   {} => [{}] {}", host, port_list.join(", "), guess),
+                None => println!("   {} => [{}]", host, port_list.join(", ")),
+            }
+        }
+
+        // 疑似蜜罐主机单独列出并弱化显示
+        if !suspected_hosts.is_empty() {
+            println!("\n{} 疑似蜜罐主机（结果仅供参考）:", Icon::Warn);
+            for host in &suspected_hosts {
+                let port_list: Vec<String> = grouped
+                    .get(&host.ip)
+                    .map(|ports| ports.iter().map(|p| p.port.to_string()).collect())
+                    .unwrap_or_default();
+                println!(
+                    "{}",
+                    dimmed(&format!(
+                        "   {} => [{}] 疑似蜜罐: {}",
+                        host.ip,
+                        port_list.join(", "),
+                        host.reasons.join("; ")
+                    ))
+                );
+            }
         }
     }
 
-    Ok(())
+    Ok(RunSummary {
+        total: total_scanned,
+        succeeded: open_count,
+        outputs,
+        concurrency: Some(concurrency),
+        timing: Some(timing),
+    })
 }
 
-/// 扫描单个端口
+/// 读取基线运行中的开放端口
 ///
 /// # 参数
-/// * `ip` - IP地址
-/// * `port` - 端口号
+/// * `run` - 基线运行目录（目录名或其前缀）
+fn baseline_open(run: &str) -> Result<HashSet<(String, u16)>, Box<dyn Error + Send + Sync>> {
+    let rows = load_run_rows(run, PORTS_FILE_NAME)?;
+    let observed = rows.iter().filter_map(|r| r["observed_at"].as_str());
+    if let Some(staleness) = describe_staleness(observed, chrono::Local::now()) {
+        println!("{} 复核基线 {}: {}", Icon::Config, run, staleness);
+    }
+    Ok(rows
+        .iter()
+        .filter_map(|r| {
+            let port = u16::try_from(r["port"].as_u64()?).ok()?;
+            Some((r["ip"].as_str()?.to_string(), port))
+        })
+        .collect())
+}
+
+/// 挑选需要复核的端口
+///
+/// # 参数
+/// * `results` - 主扫描结果
+/// * `verify` - 复核参数
+/// * `flapped` - 扫描期间结果反复的端口
+/// * `baseline` - 基线中开放的端口
+///
+/// # 返回
+/// * `Vec<(IP, 端口)>` - 基线中开放、本次未开放的端口，开放端口很少的主机上的开放端口，以及结果反复的端口
+pub fn verify_candidates(
+    results: &[PortScanResult],
+    verify: &VerifyArgs,
+    flapped: &HashSet<(String, u16)>,
+    baseline: &HashSet<(String, u16)>,
+) -> Vec<(String, u16)> {
+    let mut open_per_host: HashMap<&str, usize> = HashMap::new();
+    for r in results.iter().filter(|r| r.is_open()) {
+        *open_per_host.entry(r.ip.as_str()).or_default() += 1;
+    }
+    results
+        .iter()
+        .filter(|r| {
+            let key = (r.ip.clone(), r.port);
+            (verify.wants(VerifyKind::Baseline) && !r.is_open() && baseline.contains(&key))
+                || (verify.wants(VerifyKind::Sparse)
+                    && r.is_open()
+                    && open_per_host[r.ip.as_str()] <= verify.sparse_ports())
+                || (verify.wants(VerifyKind::Flapped) && flapped.contains(&key))
+        })
+        .map(|r| (r.ip.clone(), r.port))
+        .collect()
+}
+
+/// 用复核参数重新扫描部分端口，复核结果替换主扫描结果，其余结果标注为未复核
+///
+/// 复核使用与主扫描相同的扫描流程，单独显示一个进度条，结果不再推送给上下文的接收方。
+///
+/// # 参数
+/// * `connector` - 连接方式
+/// * `results` - 主扫描结果
+/// * `candidates` - 需要复核的 (IP, 端口)
 /// * `fps` - 指纹库
-/// * `progress` - 进度条（用于输出信息）
+/// * `opts` - 复核的并发、超时及重试参数
+/// * `ctx` - 扫描上下文
+/// * `stats` - 主扫描的统计（复核替换的结果按新结论重新计数）
 ///
 /// # 返回
-/// * `PortScanResult` - 扫描结果
-async fn scan_single_port(
-    ip: &str,
-    port: u16,
-    _fps: &[crate::commands::pentest::fingerprint::Fingerprint],
-    progress: &ScanProgress,
-) -> PortScanResult {
-    let addr = format!("{}:{}", ip, port);
-    let mut evidence: Vec<String> = Vec::new();
-    let mut banner = String::new();
+/// * `(复核数, 结论改变数)`
+pub async fn verify_results<C: PortConnector>(
+    connector: &C,
+    results: &mut [PortScanResult],
+    candidates: Vec<(String, u16)>,
+    fps: &[crate::commands::pentest::fingerprint::Fingerprint],
+    opts: PortProbeOptions<'_>,
+    ctx: &ScanContext,
+    stats: &ScanStats,
+) -> (usize, usize) {
+    for result in results.iter_mut() {
+        result.verification = Some(Verification::UNVERIFIED);
+    }
+    if candidates.is_empty() {
+        return (0, 0);
+    }
 
-    // 尝试连接并读取banner
-    if let Some(buf) = connect_and_read(
-        &addr,
-        Duration::from_secs(3),
-        Duration::from_secs(2),
-        Duration::from_millis(400),
-        4096,
+    let verify_ctx = ctx.without_results();
+    let progress = verify_ctx.new_progress(candidates.len() as u64);
+    progress.set_message("复核");
+    let mut verified = Vec::new();
+    scan_ports_with(
+        connector,
+        candidates.iter().map(|(ip, port)| (ip.as_str(), *port)),
+        fps,
+        opts,
+        &progress,
+        &verify_ctx,
+        |result, _| verified.push(result),
     )
-    .await
-    {
-        // 识别协议和服务
-        if buf.starts_with(b"SSH-") {
-            if let Ok(s) = std::str::from_utf8(&buf) {
-                banner = s.lines().next().unwrap_or("SSH").to_string();
-                evidence.push("ssh-banner".to_string());
-            }
-        } else if is_mysql_handshake(&buf) {
-            banner = extract_mysql_banner(&buf);
-            evidence.push(format!("mysql-handshake (len={})", buf.len()));
-        } else if is_rdp_response(&buf) {
-            banner = extract_rdp_banner(&buf);
-            evidence.push("rdp-response".to_string());
-        } else {
-            banner = extract_banner_text(&buf);
-            evidence.push("initial-raw".to_string());
+    .await;
+    progress.finish_with_message(format!("{} 复核完成", Icon::Ok));
+
+    let mut changed = 0;
+    for mut result in verified.iter().cloned() {
+        let Some(original) = results
+            .iter_mut()
+            .find(|r| r.ip == result.ip && r.port == result.port)
+        else {
+            continue;
+        };
+        let flipped = result.is_open() != original.is_open();
+        stats.reclassify(original.outcome(), result.outcome());
+        result.verification = Some(Verification::verified(flipped));
+        *original = result;
+        changed += usize::from(flipped);
+    }
+    (verified.len(), changed)
+}
+
+/// 端口敲门器（回放录制时只标记不发送）
+fn scan_knocker(args: &PortScanArgs, ctx: &ScanContext) -> Option<Knocker> {
+    let knocker = args.knock.knocker()?;
+    Some(if ctx.tape().is_replay() {
+        knocker.silent()
+    } else {
+        knocker
+    })
+}
+
+/// 检查出口地址都是本机地址（能够绑定），在发出探测之前报错
+fn check_egress(egress: &[IpAddr]) -> Result<(), Box<dyn Error + Send + Sync>> {
+    for &source in egress {
+        std::net::UdpSocket::bind(SocketAddr::new(source, 0))
+            .map_err(|e| format!("出口地址 {} 不可用（不是本机地址？）: {}", source, e))?;
+    }
+    Ok(())
+}
+
+/// 按出口统计尝试数与连通数：(出口, 尝试, 连通)
+///
+/// 出口按顺序尝试，经第 n 个出口连通的任务也尝试过它之前的出口，各出口均不通的任务尝试过全部出口。
+fn egress_stats(results: &[PortScanResult], egress: &[IpAddr]) -> Vec<(IpAddr, usize, usize)> {
+    let mut stats: Vec<(IpAddr, usize, usize)> = egress.iter().map(|&ip| (ip, 0, 0)).collect();
+    for result in results {
+        let position = result
+            .egress
+            .and_then(|source| egress.iter().position(|&ip| ip == source));
+        let tried = match position {
+            Some(i) => {
+                stats[i].2 += 1;
+                i + 1
+            }
+            None => egress.len(),
+        };
+        for entry in &mut stats[..tried] {
+            entry.1 += 1;
         }
+    }
+    stats
+}
+
+/// 按IP分组开放端口
+fn group_open_ports(results: &[PortScanResult]) -> BTreeMap<String, Vec<&PortScanResult>> {
+    let mut grouped: BTreeMap<String, Vec<&PortScanResult>> = BTreeMap::new();
+    for result in results.iter().filter(|r| r.is_open()) {
+        grouped.entry(result.ip.clone()).or_default().push(result);
+    }
+    grouped
+}
+
+/// 汇总每个有开放端口的主机的特征并推测操作系统
+///
+/// # 参数
+/// * `results` - 端口扫描结果
+/// * `ttls` - 各主机ICMP回复的TTL
+///
+/// # 返回
+/// * IP -> 推测结果（没有可用特征的主机不包含在内）
+fn guess_hosts(
+    results: &[PortScanResult],
+    ttls: &HashMap<String, u8>,
+) -> BTreeMap<String, OsGuess> {
+    group_open_ports(results)
+        .into_iter()
+        .filter_map(|(ip, ports)| {
+            let signals = OsSignals {
+                ttl: ttls.get(&ip).copied(),
+                // 连接扫描拿不到SYN-ACK报文
+                tcp_window: None,
+                open_ports: ports.iter().map(|r| r.port).collect(),
+                // 只使用实际收到的数据，按端口表标注的服务名不算
+                banners: ports
+                    .iter()
+                    .filter(|r| r.received_data())
+                    .map(|r| r.banner.clone())
+                    .collect(),
+            };
+            Some((ip, guess_os(&signals)?))
+        })
+        .collect()
+}
+
+/// 单个主机的汇总（写入运行目录的 `hosts.json`）
+#[derive(Debug, Clone, Serialize)]
+pub struct HostRecord {
+    /// IP地址
+    pub ip: String,
+    /// 开放端口
+    pub open_ports: Vec<u16>,
+    /// 操作系统推测（如 `Linux (Ubuntu)`）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os: Option<String>,
+    /// 推测置信度（0~1）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os_confidence: Option<f64>,
+    /// 蜜罐检测得分
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub honeypot_score: Option<f64>,
+    /// 是否疑似蜜罐
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub suspected_honeypot: bool,
+    /// 指向该IP的其他目标写法
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// 目标标签
+    #[serde(skip_serializing_if = "Tags::is_empty")]
+    pub tags: Tags,
+    /// 地理位置及ASN
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geo: Option<GeoInfo>,
+    /// 最近一次观测到开放端口的时间及探测方式
+    #[serde(flatten)]
+    pub observed: Provenance,
+}
+
+/// 按主机汇总开放端口、系统推测和蜜罐检测结果
+pub fn host_records(
+    results: &[PortScanResult],
+    os_guesses: &BTreeMap<String, OsGuess>,
+    assessments: &BTreeMap<String, HostAssessment>,
+) -> Vec<HostRecord> {
+    group_open_ports(results)
+        .into_iter()
+        .map(|(ip, ports)| {
+            let guess = os_guesses.get(&ip);
+            let assessment = assessments.get(&ip);
+            HostRecord {
+                open_ports: ports.iter().map(|r| r.port).collect(),
+                os: guess.map(OsGuess::name),
+                os_confidence: guess.map(|g| g.confidence),
+                honeypot_score: assessment.map(|a| a.score),
+                suspected_honeypot: assessment.is_some_and(|a| a.suspected),
+                aliases: ports[0].aliases.clone(),
+                tags: ports[0].tags.clone(),
+                geo: ports[0].geo.clone(),
+                observed: ports
+                    .iter()
+                    .map(|r| &r.observed)
+                    .max_by(|a, b| a.observed_at.cmp(&b.observed_at))
+                    .cloned()
+                    .unwrap_or_default(),
+                ip,
+            }
+        })
+        .collect()
+}
 
-        // 生成结果 - 端口开放
-        if banner.trim().is_empty() {
-            banner = "服务未知".to_string();
+/// 将开放端口和疑似蜜罐主机整理为标准化的发现列表
+///
+/// 开放端口本身只是暴露面信息，统一定为“信息”级别；疑似蜜罐主机的端口结果不可信，
+/// 其开放端口不再单独列出。
+///
+/// # 参数
+/// * `open_ports` - 开放端口
+/// * `suspected_hosts` - 疑似蜜罐主机
+fn findings(open_ports: &[&PortScanResult], suspected_hosts: &[&HostAssessment]) -> Vec<Finding> {
+    let is_suspected = |ip: &str| suspected_hosts.iter().any(|h| h.ip == ip);
+    let ports = open_ports.iter().filter(|r| !is_suspected(&r.ip)).map(|r| {
+        let mut evidence = r.evidence.clone();
+        if !r.banner.is_empty() {
+            evidence.insert(0, r.banner.clone());
         }
-        progress.println(format!(
-            "  ✅ {}:{} | {} | {:?}",
-            ip, port, banner, evidence
-        ));
-        PortScanResult::open(ip.to_string(), port, banner, evidence)
+        Finding {
+            module: "pentest portscan".to_string(),
+            asset: r.ip.clone(),
+            port: Some(r.port),
+            protocol: "tcp".to_string(),
+            check_id: "open-port".to_string(),
+            title: format!("开放端口 {}/tcp", r.port),
+            severity: Severity::Info,
+            evidence: evidence.join("; "),
+            remediation: "确认该端口是否需要对外开放，不需要的服务应关闭或限制访问来源".to_string(),
+            params: FindingParams::new(),
+            sources: Vec::new(),
+            observed: r.observed.clone(),
+        }
+    });
+    let hosts = suspected_hosts.iter().map(|h| Finding {
+        module: "pentest portscan".to_string(),
+        asset: h.ip.clone(),
+        port: None,
+        protocol: "tcp".to_string(),
+        check_id: "suspected-honeypot".to_string(),
+        title: "疑似蜜罐/tarpit主机".to_string(),
+        severity: Severity::Info,
+        evidence: format!("得分 {:.1}: {}", h.score, h.reasons.join("; ")),
+        remediation: "核实该主机是否为蜜罐，其端口扫描结果不应计入资产暴露面".to_string(),
+        params: FindingParams::new(),
+        sources: Vec::new(),
+        observed: Provenance::now("honeypot-analysis"),
+    });
+    ports.chain(hosts).collect()
+}
+
+/// 终端中以暗色显示文本（输出被重定向时原样返回）
+fn dimmed(text: &str) -> String {
+    if std::io::stdout().is_terminal() {
+        format!("\x1b[2m{}\x1b[0m", text)
     } else {
-        // 无直接banner，尝试协议探测
-        let is_open = probe_specific_protocols(ip, port, &mut banner, &mut evidence).await;
-
-        if is_open {
-            if banner.trim().is_empty() {
-                banner = "服务未知".to_string();
-            }
-            progress.println(format!(
-                "  ✅ {}:{} | {} | {:?}",
-                ip, port, banner, evidence
-            ));
-            PortScanResult::open(ip.to_string(), port, banner, evidence)
+        text.to_string()
+    }
+}
+
+/// 将端口扫描结果导出为Excel
+///
+/// # 参数
+/// * `results` - 扫描结果
+/// * `kind` - 输出子目录及文件名前缀
+/// * `os_guesses` - 各主机的操作系统推测（非空或目标带标签时追加主机汇总表）
+/// * `metrics` - 各主机的扫描指标（非空时追加主机汇总表，并包含全部已扫描主机）
+/// * `options` - 导出选项（决定写入运行目录还是平铺目录）
+///
+/// # 返回
+/// * 待执行的导出阶段（由调用方放到阻塞线程池执行，见 [`run_stage`]）
+pub fn export_results(
+    results: &[PortScanResult],
+    kind: OutputKind,
+    os_guesses: &BTreeMap<String, OsGuess>,
+    metrics: &ScanMetrics,
+    mut options: ExcelOptions,
+) -> ExcelExport {
+    let keys = tag_keys(results.iter().map(|r| &r.tags));
+    if !os_guesses.is_empty() || !keys.is_empty() || !metrics.is_empty() {
+        options
+            .extra_sheets
+            .push(host_summary_sheet(results, os_guesses, metrics, &keys));
+    }
+    // 查询过地理位置时增加地理位置列及ASN汇总表
+    let has_geo = results.iter().any(|r| r.geo.is_some());
+    if has_geo {
+        options.extra_sheets.push(asn_rollup_sheet(
+            results
+                .iter()
+                .filter_map(|r| Some((r.ip.as_str(), r.geo.as_ref()?))),
+        ));
+    }
+
+    // 有疑似蜜罐主机时增加备注列，有目标被合并时增加别名列，指定了出口时增加出口列，敲过门时增加敲门列，
+    // 两段式探测时增加探测阶段列
+    let flagged = results.iter().any(|r| r.suspected_honeypot);
+    let knocked = results.iter().any(|r| r.knocked);
+    let has_aliases = results.iter().any(|r| !r.aliases.is_empty());
+    let has_egress = results.iter().any(|r| r.egress.is_some());
+    let mut headers = vec!["IP地址", "端口", "状态", "服务", "证据"];
+    if flagged {
+        headers.push("备注");
+    }
+    if has_aliases {
+        headers.push("别名");
+    }
+    if has_egress {
+        headers.push("出口");
+    }
+    if knocked {
+        headers.push("敲门");
+    }
+    let staged = results.iter().any(|r| r.stage.is_some());
+    if staged {
+        headers.push("探测阶段");
+    }
+    if has_geo {
+        headers.extend(GeoInfo::HEADERS);
+    }
+    let has_verification = results.iter().any(|r| r.verification.is_some());
+    if has_verification {
+        headers.extend(Verification::HEADERS);
+    }
+    let has_asset_changes = results.iter().any(|r| !r.asset_changes.is_empty());
+    if has_asset_changes {
+        headers.push("资产变化");
+    }
+    // 补充探测的列按探测声明的顺序排在标签之前，没有结果的列不输出
+    let probe_columns = probes::export_columns(results);
+    headers.extend(probe_columns.iter().map(|c| c.header));
+    headers.extend(keys.iter().map(String::as_str));
+    headers.extend(Provenance::HEADERS);
+    ExcelExport::prepare_with(
+        results,
+        &headers,
+        |r, row| {
+            row.cell(&r.ip)
+                .cell(r.port)
+                .cell(&r.status)
+                .cell(&r.banner)
+                .cell(r.evidence.join("; "));
+            if flagged {
+                row.cell(if r.suspected_honeypot {
+                    "疑似蜜罐"
+                } else {
+                    ""
+                });
+            }
+            if has_aliases {
+                row.cell(r.aliases.join(", "));
+            }
+            if has_egress {
+                row.cell(r.egress.map(|ip| ip.to_string()).unwrap_or_default());
+            }
+            if knocked {
+                row.cell(if r.knocked { "已敲门" } else { "" });
+            }
+            if staged {
+                row.cell(r.stage.map(|s| s.to_string()).unwrap_or_default());
+            }
+            if has_geo {
+                row.cells(GeoInfo::cells(r.geo.as_ref()));
+            }
+            if has_verification {
+                row.cells(Verification::cells(r.verification.as_ref()));
+            }
+            if has_asset_changes {
+                row.cell(r.asset_changes.join("; "));
+            }
+            row.cells(probe_columns.iter().map(|c| probes::cell(&r.probes, c.key)))
+                .cells(
+                    keys.iter()
+                        .map(|k| r.tags.get(k).map_or("", String::as_str)),
+                )
+                .cells(r.observed.cells());
+        },
+        kind,
+        &options,
+    )
+}
+
+/// 中间结果的写入方式：JSON，开启 -o 时另写Excel（不含主机汇总表）
+fn snapshot_writer(excel: bool, ctx: &ScanContext) -> SnapshotWriter<PortScanResult> {
+    let run_dir = ctx.run_dir().cloned();
+    let options = ctx.excel_options();
+    Box::new(move |rows, n| {
+        let mut files = vec![write_json_snapshot(
+            rows,
+            n,
+            OutputKind::PORTSCAN,
+            run_dir.as_deref(),
+        )?];
+        if excel {
+            let options = ExcelOptions {
+                file_name: Some(snapshot_file_name(
+                    OutputKind::PORTSCAN.prefix,
+                    n,
+                    "xlsx",
+                    run_dir.is_none(),
+                )),
+                quiet: true,
+                ..options.clone()
+            };
+            files.push(
+                export_results(
+                    rows,
+                    OutputKind::PORTSCAN,
+                    &BTreeMap::new(),
+                    &ScanMetrics::default(),
+                    options,
+                )
+                .run()?
+                .into(),
+            );
+        }
+        Ok(files)
+    })
+}
+
+/// 生成主机汇总表（每个有开放端口的主机一行，标签各占一列）
+///
+/// 有扫描指标时每个已扫描的主机一行（没有开放端口的主机往往耗时最长），并追加指标列。
+fn host_summary_sheet(
+    results: &[PortScanResult],
+    os_guesses: &BTreeMap<String, OsGuess>,
+    metrics: &ScanMetrics,
+    keys: &[String],
+) -> ExcelSheet {
+    let mut grouped = group_open_ports(results);
+    let tags: BTreeMap<&str, &Tags> = results.iter().map(|r| (r.ip.as_str(), &r.tags)).collect();
+    for ip in metrics.hosts().keys() {
+        grouped.entry(ip.clone()).or_default();
+    }
+    let rows = grouped
+        .into_iter()
+        .map(|(ip, ports)| {
+            let guess = os_guesses.get(&ip);
+            let mut row = vec![
+                ip.clone(),
+                ports.len().to_string(),
+                ports
+                    .iter()
+                    .map(|r| r.port.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                guess.map(OsGuess::name).unwrap_or_default(),
+                guess
+                    .map(|g| format!("{:.0}%", g.confidence * 100.0))
+                    .unwrap_or_default(),
+                guess.map(|g| g.reasons.join("; ")).unwrap_or_default(),
+            ];
+            if !metrics.is_empty() {
+                row.extend(metrics.host_cells(&ip));
+            }
+            let tags = tags.get(ip.as_str());
+            row.extend(
+                keys.iter()
+                    .map(|k| tags.and_then(|t| t.get(k)).cloned().unwrap_or_default()),
+            );
+            row
+        })
+        .collect();
+    let mut headers = [
+        "IP地址",
+        "开放端口数",
+        "开放端口",
+        "操作系统（推测）",
+        "置信度",
+        "推测依据",
+    ]
+    .map(String::from)
+    .to_vec();
+    if !metrics.is_empty() {
+        headers.extend(ScanMetrics::HOST_HEADERS.map(String::from));
+    }
+    headers.extend(keys.iter().cloned());
+    ExcelSheet {
+        name: "主机汇总".to_string(),
+        headers,
+        rows,
+    }
+}
+
+/// 终端摘要中列出的最慢主机数
+const SLOWEST_HOSTS: usize = 10;
+
+/// 终端摘要中列出的无结果主机数
+const SILENT_HOSTS: usize = 20;
+
+/// 按样本外推开放端口的主机数（开启 --live 时同时外推存活主机数）
+///
+/// # 参数
+/// * `sample` - 本次抽样
+/// * `outcomes` - 每个样本主机的扫描结论
+/// * `live` - 是否做过存活探测
+fn sample_estimates(
+    sample: &Sample,
+    outcomes: &[HostOutcome],
+    live: bool,
+) -> Vec<(&'static str, Estimate)> {
+    // 取消时未扫描的主机不计入样本
+    let observed = outcomes
+        .iter()
+        .filter(|o| o.status != HostStatus::Skipped)
+        .count();
+    let open = outcomes.iter().filter(|o| o.open > 0).count();
+    let mut estimates = vec![("开放端口的主机", sample.estimate(open, observed))];
+    if live {
+        let alive = observed
+            - outcomes
+                .iter()
+                .filter(|o| o.status == HostStatus::NotAlive)
+                .count();
+        estimates.push(("存活主机", sample.estimate(alive, observed)));
+    }
+    estimates
+}
+
+/// 打印没有开放端口的主机及原因
+///
+/// # 参数
+/// * `outcomes` - 各目标主机的结论
+/// * `n` - 最多打印的数量
+fn print_silent_hosts(outcomes: &[HostOutcome], n: usize) {
+    let silent: Vec<&HostOutcome> = outcomes.iter().filter(|o| o.open == 0).collect();
+    if silent.is_empty() {
+        return;
+    }
+    println!("\n{} 没有开放端口的主机:", Icon::List);
+    for outcome in silent.iter().take(n) {
+        println!(
+            "   {} => {}（{}）",
+            outcome.ip, outcome.status, outcome.detail
+        );
+    }
+    if silent.len() > n {
+        println!("   …… 另有 {} 个（完整列表见主机状态表）", silent.len() - n);
+    }
+}
+
+/// 打印耗时最长的主机
+///
+/// # 参数
+/// * `metrics` - 扫描指标
+/// * `n` - 最多打印的数量
+fn print_slowest_hosts(metrics: &ScanMetrics, n: usize) {
+    // 只有一个主机时没有比较的意义
+    if metrics.hosts().len() < 2 {
+        return;
+    }
+    println!("\n{} 耗时最长的主机:", Icon::Stats);
+    for (ip, m) in metrics.slowest(n) {
+        let latency = m
+            .avg_connect()
+            .map(format_duration)
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "   {} => 耗时 {}, 探测 {} 次, 平均连接延迟 {}, 超时 {} 次",
+            ip,
+            format_duration(m.wall_time()),
+            m.probes,
+            latency,
+            m.timeouts
+        );
+    }
+}
+
+/// 建立TCP连接的方式
+///
+/// 端口扫描只通过该接口建立连接，测试时可替换为返回预设连接结果的实现。
+pub trait PortConnector: Sync {
+    /// 连接得到的数据流
+    type Stream: AsyncRead + AsyncWrite + Unpin + Send + 'static;
+
+    /// 连接目标端口（超时由调用方控制）
+    ///
+    /// `source` 为本次连接绑定的本地出口地址，为 `None` 时由系统选路；
+    /// 每次连接单独指定，同一任务的重试可以改走其他出口。
+    fn connect(
+        &self,
+        ip: &str,
+        port: u16,
+        source: Option<IpAddr>,
+    ) -> impl Future<Output = io::Result<Self::Stream>> + Send;
+}
+
+/// 使用系统TCP连接的实现
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TcpConnector;
+
+impl PortConnector for TcpConnector {
+    type Stream = TcpStream;
+
+    async fn connect(&self, ip: &str, port: u16, source: Option<IpAddr>) -> io::Result<TcpStream> {
+        let Some(source) = source else {
+            return TcpStream::connect((ip, port)).await;
+        };
+        let target: IpAddr = ip.parse().map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("无效的IP地址: {}", ip))
+        })?;
+        let socket = if source.is_ipv4() {
+            TcpSocket::new_v4()?
         } else {
-            PortScanResult::closed(ip.to_string(), port)
+            TcpSocket::new_v6()?
+        };
+        socket.bind(SocketAddr::new(source, 0))?;
+        socket.connect(SocketAddr::new(target, port)).await
+    }
+}
+
+/// 按录制或回放设置建立连接的方式（实际探测时直接使用内层连接）
+pub struct TapeConnector<'a, C> {
+    inner: C,
+    tape: &'a Tape,
+}
+
+impl<'a, C: PortConnector> TapeConnector<'a, C> {
+    /// 包装连接方式
+    ///
+    /// # 参数
+    /// * `inner` - 实际建立连接的方式（回放时不使用）
+    /// * `tape` - 录制或回放设置
+    pub fn new(inner: C, tape: &'a Tape) -> Self {
+        Self { inner, tape }
+    }
+}
+
+impl<C: PortConnector> PortConnector for TapeConnector<'_, C> {
+    type Stream = TapeStream<C::Stream>;
+
+    async fn connect(
+        &self,
+        ip: &str,
+        port: u16,
+        source: Option<IpAddr>,
+    ) -> io::Result<TapeStream<C::Stream>> {
+        // 指定出口时各出口分别录制
+        let suffix = match source {
+            Some(source) => format!(":{}@{}", port, source),
+            None => format!(":{}", port),
+        };
+        self.tape
+            .connect(ip, &suffix, self.inner.connect(ip, port, source))
+            .await
+    }
+}
+
+/// 记录连接耗时的连接方式（每个探测任务一个，由工作池创建）
+///
+/// 被调用方超时放弃的连接不会走到记录完成的那一步，开始数与完成数之差即为连接超时数。
+struct TimedConnector<'a, C> {
+    inner: &'a C,
+    stats: Mutex<ConnectStats>,
+}
+
+/// 一个探测任务中的连接统计
+#[derive(Debug, Default, Clone)]
+struct ConnectStats {
+    started: u32,
+    completed: u32,
+    total: Duration,
+    /// 自上次取出以来是否有连接得到了目标的应答（连接成功或被拒绝）
+    reached: bool,
+    /// 自上次取出以来最后一次连接错误（被拒绝除外）
+    error: Option<(io::ErrorKind, String)>,
+}
+
+impl<'a, C: PortConnector> TimedConnector<'a, C> {
+    fn new(inner: &'a C) -> Self {
+        Self {
+            inner,
+            stats: Mutex::new(ConnectStats::default()),
+        }
+    }
+
+    /// 取出并清除"目标有应答"的标记（用于判断当前出口是否连通）
+    fn take_reached(&self) -> bool {
+        std::mem::take(&mut self.stats.lock().unwrap().reached)
+    }
+
+    /// 取出并清除最后一次连接错误
+    fn take_error(&self) -> Option<(io::ErrorKind, String)> {
+        self.stats.lock().unwrap().error.take()
+    }
+
+    /// 结束任务并生成计时
+    fn finish(self, started: Instant, attempts: u32, hard_timeouts: u32) -> ProbeTiming {
+        let stats = self.stats.into_inner().unwrap();
+        ProbeTiming {
+            started,
+            finished: Instant::now(),
+            attempts,
+            timeouts: hard_timeouts + (stats.started - stats.completed),
+            connects: stats.completed,
+            connect_time: stats.total,
+            retransmits: 0,
+        }
+    }
+}
+
+impl<C: PortConnector> PortConnector for TimedConnector<'_, C> {
+    type Stream = C::Stream;
+
+    async fn connect(&self, ip: &str, port: u16, source: Option<IpAddr>) -> io::Result<C::Stream> {
+        self.stats.lock().unwrap().started += 1;
+        let start = Instant::now();
+        let result = self.inner.connect(ip, port, source).await;
+        let mut stats = self.stats.lock().unwrap();
+        stats.completed += 1;
+        stats.total += start.elapsed();
+        match result {
+            Ok(_) => stats.reached = true,
+            Err(ref e) if e.kind() == io::ErrorKind::ConnectionRefused => stats.reached = true,
+            Err(ref e) => stats.error = Some((e.kind(), e.to_string())),
+        }
+        result
+    }
+}
+
+/// 端口扫描的并发及超时参数
+#[derive(Debug, Clone, Copy)]
+pub struct PortProbeOptions<'a> {
+    /// 最大并发数
+    pub concurrency: usize,
+    /// 连接及读取超时
+    pub probe_timeout: Duration,
+    /// 探测超过硬性时限时的重试次数
+    pub retries: u32,
+    /// 依次尝试的本地出口地址（为空时由系统选路）
+    pub egress: &'a [IpAddr],
+    /// 探测每个主机前执行的敲门（为空时不敲门）
+    pub knocker: Option<&'a Knocker>,
+    /// 两段式探测计划（为空时只探测一轮，超时使用 `probe_timeout`）
+    pub retransmit: Option<RetransmitPlan>,
+    /// 端口确认开放后执行的补充探测
+    pub probes: &'a ProbeRegistry,
+}
+
+/// 使用指定连接方式并发扫描端口
+///
+/// 暂停期间不再发起新的连接，取消后不再取出新任务；
+/// 每个结果先推送给上下文的接收方，再连同该任务的计时交给 `on_result`。
+///
+/// 指定了多个出口时，经某个出口没有得到目标任何应答（不可达或超时）的任务改用下一个出口重试，
+/// 连通的出口记录在结果中。
+///
+/// 指定了敲门器时，每个主机在第一次探测前先敲门，结果中标注已敲门。
+///
+/// 指定了两段式探测计划时，首探超时的端口暂不给出结论，首轮全部结束后按主机成批重探，
+/// 结果中记录得出结论的阶段，重探的计时与首探合并后交给 `on_result`。
+///
+/// # 参数
+/// * `connector` - 连接方式
+/// * `tasks` - (IP, 端口) 任务（可以是惰性迭代器）
+/// * `fps` - 指纹库
+/// * `opts` - 并发及超时参数
+/// * `progress` - 进度条
+/// * `ctx` - 扫描上下文
+/// * `on_result` - 结果回调（按完成顺序调用，由调用方保存结果及汇总计时）
+pub async fn scan_ports_with<'a, C, I, F>(
+    connector: &C,
+    tasks: I,
+    fps: &[crate::commands::pentest::fingerprint::Fingerprint],
+    opts: PortProbeOptions<'_>,
+    progress: &ScanProgress,
+    ctx: &ScanContext,
+    on_result: F,
+) where
+    C: PortConnector,
+    I: IntoIterator<Item = (&'a str, u16)>,
+    F: FnMut(PortScanResult, ProbeTiming),
+{
+    scan_ports_streamed(
+        connector,
+        stream::iter(tasks),
+        fps,
+        opts,
+        progress,
+        ctx,
+        on_result,
+    )
+    .await;
+}
+
+/// 与 [`scan_ports_with`] 相同，但任务来自异步流（如上一阶段陆续发现的存活主机，见 [`live_port_tasks`]）
+///
+/// 任务流暂时没有新任务时工作池等待，直到任务流结束且进行中的探测全部完成才返回。
+pub async fn scan_ports_streamed<C, S, H, F>(
+    connector: &C,
+    tasks: S,
+    fps: &[crate::commands::pentest::fingerprint::Fingerprint],
+    opts: PortProbeOptions<'_>,
+    progress: &ScanProgress,
+    ctx: &ScanContext,
+    mut on_result: F,
+) where
+    C: PortConnector,
+    S: Stream<Item = (H, u16)>,
+    H: AsRef<str>,
+    F: FnMut(PortScanResult, ProbeTiming),
+{
+    let rtt = HostRtt::default();
+    // 两段式探测时首探超时的端口先不给出结论，首轮结束后再重探
+    let mut pending: Vec<(PortScanResult, ProbeTiming)> = Vec::new();
+    let (dispatch, results) = run_tracked_streamed(
+        tasks,
+        opts.concurrency,
+        progress,
+        ctx.token(),
+        |(ip, port)| {
+            // 放弃某个主机（如接口中取消该主机）时，其进行中的探测随主机令牌一起放弃
+            let host = ctx.host_token(ip.as_ref());
+            let rtt = &rtt;
+            async move {
+                let ip = ip.as_ref();
+                // 目标行上的参数覆盖优先于全局的超时及次数
+                let overrides = ctx.target_overrides(ip);
+                let probe_timeout = overrides
+                    .timeout()
+                    .unwrap_or_else(|| match opts.retransmit {
+                        Some(plan) => plan.first_timeout(rtt.get(ip)),
+                        None => opts.probe_timeout,
+                    });
+                let retries = overrides.count.map_or(opts.retries, |n| n - 1);
+                host.run_until_cancelled(probe_port(
+                    connector,
+                    ip,
+                    port,
+                    fps,
+                    &opts,
+                    progress,
+                    ctx,
+                    probe_timeout,
+                    retries,
+                ))
+                .await
+            }
+        },
+    );
+    let collector = results.for_each(|probe| {
+        let Some(Some((mut result, timing))) = probe else {
+            progress.record(Outcome::Cancelled);
+            return future::ready(());
+        };
+        if timing.connects > 0 {
+            rtt.observe(&result.ip, timing.connect_time / timing.connects);
+        }
+        if opts.retransmit.is_some() {
+            if result.timed_out() {
+                pending.push((result, timing));
+                return future::ready(());
+            }
+            result.stage = Some(ProbeStage::First);
+        }
+        let outcome = result.outcome();
+        ctx.emit_with_outcome(&result, outcome);
+        on_result(result, timing);
+        progress.record(outcome);
+        future::ready(())
+    });
+    tokio::join!(dispatch, collector);
+
+    if let Some(plan) = opts.retransmit
+        && !pending.is_empty()
+    {
+        retransmit_ports(
+            connector,
+            pending,
+            fps,
+            &opts,
+            plan,
+            progress,
+            ctx,
+            &mut on_result,
+        )
+        .await;
+    }
+}
+
+/// 两段式探测的重探：首探超时的端口按主机成批以重探超时再探测一次
+///
+/// 重探的任务已在首轮分发时计入统计，这里只记录结论；
+/// 扫描已取消或主机被放弃时不再重探，保留首探的结论。
+///
+/// # 参数
+/// * `connector` - 连接方式
+/// * `pending` - 首探超时的结果及其计时
+/// * `fps` - 指纹库
+/// * `opts` - 并发及超时参数
+/// * `plan` - 两段式探测计划
+/// * `progress` - 进度条
+/// * `ctx` - 扫描上下文
+/// * `on_result` - 结果回调
+#[allow(clippy::too_many_arguments)]
+async fn retransmit_ports<C, F>(
+    connector: &C,
+    mut pending: Vec<(PortScanResult, ProbeTiming)>,
+    fps: &[crate::commands::pentest::fingerprint::Fingerprint],
+    opts: &PortProbeOptions<'_>,
+    plan: RetransmitPlan,
+    progress: &ScanProgress,
+    ctx: &ScanContext,
+    on_result: &mut F,
+) where
+    C: PortConnector,
+    F: FnMut(PortScanResult, ProbeTiming),
+{
+    // 同一主机的端口相邻，主机的重探集中在一段时间内完成
+    pending.sort_by(|a, b| a.0.ip.cmp(&b.0.ip));
+    if !ctx.is_cancelled() {
+        progress.println(format!(
+            "  {} {} 个端口首探超时，以 {} 超时重探",
+            Icon::Scan,
+            pending.len(),
+            format_duration(plan.retry)
+        ));
+    }
+    let (dispatch, results) = run_streamed(
+        stream::iter(pending),
+        opts.concurrency,
+        |(first, first_timing)| {
+            let host = ctx.host_token(&first.ip);
+            async move {
+                let retries = ctx
+                    .target_overrides(&first.ip)
+                    .count
+                    .map_or(opts.retries, |n| n - 1);
+                let retried = if ctx.is_cancelled() {
+                    None
+                } else {
+                    host.run_until_cancelled(probe_port(
+                        connector, &first.ip, first.port, fps, opts, progress, ctx, plan.retry,
+                        retries,
+                    ))
+                    .await
+                };
+                (first, first_timing, retried)
+            }
+        },
+    );
+    let collector = results.for_each(|(first, first_timing, retried)| {
+        let (mut result, timing) = match retried {
+            Some((mut result, timing)) => {
+                result.stage = Some(ProbeStage::Retry);
+                let timing = ProbeTiming {
+                    retransmits: 1,
+                    ..timing
+                };
+                (result, first_timing.merge(timing))
+            }
+            None => (first, first_timing),
+        };
+        result.stage.get_or_insert(ProbeStage::First);
+        let outcome = result.outcome();
+        ctx.emit_with_outcome(&result, outcome);
+        on_result(result, timing);
+        progress.record(outcome);
+        future::ready(())
+    });
+    tokio::join!(dispatch, collector);
+}
+
+/// 探测单个端口：依次经各出口连接，超过硬性时限时按次数重试
+///
+/// # 参数
+/// * `connector` - 连接方式
+/// * `ip` - IP地址
+/// * `port` - 端口号
+/// * `fps` - 指纹库
+/// * `opts` - 并发及超时参数（使用其中的出口及敲门器）
+/// * `progress` - 进度条
+/// * `ctx` - 扫描上下文
+/// * `probe_timeout` - 本次探测的连接及读取超时
+/// * `retries` - 超过硬性时限时的重试次数
+///
+/// # 返回
+/// * `(PortScanResult, ProbeTiming)` - 探测结果及本次探测的计时
+#[allow(clippy::too_many_arguments)]
+async fn probe_port<C: PortConnector>(
+    connector: &C,
+    ip: &str,
+    port: u16,
+    fps: &[crate::commands::pentest::fingerprint::Fingerprint],
+    opts: &PortProbeOptions<'_>,
+    progress: &ScanProgress,
+    ctx: &ScanContext,
+    probe_timeout: Duration,
+    retries: u32,
+) -> (PortScanResult, ProbeTiming) {
+    ctx.pause.wait().await;
+    let knocked = match opts.knocker {
+        Some(knocker) => knocker.before_probe(ip).await,
+        None => false,
+    };
+    let timed = TimedConnector::new(connector);
+    let started = Instant::now();
+    let mut attempts = 0;
+    let mut hard_timeouts = 0;
+    // 未指定出口时只有一条由系统选路的路径
+    let routes = opts
+        .egress
+        .iter()
+        .copied()
+        .map(Some)
+        .chain(opts.egress.is_empty().then_some(None));
+    let mut result = None;
+    for source in routes {
+        let mut retry = 0;
+        let mut attempt = loop {
+            let permit = ctx.throttle().acquire(ip).await;
+            let result = scan_single_port(
+                &timed,
+                ip,
+                port,
+                source,
+                fps,
+                opts.probes,
+                progress,
+                probe_timeout,
+            )
+            .await;
+            drop(permit);
+            attempts += 1;
+            if result.status == "超时" {
+                hard_timeouts += 1;
+            }
+            if result.status != "超时" || retry >= retries {
+                break result;
+            }
+            retry += 1;
+        };
+        let reached = attempt.is_open() || timed.take_reached();
+        if reached {
+            attempt.egress = source;
+        }
+        let error = timed.take_error();
+        if !attempt.is_open() {
+            attempt.failure = Some(ConnectFailure::classify(reached, error));
+        }
+        result = Some(attempt);
+        if reached {
+            break;
+        }
+    }
+    let mut result = result.expect("至少有一条探测路径");
+    result.knocked = knocked;
+    let timing = timed.finish(started, attempts, hard_timeouts);
+    (result, timing)
+}
+
+/// 存活探测阶段（与端口扫描流水线执行，见 [`live_port_tasks`]）
+pub struct LiveStage<'a, P> {
+    /// 探测器
+    pub pinger: &'a P,
+    /// 超时及尝试次数
+    pub opts: PingOptions,
+    /// 最大并发数
+    pub concurrency: usize,
+    /// 存活探测的进度条
+    pub progress: &'a ScanProgress,
+    /// 存活探测的上下文（通常不推送结果，见 [`ScanContext::without_results`]）
+    pub ctx: &'a ScanContext,
+    /// 全部存活探测结果（用于存活主机列表及TTL）
+    pub results: &'a ResultCollector<PingResult>,
+}
+
+/// 存活探测与端口扫描流水线：主机确认存活后立即产出其端口的扫描任务
+///
+/// 存活探测的结果流与端口扫描之间是有界通道，端口扫描跟不上时存活探测随之放缓；
+/// 端口扫描进度条的总数随存活主机的发现逐步增加。
+/// `interleave` 为真时（限制了单主机并行数）已发现的各存活主机的端口轮流产出，
+/// 避免工作槽位都在等同一主机；否则逐个主机产出（敲门后的开放窗口较短）。
+///
+/// # 参数
+/// * `live` - 存活探测阶段
+/// * `targets` - 全部目标
+/// * `ports` - 每个存活主机扫描的端口
+/// * `progress` - 端口扫描的进度条
+/// * `interleave` - 是否轮流产出各主机的端口
+///
+/// # 返回
+/// * `(存活探测的分发任务, 端口扫描任务流)` - 两者须同时驱动（任务流交给 [`scan_ports_streamed`]）
+pub fn live_port_tasks<'a, P: Pinger>(
+    live: LiveStage<'a, P>,
+    targets: impl IntoIterator<Item = String, IntoIter: Send + 'a>,
+    ports: &'a [u16],
+    progress: &'a ScanProgress,
+    interleave: bool,
+) -> (
+    impl Future<Output = ()> + Send + 'a,
+    BoxStream<'a, (Arc<str>, u16)>,
+) {
+    // 目标按需取出交给存活探测，存活主机的地址由其各端口的任务共用
+    let (dispatch, results) = ping_streamed(
+        live.pinger,
+        targets,
+        live.opts,
+        live.concurrency,
+        live.progress,
+        live.ctx,
+    );
+    let collected = live.results;
+    let alive = results.filter_map(move |result| {
+        let host = result
+            .is_success()
+            .then(|| Arc::<str>::from(result.ip.as_str()));
+        if host.is_some() {
+            progress.inc_length(ports.len() as u64);
+        }
+        collected.push(result);
+        future::ready(host)
+    });
+    let host_tasks =
+        move |ip: Arc<str>| stream::iter(ports.iter().map(move |&port| (ip.clone(), port)));
+    let tasks = if interleave {
+        alive.map(host_tasks).flatten_unordered(None).boxed()
+    } else {
+        alive.flat_map(host_tasks).boxed()
+    };
+
+    (dispatch, tasks)
+}
+
+/// 扫描单个端口
+///
+/// 连接成功即为开放：先识别服务端主动发送的数据，再依次执行启用的补充探测，
+/// 仍未识别出服务时按默认端口表标注。
+///
+/// # 参数
+/// * `connector` - 连接方式
+/// * `ip` - IP地址
+/// * `port` - 端口号
+/// * `source` - 本地出口地址（为 `None` 时由系统选路）
+/// * `fps` - 指纹库
+/// * `probes` - 补充探测
+/// * `progress` - 进度条（用于输出信息）
+/// * `probe_timeout` - 连接及读取超时
+///
+/// # 返回
+/// * `PortScanResult` - 扫描结果
+#[allow(clippy::too_many_arguments)]
+async fn scan_single_port<C: PortConnector>(
+    connector: &C,
+    ip: &str,
+    port: u16,
+    source: Option<IpAddr>,
+    _fps: &[crate::commands::pentest::fingerprint::Fingerprint],
+    probes: &ProbeRegistry,
+    progress: &ScanProgress,
+    probe_timeout: Duration,
+) -> PortScanResult {
+    // 单个探测阶段（连接 + 读取）的硬性时限，防止慢速发送的服务端拖住工作槽位
+    let stage_limit = probe_timeout * 2 + PROBE_GRACE;
+
+    // 尝试连接并读取banner
+    let initial = match timeout(
+        stage_limit,
+        connect_and_read(
+            connector,
+            ip,
+            port,
+            source,
+            probe_timeout,
+            probe_timeout,
+            Duration::from_millis(400),
+        ),
+    )
+    .await
+    {
+        Ok(initial) => initial,
+        Err(_) => return PortScanResult::timeout(ip.to_string(), port),
+    };
+    let Some(buf) = initial else {
+        return PortScanResult::closed(ip.to_string(), port);
+    };
+
+    // 识别协议和服务，再执行补充探测
+    let (banner, evidence) = classify_banner(&buf);
+    let mut result = PortScanResult::open(ip.to_string(), port, banner, evidence);
+    let reconnect = Reconnect {
+        connector,
+        ip,
+        port,
+        source,
+    };
+    probes
+        .run(ip, port, probe_timeout, &reconnect, &mut result)
+        .await;
+    fallback_banner(&mut result);
+    progress.println(format!(
+        "  {} {}:{} | {} | {:?}",
+        Icon::Ok,
+        ip,
+        port,
+        result.banner,
+        result.evidence
+    ));
+    result
+}
+
+/// 补充探测重新连接同一端口（与扫描使用同一连接方式及出口）
+struct Reconnect<'a, C> {
+    connector: &'a C,
+    ip: &'a str,
+    port: u16,
+    source: Option<IpAddr>,
+}
+
+impl<C: PortConnector> ProbeConnect for Reconnect<'_, C> {
+    fn connect(&self) -> BoxFuture<'_, io::Result<ProbeStream>> {
+        Box::pin(async move {
+            let stream = self
+                .connector
+                .connect(self.ip, self.port, self.source)
+                .await?;
+            Ok(Box::new(stream) as ProbeStream)
+        })
+    }
+}
+
+/// 建立TCP连接并读取服务端主动发送的数据（banner）
+///
+/// # 参数
+/// * `connector` - 连接方式
+/// * `ip` - IP地址
+/// * `port` - 端口号
+/// * `source` - 本地出口地址
+/// * `connect_timeout` - 连接超时
+/// * `first_read_timeout` - 等待首个数据包的超时
+/// * `idle_timeout` - 后续数据包之间的空闲超时
+///
+/// 最多读取 [`BANNER_MAX_BYTES`] 字节。
+///
+/// # 返回
+/// * `Some(Vec<u8>)` - 连接成功，及服务端主动发送的数据（未发送时为空）
+/// * `None` - 连接失败
+async fn connect_and_read<C: PortConnector>(
+    connector: &C,
+    ip: &str,
+    port: u16,
+    source: Option<IpAddr>,
+    connect_timeout: Duration,
+    first_read_timeout: Duration,
+    idle_timeout: Duration,
+) -> Option<Vec<u8>> {
+    let max_bytes = BANNER_MAX_BYTES;
+    let mut stream = timeout(connect_timeout, connector.connect(ip, port, source))
+        .await
+        .ok()?
+        .ok()?;
+
+    let mut buf = Vec::with_capacity(max_bytes.min(1024));
+    let mut chunk = [0u8; 1024];
+    let mut wait = first_read_timeout;
+
+    while buf.len() < max_bytes {
+        match timeout(wait, stream.read(&mut chunk)).await {
+            Ok(Ok(n)) if n > 0 => {
+                let take = n.min(max_bytes - buf.len());
+                buf.extend_from_slice(&chunk[..take]);
+                wait = idle_timeout;
+            }
+            _ => break,
+        }
+    }
+
+    Some(buf)
+}
+
+/// 探测阶段硬性时限在连接与读取超时之外的宽限时间
+const PROBE_GRACE: Duration = Duration::from_secs(1);
+
+/// 连接后读取服务端主动发送数据的上限（字节）
+const BANNER_MAX_BYTES: usize = 4096;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::net::ping::{ProbeOutcome, ping_concurrent_with};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_slow_drip_service_hits_hard_limit() {
+        // 每300ms发送1字节的服务端：不触发空闲超时，也读不满缓冲区
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            loop {
+                if socket.write_all(b"x").await.is_err() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(300)).await;
+            }
+        });
+
+        let progress = ScanProgress::new(1);
+        let start = Instant::now();
+        let result = scan_single_port(
+            &TcpConnector,
+            "127.0.0.1",
+            port,
+            None,
+            &[],
+            ProbeRegistry::standard(),
+            &progress,
+            Duration::from_secs(1),
+        )
+        .await;
+
+        assert_eq!(result.status, "超时");
+        assert!(!result.is_open());
+        // 硬性时限为 2 * 1s + PROBE_GRACE
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    /// 连接前先等待一段时间的本机连接方式（模拟往返时间较长的慢速路径）
+    struct SlowConnector {
+        /// 端口 -> 连接前的等待时间，未配置的端口立即连接
+        delays: HashMap<u16, Duration>,
+    }
+
+    impl PortConnector for SlowConnector {
+        type Stream = TcpStream;
+
+        async fn connect(
+            &self,
+            ip: &str,
+            port: u16,
+            source: Option<IpAddr>,
+        ) -> io::Result<TcpStream> {
+            if let Some(&delay) = self.delays.get(&port) {
+                tokio::time::sleep(delay).await;
+            }
+            TcpConnector.connect(ip, port, source).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retransmit_finds_slow_open_port() {
+        // 两个连接后立即发送banner的本机服务，其中一个的连接要等400ms才建立
+        let mut ports = Vec::new();
+        for _ in 0..2 {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            ports.push(listener.local_addr().unwrap().port());
+            tokio::spawn(async move {
+                while let Ok((mut socket, _)) = listener.accept().await {
+                    let _ = socket.write_all(b"SSH-2.0-OpenSSH_9.6\r\n").await;
+                }
+            });
+        }
+        let (fast, slow) = (ports[0], ports[1]);
+        let connector = SlowConnector {
+            delays: HashMap::from([(slow, Duration::from_millis(400))]),
+        };
+        let ctx = background();
+        let scan = |opts: PortProbeOptions<'static>| {
+            let (connector, ctx, ports) = (&connector, &ctx, &ports);
+            async move {
+                let progress = ctx.new_progress(2);
+                let tasks = ports.iter().map(|&port| ("127.0.0.1", port));
+                let mut results = Vec::new();
+                scan_ports_with(connector, tasks, &[], opts, &progress, ctx, |r, t| {
+                    results.push((r, t))
+                })
+                .await;
+                results.sort_by_key(|(r, _)| r.port != fast);
+                (results, progress.stats().snapshot())
+            }
+        };
+        let short = Duration::from_millis(100);
+
+        // 单一的短超时把慢速的开放端口误判为被过滤
+        let (single, _) = scan(PortProbeOptions {
+            probe_timeout: short,
+            ..opts(2)
+        })
+        .await;
+        assert!(single[0].0.is_open());
+        assert!(!single[1].0.is_open());
+        assert_eq!(single[1].0.failure, Some(ConnectFailure::Filtered));
+
+        // 两段式探测：首探同样用短超时，只有超时的端口以长超时重探
+        let plan = RetransmitPlan {
+            first: Some(short),
+            retry: Duration::from_secs(2),
+            fallback: Duration::from_secs(2),
+        };
+        let (staged, stats) = scan(PortProbeOptions {
+            retransmit: Some(plan),
+            ..opts(2)
+        })
+        .await;
+        let (first, first_timing) = &staged[0];
+        assert!(first.is_open());
+        assert_eq!(first.stage, Some(ProbeStage::First));
+        assert_eq!(first_timing.retransmits, 0);
+        let (retried, timing) = &staged[1];
+        assert!(retried.is_open(), "{:?}", retried);
+        assert_eq!(retried.stage, Some(ProbeStage::Retry));
+        assert_eq!(timing.retransmits, 1);
+        assert_eq!(timing.attempts, 2);
+        assert!(timing.timeouts > 0);
+        // 重探不另计为新任务
+        assert_eq!(
+            (stats.dispatched, stats.completed, stats.succeeded),
+            (2, 2, 2)
+        );
+    }
+
+    /// 预设的端口行为
+    #[derive(Clone)]
+    enum Behavior {
+        /// 拒绝连接
+        Refuse,
+        /// 连接后主动发送数据
+        Banner(&'static [u8]),
+        /// 连接后不发送数据，收到探测报文后回复
+        Respond(&'static [u8]),
+        /// 连接后立即关闭
+        Silent,
+    }
+
+    /// 按端口返回预设连接结果的连接方式，记录连接次数和最大并发
+    #[derive(Default)]
+    struct ScriptedConnector {
+        /// 端口 -> (连接耗时, 行为)，未配置的端口拒绝连接
+        ports: HashMap<u16, (Duration, Behavior)>,
+        /// 未配置端口的连接耗时
+        latency: Duration,
+        /// 经该出口的连接一律不可达
+        unreachable_via: Option<IpAddr>,
+        connects: AtomicUsize,
+        in_flight: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl ScriptedConnector {
+        fn with(mut self, port: u16, latency_ms: u64, behavior: Behavior) -> Self {
+            self.ports
+                .insert(port, (Duration::from_millis(latency_ms), behavior));
+            self
+        }
+    }
+
+    impl PortConnector for ScriptedConnector {
+        type Stream = tokio::io::DuplexStream;
+
+        async fn connect(
+            &self,
+            _ip: &str,
+            port: u16,
+            source: Option<IpAddr>,
+        ) -> io::Result<Self::Stream> {
+            self.connects.fetch_add(1, Ordering::SeqCst);
+            if source.is_some() && source == self.unreachable_via {
+                return Err(io::ErrorKind::HostUnreachable.into());
+            }
+            let (latency, behavior) = self
+                .ports
+                .get(&port)
+                .cloned()
+                .unwrap_or((self.latency, Behavior::Refuse));
+
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(latency).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            let (client, mut server) = tokio::io::duplex(4096);
+            match behavior {
+                Behavior::Refuse => return Err(io::ErrorKind::ConnectionRefused.into()),
+                Behavior::Banner(data) => {
+                    tokio::spawn(async move {
+                        let _ = server.write_all(data).await;
+                    });
+                }
+                Behavior::Respond(data) => {
+                    tokio::spawn(async move {
+                        let mut buf = [0u8; 256];
+                        if let Ok(n) = server.read(&mut buf).await
+                            && n > 0
+                        {
+                            let _ = server.write_all(data).await;
+                        }
+                    });
+                }
+                Behavior::Silent => drop(server),
+            }
+            Ok(client)
+        }
+    }
+
+    fn opts(concurrency: usize) -> PortProbeOptions<'static> {
+        PortProbeOptions {
+            egress: &[],
+            knocker: None,
+            retransmit: None,
+            concurrency,
+            retries: 0,
+            probe_timeout: Duration::from_secs(1),
+            probes: ProbeRegistry::standard(),
+        }
+    }
+
+    async fn scan(
+        connector: &ScriptedConnector,
+        ports: &[u16],
+        concurrency: usize,
+        ctx: &ScanContext,
+    ) -> Vec<PortScanResult> {
+        let progress = ctx.new_progress(ports.len() as u64);
+        let tasks = ports.iter().map(|&port| ("10.0.0.1", port));
+        let mut results = Vec::new();
+        scan_ports_with(
+            connector,
+            tasks,
+            &[],
+            opts(concurrency),
+            &progress,
+            ctx,
+            |r, _| results.push(r),
+        )
+        .await;
+        results
+    }
+
+    fn background() -> ScanContext {
+        ScanContext::background(tokio::sync::mpsc::unbounded_channel().0)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_connect_concurrency_limit_is_respected() {
+        let connector = ScriptedConnector {
+            latency: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let ports: Vec<u16> = (1..=30).collect();
+        let ctx = background();
+        let results = scan(&connector, &ports, 5, &ctx).await;
+
+        assert_eq!(results.len(), 30);
+        assert!(results.iter().all(|r| r.status == "关闭"));
+        assert_eq!(connector.peak.load(Ordering::SeqCst), 5);
+        // 拒绝连接的端口不再进行协议探测
+        assert_eq!(connector.connects.load(Ordering::SeqCst), 30);
+        assert_eq!(ctx.progress(), (30, 30));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_services_are_classified_in_completion_order() {
+        let connector = ScriptedConnector::default()
+            .with(22, 300, Behavior::Banner(b"SSH-2.0-OpenSSH_9.6\r\n"))
+            .with(
+                80,
+                100,
+                Behavior::Respond(b"HTTP/1.1 200 OK\r\nServer: nginx\r\n\r\n"),
+            )
+            .with(3306, 200, Behavior::Silent)
+            .with(8080, 50, Behavior::Refuse);
+        let mut seen = Vec::new();
+        let ctx = background();
+        let progress = ctx.new_progress(4);
+        let tasks = [22, 80, 3306, 8080].map(|port| ("10.0.0.1", port));
+        let mut results = Vec::new();
+        let mut timings = BTreeMap::new();
+        scan_ports_with(&connector, tasks, &[], opts(4), &progress, &ctx, |r, t| {
+            seen.push(r.port);
+            timings.insert(r.port, t);
+            results.push(r);
+        })
+        .await;
+
+        let order: Vec<u16> = results.iter().map(|r| r.port).collect();
+        assert_eq!(order, seen);
+        // 3306 首次连接即断开，重连后完成；80 需等待首包超时后再发送HTTP探测
+        assert_eq!(order, [8080, 22, 3306, 80]);
+
+        let find = |port: u16| results.iter().find(|r| r.port == port).unwrap();
+        assert_eq!(find(22).banner, "SSH-2.0-OpenSSH_9.6");
+        assert_eq!(find(22).evidence, ["ssh-banner"]);
+        assert_eq!(find(80).banner, "HTTP/1.1 200 OK | nginx");
+        assert_eq!(find(3306).banner, "MySQL");
+        assert!(!find(3306).received_data());
+        assert!(!find(8080).is_open());
+
+        // 计时来自工作池：每个任务的连接次数与探测流程一致
+        let connects: Vec<u32> = timings.values().map(|t| t.connects).collect();
+        assert_eq!(connects, [1, 2, 2, 1]);
+        assert!(timings.values().all(|t| t.attempts == 1 && t.timeouts == 0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_unreachable_egress_falls_back_to_next_source() {
+        let first: IpAddr = "10.0.0.5".parse().unwrap();
+        let second: IpAddr = "10.1.0.5".parse().unwrap();
+        let connector = ScriptedConnector {
+            unreachable_via: Some(first),
+            ..Default::default()
+        }
+        .with(22, 10, Behavior::Banner(b"SSH-2.0-OpenSSH_9.6\r\n"));
+        let ctx = background();
+        let progress = ctx.new_progress(2);
+        let egress = [first, second];
+        let opts = PortProbeOptions {
+            egress: &egress,
+            ..opts(2)
+        };
+        let tasks = [("10.0.0.1", 22), ("10.0.0.1", 23)];
+        let mut results = Vec::new();
+        scan_ports_with(&connector, tasks, &[], opts, &progress, &ctx, |r, t| {
+            results.push((r, t))
+        })
+        .await;
+        results.sort_by_key(|(r, _)| r.port);
+
+        let (ssh, timing) = &results[0];
+        assert!(ssh.is_open());
+        assert_eq!(ssh.egress, Some(second));
+        assert_eq!(timing.attempts, 2);
+        // 被拒绝说明经第二个出口能到达目标，同样记为连通
+        let (refused, _) = &results[1];
+        assert!(!refused.is_open());
+        assert_eq!(refused.egress, Some(second));
+
+        let results: Vec<PortScanResult> = results.into_iter().map(|(r, _)| r).collect();
+        assert_eq!(
+            egress_stats(&results, &egress),
+            [(first, 2, 0), (second, 2, 2)]
+        );
+        // 各出口均不通的任务计入全部出口的尝试数
+        let lost = PortScanResult::closed("10.0.0.2".to_string(), 22);
+        assert_eq!(
+            egress_stats(&[lost], &egress),
+            [(first, 1, 0), (second, 1, 0)]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_closed_ports_record_why_they_failed() {
+        let gateway: IpAddr = "10.0.0.5".parse().unwrap();
+        let connector = ScriptedConnector {
+            unreachable_via: Some(gateway),
+            latency: Duration::from_secs(30),
+            ..Default::default()
+        }
+        .with(22, 10, Behavior::Refuse);
+        let ctx = background();
+        let filtered = scan(&connector, &[22, 23], 2, &ctx).await;
+        let failures: Vec<_> = filtered.iter().map(|r| r.failure.clone()).collect();
+        assert!(failures.contains(&Some(ConnectFailure::Reached)));
+        assert!(failures.contains(&Some(ConnectFailure::Filtered)));
+
+        let progress = ctx.new_progress(1);
+        let egress = [gateway];
+        let opts = PortProbeOptions {
+            egress: &egress,
+            ..opts(1)
+        };
+        let mut results = Vec::new();
+        scan_ports_with(
+            &connector,
+            [("10.0.0.1", 22)],
+            &[],
+            opts,
+            &progress,
+            &ctx,
+            |r, _| results.push(r),
+        )
+        .await;
+        assert!(matches!(
+            results[0].failure,
+            Some(ConnectFailure::Unreachable(_))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancel_stops_port_dispatch() {
+        let connector = ScriptedConnector {
+            latency: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let ports: Vec<u16> = (1..=20).collect();
+        let ctx = background();
+        let (results, _) = tokio::join!(scan(&connector, &ports, 4, &ctx), async {
+            // 每个端口一次连接共100ms，第二批进行中时取消
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            ctx.cancel();
+        });
+
+        // 第一批的结果保留，第二批在第一次连接中被放弃
+        assert_eq!(results.len(), 4);
+        assert_eq!(connector.connects.load(Ordering::SeqCst), 8);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_verify_pass_reprobes_suspicious_ports() {
+        let open =
+            |ip: &str, port| PortScanResult::open(ip.to_string(), port, String::new(), vec![]);
+        let closed = |port| PortScanResult::closed("10.0.0.1".to_string(), port);
+        let mut results = vec![
+            open("10.0.0.1", 22),
+            closed(80),
+            closed(443),
+            closed(8080),
+            open("10.0.0.2", 22),
+            open("10.0.0.2", 80),
+            open("10.0.0.2", 443),
+        ];
+        let verify = VerifyArgs {
+            verify: true,
+            ..Default::default()
+        };
+        let flapped = HashSet::from([("10.0.0.1".to_string(), 8080)]);
+        let baseline = HashSet::from([("10.0.0.1".to_string(), 80), ("10.0.0.2".to_string(), 22)]);
+        let candidates = verify_candidates(&results, &verify, &flapped, &baseline);
+        // 10.0.0.1 只有一个开放端口；10.0.0.2 开放端口较多且与基线一致，不复核
+        assert_eq!(
+            candidates,
+            [
+                ("10.0.0.1".to_string(), 22),
+                ("10.0.0.1".to_string(), 80),
+                ("10.0.0.1".to_string(), 8080),
+            ]
+        );
+        let only_sparse = VerifyArgs {
+            verify_only: vec![VerifyKind::Sparse],
+            ..verify.clone()
+        };
+        assert_eq!(
+            verify_candidates(&results, &only_sparse, &flapped, &baseline).len(),
+            1
+        );
+
+        // 复核时 22 拒绝连接（主扫描为SYN代理的假开放），80 返回banner，8080 仍然拒绝
+        let connector =
+            ScriptedConnector::default().with(80, 10, Behavior::Banner(b"SSH-2.0-OpenSSH_9.6\r\n"));
+        let ctx = background();
+        let stats = ScanStats::default();
+        for result in &results {
+            stats.dispatch();
+            stats.complete(result.outcome());
+        }
+        let (checked, changed) = verify_results(
+            &connector,
+            &mut results,
+            candidates,
+            &[],
+            opts(4),
+            &ctx,
+            &stats,
+        )
+        .await;
+        assert_eq!((checked, changed), (3, 2));
+        let open = results.iter().filter(|r| r.is_open()).count();
+        assert_eq!(stats.snapshot().succeeded as usize, open);
+        assert!(!results[0].is_open());
+        assert_eq!(results[0].verification, Some(Verification::verified(true)));
+        assert!(results[1].is_open());
+        assert_eq!(results[3].verification, Some(Verification::verified(false)));
+        assert_eq!(results[2].verification, Some(Verification::UNVERIFIED));
+        assert_eq!(ctx.progress(), (3, 3));
+    }
+
+    #[test]
+    fn test_findings_skip_ports_of_suspected_hosts() {
+        let ssh = PortScanResult::open(
+            "10.0.0.1".to_string(),
+            22,
+            "SSH-2.0-OpenSSH_8.9".to_string(),
+            vec!["banner匹配".to_string()],
+        );
+        let trap = PortScanResult::open("10.0.0.9".to_string(), 80, String::new(), vec![]);
+        let assessment = HostAssessment {
+            ip: "10.0.0.9".to_string(),
+            score: 3.0,
+            reasons: vec!["全部端口开放".to_string()],
+            suspected: true,
+        };
+
+        let list = findings(&[&ssh, &trap], &[&assessment]);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].port, Some(22));
+        assert_eq!(list[0].evidence, "SSH-2.0-OpenSSH_8.9; banner匹配");
+        assert_eq!(list[1].asset, "10.0.0.9");
+        assert_eq!(list[1].check_id, "suspected-honeypot");
+        assert!(list[1].port.is_none());
+    }
+
+    /// 稀疏网段的存活探测：只有少数主机回复，其余等到超时
+    struct SparsePinger(HashSet<&'static str>);
+
+    impl Pinger for SparsePinger {
+        async fn probe(&self, ip: &str, _opts: PingOptions) -> ProbeOutcome {
+            if self.0.contains(ip) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                ProbeOutcome::Reply {
+                    response_time: Some(10.0),
+                    ttl: Some(64),
+                    tcp_port: None,
+                }
+            } else {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                ProbeOutcome::TimedOut
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_live_hosts_are_scanned_while_pinging() {
+        // /24 中只有 4 个存活主机，每个主机扫描 50 个端口
+        let alive = ["10.0.0.3", "10.0.0.60", "10.0.0.130", "10.0.0.250"];
+        let pinger = SparsePinger(alive.into_iter().collect());
+        let targets: Vec<String> = (1..=254).map(|i| format!("10.0.0.{}", i)).collect();
+        let ports: Vec<u16> = (1..=50).collect();
+        let connector = ScriptedConnector {
+            latency: Duration::from_millis(200),
+            ..Default::default()
+        };
+        let ctx = background();
+        let ping_opts = PingOptions {
+            timeout_secs: 1,
+            count: 1,
+            stats: false,
+        };
+
+        // 先探测存活再扫描端口的耗时
+        let started = tokio::time::Instant::now();
+        let pinged = ResultCollector::new();
+        let ping_progress = ctx.new_progress(targets.len() as u64);
+        ping_concurrent_with(
+            &pinger,
+            targets.clone(),
+            ping_opts,
+            16,
+            &ping_progress,
+            &ctx,
+            &pinged,
+        )
+        .await
+        .unwrap();
+        let ping_time = started.elapsed();
+        let alive_ips: Vec<&str> = alive.to_vec();
+        let started = tokio::time::Instant::now();
+        let sequential = scan(&connector, &ports, 10, &ctx).await.len() * alive_ips.len();
+        let port_time = started.elapsed() * alive_ips.len() as u32;
+
+        for interleave in [false, true] {
+            let started = tokio::time::Instant::now();
+            let pinged = ResultCollector::new();
+            let ping_progress = ctx.new_progress(targets.len() as u64);
+            let progress = ctx.new_progress(0);
+            let live = LiveStage {
+                pinger: &pinger,
+                opts: ping_opts,
+                concurrency: 16,
+                progress: &ping_progress,
+                ctx: &ctx,
+                results: &pinged,
+            };
+            let (dispatch, tasks) =
+                live_port_tasks(live, targets.clone(), &ports, &progress, interleave);
+            let mut results = Vec::new();
+            let scan =
+                scan_ports_streamed(&connector, tasks, &[], opts(10), &progress, &ctx, |r, _| {
+                    results.push(r)
+                });
+            tokio::join!(dispatch, scan);
+            let pipelined = started.elapsed();
+
+            assert_eq!(pinged.len(), 254);
+            assert_eq!(results.len(), sequential);
+            assert_eq!(progress.length(), 200);
+            let hosts: HashSet<&str> = results.iter().map(|r| r.ip.as_str()).collect();
+            assert_eq!(hosts, alive.into_iter().collect());
+            // 总耗时接近两个阶段中较长的一个，而不是两者之和
+            assert!(pipelined < ping_time + port_time / 4, "{:?}", pipelined);
+            assert!(pipelined >= ping_time.max(port_time));
         }
     }
 }
@@ -0,0 +1,855 @@
+//! 端口扫描的补充探测
+//!
+//! 端口确认开放后，扫描流程按注册顺序依次执行启用的探测（`--probes` 选择），
+//! 每个探测只声明自己适用的端口及产出的列，不需要改动扫描流程和导出代码：
+//! 探测值保存在结果的 `probes` 字段中，Excel按 [`ProbeRegistry::builtin`] 中声明的列导出。
+//!
+//! 主机级的补充识别（系统推测、蜜罐检测、复核）基于整台主机的结果，不属于端口探测。
+//!
+//! 新增一个探测只需实现 [`Probe`] 并在 [`ProbeRegistry::builtin`] 中注册：
+//!
+//! ```
+//! use futures::future::BoxFuture;
+//! use gxr::commands::pentest::portscan::PortScanResult;
+//! use gxr::commands::pentest::probes::{
+//!     Probe, ProbeColumn, ProbeContext, ProbeOutput, ProbeRegistry, ProbeStream,
+//! };
+//! use std::time::Duration;
+//! use tokio::io::{AsyncReadExt, AsyncWriteExt};
+//!
+//! /// Redis是否允许未认证访问
+//! struct RedisPing;
+//!
+//! impl Probe for RedisPing {
+//!     fn name(&self) -> &'static str {
+//!         "redis"
+//!     }
+//!
+//!     fn columns(&self) -> &'static [ProbeColumn] {
+//!         &[ProbeColumn { key: "redis_auth", header: "Redis认证" }]
+//!     }
+//!
+//!     fn applicable(&self, port: u16, _prior: &PortScanResult) -> bool {
+//!         port == 6379
+//!     }
+//!
+//!     fn run<'a>(&'a self, ctx: &'a ProbeContext<'a>) -> BoxFuture<'a, ProbeOutput> {
+//!         Box::pin(async move {
+//!             let mut output = ProbeOutput::default();
+//!             let Ok(mut stream) = ctx.connect().await else {
+//!                 return output;
+//!             };
+//!             let mut buf = [0u8; 64];
+//!             if stream.write_all(b"PING\r\n").await.is_ok()
+//!                 && let Ok(n) = stream.read(&mut buf).await
+//!             {
+//!                 let open = buf[..n].starts_with(b"+PONG");
+//!                 output.set("redis_auth", if open { "无需认证" } else { "需要认证" });
+//!                 output.evidence.push("redis-ping".to_string());
+//!             }
+//!             output
+//!         })
+//!     }
+//! }
+//!
+//! # tokio::runtime::Runtime::new().unwrap().block_on(async {
+//! let mut registry = ProbeRegistry::new();
+//! registry.register(RedisPing);
+//! assert_eq!(registry.names(), ["redis"]);
+//!
+//! // 用内存连接模拟未设置密码的Redis
+//! let connect = || async {
+//!     let (client, mut server) = tokio::io::duplex(64);
+//!     tokio::spawn(async move {
+//!         let mut buf = [0u8; 16];
+//!         let _ = server.read(&mut buf).await;
+//!         let _ = server.write_all(b"+PONG\r\n").await;
+//!     });
+//!     Ok(Box::new(client) as ProbeStream)
+//! };
+//! let mut result = PortScanResult::open("10.0.0.1".to_string(), 6379, String::new(), Vec::new());
+//! registry.run("10.0.0.1", 6379, Duration::from_secs(1), &connect, &mut result).await;
+//! assert_eq!(result.probes["redis_auth"].to_string(), "无需认证");
+//! assert_eq!(result.evidence, ["redis-ping"]);
+//! # });
+//! ```
+use crate::commands::net::http::extract_title;
+use crate::commands::pentest::port_list::service_name;
+use crate::commands::pentest::portscan::{CONNECT_EVIDENCE, PortScanResult};
+use crate::utils::cluster::favicon_hash;
+use crate::utils::identity::identity;
+use crate::utils::tls::connect_insecure;
+use futures::future::BoxFuture;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::timeout;
+
+/// 未指定 `--probes` 时启用的探测
+pub const DEFAULT_PROBES: &[&str] = &["banner"];
+
+/// 探测连接：可读写的数据流
+pub trait ProbeIo: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> ProbeIo for T {}
+
+/// 探测建立的连接
+pub type ProbeStream = Box<dyn ProbeIo>;
+
+/// 建立到当前端口的新连接（与扫描使用同一连接方式及出口）
+///
+/// 返回连接的闭包（如测试中的内存连接）可直接使用。
+pub trait ProbeConnect: Send + Sync {
+    /// 建立连接（超时由调用方控制）
+    fn connect(&self) -> BoxFuture<'_, io::Result<ProbeStream>>;
+}
+
+impl<F, Fut> ProbeConnect for F
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = io::Result<ProbeStream>> + Send + 'static,
+{
+    fn connect(&self) -> BoxFuture<'_, io::Result<ProbeStream>> {
+        Box::pin(self())
+    }
+}
+
+/// 探测产出的一列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeColumn {
+    /// 结果中的字段名（各探测之间不能重复，如 `tls_version`）
+    pub key: &'static str,
+    /// Excel中的列名
+    pub header: &'static str,
+}
+
+/// 探测值
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum ProbeValue {
+    /// 文本
+    Text(String),
+    /// 整数
+    Integer(i64),
+    /// 是/否
+    Flag(bool),
+}
+
+impl fmt::Display for ProbeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(text) => f.write_str(text),
+            Self::Integer(n) => write!(f, "{}", n),
+            Self::Flag(flag) => f.write_str(if *flag { "是" } else { "否" }),
+        }
+    }
+}
+
+impl From<String> for ProbeValue {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<&str> for ProbeValue {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+impl From<i64> for ProbeValue {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<bool> for ProbeValue {
+    fn from(value: bool) -> Self {
+        Self::Flag(value)
+    }
+}
+
+/// 一次探测的产出
+#[derive(Debug, Default)]
+pub struct ProbeOutput {
+    /// 识别到的服务（为 `None` 时保留已有的）
+    pub banner: Option<String>,
+    /// 追加的识别证据
+    pub evidence: Vec<String>,
+    /// 各列的值（字段名须在 [`Probe::columns`] 中声明）
+    pub values: Vec<(&'static str, ProbeValue)>,
+}
+
+impl ProbeOutput {
+    /// 设置一列的值
+    pub fn set(&mut self, key: &'static str, value: impl Into<ProbeValue>) {
+        self.values.push((key, value.into()));
+    }
+}
+
+/// 探测时可用的信息
+pub struct ProbeContext<'a> {
+    /// IP地址
+    pub ip: &'a str,
+    /// 端口号
+    pub port: u16,
+    /// 连接、发送及读取超时
+    pub io_timeout: Duration,
+    /// 扫描及之前的探测得到的结果
+    pub prior: &'a PortScanResult,
+    connector: &'a dyn ProbeConnect,
+}
+
+impl<'a> ProbeContext<'a> {
+    /// 建立到当前端口的新连接
+    pub async fn connect(&self) -> io::Result<ProbeStream> {
+        timeout(self.io_timeout, self.connector.connect())
+            .await
+            .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))?
+    }
+}
+
+/// 端口确认开放后执行的探测
+pub trait Probe: Send + Sync {
+    /// 探测名称（`--probes` 中使用）
+    fn name(&self) -> &'static str;
+
+    /// 产出的列（没有单独的列、只补充服务及证据的探测为空）
+    fn columns(&self) -> &'static [ProbeColumn] {
+        &[]
+    }
+
+    /// 是否适用于该端口
+    ///
+    /// # 参数
+    /// * `port` - 端口号
+    /// * `prior` - 扫描及之前的探测得到的结果
+    fn applicable(&self, port: u16, prior: &PortScanResult) -> bool;
+
+    /// 执行探测（超过探测时限时被中止，产出丢弃）
+    fn run<'a>(&'a self, ctx: &'a ProbeContext<'a>) -> BoxFuture<'a, ProbeOutput>;
+}
+
+/// 按顺序执行的一组探测
+#[derive(Clone, Default)]
+pub struct ProbeRegistry {
+    probes: Vec<Arc<dyn Probe>>,
+}
+
+impl fmt::Debug for ProbeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl ProbeRegistry {
+    /// 空的探测集合
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 全部内置探测（按依赖顺序：后面的探测可以使用前面的结果）
+    pub fn builtin() -> &'static ProbeRegistry {
+        static BUILTIN: OnceLock<ProbeRegistry> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            let mut registry = ProbeRegistry::new();
+            registry
+                .register(BannerProbe)
+                .register(TlsProbe)
+                .register(WebInfoProbe)
+                .register(FaviconProbe);
+            registry
+        })
+    }
+
+    /// 默认启用的探测（见 [`DEFAULT_PROBES`]）
+    pub fn standard() -> &'static ProbeRegistry {
+        static STANDARD: OnceLock<ProbeRegistry> = OnceLock::new();
+        STANDARD.get_or_init(|| {
+            let names: Vec<String> = DEFAULT_PROBES.iter().map(|s| s.to_string()).collect();
+            Self::builtin().select(&names).expect("默认探测均已注册")
+        })
+    }
+
+    /// 注册一个探测（排在已注册的探测之后）
+    pub fn register(&mut self, probe: impl Probe + 'static) -> &mut Self {
+        self.probes.push(Arc::new(probe));
+        self
+    }
+
+    /// 按名称选出部分探测，保持注册顺序
+    ///
+    /// # 参数
+    /// * `names` - 探测名称（为空时为 [`DEFAULT_PROBES`]，`none` 表示不执行任何探测）
+    ///
+    /// # 返回
+    /// * `Ok(ProbeRegistry)` - 选出的探测
+    /// * `Err` - 存在未注册的名称
+    pub fn select(&self, names: &[String]) -> Result<ProbeRegistry, Box<dyn Error + Send + Sync>> {
+        let names: Vec<&str> = if names.is_empty() {
+            DEFAULT_PROBES.to_vec()
+        } else {
+            names.iter().map(|n| n.trim()).collect()
+        };
+        if let Some(unknown) = names
+            .iter()
+            .find(|n| **n != "none" && !self.names().contains(n))
+        {
+            return Err(format!(
+                "未知的探测: {}（可用: {}）",
+                unknown,
+                self.names().join(", ")
+            )
+            .into());
+        }
+        Ok(ProbeRegistry {
+            probes: self
+                .probes
+                .iter()
+                .filter(|p| names.contains(&p.name()))
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// 探测名称
+    pub fn names(&self) -> Vec<&'static str> {
+        self.probes.iter().map(|p| p.name()).collect()
+    }
+
+    /// 各探测声明的列（按注册顺序）
+    pub fn columns(&self) -> impl Iterator<Item = &'static ProbeColumn> + '_ {
+        self.probes.iter().flat_map(|p| p.columns())
+    }
+
+    /// 对开放端口依次执行适用的探测，产出合并到结果中
+    ///
+    /// 每个探测的时限为连接超时的两倍加1秒，超时的探测不影响端口的结论。
+    ///
+    /// # 参数
+    /// * `ip` - IP地址
+    /// * `port` - 端口号
+    /// * `io_timeout` - 连接、发送及读取超时
+    /// * `connector` - 建立新连接的方式
+    /// * `result` - 端口的扫描结果
+    pub async fn run(
+        &self,
+        ip: &str,
+        port: u16,
+        io_timeout: Duration,
+        connector: &dyn ProbeConnect,
+        result: &mut PortScanResult,
+    ) {
+        let limit = io_timeout * 2 + Duration::from_secs(1);
+        for probe in &self.probes {
+            if !probe.applicable(port, result) {
+                continue;
+            }
+            let ctx = ProbeContext {
+                ip,
+                port,
+                io_timeout,
+                prior: result,
+                connector,
+            };
+            let Ok(output) = timeout(limit, probe.run(&ctx)).await else {
+                continue;
+            };
+            if let Some(banner) = output.banner {
+                result.banner = banner;
+            }
+            result.evidence.extend(output.evidence);
+            for (key, value) in output.values {
+                debug_assert!(
+                    probe.columns().iter().any(|c| c.key == key),
+                    "探测 {} 未声明列 {}",
+                    probe.name(),
+                    key
+                );
+                result.probes.insert(key.to_string(), value);
+            }
+        }
+    }
+}
+
+/// 结果中出现过的探测列（按 [`ProbeRegistry::builtin`] 的声明顺序）
+pub fn export_columns(results: &[PortScanResult]) -> Vec<&'static ProbeColumn> {
+    ProbeRegistry::builtin()
+        .columns()
+        .filter(|c| results.iter().any(|r| r.probes.contains_key(c.key)))
+        .collect()
+}
+
+/// 探测值在Excel中的写法（没有值时为空）
+pub fn cell(values: &BTreeMap<String, ProbeValue>, key: &str) -> String {
+    values
+        .get(key)
+        .map(ProbeValue::to_string)
+        .unwrap_or_default()
+}
+
+/// RDP X.224 连接请求（携带RDP协商请求）
+const RDP_NEG_REQUEST: &[u8] = &[
+    0x03, 0x00, 0x00, 0x13, 0x0e, 0xe0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x08, 0x00, 0x03,
+    0x00, 0x00, 0x00,
+];
+
+/// 识别连接后服务端主动发送的数据（banner）
+///
+/// # 返回
+/// * `(服务信息, 识别证据)` - 没有数据时均为空
+pub fn classify_banner(buf: &[u8]) -> (String, Vec<String>) {
+    if buf.is_empty() {
+        (String::new(), Vec::new())
+    } else if buf.starts_with(b"SSH-") {
+        let banner = String::from_utf8_lossy(buf)
+            .lines()
+            .next()
+            .unwrap_or("SSH")
+            .to_string();
+        (banner, vec!["ssh-banner".to_string()])
+    } else if is_mysql_handshake(buf) {
+        (
+            extract_mysql_banner(buf),
+            vec![format!("mysql-handshake (len={})", buf.len())],
+        )
+    } else if is_rdp_response(buf) {
+        (extract_rdp_banner(buf), vec!["rdp-response".to_string()])
+    } else {
+        (extract_banner_text(buf), vec!["initial-raw".to_string()])
+    }
+}
+
+/// 判断是否为MySQL握手包（协议版本10）或错误包
+fn is_mysql_handshake(buf: &[u8]) -> bool {
+    if buf.len() < 5 {
+        return false;
+    }
+    let payload_len = u32::from_le_bytes([buf[0], buf[1], buf[2], 0]) as usize;
+    payload_len > 0 && buf[3] == 0 && (buf[4] == 0x0a || buf[4] == 0xff)
+}
+
+/// 从MySQL握手包中提取版本信息
+fn extract_mysql_banner(buf: &[u8]) -> String {
+    if buf.len() > 5 && buf[4] == 0x0a {
+        let version: Vec<u8> = buf[5..].iter().take_while(|&&b| b != 0).copied().collect();
+        format!("MySQL {}", String::from_utf8_lossy(&version))
+    } else {
+        "MySQL".to_string()
+    }
+}
+
+/// 判断是否为RDP（TPKT + X.224 连接确认）响应
+fn is_rdp_response(buf: &[u8]) -> bool {
+    buf.len() >= 11 && buf[0] == 0x03 && buf[1] == 0x00 && buf[5] == 0xd0
+}
+
+/// 从RDP响应中提取服务信息
+fn extract_rdp_banner(buf: &[u8]) -> String {
+    // 协商响应类型 0x02 表示服务端接受了安全协议协商
+    if buf.len() >= 19 && buf[11] == 0x02 {
+        "RDP (NLA/TLS)".to_string()
+    } else {
+        "RDP".to_string()
+    }
+}
+
+/// 从HTTP响应中提取状态行和Server头
+fn extract_http_banner(buf: &[u8]) -> String {
+    let text = String::from_utf8_lossy(buf);
+    let status = text.lines().next().unwrap_or("HTTP").trim().to_string();
+    match text
+        .lines()
+        .find(|l| l.to_ascii_lowercase().starts_with("server:"))
+    {
+        Some(server) => format!("{} | {}", status, server[7..].trim()),
+        None => status,
+    }
+}
+
+/// 从原始数据中提取可打印的banner文本（首行，最多128个字符）
+fn extract_banner_text(buf: &[u8]) -> String {
+    String::from_utf8_lossy(buf)
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or("")
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(128)
+        .collect()
+}
+
+/// 服务识别：对未主动发送banner的端口发送探测报文
+///
+/// RDP端口发送协商请求，其余端口为HTTP请求或 `--tcp-banner-probe` 指定的报文，根据响应内容识别服务。
+struct BannerProbe;
+
+impl Probe for BannerProbe {
+    fn name(&self) -> &'static str {
+        "banner"
+    }
+
+    fn applicable(&self, _port: u16, prior: &PortScanResult) -> bool {
+        prior.evidence.is_empty()
+    }
+
+    fn run<'a>(&'a self, ctx: &'a ProbeContext<'a>) -> BoxFuture<'a, ProbeOutput> {
+        Box::pin(async move {
+            let mut output = ProbeOutput::default();
+            let Ok(mut stream) = ctx.connect().await else {
+                return output;
+            };
+            // --tcp-banner-probe 替换默认的HTTP请求，HTTP请求带上探测标识中的User-Agent及附加请求头
+            let identity = identity();
+            let (payload, probe_name): (Vec<u8>, &str) = if ctx.port == 3389 {
+                (RDP_NEG_REQUEST.to_vec(), "rdp-probe")
+            } else if let Some(ref probe) = identity.tcp_probe {
+                (probe.clone(), "custom-probe")
+            } else {
+                (
+                    format!(
+                        "GET / HTTP/1.0\r\nHost: {}\r\n{}\r\n",
+                        ctx.ip,
+                        identity.raw_header_lines()
+                    )
+                    .into_bytes(),
+                    "http-probe",
+                )
+            };
+
+            let mut buf = Vec::new();
+            if let Ok(Ok(())) = timeout(ctx.io_timeout, stream.write_all(&payload)).await {
+                let mut chunk = [0u8; 2048];
+                if let Ok(Ok(n)) = timeout(ctx.io_timeout, stream.read(&mut chunk)).await {
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+            }
+
+            if buf.starts_with(b"HTTP/") {
+                output.banner = Some(extract_http_banner(&buf));
+                output.evidence.push(probe_name.to_string());
+            } else if is_rdp_response(&buf) {
+                output.banner = Some(extract_rdp_banner(&buf));
+                output.evidence.push("rdp-response".to_string());
+            } else if !buf.is_empty() {
+                output.banner = Some(extract_banner_text(&buf));
+                output.evidence.push(format!("{}-raw", probe_name));
+            }
+            output
+        })
+    }
+}
+
+/// 常见的TLS端口（其余端口在没有识别出明文协议时才尝试握手）
+const TLS_PORTS: &[u16] = &[443, 465, 636, 993, 995, 3269, 5986, 8443, 9443];
+
+/// TLS握手：记录协议版本、加密套件及证书指纹
+struct TlsProbe;
+
+impl Probe for TlsProbe {
+    fn name(&self) -> &'static str {
+        "tls"
+    }
+
+    fn columns(&self) -> &'static [ProbeColumn] {
+        &[
+            ProbeColumn {
+                key: "tls_version",
+                header: "TLS版本",
+            },
+            ProbeColumn {
+                key: "tls_cipher",
+                header: "加密套件",
+            },
+            ProbeColumn {
+                key: "tls_cert_sha256",
+                header: "证书SHA-256",
+            },
+        ]
+    }
+
+    fn applicable(&self, port: u16, prior: &PortScanResult) -> bool {
+        // 服务端不主动发送数据、对明文请求回复二进制数据的端口可能是TLS
+        TLS_PORTS.contains(&port) || prior.evidence.iter().all(|e| e.ends_with("-probe-raw"))
+    }
+
+    fn run<'a>(&'a self, ctx: &'a ProbeContext<'a>) -> BoxFuture<'a, ProbeOutput> {
+        Box::pin(async move {
+            let mut output = ProbeOutput::default();
+            let Ok(stream) = ctx.connect().await else {
+                return output;
+            };
+            let Ok(Ok(tls)) = timeout(ctx.io_timeout, connect_insecure(stream, ctx.ip)).await
+            else {
+                return output;
+            };
+            let session = tls.get_ref().1;
+            if let Some(version) = session.protocol_version() {
+                output.set("tls_version", format!("{:?}", version).replace('_', "."));
+            }
+            if let Some(suite) = session.negotiated_cipher_suite() {
+                output.set("tls_cipher", format!("{:?}", suite.suite()));
+            }
+            if let Some(cert) = session.peer_certificates().and_then(|c| c.first()) {
+                output.set("tls_cert_sha256", hex::encode(Sha256::digest(cert)));
+            }
+            output.evidence.push("tls-handshake".to_string());
+            if ctx.prior.banner.is_empty() {
+                output.banner = Some("TLS".to_string());
+            }
+            output
+        })
+    }
+}
+
+/// 网页信息：状态码、Server及标题（HTTP响应的端口，或TLS握手成功的端口走HTTPS）
+struct WebInfoProbe;
+
+impl Probe for WebInfoProbe {
+    fn name(&self) -> &'static str {
+        "webinfo"
+    }
+
+    fn columns(&self) -> &'static [ProbeColumn] {
+        &[
+            ProbeColumn {
+                key: "web_status",
+                header: "HTTP状态码",
+            },
+            ProbeColumn {
+                key: "web_server",
+                header: "Server",
+            },
+            ProbeColumn {
+                key: "web_title",
+                header: "页面标题",
+            },
+        ]
+    }
+
+    fn applicable(&self, _port: u16, prior: &PortScanResult) -> bool {
+        prior.banner.starts_with("HTTP/") || prior.probes.contains_key("tls_version")
+    }
+
+    fn run<'a>(&'a self, ctx: &'a ProbeContext<'a>) -> BoxFuture<'a, ProbeOutput> {
+        Box::pin(async move {
+            let mut output = ProbeOutput::default();
+            let Ok(reply) = http_get(ctx, "/", WEB_BODY_MAX_BYTES).await else {
+                return output;
+            };
+            output.set("web_status", i64::from(reply.status));
+            if let Some(server) = reply.header("server") {
+                output.set("web_server", server);
+            }
+            let title = extract_title(&String::from_utf8_lossy(&reply.body));
+            if !title.is_empty() {
+                output.set("web_title", title);
+            }
+            output.evidence.push("webinfo".to_string());
+            output
+        })
+    }
+}
+
+/// favicon哈希（与常见测绘平台的 `icon_hash` 一致，需先执行 webinfo）
+struct FaviconProbe;
+
+impl Probe for FaviconProbe {
+    fn name(&self) -> &'static str {
+        "favicon"
+    }
+
+    fn columns(&self) -> &'static [ProbeColumn] {
+        &[ProbeColumn {
+            key: "favicon_hash",
+            header: "favicon哈希",
+        }]
+    }
+
+    fn applicable(&self, _port: u16, prior: &PortScanResult) -> bool {
+        prior.probes.contains_key("web_status")
+    }
+
+    fn run<'a>(&'a self, ctx: &'a ProbeContext<'a>) -> BoxFuture<'a, ProbeOutput> {
+        Box::pin(async move {
+            let mut output = ProbeOutput::default();
+            if let Ok(reply) = http_get(ctx, "/favicon.ico", FAVICON_MAX_BYTES).await
+                && reply.status == 200
+                && !reply.body.is_empty()
+            {
+                output.set("favicon_hash", i64::from(favicon_hash(&reply.body)));
+            }
+            output
+        })
+    }
+}
+
+/// 网页信息读取的最大字节数
+const WEB_BODY_MAX_BYTES: usize = 64 * 1024;
+
+/// favicon读取的最大字节数
+const FAVICON_MAX_BYTES: usize = 256 * 1024;
+
+/// 简单的HTTP响应
+struct HttpReply {
+    status: u16,
+    head: String,
+    body: Vec<u8>,
+}
+
+impl HttpReply {
+    /// 响应头的值（名称不区分大小写）
+    fn header(&self, name: &str) -> Option<String> {
+        self.head.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().to_string())
+        })
+    }
+}
+
+/// 发送HTTP/1.0 GET请求（TLS握手成功过的端口走HTTPS）并读取响应
+async fn http_get(ctx: &ProbeContext<'_>, path: &str, max_bytes: usize) -> io::Result<HttpReply> {
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\n{}\r\n",
+        path,
+        ctx.ip,
+        identity().raw_header_lines()
+    );
+    let stream = ctx.connect().await?;
+    let raw = if ctx.prior.probes.contains_key("tls_version") {
+        let tls = timeout(ctx.io_timeout, connect_insecure(stream, ctx.ip))
+            .await
+            .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??;
+        exchange(tls, request.as_bytes(), max_bytes, ctx.io_timeout).await?
+    } else {
+        exchange(stream, request.as_bytes(), max_bytes, ctx.io_timeout).await?
+    };
+    let split = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "不是HTTP响应"))?;
+    let head = String::from_utf8_lossy(&raw[..split]).to_string();
+    let status = head
+        .strip_prefix("HTTP/")
+        .and_then(|s| s.split_whitespace().nth(1))
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "不是HTTP响应"))?;
+    Ok(HttpReply {
+        status,
+        head,
+        body: raw[split + 4..].to_vec(),
+    })
+}
+
+/// 发送请求并读取响应，直到连接关闭或读满
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    request: &[u8],
+    max_bytes: usize,
+    io_timeout: Duration,
+) -> io::Result<Vec<u8>> {
+    timeout(io_timeout, stream.write_all(request))
+        .await
+        .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??;
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    while buf.len() < max_bytes {
+        match timeout(io_timeout, stream.read(&mut chunk)).await {
+            Ok(Ok(n)) if n > 0 => {
+                buf.extend_from_slice(&chunk[..n.min(max_bytes - buf.len())]);
+            }
+            _ => break,
+        }
+    }
+    Ok(buf)
+}
+
+/// 没有识别出服务时按默认端口表标注
+///
+/// # 参数
+/// * `result` - 执行完探测的开放端口
+pub fn fallback_banner(result: &mut PortScanResult) {
+    if result.evidence.is_empty() {
+        if let Some(name) = service_name(result.port) {
+            result.banner = name.to_string();
+        }
+        result.evidence.push(CONNECT_EVIDENCE.to_string());
+    }
+    if result.banner.trim().is_empty() {
+        result.banner = "服务未知".to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_keeps_registration_order() {
+        let builtin = ProbeRegistry::builtin();
+        let names = |list: &[&str]| {
+            let list: Vec<String> = list.iter().map(|s| s.to_string()).collect();
+            builtin.select(&list).map(|r| r.names())
+        };
+        assert_eq!(
+            names(&["favicon", "banner", "webinfo"]).unwrap(),
+            ["banner", "webinfo", "favicon"]
+        );
+        assert_eq!(names(&[]).unwrap(), DEFAULT_PROBES);
+        assert!(names(&["none"]).unwrap().is_empty());
+        let err = names(&["banner", "snmp"]).unwrap_err().to_string();
+        assert!(err.contains("snmp") && err.contains("tls"), "{}", err);
+
+        // 声明的列不重复
+        let keys: Vec<&str> = builtin.columns().map(|c| c.key).collect();
+        let mut unique = keys.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(keys.len(), unique.len());
+    }
+
+    #[tokio::test]
+    async fn test_webinfo_reads_title_after_banner() {
+        let connect = || async {
+            let (client, mut server) = tokio::io::duplex(4096);
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1024];
+                let _ = server.read(&mut buf).await;
+                let _ = server
+                    .write_all(b"HTTP/1.0 200 OK\r\nServer: nginx\r\n\r\n<title> Jenkins\n</title>")
+                    .await;
+            });
+            Ok(Box::new(client) as ProbeStream)
+        };
+        let names = ["banner", "webinfo"].map(String::from);
+        let registry = ProbeRegistry::builtin().select(&names).unwrap();
+        let mut result =
+            PortScanResult::open("10.0.0.1".to_string(), 8080, String::new(), Vec::new());
+        registry
+            .run(
+                "10.0.0.1",
+                8080,
+                Duration::from_secs(1),
+                &connect,
+                &mut result,
+            )
+            .await;
+        fallback_banner(&mut result);
+
+        assert_eq!(result.banner, "HTTP/1.0 200 OK | nginx");
+        assert_eq!(result.evidence, ["http-probe", "webinfo"]);
+        assert_eq!(result.probes["web_status"], ProbeValue::Integer(200));
+        assert_eq!(cell(&result.probes, "web_title"), "Jenkins");
+        assert_eq!(cell(&result.probes, "favicon_hash"), "");
+    }
+}
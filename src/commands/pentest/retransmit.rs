@@ -0,0 +1,186 @@
+// src/commands/pentest/retransmit.rs
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 按主机往返时间推算首探超时时使用的倍数
+pub const RTT_MULTIPLIER: u32 = 4;
+
+/// 推算出的首探超时的下限
+pub const MIN_FIRST_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// 两段式探测参数
+///
+/// 单一超时只能二选一：超时短会把响应慢的开放端口误判为被过滤，超时长又会在大段被过滤的端口上空等。
+/// 指定任一参数后，首探使用较短的超时，只有首探超时的端口在首轮全部结束后按主机成批以较长的超时重探。
+#[derive(Args, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetransmitArgs {
+    /// 两段式探测的首探超时（毫秒），默认按该主机已测得的连接往返时间推算（尚未测得时同 -T）
+    #[arg(
+        long,
+        env = "GXTOOLS_TIMEOUT_FIRST",
+        value_name = "MS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub timeout_first: Option<u64>,
+
+    /// 两段式探测的重探超时（秒），只用于首探超时的端口，默认同 -T
+    #[arg(
+        long,
+        env = "GXTOOLS_TIMEOUT_RETRY",
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub timeout_retry: Option<u64>,
+}
+
+impl RetransmitArgs {
+    /// 按参数生成两段式探测计划，两个参数都未指定时返回 `None`（只探测一轮）
+    ///
+    /// # 参数
+    /// * `probe_timeout` - 模块的连接及读取超时（-T）
+    pub fn plan(&self, probe_timeout: Duration) -> Option<RetransmitPlan> {
+        if self.timeout_first.is_none() && self.timeout_retry.is_none() {
+            return None;
+        }
+        Some(RetransmitPlan {
+            first: self.timeout_first.map(Duration::from_millis),
+            retry: self
+                .timeout_retry
+                .map_or(probe_timeout, Duration::from_secs),
+            fallback: probe_timeout,
+        })
+    }
+}
+
+/// 两段式探测计划
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetransmitPlan {
+    /// 固定的首探超时（为空时按主机往返时间推算）
+    pub first: Option<Duration>,
+    /// 重探超时
+    pub retry: Duration,
+    /// 尚未测得主机往返时间时的首探超时，同时是推算值的上限
+    pub fallback: Duration,
+}
+
+impl RetransmitPlan {
+    /// 某个主机的首探超时
+    ///
+    /// # 参数
+    /// * `rtt` - 该主机已测得的连接往返时间
+    ///
+    /// # 返回
+    /// * 指定了 `--timeout-first` 时为该值，否则为往返时间的 [`RTT_MULTIPLIER`] 倍，
+    ///   不低于 [`MIN_FIRST_TIMEOUT`]、不高于 -T；尚未测得时为 -T
+    pub fn first_timeout(&self, rtt: Option<Duration>) -> Duration {
+        if let Some(first) = self.first {
+            return first;
+        }
+        match rtt {
+            Some(rtt) => (rtt * RTT_MULTIPLIER)
+                .max(MIN_FIRST_TIMEOUT)
+                .min(self.fallback),
+            None => self.fallback,
+        }
+    }
+}
+
+/// 得出端口结论的探测阶段（两段式探测时记录在结果中）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProbeStage {
+    /// 首探
+    #[serde(rename = "首探")]
+    First,
+    /// 首探超时后的重探
+    #[serde(rename = "重探")]
+    Retry,
+}
+
+impl fmt::Display for ProbeStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ProbeStage::First => "首探",
+            ProbeStage::Retry => "重探",
+        })
+    }
+}
+
+/// 扫描中测得的各主机连接往返时间（取最小值，用于推算首探超时）
+#[derive(Debug, Default)]
+pub struct HostRtt {
+    rtts: Mutex<HashMap<String, Duration>>,
+}
+
+impl HostRtt {
+    /// 记录一次测得的往返时间
+    ///
+    /// # 参数
+    /// * `ip` - 主机
+    /// * `rtt` - 完成一次连接（成功或被拒绝）的耗时
+    pub fn observe(&self, ip: &str, rtt: Duration) {
+        let mut rtts = self.rtts.lock().unwrap();
+        rtts.entry(ip.to_string())
+            .and_modify(|min| *min = (*min).min(rtt))
+            .or_insert(rtt);
+    }
+
+    /// 主机已测得的最小往返时间
+    pub fn get(&self, ip: &str) -> Option<Duration> {
+        self.rtts.lock().unwrap().get(ip).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_timeout_follows_measured_rtt() {
+        let args = RetransmitArgs::default();
+        assert_eq!(args.plan(Duration::from_secs(3)), None);
+
+        let plan = RetransmitArgs {
+            timeout_retry: Some(10),
+            ..Default::default()
+        }
+        .plan(Duration::from_secs(3))
+        .unwrap();
+        assert_eq!(plan.retry, Duration::from_secs(10));
+
+        let rtt = HostRtt::default();
+        assert_eq!(
+            plan.first_timeout(rtt.get("10.0.0.1")),
+            Duration::from_secs(3)
+        );
+        rtt.observe("10.0.0.1", Duration::from_millis(120));
+        rtt.observe("10.0.0.1", Duration::from_millis(80));
+        assert_eq!(
+            plan.first_timeout(rtt.get("10.0.0.1")),
+            Duration::from_millis(320)
+        );
+        rtt.observe("10.0.0.2", Duration::from_millis(5));
+        assert_eq!(plan.first_timeout(rtt.get("10.0.0.2")), MIN_FIRST_TIMEOUT);
+        rtt.observe("10.0.0.3", Duration::from_secs(2));
+        assert_eq!(
+            plan.first_timeout(rtt.get("10.0.0.3")),
+            Duration::from_secs(3)
+        );
+
+        let fixed = RetransmitArgs {
+            timeout_first: Some(150),
+            ..Default::default()
+        }
+        .plan(Duration::from_secs(3))
+        .unwrap();
+        assert_eq!(
+            fixed.first_timeout(rtt.get("10.0.0.1")),
+            Duration::from_millis(150)
+        );
+        assert_eq!(fixed.retry, Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,484 @@
+// src/commands/pentest/tui.rs
+use crate::commands::pentest::portscan::{PortScanResult, export_results};
+use crate::utils::blocking::BlockingStage;
+use crate::utils::metrics::ScanMetrics;
+use crate::utils::output::OutputKind;
+use crate::utils::pause::PauseGate;
+use crate::utils::{ExcelOptions, ScanProgress, format_duration};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, Cell, Gauge, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{self, IsTerminal};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio_util::sync::CancellationToken;
+
+/// 界面刷新间隔
+const TICK: Duration = Duration::from_millis(100);
+
+/// 检查当前是否运行在交互式终端中
+///
+/// # 返回
+/// * `Ok(())` - 标准输入和输出均为TTY
+/// * `Err` - 非交互环境（如管道、重定向、CI）
+pub fn ensure_tty() -> Result<(), Box<dyn Error + Send + Sync>> {
+    if !(io::stdin().is_terminal() && io::stdout().is_terminal()) {
+        return Err("--tui 需要在交互式终端中运行（当前标准输入或输出不是终端）".into());
+    }
+    Ok(())
+}
+
+/// 界面对扫描的控制句柄
+pub struct TuiControl {
+    /// 分发暂停开关
+    pub pause: PauseGate,
+    /// 扫描的取消令牌（取消后不再分发新的探测，进行中的探测立即放弃）
+    pub cancel: CancellationToken,
+    /// 扫描进度条（界面关闭后恢复显示）
+    pub progress: ScanProgress,
+    /// 导出部分结果时使用的选项
+    pub export: ExcelOptions,
+}
+
+/// 界面退出方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TuiExit {
+    /// 扫描完成后退出
+    Finished,
+    /// 关闭界面，扫描在后台继续
+    Detached,
+    /// 用户确认中止扫描
+    Aborted,
+}
+
+/// 状态筛选
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StateFilter {
+    Open,
+    All,
+    Closed,
+    Timeout,
+}
+
+impl StateFilter {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Open => "开放",
+            Self::All => "全部",
+            Self::Closed => "关闭",
+            Self::Timeout => "超时",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::Open => Self::All,
+            Self::All => Self::Closed,
+            Self::Closed => Self::Timeout,
+            Self::Timeout => Self::Open,
+        }
+    }
+
+    fn matches(self, result: &PortScanResult) -> bool {
+        self == Self::All || result.status == self.label()
+    }
+}
+
+/// 界面状态
+struct App {
+    rx: UnboundedReceiver<PortScanResult>,
+    results: Vec<PortScanResult>,
+    finished: bool,
+    started: Instant,
+    state_filter: StateFilter,
+    text_filter: String,
+    editing_filter: bool,
+    confirm_quit: bool,
+    table: TableState,
+    status: String,
+    control: TuiControl,
+}
+
+/// 运行交互界面，直到用户退出
+///
+/// 界面只消费结果通道中的副本，不参与扫描本身；通道关闭即视为扫描完成。
+/// 应在阻塞线程中调用（如 `tokio::task::spawn_blocking`）。
+///
+/// # 参数
+/// * `rx` - 扫描结果通道
+/// * `control` - 暂停/中止控制句柄
+///
+/// # 返回
+/// * `Ok(TuiExit)` - 退出方式
+/// * `Err` - 终端读写失败
+pub fn run(rx: UnboundedReceiver<PortScanResult>, control: TuiControl) -> io::Result<TuiExit> {
+    let mut app = App::new(rx, control);
+    let mut terminal = ratatui::init();
+    let exit = app.event_loop(&mut terminal);
+    ratatui::restore();
+
+    if matches!(exit, Ok(TuiExit::Detached)) {
+        app.control.progress.set_hidden(false);
+        println!("ℹ️  交互界面已关闭，扫描在后台继续");
+    }
+    exit
+}
+
+impl App {
+    fn new(rx: UnboundedReceiver<PortScanResult>, control: TuiControl) -> Self {
+        Self {
+            rx,
+            results: Vec::new(),
+            finished: false,
+            started: Instant::now(),
+            state_filter: StateFilter::Open,
+            text_filter: String::new(),
+            editing_filter: false,
+            confirm_quit: false,
+            table: TableState::default(),
+            status: String::new(),
+            control,
+        }
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> io::Result<TuiExit> {
+        loop {
+            self.drain();
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if !event::poll(TICK)? {
+                continue;
+            }
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                if key.code == KeyCode::Char('e') && !self.editing_filter && !self.confirm_quit {
+                    self.export();
+                    // 导出时的控制台输出会弄乱画面，强制整屏重绘
+                    terminal.clear()?;
+                    continue;
+                }
+                if let Some(exit) = self.on_key(key.code) {
+                    return Ok(exit);
+                }
+            }
+        }
+    }
+
+    /// 取出通道中的新结果
+    fn drain(&mut self) {
+        loop {
+            match self.rx.try_recv() {
+                Ok(result) => self.results.push(result),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// 处理按键，返回 `Some` 时退出界面
+    fn on_key(&mut self, code: KeyCode) -> Option<TuiExit> {
+        if self.editing_filter {
+            match code {
+                KeyCode::Char(c) => self.text_filter.push(c),
+                KeyCode::Backspace => {
+                    self.text_filter.pop();
+                }
+                KeyCode::Enter | KeyCode::Esc => self.editing_filter = false,
+                _ => {}
+            }
+            self.table.select(Some(0));
+            return None;
+        }
+
+        if self.confirm_quit {
+            match code {
+                KeyCode::Char('y') => {
+                    self.control.cancel.cancel();
+                    // 让等待中的探测尽快结束
+                    self.control.pause.release();
+                    return Some(TuiExit::Aborted);
+                }
+                KeyCode::Char('n') => return Some(TuiExit::Detached),
+                KeyCode::Esc => self.confirm_quit = false,
+                _ => {}
+            }
+            return None;
+        }
+
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                if self.finished {
+                    return Some(TuiExit::Finished);
+                }
+                self.confirm_quit = true;
+            }
+            KeyCode::Char('p') => {
+                self.status = if self.control.pause.toggle() {
+                    "已暂停".to_string()
+                } else {
+                    "已继续".to_string()
+                };
+            }
+            KeyCode::Char('s') => {
+                self.state_filter = self.state_filter.next();
+                self.table.select(Some(0));
+            }
+            KeyCode::Char('/') => self.editing_filter = true,
+            KeyCode::Down | KeyCode::Char('j') => self.table.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.table.select_previous(),
+            KeyCode::PageDown => self.table.scroll_down_by(20),
+            KeyCode::PageUp => self.table.scroll_up_by(20),
+            _ => {}
+        }
+        None
+    }
+
+    /// 导出当前已收到的全部结果
+    fn export(&mut self) {
+        self.status = match export_results(
+            &self.results,
+            OutputKind::PORTSCAN_PARTIAL,
+            &BTreeMap::new(),
+            &ScanMetrics::default(),
+            self.control.export.clone(),
+        )
+        .run()
+        {
+            Ok(path) => format!("已导出: {}", path),
+            Err(e) => format!("导出失败: {}", e),
+        };
+    }
+
+    /// 当前筛选条件下可见的结果
+    fn visible(&self) -> Vec<&PortScanResult> {
+        let needle = self.text_filter.to_lowercase();
+        self.results
+            .iter()
+            .filter(|r| self.state_filter.matches(r))
+            .filter(|r| {
+                needle.is_empty()
+                    || r.ip.contains(&needle)
+                    || r.port.to_string() == needle
+                    || r.banner.to_lowercase().contains(&needle)
+            })
+            .collect()
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [table_area, detail_area] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(body);
+
+        self.draw_header(frame, header);
+
+        // 表格状态单独取出，避免与筛选结果的借用冲突
+        let mut table_state = std::mem::take(&mut self.table);
+        let visible = self.visible();
+        if table_state.selected().is_none_or(|i| i >= visible.len()) {
+            table_state.select(if visible.is_empty() { None } else { Some(0) });
+        }
+
+        let rows = visible.iter().map(|r| {
+            Row::new(vec![
+                Cell::from(r.ip.clone()),
+                Cell::from(r.port.to_string()),
+                Cell::from(r.status.clone()),
+                Cell::from(r.banner.clone()),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(16),
+                Constraint::Length(6),
+                Constraint::Length(6),
+                Constraint::Min(10),
+            ],
+        )
+        .header(
+            Row::new(vec!["IP地址", "端口", "状态", "服务"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::bordered().title(format!(
+            " 结果 [{}] {} 条 ",
+            self.state_filter.label(),
+            visible.len()
+        )))
+        .row_highlight_style(Style::default().bg(Color::Blue));
+
+        let detail = match table_state.selected().and_then(|i| visible.get(i)) {
+            Some(r) => {
+                let mut lines = vec![
+                    Line::from(format!("目标: {}:{}", r.ip, r.port)),
+                    Line::from(format!("状态: {}", r.status)),
+                    Line::from(format!("服务: {}", r.banner)),
+                    Line::from("证据:"),
+                ];
+                lines.extend(r.evidence.iter().map(|e| Line::from(format!("  - {}", e))));
+                Text::from(lines)
+            }
+            None => Text::from("暂无结果"),
+        };
+        let detail = Paragraph::new(detail)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(" 详情 "));
+
+        frame.render_stateful_widget(table, table_area, &mut table_state);
+        frame.render_widget(detail, detail_area);
+        frame.render_widget(Paragraph::new(self.footer_text()), footer);
+        self.table = table_state;
+    }
+
+    fn draw_header(&self, frame: &mut Frame, area: Rect) {
+        let done = self.results.len() as u64;
+        // 总数取自进度条（存活探测与端口扫描同时进行时随存活主机的发现增加）
+        let total = self.control.progress.length();
+        let elapsed = self.started.elapsed();
+        // 速率与剩余时间不计入暂停时长
+        let active = elapsed.saturating_sub(self.control.pause.paused_duration());
+        let rate = done as f64 / active.as_secs_f64().max(0.001);
+        let eta = if rate > 0.0 && done < total {
+            format_duration(Duration::from_secs_f64((total - done) as f64 / rate))
+        } else {
+            "-".to_string()
+        };
+        let open = self.results.iter().filter(|r| r.is_open()).count();
+        let state = if self.finished {
+            "已完成"
+        } else if self.control.pause.is_paused() {
+            "已暂停"
+        } else {
+            "扫描中"
+        };
+
+        let gauge = Gauge::default()
+            .block(Block::bordered().title(format!(" 端口扫描 - {} ", state)))
+            .gauge_style(Style::default().fg(Color::Cyan))
+            .ratio((done as f64 / total.max(1) as f64).min(1.0))
+            .label(format!(
+                "{}/{} | {:.0}/s | 已用 {} | 剩余 {} | 开放 {}",
+                done,
+                total,
+                rate,
+                format_duration(elapsed),
+                eta,
+                open
+            ));
+        frame.render_widget(gauge, area);
+    }
+
+    fn footer_text(&self) -> String {
+        if self.confirm_quit {
+            return "扫描仍在进行: y 中止扫描 | n 关闭界面并在后台继续 | Esc 取消".to_string();
+        }
+        if self.editing_filter {
+            return format!("筛选(IP/端口/服务): {}_  (Enter 确认)", self.text_filter);
+        }
+        let mut text = format!(
+            "p 暂停/继续 | s 状态[{}] | / 筛选[{}] | e 导出 | ↑↓ 选择 | q 退出",
+            self.state_filter.label(),
+            self.text_filter
+        );
+        if !self.status.is_empty() {
+            text.push_str(" | ");
+            text.push_str(&self.status);
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::provenance::Provenance;
+    use tokio::sync::mpsc;
+
+    fn result(ip: &str, port: u16, status: &str, banner: &str) -> PortScanResult {
+        PortScanResult {
+            ip: ip.to_string(),
+            port,
+            status: status.to_string(),
+            banner: banner.to_string(),
+            evidence: Vec::new(),
+            suspected_honeypot: false,
+            aliases: Vec::new(),
+            tags: Default::default(),
+            geo: None,
+            egress: None,
+            verification: None,
+            failure: None,
+            knocked: false,
+            asset_changes: Vec::new(),
+            stage: None,
+            probes: BTreeMap::new(),
+            observed: Provenance::default(),
+        }
+    }
+
+    #[test]
+    fn test_visible_applies_state_and_text_filters() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let control = TuiControl {
+            pause: PauseGate::new(),
+            cancel: CancellationToken::new(),
+            progress: ScanProgress::new(4),
+            export: ExcelOptions::default(),
+        };
+        let mut app = App::new(rx, control);
+        tx.send(result("10.0.0.1", 22, "开放", "SSH-2.0-OpenSSH"))
+            .unwrap();
+        tx.send(result("10.0.0.1", 23, "关闭", "")).unwrap();
+        tx.send(result("10.0.0.2", 80, "开放", "HTTP/1.1 200 OK | nginx"))
+            .unwrap();
+        drop(tx);
+        app.drain();
+
+        assert!(app.finished);
+        assert_eq!(app.visible().len(), 2);
+
+        app.text_filter = "nginx".to_string();
+        assert_eq!(app.visible()[0].port, 80);
+
+        app.text_filter.clear();
+        app.state_filter = StateFilter::All;
+        assert_eq!(app.visible().len(), 3);
+    }
+
+    #[test]
+    fn test_quit_requires_confirmation_while_running() {
+        let (_tx, rx) = mpsc::unbounded_channel();
+        let cancel = CancellationToken::new();
+        let control = TuiControl {
+            pause: PauseGate::new(),
+            cancel: cancel.clone(),
+            progress: ScanProgress::new(10),
+            export: ExcelOptions::default(),
+        };
+        let mut app = App::new(rx, control);
+
+        assert_eq!(app.on_key(KeyCode::Char('q')), None);
+        assert!(app.confirm_quit);
+        assert_eq!(app.on_key(KeyCode::Esc), None);
+        assert!(!app.confirm_quit);
+
+        app.on_key(KeyCode::Char('q'));
+        assert_eq!(app.on_key(KeyCode::Char('y')), Some(TuiExit::Aborted));
+        assert!(cancel.is_cancelled());
+    }
+}
@@ -0,0 +1,767 @@
+// src/commands/pentest/udp_probes.rs
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+/// 单个应答最多读取的字节数（超出部分丢弃，解析器只处理该长度以内的数据）
+pub const MAX_RESPONSE_BYTES: usize = 1500;
+
+/// 摘要文本的最大字符数
+const MAX_SUMMARY_CHARS: usize = 160;
+
+/// 报文中最多解析的记录数（DNS应答、NetBIOS名称、IKE载荷等）
+const MAX_RECORDS: usize = 32;
+
+/// 常用的SNMP团体名（依次尝试）
+pub const SNMP_COMMUNITIES: &[&str] = &["public", "private"];
+
+/// 探测报文中使用的事务ID（用于校验应答）
+const TRANSACTION_ID: u16 = 0x4758;
+
+/// IKE探测的发起方cookie
+const IKE_COOKIE: [u8; 8] = *b"gxtools!";
+
+/// UDP服务探测
+#[derive(Debug, Clone, Copy)]
+pub struct UdpProbe {
+    /// 探测名称
+    pub name: &'static str,
+    /// 适用端口
+    pub ports: &'static [u16],
+    /// 生成探测报文（可能有多个，依次发送直到收到应答）
+    pub payloads: fn() -> Vec<Vec<u8>>,
+    /// 解析应答，返回摘要；无法识别时返回 `None`
+    pub parse: fn(&[u8]) -> Option<String>,
+}
+
+/// 内置的UDP服务探测
+pub const UDP_PROBES: &[UdpProbe] = &[
+    UdpProbe {
+        name: "dns-version",
+        ports: &[53],
+        payloads: || vec![dns_version_query()],
+        parse: parse_dns_version,
+    },
+    UdpProbe {
+        name: "ntp-readvar",
+        ports: &[123],
+        payloads: || vec![ntp_readvar_request()],
+        parse: parse_ntp_readvar,
+    },
+    UdpProbe {
+        name: "netbios-stat",
+        ports: &[137],
+        payloads: || vec![netbios_status_query()],
+        parse: parse_netbios_status,
+    },
+    UdpProbe {
+        name: "snmp-sysdescr",
+        ports: &[161],
+        payloads: || {
+            SNMP_COMMUNITIES
+                .iter()
+                .map(|c| snmp_sysdescr_request(c))
+                .collect()
+        },
+        parse: parse_snmp_sysdescr,
+    },
+    UdpProbe {
+        name: "ike-main-mode",
+        ports: &[500],
+        payloads: || vec![ike_main_mode_request()],
+        parse: parse_ike_response,
+    },
+];
+
+/// 查询端口对应的UDP探测
+///
+/// # 参数
+/// * `port` - 端口号
+pub fn probe_for_port(port: u16) -> Option<&'static UdpProbe> {
+    UDP_PROBES.iter().find(|p| p.ports.contains(&port))
+}
+
+/// 向UDP端口发送对应的探测报文并解析应答
+///
+/// # 参数
+/// * `ip` - IP地址
+/// * `port` - 端口号
+/// * `io_timeout` - 单次发送及等待应答的超时
+///
+/// # 返回
+/// * `Some(String)` - 识别到的服务摘要
+/// * `None` - 端口没有对应的探测、无应答或应答无法识别
+pub async fn grab_udp_banner(ip: IpAddr, port: u16, io_timeout: Duration) -> Option<String> {
+    let probe = probe_for_port(port)?;
+    send_probe(probe, SocketAddr::new(ip, port), io_timeout).await
+}
+
+/// 发送一个探测的全部报文，直到收到可识别的应答
+///
+/// 每个报文等待一次应答，总耗时不超过 `报文数 × io_timeout`。
+///
+/// # 参数
+/// * `probe` - 探测
+/// * `addr` - 目标地址
+/// * `io_timeout` - 单次发送及等待应答的超时
+pub async fn send_probe(
+    probe: &UdpProbe,
+    addr: SocketAddr,
+    io_timeout: Duration,
+) -> Option<String> {
+    let bind: SocketAddr = match addr.ip() {
+        IpAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        IpAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(bind).await.ok()?;
+    socket.connect(addr).await.ok()?;
+
+    let mut buf = vec![0u8; MAX_RESPONSE_BYTES];
+    for payload in (probe.payloads)() {
+        if timeout(io_timeout, socket.send(&payload)).await.is_err() {
+            return None;
+        }
+        if let Ok(Ok(n)) = timeout(io_timeout, socket.recv(&mut buf)).await
+            && let Some(summary) = (probe.parse)(&buf[..n])
+        {
+            return Some(summary);
+        }
+    }
+    None
+}
+
+/// 从不可信数据中截取可打印文本
+fn printable(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .take(MAX_SUMMARY_CHARS)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn be16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+// ---------------------------------------------------------------------------
+// DNS: CHAOS TXT version.bind
+// ---------------------------------------------------------------------------
+
+/// DNS CHAOS类 TXT 查询 version.bind
+fn dns_version_query() -> Vec<u8> {
+    let mut packet = Vec::with_capacity(30);
+    packet.extend_from_slice(&TRANSACTION_ID.to_be_bytes());
+    // 标准查询，期望递归；1个问题
+    packet.extend_from_slice(&[0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    packet.extend_from_slice(b"\x07version\x04bind\x00");
+    // TXT, CH
+    packet.extend_from_slice(&[0x00, 0x10, 0x00, 0x03]);
+    packet
+}
+
+/// 跳过DNS报文中的名称（支持压缩指针），返回名称之后的偏移
+fn skip_dns_name(data: &[u8], mut offset: usize) -> Option<usize> {
+    for _ in 0..128 {
+        let len = *data.get(offset)?;
+        match len {
+            0 => return Some(offset + 1),
+            // 压缩指针占两个字节，之后名称结束
+            l if l & 0xc0 == 0xc0 => {
+                data.get(offset + 1)?;
+                return Some(offset + 2);
+            }
+            l if l & 0xc0 != 0 => return None,
+            l => offset = offset.checked_add(1 + l as usize)?,
+        }
+    }
+    None
+}
+
+fn dns_rcode_name(rcode: u8) -> String {
+    match rcode {
+        1 => "FORMERR".to_string(),
+        2 => "SERVFAIL".to_string(),
+        3 => "NXDOMAIN".to_string(),
+        4 => "NOTIMP".to_string(),
+        5 => "REFUSED".to_string(),
+        n => format!("rcode={}", n),
+    }
+}
+
+/// 解析 version.bind 应答
+fn parse_dns_version(data: &[u8]) -> Option<String> {
+    if be16(data, 0)? != TRANSACTION_ID || data.get(2)? & 0x80 == 0 {
+        return None;
+    }
+    let rcode = data.get(3)? & 0x0f;
+    let questions = be16(data, 4)? as usize;
+    let answers = be16(data, 6)? as usize;
+    if rcode != 0 {
+        return Some(format!(
+            "DNS（version.bind 查询被拒: {}）",
+            dns_rcode_name(rcode)
+        ));
+    }
+
+    let mut offset = 12;
+    for _ in 0..questions.min(MAX_RECORDS) {
+        offset = skip_dns_name(data, offset)?.checked_add(4)?;
+    }
+    for _ in 0..answers.min(MAX_RECORDS) {
+        offset = skip_dns_name(data, offset)?;
+        let rtype = be16(data, offset)?;
+        let rdlength = be16(data, offset + 8)? as usize;
+        let rdata_start = offset + 10;
+        let rdata = data.get(rdata_start..rdata_start.checked_add(rdlength)?)?;
+        offset = rdata_start + rdlength;
+        if rtype != 16 {
+            continue;
+        }
+        // TXT: 若干个长度前缀的字符串
+        let mut texts = Vec::new();
+        let mut pos = 0;
+        while let Some(&len) = rdata.get(pos) {
+            let text = rdata.get(pos + 1..pos + 1 + len as usize)?;
+            texts.push(printable(text));
+            pos += 1 + len as usize;
+        }
+        let version = texts.join(" ");
+        if !version.is_empty() {
+            return Some(format!("DNS version.bind: {}", version));
+        }
+    }
+    Some("DNS（version.bind 无应答记录）".to_string())
+}
+
+// ---------------------------------------------------------------------------
+// NTP: mode 6 readvar
+// ---------------------------------------------------------------------------
+
+/// NTP 控制报文（mode 6）READVAR 请求
+fn ntp_readvar_request() -> Vec<u8> {
+    let mut packet = vec![0x16, 0x02];
+    packet.extend_from_slice(&TRANSACTION_ID.to_be_bytes());
+    packet.extend_from_slice(&[0; 8]);
+    packet
+}
+
+/// 解析 READVAR 应答（只取 version 和 system 变量）
+fn parse_ntp_readvar(data: &[u8]) -> Option<String> {
+    let header = data.get(..12)?;
+    // mode 6 且为应答
+    if header[0] & 0x07 != 6 || header[1] & 0x80 == 0 || be16(header, 2)? != TRANSACTION_ID {
+        return None;
+    }
+    if header[1] & 0x40 != 0 {
+        return Some("NTP（拒绝 mode 6 查询）".to_string());
+    }
+    let count = be16(header, 10)? as usize;
+    let body = data.get(12..12 + count.min(data.len() - 12))?;
+    let text = String::from_utf8_lossy(body);
+
+    let mut fields = Vec::new();
+    for item in text.split(',').take(MAX_RECORDS) {
+        let Some((key, value)) = item.trim().split_once('=') else {
+            continue;
+        };
+        if matches!(key.trim(), "version" | "system") {
+            fields.push(printable(value.trim().trim_matches('"').as_bytes()));
+        }
+    }
+    if fields.is_empty() {
+        Some("NTP（允许 mode 6 查询）".to_string())
+    } else {
+        Some(format!("NTP: {}", fields.join("; ")))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// NetBIOS: 节点状态查询
+// ---------------------------------------------------------------------------
+
+/// NetBIOS 节点状态（NBSTAT）查询，名称为 "*"
+fn netbios_status_query() -> Vec<u8> {
+    let mut packet = Vec::with_capacity(50);
+    packet.extend_from_slice(&TRANSACTION_ID.to_be_bytes());
+    packet.extend_from_slice(&[0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    // 一级编码：每个字节拆成两个半字节，各加 'A'
+    packet.push(0x20);
+    let mut name = [0u8; 16];
+    name[0] = b'*';
+    for byte in name {
+        packet.push(b'A' + (byte >> 4));
+        packet.push(b'A' + (byte & 0x0f));
+    }
+    packet.push(0x00);
+    // NBSTAT, IN
+    packet.extend_from_slice(&[0x00, 0x21, 0x00, 0x01]);
+    packet
+}
+
+/// 解析节点状态应答：主机名、工作组/域及MAC地址
+fn parse_netbios_status(data: &[u8]) -> Option<String> {
+    if be16(data, 0)? != TRANSACTION_ID || data.get(2)? & 0x80 == 0 || be16(data, 6)? == 0 {
+        return None;
+    }
+    let offset = skip_dns_name(data, 12)?;
+    if be16(data, offset)? != 0x21 {
+        return None;
+    }
+    let rdlength = be16(data, offset + 8)? as usize;
+    let rdata_start = offset + 10;
+    let rdata = data.get(rdata_start..rdata_start.checked_add(rdlength)?)?;
+    let count = *rdata.first()? as usize;
+
+    let mut host = None;
+    let mut group = None;
+    for i in 0..count.min(MAX_RECORDS) {
+        let entry = rdata.get(1 + i * 18..1 + (i + 1) * 18)?;
+        let name = printable(&entry[..15]);
+        let suffix = entry[15];
+        let is_group = entry[16] & 0x80 != 0;
+        match (suffix, is_group) {
+            (0x00, false) if host.is_none() => host = Some(name),
+            (0x00, true) if group.is_none() => group = Some(name),
+            _ => {}
+        }
+    }
+
+    let mut summary = format!("NetBIOS: {}", host.as_deref().unwrap_or("?"));
+    if let Some(group) = group {
+        summary.push_str(&format!("（工作组/域 {}）", group));
+    }
+    // 名称表之后是6字节的MAC地址
+    if let Some(mac) = rdata.get(1 + count * 18..1 + count * 18 + 6)
+        && mac.iter().any(|&b| b != 0)
+    {
+        let mac: Vec<String> = mac.iter().map(|b| format!("{:02x}", b)).collect();
+        summary.push_str(&format!(" MAC {}", mac.join(":")));
+    }
+    Some(summary)
+}
+
+// ---------------------------------------------------------------------------
+// SNMP: v1 GetRequest sysDescr.0
+// ---------------------------------------------------------------------------
+
+/// sysDescr.0 (1.3.6.1.2.1.1.1.0) 的BER编码
+const SYS_DESCR_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00];
+
+/// 编码一个短TLV（内容不超过127字节）
+fn ber(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(content.len() + 2);
+    out.push(tag);
+    out.push(content.len() as u8);
+    out.extend_from_slice(content);
+    out
+}
+
+/// SNMPv1 GetRequest sysDescr.0
+fn snmp_sysdescr_request(community: &str) -> Vec<u8> {
+    let varbind = ber(0x30, &[ber(0x06, SYS_DESCR_OID), vec![0x05, 0x00]].concat());
+    let pdu = ber(
+        0xa0,
+        &[
+            ber(0x02, &TRANSACTION_ID.to_be_bytes()),
+            ber(0x02, &[0]),
+            ber(0x02, &[0]),
+            ber(0x30, &varbind),
+        ]
+        .concat(),
+    );
+    ber(
+        0x30,
+        &[ber(0x02, &[0]), ber(0x04, community.as_bytes()), pdu].concat(),
+    )
+}
+
+/// 读取一个TLV，返回 (标签, 内容, 之后的偏移)
+fn read_tlv(data: &[u8], offset: usize) -> Option<(u8, &[u8], usize)> {
+    let tag = *data.get(offset)?;
+    let first = *data.get(offset + 1)?;
+    let (len, start) = if first & 0x80 == 0 {
+        (first as usize, offset + 2)
+    } else {
+        // 长格式：只接受1~2字节长度
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 2 {
+            return None;
+        }
+        let bytes = data.get(offset + 2..offset + 2 + n)?;
+        let len = bytes.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        (len, offset + 2 + n)
+    };
+    let end = start.checked_add(len)?;
+    Some((tag, data.get(start..end)?, end))
+}
+
+/// 解析 GetResponse 中的 sysDescr
+fn parse_snmp_sysdescr(data: &[u8]) -> Option<String> {
+    let (tag, message, _) = read_tlv(data, 0)?;
+    if tag != 0x30 {
+        return None;
+    }
+    let (_, _, offset) = read_tlv(message, 0)?; // version
+    let (tag, community, offset) = read_tlv(message, offset)?;
+    if tag != 0x04 {
+        return None;
+    }
+    let (tag, pdu, _) = read_tlv(message, offset)?;
+    if tag != 0xa2 {
+        return None;
+    }
+    let (_, _, offset) = read_tlv(pdu, 0)?; // request-id
+    let (_, error_status, offset) = read_tlv(pdu, offset)?;
+    let (_, _, offset) = read_tlv(pdu, offset)?; // error-index
+    let (_, varbinds, _) = read_tlv(pdu, offset)?;
+    let community = printable(community);
+    if error_status.iter().any(|&b| b != 0) {
+        return Some(format!("SNMP（团体名 {} 可用，查询出错）", community));
+    }
+    let (_, varbind, _) = read_tlv(varbinds, 0)?;
+    let (_, oid, offset) = read_tlv(varbind, 0)?;
+    let (tag, value, _) = read_tlv(varbind, offset)?;
+    if oid != SYS_DESCR_OID || tag != 0x04 {
+        return Some(format!("SNMP（团体名 {} 可用）", community));
+    }
+    Some(format!(
+        "SNMP（团体名 {}）: {}",
+        community,
+        printable(value)
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// IKE: IKEv1 main mode
+// ---------------------------------------------------------------------------
+
+/// 常见的IKE厂商ID
+const IKE_VENDOR_IDS: &[(&str, &str)] = &[
+    (
+        "afcad71368a1f1c96b8696fc77570100",
+        "Dead Peer Detection v1.0",
+    ),
+    ("4a131c81070358455c5728f20e95452f", "RFC 3947 NAT-T"),
+    ("12f5f28c457168a9702d9fe274cc0100", "Cisco Unity"),
+    ("09002689dfd6b712", "XAUTH"),
+];
+
+/// IKEv1 main mode 的首个报文（3DES/SHA1/PSK/Group2 提议）
+fn ike_main_mode_request() -> Vec<u8> {
+    // 转换属性（TV格式）：加密=3DES, 哈希=SHA1, 认证=PSK, 组=2, 生存期类型=秒, 生存期=28800
+    let attributes: [[u8; 4]; 6] = [
+        [0x80, 0x01, 0x00, 0x05],
+        [0x80, 0x02, 0x00, 0x02],
+        [0x80, 0x03, 0x00, 0x01],
+        [0x80, 0x04, 0x00, 0x02],
+        [0x80, 0x0b, 0x00, 0x01],
+        [0x80, 0x0c, 0x70, 0x80],
+    ];
+    let attributes = attributes.concat();
+
+    // 变换载荷：编号1，KEY_IKE
+    let mut transform = vec![0x00, 0x00];
+    transform.extend_from_slice(&((8 + attributes.len()) as u16).to_be_bytes());
+    transform.extend_from_slice(&[0x01, 0x01, 0x00, 0x00]);
+    transform.extend_from_slice(&attributes);
+
+    // 提议载荷：编号1，ISAKMP，无SPI，1个变换
+    let mut proposal = vec![0x00, 0x00];
+    proposal.extend_from_slice(&((8 + transform.len()) as u16).to_be_bytes());
+    proposal.extend_from_slice(&[0x01, 0x01, 0x00, 0x01]);
+    proposal.extend_from_slice(&transform);
+
+    // SA载荷：DOI=IPSEC，情形=仅身份
+    let mut sa = vec![0x00, 0x00];
+    sa.extend_from_slice(&((12 + proposal.len()) as u16).to_be_bytes());
+    sa.extend_from_slice(&[0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01]);
+    sa.extend_from_slice(&proposal);
+
+    // ISAKMP头：下一载荷=SA，版本1.0，交换类型=main mode
+    let mut packet = IKE_COOKIE.to_vec();
+    packet.extend_from_slice(&[0; 8]);
+    packet.extend_from_slice(&[0x01, 0x10, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    packet.extend_from_slice(&((28 + sa.len()) as u32).to_be_bytes());
+    packet.extend_from_slice(&sa);
+    packet
+}
+
+/// 解析IKE应答：列出厂商ID和通知类型
+fn parse_ike_response(data: &[u8]) -> Option<String> {
+    let header = data.get(..28)?;
+    if header[..8] != IKE_COOKIE {
+        return None;
+    }
+    let mut next = header[16];
+    let mut offset = 28;
+    let mut vendors = Vec::new();
+    let mut notices = Vec::new();
+    for _ in 0..MAX_RECORDS {
+        if next == 0 {
+            break;
+        }
+        let payload_next = *data.get(offset)?;
+        let len = be16(data, offset + 2)? as usize;
+        if len < 4 {
+            return None;
+        }
+        let body = data.get(offset + 4..offset.checked_add(len)?)?;
+        match next {
+            // 厂商ID
+            13 => {
+                let hex = hex::encode(body);
+                let name = IKE_VENDOR_IDS
+                    .iter()
+                    .find(|(prefix, _)| hex.starts_with(prefix))
+                    .map(|(_, name)| name.to_string())
+                    .unwrap_or_else(|| hex.chars().take(32).collect());
+                vendors.push(name);
+            }
+            // 通知：DOI(4) 协议(1) SPI长度(1) 通知类型(2)
+            11 => {
+                if let Some(kind) = be16(body, 6) {
+                    notices.push(match kind {
+                        14 => "NO-PROPOSAL-CHOSEN".to_string(),
+                        n => format!("通知 {}", n),
+                    });
+                }
+            }
+            _ => {}
+        }
+        next = payload_next;
+        offset += len;
+    }
+
+    let mut summary = String::from("IKE");
+    if header[16] == 1 {
+        summary.push_str("（接受 main mode 提议）");
+    }
+    if !notices.is_empty() {
+        summary.push_str(&format!(" {}", notices.join(", ")));
+    }
+    if !vendors.is_empty() {
+        summary.push_str(&format!(" VID: {}", vendors.join(", ")));
+    }
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 确定性伪随机字节
+    fn noise(seed: u64, len: usize) -> Vec<u8> {
+        let mut x = seed | 1;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect()
+    }
+
+    /// 对合法应答的每个截断、每个单字节翻转及随机数据调用解析器，不应崩溃
+    fn assert_robust(parse: fn(&[u8]) -> Option<String>, valid: &[u8]) {
+        for end in 0..valid.len() {
+            let _ = parse(&valid[..end]);
+        }
+        for i in 0..valid.len() {
+            for flip in [0x01, 0x80, 0xff] {
+                let mut bytes = valid.to_vec();
+                bytes[i] ^= flip;
+                if let Some(summary) = parse(&bytes) {
+                    assert!(summary.chars().count() <= MAX_SUMMARY_CHARS + 64);
+                }
+            }
+        }
+        for seed in 0..200 {
+            let mut bytes = valid[..valid.len().min(12)].to_vec();
+            bytes.extend(noise(seed, (seed as usize * 7) % 300));
+            let _ = parse(&bytes);
+            let _ = parse(&noise(seed, seed as usize % 64));
+        }
+    }
+
+    fn dns_version_response() -> Vec<u8> {
+        let mut packet = dns_version_query();
+        packet[2] = 0x85; // QR | AA
+        packet[7] = 1; // 1个应答
+        packet.extend_from_slice(&[0xc0, 0x0c, 0x00, 0x10, 0x00, 0x03, 0, 0, 0, 0]);
+        let txt = b"9.18.1-Ubuntu";
+        packet.extend_from_slice(&((txt.len() + 1) as u16).to_be_bytes());
+        packet.push(txt.len() as u8);
+        packet.extend_from_slice(txt);
+        packet
+    }
+
+    #[test]
+    fn test_dns_version() {
+        assert_eq!(
+            parse_dns_version(&dns_version_response()).as_deref(),
+            Some("DNS version.bind: 9.18.1-Ubuntu")
+        );
+        let mut refused = dns_version_query();
+        refused[2] = 0x81;
+        refused[3] = 0x05;
+        assert!(parse_dns_version(&refused).unwrap().contains("REFUSED"));
+        // 查询本身不是应答
+        assert!(parse_dns_version(&dns_version_query()).is_none());
+        assert_robust(parse_dns_version, &dns_version_response());
+    }
+
+    #[test]
+    fn test_ntp_readvar() {
+        let body = br#"version="ntpd 4.2.8p15@1.3728-o", processor="x86_64", system="Linux/5.15.0", leap=00"#;
+        let mut packet = ntp_readvar_request();
+        packet[1] = 0x82;
+        packet[10..12].copy_from_slice(&(body.len() as u16).to_be_bytes());
+        packet.extend_from_slice(body);
+        assert_eq!(
+            parse_ntp_readvar(&packet).as_deref(),
+            Some("NTP: ntpd 4.2.8p15@1.3728-o; Linux/5.15.0")
+        );
+        // 声明的长度超过实际数据时只解析已有部分
+        packet[10..12].copy_from_slice(&0xffffu16.to_be_bytes());
+        assert!(parse_ntp_readvar(&packet).is_some());
+
+        let mut refused = ntp_readvar_request();
+        refused[1] = 0xc2;
+        assert!(parse_ntp_readvar(&refused).unwrap().contains("拒绝"));
+        assert_robust(parse_ntp_readvar, &packet);
+    }
+
+    #[test]
+    fn test_netbios_status() {
+        let mut packet = netbios_status_query();
+        packet[2] = 0x84;
+        packet[5] = 0; // 无问题
+        packet[7] = 1; // 1个应答
+        packet.extend_from_slice(&[0, 0, 0, 0]); // TTL
+        let mut rdata = vec![2u8];
+        for (name, flags) in [("FILESRV", 0x04u8), ("CORP", 0x84)] {
+            let mut entry = [b' '; 18];
+            entry[..name.len()].copy_from_slice(name.as_bytes());
+            entry[15] = 0x00;
+            entry[16] = flags;
+            entry[17] = 0x00;
+            rdata.extend_from_slice(&entry);
+        }
+        rdata.extend_from_slice(&[0x00, 0x0c, 0x29, 0xab, 0xcd, 0xef]);
+        packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        packet.extend_from_slice(&rdata);
+        assert_eq!(
+            parse_netbios_status(&packet).as_deref(),
+            Some("NetBIOS: FILESRV（工作组/域 CORP） MAC 00:0c:29:ab:cd:ef")
+        );
+        assert_robust(parse_netbios_status, &packet);
+    }
+
+    #[test]
+    fn test_snmp_sysdescr() {
+        let request = snmp_sysdescr_request("public");
+        // 请求的结构能被同一个TLV解析器读回
+        let (tag, message, end) = read_tlv(&request, 0).unwrap();
+        assert_eq!((tag, end), (0x30, request.len()));
+        assert_eq!(read_tlv(message, 3).unwrap().1, b"public");
+
+        let descr = b"Linux router 5.10.0 #1 SMP mips";
+        let varbind = ber(0x30, &[ber(0x06, SYS_DESCR_OID), ber(0x04, descr)].concat());
+        let pdu = ber(
+            0xa2,
+            &[
+                ber(0x02, &TRANSACTION_ID.to_be_bytes()),
+                ber(0x02, &[0]),
+                ber(0x02, &[0]),
+                ber(0x30, &varbind),
+            ]
+            .concat(),
+        );
+        let response = ber(0x30, &[ber(0x02, &[0]), ber(0x04, b"public"), pdu].concat());
+        assert_eq!(
+            parse_snmp_sysdescr(&response).as_deref(),
+            Some("SNMP（团体名 public）: Linux router 5.10.0 #1 SMP mips")
+        );
+        // GetRequest 不是应答
+        assert!(parse_snmp_sysdescr(&request).is_none());
+        // 长格式长度
+        assert_eq!(
+            read_tlv(&[0x04, 0x81, 0x02, b'o', b'k'], 0),
+            Some((0x04, &b"ok"[..], 5))
+        );
+        assert!(read_tlv(&[0x04, 0x85, 0, 0, 0, 0, 1], 0).is_none());
+        assert_robust(parse_snmp_sysdescr, &response);
+    }
+
+    #[test]
+    fn test_ike_vendor_ids() {
+        let request = ike_main_mode_request();
+        assert_eq!(
+            u32::from_be_bytes(request[24..28].try_into().unwrap()) as usize,
+            request.len()
+        );
+
+        let mut response = request[..28].to_vec();
+        response[16] = 13; // 首个载荷为厂商ID
+        for (i, vid) in [
+            "afcad71368a1f1c96b8696fc77570100",
+            "4a131c81070358455c5728f20e95452f",
+        ]
+        .iter()
+        .enumerate()
+        {
+            let body = hex::decode(vid).unwrap();
+            response.push(if i == 0 { 13 } else { 0 });
+            response.push(0);
+            response.extend_from_slice(&((body.len() + 4) as u16).to_be_bytes());
+            response.extend_from_slice(&body);
+        }
+        assert_eq!(
+            parse_ike_response(&response).as_deref(),
+            Some("IKE VID: Dead Peer Detection v1.0, RFC 3947 NAT-T")
+        );
+        // 载荷长度小于头部长度
+        let mut bad = response.clone();
+        bad[30..32].copy_from_slice(&[0, 2]);
+        assert!(parse_ike_response(&bad).is_none());
+        assert_robust(parse_ike_response, &response);
+    }
+
+    #[test]
+    fn test_probe_for_port() {
+        assert_eq!(probe_for_port(161).unwrap().name, "snmp-sysdescr");
+        assert_eq!((probe_for_port(161).unwrap().payloads)().len(), 2);
+        assert!(probe_for_port(80).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_send_probe_against_local_server() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            let (_, peer) = server.recv_from(&mut buf).await.unwrap();
+            server.send_to(&dns_version_response(), peer).await.unwrap();
+        });
+        let probe = probe_for_port(53).unwrap();
+        let summary = send_probe(probe, addr, Duration::from_secs(1)).await;
+        assert_eq!(summary.as_deref(), Some("DNS version.bind: 9.18.1-Ubuntu"));
+
+        // 无应答时每个报文只等待一次超时
+        let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = silent.local_addr().unwrap();
+        let start = std::time::Instant::now();
+        let snmp = probe_for_port(161).unwrap();
+        assert!(
+            send_probe(snmp, addr, Duration::from_millis(100))
+                .await
+                .is_none()
+        );
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,423 @@
+// src/commands/profile.rs
+use crate::utils::config_dir;
+use chrono::Local;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Args, Command, Parser, Subcommand};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// 配置档所在的子目录
+const PROFILE_DIR_NAME: &str = "profiles";
+
+/// 敏感参数在配置档中引用环境变量时使用的键
+const ENV_REF_KEY: &str = "env";
+
+/// 扫描命令共用的配置档参数
+#[derive(Args, Debug, Clone, Default)]
+pub struct ProfileOptions {
+    /// 以配置档中保存的参数作为默认值（命令行显式给出的参数优先）
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// 将本次生效的全部参数保存为配置档
+    #[arg(long, value_name = "NAME")]
+    pub save_profile: Option<String>,
+}
+
+/// 配置档命令参数
+#[derive(Parser, Debug)]
+pub struct ProfileArgs {
+    #[command(subcommand)]
+    pub command: ProfileCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileCommands {
+    /// 列出所有配置档
+    #[command(name = "list")]
+    List,
+    /// 查看配置档内容
+    #[command(name = "show")]
+    Show {
+        /// 配置档名称
+        name: String,
+    },
+    /// 删除配置档
+    #[command(name = "delete")]
+    Delete {
+        /// 配置档名称
+        name: String,
+    },
+}
+
+/// 保存的扫描配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    /// 配置档名称
+    pub name: String,
+    /// 所属模块（如 "net ping"）
+    pub module: String,
+    /// 保存时的工具版本
+    pub version: String,
+    /// 保存时间
+    pub created_at: String,
+    /// 参数名 -> 参数值（敏感参数只保存环境变量引用）
+    pub options: Map<String, Value>,
+}
+
+/// 执行配置档命令
+///
+/// # 参数
+/// * `args` - 配置档命令参数
+pub fn run(args: &ProfileArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match args.command {
+        ProfileCommands::List => {
+            let profiles = list_profiles()?;
+            if profiles.is_empty() {
+                println!("📭 暂无配置档（目录: {}）", profile_dir().display());
+                return Ok(());
+            }
+            println!("📄 配置档（共 {} 个）:", profiles.len());
+            for p in profiles {
+                println!(
+                    "   {} | {} | {} | {}",
+                    p.name,
+                    p.module,
+                    p.version,
+                    p.created_at
+                        .get(..19)
+                        .unwrap_or(&p.created_at)
+                        .replace('T', " ")
+                );
+            }
+        }
+        ProfileCommands::Show { ref name } => {
+            let profile = load_profile(name)?;
+            println!("📄 配置档: {}", profile.name);
+            println!("   模块: {}", profile.module);
+            println!("   版本: {}", profile.version);
+            println!("   保存时间: {}", profile.created_at);
+            println!("   参数:");
+            for (key, value) in &profile.options {
+                println!(
+                    "     --{} = {}",
+                    key.replace('_', "-"),
+                    describe_value(value)
+                );
+            }
+        }
+        ProfileCommands::Delete { ref name } => {
+            let path = profile_path(name)?;
+            if !path.exists() {
+                return Err(format!("配置档不存在: {}", name).into());
+            }
+            fs::remove_file(&path)?;
+            println!("🗑️  已删除配置档: {}", name);
+        }
+    }
+    Ok(())
+}
+
+/// 配置档目录
+pub fn profile_dir() -> PathBuf {
+    config_dir().join(PROFILE_DIR_NAME)
+}
+
+/// 配置档文件路径（校验名称，防止写出配置档目录）
+fn profile_path(name: &str) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(format!(
+            "无效的配置档名称 \"{}\"，只能包含字母、数字、-、_ 和 .，且不能以 . 开头",
+            name
+        )
+        .into());
+    }
+    Ok(profile_dir().join(format!("{}.json", name)))
+}
+
+/// 读取配置档
+///
+/// # 参数
+/// * `name` - 配置档名称
+pub fn load_profile(name: &str) -> Result<Profile, Box<dyn Error + Send + Sync>> {
+    let path = profile_path(name)?;
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("无法读取配置档 {}: {}", name, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("配置档 {} 格式错误: {}", name, e).into())
+}
+
+/// 列出所有配置档（按名称排序，跳过无法解析的文件）
+pub fn list_profiles() -> Result<Vec<Profile>, Box<dyn Error + Send + Sync>> {
+    let dir = profile_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut profiles: Vec<Profile> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(profiles)
+}
+
+/// 按 `--profile` / `--save-profile` 套用或保存配置档
+///
+/// 配置档中的参数覆盖环境变量和默认值，命令行显式给出的参数保持不变。
+///
+/// # 参数
+/// * `args` - 扫描参数（套用配置档后原地更新）
+/// * `options` - 本次的配置档参数
+/// * `module` - 模块名（如 "net ping"）
+/// * `cmd` - 模块的命令定义（用于识别敏感参数）
+/// * `matches` - 模块的解析结果（用于识别命令行显式给出的参数）
+pub fn resolve<T: Serialize + DeserializeOwned>(
+    args: &mut T,
+    options: &ProfileOptions,
+    module: &str,
+    cmd: &Command,
+    matches: &ArgMatches,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if let Some(ref name) = options.profile {
+        let profile = load_profile(name)?;
+        if profile.version != env!("CARGO_PKG_VERSION") {
+            eprintln!(
+                "⚠️  配置档 {} 由 {} 版本保存，当前版本为 {}",
+                name,
+                profile.version,
+                env!("CARGO_PKG_VERSION")
+            );
+        }
+        *args = apply_profile(args, &profile, module, cmd, matches)?;
+        println!("📄 已套用配置档: {}", name);
+    }
+
+    if let Some(ref name) = options.save_profile {
+        let path = save_profile(name, module, args, cmd)?;
+        println!("💾 配置档已保存: {}", path.display());
+    }
+    Ok(())
+}
+
+/// 以配置档中的参数覆盖未在命令行显式给出的参数
+fn apply_profile<T: Serialize + DeserializeOwned>(
+    args: &T,
+    profile: &Profile,
+    module: &str,
+    cmd: &Command,
+    matches: &ArgMatches,
+) -> Result<T, Box<dyn Error + Send + Sync>> {
+    if profile.module != module {
+        return Err(format!(
+            "配置档 {} 属于模块 \"{}\"，不能用于 \"{}\"",
+            profile.name, profile.module, module
+        )
+        .into());
+    }
+
+    let Value::Object(mut current) = serde_json::to_value(args)? else {
+        return Err("扫描参数无法序列化为配置档".into());
+    };
+    let mut unsupported = Vec::new();
+    for (key, value) in &profile.options {
+        if !current.contains_key(key) {
+            unsupported.push(key.as_str());
+            continue;
+        }
+        if matches.value_source(key) == Some(ValueSource::CommandLine) {
+            continue;
+        }
+        let value = match value.get(ENV_REF_KEY).and_then(Value::as_str) {
+            Some(var) if is_sensitive(cmd, key).is_some() => {
+                let resolved = std::env::var(var).map_err(|_| {
+                    format!(
+                        "配置档中的参数 --{} 引用了环境变量 {}，但该变量未设置",
+                        key.replace('_', "-"),
+                        var
+                    )
+                })?;
+                Value::String(resolved)
+            }
+            _ => value.clone(),
+        };
+        current.insert(key.clone(), value);
+    }
+    if !unsupported.is_empty() {
+        eprintln!(
+            "⚠️  配置档中的参数 {} 当前版本不支持，已忽略",
+            unsupported.join(", ")
+        );
+    }
+
+    serde_json::from_value(Value::Object(current))
+        .map_err(|e| format!("配置档 {} 中的参数无效: {}", profile.name, e).into())
+}
+
+/// 将生效参数保存为配置档
+///
+/// 未设置的参数不保存；敏感参数（帮助中隐藏环境变量值的参数）只保存其环境变量名，
+/// 没有对应环境变量的敏感参数不保存，需每次在命令行给出。
+fn save_profile<T: Serialize>(
+    name: &str,
+    module: &str,
+    args: &T,
+    cmd: &Command,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let path = profile_path(name)?;
+    let Value::Object(values) = serde_json::to_value(args)? else {
+        return Err("扫描参数无法序列化为配置档".into());
+    };
+
+    let mut options = Map::new();
+    for (key, value) in values {
+        if value.is_null() {
+            continue;
+        }
+        match is_sensitive(cmd, &key) {
+            Some(Some(var)) => {
+                options.insert(key, json!({ ENV_REF_KEY: var }));
+            }
+            Some(None) => eprintln!(
+                "⚠️  参数 --{} 属于敏感信息，未保存到配置档",
+                key.replace('_', "-")
+            ),
+            None => {
+                options.insert(key, value);
+            }
+        }
+    }
+
+    let profile = Profile {
+        name: name.to_string(),
+        module: module.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Local::now().to_rfc3339(),
+        options,
+    };
+    fs::create_dir_all(profile_dir())?;
+    fs::write(&path, serde_json::to_string_pretty(&profile)?)?;
+    Ok(path)
+}
+
+/// 判断参数是否为敏感参数
+///
+/// # 返回
+/// * `None` - 非敏感参数
+/// * `Some(Some(var))` - 敏感参数及其环境变量名
+/// * `Some(None)` - 敏感参数，但没有绑定环境变量
+fn is_sensitive(cmd: &Command, id: &str) -> Option<Option<String>> {
+    cmd.get_arguments()
+        .find(|a| a.get_id() == id && a.is_hide_env_values_set())
+        .map(|a| a.get_env().map(|e| e.to_string_lossy().into_owned()))
+}
+
+/// 参数值的显示形式
+fn describe_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Object(map) if map.contains_key(ENV_REF_KEY) => {
+            format!("(环境变量 {})", map[ENV_REF_KEY].as_str().unwrap_or("?"))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+
+    #[derive(Parser, Debug, Serialize, Deserialize)]
+    struct DemoArgs {
+        #[arg(short, long, required_unless_present = "profile")]
+        target: Option<String>,
+        #[arg(long, default_value = "3")]
+        timeout: u64,
+        #[arg(long)]
+        live: bool,
+        #[arg(long, env = "GXTOOLS_TEST_SECRET", hide_env_values = true)]
+        password: Option<String>,
+        #[command(flatten)]
+        #[serde(skip)]
+        profile_args: ProfileOptions,
+    }
+
+    fn parse(argv: &[&str]) -> (DemoArgs, ArgMatches) {
+        let matches = DemoArgs::command().get_matches_from(argv);
+        (DemoArgs::from_arg_matches(&matches).unwrap(), matches)
+    }
+
+    fn profile(module: &str, options: Value) -> Profile {
+        Profile {
+            name: "demo".to_string(),
+            module: module.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: String::new(),
+            options: options.as_object().unwrap().clone(),
+        }
+    }
+
+    #[test]
+    fn test_explicit_flags_override_profile() {
+        let saved = profile(
+            "demo",
+            json!({ "target": "10.0.0.0/24", "timeout": 9, "live": true, "removed": 1 }),
+        );
+        let (args, matches) = parse(&["demo", "--profile", "demo", "--timeout", "1"]);
+        let merged = apply_profile(&args, &saved, "demo", &DemoArgs::command(), &matches).unwrap();
+        assert_eq!(merged.target.as_deref(), Some("10.0.0.0/24"));
+        assert_eq!(merged.timeout, 1);
+        assert!(merged.live);
+    }
+
+    #[test]
+    fn test_profile_rejected_for_other_module() {
+        let saved = profile("net ping", json!({ "target": "10.0.0.1" }));
+        let (args, matches) = parse(&["demo", "--profile", "demo"]);
+        let err = apply_profile(
+            &args,
+            &saved,
+            "pentest portscan",
+            &DemoArgs::command(),
+            &matches,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("net ping"));
+    }
+
+    #[test]
+    fn test_sensitive_values_saved_as_env_reference() {
+        let cmd = DemoArgs::command();
+        assert_eq!(
+            is_sensitive(&cmd, "password"),
+            Some(Some("GXTOOLS_TEST_SECRET".to_string()))
+        );
+        assert_eq!(is_sensitive(&cmd, "timeout"), None);
+
+        // 引用的环境变量未设置时拒绝套用
+        let saved = profile(
+            "demo",
+            json!({ "password": { "env": "GXTOOLS_TEST_SECRET" } }),
+        );
+        let (args, matches) = parse(&["demo", "--target", "10.0.0.1"]);
+        assert!(apply_profile(&args, &saved, "demo", &cmd, &matches).is_err());
+    }
+
+    #[test]
+    fn test_profile_name_validated() {
+        assert!(profile_path("engagement-2024.q1").is_ok());
+        assert!(profile_path("../escape").is_err());
+        assert!(profile_path("a/b").is_err());
+        assert!(profile_path("").is_err());
+    }
+}
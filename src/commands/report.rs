@@ -0,0 +1,1001 @@
+// src/commands/report.rs
+use crate::commands::history::{RunRecord, history_file, load_records, open_path};
+use crate::commands::reverify::{self, ReverifyArgs};
+use crate::utils::console::Icon;
+use crate::utils::finding::csv_cell;
+use crate::utils::integrity::{ArtifactCheck, SignatureCheck, load_verifying_key, verify_run};
+use crate::utils::meta::{self, meta};
+use crate::utils::output::OutputKind;
+use crate::utils::redact::Redactor;
+use crate::utils::run_dir::{
+    CONCURRENCY_FILE_NAME, DUAL_STACK_FILE_NAME, FINDINGS_FILE_NAME, HOSTS_FILE_NAME,
+    PORTS_FILE_NAME, RUNS_DIR_NAME, RunDir, RunManifest, SummaryItem, TARGET_OVERRIDES_FILE_NAME,
+};
+use crate::utils::{
+    ExcelOptions, output_root, parse_targets, save_table_to_excel, save_to_excel_with_options,
+};
+use axum::Router;
+use axum::response::Html;
+use axum::routing::get;
+use chrono::{DateTime, FixedOffset, Local, NaiveDate};
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use tokio::net::TcpListener;
+
+/// 结果页面模板（内嵌于程序中，离线可用）
+///
+/// 页面数据以JSON形式替换模板中的 `__RUN_DATA__`，页面标题替换 `__TITLE__`。
+const REPORT_TEMPLATE: &str = include_str!("../../assets/report.html");
+
+/// 报告命令参数
+#[derive(Parser, Debug)]
+pub struct ReportArgs {
+    #[command(subcommand)]
+    pub command: ReportCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ReportCommands {
+    /// 在浏览器中查看某次运行的结果（启动仅本机可访问的临时服务，Ctrl+C 退出）
+    #[command(name = "view")]
+    View {
+        /// 运行目录（如 output/runs/20240102_100000_ping_ab12），也可只写目录名或其前缀
+        run: String,
+
+        /// 不自动打开浏览器，只打印访问地址
+        #[arg(long)]
+        no_open: bool,
+    },
+    /// 按历次 ping / portscan 运行结果列出某台主机的状态变化（上线、离线、端口开放/关闭）
+    #[command(name = "timeline")]
+    Timeline {
+        /// 主机IP
+        #[arg(long, value_name = "IP")]
+        host: Ipv4Addr,
+
+        /// 只统计该日期（含）之后的运行，格式：2024-01-01
+        #[arg(short, long, value_name = "DATE")]
+        since: Option<NaiveDate>,
+
+        /// 是否输出结果到Excel文件
+        #[arg(short = 'o', long, env = "GXTOOLS_OUTPUT")]
+        output: bool,
+    },
+    /// 清理早于保留天数的运行目录（默认只列出，加 --yes 才删除）
+    #[command(name = "prune")]
+    Prune {
+        /// 保留最近多少天的运行目录
+        #[arg(long, value_name = "DAYS")]
+        retain_days: u32,
+
+        /// 确认删除
+        #[arg(long)]
+        yes: bool,
+    },
+    /// 重新计算运行目录中各产物的SHA256并与产物索引比对，确认交付物未被修改
+    #[command(name = "verify")]
+    Verify {
+        /// 运行目录（如 output/runs/20240102_100000_ping_ab12），也可只写目录名或其前缀
+        run: String,
+
+        /// 签名公钥文件（Base64编码的Ed25519公钥），指定时同时校验产物索引的签名
+        #[arg(long, env = "GXTOOLS_PUBLIC_KEY", value_name = "FILE")]
+        public_key: Option<PathBuf>,
+    },
+    /// 整改后复查：按之前导出的发现逐条重新执行检查，标记 已修复/未修复/无法验证
+    #[command(name = "reverify")]
+    Reverify(ReverifyArgs),
+    /// 将结果文件脱敏后另存，供对外提交（IP、主机名替换为化名，对照表另行保存）
+    #[command(name = "redact")]
+    Redact {
+        /// 结果文件（JSON，如运行目录下的 ports.json、hosts.json）
+        #[arg(short, long, value_name = "FILE")]
+        input: PathBuf,
+
+        /// 输出文件，按扩展名决定格式：xlsx、csv、json、html
+        #[arg(short = 'O', long, value_name = "FILE")]
+        output_file: PathBuf,
+
+        /// 对照表文件（CSV，已存在时追加合并），默认为输出文件名加 _map.csv
+        #[arg(long, value_name = "FILE")]
+        map: Option<PathBuf>,
+
+        /// 化名的盐值（同一项目使用相同盐值，多份报告的化名保持一致）
+        #[arg(long, env = "GXTOOLS_REDACT_SALT", value_name = "SALT")]
+        salt: String,
+
+        /// 保留Banner、标题等证据字段
+        #[arg(long)]
+        keep_evidence: bool,
+    },
+}
+
+/// 页面展示的一次运行的数据
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    /// 产物索引
+    pub manifest: RunManifest,
+    /// 带星期的开始时间（按界面语言格式化）
+    pub started_at: String,
+    /// 运行元数据（按显示顺序，显示在页头）
+    pub meta: Vec<SummaryItem>,
+    /// 统计摘要
+    pub summary: Vec<SummaryItem>,
+    /// 结果表格
+    pub tables: Vec<ReportTable>,
+}
+
+/// 单个结果表格
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportTable {
+    /// 来源文件
+    pub file: String,
+    /// 表格标题
+    pub title: String,
+    /// 数据行
+    pub rows: Vec<Value>,
+}
+
+/// 执行报告命令
+///
+/// # 参数
+/// * `args` - 报告命令参数
+///
+/// # 返回
+/// * `Ok(())` - 收到 Ctrl+C 后正常退出
+/// * `Err` - 运行目录无效或启动服务失败
+pub async fn run(args: &ReportArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match &args.command {
+        ReportCommands::View { run, no_open } => {
+            let dir = resolve_run_dir(run)?;
+            let report = load_report(&dir)?;
+            let page = render_html(&report)?;
+
+            // 随机端口，仅本机可访问
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .await
+                .map_err(|e| format!("无法启动本地服务: {}", e))?;
+            let url = format!("http://{}/", listener.local_addr()?);
+
+            println!("{} 运行目录: {}", Icon::List, dir.display());
+            println!("{} 结果页面: {}", Icon::Ok, url);
+            if !no_open && let Err(e) = open_path(Path::new(&url)) {
+                eprintln!("{} {}，请手动在浏览器中打开上述地址", Icon::Warn, e);
+            }
+            println!("   按 Ctrl+C 退出");
+
+            axum::serve(listener, router(page, report))
+                .with_graceful_shutdown(async {
+                    let _ = tokio::signal::ctrl_c().await;
+                })
+                .await?;
+
+            println!("{} 结果页面服务已停止", Icon::Ok);
+            Ok(())
+        }
+        ReportCommands::Timeline {
+            host,
+            since,
+            output,
+        } => {
+            let host = host.to_string();
+            let records = load_records(&history_file());
+            let observations: Vec<Observation> =
+                collect_observations(output_root(), &host, &records)
+                    .into_iter()
+                    .filter(|o| since.is_none_or(|d| o.started_at.date_naive() >= d))
+                    .collect();
+            if observations.is_empty() {
+                println!(
+                    "{} 没有包含 {} 的 ping / portscan 运行结果",
+                    Icon::List,
+                    host
+                );
+                return Ok(());
+            }
+
+            let events = timeline_events(&observations);
+            println!(
+                "{} {} 的状态变化（{} 次运行，{} 个事件）:",
+                Icon::List,
+                host,
+                observations.len(),
+                events.len()
+            );
+            for e in &events {
+                println!(
+                    "   {} | {} | {} | {}",
+                    e.time.format("%Y-%m-%d %H:%M:%S"),
+                    e.event,
+                    e.detail,
+                    e.run
+                );
+            }
+
+            if *output && !events.is_empty() {
+                let headers = ["时间", "事件", "详情", "模块", "运行目录"];
+                save_to_excel_with_options(
+                    &events,
+                    &headers,
+                    |e| {
+                        vec![
+                            e.time.format("%Y-%m-%d %H:%M:%S").to_string(),
+                            e.event.to_string(),
+                            e.detail.clone(),
+                            e.module.clone(),
+                            e.run.clone(),
+                        ]
+                    },
+                    OutputKind::TIMELINE,
+                    &ExcelOptions::default(),
+                )?;
+            }
+            Ok(())
+        }
+        ReportCommands::Redact {
+            input,
+            output_file,
+            map,
+            salt,
+            keep_evidence,
+        } => {
+            let map = map.clone().unwrap_or_else(|| {
+                let stem = output_file
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                output_file.with_file_name(format!("{}_map.csv", stem))
+            });
+            let redactor = Redactor::new(salt, *keep_evidence).with_mapping_file(map)?;
+            let rows = redact_file(input, output_file, &redactor)?;
+            let map = redactor.save_mapping()?.unwrap_or_default();
+            println!(
+                "{} 已脱敏 {} 条结果: {}",
+                Icon::Ok,
+                rows,
+                output_file.display()
+            );
+            println!("{} 对照表（请勿随报告外发）: {}", Icon::Warn, map.display());
+            Ok(())
+        }
+        ReportCommands::Verify { run, public_key } => {
+            let dir = resolve_run_dir(run)?;
+            let key = public_key.as_deref().map(load_verifying_key).transpose()?;
+            let report = verify_run(&dir, key.as_ref())?;
+
+            println!("{} 运行目录: {}", Icon::List, dir.display());
+            for (file, check) in &report.artifacts {
+                match check {
+                    ArtifactCheck::Ok => println!("   {} {}", Icon::Ok, file),
+                    ArtifactCheck::Missing(e) => {
+                        println!("   {} {}: 文件缺失或无法读取（{}）", Icon::Fail, file, e)
+                    }
+                    ArtifactCheck::Mismatch { expected, actual } => println!(
+                        "   {} {}: 内容已被修改（索引 {}，实际 {}）",
+                        Icon::Fail,
+                        file,
+                        expected,
+                        actual
+                    ),
+                }
+            }
+            let icon = match report.signature {
+                SignatureCheck::Valid => Icon::Ok,
+                SignatureCheck::Invalid | SignatureCheck::Missing => Icon::Fail,
+                SignatureCheck::Unchecked | SignatureCheck::Unsigned => Icon::Warn,
+            };
+            println!("   {} 产物索引: {}", icon, report.signature);
+
+            if !report.passed() {
+                return Err(format!(
+                    "校验未通过: {} / {} 个产物不一致（产物索引: {}）",
+                    report.failures(),
+                    report.artifacts.len(),
+                    report.signature
+                )
+                .into());
+            }
+            println!(
+                "{} 校验通过: {} 个产物均与产物索引一致",
+                Icon::Ok,
+                report.artifacts.len()
+            );
+            Ok(())
+        }
+        ReportCommands::Reverify(args) => reverify::run(args).await,
+        ReportCommands::Prune { retain_days, yes } => {
+            let cutoff = Local::now() - chrono::Duration::days(i64::from(*retain_days));
+            let expired = expired_runs(output_root(), cutoff);
+            if expired.is_empty() {
+                println!("{} 没有早于 {} 天的运行目录", Icon::List, retain_days);
+                return Ok(());
+            }
+
+            for dir in &expired {
+                if *yes {
+                    fs::remove_dir_all(dir)
+                        .map_err(|e| format!("无法删除 {}: {}", dir.display(), e))?;
+                }
+                println!("   {}", dir.display());
+            }
+            if *yes {
+                println!("{} 已删除 {} 个运行目录", Icon::Ok, expired.len());
+            } else {
+                println!(
+                    "{} 以上 {} 个运行目录早于 {} 天，加 --yes 确认删除",
+                    Icon::Warn,
+                    expired.len(),
+                    retain_days
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+/// 读取结果文件，脱敏后按输出文件的扩展名写出
+///
+/// # 参数
+/// * `input` - 结果文件（JSON数组或单个对象）
+/// * `output` - 输出文件（xlsx、csv、json、html）
+/// * `redactor` - 脱敏器
+///
+/// # 返回
+/// * `Ok(usize)` - 写出的结果条数
+pub fn redact_file(
+    input: &Path,
+    output: &Path,
+    redactor: &Redactor,
+) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let text = fs::read_to_string(input)
+        .map_err(|e| format!("无法读取结果文件 {}: {}", input.display(), e))?;
+    let rows: Vec<Value> = match serde_json::from_str(&text)
+        .map_err(|e| format!("结果文件 {} 不是有效的JSON: {}", input.display(), e))?
+    {
+        Value::Array(rows) => rows,
+        row => vec![row],
+    };
+    let rows: Vec<Value> = rows.iter().map(|row| redactor.value(row)).collect();
+
+    let extension = output
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    match extension.as_str() {
+        "json" => fs::write(output, serde_json::to_string_pretty(&rows)?)?,
+        "xlsx" => {
+            let (headers, cells) = flatten_rows(&rows);
+            let headers: Vec<&str> = headers.iter().map(String::as_str).collect();
+            save_table_to_excel(output, &headers, cells.into_iter())?;
+        }
+        "csv" => {
+            let (headers, cells) = flatten_rows(&rows);
+            let mut out = String::from("\u{feff}");
+            for line in std::iter::once(headers).chain(cells) {
+                let line: Vec<String> = line.iter().map(|c| csv_cell(c)).collect();
+                out.push_str(&line.join(","));
+                out.push_str("\r\n");
+            }
+            fs::write(output, out)?;
+        }
+        "html" | "htm" => {
+            let name = output
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let report = RunReport {
+                manifest: RunManifest {
+                    id: name.clone(),
+                    module: "report redact".to_string(),
+                    started_at: Local::now().to_rfc3339(),
+                    meta: meta().clone(),
+                    artifacts: Vec::new(),
+                },
+                started_at: meta::format_timestamp(Local::now()),
+                meta: meta::summary_items(meta()),
+                summary: vec![("结果".to_string(), format!("{} 条（已脱敏）", rows.len()))],
+                tables: vec![ReportTable {
+                    file: name,
+                    title: "脱敏结果".to_string(),
+                    rows: rows.clone(),
+                }],
+            };
+            fs::write(output, render_html(&report)?)?;
+        }
+        _ => {
+            return Err(format!(
+                "不支持的输出格式: {}（可用 xlsx、csv、json、html）",
+                output.display()
+            )
+            .into());
+        }
+    }
+    Ok(rows.len())
+}
+
+/// 把JSON结果展开为表格：列为各条结果中出现过的字段（按首次出现的顺序）
+fn flatten_rows(rows: &[Value]) -> (Vec<String>, Vec<Vec<String>>) {
+    let mut headers: Vec<String> = Vec::new();
+    for row in rows {
+        match row {
+            Value::Object(map) => {
+                for key in map.keys() {
+                    if !headers.contains(key) {
+                        headers.push(key.clone());
+                    }
+                }
+            }
+            _ if !headers.iter().any(|h| h == "值") => headers.push("值".to_string()),
+            _ => {}
+        }
+    }
+    let cell = |value: Option<&Value>| match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(items)) if items.iter().all(|v| !v.is_object() && !v.is_array()) => items
+            .iter()
+            .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Some(v) => v.to_string(),
+    };
+    let cells = rows
+        .iter()
+        .map(|row| {
+            headers
+                .iter()
+                .map(|h| match row {
+                    Value::Object(map) => cell(map.get(h)),
+                    other if h == "值" => cell(Some(other)),
+                    _ => String::new(),
+                })
+                .collect()
+        })
+        .collect();
+    (headers, cells)
+}
+
+/// 某次运行中对一台主机的观测结果
+#[derive(Debug, Clone)]
+pub struct Observation {
+    /// 运行开始时间
+    pub started_at: DateTime<FixedOffset>,
+    /// 运行目录名
+    pub run: String,
+    /// 模块名称
+    pub module: String,
+    /// 是否存活（ping 结果，或端口扫描发现了开放端口）
+    pub up: Option<bool>,
+    /// 开放端口（仅端口扫描）
+    pub ports: Option<BTreeSet<u16>>,
+}
+
+/// 主机状态变化事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineEvent {
+    /// 观测到变化的运行开始时间
+    pub time: DateTime<FixedOffset>,
+    /// 事件（上线/离线/端口开放/端口关闭）
+    pub event: &'static str,
+    /// 详情（端口号或ping状态）
+    pub detail: String,
+    /// 模块名称
+    pub module: String,
+    /// 运行目录名
+    pub run: String,
+}
+
+/// 遍历输出根目录下的运行目录，收集对某台主机的观测结果（按运行时间排序）
+///
+/// ping 结果中包含该主机时记录存活状态；端口扫描结果只保存有开放端口的主机，
+/// 因此没有该主机时，只有历史记录中的扫描目标覆盖了该主机才记为"没有开放端口"。
+///
+/// # 参数
+/// * `root` - 输出根目录
+/// * `host` - 主机IP
+/// * `records` - 历史记录（用于判断端口扫描的目标范围）
+pub fn collect_observations(root: &Path, host: &str, records: &[RunRecord]) -> Vec<Observation> {
+    let mut observations: Vec<Observation> = fs::read_dir(root.join(RUNS_DIR_NAME))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| observe_run(&e.path(), host, records))
+                .collect()
+        })
+        .unwrap_or_default();
+    observations.sort_by_key(|o| o.started_at);
+    observations
+}
+
+/// 读取单个运行目录中对某台主机的观测结果
+fn observe_run(dir: &Path, host: &str, records: &[RunRecord]) -> Option<Observation> {
+    let manifest = RunDir::load_manifest(dir).ok()?;
+    let is_ping = manifest.module.ends_with("ping");
+    if !is_ping && !manifest.module.ends_with("portscan") {
+        return None;
+    }
+    let started_at = DateTime::parse_from_rfc3339(&manifest.started_at).ok()?;
+    let report = load_report(dir).ok()?;
+    let rows = |file: &str| -> Vec<Value> {
+        report
+            .tables
+            .iter()
+            .filter(|t| t.file == file)
+            .flat_map(|t| t.rows.iter())
+            .filter(|r| r["ip"] == host)
+            .cloned()
+            .collect()
+    };
+
+    let (up, ports) = if is_ping {
+        let row = rows(HOSTS_FILE_NAME).into_iter().next()?;
+        (Some(row["status"] == "成功"), None)
+    } else {
+        let ports: BTreeSet<u16> = rows(PORTS_FILE_NAME)
+            .iter()
+            .filter_map(|r| r["port"].as_u64())
+            .filter_map(|p| u16::try_from(p).ok())
+            .collect();
+        if ports.is_empty() && !run_covers(records, &manifest.id, host) {
+            return None;
+        }
+        ((!ports.is_empty()).then_some(true), Some(ports))
+    };
+
+    Some(Observation {
+        started_at,
+        run: dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        module: manifest.module,
+        up,
+        ports,
+    })
+}
+
+/// 历史记录中该次运行的扫描目标是否包含某台主机（来自文件的目标无法判断）
+fn run_covers(records: &[RunRecord], id: &str, host: &str) -> bool {
+    records.iter().filter(|r| r.id == id).any(|r| {
+        r.targets
+            .split(" + ")
+            .filter(|part| !part.starts_with("xlsx:"))
+            .filter_map(|part| parse_targets(part).ok())
+            .any(|ips| ips.iter().any(|ip| ip == host))
+    })
+}
+
+/// 按观测结果计算状态变化事件
+///
+/// 第一次观测到存活或开放端口也记为事件（即主机/端口出现的时间）。
+///
+/// # 参数
+/// * `observations` - 按时间排序的观测结果
+pub fn timeline_events(observations: &[Observation]) -> Vec<TimelineEvent> {
+    let mut events = Vec::new();
+    let mut last_up: Option<bool> = None;
+    let mut last_ports: BTreeSet<u16> = BTreeSet::new();
+
+    for o in observations {
+        let mut push = |event: &'static str, detail: String| {
+            events.push(TimelineEvent {
+                time: o.started_at,
+                event,
+                detail,
+                module: o.module.clone(),
+                run: o.run.clone(),
+            })
+        };
+
+        if let Some(up) = o.up
+            && last_up != Some(up)
+        {
+            let (event, detail) = match (up, o.ports.is_some()) {
+                (true, true) => ("上线", "发现开放端口"),
+                (true, false) => ("上线", "ping 成功"),
+                (false, _) => ("离线", "ping 失败"),
+            };
+            // 第一次观测就离线不算状态变化
+            if up || last_up.is_some() {
+                push(event, detail.to_string());
+            }
+            last_up = Some(up);
+        }
+
+        if let Some(ref ports) = o.ports {
+            for port in ports.difference(&last_ports) {
+                push("端口开放", port.to_string());
+            }
+            for port in last_ports.difference(ports) {
+                push("端口关闭", port.to_string());
+            }
+            last_ports = ports.clone();
+        }
+    }
+    events
+}
+
+/// 开始时间早于截止时间的运行目录（没有产物索引的目录不处理）
+///
+/// # 参数
+/// * `root` - 输出根目录
+/// * `cutoff` - 截止时间
+pub fn expired_runs(root: &Path, cutoff: DateTime<Local>) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(root.join(RUNS_DIR_NAME))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    RunDir::load_manifest(p)
+                        .ok()
+                        .and_then(|m| DateTime::parse_from_rfc3339(&m.started_at).ok())
+                        .is_some_and(|t| t < cutoff)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+/// 构建结果页面路由
+///
+/// # 参数
+/// * `page` - 渲染好的页面
+/// * `report` - 页面数据（同时以 `/run.json` 提供）
+pub fn router(page: String, report: RunReport) -> Router {
+    Router::new()
+        .route("/", get(move || async move { Html(page) }))
+        .route("/run.json", get(move || async move { axum::Json(report) }))
+}
+
+/// 解析运行目录参数
+///
+/// 依次尝试：已存在的路径、输出根目录下 `runs/` 中的目录名、目录名前缀（须唯一）。
+///
+/// # 参数
+/// * `run` - 命令行传入的运行目录
+pub fn resolve_run_dir(run: &str) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let path = PathBuf::from(run);
+    if path.is_dir() {
+        return Ok(path);
+    }
+
+    let runs = output_root().join(RUNS_DIR_NAME);
+    let mut matches: Vec<PathBuf> = fs::read_dir(&runs)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.is_dir()
+                        && p.file_name()
+                            .is_some_and(|n| n.to_string_lossy().starts_with(run))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    match matches.len() {
+        0 => Err(format!("未找到运行目录: {}", run).into()),
+        1 => Ok(matches.remove(0)),
+        n => Err(format!("运行目录 {} 匹配到 {} 个，请提供更长的名称", run, n).into()),
+    }
+}
+
+/// 从运行目录读取产物索引及JSON结果（不重新扫描）
+///
+/// 已登记但文件缺失或内容被修改的产物只打印警告并跳过。
+///
+/// # 参数
+/// * `dir` - 运行目录
+pub fn load_report(dir: &Path) -> Result<RunReport, Box<dyn Error + Send + Sync>> {
+    let manifest = RunDir::load_manifest(dir)?;
+    let mut summary = Vec::new();
+    let mut tables = Vec::new();
+
+    for artifact in &manifest.artifacts {
+        if artifact.kind != "json" && artifact.kind != "summary" {
+            continue;
+        }
+        let path = dir.join(&artifact.file);
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("{} 跳过产物 {}: {}", Icon::Warn, artifact.file, e);
+                continue;
+            }
+        };
+        if hex::encode(Sha256::digest(&data)) != artifact.sha256 {
+            eprintln!(
+                "{} 跳过产物 {}: 内容与产物索引不一致",
+                Icon::Warn,
+                artifact.file
+            );
+            continue;
+        }
+
+        if artifact.kind == "summary" {
+            summary = serde_json::from_slice(&data)
+                .map_err(|e| format!("统计摘要格式错误 {}: {}", artifact.file, e))?;
+        } else {
+            let rows: Vec<Value> = serde_json::from_slice(&data)
+                .map_err(|e| format!("结果文件格式错误 {}: {}", artifact.file, e))?;
+            tables.push(ReportTable {
+                title: table_title(&artifact.file),
+                file: artifact.file.clone(),
+                rows,
+            });
+        }
+    }
+
+    if summary.is_empty() && tables.is_empty() {
+        return Err(format!("运行目录 {} 中没有可查看的JSON结果", dir.display()).into());
+    }
+
+    let started_at = DateTime::parse_from_rfc3339(&manifest.started_at)
+        .map(|t| meta::format_timestamp(t.with_timezone(&Local)))
+        .unwrap_or_else(|_| manifest.started_at.clone());
+    Ok(RunReport {
+        started_at,
+        meta: meta::summary_items(&manifest.meta),
+        manifest,
+        summary,
+        tables,
+    })
+}
+
+/// 读取某次运行中一个JSON结果文件的数据行（供复核基线等使用）
+///
+/// # 参数
+/// * `run` - 运行目录（同 `report view`）
+/// * `file` - 结果文件名（如 `hosts.json`）
+pub fn load_run_rows(run: &str, file: &str) -> Result<Vec<Value>, Box<dyn Error + Send + Sync>> {
+    let dir = resolve_run_dir(run)?;
+    load_report(&dir)?
+        .tables
+        .into_iter()
+        .find(|t| t.file == file)
+        .map(|t| t.rows)
+        .ok_or_else(|| format!("运行目录 {} 中没有 {}", dir.display(), file).into())
+}
+
+/// 表格标题（按结果文件名）
+fn table_title(file: &str) -> String {
+    match file {
+        HOSTS_FILE_NAME => "主机",
+        PORTS_FILE_NAME => "端口",
+        FINDINGS_FILE_NAME => "发现",
+        DUAL_STACK_FILE_NAME => "双栈主机",
+        TARGET_OVERRIDES_FILE_NAME => "目标参数覆盖",
+        CONCURRENCY_FILE_NAME => "自适应并发",
+        _ => file,
+    }
+    .to_string()
+}
+
+/// 使用内嵌模板渲染结果页面
+///
+/// # 参数
+/// * `report` - 页面数据
+pub fn render_html(report: &RunReport) -> Result<String, Box<dyn Error + Send + Sync>> {
+    // 转义 `<`，避免结果中的 `</script>` 等内容提前结束脚本块
+    let data = serde_json::to_string(report)?.replace('<', "\\u003c");
+    let title = format!("{} · {}", report.manifest.module, report.manifest.id)
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    Ok(REPORT_TEMPLATE
+        .replace("__TITLE__", &title)
+        .replace("__RUN_DATA__", &data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    fn sample_run(root: &Path) -> PathBuf {
+        let run = RunDir::new(root, "20240102100000-ab12", "pentest portscan");
+        let ports = vec![serde_json::json!({
+            "ip": "10.0.0.1",
+            "port": 80,
+            "banner": "</script><script>alert(1)</script>",
+        })];
+        run.write_json(PORTS_FILE_NAME, "json", &ports, 1).unwrap();
+        run.write_summary(&[("总计".to_string(), "1 个端口".to_string())])
+            .unwrap();
+        run.path().to_path_buf()
+    }
+
+    #[test]
+    fn test_report_is_loaded_from_manifest() {
+        let root = std::env::temp_dir().join(format!("gxr_report_{}", std::process::id()));
+        let dir = sample_run(&root);
+        // 未登记的文件不展示
+        fs::write(dir.join("extra.json"), "[]").unwrap();
+
+        let report = load_report(&dir).unwrap();
+        assert_eq!(
+            report.summary,
+            [("总计".to_string(), "1 个端口".to_string())]
+        );
+        assert_eq!(report.tables.len(), 1);
+        assert_eq!(report.tables[0].title, "端口");
+
+        // 被修改的产物不展示
+        fs::write(dir.join(PORTS_FILE_NAME), "[]").unwrap();
+        let report = load_report(&dir).unwrap();
+        fs::remove_dir_all(&root).ok();
+        assert!(report.tables.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_page_embeds_escaped_data() {
+        let root = std::env::temp_dir().join(format!("gxr_report_page_{}", std::process::id()));
+        let report = load_report(&sample_run(&root)).unwrap();
+        fs::remove_dir_all(&root).ok();
+        let page = render_html(&report).unwrap();
+
+        // 只有模板自身的两个脚本块
+        assert_eq!(page.matches("</script>").count(), 2);
+        assert!(page.contains("<title>pentest portscan · 20240102100000-ab12</title>"));
+
+        let app = router(page, report);
+        let response = app
+            .clone()
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .oneshot(Request::get("/run.json").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let value: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(value["tables"][0]["rows"][0]["port"], 80);
+    }
+
+    #[test]
+    fn test_timeline_from_runs() {
+        let root = std::env::temp_dir().join(format!("gxr_timeline_{}", std::process::id()));
+        let ping = |id: &str, status: &str| {
+            let run = RunDir::new(&root, id, "net ping");
+            let hosts = vec![serde_json::json!({"ip": "10.0.0.5", "status": status})];
+            run.write_json(HOSTS_FILE_NAME, "json", &hosts, 1).unwrap();
+        };
+        let scan = |id: &str, ports: &[u16]| {
+            let run = RunDir::new(&root, id, "pentest portscan");
+            let rows: Vec<Value> = ports
+                .iter()
+                .map(|p| serde_json::json!({"ip": "10.0.0.5", "port": p, "status": "开放"}))
+                .collect();
+            let other = vec![serde_json::json!({"ip": "10.0.0.9", "port": 22})];
+            let rows = if rows.is_empty() { other } else { rows };
+            run.write_json(PORTS_FILE_NAME, "json", &rows, 1).unwrap();
+        };
+        ping("1-0001", "超时");
+        ping("2-0002", "成功");
+        scan("3-0003", &[22, 80]);
+        scan("4-0004", &[80, 443]);
+        // 目标范围不包含该主机的扫描不算观测
+        scan("5-0005", &[]);
+        scan("6-0006", &[]);
+        ping("7-0007", "失败");
+
+        let record = |id: &str, targets: &str| -> RunRecord {
+            serde_json::from_value(serde_json::json!({
+                "id": id, "module": "pentest portscan", "command_line": "", "targets": targets,
+                "started_at": "", "duration_ms": 0, "total": 0, "succeeded": 0, "outputs": [],
+                "exit_status": "成功", "error": null,
+            }))
+            .unwrap()
+        };
+        let records = [
+            record("5-0005", "10.0.1.0/24"),
+            record("6-0006", "10.0.0.1-10 + xlsx:hosts.xlsx"),
+        ];
+        let observations = collect_observations(&root, "10.0.0.5", &records);
+        let events = timeline_events(&observations);
+        fs::remove_dir_all(&root).ok();
+
+        assert_eq!(observations.len(), 6);
+        let summary: Vec<(&str, &str)> = events
+            .iter()
+            .map(|e| (e.event, e.detail.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("上线", "ping 成功"),
+                ("端口开放", "22"),
+                ("端口开放", "80"),
+                ("端口开放", "443"),
+                ("端口关闭", "22"),
+                ("端口关闭", "80"),
+                ("端口关闭", "443"),
+                ("离线", "ping 失败"),
+            ]
+        );
+        assert!(events[7].run.ends_with("_ping_0007"));
+    }
+
+    #[test]
+    fn test_expired_runs_by_manifest_time() {
+        let root = std::env::temp_dir().join(format!("gxr_prune_{}", std::process::id()));
+        let old = RunDir::new(&root, "1-0001", "net ping");
+        old.write_summary(&[]).unwrap();
+        let manifest = old.path().join(crate::utils::run_dir::MANIFEST_FILE_NAME);
+        let text = fs::read_to_string(&manifest).unwrap();
+        let started = RunDir::load_manifest(old.path()).unwrap().started_at;
+        fs::write(
+            &manifest,
+            text.replace(&started, "2020-01-01T00:00:00+00:00"),
+        )
+        .unwrap();
+        let new = RunDir::new(&root, "2-0002", "net ping");
+        new.write_summary(&[]).unwrap();
+        // 没有产物索引的目录不清理
+        fs::create_dir_all(root.join(RUNS_DIR_NAME).join("manual")).unwrap();
+
+        let expired = expired_runs(&root, Local::now() - chrono::Duration::days(30));
+        fs::remove_dir_all(&root).ok();
+        assert_eq!(expired, [old.path().to_path_buf()]);
+    }
+
+    #[test]
+    fn test_redact_file_to_csv_and_json() {
+        let dir = std::env::temp_dir().join(format!("gxr_report_redact_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join(PORTS_FILE_NAME);
+        fs::write(
+            &input,
+            serde_json::json!([
+                {"ip": "10.0.0.1", "port": 22, "banner": "SSH-2.0-OpenSSH_8.0", "evidence": ["x"]},
+                {"ip": "10.0.0.2", "port": 80, "aliases": ["web.corp.local"]},
+            ])
+            .to_string(),
+        )
+        .unwrap();
+        let redactor = Redactor::new("salt", false);
+        let csv = dir.join("out.csv");
+        assert_eq!(redact_file(&input, &csv, &redactor).unwrap(), 2);
+        let json = dir.join("out.json");
+        redact_file(&input, &json, &redactor).unwrap();
+        let csv = fs::read_to_string(&csv).unwrap();
+        let json: Value = serde_json::from_str(&fs::read_to_string(&json).unwrap()).unwrap();
+        let unsupported = redact_file(&input, &dir.join("out.txt"), &redactor);
+        fs::remove_dir_all(&dir).ok();
+
+        let ip = redactor.ip("10.0.0.1".parse().unwrap());
+        let alias = redactor.hostname("web.corp.local");
+        assert_eq!(
+            csv,
+            format!(
+                "\u{feff}ip,port,aliases\r\n{},22,\r\n{},80,{}\r\n",
+                ip,
+                redactor.ip("10.0.0.2".parse().unwrap()),
+                alias
+            )
+        );
+        assert_eq!(json[0], serde_json::json!({"ip": ip, "port": 22}));
+        assert!(!csv.contains("10.0.0") && !csv.contains("OpenSSH"));
+        assert!(unsupported.is_err());
+    }
+}
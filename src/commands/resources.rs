@@ -0,0 +1,771 @@
+// src/commands/resources.rs
+use crate::commands::pentest::fingerprint::Fingerprint;
+use crate::utils::config_dir;
+use crate::utils::console::Icon;
+use crate::utils::finding::Severity;
+use clap::{Parser, Subcommand};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// 资源目录名（位于配置目录下）
+pub const RESOURCES_DIR_NAME: &str = "resources";
+
+/// 漏洞验证模板所在的子目录
+pub const TEMPLATES_DIR_NAME: &str = "templates";
+
+/// 内置资源：(文件名, 内容)，模板的文件名带 `templates/` 前缀
+const EMBEDDED: &[(&str, &str)] = &[
+    (
+        "fingerprints.yaml",
+        include_str!("../../assets/resources/fingerprints.yaml"),
+    ),
+    (
+        "consoles.yaml",
+        include_str!("../../assets/resources/consoles.yaml"),
+    ),
+    (
+        "creds.yaml",
+        include_str!("../../assets/resources/creds.yaml"),
+    ),
+    (
+        "templates/git-config-exposure.yaml",
+        include_str!("../../assets/resources/templates/git-config-exposure.yaml"),
+    ),
+    (
+        "templates/spring-actuator-env.yaml",
+        include_str!("../../assets/resources/templates/spring-actuator-env.yaml"),
+    ),
+];
+
+/// 资源管理命令参数
+#[derive(Parser, Debug)]
+pub struct ResourcesArgs {
+    #[command(subcommand)]
+    pub command: ResourcesCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ResourcesCommands {
+    /// 列出生效的资源数量及来源（内置、配置目录、--resources 指定的目录）
+    #[command(name = "list")]
+    List,
+}
+
+/// 资源类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceKind {
+    /// 服务指纹（fingerprints.json / fingerprints.yaml）
+    Fingerprints,
+    /// 管理后台路径（consoles.yaml）
+    Consoles,
+    /// 默认凭据（creds.yaml）
+    Credentials,
+    /// 漏洞验证模板（templates/*.yaml，每个文件一个模板）
+    Templates,
+}
+
+impl ResourceKind {
+    /// 全部类别（按列出顺序）
+    pub const ALL: [ResourceKind; 4] = [
+        ResourceKind::Fingerprints,
+        ResourceKind::Consoles,
+        ResourceKind::Credentials,
+        ResourceKind::Templates,
+    ];
+
+    /// 中文名称
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Fingerprints => "服务指纹",
+            Self::Consoles => "管理后台路径",
+            Self::Credentials => "默认凭据",
+            Self::Templates => "漏洞验证模板",
+        }
+    }
+
+    /// 资源目录中该类别的文件名（不含扩展名）或子目录名
+    fn stem(self) -> &'static str {
+        match self {
+            Self::Fingerprints => "fingerprints",
+            Self::Consoles => "consoles",
+            Self::Credentials => "creds",
+            Self::Templates => TEMPLATES_DIR_NAME,
+        }
+    }
+
+    /// 按资源目录中的相对路径判断类别
+    fn of(name: &str) -> Option<Self> {
+        let name = name.replace('\\', "/");
+        if let Some(file) = name.strip_prefix("templates/") {
+            return (!file.contains('/') && has_known_extension(file)).then_some(Self::Templates);
+        }
+        let (stem, _) = name.rsplit_once('.')?;
+        Self::ALL
+            .into_iter()
+            .find(|k| *k != Self::Templates && k.stem() == stem && has_known_extension(&name))
+    }
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// 资源文件支持的扩展名
+const EXTENSIONS: &[&str] = &["json", "yaml", "yml"];
+
+fn has_known_extension(name: &str) -> bool {
+    name.rsplit_once('.')
+        .is_some_and(|(_, ext)| EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+/// 管理后台路径
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Console {
+    /// 名称（同名条目按优先级覆盖）
+    pub name: String,
+    /// 访问路径
+    pub paths: Vec<String>,
+    /// 常见端口（为空则不限）
+    #[serde(default)]
+    pub ports: Vec<u16>,
+    /// 确认为该后台需匹配的正文关键字
+    #[serde(default)]
+    pub match_body: Vec<String>,
+}
+
+/// 默认凭据
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Credential {
+    /// 服务（如 ssh、mysql、tomcat）
+    pub service: String,
+    /// 用户名
+    #[serde(default)]
+    pub username: String,
+    /// 密码
+    #[serde(default)]
+    pub password: String,
+}
+
+/// 漏洞验证模板（HTTP请求 + 响应匹配）
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PocTemplate {
+    /// 模板ID（同ID模板按优先级覆盖）
+    pub id: String,
+    /// 名称
+    pub name: String,
+    /// 风险等级
+    pub severity: Severity,
+    /// 适用端口（为空则不限）
+    #[serde(default)]
+    pub ports: Vec<u16>,
+    /// 请求方法
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// 请求路径
+    pub path: String,
+    /// 命中需满足的状态码（为空则不限）
+    #[serde(default)]
+    pub match_status: Vec<u16>,
+    /// 命中需全部出现的正文关键字
+    #[serde(default)]
+    pub match_body: Vec<String>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+/// 可合并的资源条目
+trait Resource: DeserializeOwned {
+    /// 合并时用于判断同一条目的键
+    fn key(&self) -> String;
+
+    /// 检查条目内容，返回问题描述
+    fn validate(&self) -> Result<(), String>;
+}
+
+fn check_path(path: &str) -> Result<(), String> {
+    if path.starts_with('/') {
+        Ok(())
+    } else {
+        Err(format!("路径必须以 / 开头: {}", path))
+    }
+}
+
+impl Resource for Fingerprint {
+    fn key(&self) -> String {
+        self.name.clone()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name 不能为空".to_string());
+        }
+        for probe in &self.probes {
+            check_path(&probe.path)?;
+            if !matches!(probe.method.as_str(), "GET" | "HEAD") {
+                return Err(format!("method 只支持 GET 或 HEAD: {}", probe.method));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Resource for Console {
+    fn key(&self) -> String {
+        self.name.clone()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name 不能为空".to_string());
+        }
+        if self.paths.is_empty() {
+            return Err(format!("{} 没有任何路径", self.name));
+        }
+        self.paths.iter().try_for_each(|p| check_path(p))
+    }
+}
+
+impl Resource for Credential {
+    fn key(&self) -> String {
+        format!(
+            "{}\u{0}{}\u{0}{}",
+            self.service, self.username, self.password
+        )
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.service.trim().is_empty() {
+            return Err("service 不能为空".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Resource for PocTemplate {
+    fn key(&self) -> String {
+        self.id.clone()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("id 不能为空".to_string());
+        }
+        check_path(&self.path)?;
+        if self.match_status.is_empty() && self.match_body.is_empty() {
+            return Err(format!(
+                "{} 没有任何匹配条件（match_status 或 match_body）",
+                self.id
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 资源文件的来源层级（后面的层级覆盖前面的）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceOrigin {
+    /// 程序内置
+    Embedded,
+    /// 配置目录下的 resources 目录
+    ConfigDir(PathBuf),
+    /// 当前目录下的 fingerprints.yaml（兼容旧版本的指纹库位置）
+    WorkingDir(PathBuf),
+    /// `--resources` 指定的目录
+    Override(PathBuf),
+}
+
+impl ResourceOrigin {
+    /// 来源名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Embedded => "内置",
+            Self::ConfigDir(_) => "配置目录",
+            Self::WorkingDir(_) => "当前目录",
+            Self::Override(_) => "--resources",
+        }
+    }
+
+    /// 来源目录（内置资源没有目录）
+    pub fn dir(&self) -> Option<&Path> {
+        match self {
+            Self::Embedded => None,
+            Self::ConfigDir(dir) | Self::WorkingDir(dir) | Self::Override(dir) => Some(dir),
+        }
+    }
+
+    /// 该层级中的资源文件：(相对路径, 完整路径)，按类别和文件名排序
+    fn files(&self) -> Vec<(String, PathBuf)> {
+        let Some(dir) = self.dir() else {
+            return Vec::new();
+        };
+        if let Self::WorkingDir(_) = self {
+            let path = dir.join("fingerprints.yaml");
+            return if path.is_file() {
+                vec![("fingerprints.yaml".to_string(), path)]
+            } else {
+                Vec::new()
+            };
+        }
+        let mut files = Vec::new();
+        for kind in ResourceKind::ALL {
+            if kind == ResourceKind::Templates {
+                let Ok(entries) = fs::read_dir(dir.join(TEMPLATES_DIR_NAME)) else {
+                    continue;
+                };
+                let mut templates: Vec<(String, PathBuf)> = entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.is_file())
+                    .filter_map(|p| {
+                        let name = format!(
+                            "{}/{}",
+                            TEMPLATES_DIR_NAME,
+                            p.file_name()?.to_string_lossy()
+                        );
+                        (ResourceKind::of(&name) == Some(kind)).then_some((name, p))
+                    })
+                    .collect();
+                templates.sort();
+                files.extend(templates);
+            } else {
+                for ext in EXTENSIONS {
+                    let name = format!("{}.{}", kind.stem(), ext);
+                    let path = dir.join(&name);
+                    if path.is_file() {
+                        files.push((name, path));
+                    }
+                }
+            }
+        }
+        files
+    }
+}
+
+/// 已加载的一个资源文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadedFile {
+    /// 资源类别
+    pub kind: ResourceKind,
+    /// 来源层级
+    pub origin: ResourceOrigin,
+    /// 显示用的位置（内置资源为文件名，其余为完整路径）
+    pub location: String,
+    /// 文件中的条目数
+    pub entries: usize,
+    /// 其中覆盖了更低层级同名条目的数量
+    pub overridden: usize,
+}
+
+/// 按键合并的条目列表：同键条目原位替换，新条目追加在后
+#[derive(Debug)]
+struct Merged<T> {
+    items: Vec<T>,
+    index: HashMap<String, usize>,
+}
+
+impl<T> Default for Merged<T> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Resource> Merged<T> {
+    /// 解析并合并一个文件
+    ///
+    /// # 返回
+    /// * `Ok((条目数, 覆盖数))`
+    /// * `Err` - `位置:行:列: 问题` 形式的错误
+    fn add(&mut self, location: &str, text: &str, single: bool) -> Result<(usize, usize), String> {
+        let items: Vec<T> = if single {
+            vec![parse(location, text)?]
+        } else {
+            parse::<Option<Vec<T>>>(location, text)?.unwrap_or_default()
+        };
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let mut overridden = 0;
+        for (i, item) in items.iter().enumerate() {
+            let key = item.key();
+            let occurrence = seen.entry(key.clone()).or_default();
+            let line = entry_line(text, &key, *occurrence);
+            *occurrence += 1;
+            let at = |message: String| match line {
+                Some(line) => format!("{}:{}: {}", location, line, message),
+                None => format!("{}: 第{}条: {}", location, i + 1, message),
+            };
+            item.validate().map_err(at)?;
+            if *occurrence > 1 {
+                return Err(at("与同一文件中的前一条目重复".to_string()));
+            }
+            if self.index.contains_key(&key) {
+                overridden += 1;
+            }
+        }
+        let count = items.len();
+        for item in items {
+            let key = item.key();
+            match self.index.get(&key) {
+                Some(&i) => self.items[i] = item,
+                None => {
+                    self.index.insert(key, self.items.len());
+                    self.items.push(item);
+                }
+            }
+        }
+        Ok((count, overridden))
+    }
+}
+
+/// 按扩展名解析JSON或YAML，错误带行列号
+fn parse<T: DeserializeOwned>(location: &str, text: &str) -> Result<T, String> {
+    if location.to_ascii_lowercase().ends_with(".json") {
+        serde_json::from_str(text).map_err(|e| {
+            let message = e.to_string();
+            let message = message
+                .split_once(" at line ")
+                .map_or(message.as_str(), |(m, _)| m);
+            format!("{}:{}:{}: {}", location, e.line(), e.column(), message)
+        })
+    } else {
+        serde_yaml::from_str(text).map_err(|e| {
+            let message = e.to_string();
+            let message = message
+                .split_once(" at line ")
+                .map_or(message.as_str(), |(m, _)| m);
+            match e.location() {
+                Some(at) => format!("{}:{}:{}: {}", location, at.line(), at.column(), message),
+                None => format!("{}: {}", location, message),
+            }
+        })
+    }
+}
+
+/// 条目所在的行号：键值第 `occurrence` 次（从0开始）出现的行（键中只取第一段，如凭据的服务名）
+fn entry_line(text: &str, key: &str, occurrence: usize) -> Option<usize> {
+    let needle = key.split('\u{0}').next().unwrap_or_default();
+    if needle.trim().is_empty() {
+        return None;
+    }
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| line.contains(needle))
+        .nth(occurrence)
+        .map(|(i, _)| i + 1)
+}
+
+/// 资源库：内置资源与资源目录中的文件按层级合并后的结果
+///
+/// 层级从低到高为：内置 → 配置目录下的 `resources/` → 当前目录的 `fingerprints.yaml`（兼容）
+/// → `--resources` 指定的目录。同名（指纹、后台按 name，模板按 id）条目由高层级覆盖，
+/// 新条目追加在后。各模块只通过 [`store`] 取用资源，合并只在加载时进行一次。
+#[derive(Debug, Default)]
+pub struct ResourceStore {
+    fingerprints: Merged<Fingerprint>,
+    consoles: Merged<Console>,
+    credentials: Merged<Credential>,
+    templates: Merged<PocTemplate>,
+    files: Vec<LoadedFile>,
+}
+
+impl ResourceStore {
+    /// 按层级加载并合并资源
+    ///
+    /// # 参数
+    /// * `origins` - 资源目录层级（从低到高，内置资源总是最先加载）
+    ///
+    /// # 返回
+    /// * `Ok(ResourceStore)` - 合并后的资源库
+    /// * `Err` - 文件无法读取或内容无效（带文件位置及行号）
+    pub fn load(origins: &[ResourceOrigin]) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut store = Self::default();
+        for (name, text) in EMBEDDED {
+            store.add(ResourceOrigin::Embedded, name, name, text)?;
+        }
+        for origin in origins {
+            for (name, path) in origin.files() {
+                let text = fs::read_to_string(&path)
+                    .map_err(|e| format!("无法读取资源文件 {}: {}", path.display(), e))?;
+                store.add(origin.clone(), &name, &path.display().to_string(), &text)?;
+            }
+        }
+        Ok(store)
+    }
+
+    fn add(
+        &mut self,
+        origin: ResourceOrigin,
+        name: &str,
+        location: &str,
+        text: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let Some(kind) = ResourceKind::of(name) else {
+            return Ok(());
+        };
+        // 扩展名决定解析格式，内置资源的位置就是文件名
+        let (entries, overridden) = match kind {
+            ResourceKind::Fingerprints => self.fingerprints.add(location, text, false),
+            ResourceKind::Consoles => self.consoles.add(location, text, false),
+            ResourceKind::Credentials => self.credentials.add(location, text, false),
+            ResourceKind::Templates => self.templates.add(location, text, true),
+        }?;
+        self.files.push(LoadedFile {
+            kind,
+            origin,
+            location: location.to_string(),
+            entries,
+            overridden,
+        });
+        Ok(())
+    }
+
+    /// 服务指纹
+    pub fn fingerprints(&self) -> &[Fingerprint] {
+        &self.fingerprints.items
+    }
+
+    /// 管理后台路径
+    pub fn consoles(&self) -> &[Console] {
+        &self.consoles.items
+    }
+
+    /// 默认凭据
+    pub fn credentials(&self) -> &[Credential] {
+        &self.credentials.items
+    }
+
+    /// 漏洞验证模板
+    pub fn templates(&self) -> &[PocTemplate] {
+        &self.templates.items
+    }
+
+    /// 某类资源合并后的条目数
+    pub fn count(&self, kind: ResourceKind) -> usize {
+        match kind {
+            ResourceKind::Fingerprints => self.fingerprints.items.len(),
+            ResourceKind::Consoles => self.consoles.items.len(),
+            ResourceKind::Credentials => self.credentials.items.len(),
+            ResourceKind::Templates => self.templates.items.len(),
+        }
+    }
+
+    /// 已加载的文件（按加载顺序，即优先级从低到高）
+    pub fn files(&self) -> &[LoadedFile] {
+        &self.files
+    }
+}
+
+static RESOURCES_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 资源库缓存：(加载时各资源文件的修改时间及大小, 资源库)
+type Cached = (Vec<(PathBuf, Option<SystemTime>, u64)>, Arc<ResourceStore>);
+
+static STORE: Mutex<Option<Cached>> = Mutex::new(None);
+
+/// 设置 `--resources` 指定的资源目录并加载资源（应在程序启动时调用）
+///
+/// 在启动时加载，资源文件有误时立即报错，而不是等到扫描中途。
+pub fn init(dir: Option<PathBuf>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if let Some(dir) = dir {
+        if !dir.is_dir() {
+            return Err(format!("资源目录不存在: {}", dir.display()).into());
+        }
+        let _ = RESOURCES_DIR.set(dir);
+    }
+    store().map(|_| ())
+}
+
+/// 当前生效的资源目录层级（从低到高，不含内置资源）
+pub fn origins() -> Vec<ResourceOrigin> {
+    let mut origins = vec![
+        ResourceOrigin::ConfigDir(config_dir().join(RESOURCES_DIR_NAME)),
+        ResourceOrigin::WorkingDir(PathBuf::from(".")),
+    ];
+    if let Some(dir) = RESOURCES_DIR.get() {
+        origins.push(ResourceOrigin::Override(dir.clone()));
+    }
+    origins
+}
+
+/// 各层级中资源文件的修改时间及大小，用于判断是否需要重新加载
+fn signature(origins: &[ResourceOrigin]) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+    origins
+        .iter()
+        .flat_map(ResourceOrigin::files)
+        .map(|(_, path)| {
+            let meta = fs::metadata(&path).ok();
+            let modified = meta.as_ref().and_then(|m| m.modified().ok());
+            let len = meta.map_or(0, |m| m.len());
+            (path, modified, len)
+        })
+        .collect()
+}
+
+/// 获取资源库
+///
+/// 资源文件增删或修改后自动重新加载（如守护进程运行期间补充指纹），
+/// 未变化时返回已合并的资源库。
+///
+/// # 返回
+/// * `Ok(Arc<ResourceStore>)` - 资源库
+/// * `Err` - 资源文件内容无效（此时不替换已加载的资源库）
+pub fn store() -> Result<Arc<ResourceStore>, Box<dyn Error + Send + Sync>> {
+    let origins = origins();
+    let signature = signature(&origins);
+    let mut cached = STORE.lock().unwrap();
+    if let Some((ref loaded, ref store)) = *cached
+        && *loaded == signature
+    {
+        return Ok(store.clone());
+    }
+    let store = Arc::new(ResourceStore::load(&origins)?);
+    *cached = Some((signature, store.clone()));
+    Ok(store)
+}
+
+/// 执行资源管理命令
+pub fn run(args: &ResourcesArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match args.command {
+        ResourcesCommands::List => {
+            let store = store()?;
+            list(&store, &origins());
+        }
+    }
+    Ok(())
+}
+
+/// 列出各类资源的数量及来源文件
+fn list(store: &ResourceStore, origins: &[ResourceOrigin]) {
+    println!("{} 资源目录（优先级从低到高）:", Icon::List);
+    println!("   内置");
+    for origin in origins {
+        let Some(dir) = origin.dir() else { continue };
+        if let ResourceOrigin::WorkingDir(_) = origin {
+            continue;
+        }
+        let state = if dir.is_dir() { "" } else { "（不存在）" };
+        println!("   {}: {}{}", origin.label(), dir.display(), state);
+    }
+    for kind in ResourceKind::ALL {
+        println!("\n{} {}: {} 条", Icon::Stats, kind, store.count(kind));
+        for file in store.files().iter().filter(|f| f.kind == kind) {
+            let overridden = if file.overridden > 0 {
+                format!("，覆盖 {} 条", file.overridden)
+            } else {
+                String::new()
+            };
+            println!(
+                "   [{}] {}: {} 条{}",
+                file.origin.label(),
+                file.location,
+                file.entries,
+                overridden
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("gxr_resources_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(TEMPLATES_DIR_NAME)).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_higher_layers_override_by_key() {
+        let dir = temp_dir("merge");
+        fs::write(
+            dir.join("consoles.yaml"),
+            "- name: Jenkins\n  paths: [/manage]\n- name: Grafana\n  paths: [/login]\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("templates/git-config-exposure.yml"),
+            "id: git-config-exposure\nname: 覆盖\nseverity: low\npath: /.git/HEAD\nmatch_status: [200]\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "不是资源文件").unwrap();
+
+        let embedded = ResourceStore::load(&[]).unwrap();
+        let store = ResourceStore::load(&[ResourceOrigin::Override(dir.clone())]).unwrap();
+        assert_eq!(
+            store.count(ResourceKind::Consoles),
+            embedded.count(ResourceKind::Consoles) + 1
+        );
+        let jenkins = store
+            .consoles()
+            .iter()
+            .find(|c| c.name == "Jenkins")
+            .unwrap();
+        assert_eq!(jenkins.paths, ["/manage"]);
+        let git = store
+            .templates()
+            .iter()
+            .find(|t| t.id == "git-config-exposure")
+            .unwrap();
+        assert_eq!((git.severity, git.method.as_str()), (Severity::Low, "GET"));
+        assert_eq!(
+            store.count(ResourceKind::Templates),
+            embedded.count(ResourceKind::Templates)
+        );
+
+        let consoles = store
+            .files()
+            .iter()
+            .rfind(|f| f.kind == ResourceKind::Consoles);
+        assert_eq!(consoles.map(|f| (f.entries, f.overridden)), Some((2, 1)));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_invalid_files_report_line() {
+        let dir = temp_dir("invalid");
+        let path = dir.join("creds.yaml");
+        fs::write(&path, "- service: ssh\n  username: root\n  pasword: root\n").unwrap();
+        let err = ResourceStore::load(&[ResourceOrigin::Override(dir.clone())])
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with(&format!("{}:3:", path.display())),
+            "{}",
+            err
+        );
+        assert!(err.contains("pasword"), "{}", err);
+
+        fs::write(
+            &path,
+            "- service: ssh\n  username: root\n- service: ftp\n- service: ssh\n  username: root\n",
+        )
+        .unwrap();
+        let err = ResourceStore::load(&[ResourceOrigin::Override(dir.clone())])
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with(&format!("{}:4: ", path.display())),
+            "{}",
+            err
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
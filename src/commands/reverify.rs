@@ -0,0 +1,592 @@
+// src/commands/reverify.rs
+use crate::commands::pentest::adinfo::{SigningStatus, adinfo_findings, check_controller};
+use crate::commands::pentest::mail::{RelayProbe, RelayVerdict, check_service, mail_findings};
+use crate::utils::ExcelExport;
+use crate::utils::blocking::run_stage;
+use crate::utils::console::Icon;
+use crate::utils::context::{self, ScanContext};
+use crate::utils::finding::{Finding, Severity, VmFinding, params_cell};
+use crate::utils::limits::{
+    ConcurrencySpec, ScanKind, effective_concurrency, parse_fixed_concurrency,
+};
+use crate::utils::output::OutputKind;
+use crate::utils::pool::run_tracked;
+use crate::utils::provenance::{Provenance, describe_staleness};
+use crate::utils::run_dir::FINDINGS_FILE_NAME;
+use crate::utils::scope;
+use crate::utils::stats::Outcome;
+use calamine::{Reader, open_workbook_auto};
+use clap::Args;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+/// 复查参数配置
+#[derive(Args, Debug)]
+pub struct ReverifyArgs {
+    /// 之前导出的发现列表（vm-json、运行目录下的 findings.json 或其Excel版本），也可直接指定运行目录
+    #[arg(long, value_name = "FILE")]
+    pub from: PathBuf,
+
+    /// 只复查这些风险等级的发现（多个用逗号隔开，如 high,medium）
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "SEVERITY")]
+    pub only: Vec<Severity>,
+
+    /// 最大并发数（auto 表示根据文件描述符上限和任务数量自动选择）
+    #[arg(
+        short = 'c',
+        long,
+        env = "GXTOOLS_CONCURRENCY",
+        default_value = "20",
+        value_name = "NUM|auto",
+        value_parser = parse_fixed_concurrency
+    )]
+    pub concurrency: ConcurrencySpec,
+
+    /// 连接及每条应答的超时时间（秒）
+    #[arg(
+        short = 'T',
+        long,
+        env = "GXTOOLS_TIMEOUT",
+        default_value = "5",
+        value_name = "SECS"
+    )]
+    pub timeout: u64,
+
+    /// 是否输出复查报告到Excel文件
+    #[arg(short = 'o', long, env = "GXTOOLS_OUTPUT")]
+    pub output: bool,
+}
+
+/// 复查结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verdict {
+    /// 问题已不存在
+    Fixed,
+    /// 问题仍然存在
+    NotFixed,
+    /// 无法重新执行检查（主机不可达、检查中断、旧版本导出缺少检查项等）
+    Unverifiable,
+}
+
+impl Verdict {
+    /// 中文名称
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Fixed => "已修复",
+            Self::NotFixed => "未修复",
+            Self::Unverifiable => "无法验证",
+        }
+    }
+
+    /// 计入复查统计的结论：得出是否修复为成功，无法验证为出错
+    pub fn outcome(self) -> Outcome {
+        match self {
+            Self::Fixed | Self::NotFixed => Outcome::Succeeded,
+            Self::Unverifiable => Outcome::Errored,
+        }
+    }
+}
+
+/// 单条发现的复查结果
+#[derive(Debug, Clone)]
+pub struct Reverification {
+    /// 原发现
+    pub finding: VmFinding,
+    /// 结论
+    pub verdict: Verdict,
+    /// 复查得到的证据
+    pub evidence: String,
+    /// 复查时间（RFC3339）
+    pub checked_at: String,
+}
+
+/// 读取之前导出的发现列表
+///
+/// 支持 JSON（vm-json、运行目录下的 findings.json）及由其转换的Excel（首行为字段名）；
+/// 指定目录时读取其中的 findings.json。
+///
+/// # 参数
+/// * `path` - 发现列表文件或运行目录
+pub fn load_findings(path: &Path) -> Result<Vec<VmFinding>, Box<dyn Error + Send + Sync>> {
+    let path = if path.is_dir() {
+        path.join(FINDINGS_FILE_NAME)
+    } else {
+        path.to_path_buf()
+    };
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let rows = match extension.as_str() {
+        "json" => {
+            let text = fs::read_to_string(&path)
+                .map_err(|e| format!("无法读取发现列表 {}: {}", path.display(), e))?;
+            match serde_json::from_str(&text)
+                .map_err(|e| format!("发现列表 {} 不是有效的JSON: {}", path.display(), e))?
+            {
+                Value::Array(rows) => rows,
+                row => vec![row],
+            }
+        }
+        "xlsx" | "xls" => read_xlsx_rows(&path)?,
+        _ => {
+            return Err(format!(
+                "不支持的发现列表格式: {}（可用 json、xlsx）",
+                path.display()
+            )
+            .into());
+        }
+    };
+    rows.into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            serde_json::from_value(row)
+                .map_err(|e| format!("第 {} 条发现格式不正确: {}", i + 1, e).into())
+        })
+        .collect()
+}
+
+/// 按首行字段名把Excel中的发现读为JSON对象
+fn read_xlsx_rows(path: &Path) -> Result<Vec<Value>, Box<dyn Error + Send + Sync>> {
+    let mut workbook = open_workbook_auto(path)
+        .map_err(|e| format!("无法打开Excel文件 {}: {}", path.display(), e))?;
+    let sheet = workbook
+        .sheet_names()
+        .first()
+        .cloned()
+        .ok_or_else(|| format!("Excel文件 {} 中没有工作表", path.display()))?;
+    let range = workbook
+        .worksheet_range(&sheet)
+        .map_err(|e| format!("无法读取工作表 {}: {}", sheet, e))?;
+    let mut rows = range.rows();
+    let headers: Vec<String> = rows
+        .next()
+        .map(|row| {
+            row.iter()
+                .map(|c| c.to_string().trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut out = Vec::new();
+    for row in rows {
+        let mut object = Map::new();
+        for (header, cell) in headers.iter().zip(row) {
+            let text = cell.to_string().trim().to_string();
+            let value = match header.as_str() {
+                "port" => text.parse::<u16>().map_or(Value::Null, Value::from),
+                "params" if text.is_empty() => Value::Object(Map::new()),
+                "params" => serde_json::from_str(&text)
+                    .map_err(|_| format!("params 列不是有效的JSON: {}", text))?,
+                _ => Value::String(text),
+            };
+            object.insert(header.clone(), value);
+        }
+        if object
+            .values()
+            .any(|v| v.as_str().is_some_and(|s| !s.is_empty()))
+        {
+            out.push(Value::Object(object));
+        }
+    }
+    Ok(out)
+}
+
+/// 端口的连通情况
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PortState {
+    /// 可以建立连接
+    Open,
+    /// 连接被拒绝
+    Closed,
+    /// 超时或网络不可达，无法区分端口被过滤与主机离线
+    Unreachable(String),
+}
+
+async fn probe_port(asset: &str, port: u16, io_timeout: Duration) -> PortState {
+    match timeout(io_timeout, TcpStream::connect((asset, port))).await {
+        Ok(Ok(_)) => PortState::Open,
+        Ok(Err(e)) if e.kind() == io::ErrorKind::ConnectionRefused => PortState::Closed,
+        Ok(Err(e)) => PortState::Unreachable(format!("连接失败: {}", e)),
+        Err(_) => PortState::Unreachable("连接超时（端口被过滤或主机不可达）".to_string()),
+    }
+}
+
+/// 按复查时重新得到的发现判定结论
+///
+/// # 参数
+/// * `check_id` - 原发现的检查项
+/// * `fresh` - 复查得到的发现
+/// * `error` - 检查中断的原因
+/// * `fixed` - 判定为已修复时的证据
+fn judge(
+    check_id: &str,
+    fresh: &[Finding],
+    error: Option<&str>,
+    fixed: String,
+) -> (Verdict, String) {
+    if let Some(f) = fresh.iter().find(|f| f.check_id == check_id) {
+        let evidence = if f.evidence.is_empty() {
+            "复查仍发现该问题".to_string()
+        } else {
+            f.evidence.clone()
+        };
+        return (Verdict::NotFixed, evidence);
+    }
+    match error {
+        Some(e) => (Verdict::Unverifiable, format!("检查中断: {}", e)),
+        None => (Verdict::Fixed, fixed),
+    }
+}
+
+/// 重新执行邮件服务检查
+async fn reverify_mail(finding: &VmFinding, port: u16, io_timeout: Duration) -> (Verdict, String) {
+    let probe = RelayProbe::from_params(&finding.params);
+    let relay = (finding.check_id == "smtp-open-relay").then_some(&probe);
+    let Some(service) = check_service(&finding.asset, port, relay, io_timeout).await else {
+        return (
+            Verdict::Unverifiable,
+            format!("端口 {} 不是已知的邮件服务端口或未连通", port),
+        );
+    };
+    let fresh = mail_findings(std::slice::from_ref(&service), relay);
+    let fixed = match finding.check_id.as_str() {
+        "smtp-open-relay" => match service.relay {
+            Some(ref r) if r.verdict == RelayVerdict::Inconclusive => {
+                return (
+                    Verdict::Unverifiable,
+                    format!("中继测试无法判断: {}", r.evidence),
+                );
+            }
+            Some(ref r) => r.evidence.clone(),
+            None => String::new(),
+        },
+        "mail-no-starttls" => format!("已提供STARTTLS: {}", service.capabilities.join("; ")),
+        "mail-plaintext-auth" => "加密前不再允许明文认证".to_string(),
+        _ => service.banner.clone(),
+    };
+    judge(&finding.check_id, &fresh, service.error.as_deref(), fixed)
+}
+
+/// 重新执行LDAP检查
+async fn reverify_ldap(finding: &VmFinding, port: u16, io_timeout: Duration) -> (Verdict, String) {
+    let signing = finding.check_id == "ldap-signing-not-required";
+    let dc = check_controller(&finding.asset, port, None, None, 1, signing, io_timeout).await;
+    if signing && dc.error.is_none() && dc.signing == Some(SigningStatus::Unknown) {
+        return (
+            Verdict::Unverifiable,
+            format!("无法判断签名要求: {}", dc.signing_evidence),
+        );
+    }
+    let fresh = adinfo_findings(std::slice::from_ref(&dc));
+    let fixed = match finding.check_id.as_str() {
+        "ldap-anonymous-read" => format!("匿名查询: {}", dc.anonymous_result),
+        "ldap-signing-not-required" => format!("简单绑定应答: {}", dc.signing_evidence),
+        _ => String::new(),
+    };
+    judge(&finding.check_id, &fresh, dc.error.as_deref(), fixed)
+}
+
+/// 重新执行产生该发现的检查
+///
+/// 端口已关闭时直接判定为已修复；超时或不可达时无法区分端口被过滤与主机离线，判定为无法验证。
+///
+/// # 参数
+/// * `finding` - 原发现
+/// * `io_timeout` - 连接及每条应答的超时
+///
+/// # 返回
+/// * `(结论, 复查证据)`
+pub async fn reverify_finding(finding: &VmFinding, io_timeout: Duration) -> (Verdict, String) {
+    if finding.check_id.is_empty() {
+        return (
+            Verdict::Unverifiable,
+            "发现缺少检查项（早期版本导出），无法重新执行检查".to_string(),
+        );
+    }
+    let Some(port) = finding.port else {
+        return (Verdict::Unverifiable, "主机级发现不支持复查".to_string());
+    };
+    match probe_port(&finding.asset, port, io_timeout).await {
+        PortState::Open => {}
+        PortState::Closed => {
+            return (
+                Verdict::Fixed,
+                format!("端口 {} 已关闭（连接被拒绝）", port),
+            );
+        }
+        PortState::Unreachable(reason) => return (Verdict::Unverifiable, reason),
+    }
+    match finding.check_id.as_str() {
+        "open-port" => (Verdict::NotFixed, format!("端口 {} 仍开放", port)),
+        "mail-service" | "smtp-open-relay" | "mail-no-starttls" | "mail-plaintext-auth" => {
+            reverify_mail(finding, port, io_timeout).await
+        }
+        "ldap-rootdse" | "ldap-anonymous-read" | "ldap-signing-not-required" => {
+            reverify_ldap(finding, port, io_timeout).await
+        }
+        other => (
+            Verdict::Unverifiable,
+            format!("检查项 {} 不支持复查", other),
+        ),
+    }
+}
+
+/// 复查之前报告中的发现，逐条给出 已修复/未修复/无法验证 的结论
+///
+/// # 参数
+/// * `args` - 复查参数
+pub async fn run(args: &ReverifyArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let loaded = load_findings(&args.from)?;
+    let mut findings: Vec<VmFinding> = loaded
+        .iter()
+        .filter(|f| args.only.is_empty() || args.only.contains(&f.severity))
+        .cloned()
+        .collect();
+    if findings.is_empty() {
+        println!("📭 {} 中没有需要复查的发现", args.from.display());
+        return Ok(());
+    }
+    println!(
+        "{} 从 {} 读取 {} 条发现，复查其中 {} 条",
+        Icon::List,
+        args.from.display(),
+        loaded.len(),
+        findings.len()
+    );
+    let observed = findings.iter().map(|f| f.observed.observed_at.as_str());
+    if let Some(staleness) = describe_staleness(observed, chrono::Local::now()) {
+        println!("{} 原发现{}", Icon::Config, staleness);
+    }
+    let legacy = findings.iter().filter(|f| f.check_id.is_empty()).count();
+    if legacy > 0 {
+        println!(
+            "{} {} 条发现缺少检查项（早期版本导出），将记为无法验证",
+            Icon::Warn,
+            legacy
+        );
+    }
+
+    let assets: Vec<String> = findings
+        .iter()
+        .map(|f| f.asset.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let excluded = scope::enforce_ips(&assets)?;
+    findings.retain(|f| !excluded.contains(&f.asset));
+    if findings.is_empty() {
+        println!("📭 授权范围内没有需要复查的发现");
+        return Ok(());
+    }
+
+    let total = findings.len();
+    let concurrency = effective_concurrency(args.concurrency, total, ScanKind::Connect);
+    let io_timeout = Duration::from_secs(args.timeout.max(1));
+    println!(
+        "{} 配置: 并发={}（{}）, 超时={}秒",
+        Icon::Config,
+        concurrency.value,
+        concurrency.reason,
+        args.timeout
+    );
+
+    context::watch_interrupt();
+    let ctx = ScanContext::cli();
+    let progress = ctx.new_progress(total as u64);
+    let mut results: Vec<(usize, Reverification)> = Vec::new();
+    run_tracked(
+        findings.into_iter().enumerate(),
+        concurrency.value,
+        &progress,
+        ctx.token(),
+        |(i, finding)| async move {
+            let (verdict, evidence) = reverify_finding(&finding, io_timeout).await;
+            let checked_at = chrono::Local::now().to_rfc3339();
+            (
+                i,
+                Reverification {
+                    finding,
+                    verdict,
+                    evidence,
+                    checked_at,
+                },
+            )
+        },
+        |(i, r)| {
+            let outcome = r.verdict.outcome();
+            results.push((i, r));
+            outcome
+        },
+    )
+    .await;
+    progress.finish_with_message(format!("{} 复查完成", Icon::Ok));
+    results.sort_by_key(|(i, _)| *i);
+    let results: Vec<Reverification> = results.into_iter().map(|(_, r)| r).collect();
+    // 先导出再逐条打印，打印出错时结果已经保存
+    if args.output {
+        run_stage(export_results(&results)).await?;
+    }
+
+    // 按原报告的顺序逐条列出
+    for r in &results {
+        let icon = match r.verdict {
+            Verdict::Fixed => Icon::Ok,
+            Verdict::NotFixed => Icon::Fail,
+            Verdict::Unverifiable => Icon::Warn,
+        };
+        println!(
+            "   {} [{}] {}:{} {} | {}",
+            icon,
+            r.verdict.label(),
+            r.finding.asset,
+            r.finding.port.unwrap_or_default(),
+            r.finding.title,
+            r.evidence
+        );
+    }
+
+    let count = |verdict: Verdict| results.iter().filter(|r| r.verdict == verdict).count();
+    println!("\n{} 复查统计:", Icon::Stats);
+    for (name, value) in progress.snapshot().summary_items("条发现") {
+        println!("   {}: {}", name, value);
+    }
+    for verdict in [Verdict::Fixed, Verdict::NotFixed, Verdict::Unverifiable] {
+        println!("   {}: {} 条", verdict.label(), count(verdict));
+    }
+    Ok(())
+}
+
+/// 导出复查报告到Excel
+fn export_results(results: &[Reverification]) -> ExcelExport {
+    let headers = [
+        "发现ID",
+        "资产",
+        "端口",
+        "检查项",
+        "标题",
+        "风险等级",
+        "复查结论",
+        "复查证据",
+        "原证据",
+        "首次发现时间",
+        "复查时间",
+        "模块",
+        "检查参数",
+        Provenance::HEADERS[0],
+        Provenance::HEADERS[1],
+    ];
+    ExcelExport::prepare(
+        results,
+        &headers,
+        |r| {
+            let f = &r.finding;
+            vec![
+                f.id.clone(),
+                f.asset.clone(),
+                f.port.map(|p| p.to_string()).unwrap_or_default(),
+                f.check_id.clone(),
+                f.title.clone(),
+                f.severity.label().to_string(),
+                r.verdict.label().to_string(),
+                r.evidence.clone(),
+                f.evidence.clone(),
+                f.first_seen.clone(),
+                r.checked_at.clone(),
+                f.module.clone(),
+                params_cell(&f.params),
+                f.observed.observed_at.clone(),
+                f.observed.probe.clone(),
+            ]
+        },
+        OutputKind::REVERIFY,
+        &ScanContext::cli().excel_options(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::finding::FindingParams;
+    use tokio::net::TcpListener;
+
+    fn finding(asset: &str, port: Option<u16>, check_id: &str) -> VmFinding {
+        VmFinding {
+            id: "0011223344556677".to_string(),
+            asset: asset.to_string(),
+            port,
+            protocol: "tcp".to_string(),
+            title: "测试发现".to_string(),
+            severity: Severity::High,
+            cvss_range: "7.0-8.9".to_string(),
+            evidence: String::new(),
+            remediation: String::new(),
+            first_seen: "2024-01-02T10:00:00+08:00".to_string(),
+            module: "pentest portscan".to_string(),
+            check_id: check_id.to_string(),
+            params: FindingParams::new(),
+            sources: Vec::new(),
+            observed: Provenance::default(),
+        }
+    }
+
+    #[test]
+    fn test_load_legacy_and_current_findings() {
+        let path = std::env::temp_dir().join(format!("gxr_reverify_{}.json", std::process::id()));
+        // 早期版本导出的发现没有 module、check_id、params
+        let legacy = r#"[{"id":"a1","asset":"10.0.0.1","port":25,"protocol":"tcp",
+            "title":"SMTP 疑似开放中继","severity":"high","cvss_range":"7.0-8.9",
+            "evidence":"","remediation":"","first_seen":"2024-01-02T10:00:00+08:00"}]"#;
+        fs::write(&path, legacy).unwrap();
+        let loaded = load_findings(&path).unwrap();
+        assert_eq!(loaded[0].port, Some(25));
+        assert!(loaded[0].check_id.is_empty() && loaded[0].params.is_empty());
+
+        let mut current = finding("10.0.0.1", Some(25), "smtp-open-relay");
+        current.params = RelayProbe {
+            from: "a@example.com".to_string(),
+            to: "b@example.net".to_string(),
+        }
+        .params();
+        fs::write(&path, serde_json::to_string(&[&current]).unwrap()).unwrap();
+        let loaded = load_findings(&path).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(loaded, vec![current]);
+        assert_eq!(
+            RelayProbe::from_params(&loaded[0].params).to,
+            "b@example.net"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reverify_open_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap().port();
+        let closed = {
+            let l = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            l.local_addr().unwrap().port()
+        };
+        let wait = Duration::from_secs(1);
+
+        let (verdict, _) =
+            reverify_finding(&finding("127.0.0.1", Some(open), "open-port"), wait).await;
+        assert_eq!(verdict, Verdict::NotFixed);
+        let (verdict, evidence) =
+            reverify_finding(&finding("127.0.0.1", Some(closed), "open-port"), wait).await;
+        assert_eq!(verdict, Verdict::Fixed);
+        assert!(evidence.contains("已关闭"));
+        // 旧版本导出缺少检查项、主机级发现均无法验证
+        let (verdict, _) = reverify_finding(&finding("127.0.0.1", Some(open), ""), wait).await;
+        assert_eq!(verdict, Verdict::Unverifiable);
+        let (verdict, _) =
+            reverify_finding(&finding("127.0.0.1", None, "suspected-honeypot"), wait).await;
+        assert_eq!(verdict, Verdict::Unverifiable);
+    }
+}
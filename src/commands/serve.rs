@@ -0,0 +1,1091 @@
+// src/commands/serve.rs
+use crate::commands::history::RunSummary;
+use crate::commands::net::ping::{self, PingArgs};
+use crate::commands::pentest::portscan::{self, PortScanArgs};
+use crate::commands::profile::{self, ProfileOptions};
+use crate::utils::context::ScanContext;
+use crate::utils::output_root;
+use crate::utils::run_dir::RunDir;
+use crate::utils::secret::Secret;
+use axum::body::Body;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Path, Request, State};
+use axum::http::{StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use chrono::Local;
+use clap::Parser;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::error::Error;
+use std::net::SocketAddr;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::sync::{Semaphore, mpsc, watch};
+
+/// 默认监听地址（仅本机可访问）
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8321";
+
+/// 通过接口提交扫描时允许使用的选项
+///
+/// 只开放决定扫描目标、探测方式和输出内容的选项；读写本机文件、读取标准输入
+/// 或需要终端的选项（如 --target-file、--export-plan、--record、--script、--tui）一律拒绝。
+const ALLOWED_OPTIONS: &[&str] = &[
+    // 目标
+    "target",
+    "targets",
+    "exclude",
+    "tag",
+    "ip-version",
+    "no-resolve",
+    "max-targets",
+    "limit",
+    "sample",
+    "sample-seed",
+    "randomize",
+    "seed",
+    // 探测
+    "timeout",
+    "concurrency",
+    "min",
+    "max",
+    "timing",
+    "max-rate",
+    "max-host-parallelism",
+    "scan-delay",
+    "retries",
+    "count",
+    "engine",
+    "method",
+    "tcp-ports",
+    "stats",
+    "ports",
+    "full",
+    "timeout-first",
+    "timeout-retry",
+    "sources",
+    "knock",
+    "knock-delay",
+    "probes",
+    "live",
+    "detect-honeypot",
+    "os-guess",
+    "resolve",
+    "resolve-timeout",
+    "verify",
+    "verify-only",
+    "verify-attempts",
+    "verify-timeout-factor",
+    "verify-sparse-ports",
+    // 输出（写入任务的运行目录）
+    "output",
+    "echo",
+    "show-failed",
+    "split-sheets",
+    "format",
+    "dry-run",
+    "profile",
+];
+
+/// 守护进程参数配置
+#[derive(Parser, Debug)]
+pub struct ServeArgs {
+    /// 监听地址
+    #[arg(
+        long,
+        env = "GXTOOLS_LISTEN",
+        default_value = DEFAULT_LISTEN,
+        value_name = "ADDR"
+    )]
+    pub listen: SocketAddr,
+
+    /// 访问令牌，客户端需携带 `Authorization: Bearer <令牌>` 请求头（未设置时启动时随机生成并显示）
+    #[arg(
+        long,
+        env = "GXTOOLS_TOKEN",
+        hide_env_values = true,
+        value_name = "TOKEN"
+    )]
+    pub token: Option<Secret>,
+
+    /// 同时运行的最大扫描数，超出的扫描排队等待
+    #[arg(
+        long,
+        env = "GXTOOLS_MAX_SCANS",
+        default_value = "2",
+        value_name = "NUM",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub max_scans: u32,
+}
+
+/// 可通过接口运行的扫描模块
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanModule {
+    /// 对应 `net ping`
+    Ping,
+    /// 对应 `pentest portscan`
+    Portscan,
+}
+
+impl ScanModule {
+    /// 对应的命令名称（与历史记录中的模块名一致）
+    fn command_name(self) -> &'static str {
+        match self {
+            ScanModule::Ping => "net ping",
+            ScanModule::Portscan => "pentest portscan",
+        }
+    }
+}
+
+/// 提交扫描的请求体
+///
+/// `options` 的键为命令行长参数名（`-` 可写作 `_`），值与命令行取值一致：
+/// 布尔值 `true` 表示开关参数，数组表示重复传入该参数。
+#[derive(Debug, Deserialize)]
+pub struct ScanRequest {
+    pub module: ScanModule,
+    #[serde(default)]
+    pub options: Map<String, Value>,
+}
+
+/// 解析后的扫描任务参数
+#[derive(Debug)]
+enum ScanJob {
+    Ping(PingArgs),
+    Portscan(PortScanArgs),
+}
+
+/// 扫描任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// 等待空闲的扫描槽位
+    Queued,
+    /// 正在运行
+    Running,
+    /// 已完成
+    Completed,
+    /// 执行失败
+    Failed,
+    /// 已取消
+    Cancelled,
+}
+
+impl JobStatus {
+    /// 是否已结束（不会再产生新结果）
+    fn is_finished(self) -> bool {
+        !matches!(self, Self::Queued | Self::Running)
+    }
+}
+
+/// 任务的可变状态
+#[derive(Debug, Clone, Serialize)]
+struct JobState {
+    status: JobStatus,
+    started_at: Option<String>,
+    finished_at: Option<String>,
+    summary: Option<RunSummary>,
+    error: Option<String>,
+}
+
+/// 一次通过接口提交的扫描
+struct Job {
+    id: String,
+    module: ScanModule,
+    created_at: String,
+    ctx: ScanContext,
+    state: Mutex<JobState>,
+    results: Mutex<Vec<Value>>,
+    /// 每有新结果或状态结束时递增，用于唤醒结果流
+    updates: watch::Sender<u64>,
+}
+
+impl Job {
+    fn status(&self) -> JobStatus {
+        self.state.lock().unwrap().status
+    }
+
+    fn push_result(&self, value: Value) {
+        self.results.lock().unwrap().push(value);
+        self.updates.send_modify(|n| *n += 1);
+    }
+
+    fn start(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.status = JobStatus::Running;
+        state.started_at = Some(Local::now().to_rfc3339());
+    }
+
+    fn finish(&self, status: JobStatus, summary: Option<RunSummary>, error: Option<String>) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.status = status;
+            state.finished_at = Some(Local::now().to_rfc3339());
+            state.summary = summary;
+            state.error = error;
+        }
+        self.updates.send_modify(|n| *n += 1);
+    }
+
+    /// 任务状态的JSON描述
+    fn describe(&self) -> Value {
+        let state = self.state.lock().unwrap().clone();
+        let (completed, total) = self.ctx.progress();
+        json!({
+            "id": self.id,
+            "module": self.module,
+            "status": state.status,
+            "created_at": self.created_at,
+            "started_at": state.started_at,
+            "finished_at": state.finished_at,
+            "progress": { "completed": completed, "total": total },
+            "results": self.results.lock().unwrap().len(),
+            "summary": state.summary,
+            "error": state.error,
+            "run_dir": self.ctx.run_dir().filter(|r| r.exists()).map(|r| r.path().display().to_string()),
+        })
+    }
+}
+
+/// 服务共享状态
+pub struct ServerState {
+    listen: SocketAddr,
+    token: Secret,
+    scans: Arc<Semaphore>,
+    next_id: AtomicU64,
+    jobs: Mutex<Vec<Arc<Job>>>,
+    output_root: PathBuf,
+}
+
+impl ServerState {
+    /// 创建服务状态
+    ///
+    /// # 参数
+    /// * `listen` - 监听地址（监听本机地址时只接受 Host 为本机的请求）
+    /// * `token` - 访问令牌
+    /// * `max_scans` - 同时运行的最大扫描数
+    /// * `output_root` - 扫描任务运行目录所在的输出根目录
+    pub fn new(
+        listen: SocketAddr,
+        token: Secret,
+        max_scans: usize,
+        output_root: &FsPath,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            listen,
+            token,
+            scans: Arc::new(Semaphore::new(max_scans.max(1))),
+            next_id: AtomicU64::new(1),
+            jobs: Mutex::new(Vec::new()),
+            output_root: output_root.to_path_buf(),
+        })
+    }
+
+    fn find(&self, id: &str) -> Option<Arc<Job>> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|j| j.id == id)
+            .cloned()
+    }
+}
+
+/// 启动扫描守护进程
+///
+/// # 参数
+/// * `args` - 守护进程参数
+///
+/// # 返回
+/// * `Ok(())` - 收到 Ctrl+C 后正常退出
+/// * `Err` - 监听失败
+pub async fn run(args: &ServeArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let listener = TcpListener::bind(args.listen)
+        .await
+        .map_err(|e| format!("无法监听 {}: {}", args.listen, e))?;
+    let (token, generated) = match args.token {
+        Some(ref token) => (token.clone(), false),
+        None => (generate_token()?, true),
+    };
+    let state = ServerState::new(
+        args.listen,
+        token.clone(),
+        args.max_scans as usize,
+        output_root(),
+    );
+
+    println!("🌐 扫描服务已启动: http://{}", args.listen);
+    println!("   接口说明: http://{}/openapi.json", args.listen);
+    println!("⚙️  配置: 最大并行扫描={}", args.max_scans);
+    if generated {
+        println!(
+            "🔑 未指定 --token，本次启动生成的访问令牌: {}",
+            token.expose()
+        );
+    }
+
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+
+    println!("👋 扫描服务已停止");
+    Ok(())
+}
+
+/// 随机生成访问令牌（32字节，十六进制）
+fn generate_token() -> Result<Secret, Box<dyn Error + Send + Sync>> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("无法生成访问令牌: {}", e))?;
+    Ok(Secret::new(hex::encode(bytes)))
+}
+
+/// 构建接口路由
+pub fn router(state: Arc<ServerState>) -> Router {
+    let api = Router::new()
+        .route("/scans", get(list_scans).post(create_scan))
+        .route("/scans/{id}", get(get_scan).delete(delete_scan))
+        .route("/scans/{id}/results", get(stream_results))
+        .route("/scans/{id}/hosts/{host}", delete(cancel_host))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token));
+
+    Router::new()
+        .route("/openapi.json", get(openapi))
+        .merge(api)
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_local_origin,
+        ))
+        .with_state(state)
+}
+
+/// 拒绝来自浏览器跨站页面或DNS重绑定的请求
+///
+/// `Origin`（浏览器发起的请求才带）必须是本机地址；监听本机地址时 `Host` 也必须是本机地址，
+/// 否则恶意网页可以借用户的浏览器访问本机接口。
+async fn require_local_origin(
+    State(state): State<Arc<ServerState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let headers = request.headers();
+    if let Some(origin) = headers.get(header::ORIGIN) {
+        let local = origin
+            .to_str()
+            .ok()
+            .and_then(|o| o.split_once("://"))
+            .is_some_and(|(_, authority)| is_local_host(authority));
+        if !local {
+            return error_response(StatusCode::FORBIDDEN, "不接受来自非本机页面的请求");
+        }
+    }
+    let host = headers.get(header::HOST);
+    if state.listen.ip().is_loopback() && host.is_some_and(|h| !h.to_str().is_ok_and(is_local_host))
+    {
+        return error_response(StatusCode::FORBIDDEN, "请求的 Host 不是本机地址");
+    }
+    next.run(request).await
+}
+
+/// 主机（可带端口）是否为本机地址
+fn is_local_host(authority: &str) -> bool {
+    let host = match authority.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// 校验 Bearer 令牌
+async fn require_token(
+    State(state): State<Arc<ServerState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if !provided.is_some_and(|p| constant_time_eq(p.as_bytes(), state.token.expose().as_bytes())) {
+        return error_response(StatusCode::UNAUTHORIZED, "缺少或错误的访问令牌");
+    }
+    next.run(request).await
+}
+
+/// 比较令牌（耗时与内容无关）
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(json!({ "error": message.into() }))).into_response()
+}
+
+/// POST /scans（请求体必须为 `application/json`）
+async fn create_scan(
+    State(state): State<Arc<ServerState>>,
+    body: Result<Json<ScanRequest>, JsonRejection>,
+) -> Response {
+    let request = match body {
+        Ok(Json(r)) => r,
+        Err(e) => return error_response(e.status(), format!("请求体无效: {}", e.body_text())),
+    };
+    let scan = match parse_scan(&request) {
+        Ok(scan) => scan,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e),
+    };
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let id = format!("scan-{}", state.next_id.fetch_add(1, Ordering::SeqCst));
+    let run_dir = RunDir::allocate_in(&state.output_root, &id, request.module.command_name());
+    let job = Arc::new(Job {
+        id,
+        module: request.module,
+        created_at: Local::now().to_rfc3339(),
+        ctx: ScanContext::background(tx).with_run_dir(run_dir),
+        state: Mutex::new(JobState {
+            status: JobStatus::Queued,
+            started_at: None,
+            finished_at: None,
+            summary: None,
+            error: None,
+        }),
+        results: Mutex::new(Vec::new()),
+        updates: watch::channel(0).0,
+    });
+    state.jobs.lock().unwrap().push(job.clone());
+
+    tokio::spawn(execute(job.clone(), scan, rx, state.scans.clone()));
+
+    (StatusCode::ACCEPTED, Json(job.describe())).into_response()
+}
+
+/// 等待空闲的扫描槽位后执行任务
+async fn execute(
+    job: Arc<Job>,
+    scan: ScanJob,
+    rx: mpsc::UnboundedReceiver<Value>,
+    scans: Arc<Semaphore>,
+) {
+    let Ok(_permit) = scans.acquire_owned().await else {
+        return;
+    };
+    // 排队期间已被取消
+    if job.status() != JobStatus::Queued {
+        return;
+    }
+    job.start();
+
+    // 扫描函数的 future 中有借用参数的闭包，编译器无法证明其满足 Send，
+    // 因此在阻塞线程上驱动（线程数受最大并行扫描数限制）
+    let handle = tokio::runtime::Handle::current();
+    let worker = job.clone();
+    let joined =
+        tokio::task::spawn_blocking(move || handle.block_on(run_job(&worker, scan, rx))).await;
+    if let Err(e) = joined {
+        job.finish(
+            JobStatus::Failed,
+            None,
+            Some(format!("扫描任务异常退出: {}", e)),
+        );
+    }
+}
+
+/// 运行扫描，并把推送的结果收集到任务中
+async fn run_job(job: &Job, scan: ScanJob, mut rx: mpsc::UnboundedReceiver<Value>) {
+    let ctx = job.ctx.clone();
+    let outcome = {
+        let run = async {
+            match scan {
+                ScanJob::Ping(ref args) => ping::run_with(args, &ctx).await,
+                ScanJob::Portscan(ref args) => portscan::run_with(args, &ctx).await,
+            }
+        };
+        tokio::pin!(run);
+        loop {
+            tokio::select! {
+                outcome = &mut run => break outcome,
+                Some(value) = rx.recv() => job.push_result(value),
+            }
+        }
+    };
+    while let Ok(value) = rx.try_recv() {
+        job.push_result(value);
+    }
+
+    match outcome {
+        Ok(summary) if ctx.is_cancelled() => job.finish(JobStatus::Cancelled, Some(summary), None),
+        Ok(summary) => job.finish(JobStatus::Completed, Some(summary), None),
+        Err(e) => job.finish(JobStatus::Failed, None, Some(e.to_string())),
+    }
+}
+
+/// GET /scans
+async fn list_scans(State(state): State<Arc<ServerState>>) -> Json<Value> {
+    let jobs = state.jobs.lock().unwrap().clone();
+    Json(Value::Array(jobs.iter().map(|j| j.describe()).collect()))
+}
+
+/// GET /scans/{id}
+async fn get_scan(State(state): State<Arc<ServerState>>, Path(id): Path<String>) -> Response {
+    match state.find(&id) {
+        Some(job) => Json(job.describe()).into_response(),
+        None => not_found(&id),
+    }
+}
+
+/// DELETE /scans/{id}：取消未结束的扫描，或删除已结束扫描的记录
+async fn delete_scan(State(state): State<Arc<ServerState>>, Path(id): Path<String>) -> Response {
+    let Some(job) = state.find(&id) else {
+        return not_found(&id);
+    };
+    match job.status() {
+        JobStatus::Queued => {
+            job.ctx.cancel();
+            job.finish(JobStatus::Cancelled, None, None);
+        }
+        JobStatus::Running => job.ctx.cancel(),
+        _ => state.jobs.lock().unwrap().retain(|j| j.id != id),
+    }
+    Json(job.describe()).into_response()
+}
+
+/// DELETE /scans/{id}/hosts/{host}：放弃运行中任务的某个主机，其余主机照常扫描
+async fn cancel_host(
+    State(state): State<Arc<ServerState>>,
+    Path((id, host)): Path<(String, String)>,
+) -> Response {
+    let Some(job) = state.find(&id) else {
+        return not_found(&id);
+    };
+    match job.status() {
+        JobStatus::Queued | JobStatus::Running => {
+            job.ctx.cancel_host(&host);
+            Json(job.describe()).into_response()
+        }
+        _ => error_response(StatusCode::CONFLICT, format!("扫描任务已结束: {}", id)),
+    }
+}
+
+/// GET /scans/{id}/results：以NDJSON流式返回结果，扫描结束后关闭
+async fn stream_results(State(state): State<Arc<ServerState>>, Path(id): Path<String>) -> Response {
+    let Some(job) = state.find(&id) else {
+        return not_found(&id);
+    };
+    // 先订阅再读取，避免漏掉读取与等待之间产生的结果
+    let updates = job.updates.subscribe();
+    let stream = futures::stream::unfold(
+        (job, updates, 0usize),
+        |(job, mut updates, sent)| async move {
+            loop {
+                let finished = job.status().is_finished();
+                let (chunk, sent) = {
+                    let results = job.results.lock().unwrap();
+                    let chunk: String =
+                        results[sent..].iter().map(|v| format!("{}\n", v)).collect();
+                    (chunk, results.len())
+                };
+                if !chunk.is_empty() {
+                    return Some((
+                        Ok::<_, std::convert::Infallible>(chunk),
+                        (job, updates, sent),
+                    ));
+                }
+                if finished || updates.changed().await.is_err() {
+                    return None;
+                }
+            }
+        },
+    );
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .body(Body::from_stream(stream))
+        .unwrap()
+}
+
+fn not_found(id: &str) -> Response {
+    error_response(StatusCode::NOT_FOUND, format!("扫描任务不存在: {}", id))
+}
+
+/// 将请求转换为扫描参数，按命令行规则校验
+fn parse_scan(request: &ScanRequest) -> Result<ScanJob, String> {
+    if let Some(key) = request
+        .options
+        .keys()
+        .find(|k| !ALLOWED_OPTIONS.contains(&k.replace('_', "-").as_str()))
+    {
+        return Err(format!("接口不支持选项 {}", key));
+    }
+    let argv = options_to_argv(&request.options)?;
+    let (job, target) = match request.module {
+        ScanModule::Ping => {
+            let args: PingArgs = parse_module(
+                "ping",
+                request.module.command_name(),
+                argv,
+                |a: &PingArgs| a.profile_args.clone(),
+            )?;
+            let target = args.target.clone();
+            (ScanJob::Ping(args), target)
+        }
+        ScanModule::Portscan => {
+            let args: PortScanArgs = parse_module(
+                "portscan",
+                request.module.command_name(),
+                argv,
+                |a: &PortScanArgs| a.profile_args.clone(),
+            )?;
+            let target = args.targets.clone();
+            (ScanJob::Portscan(args), target)
+        }
+    };
+    // 守护进程的标准输入不属于请求方，配置档中的目标同样不能从标准输入读取
+    if target.as_deref().map(str::trim) == Some("-") {
+        return Err("接口不支持从标准输入读取目标".to_string());
+    }
+    Ok(job)
+}
+
+/// 按命令行规则解析模块参数，并套用选项中指定的配置档
+fn parse_module<T: Parser + Serialize + DeserializeOwned>(
+    name: &str,
+    module: &str,
+    argv: Vec<String>,
+    profile_options: fn(&T) -> ProfileOptions,
+) -> Result<T, String> {
+    let cmd = T::command();
+    let matches = cmd
+        .clone()
+        .try_get_matches_from(std::iter::once(name.to_string()).chain(argv))
+        .map_err(|e| e.to_string())?;
+    let mut args = T::from_arg_matches(&matches).map_err(|e| e.to_string())?;
+    let options = profile_options(&args);
+    let Some(ref name) = options.profile else {
+        return Ok(args);
+    };
+    let before = serde_json::to_value(&args).map_err(|e| e.to_string())?;
+    profile::resolve(&mut args, &options, module, &cmd, &matches).map_err(|e| e.to_string())?;
+    // 配置档同样只能改变接口开放的选项，否则套用配置档即可绕过上面的限制
+    let after = serde_json::to_value(&args).map_err(|e| e.to_string())?;
+    let denied = disallowed_changes(&before, &after);
+    if !denied.is_empty() {
+        return Err(format!(
+            "配置档 {} 含有接口不支持的选项 {}",
+            name,
+            denied.join(", ")
+        ));
+    }
+    Ok(args)
+}
+
+/// 套用配置档后取值发生变化、但接口不开放的选项（如 `--script`）
+///
+/// # 参数
+/// * `before` - 套用配置档前的参数
+/// * `after` - 套用配置档后的参数
+fn disallowed_changes(before: &Value, after: &Value) -> Vec<String> {
+    let Some(after) = after.as_object() else {
+        return Vec::new();
+    };
+    after
+        .iter()
+        .filter(|(key, value)| before.get(key.as_str()) != Some(*value))
+        .map(|(key, _)| key.replace('_', "-"))
+        .filter(|option| !ALLOWED_OPTIONS.contains(&option.as_str()))
+        .map(|option| format!("--{}", option))
+        .collect()
+}
+
+/// 将JSON选项转换为命令行参数
+///
+/// # 参数
+/// * `options` - 键为长参数名的选项表
+///
+/// # 返回
+/// * `Ok(Vec<String>)` - 命令行参数（不含程序名）
+/// * `Err` - 选项值类型不支持
+pub fn options_to_argv(options: &Map<String, Value>) -> Result<Vec<String>, String> {
+    let mut argv = Vec::new();
+    for (key, value) in options {
+        let flag = format!("--{}", key.replace('_', "-"));
+        let values = match value {
+            Value::Bool(true) => {
+                argv.push(flag);
+                continue;
+            }
+            Value::Bool(false) | Value::Null => continue,
+            Value::Array(items) => items.as_slice(),
+            scalar => std::slice::from_ref(scalar),
+        };
+        for item in values {
+            let text = match item {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string(),
+                _ => return Err(format!("选项 {} 的值只能是字符串、数字或布尔值", key)),
+            };
+            // 使用 --flag=value 形式，避免以 - 开头的值被当作参数
+            argv.push(format!("{}={}", flag, text));
+        }
+    }
+    Ok(argv)
+}
+
+/// GET /openapi.json：接口说明（无需认证）
+async fn openapi() -> Json<Value> {
+    Json(openapi_document())
+}
+
+/// 接口说明文档（OpenAPI 3 格式的精简描述）
+fn openapi_document() -> Value {
+    let id_param =
+        json!([{ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }]);
+    let job = json!({ "$ref": "#/components/schemas/Scan" });
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "gxtools scan API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "通过HTTP提交并跟踪扫描任务。所有请求需携带访问令牌，不接受 Origin 非本机的浏览器请求；只开放扫描相关选项，读写本机文件或读取标准输入的选项不可用。options 的键为命令行长参数名，取值规则与命令行一致；布尔值 true 表示开关参数，数组表示重复传入。可通过 profile 选项套用已保存的配置档（配置档只能设置接口开放的选项），显式给出的选项优先；未给出的参数使用守护进程的环境变量或默认值。"
+        },
+        "components": {
+            "securitySchemes": { "bearer": { "type": "http", "scheme": "bearer" } },
+            "schemas": {
+                "ScanRequest": {
+                    "type": "object",
+                    "required": ["module"],
+                    "properties": {
+                        "module": { "type": "string", "enum": ["ping", "portscan"] },
+                        "options": {
+                            "type": "object",
+                            "additionalProperties": true,
+                            "example": { "targets": "192.168.1.0/24", "ports": "22,80,443", "timeout": 2 }
+                        }
+                    }
+                },
+                "Scan": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "string" },
+                        "module": { "type": "string", "enum": ["ping", "portscan"] },
+                        "status": { "type": "string", "enum": ["queued", "running", "completed", "failed", "cancelled"] },
+                        "created_at": { "type": "string", "format": "date-time" },
+                        "started_at": { "type": "string", "format": "date-time", "nullable": true },
+                        "finished_at": { "type": "string", "format": "date-time", "nullable": true },
+                        "progress": {
+                            "type": "object",
+                            "properties": { "completed": { "type": "integer" }, "total": { "type": "integer" } }
+                        },
+                        "results": { "type": "integer", "description": "已产生的结果数" },
+                        "summary": { "type": "object", "nullable": true },
+                        "error": { "type": "string", "nullable": true }
+                    }
+                },
+                "Error": { "type": "object", "properties": { "error": { "type": "string" } } }
+            }
+        },
+        "security": [{ "bearer": [] }],
+        "paths": {
+            "/scans": {
+                "get": {
+                    "summary": "列出扫描任务",
+                    "responses": { "200": { "description": "任务列表", "content": { "application/json": { "schema": { "type": "array", "items": job } } } } }
+                },
+                "post": {
+                    "summary": "提交扫描任务（超过最大并行数时排队）",
+                    "requestBody": { "required": true, "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ScanRequest" } } } },
+                    "responses": {
+                        "202": { "description": "已接受", "content": { "application/json": { "schema": job } } },
+                        "400": { "description": "模块或选项无效", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Error" } } } },
+                        "415": { "description": "请求体不是 application/json" }
+                    }
+                }
+            },
+            "/scans/{id}": {
+                "parameters": id_param,
+                "get": {
+                    "summary": "查询任务状态和进度",
+                    "responses": { "200": { "description": "任务状态", "content": { "application/json": { "schema": job } } }, "404": { "description": "任务不存在" } }
+                },
+                "delete": {
+                    "summary": "取消未结束的任务；已结束的任务则删除其记录",
+                    "responses": { "200": { "description": "任务状态", "content": { "application/json": { "schema": job } } }, "404": { "description": "任务不存在" } }
+                }
+            },
+            "/scans/{id}/hosts/{host}": {
+                "parameters": [
+                    { "name": "id", "in": "path", "required": true, "schema": { "type": "string" } },
+                    { "name": "host", "in": "path", "required": true, "schema": { "type": "string" } }
+                ],
+                "delete": {
+                    "summary": "放弃任务中的某个主机：尚未开始的探测跳过，进行中的探测立即放弃，计为未完成",
+                    "responses": { "200": { "description": "任务状态", "content": { "application/json": { "schema": job } } }, "404": { "description": "任务不存在" }, "409": { "description": "任务已结束" } }
+                }
+            },
+            "/scans/{id}/results": {
+                "parameters": id_param,
+                "get": {
+                    "summary": "以NDJSON流式返回结果（每行一个结果，任务结束后关闭连接）",
+                    "responses": { "200": { "description": "结果流", "content": { "application/x-ndjson": {} } }, "404": { "description": "任务不存在" } }
+                }
+            },
+            "/openapi.json": {
+                "get": { "summary": "接口说明", "security": [], "responses": { "200": { "description": "本文档" } } }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Bytes;
+    use axum::http::Request;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Bytes) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, body)
+    }
+
+    fn authed(method: &str, uri: &str, body: Body) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_options_to_argv() {
+        let options = json!({
+            "target": "10.0.0.1",
+            "timeout": 1,
+            "echo": true,
+            "output": false,
+            "target_xlsx": ["a.xlsx"],
+        });
+        let argv = options_to_argv(options.as_object().unwrap()).unwrap();
+        assert_eq!(
+            argv,
+            vec![
+                "--echo",
+                "--target=10.0.0.1",
+                "--target-xlsx=a.xlsx",
+                "--timeout=1"
+            ]
+        );
+
+        let bad = json!({ "target": { "ip": "10.0.0.1" } });
+        assert!(options_to_argv(bad.as_object().unwrap()).is_err());
+    }
+
+    fn state(root: &std::path::Path) -> Arc<ServerState> {
+        ServerState::new(
+            DEFAULT_LISTEN.parse().unwrap(),
+            Secret::new("secret"),
+            1,
+            root,
+        )
+    }
+
+    #[test]
+    fn test_allowed_options_exist() {
+        use clap::CommandFactory;
+        let ping = PingArgs::command();
+        let portscan = PortScanArgs::command();
+        for option in ALLOWED_OPTIONS {
+            let known =
+                |cmd: &clap::Command| cmd.get_arguments().any(|a| a.get_long() == Some(*option));
+            assert!(known(&ping) || known(&portscan), "{}", option);
+        }
+    }
+
+    #[test]
+    fn test_parse_scan_rejects_local_io_options() {
+        for options in [
+            json!({ "target": "10.0.0.1", "export_plan": "/tmp/plan.xlsx" }),
+            json!({ "target": "10.0.0.1", "record": "/tmp/tape" }),
+            json!({ "target": "10.0.0.1", "script": "/tmp/hook.rhai" }),
+            json!({ "target_file": "/etc/hosts" }),
+            json!({ "target": "-" }),
+        ] {
+            let request: ScanRequest =
+                serde_json::from_value(json!({ "module": "ping", "options": options })).unwrap();
+            assert!(parse_scan(&request).is_err(), "{:?}", request.options);
+        }
+        let request: ScanRequest = serde_json::from_value(
+            json!({ "module": "portscan", "options": { "targets": "10.0.0.1", "ports": "22" } }),
+        )
+        .unwrap();
+        assert!(parse_scan(&request).is_ok());
+    }
+
+    #[test]
+    fn test_profile_cannot_set_local_io_options() {
+        let before = json!({ "target": null, "timeout": 1000, "script": null, "tui": false });
+        let allowed = json!({ "target": "10.0.0.1", "timeout": 500, "script": null, "tui": false });
+        assert!(disallowed_changes(&before, &allowed).is_empty());
+        let denied = json!({ "target": "10.0.0.1", "timeout": 1000, "script": "/tmp/hook.rhai", "tui": true });
+        assert_eq!(disallowed_changes(&before, &denied), ["--script", "--tui"]);
+    }
+
+    #[test]
+    fn test_is_local_host() {
+        assert!(is_local_host("127.0.0.1:8321"));
+        assert!(is_local_host("localhost"));
+        assert!(is_local_host("[::1]:8321"));
+        assert!(!is_local_host("evil.example:8321"));
+        assert!(!is_local_host("192.168.1.10"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_cross_site_requests() {
+        let app = router(state(&std::env::temp_dir()));
+        let body = json!({ "module": "ping", "options": { "target": "127.0.0.1" } }).to_string();
+
+        // 浏览器无需预检即可发送的 text/plain 请求
+        let mut request = authed("POST", "/scans", Body::from(body.clone()));
+        request
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, "text/plain".parse().unwrap());
+        let (status, _) = send(&app, request).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let mut request = authed("GET", "/scans", Body::empty());
+        request
+            .headers_mut()
+            .insert(header::ORIGIN, "https://evil.example".parse().unwrap());
+        let (status, _) = send(&app, request).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // DNS重绑定：域名解析到本机，但 Host 仍是外部域名
+        let mut request = authed("GET", "/scans", Body::empty());
+        request
+            .headers_mut()
+            .insert(header::HOST, "evil.example:8321".parse().unwrap());
+        let (status, _) = send(&app, request).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let mut request = authed("GET", "/scans", Body::empty());
+        request
+            .headers_mut()
+            .insert(header::HOST, "127.0.0.1:8321".parse().unwrap());
+        request
+            .headers_mut()
+            .insert(header::ORIGIN, "http://localhost:8321".parse().unwrap());
+        let (status, _) = send(&app, request).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_parse_scan_validates_like_cli() {
+        let request: ScanRequest = serde_json::from_value(
+            json!({ "module": "portscan", "options": { "targets": "10.0.0.1", "tui": true } }),
+        )
+        .unwrap();
+        assert!(parse_scan(&request).is_err());
+
+        let request: ScanRequest =
+            serde_json::from_value(json!({ "module": "ping", "options": { "count": "many" } }))
+                .unwrap();
+        assert!(parse_scan(&request).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_requests_require_token() {
+        let app = router(state(&std::env::temp_dir()));
+
+        let request = Request::builder()
+            .uri("/scans")
+            .body(Body::empty())
+            .unwrap();
+        let (status, _) = send(&app, request).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _) = send(&app, authed("GET", "/scans", Body::empty())).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let request = Request::builder()
+            .uri("/openapi.json")
+            .body(Body::empty())
+            .unwrap();
+        let (status, _) = send(&app, request).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_scan_lifecycle() {
+        // 任务会在运行目录下写入结果，放在临时目录中，不落在工作目录中
+        let root = std::env::temp_dir().join(format!("gxr_serve_output_{}", std::process::id()));
+        let app = router(state(&root));
+
+        let body = json!({ "module": "ping", "options": { "target": "127.0.0.1", "count": 1, "timeout": 1 } });
+        let (status, body) =
+            send(&app, authed("POST", "/scans", Body::from(body.to_string()))).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let created: Value = serde_json::from_slice(&body).unwrap();
+        let id = created["id"].as_str().unwrap().to_string();
+
+        // 结果流在扫描结束后关闭
+        let uri = format!("/scans/{}/results", id);
+        let (status, body) = tokio::time::timeout(
+            Duration::from_secs(10),
+            send(&app, authed("GET", &uri, Body::empty())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::OK);
+        let lines: Vec<Value> = body
+            .split(|&b| b == b'\n')
+            .filter(|l| !l.is_empty())
+            .map(|l| serde_json::from_slice(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["ip"], "127.0.0.1");
+
+        let (_, body) = send(
+            &app,
+            authed("GET", &format!("/scans/{}", id), Body::empty()),
+        )
+        .await;
+        let job: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(job["status"], "completed");
+        assert_eq!(job["summary"]["total"], 1);
+        assert_eq!(job["progress"]["completed"], 1);
+
+        // 已结束的任务可删除
+        let (status, _) = send(
+            &app,
+            authed("DELETE", &format!("/scans/{}", id), Body::empty()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(
+            &app,
+            authed("GET", &format!("/scans/{}", id), Body::empty()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,51 @@
+// src/commands/template.rs
+use crate::utils::{create_excel_template, ensure_output_dir, output_root};
+use clap::{Parser, Subcommand};
+use std::error::Error;
+use std::path::PathBuf;
+
+/// 目标导入模板的表头（第一列为 --target-xlsx 默认读取的列）
+pub const TARGET_TEMPLATE_HEADERS: &[&str] = &["IP地址", "资产名称", "业务系统", "责任人", "备注"];
+
+/// 模板生成命令参数
+#[derive(Parser, Debug)]
+pub struct TemplateArgs {
+    #[command(subcommand)]
+    pub command: TemplateCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TemplateCommands {
+    /// 生成目标导入模板（供客户填写后配合 --target-xlsx 使用）
+    #[command(name = "targets")]
+    Targets {
+        /// 输出文件路径（默认 <输出目录>/template/targets_template.xlsx）
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+}
+
+/// 执行模板生成命令
+pub fn run(args: &TemplateArgs) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match &args.command {
+        TemplateCommands::Targets { output } => {
+            let path = match output {
+                Some(path) => path.clone(),
+                None => ensure_output_dir(&output_root().join("template").to_string_lossy())?
+                    .join("targets_template.xlsx"),
+            };
+            let headers = TARGET_TEMPLATE_HEADERS
+                .iter()
+                .map(|h| h.to_string())
+                .collect();
+            create_excel_template(&path, headers)?;
+            println!("✅ 目标导入模板已生成: {}", path.display());
+            println!(
+                "   填写后使用: --target-xlsx {} --column {}",
+                path.display(),
+                TARGET_TEMPLATE_HEADERS[0]
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,421 @@
+// src/commands/update.rs
+use crate::utils::console::Icon;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use clap::Parser;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 默认的版本清单地址
+pub const DEFAULT_UPDATE_URL: &str = "https://tools.corp/gxtools/latest.json";
+
+/// `--check-only` 发现新版本时的退出码
+pub const UPDATE_AVAILABLE_EXIT_CODE: i32 = 10;
+
+/// 构建时内置的签名公钥（Base64编码的Ed25519公钥），设置后更新包必须带有效签名
+const PINNED_PUBLIC_KEY: Option<&str> = option_env!("GXTOOLS_UPDATE_PUBKEY");
+
+/// 请求超时
+const HTTP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 自更新参数配置
+#[derive(Parser, Debug)]
+pub struct SelfUpdateArgs {
+    /// 版本清单地址
+    #[arg(
+        long,
+        env = "GXTOOLS_UPDATE_URL",
+        default_value = DEFAULT_UPDATE_URL,
+        value_name = "URL"
+    )]
+    pub url: String,
+
+    /// 只检查是否有新版本（有新版本时退出码为 10）
+    #[arg(long)]
+    pub check_only: bool,
+
+    /// 未内置签名公钥时仍然安装（只校验SHA256，不校验签名；默认拒绝安装）
+    #[arg(long)]
+    pub insecure_skip_signature: bool,
+}
+
+/// 版本清单
+///
+/// ```json
+/// {
+///   "version": "0.2.0",
+///   "platforms": {
+///     "x86_64-linux": { "url": "gxtools-linux", "sha256": "…", "signature": "…" }
+///   }
+/// }
+/// ```
+/// 平台键为 `架构-系统`（见 [`platform_key`]），`url` 可以是相对清单地址的路径，
+/// `signature` 为对版本号、平台键和文件SHA256的Ed25519签名（Base64，签名内容见 [`signed_payload`]），
+/// 清单中的版本号或平台被改动时签名随之失效，无法把旧版本冒充为新版本下发。
+#[derive(Debug, Deserialize)]
+pub struct Manifest {
+    /// 最新版本号
+    pub version: String,
+    /// 平台键 -> 发布文件
+    pub platforms: HashMap<String, Release>,
+}
+
+/// 单个平台的发布文件
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    /// 下载地址
+    pub url: String,
+    /// 文件的SHA256（十六进制）
+    pub sha256: String,
+    /// 版本号、平台键及文件SHA256的Ed25519签名（Base64）
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+/// 自更新结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateOutcome {
+    /// 已是最新版本
+    UpToDate,
+    /// 有新版本（仅检查）
+    Available,
+    /// 已更新
+    Updated,
+}
+
+/// 执行自更新
+///
+/// # 参数
+/// * `args` - 自更新参数
+///
+/// # 返回
+/// * `Ok(UpdateOutcome)` - 检查或更新结果
+/// * `Err` - 下载、校验或替换失败（当前程序保持不变）
+pub async fn run(args: &SelfUpdateArgs) -> Result<UpdateOutcome, Box<dyn Error + Send + Sync>> {
+    let exe = std::env::current_exe().map_err(|e| format!("无法确定当前程序路径: {}", e))?;
+    cleanup_previous(&exe);
+
+    let public_key = PINNED_PUBLIC_KEY.map(parse_public_key).transpose()?;
+    // 重定向到 http 同样拒绝
+    let client = reqwest::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .https_only(true)
+        .build()?;
+    let manifest_url = reqwest::Url::parse(&args.url)
+        .map_err(|e| format!("无效的清单地址 {}: {}", args.url, e))?;
+    require_https(&manifest_url, "清单地址")?;
+
+    println!("{} 检查更新: {}", Icon::Scan, manifest_url);
+    let body = fetch(&client, manifest_url.clone()).await?;
+    let manifest: Manifest =
+        serde_json::from_slice(&body).map_err(|e| format!("版本清单格式错误: {}", e))?;
+
+    let current = env!("CARGO_PKG_VERSION");
+    println!("   当前版本: {}, 最新版本: {}", current, manifest.version);
+    if !is_newer(&manifest.version, current)? {
+        println!("{} 已是最新版本", Icon::Ok);
+        return Ok(UpdateOutcome::UpToDate);
+    }
+
+    let platform = platform_key();
+    let release = manifest
+        .platforms
+        .get(&platform)
+        .ok_or_else(|| format!("版本 {} 没有提供 {} 平台的文件", manifest.version, platform))?;
+    println!("⬆️  发现新版本: {}", manifest.version);
+    if args.check_only {
+        return Ok(UpdateOutcome::Available);
+    }
+
+    if public_key.is_none() {
+        if !args.insecure_skip_signature {
+            return Err("未内置签名公钥，无法校验更新包签名，已拒绝安装\
+                （确认下载来源可信时可加 --insecure-skip-signature 只校验SHA256）"
+                .into());
+        }
+        println!(
+            "{} 未内置签名公钥，已按 --insecure-skip-signature 跳过签名校验，仅校验SHA256",
+            Icon::Warn
+        );
+    }
+
+    let download_url = manifest_url
+        .join(&release.url)
+        .map_err(|e| format!("无效的下载地址 {}: {}", release.url, e))?;
+    require_https(&download_url, "下载地址")?;
+    println!("⬇️  下载: {}", download_url);
+    let binary = fetch(&client, download_url).await?;
+    verify_release(
+        &binary,
+        &manifest.version,
+        &platform,
+        release,
+        public_key.as_ref(),
+    )?;
+    println!("🔐 校验通过（{} 字节）", binary.len());
+
+    install(&binary, &exe)?;
+    println!(
+        "{} 已更新到 {}: {}",
+        Icon::Ok,
+        manifest.version,
+        exe.display()
+    );
+    Ok(UpdateOutcome::Updated)
+}
+
+/// 下载地址内容
+async fn fetch(
+    client: &reqwest::Client,
+    url: reqwest::Url,
+) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let response = client
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| format!("请求 {} 失败: {}", url, e))?
+        .error_for_status()
+        .map_err(|e| format!("请求 {} 失败: {}", url, e))?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// 只允许 https 地址（清单及更新包经明文传输时可被中间人替换）
+///
+/// # 参数
+/// * `url` - 地址
+/// * `what` - 地址的用途（用于错误信息）
+fn require_https(url: &reqwest::Url, what: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if url.scheme() != "https" {
+        return Err(format!("{}必须使用 https: {}", what, url).into());
+    }
+    Ok(())
+}
+
+/// 当前平台在版本清单中的键（如 `x86_64-linux`、`x86_64-windows`、`aarch64-macos`）
+pub fn platform_key() -> String {
+    format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
+/// 比较版本号（`主.次.修订`，可带 `v` 前缀）
+///
+/// # 返回
+/// * `Ok(true)` - `latest` 比 `current` 新
+/// * `Err` - 版本号格式无效
+pub fn is_newer(latest: &str, current: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    Ok(parse_version(latest)? > parse_version(current)?)
+}
+
+fn parse_version(version: &str) -> Result<Vec<u64>, Box<dyn Error + Send + Sync>> {
+    version
+        .trim()
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.parse::<u64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("无效的版本号: {}", version).into())
+}
+
+fn parse_public_key(encoded: &str) -> Result<VerifyingKey, Box<dyn Error + Send + Sync>> {
+    let bytes: [u8; 32] = BASE64
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or("内置的签名公钥格式无效")?;
+    Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
+/// 发布文件签名的内容：版本号、平台键和文件SHA256（小写十六进制），各占一行
+///
+/// # 参数
+/// * `version` - 清单中的版本号
+/// * `platform` - 平台键
+/// * `sha256` - 文件的SHA256
+pub fn signed_payload(version: &str, platform: &str, sha256: &str) -> Vec<u8> {
+    format!(
+        "gxtools-update\n{}\n{}\n{}\n",
+        version.trim(),
+        platform,
+        sha256.trim().to_ascii_lowercase()
+    )
+    .into_bytes()
+}
+
+/// 校验下载文件的SHA256及签名
+///
+/// # 参数
+/// * `binary` - 下载的文件内容
+/// * `version` - 清单中的版本号（签名覆盖版本号，防止旧版本冒充新版本）
+/// * `platform` - 平台键
+/// * `release` - 清单中的发布信息
+/// * `public_key` - 签名公钥（为空时不要求签名）
+pub fn verify_release(
+    binary: &[u8],
+    version: &str,
+    platform: &str,
+    release: &Release,
+    public_key: Option<&VerifyingKey>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let actual = hex::encode(Sha256::digest(binary));
+    if !actual.eq_ignore_ascii_case(release.sha256.trim()) {
+        return Err(format!("SHA256校验失败: 期望 {}，实际 {}", release.sha256, actual).into());
+    }
+
+    if let Some(key) = public_key {
+        let encoded = release
+            .signature
+            .as_deref()
+            .ok_or("更新包缺少签名，已拒绝安装")?;
+        let signature = BASE64
+            .decode(encoded.trim())
+            .ok()
+            .and_then(|b| Signature::from_slice(&b).ok())
+            .ok_or("更新包签名格式无效")?;
+        key.verify(&signed_payload(version, platform, &actual), &signature)
+            .map_err(|_| "更新包签名校验失败（文件、版本号或平台与签名不符），已拒绝安装")?;
+    }
+    Ok(())
+}
+
+/// 用新文件替换当前程序
+///
+/// 新文件先写入程序所在目录的临时文件，再通过重命名替换：
+/// Unix下直接覆盖（运行中的进程不受影响）；Windows下运行中的程序无法覆盖，
+/// 先将其重命名为 `.old`，再把新文件移入原位置，失败时还原。
+///
+/// # 参数
+/// * `binary` - 新程序内容
+/// * `exe` - 当前程序路径
+pub fn install(binary: &[u8], exe: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let dir = exe.parent().ok_or("无法确定程序所在目录")?;
+    let staged = dir.join(format!(".gxtools-update-{}.tmp", std::process::id()));
+    fs::write(&staged, binary).map_err(|e| format!("无法写入 {}: {}", staged.display(), e))?;
+
+    let result = replace_executable(&staged, exe);
+    if result.is_err() {
+        let _ = fs::remove_file(&staged);
+    }
+    result
+}
+
+#[cfg(unix)]
+fn replace_executable(staged: &Path, exe: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = fs::metadata(exe)
+        .map(|m| m.permissions().mode())
+        .unwrap_or(0o755);
+    fs::set_permissions(staged, fs::Permissions::from_mode(mode))?;
+    fs::rename(staged, exe).map_err(|e| format!("无法替换 {}: {}", exe.display(), e))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn replace_executable(staged: &Path, exe: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let old = previous_path(exe);
+    let _ = fs::remove_file(&old);
+    fs::rename(exe, &old).map_err(|e| format!("无法移动 {}: {}", exe.display(), e))?;
+    if let Err(e) = fs::rename(staged, exe) {
+        let _ = fs::rename(&old, exe);
+        return Err(format!("无法替换 {}: {}", exe.display(), e).into());
+    }
+    Ok(())
+}
+
+/// Windows下被替换的旧程序路径
+fn previous_path(exe: &Path) -> PathBuf {
+    let mut name = exe.file_name().unwrap_or_default().to_os_string();
+    name.push(".old");
+    exe.with_file_name(name)
+}
+
+/// 清理上次更新留下的旧程序（Windows下旧程序在更新时仍在运行，无法立即删除）
+fn cleanup_previous(exe: &Path) {
+    let _ = fs::remove_file(previous_path(exe));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const PLATFORM: &str = "x86_64-linux";
+
+    fn release_for(binary: &[u8], version: &str, key: Option<&SigningKey>) -> Release {
+        let sha256 = hex::encode(Sha256::digest(binary));
+        let payload = signed_payload(version, PLATFORM, &sha256);
+        Release {
+            url: "gxtools".to_string(),
+            signature: key.map(|k| BASE64.encode(k.sign(&payload).to_bytes())),
+            sha256,
+        }
+    }
+
+    #[test]
+    fn test_version_comparison() {
+        assert!(is_newer("0.2.0", "0.1.9").unwrap());
+        assert!(is_newer("v1.10.0", "1.9.3").unwrap());
+        assert!(!is_newer("0.1.0", "0.1.0").unwrap());
+        assert!(!is_newer("0.1.0", "0.2.0").unwrap());
+        assert!(is_newer("latest", "0.1.0").is_err());
+    }
+
+    #[test]
+    fn test_require_https() {
+        let manifest = reqwest::Url::parse(DEFAULT_UPDATE_URL).unwrap();
+        assert!(require_https(&manifest, "清单地址").is_ok());
+        assert!(require_https(&manifest.join("gxtools-linux").unwrap(), "下载地址").is_ok());
+        let http = manifest.join("http://tools.corp/gxtools-linux").unwrap();
+        assert!(require_https(&http, "下载地址").is_err());
+        let file = reqwest::Url::parse("file:///tmp/latest.json").unwrap();
+        assert!(require_https(&file, "清单地址").is_err());
+    }
+
+    #[test]
+    fn test_verify_release_checks_hash_and_signature() {
+        let binary = b"new gxtools build";
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let public = key.verifying_key();
+
+        let verify = |binary: &[u8], version: &str, platform: &str, release: &Release| {
+            verify_release(binary, version, platform, release, Some(&public))
+        };
+
+        let unsigned = release_for(binary, "0.2.0", None);
+        assert!(verify_release(binary, "0.2.0", PLATFORM, &unsigned, None).is_ok());
+        let release = release_for(binary, "0.2.0", Some(&key));
+        assert!(verify(binary, "0.2.0", PLATFORM, &release).is_ok());
+
+        // 内容被篡改
+        assert!(verify(b"tampered build", "0.2.0", PLATFORM, &release).is_err());
+
+        // 旧版本的签名文件被标成新版本或其他平台下发
+        assert!(verify(binary, "0.3.0", PLATFORM, &release).is_err());
+        assert!(verify(binary, "0.2.0", "aarch64-macos", &release).is_err());
+
+        // 固定公钥时缺少签名或签名来自其他密钥
+        assert!(verify(binary, "0.2.0", PLATFORM, &unsigned).is_err());
+        let other = SigningKey::from_bytes(&[9; 32]);
+        let release = release_for(binary, "0.2.0", Some(&other));
+        assert!(verify(binary, "0.2.0", PLATFORM, &release).is_err());
+    }
+
+    #[test]
+    fn test_install_replaces_file() {
+        let dir = std::env::temp_dir().join(format!("gxtools-update-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("gxtools");
+        fs::write(&exe, b"old").unwrap();
+
+        install(b"new", &exe).unwrap();
+        assert_eq!(fs::read(&exe).unwrap(), b"new");
+        // 临时文件不残留
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
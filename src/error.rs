@@ -0,0 +1,81 @@
+// src/error.rs
+use std::fmt;
+
+/// 工具箱统一错误类型
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GxError {
+    /// 端口参数中存在无效项（包含全部无效项的明细）
+    InvalidPorts(Vec<PortSpecError>),
+}
+
+impl fmt::Display for GxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GxError::InvalidPorts(errors) => {
+                write!(f, "端口参数无效")?;
+                for e in errors {
+                    write!(f, "\n   - {}", e)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for GxError {}
+
+/// 单个端口参数项的解析错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortSpecError {
+    /// 在逗号分隔列表中的位置（从1开始，空输入为0）
+    pub position: usize,
+    /// 原始参数项
+    pub token: String,
+    /// 错误原因
+    pub reason: PortErrorReason,
+}
+
+impl fmt::Display for PortSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.position == 0 {
+            write!(f, "{}", self.reason)
+        } else {
+            write!(
+                f,
+                "第{}项 \"{}\": {}",
+                self.position, self.token, self.reason
+            )
+        }
+    }
+}
+
+/// 端口参数项无效的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortErrorReason {
+    /// 端口参数为空
+    Empty,
+    /// 不是数字
+    NonNumeric,
+    /// 超出 1-65535 范围
+    OutOfRange,
+    /// 范围起始值大于结束值
+    ReversedRange,
+    /// 端口号为0
+    ZeroPort,
+    /// 排除后没有剩余端口
+    AllExcluded,
+}
+
+impl fmt::Display for PortErrorReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            PortErrorReason::Empty => "端口参数为空",
+            PortErrorReason::NonNumeric => "不是有效的数字",
+            PortErrorReason::OutOfRange => "超出端口范围(1-65535)",
+            PortErrorReason::ReversedRange => "范围起始值大于结束值",
+            PortErrorReason::ZeroPort => "端口号不能为0",
+            PortErrorReason::AllExcluded => "排除后没有剩余端口",
+        };
+        write!(f, "{}", msg)
+    }
+}
@@ -1,11 +1,233 @@
-use clap::{Parser, Subcommand};
+use chrono::Local;
+use clap::{ArgMatches, Command, CommandFactory, FromArgMatches, Parser, Subcommand};
+use gxr::commands::assets::{self, AssetsArgs};
+use gxr::commands::config::{self, ConfigArgs};
+use gxr::commands::ctl::{self, CtlArgs};
+use gxr::commands::doctor::{self, DoctorArgs};
+use gxr::commands::history::{self, HistoryArgs, RunRecord, RunSummary};
+use gxr::commands::profile::{self, ProfileArgs};
+use gxr::commands::report::{self, ReportArgs};
+use gxr::commands::resources::{self, ResourcesArgs};
+use gxr::commands::serve::{self, ServeArgs};
+use gxr::commands::template::{self, TemplateArgs};
+use gxr::commands::update::{self, SelfUpdateArgs, UpdateOutcome};
 use gxr::commands::{net, pentest};
+use gxr::utils::console::{self, Icon};
+use gxr::utils::context::{self, ScanContext};
+use gxr::utils::control::ControlServer;
+use gxr::utils::dns;
+use gxr::utils::finding;
+use gxr::utils::identity::{self, HexPayload, ProbeIdentity, set_identity};
+use gxr::utils::integrity::{load_signing_key, set_signing_key};
+use gxr::utils::meta;
+use gxr::utils::redact::{DEFAULT_MAPPING_FILE_NAME, Redactor, set_redactor};
+use gxr::utils::run_dir::RunDir;
+use gxr::utils::scope;
+use gxr::utils::secret::{redact_command_line, sensitive_args, set_show_secrets};
+use gxr::utils::targets::TargetSourceArgs;
+use gxr::utils::window::{ScanWindow, set_window};
+use gxr::utils::{
+    DEFAULT_OUTPUT_ROOT, Language, set_config_dir, set_flat_output, set_language, set_output_root,
+};
+use std::path::PathBuf;
 use std::process;
+use std::sync::Arc;
+use std::time::Instant;
 
 #[derive(Parser, Debug)]
 #[command(name = "gxtools")]
 #[command(version, about = "GX安全工具箱 - 网络测试、渗透测试、等保核查工具集", long_about = None)]
 struct Cli {
+    /// 不记录本次运行的历史（适用于不允许留存本地痕迹的场景）
+    #[arg(long, global = true, env = "GXTOOLS_NO_HISTORY")]
+    no_history: bool,
+
+    /// 界面语言（影响耗时等输出格式）
+    #[arg(
+        long,
+        global = true,
+        env = "GXTOOLS_LANG",
+        value_enum,
+        default_value = "en",
+        value_name = "LANG"
+    )]
+    lang: Language,
+
+    /// 输出根目录（结果文件、模板及历史记录均保存在此目录下）
+    #[arg(
+        long,
+        global = true,
+        env = "GXTOOLS_OUTPUT_DIR",
+        default_value = DEFAULT_OUTPUT_ROOT,
+        value_name = "DIR"
+    )]
+    output_dir: PathBuf,
+
+    /// 使用旧的平铺输出结构（<输出根目录>/<模块>/），不为每次运行单独建目录
+    #[arg(long, global = true, env = "GXTOOLS_FLAT_OUTPUT")]
+    flat_output: bool,
+
+    /// 在报告中显示明文凭据（默认只显示首尾字符，如 p*****d）
+    #[arg(long, global = true, env = "GXTOOLS_SHOW_SECRETS")]
+    show_secrets: bool,
+
+    /// 强制使用ASCII符号和进度条字符（默认根据终端能力自动选择）
+    #[arg(long, global = true, env = "GXTOOLS_ASCII")]
+    ascii: bool,
+
+    /// 输出详细信息（如导出速度）
+    #[arg(short = 'v', long, global = true, env = "GXTOOLS_VERBOSE")]
+    verbose: bool,
+
+    /// 不合并各来源报告的重复发现（用于排查检测之间的重叠）
+    #[arg(long, global = true, env = "GXTOOLS_NO_DEDUP")]
+    no_dedup: bool,
+
+    /// 配置目录（保存配置档），默认为系统的用户配置目录
+    #[arg(long, global = true, env = "GXTOOLS_CONFIG_DIR", value_name = "DIR")]
+    config_dir: Option<PathBuf>,
+
+    /// 资源目录（fingerprints.json、consoles.yaml、creds.yaml、templates/*.yaml），优先于配置目录下的 resources 目录及内置资源
+    #[arg(long, global = true, env = "GXTOOLS_RESOURCES", value_name = "DIR")]
+    resources: Option<PathBuf>,
+
+    /// 上游DNS服务器（可重复指定，如 10.0.0.53 或 10.0.0.53:5353），覆盖配置文件中的 dns.servers
+    #[arg(
+        long = "dns-server",
+        global = true,
+        env = "GXTOOLS_DNS_SERVER",
+        value_delimiter = ',',
+        value_name = "ADDR"
+    )]
+    dns_servers: Vec<String>,
+
+    /// 加密DNS上游（dot://1.1.1.1 或 https://doh.internal/dns-query），覆盖配置文件中的 dns.upstream
+    #[arg(long, global = true, env = "GXTOOLS_DNS", value_name = "URL")]
+    dns: Option<String>,
+
+    /// 加密DNS上游连接或握手失败时回退到明文DNS（--dns-server 或系统配置），证书不符时不回退
+    #[arg(long, global = true, env = "GXTOOLS_DNS_ALLOW_FALLBACK")]
+    dns_allow_fallback: bool,
+
+    /// 加密DNS上游证书的SHA-256指纹（可重复指定），指定后只接受这些证书，可用于自签名证书
+    #[arg(
+        long = "dns-pin",
+        global = true,
+        env = "GXTOOLS_DNS_PIN",
+        value_delimiter = ',',
+        value_name = "SHA256"
+    )]
+    dns_pins: Vec<String>,
+
+    /// 授权范围文件（每行一个网段、IP或域名），范围外的目标不会被探测，覆盖配置文件中的 scope.file
+    #[arg(long, global = true, env = "GXTOOLS_SCOPE_FILE", value_name = "FILE")]
+    scope_file: Option<PathBuf>,
+
+    /// 存在授权范围外的目标时中止运行（默认排除这些目标后继续）
+    #[arg(long, global = true, env = "GXTOOLS_STRICT_SCOPE")]
+    strict_scope: bool,
+
+    /// 确认已获授权运行主动攻击类模块（如爆破、漏洞验证），非交互运行时必须指定
+    #[arg(long, global = true, env = "GXTOOLS_I_UNDERSTAND_ACTIVE_SCAN")]
+    i_understand_active_scan: bool,
+
+    /// 导出结果时脱敏：IP、主机名替换为化名并删除Banner、标题等证据列，对照表另行保存
+    #[arg(
+        long,
+        global = true,
+        env = "GXTOOLS_REDACT_OUTPUT",
+        requires = "redact_salt"
+    )]
+    redact_output: bool,
+
+    /// 脱敏化名的盐值（同一项目使用相同盐值，多份报告的化名保持一致）
+    #[arg(long, global = true, env = "GXTOOLS_REDACT_SALT", value_name = "SALT")]
+    redact_salt: Option<String>,
+
+    /// 脱敏时保留Banner、标题等证据列
+    #[arg(long, global = true, env = "GXTOOLS_REDACT_KEEP_EVIDENCE")]
+    redact_keep_evidence: bool,
+
+    /// 脱敏对照表文件（已存在时追加合并），默认为 <输出根目录>/redaction_map.csv
+    #[arg(long, global = true, env = "GXTOOLS_REDACT_MAP", value_name = "FILE")]
+    redact_map: Option<PathBuf>,
+
+    /// 只在该时间窗口内发起探测（如 00:00-06:00，可跨午夜），窗口外自动暂停，窗口打开后继续
+    #[arg(
+        long,
+        global = true,
+        env = "GXTOOLS_WINDOW",
+        value_name = "HH:MM-HH:MM"
+    )]
+    window: Option<String>,
+
+    /// 扫描窗口所在时区（固定偏移如 +08:00，或 Asia/Shanghai 等没有夏令时的时区），默认为本机时区
+    #[arg(
+        long,
+        global = true,
+        env = "GXTOOLS_TIMEZONE",
+        requires = "window",
+        value_name = "TZ"
+    )]
+    timezone: Option<String>,
+
+    /// 运行元数据（可重复指定，如 --meta project=XX --meta tester=张三 --meta auth=GX-2024-017），
+    /// 写入终端横幅、Excel、HTML报告、产物索引及历史记录，同名时覆盖配置文件的 meta 段落
+    #[arg(
+        long = "meta",
+        global = true,
+        env = "GXTOOLS_META",
+        value_name = "KEY=VALUE",
+        value_parser = meta::parse_meta
+    )]
+    meta: Vec<(String, String)>,
+
+    /// HTTP请求使用的User-Agent（便于蓝队按User-Agent放行），写入运行元数据
+    #[arg(
+        long,
+        global = true,
+        env = "GXTOOLS_USER_AGENT",
+        value_name = "STRING",
+        conflicts_with = "randomize_ua"
+    )]
+    user_agent: Option<String>,
+
+    /// 每个HTTP请求在内置的常见浏览器User-Agent中轮换（适用于需要混入正常流量的场景）
+    #[arg(long, global = true, env = "GXTOOLS_RANDOMIZE_UA")]
+    randomize_ua: bool,
+
+    /// 每个HTTP请求附加的请求头（可重复指定，如 --http-header "X-Pentest-ID: GX-2024-017"），
+    /// 端口扫描的HTTP探测同样附加，写入运行元数据
+    #[arg(
+        long = "http-header",
+        global = true,
+        value_name = "NAME: VALUE",
+        value_parser = identity::parse_header
+    )]
+    http_headers: Vec<(String, String)>,
+
+    /// 端口扫描对未主动发送banner的端口发送的探测报文（十六进制，如 48454c4c4f0d0a），替换默认的HTTP请求
+    #[arg(
+        long,
+        global = true,
+        env = "GXTOOLS_TCP_BANNER_PROBE",
+        value_name = "HEX"
+    )]
+    tcp_banner_probe: Option<HexPayload>,
+
+    /// 写入ICMP回显请求填充数据的标记字符串（系统ping的 -p，取前16字节，Windows不支持）
+    #[arg(
+        long,
+        global = true,
+        env = "GXTOOLS_ICMP_MARKER",
+        value_name = "STRING"
+    )]
+    icmp_marker: Option<String>,
+
+    /// 用该Ed25519私钥文件（Base64编码）对运行目录的产物索引签名，可用 report verify --public-key 校验
+    #[arg(long, global = true, env = "GXTOOLS_SIGN_KEY", value_name = "FILE")]
+    sign_key: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -22,49 +244,475 @@ enum Commands {
         #[command(subcommand)]
         subcommand: PentestCommands,
     },
+    /// 历史运行记录
+    History(HistoryArgs),
+    /// 管理资产库（扫描发现的主机及其变化）
+    Assets(AssetsArgs),
+    /// 查看运行结果
+    Report(ReportArgs),
+    /// 生成导入模板
+    Template(TemplateArgs),
+    /// 查看指纹、管理后台路径、默认凭据及漏洞验证模板等资源
+    Resources(ResourcesArgs),
+    /// 查看生效配置及环境变量
+    Config(ConfigArgs),
+    /// 管理扫描配置档
+    Profile(ProfileArgs),
+    /// 以守护进程方式运行，通过本地HTTP接口提交扫描
+    Serve(ServeArgs),
+    /// 查询或控制另一个终端中运行的扫描（进度、已发现结果、速率、暂停、停止）
+    Ctl(CtlArgs),
+    /// 从内部发布地址检查并安装新版本
+    SelfUpdate(SelfUpdateArgs),
+    /// 检查运行环境（ICMP权限、ping程序、文件描述符上限、输出目录、代理、DNS等）
+    Doctor(DoctorArgs),
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 enum NetCommands {
     /// Ping主机存活扫描
     #[command(name = "ping")]
     Ping(net::ping::PingArgs),
+    /// Web服务探测（状态码、标题、正文关键字查找）
+    #[command(name = "http")]
+    Http(net::http::HttpArgs),
+    /// 网络测绘（本地网段ARP+ICMP、远程网段ICMP+TCP，多网卡并行）
+    #[command(name = "map")]
+    Map(net::map::MapArgs),
+    /// ARP扫描本地网段的存活主机（需要root或CAP_NET_RAW，仅支持Linux）
+    #[command(name = "arpscan")]
+    ArpScan(net::arpscan::ArpScanArgs),
+    /// 按主机名字典解析内网域名（检测泛解析，结果可作为 --target-file 导入）
+    #[command(name = "dnssweep")]
+    DnsSweep(net::dnssweep::DnsSweepArgs),
 }
 
+// 只在启动时构造一次，参数结构体大小不影响性能
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 enum PentestCommands {
     /// 端口扫描
     #[command(name = "portscan")]
     PortScan(pentest::portscan::PortScanArgs),
+    /// 对已有扫描结果（masscan/nmap）中的开放端口执行补充探测
+    #[command(name = "enrich")]
+    Enrich(pentest::enrich::EnrichArgs),
+    /// 邮件服务（SMTP/POP3/IMAP）安全检查
+    #[command(name = "mail")]
+    Mail(pentest::mail::MailArgs),
+    /// 域控制器定位及LDAP匿名访问、签名要求检查（不使用凭据）
+    #[command(name = "adinfo")]
+    AdInfo(pentest::adinfo::AdInfoArgs),
 }
 
 #[tokio::main]
 async fn main() {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    console::init(cli.ascii);
+    console::set_verbose(cli.verbose);
+    finding::set_dedup(!cli.no_dedup);
+    set_language(cli.lang);
+    set_output_root(cli.output_dir.clone());
+    set_flat_output(cli.flat_output);
+    set_show_secrets(cli.show_secrets);
+    if let Some(ref dir) = cli.config_dir {
+        set_config_dir(dir.clone());
+    }
+    if let Err(e) = resources::init(cli.resources.clone()) {
+        eprintln!("{} 执行失败: {}", Icon::Fail, e);
+        process::exit(1);
+    }
+    if let Err(e) = dns::init(
+        &cli.dns_servers,
+        cli.dns.as_deref(),
+        cli.dns_allow_fallback,
+        &cli.dns_pins,
+    ) {
+        eprintln!("{} 执行失败: {}", Icon::Fail, e);
+        process::exit(1);
+    }
+    if let Err(e) = scope::init(
+        cli.scope_file.as_deref(),
+        cli.strict_scope,
+        cli.i_understand_active_scan,
+    ) {
+        eprintln!("{} 执行失败: {}", Icon::Fail, e);
+        process::exit(1);
+    }
 
-    let result = match cli.command {
-        Commands::Net { subcommand } => handle_net_command(subcommand).await,
-        Commands::Pentest { subcommand } => handle_pentest_command(subcommand).await,
+    if let Some(ref spec) = cli.window {
+        match ScanWindow::parse(spec, cli.timezone.as_deref()) {
+            Ok(window) => set_window(window),
+            Err(e) => {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    if cli.icmp_marker.is_some() && cfg!(target_os = "windows") {
+        eprintln!(
+            "{} Windows的ping不支持自定义填充数据，--icmp-marker 不生效",
+            Icon::Warn
+        );
+    }
+    set_identity(ProbeIdentity {
+        user_agent: cli.user_agent.clone(),
+        randomize_ua: cli.randomize_ua,
+        headers: cli.http_headers.clone(),
+        tcp_probe: cli.tcp_banner_probe.clone().map(|p| p.0),
+        icmp_marker: cli.icmp_marker.clone(),
+    });
+
+    match meta::resolve(
+        &cli.meta,
+        cli.window.as_deref(),
+        identity::identity().meta_entries(),
+    ) {
+        Ok(run_meta) => meta::set_meta(run_meta),
+        Err(e) => {
+            eprintln!("{} 执行失败: {}", Icon::Fail, e);
+            process::exit(1);
+        }
+    }
+
+    if let Some(ref path) = cli.sign_key {
+        match load_signing_key(path) {
+            Ok(key) => set_signing_key(key),
+            Err(e) => {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    if cli.redact_output {
+        let map = cli
+            .redact_map
+            .clone()
+            .unwrap_or_else(|| cli.output_dir.join(DEFAULT_MAPPING_FILE_NAME));
+        let salt = cli.redact_salt.as_deref().unwrap_or_default();
+        match Redactor::new(salt, cli.redact_keep_evidence).with_mapping_file(map) {
+            Ok(redactor) => {
+                if let Some(path) = redactor.mapping_file() {
+                    println!("{} 导出结果已脱敏，对照表: {}", Icon::Ok, path.display());
+                }
+                set_redactor(redactor);
+            }
+            Err(e) => {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    let mut command = cli.command;
+    if let Err(e) = apply_profile(&mut command, &matches) {
+        eprintln!("{} 执行失败: {}", Icon::Fail, e);
+        process::exit(1);
+    }
+
+    let started_at = Local::now();
+    let start = Instant::now();
+    let run_id = RunRecord::new_id();
+
+    let (module, targets, run_dir, result) = match command {
+        Commands::Net { subcommand } => {
+            let (module, targets) = describe_net_command(&subcommand);
+            meta::print_banner(module, started_at);
+            let run_dir = RunDir::allocate(&run_id, module);
+            context::watch_interrupt();
+            let ctx = ScanContext::cli()
+                .with_run_dir(run_dir.clone())
+                .with_control();
+            let control = start_control(&ctx, run_dir.as_ref(), &run_id, module);
+            let result = handle_net_command(subcommand, &ctx).await;
+            drop(control);
+            (module, targets, run_dir, result)
+        }
+        Commands::Pentest { subcommand } => {
+            let (module, targets) = describe_pentest_command(&subcommand);
+            meta::print_banner(module, started_at);
+            let run_dir = RunDir::allocate(&run_id, module);
+            context::watch_interrupt();
+            let ctx = ScanContext::cli()
+                .with_run_dir(run_dir.clone())
+                .with_control();
+            let control = start_control(&ctx, run_dir.as_ref(), &run_id, module);
+            let result = handle_pentest_command(subcommand, &ctx).await;
+            drop(control);
+            (module, targets, run_dir, result)
+        }
+        Commands::History(args) => {
+            if let Err(e) = history::run(&args) {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+            return;
+        }
+        Commands::Assets(args) => {
+            if let Err(e) = assets::run(&args) {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+            return;
+        }
+        Commands::Report(args) => {
+            if let Err(e) = report::run(&args).await {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+            return;
+        }
+        Commands::Template(args) => {
+            if let Err(e) = template::run(&args) {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+            return;
+        }
+        Commands::Resources(args) => {
+            if let Err(e) = resources::run(&args) {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+            return;
+        }
+        Commands::Config(args) => {
+            if let Err(e) = config::run(&args, &Cli::command(), &matches) {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+            return;
+        }
+        Commands::Profile(args) => {
+            if let Err(e) = profile::run(&args) {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+            return;
+        }
+        Commands::Serve(args) => {
+            if let Err(e) = serve::run(&args).await {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+            return;
+        }
+        Commands::Ctl(args) => {
+            if let Err(e) = ctl::run(&args).await {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+            return;
+        }
+        Commands::Doctor(args) => match doctor::run(&args).await {
+            Ok(code) => process::exit(code),
+            Err(e) => {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+        },
+        Commands::SelfUpdate(args) => match update::run(&args).await {
+            Ok(UpdateOutcome::Available) => process::exit(update::UPDATE_AVAILABLE_EXIT_CODE),
+            Ok(_) => return,
+            Err(e) => {
+                eprintln!("{} 执行失败: {}", Icon::Fail, e);
+                process::exit(1);
+            }
+        },
     };
 
+    let dns_stats = dns::stats();
+    if let Some(stats) = dns_stats {
+        println!(
+            "   DNS: 解析 {} 次，命中缓存 {} 次，失败 {} 次",
+            stats.queries, stats.cache_hits, stats.failures
+        );
+    }
+
+    if !cli.no_history {
+        let summary = result.as_ref().cloned().unwrap_or_default();
+        let record = RunRecord {
+            id: run_id,
+            module: module.to_string(),
+            // 敏感参数的值不写入历史记录
+            command_line: redact_command_line(
+                &std::env::args().collect::<Vec<_>>(),
+                &sensitive_args(&Cli::command()),
+            ),
+            targets,
+            started_at: started_at.to_rfc3339(),
+            duration_ms: start.elapsed().as_millis() as u64,
+            total: summary.total,
+            succeeded: summary.succeeded,
+            outputs: summary.outputs,
+            exit_status: if result.is_ok() { "成功" } else { "失败" }.to_string(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            concurrency: summary.concurrency,
+            run_dir: run_dir
+                .filter(|r| r.exists())
+                .map(|r| r.path().display().to_string()),
+            dns: dns_stats,
+            timing: summary.timing,
+            meta: meta::current(),
+        };
+        history::record_run(&history::history_file(), &record);
+    }
+
     if let Err(e) = result {
-        eprintln!("❌ 执行失败: {}", e);
+        eprintln!("{} 执行失败: {}", Icon::Fail, e);
         process::exit(1);
     }
 }
 
+/// 为扫描开启控制通道（使用平铺输出结构时没有运行目录，不开启）
+///
+/// 开启失败只提示，不影响扫描。
+fn start_control(
+    ctx: &ScanContext,
+    run_dir: Option<&Arc<RunDir>>,
+    run_id: &str,
+    module: &str,
+) -> Option<ControlServer> {
+    match ControlServer::start(ctx, run_dir?, run_id, module) {
+        Ok(server) => {
+            println!(
+                "{} 控制通道已开启，可在其他终端执行: gxtools ctl {} status",
+                Icon::Config,
+                run_id
+            );
+            Some(server)
+        }
+        Err(e) => {
+            println!("{} 无法开启控制通道: {}", Icon::Warn, e);
+            None
+        }
+    }
+}
+
+/// 按 --profile / --save-profile 套用或保存扫描命令的配置档
+fn apply_profile(
+    command: &mut Commands,
+    matches: &ArgMatches,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let root = Cli::command();
+    let (cmd, sub_matches) = leaf_command(&root, matches);
+    match command {
+        Commands::Net {
+            subcommand: NetCommands::Ping(args),
+        } => {
+            let options = args.profile_args.clone();
+            profile::resolve(args, &options, "net ping", cmd, sub_matches)
+        }
+        Commands::Pentest {
+            subcommand: PentestCommands::PortScan(args),
+        } => {
+            let options = args.profile_args.clone();
+            profile::resolve(args, &options, "pentest portscan", cmd, sub_matches)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 沿子命令找到最终执行的命令定义及其解析结果
+fn leaf_command<'a>(root: &'a Command, matches: &'a ArgMatches) -> (&'a Command, &'a ArgMatches) {
+    let (mut cmd, mut matches) = (root, matches);
+    while let Some((name, sub_matches)) = matches.subcommand() {
+        match cmd.find_subcommand(name) {
+            Some(sub) => (cmd, matches) = (sub, sub_matches),
+            None => break,
+        }
+    }
+    (cmd, matches)
+}
+
+/// 返回网络测试命令的模块名和目标描述（用于历史记录）
+fn describe_net_command(cmd: &NetCommands) -> (&'static str, String) {
+    match cmd {
+        NetCommands::Ping(args) => (
+            "net ping",
+            describe_targets(args.target.as_deref(), &args.sources),
+        ),
+        NetCommands::Http(args) => (
+            "net http",
+            describe_targets(args.target.as_deref(), &args.sources),
+        ),
+        NetCommands::Map(args) => (
+            "net map",
+            args.target
+                .clone()
+                .unwrap_or_else(|| "本地网段".to_string()),
+        ),
+        NetCommands::ArpScan(args) => (
+            "net arpscan",
+            describe_targets(args.target.as_deref(), &args.sources),
+        ),
+        NetCommands::DnsSweep(args) => ("net dnssweep", args.domain.join(",")),
+    }
+}
+
+/// 返回渗透测试命令的模块名和目标描述（用于历史记录）
+fn describe_pentest_command(cmd: &PentestCommands) -> (&'static str, String) {
+    match cmd {
+        PentestCommands::PortScan(args) => (
+            "pentest portscan",
+            describe_targets(args.targets.as_deref(), &args.sources),
+        ),
+        PentestCommands::Enrich(args) => ("pentest enrich", args.input.display().to_string()),
+        PentestCommands::Mail(args) => (
+            "pentest mail",
+            describe_targets(args.targets.as_deref(), &args.sources),
+        ),
+        PentestCommands::AdInfo(args) => {
+            let mut target = describe_targets(args.targets.as_deref(), &args.sources);
+            if let Some(ref domain) = args.domain {
+                target = [domain.clone(), target]
+                    .into_iter()
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" + ");
+            }
+            ("pentest adinfo", target)
+        }
+    }
+}
+
+/// 合并 -t 与其他目标来源的描述
+fn describe_targets(target: Option<&str>, sources: &TargetSourceArgs) -> String {
+    let mut parts: Vec<String> = target.map(str::to_string).into_iter().collect();
+    if let Some(ref path) = sources.target_xlsx {
+        parts.push(format!("xlsx:{}", path.display()));
+    }
+    if let Some(ref path) = sources.target_file {
+        parts.push(format!("file:{}", path.display()));
+    }
+    parts.join(" + ")
+}
+
 async fn handle_net_command(
     cmd: NetCommands,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ctx: &ScanContext,
+) -> Result<RunSummary, Box<dyn std::error::Error + Send + Sync>> {
     match cmd {
-        NetCommands::Ping(args) => net::ping::run(&args).await,
+        NetCommands::Ping(args) => net::ping::run_with(&args, ctx).await,
+        NetCommands::Http(args) => net::http::run_with(&args, ctx).await,
+        NetCommands::Map(args) => net::map::run_with(&args, ctx).await,
+        NetCommands::ArpScan(args) => net::arpscan::run_with(&args, ctx).await,
+        NetCommands::DnsSweep(args) => net::dnssweep::run_with(&args, ctx).await,
     }
 }
 
 async fn handle_pentest_command(
     cmd: PentestCommands,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ctx: &ScanContext,
+) -> Result<RunSummary, Box<dyn std::error::Error + Send + Sync>> {
     match cmd {
-        PentestCommands::PortScan(args) => pentest::portscan::run(&args).await,
+        PentestCommands::PortScan(args) => pentest::portscan::run_with(&args, ctx).await,
+        PentestCommands::Enrich(args) => pentest::enrich::run_with(&args, ctx).await,
+        PentestCommands::Mail(args) => pentest::mail::run_with(&args, ctx).await,
+        PentestCommands::AdInfo(args) => pentest::adinfo::run_with(&args, ctx).await,
     }
 }
@@ -0,0 +1,476 @@
+// src/utils/adaptive.rs
+use super::ScanProgress;
+use super::console::Icon;
+use super::limits::ConcurrencySpec;
+use super::run_dir::SummaryItem;
+use super::stats::Outcome;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// 自适应并发的默认下限
+pub const DEFAULT_MIN: usize = 50;
+
+/// 自适应并发的默认上限
+pub const DEFAULT_MAX: usize = 2000;
+
+/// 实际扫描时每隔多久调整一次
+const ADJUST_INTERVAL: Duration = Duration::from_secs(3);
+
+/// 回放时每完成多少个任务调整一次（不依赖时间，结果可复现）
+const REPLAY_WINDOW: u64 = 200;
+
+/// 一个窗口内至少完成多少个任务才调整（样本太少时继续累积）
+const MIN_SAMPLES: u64 = 20;
+
+/// 出错率超过此值时并发减半
+const BACKOFF_ERROR_RATE: f64 = 0.05;
+
+/// 出错率不超过此值时增加并发
+const RAMP_ERROR_RATE: f64 = 0.01;
+
+/// 每次增加的并发占上限的比例（加性增加）
+const RAMP_STEPS: usize = 20;
+
+/// 并发的上下限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdaptiveBounds {
+    /// 下限（也是初始并发）
+    pub min: usize,
+    /// 上限
+    pub max: usize,
+}
+
+/// 一个窗口内的任务结论计数（不含被取消的任务）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Window {
+    /// 完成数
+    pub completed: u64,
+    /// 出错数（端口扫描中含超时）
+    pub errored: u64,
+}
+
+impl Window {
+    /// 计入一个任务的结论
+    pub fn record(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Cancelled => return,
+            Outcome::Errored => self.errored += 1,
+            Outcome::Succeeded | Outcome::Failed => {}
+        }
+        self.completed += 1;
+    }
+
+    /// 出错率（0~1）
+    pub fn error_rate(&self) -> f64 {
+        self.errored as f64 / self.completed.max(1) as f64
+    }
+}
+
+/// AIMD 调整规则：出错率高时并发减半，出错率低且并发已用满时加上固定步长
+#[derive(Debug, Clone)]
+pub struct Aimd {
+    bounds: AdaptiveBounds,
+    limit: usize,
+    step: usize,
+}
+
+impl Aimd {
+    /// 从下限开始
+    pub fn new(bounds: AdaptiveBounds) -> Self {
+        Self {
+            bounds,
+            limit: bounds.min,
+            step: (bounds.max / RAMP_STEPS).max(1),
+        }
+    }
+
+    /// 当前并发
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// 按一个窗口的结论调整并发
+    ///
+    /// # 参数
+    /// * `window` - 窗口内的结论计数
+    /// * `saturated` - 窗口内是否有探测因并发用满而等待（受速率限制时不会用满，也就不再增加）
+    ///
+    /// # 返回
+    /// * `Some(原因)` - 并发已改变
+    /// * `None` - 保持不变
+    pub fn observe(&mut self, window: &Window, saturated: bool) -> Option<String> {
+        let rate = window.error_rate();
+        let (limit, reason) = if rate > BACKOFF_ERROR_RATE {
+            (
+                (self.limit / 2).max(self.bounds.min),
+                format!(
+                    "出错率 {:.1}% 超过 {:.0}%，减半",
+                    rate * 100.0,
+                    BACKOFF_ERROR_RATE * 100.0
+                ),
+            )
+        } else if rate <= RAMP_ERROR_RATE && saturated {
+            (
+                (self.limit + self.step).min(self.bounds.max),
+                format!("出错率 {:.1}%，增加 {}", rate * 100.0, self.step),
+            )
+        } else {
+            return None;
+        };
+        if limit == self.limit {
+            return None;
+        }
+        self.limit = limit;
+        Some(reason)
+    }
+}
+
+/// 可在扫描过程中调整的并发许可
+///
+/// 工作池按上限分发，每次探测发起前再从这里取得许可（见 [`Throttle::acquire`](super::timing::Throttle::acquire)），
+/// 调低并发时进行中的探测不受影响，释放后不再补足。
+#[derive(Debug)]
+pub struct AdaptiveLimiter {
+    limit: AtomicUsize,
+    active: AtomicUsize,
+    saturated: AtomicBool,
+    notify: Notify,
+}
+
+/// 并发许可，释放后等待中的下一个探测才能发起
+pub struct AdaptiveSlot {
+    limiter: Arc<AdaptiveLimiter>,
+}
+
+impl AdaptiveLimiter {
+    /// 创建许可
+    ///
+    /// # 参数
+    /// * `limit` - 初始并发（0按1处理）
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit: AtomicUsize::new(limit.max(1)),
+            active: AtomicUsize::new(0),
+            saturated: AtomicBool::new(false),
+            notify: Notify::new(),
+        }
+    }
+
+    /// 当前并发
+    pub fn limit(&self) -> usize {
+        self.limit.load(Ordering::SeqCst)
+    }
+
+    /// 进行中的探测数
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// 调整并发（0按1处理），调高时等待中的探测立即发起
+    pub fn set_limit(&self, limit: usize) {
+        self.limit.store(limit.max(1), Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// 上次查询以来是否有探测因并发用满而等待（查询后清零）
+    pub fn take_saturated(&self) -> bool {
+        self.saturated.swap(false, Ordering::SeqCst)
+    }
+
+    /// 等待取得一个许可
+    pub async fn acquire(self: &Arc<Self>) -> AdaptiveSlot {
+        loop {
+            // 先登记再检查，避免检查之后、等待之前的释放被错过
+            let notified = self.notify.notified();
+            let mut notified = std::pin::pin!(notified);
+            notified.as_mut().enable();
+            let acquired = self
+                .active
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| {
+                    (active < self.limit()).then_some(active + 1)
+                })
+                .is_ok();
+            if acquired {
+                return AdaptiveSlot {
+                    limiter: self.clone(),
+                };
+            }
+            self.saturated.store(true, Ordering::SeqCst);
+            notified.await;
+        }
+    }
+}
+
+impl Drop for AdaptiveSlot {
+    fn drop(&mut self) {
+        self.limiter.active.fetch_sub(1, Ordering::SeqCst);
+        self.limiter.notify.notify_one();
+    }
+}
+
+/// 并发随时间变化的一个点（写入运行目录的 `concurrency.json`，报告中绘制为折线）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConcurrencyPoint {
+    /// 距扫描开始的秒数（回放时按任务数调整，不记录时间）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_secs: Option<u64>,
+    /// 调整时已完成的任务数
+    pub completed: u64,
+    /// 调整后的并发
+    pub concurrency: usize,
+    /// 窗口内的出错率（%）
+    pub error_rate: f64,
+    /// 调整原因
+    pub reason: String,
+}
+
+/// 调整的节拍
+#[derive(Debug, Clone, Copy)]
+enum Clock {
+    /// 每隔一段时间
+    Time(Duration),
+    /// 每完成一定数量的任务（回放）
+    Tasks(u64),
+}
+
+#[derive(Debug)]
+struct ControllerState {
+    aimd: Aimd,
+    window: Window,
+    window_start: Instant,
+    completed: u64,
+    series: Vec<ConcurrencyPoint>,
+}
+
+/// 自适应并发控制器（`-c auto-adaptive`）
+///
+/// 每个任务的结论经进度条交给控制器（见 [`ScanProgress::record`]），
+/// 按窗口内的出错率调整 [`AdaptiveLimiter`] 的并发：实际扫描时每隔几秒调整一次，
+/// 回放时每完成固定数量的任务调整一次且总视为并发已用满，相同的结论序列得到相同的调整。
+#[derive(Debug)]
+pub struct AdaptiveController {
+    limiter: Arc<AdaptiveLimiter>,
+    clock: Clock,
+    started: Instant,
+    state: Mutex<ControllerState>,
+}
+
+impl AdaptiveController {
+    /// 创建控制器，从下限开始
+    ///
+    /// # 参数
+    /// * `bounds` - 并发的上下限
+    /// * `replay` - 是否为回放（按任务数而不是时间调整）
+    pub fn new(bounds: AdaptiveBounds, replay: bool) -> Self {
+        let aimd = Aimd::new(bounds);
+        let now = Instant::now();
+        Self {
+            limiter: Arc::new(AdaptiveLimiter::new(aimd.limit())),
+            clock: if replay {
+                Clock::Tasks(REPLAY_WINDOW)
+            } else {
+                Clock::Time(ADJUST_INTERVAL)
+            },
+            started: now,
+            state: Mutex::new(ControllerState {
+                series: vec![ConcurrencyPoint {
+                    elapsed_secs: (!replay).then_some(0),
+                    completed: 0,
+                    concurrency: aimd.limit(),
+                    error_rate: 0.0,
+                    reason: "初始".to_string(),
+                }],
+                aimd,
+                window: Window::default(),
+                window_start: now,
+                completed: 0,
+            }),
+        }
+    }
+
+    /// 按并发参数创建控制器（不是 `auto-adaptive` 时为 `None`）
+    ///
+    /// # 参数
+    /// * `spec` - 并发参数
+    /// * `effective` - 工作池的并发（见 [`resolve_concurrency`](super::limits::resolve_concurrency)），作为上限
+    /// * `replay` - 是否为回放
+    pub fn for_spec(spec: ConcurrencySpec, effective: usize, replay: bool) -> Option<Self> {
+        let ConcurrencySpec::Adaptive { min, .. } = spec else {
+            return None;
+        };
+        let bounds = AdaptiveBounds {
+            min: min.min(effective).max(1),
+            max: effective.max(1),
+        };
+        Some(Self::new(bounds, replay))
+    }
+
+    /// 控制器调整的并发许可
+    pub fn limiter(&self) -> Arc<AdaptiveLimiter> {
+        self.limiter.clone()
+    }
+
+    /// 计入一个任务的结论，到达节拍时调整并发并在进度条上方打印
+    ///
+    /// # 参数
+    /// * `outcome` - 任务的结论
+    /// * `progress` - 任务所在阶段的进度条
+    pub fn observe(&self, outcome: Outcome, progress: &ScanProgress) {
+        let mut state = self.state.lock().unwrap();
+        state.window.record(outcome);
+        if outcome != Outcome::Cancelled {
+            state.completed += 1;
+        }
+        let due = match self.clock {
+            Clock::Time(interval) => state.window_start.elapsed() >= interval,
+            Clock::Tasks(n) => state.window.completed >= n,
+        };
+        if !due || state.window.completed < MIN_SAMPLES {
+            return;
+        }
+
+        let window = std::mem::take(&mut state.window);
+        state.window_start = Instant::now();
+        let saturated = match self.clock {
+            Clock::Time(_) => self.limiter.take_saturated(),
+            Clock::Tasks(_) => true,
+        };
+        let from = state.aimd.limit();
+        let Some(reason) = state.aimd.observe(&window, saturated) else {
+            return;
+        };
+        let to = state.aimd.limit();
+        self.limiter.set_limit(to);
+        progress.println(format!(
+            "{} 自适应并发: {} → {}（{}）",
+            Icon::Config,
+            from,
+            to,
+            reason
+        ));
+        let point = ConcurrencyPoint {
+            elapsed_secs: match self.clock {
+                Clock::Time(_) => Some(self.started.elapsed().as_secs()),
+                Clock::Tasks(_) => None,
+            },
+            completed: state.completed,
+            concurrency: to,
+            error_rate: (window.error_rate() * 1000.0).round() / 10.0,
+            reason,
+        };
+        state.series.push(point);
+    }
+
+    /// 并发随时间的变化（第一个点为初始并发）
+    pub fn series(&self) -> Vec<ConcurrencyPoint> {
+        self.state.lock().unwrap().series.clone()
+    }
+
+    /// 写入统计摘要的条目
+    pub fn summary_item(&self) -> SummaryItem {
+        let series = self.series();
+        let values = series.iter().map(|p| p.concurrency);
+        let (low, high) = (
+            values.clone().min().unwrap_or_default(),
+            values.max().unwrap_or_default(),
+        );
+        (
+            "自适应并发".to_string(),
+            format!(
+                "{} → {}，调整 {} 次（最低 {}，最高 {}）",
+                series.first().map_or(0, |p| p.concurrency),
+                series.last().map_or(0, |p| p.concurrency),
+                series.len() - 1,
+                low,
+                high
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: AdaptiveBounds = AdaptiveBounds { min: 50, max: 400 };
+
+    fn window(completed: u64, errored: u64) -> Window {
+        Window { completed, errored }
+    }
+
+    #[test]
+    fn test_aimd_ramps_and_backs_off_within_bounds() {
+        let mut aimd = Aimd::new(BOUNDS);
+        assert_eq!(aimd.limit(), 50);
+        // 并发未用满（如受速率限制）时不增加
+        assert_eq!(aimd.observe(&window(100, 0), false), None);
+        for _ in 0..20 {
+            aimd.observe(&window(100, 0), true);
+        }
+        assert_eq!(aimd.limit(), 400);
+        // 出错率介于两个阈值之间时保持
+        assert_eq!(aimd.observe(&window(100, 3), true), None);
+        assert!(
+            aimd.observe(&window(100, 20), true)
+                .unwrap()
+                .contains("减半")
+        );
+        assert_eq!(aimd.limit(), 200);
+        for _ in 0..5 {
+            aimd.observe(&window(100, 50), true);
+        }
+        assert_eq!(aimd.limit(), 50);
+    }
+
+    #[test]
+    fn test_replay_adjustments_are_deterministic() {
+        let run = || {
+            let controller = AdaptiveController::new(BOUNDS, true);
+            let progress = ScanProgress::new(0);
+            progress.set_hidden(true);
+            for i in 0..2000u64 {
+                // 前半段全部成功，后半段每4个出错1个
+                let outcome = if i >= 1000 && i % 4 == 0 {
+                    Outcome::Errored
+                } else {
+                    Outcome::Succeeded
+                };
+                controller.observe(outcome, &progress);
+            }
+            (controller.series(), controller.limiter().limit())
+        };
+        let (series, limit) = run();
+        assert_eq!((series.clone(), limit), run());
+        let values: Vec<usize> = series.iter().map(|p| p.concurrency).collect();
+        assert_eq!(values, [50, 70, 90, 110, 130, 150, 75, 50]);
+        assert!(series.iter().all(|p| p.elapsed_secs.is_none()));
+        assert_eq!(limit, 50);
+    }
+
+    #[tokio::test]
+    async fn test_limiter_follows_limit_changes() {
+        let limiter = Arc::new(AdaptiveLimiter::new(2));
+        let a = limiter.acquire().await;
+        let _b = limiter.acquire().await;
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire().await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+        assert!(limiter.take_saturated());
+
+        limiter.set_limit(1);
+        drop(a);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+        assert_eq!(limiter.active(), 1);
+
+        limiter.set_limit(3);
+        let _c = waiting.await.unwrap();
+        assert_eq!(limiter.active(), 2);
+    }
+}
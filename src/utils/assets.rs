@@ -0,0 +1,673 @@
+// src/utils/assets.rs
+use super::blocking::{BlockingStage, run_stage};
+use super::console::Icon;
+use super::run_dir::SummaryItem;
+use super::targets::Tags;
+use super::{config_dir, config_file, ip_sort_key, load_config_section};
+use chrono::{DateTime, Local};
+use clap::Args;
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior, params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 配置文件中的段落名
+pub const CONFIG_SECTION: &str = "assets";
+
+/// 资产库默认文件名（位于配置目录下）
+pub const ASSET_DB_FILE_NAME: &str = "assets.db";
+
+/// 默认多少天没有响应视为长期未响应
+pub const DEFAULT_STALE_DAYS: u32 = 30;
+
+/// 其他扫描正在写入时的最长等待时间
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 依次执行的结构迁移，第 N 项把库从版本 N 升级到 N+1（只能追加，不能修改已发布的项）
+const MIGRATIONS: &[&str] = &[
+    // 版本1：主机及其别名
+    "CREATE TABLE assets (
+        ip TEXT PRIMARY KEY,
+        first_seen TEXT NOT NULL,
+        last_seen TEXT NOT NULL,
+        last_scanned TEXT NOT NULL,
+        open_ports TEXT NOT NULL DEFAULT '',
+        os_guess TEXT,
+        tags TEXT NOT NULL DEFAULT '{}'
+    );
+    CREATE TABLE aliases (
+        ip TEXT NOT NULL REFERENCES assets(ip) ON DELETE CASCADE,
+        alias TEXT NOT NULL,
+        PRIMARY KEY (ip, alias)
+    );
+    CREATE INDEX aliases_alias ON aliases(alias);",
+];
+
+/// 当前程序使用的资产库结构版本
+pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// 资产库设置（在配置文件的 `assets` 段落中开启）
+///
+/// ```yaml
+/// assets:
+///   enabled: true
+///   file: /data/gxtools/assets.db
+///   stale_days: 30
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssetConfig {
+    /// 每次扫描后更新资产库并标注变化
+    pub enabled: bool,
+    /// 资产库文件，默认为配置目录下的 assets.db
+    pub file: Option<PathBuf>,
+    /// 超过多少天没有响应标注为长期未响应
+    pub stale_days: u32,
+}
+
+impl Default for AssetConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            file: None,
+            stale_days: DEFAULT_STALE_DAYS,
+        }
+    }
+}
+
+impl AssetConfig {
+    /// 从配置文件读取资产库设置
+    pub fn load() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        load_config_section(&config_file(), CONFIG_SECTION)
+    }
+
+    /// 资产库文件路径
+    pub fn path(&self) -> PathBuf {
+        self.file
+            .clone()
+            .unwrap_or_else(|| config_dir().join(ASSET_DB_FILE_NAME))
+    }
+}
+
+/// 资产库参数
+#[derive(Args, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssetArgs {
+    /// 本次运行不读写资产库（资产库在配置文件的 assets 段落中开启）
+    #[arg(long, env = "GXTOOLS_NO_ASSET_DB")]
+    pub no_asset_db: bool,
+}
+
+/// 一次扫描对一个主机的观察
+#[derive(Debug, Clone, Default)]
+pub struct Observation {
+    pub ip: String,
+    /// 本次是否有响应（存活或有端口应答）
+    pub responding: bool,
+    /// 本次发现的开放端口（只在扫描了端口时有意义）
+    pub open_ports: BTreeSet<u16>,
+    /// 推测的操作系统
+    pub os_guess: Option<String>,
+    /// 指向该IP的其他目标写法（如主机名）
+    pub aliases: Vec<String>,
+    /// 目标标签
+    pub tags: Tags,
+}
+
+/// 与上次记录相比的变化（标注在结果行上）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetChange {
+    /// 资产库中没有的主机
+    New,
+    /// 开放端口有增减（只比较本次扫描了的端口）
+    PortsChanged { added: Vec<u16>, removed: Vec<u16> },
+    /// 已知主机超过设定天数没有响应
+    Stale { last_seen: String },
+}
+
+impl fmt::Display for AssetChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetChange::New => f.write_str("新增资产"),
+            AssetChange::PortsChanged { added, removed } => {
+                let ports: Vec<String> = added
+                    .iter()
+                    .map(|p| format!("+{}", p))
+                    .chain(removed.iter().map(|p| format!("-{}", p)))
+                    .collect();
+                write!(f, "端口变化 ({})", ports.join(", "))
+            }
+            AssetChange::Stale { last_seen } => {
+                write!(
+                    f,
+                    "长期未响应（上次响应 {}）",
+                    last_seen.get(..10).unwrap_or(last_seen)
+                )
+            }
+        }
+    }
+}
+
+/// 资产库中的一个主机
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AssetRecord {
+    pub ip: String,
+    /// 首次发现时间（RFC3339）
+    pub first_seen: String,
+    /// 最近一次有响应的时间
+    pub last_seen: String,
+    /// 最近一次被扫描的时间
+    pub last_scanned: String,
+    /// 最近已知的开放端口
+    pub open_ports: Vec<u16>,
+    pub os_guess: Option<String>,
+    pub tags: Tags,
+    pub aliases: Vec<String>,
+}
+
+impl AssetRecord {
+    /// 距现在超过 `days` 天没有响应
+    pub fn is_stale(&self, days: u32) -> bool {
+        DateTime::parse_from_rfc3339(&self.last_seen)
+            .map(|t| Local::now().signed_duration_since(t) > chrono::Duration::days(days as i64))
+            .unwrap_or(false)
+    }
+}
+
+/// 本次运行的资产变化统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChangeCounts {
+    pub new: usize,
+    pub ports_changed: usize,
+    pub stale: usize,
+}
+
+impl ChangeCounts {
+    /// 统计各类变化的主机数
+    pub fn count(changes: &HashMap<String, Vec<AssetChange>>) -> Self {
+        let mut counts = Self::default();
+        for change in changes.values().flatten() {
+            match change {
+                AssetChange::New => counts.new += 1,
+                AssetChange::PortsChanged { .. } => counts.ports_changed += 1,
+                AssetChange::Stale { .. } => counts.stale += 1,
+            }
+        }
+        counts
+    }
+
+    /// 运行摘要中的一项
+    pub fn summary_item(&self) -> SummaryItem {
+        (
+            "资产库".to_string(),
+            format!(
+                "新增 {} 个，端口变化 {} 个，长期未响应 {} 个",
+                self.new, self.ports_changed, self.stale
+            ),
+        )
+    }
+}
+
+/// 资产库（SQLite）
+///
+/// 以IP为键记录各次扫描发现的主机：首次/最近发现时间、最近已知的开放端口、系统推测及标签，
+/// 别名（主机名等）另表记录，可以按别名查找。
+///
+/// 多个扫描可能同时写入同一个库：库以WAL模式打开，每次写入在一个立即加锁的事务中完成，
+/// 其他扫描正在写入时等待（最长30秒）而不是报错。
+/// 结构版本记录在 `user_version` 中，打开时按 [`MIGRATIONS`] 逐步升级；
+/// 库的版本高于程序支持的版本时拒绝打开，避免旧版本程序写坏新结构。
+pub struct AssetDb {
+    conn: Connection,
+    stale_days: u32,
+}
+
+impl AssetDb {
+    /// 打开（不存在时创建）资产库并升级到当前结构版本
+    ///
+    /// # 参数
+    /// * `path` - 资产库文件
+    /// * `stale_days` - 超过多少天没有响应标注为长期未响应
+    pub fn open(path: &Path, stale_days: u32) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("无法创建资产库目录 {}: {}", parent.display(), e))?;
+        }
+        let mut conn = Connection::open(path)
+            .map_err(|e| format!("无法打开资产库 {}: {}", path.display(), e))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrate(&mut conn).map_err(|e| format!("资产库 {}: {}", path.display(), e))?;
+        Ok(Self { conn, stale_days })
+    }
+
+    /// 按配置文件及本次参数打开资产库
+    ///
+    /// # 返回
+    /// * `Ok(None)` - 未开启资产库或本次指定了 `--no-asset-db`
+    pub fn open_for_run(args: &AssetArgs) -> Result<Option<Self>, Box<dyn Error + Send + Sync>> {
+        let config = AssetConfig::load()?;
+        if !config.enabled || args.no_asset_db {
+            return Ok(None);
+        }
+        let path = config.path();
+        let db = Self::open(&path, config.stale_days)?;
+        println!("{} 资产库: {}", Icon::Config, path.display());
+        Ok(Some(db))
+    }
+
+    /// 记录本次扫描的观察结果，返回各主机与上次记录相比的变化
+    ///
+    /// 没有响应的未知主机不写入；扫描了端口时（`scanned_ports` 不为空）只比较本次扫描了的端口，
+    /// 未扫描端口的已知状态保留。
+    ///
+    /// # 参数
+    /// * `observations` - 各主机的观察结果
+    /// * `scanned_ports` - 本次扫描的端口（只做存活探测时为 `None`）
+    ///
+    /// # 返回
+    /// * `Ok(HashMap)` - IP => 变化（没有变化的主机不出现）
+    pub fn record(
+        &mut self,
+        observations: &[Observation],
+        scanned_ports: Option<&BTreeSet<u16>>,
+    ) -> Result<HashMap<String, Vec<AssetChange>>, Box<dyn Error + Send + Sync>> {
+        let now = Local::now().to_rfc3339();
+        let mut changes: HashMap<String, Vec<AssetChange>> = HashMap::new();
+        let tx = self
+            .conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        for obs in observations {
+            let previous = load_record(&tx, &obs.ip)?;
+            let mut noted = Vec::new();
+            match previous {
+                None if !obs.responding => continue,
+                None => {
+                    let open = scanned_ports.map(|_| &obs.open_ports);
+                    tx.execute(
+                        "INSERT INTO assets (ip, first_seen, last_seen, last_scanned, open_ports, os_guess, tags)
+                         VALUES (?1, ?2, ?2, ?2, ?3, ?4, ?5)",
+                        params![
+                            obs.ip,
+                            now,
+                            join_ports(open.into_iter().flatten()),
+                            obs.os_guess,
+                            serde_json::to_string(&obs.tags)?
+                        ],
+                    )?;
+                    noted.push(AssetChange::New);
+                }
+                Some(previous) if obs.responding => {
+                    let known: BTreeSet<u16> = previous.open_ports.iter().copied().collect();
+                    let mut open_ports = known.clone();
+                    if let Some(scanned) = scanned_ports {
+                        let added: Vec<u16> = obs.open_ports.difference(&known).copied().collect();
+                        let removed: Vec<u16> = known
+                            .iter()
+                            .filter(|p| scanned.contains(p) && !obs.open_ports.contains(p))
+                            .copied()
+                            .collect();
+                        open_ports.retain(|p| !scanned.contains(p));
+                        open_ports.extend(&obs.open_ports);
+                        if !added.is_empty() || !removed.is_empty() {
+                            noted.push(AssetChange::PortsChanged { added, removed });
+                        }
+                    }
+                    let mut tags = previous.tags;
+                    tags.extend(obs.tags.clone());
+                    tx.execute(
+                        "UPDATE assets SET last_seen = ?2, last_scanned = ?2, open_ports = ?3,
+                         os_guess = COALESCE(?4, os_guess), tags = ?5 WHERE ip = ?1",
+                        params![
+                            obs.ip,
+                            now,
+                            join_ports(&open_ports),
+                            obs.os_guess,
+                            serde_json::to_string(&tags)?
+                        ],
+                    )?;
+                }
+                Some(previous) => {
+                    tx.execute(
+                        "UPDATE assets SET last_scanned = ?2 WHERE ip = ?1",
+                        params![obs.ip, now],
+                    )?;
+                    if previous.is_stale(self.stale_days) {
+                        noted.push(AssetChange::Stale {
+                            last_seen: previous.last_seen,
+                        });
+                    }
+                }
+            }
+            for alias in &obs.aliases {
+                tx.execute(
+                    "INSERT OR IGNORE INTO aliases (ip, alias) VALUES (?1, ?2)",
+                    params![obs.ip, alias],
+                )?;
+            }
+            if !noted.is_empty() {
+                changes.insert(obs.ip.clone(), noted);
+            }
+        }
+
+        tx.commit()?;
+        Ok(changes)
+    }
+
+    /// 全部主机（按IP排序）
+    pub fn list(&self) -> Result<Vec<AssetRecord>, Box<dyn Error + Send + Sync>> {
+        let mut stmt = self.conn.prepare("SELECT ip FROM assets")?;
+        let ips = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut records = Vec::with_capacity(ips.len());
+        for ip in ips {
+            records.extend(load_record(&self.conn, &ip)?);
+        }
+        records.sort_by_key(|r| ip_sort_key(&r.ip));
+        Ok(records)
+    }
+
+    /// 按IP或别名查找主机
+    pub fn get(&self, key: &str) -> Result<Option<AssetRecord>, Box<dyn Error + Send + Sync>> {
+        if let Some(record) = load_record(&self.conn, key)? {
+            return Ok(Some(record));
+        }
+        let ip: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT ip FROM aliases WHERE alias = ?1 ORDER BY ip LIMIT 1",
+                [key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match ip {
+            Some(ip) => load_record(&self.conn, &ip),
+            None => Ok(None),
+        }
+    }
+
+    /// 删除主机（连同其别名）
+    ///
+    /// # 返回
+    /// * `Ok(bool)` - 是否存在该主机
+    pub fn forget(&mut self, ip: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM assets WHERE ip = ?1", [ip])?
+            > 0)
+    }
+
+    /// 超过多少天没有响应视为长期未响应
+    pub fn stale_days(&self) -> u32 {
+        self.stale_days
+    }
+}
+
+/// 记录本次扫描并标注变化的阶段（数据库读写放到阻塞线程池执行）
+pub struct AssetStage {
+    pub db: AssetDb,
+    pub observations: Vec<Observation>,
+    pub scanned_ports: Option<BTreeSet<u16>>,
+}
+
+impl BlockingStage for AssetStage {
+    type Output = HashMap<String, Vec<AssetChange>>;
+    const NAME: &'static str = "资产库更新";
+
+    fn run(mut self) -> Result<Self::Output, Box<dyn Error + Send + Sync>> {
+        self.db
+            .record(&self.observations, self.scanned_ports.as_ref())
+    }
+}
+
+/// 扫描结束后更新资产库
+///
+/// 部分结果无法区分“没有响应”和“没来得及探测”，扫描被取消时不更新。
+///
+/// # 参数
+/// * `db` - 资产库（未开启时为 `None`）
+/// * `observations` - 各主机的观察结果
+/// * `scanned_ports` - 本次扫描的端口（只做存活探测时为 `None`）
+/// * `cancelled` - 扫描是否被取消
+///
+/// # 返回
+/// * `Ok(Some(HashMap))` - IP => 变化
+/// * `Ok(None)` - 没有更新资产库
+pub async fn update(
+    db: Option<AssetDb>,
+    observations: Vec<Observation>,
+    scanned_ports: Option<BTreeSet<u16>>,
+    cancelled: bool,
+) -> Result<Option<HashMap<String, Vec<AssetChange>>>, Box<dyn Error + Send + Sync>> {
+    let Some(db) = db else {
+        return Ok(None);
+    };
+    if cancelled {
+        println!("{} 扫描已取消，本次结果不写入资产库", Icon::Warn);
+        return Ok(None);
+    }
+    let stage = AssetStage {
+        db,
+        observations,
+        scanned_ports,
+    };
+    Ok(Some(run_stage(stage).await?))
+}
+
+/// 结果行上的变化标注
+pub fn change_notes(changes: &HashMap<String, Vec<AssetChange>>, ip: &str) -> Vec<String> {
+    changes
+        .get(ip)
+        .map(|c| c.iter().map(AssetChange::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// 按结构版本逐步升级
+fn migrate(conn: &mut Connection) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let version: i64 = tx.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    let version = version as usize;
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "资产库结构版本 {} 高于本程序支持的版本 {}，请升级程序",
+            version, SCHEMA_VERSION
+        )
+        .into());
+    }
+    for (from, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        tx.execute_batch(sql)
+            .map_err(|e| format!("升级到结构版本 {} 失败: {}", from + 1, e))?;
+    }
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION as i64)?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn load_record(
+    conn: &Connection,
+    ip: &str,
+) -> Result<Option<AssetRecord>, Box<dyn Error + Send + Sync>> {
+    let row = conn
+        .query_row(
+            "SELECT first_seen, last_seen, last_scanned, open_ports, os_guess, tags
+             FROM assets WHERE ip = ?1",
+            [ip],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            },
+        )
+        .optional()?;
+    let Some((first_seen, last_seen, last_scanned, open_ports, os_guess, tags)) = row else {
+        return Ok(None);
+    };
+    let mut stmt = conn.prepare("SELECT alias FROM aliases WHERE ip = ?1 ORDER BY alias")?;
+    let aliases = stmt
+        .query_map([ip], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some(AssetRecord {
+        ip: ip.to_string(),
+        first_seen,
+        last_seen,
+        last_scanned,
+        open_ports: open_ports
+            .split(',')
+            .filter_map(|p| p.parse().ok())
+            .collect(),
+        os_guess,
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+        aliases,
+    }))
+}
+
+fn join_ports<'a>(ports: impl IntoIterator<Item = &'a u16>) -> String {
+    ports
+        .into_iter()
+        .map(u16::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("gxr_assets_{}_{}.db", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn seen(ip: &str, ports: &[u16]) -> Observation {
+        Observation {
+            ip: ip.to_string(),
+            responding: true,
+            open_ports: ports.iter().copied().collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_changes_are_annotated_between_scans() {
+        let path = temp_db("changes");
+        let mut db = AssetDb::open(&path, 30).unwrap();
+        let scanned: BTreeSet<u16> = [22, 80, 443, 3389].into();
+
+        let first = db
+            .record(
+                &[
+                    seen("10.0.0.1", &[22, 80]),
+                    Observation {
+                        ip: "10.0.0.2".into(),
+                        ..Default::default()
+                    },
+                ],
+                Some(&scanned),
+            )
+            .unwrap();
+        assert_eq!(first["10.0.0.1"], [AssetChange::New]);
+        // 没有响应的未知主机不写入
+        assert!(!first.contains_key("10.0.0.2"));
+        assert!(db.get("10.0.0.2").unwrap().is_none());
+
+        // 只扫描了 80、3389 时，22 的已知状态保留
+        let partial: BTreeSet<u16> = [80, 3389].into();
+        let second = db
+            .record(&[seen("10.0.0.1", &[3389])], Some(&partial))
+            .unwrap();
+        assert_eq!(second["10.0.0.1"][0].to_string(), "端口变化 (+3389, -80)");
+        assert_eq!(db.get("10.0.0.1").unwrap().unwrap().open_ports, [22, 3389]);
+
+        // 存活探测不比较端口
+        assert!(
+            db.record(&[seen("10.0.0.1", &[])], None)
+                .unwrap()
+                .is_empty()
+        );
+
+        // 超过设定天数没有响应
+        db.conn
+            .execute(
+                "UPDATE assets SET last_seen = '2020-01-02T00:00:00+08:00'",
+                [],
+            )
+            .unwrap();
+        let silent = Observation {
+            ip: "10.0.0.1".into(),
+            ..Default::default()
+        };
+        let third = db.record(&[silent], Some(&scanned)).unwrap();
+        assert_eq!(
+            third["10.0.0.1"][0].to_string(),
+            "长期未响应（上次响应 2020-01-02）"
+        );
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_schema_is_versioned() {
+        let path = temp_db("schema");
+        {
+            let mut db = AssetDb::open(&path, 30).unwrap();
+            let mut obs = seen("10.0.0.1", &[]);
+            obs.aliases = vec!["web.corp.local".into()];
+            db.record(&[obs], None).unwrap();
+        }
+        // 重复打开不会重复迁移，按别名可以找到主机
+        let mut db = AssetDb::open(&path, 30).unwrap();
+        let version: i64 = db
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version as usize, SCHEMA_VERSION);
+        assert_eq!(db.get("web.corp.local").unwrap().unwrap().ip, "10.0.0.1");
+        assert!(db.forget("10.0.0.1").unwrap());
+        assert!(db.list().unwrap().is_empty());
+
+        // 更新版本程序创建的库拒绝打开
+        db.conn
+            .pragma_update(None, "user_version", SCHEMA_VERSION as i64 + 1)
+            .unwrap();
+        drop(db);
+        let err = AssetDb::open(&path, 30).err().unwrap().to_string();
+        assert!(err.contains("请升级程序"), "{}", err);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_concurrent_scans_do_not_lose_writes() {
+        let path = temp_db("concurrent");
+        AssetDb::open(&path, 30).unwrap();
+        let writers: Vec<_> = (0..4)
+            .map(|n| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let mut db = AssetDb::open(&path, 30).unwrap();
+                    for i in 0..25 {
+                        let ip = format!("10.{}.0.{}", n, i);
+                        db.record(&[seen(&ip, &[22])], None).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(AssetDb::open(&path, 30).unwrap().list().unwrap().len(), 100);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,74 @@
+// src/utils/blocking.rs
+use std::error::Error;
+
+/// CPU密集的处理阶段（Excel生成、正文解码与查找、相似页面聚类等）
+///
+/// 阶段持有所需的全部数据，由 [`run_stage`] 放到阻塞线程池执行；
+/// 异步运行时的工作线程只处理网络IO，进行中的探测不会因后处理而延迟。
+/// 在同步上下文（如交互界面、测试）中可以直接调用 [`BlockingStage::run`]。
+pub trait BlockingStage: Send + 'static {
+    /// 阶段的产出
+    type Output: Send + 'static;
+
+    /// 阶段名称（用于出错信息）
+    const NAME: &'static str;
+
+    /// 执行阶段
+    fn run(self) -> Result<Self::Output, Box<dyn Error + Send + Sync>>;
+}
+
+/// 在阻塞线程池中执行一个阶段
+///
+/// # 参数
+/// * `stage` - 要执行的阶段
+///
+/// # 返回
+/// * `Ok(Output)` - 阶段的产出
+/// * `Err` - 阶段出错或异常终止
+pub async fn run_stage<S: BlockingStage>(
+    stage: S,
+) -> Result<S::Output, Box<dyn Error + Send + Sync>> {
+    tokio::task::spawn_blocking(move || stage.run())
+        .await
+        .map_err(|e| format!("{}异常终止: {}", S::NAME, e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    struct Spin(Duration);
+
+    impl BlockingStage for Spin {
+        type Output = u32;
+        const NAME: &'static str = "测试阶段";
+
+        fn run(self) -> Result<u32, Box<dyn Error + Send + Sync>> {
+            let start = Instant::now();
+            while start.elapsed() < self.0 {
+                std::hint::spin_loop();
+            }
+            Ok(7)
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_stage_does_not_block_runtime() {
+        // 单线程运行时中，阶段执行期间计时任务照常推进
+        let ticker = tokio::spawn(async {
+            let mut ticks = 0;
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_millis(150) {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                ticks += 1;
+            }
+            ticks
+        });
+        assert_eq!(
+            run_stage(Spin(Duration::from_millis(200))).await.unwrap(),
+            7
+        );
+        assert!(ticker.await.unwrap() > 5);
+    }
+}
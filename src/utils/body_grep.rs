@@ -0,0 +1,315 @@
+// src/utils/body_grep.rs
+use clap::Args;
+use encoding_rs::{Encoding, UTF_8};
+use regex::Regex;
+use serde::Serialize;
+use std::error::Error;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+/// 每个模式最多保留的摘录数
+const MAX_EXCERPTS: usize = 3;
+
+/// 摘录在匹配前后各保留的字符数
+const EXCERPT_CONTEXT: usize = 40;
+
+/// 判断二进制内容及查找meta charset时检查的字节数
+const SNIFF_BYTES: usize = 1024;
+
+/// 按正文内容查找的参数（各HTTP探测模块共用）
+#[derive(Args, Debug, Clone, Default)]
+pub struct GrepArgs {
+    /// 在响应正文中查找的正则（可重复；`(?i)` 开头忽略大小写）
+    #[arg(long = "grep", value_name = "REGEX")]
+    pub patterns: Vec<String>,
+
+    /// 从文件读取带标签的正则（每行 `标签<TAB>正则`，没有TAB时以正则本身为标签，`#` 开头为注释）
+    #[arg(long, value_name = "FILE")]
+    pub grep_file: Option<PathBuf>,
+}
+
+/// 一个带标签的查找模式
+#[derive(Debug, Clone)]
+pub struct GrepPattern {
+    /// 标签（输出及导出的列名）
+    pub label: String,
+    /// 正则
+    pub regex: Regex,
+}
+
+/// 单个模式在正文中的匹配结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GrepMatch {
+    /// 模式标签
+    pub label: String,
+    /// 匹配次数
+    pub count: usize,
+    /// 匹配处的上下文摘录（最多3条，空白已合并）
+    pub excerpts: Vec<String>,
+}
+
+/// 一组查找模式
+#[derive(Debug, Clone)]
+pub struct BodyGrep {
+    patterns: Vec<GrepPattern>,
+}
+
+impl BodyGrep {
+    /// 按命令行参数编译查找模式
+    ///
+    /// # 返回
+    /// * `Ok(None)` - 未指定任何模式
+    /// * `Err` - 正则无效或模式文件无法读取
+    pub fn from_args(args: &GrepArgs) -> Result<Option<Self>, Box<dyn Error + Send + Sync>> {
+        let mut sources: Vec<(String, String)> = args
+            .patterns
+            .iter()
+            .map(|p| (p.clone(), p.clone()))
+            .collect();
+        if let Some(ref path) = args.grep_file {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("无法读取模式文件 {}: {}", path.display(), e))?;
+            sources.extend(parse_pattern_file(&text));
+        }
+        if sources.is_empty() {
+            return Ok(None);
+        }
+        let mut patterns: Vec<GrepPattern> = Vec::new();
+        for (label, pattern) in sources {
+            if patterns.iter().any(|p| p.label == label) {
+                return Err(format!("重复的模式标签: {}", label).into());
+            }
+            let regex =
+                Regex::new(&pattern).map_err(|e| format!("无效的正则 {}: {}", pattern, e))?;
+            patterns.push(GrepPattern { label, regex });
+        }
+        Ok(Some(Self { patterns }))
+    }
+
+    /// 模式标签（按指定顺序）
+    pub fn labels(&self) -> Vec<&str> {
+        self.patterns.iter().map(|p| p.label.as_str()).collect()
+    }
+
+    /// 在文本中查找所有模式
+    ///
+    /// # 返回
+    /// * `Vec<GrepMatch>` - 有匹配的模式（按指定顺序）
+    pub fn scan(&self, text: &str) -> Vec<GrepMatch> {
+        self.patterns
+            .iter()
+            .filter_map(|p| {
+                let mut count = 0;
+                let mut excerpts = Vec::new();
+                for m in p.regex.find_iter(text) {
+                    count += 1;
+                    if excerpts.len() < MAX_EXCERPTS {
+                        excerpts.push(excerpt(text, m.start(), m.end()));
+                    }
+                }
+                (count > 0).then(|| GrepMatch {
+                    label: p.label.clone(),
+                    count,
+                    excerpts,
+                })
+            })
+            .collect()
+    }
+
+    /// 终端中高亮摘录里的匹配内容（输出被重定向时原样返回）
+    ///
+    /// # 参数
+    /// * `label` - 模式标签
+    /// * `text` - 摘录
+    pub fn highlight(&self, label: &str, text: &str) -> String {
+        match self.patterns.iter().find(|p| p.label == label) {
+            Some(p) if std::io::stdout().is_terminal() => {
+                p.regex.replace_all(text, "\x1b[1;31m$0\x1b[0m").to_string()
+            }
+            _ => text.to_string(),
+        }
+    }
+}
+
+/// 解析模式文件
+///
+/// # 返回
+/// * `Vec<(标签, 正则)>`
+fn parse_pattern_file(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|line| match line.split_once('\t') {
+            Some((label, pattern)) if !label.trim().is_empty() => {
+                (label.trim().to_string(), pattern.trim().to_string())
+            }
+            _ => (line.trim().to_string(), line.trim().to_string()),
+        })
+        .collect()
+}
+
+/// 截取匹配处前后的上下文（按字符截取，空白合并为一个空格）
+fn excerpt(text: &str, start: usize, end: usize) -> String {
+    let from = text[..start]
+        .char_indices()
+        .rev()
+        .nth(EXCERPT_CONTEXT - 1)
+        .map_or(0, |(i, _)| i);
+    let to = text[end..]
+        .char_indices()
+        .nth(EXCERPT_CONTEXT)
+        .map_or(text.len(), |(i, _)| end + i);
+    let mut out = text[from..to]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if from > 0 {
+        out.insert(0, '…');
+    }
+    if to < text.len() {
+        out.push('…');
+    }
+    out
+}
+
+/// 判断响应是否为二进制内容（不做正文查找）
+///
+/// # 参数
+/// * `content_type` - Content-Type 头
+/// * `body` - 正文（只检查开头部分是否含NUL字节）
+pub fn is_binary(content_type: Option<&str>, body: &[u8]) -> bool {
+    if let Some(ct) = content_type {
+        let mime = ct.split(';').next().unwrap_or_default().trim();
+        let mime = mime.to_ascii_lowercase();
+        let textual = mime.starts_with("text/")
+            || mime.ends_with("+json")
+            || mime.ends_with("+xml")
+            || ["json", "javascript", "xml", "x-www-form-urlencoded"]
+                .iter()
+                .any(|t| mime.ends_with(t));
+        let binary = ["image/", "audio/", "video/", "font/"]
+            .iter()
+            .any(|t| mime.starts_with(t))
+            || (mime.starts_with("application/") && !textual);
+        if binary {
+            return true;
+        }
+    }
+    body[..body.len().min(SNIFF_BYTES)].contains(&0)
+}
+
+/// 按声明的字符集把正文解码为文本
+///
+/// 依次使用 Content-Type 的 charset、正文开头 meta 标签中的 charset、BOM，默认UTF-8。
+///
+/// # 参数
+/// * `content_type` - Content-Type 头
+/// * `body` - 正文
+pub fn decode_body(content_type: Option<&str>, body: &[u8]) -> String {
+    let head = String::from_utf8_lossy(&body[..body.len().min(SNIFF_BYTES)]).to_ascii_lowercase();
+    let encoding = content_type
+        .and_then(charset_label)
+        .or_else(|| charset_label(&head))
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .unwrap_or(UTF_8);
+    encoding.decode(body).0.into_owned()
+}
+
+/// 提取 `charset=` 之后的字符集名称
+fn charset_label(text: &str) -> Option<String> {
+    let lower = text.to_ascii_lowercase();
+    let start = lower.find("charset=")? + "charset=".len();
+    let label: String = lower[start..]
+        .trim_start_matches(['"', '\''])
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+        .collect();
+    (!label.is_empty()).then_some(label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grep(patterns: &[&str]) -> BodyGrep {
+        let args = GrepArgs {
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            grep_file: None,
+        };
+        BodyGrep::from_args(&args).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_scan_counts_and_excerpts() {
+        let body = format!(
+            "{}<p>Copyright 2024 Acme\n  Corp</p>{}<!-- eval(base64_decode($_POST[x])) -->",
+            "x".repeat(100),
+            "y".repeat(100)
+        );
+        let g = grep(&["(?i)acme", r"eval\(base64_decode", "absent"]);
+        let matches = g.scan(&body);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].label, "(?i)acme");
+        assert_eq!(matches[0].count, 1);
+        let excerpt = &matches[0].excerpts[0];
+        assert!(excerpt.starts_with('…') && excerpt.ends_with('…'));
+        assert!(excerpt.contains("Copyright 2024 Acme Corp</p>"));
+        // 前面40个字符中有5个是注释开头，结尾不足40个字符时不加省略号
+        assert_eq!(
+            matches[1].excerpts[0].chars().filter(|&c| c == 'y').count(),
+            35
+        );
+        assert!(matches[1].excerpts[0].ends_with(")) -->"));
+
+        let many = grep(&["a"]).scan(&"a".repeat(10));
+        assert_eq!((many[0].count, many[0].excerpts.len()), (10, 3));
+    }
+
+    #[test]
+    fn test_pattern_file_labels() {
+        let parsed = parse_pattern_file(
+            "# 标记\n\nwebshell\t(?i)eval\\(\\$_POST\r\n公司名\tAcme Corp\nplain-pattern\n",
+        );
+        assert_eq!(
+            parsed,
+            [
+                ("webshell".to_string(), r"(?i)eval\(\$_POST".to_string()),
+                ("公司名".to_string(), "Acme Corp".to_string()),
+                ("plain-pattern".to_string(), "plain-pattern".to_string()),
+            ]
+        );
+
+        let args = GrepArgs {
+            patterns: vec!["(".to_string()],
+            grep_file: None,
+        };
+        assert!(BodyGrep::from_args(&args).is_err());
+        assert!(BodyGrep::from_args(&GrepArgs::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_binary_detection() {
+        assert!(is_binary(Some("image/png"), b"text"));
+        assert!(is_binary(Some("application/octet-stream"), b""));
+        assert!(is_binary(None, b"GIF89a\0\0"));
+        assert!(!is_binary(Some("application/json; charset=utf-8"), b"{}"));
+        assert!(!is_binary(Some("application/vnd.api+json"), b"{}"));
+        assert!(!is_binary(Some("text/html"), b"<html>"));
+        assert!(!is_binary(None, b"<html>"));
+    }
+
+    #[test]
+    fn test_decode_charset() {
+        let (gbk, _, _) = encoding_rs::GBK.encode("版权所有 某公司");
+        assert_eq!(
+            decode_body(Some("text/html; charset=GBK"), &gbk),
+            "版权所有 某公司"
+        );
+
+        let mut page = b"<html><head><meta charset=\"gb2312\"></head>".to_vec();
+        page.extend_from_slice(&gbk);
+        assert!(decode_body(Some("text/html"), &page).ends_with("版权所有 某公司"));
+
+        assert_eq!(decode_body(None, "中文".as_bytes()), "中文");
+    }
+}
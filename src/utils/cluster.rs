@@ -0,0 +1,282 @@
+// src/utils/cluster.rs
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use clap::Args;
+use sha2::{Digest, Sha256};
+
+/// 默认的相似页面阈值（simhash汉明距离）
+pub const DEFAULT_CLUSTER_DISTANCE: u32 = 3;
+
+/// 计算simhash时每个特征包含的词数
+const SHINGLE_WORDS: usize = 3;
+
+/// favicon按base64编码后每行的字符数（与常见测绘平台的favicon哈希一致）
+const BASE64_LINE_CHARS: usize = 76;
+
+/// 按响应内容聚类的参数（各HTTP探测模块共用）
+#[derive(Args, Debug, Clone, Default)]
+pub struct ClusterArgs {
+    /// 按正文哈希、页面相似度及favicon哈希把相同的服务归为一组，结果标注集群编号
+    #[arg(long)]
+    pub cluster: bool,
+
+    /// 视为相似页面的最大simhash汉明距离（0表示只合并规范化后完全相同的页面）
+    #[arg(long, default_value_t = DEFAULT_CLUSTER_DISTANCE, value_name = "BITS")]
+    pub cluster_distance: u32,
+}
+
+/// 单个响应的内容指纹
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// 正文的SHA-256（无正文时为空）
+    pub sha256: String,
+    /// 规范化HTML的simhash（正文没有可用的词时为 `None`）
+    pub simhash: Option<u64>,
+    /// favicon的mmh3哈希
+    pub favicon: Option<i32>,
+}
+
+impl Fingerprint {
+    /// 两个响应是否属于同一组
+    ///
+    /// # 参数
+    /// * `other` - 另一个响应的指纹
+    /// * `max_distance` - 视为相似的最大simhash汉明距离
+    pub fn matches(&self, other: &Self, max_distance: u32) -> bool {
+        (!self.sha256.is_empty() && self.sha256 == other.sha256)
+            || matches!((self.simhash, other.simhash), (Some(a), Some(b)) if (a ^ b).count_ones() <= max_distance)
+            || matches!((self.favicon, other.favicon), (Some(a), Some(b)) if a == b)
+    }
+}
+
+/// 正文的SHA-256（十六进制）
+pub fn body_sha256(body: &[u8]) -> String {
+    hex::encode(Sha256::digest(body))
+}
+
+/// 规范化HTML的simhash
+///
+/// 转为小写、数字统一替换为0（时间戳、随机令牌等不影响结果），按非字母数字字符切词，
+/// 以连续3个词为一个特征。
+///
+/// # 返回
+/// * `None` - 没有可用的词
+pub fn simhash(text: &str) -> Option<u64> {
+    let normalized: String = text
+        .chars()
+        .map(|c| {
+            if c.is_ascii_digit() {
+                '0'
+            } else {
+                c.to_lowercase().next().unwrap_or(c)
+            }
+        })
+        .collect();
+    let words: Vec<&str> = normalized
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    let mut weights = [0i64; 64];
+    for shingle in words.windows(SHINGLE_WORDS.min(words.len())) {
+        let hash = fnv1a64(shingle.join(" ").as_bytes());
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+    Some(
+        weights
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| **w > 0)
+            .fold(0u64, |acc, (bit, _)| acc | 1 << bit),
+    )
+}
+
+/// favicon哈希：base64编码（每76个字符换行）后的mmh3，与常见测绘平台的 `icon_hash` 一致
+pub fn favicon_hash(icon: &[u8]) -> i32 {
+    let encoded = STANDARD.encode(icon);
+    let mut lines = String::with_capacity(encoded.len() + encoded.len() / BASE64_LINE_CHARS + 1);
+    for chunk in encoded.as_bytes().chunks(BASE64_LINE_CHARS) {
+        lines.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        lines.push('\n');
+    }
+    murmur3_32(lines.as_bytes(), 0) as i32
+}
+
+/// 把指纹相同或相似的响应分组
+///
+/// # 参数
+/// * `fingerprints` - 各响应的指纹
+/// * `max_distance` - 视为相似的最大simhash汉明距离
+///
+/// # 返回
+/// 每个响应的集群编号（A、B……Z、AA……，按集群大小从大到小），不与其他响应同组的为 `None`
+pub fn assign_clusters(fingerprints: &[Fingerprint], max_distance: u32) -> Vec<Option<String>> {
+    let mut parent: Vec<usize> = (0..fingerprints.len()).collect();
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for i in 0..fingerprints.len() {
+        for j in i + 1..fingerprints.len() {
+            if fingerprints[i].matches(&fingerprints[j], max_distance) {
+                let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let roots: Vec<usize> = (0..fingerprints.len())
+        .map(|i| find(&mut parent, i))
+        .collect();
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    for &root in &roots {
+        match groups.iter_mut().find(|(r, _)| *r == root) {
+            Some((_, size)) => *size += 1,
+            None => groups.push((root, 1)),
+        }
+    }
+    groups.retain(|(_, size)| *size > 1);
+    // 同样大小的集群按首次出现的顺序编号
+    groups.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    roots
+        .iter()
+        .map(|root| {
+            groups
+                .iter()
+                .position(|(r, _)| r == root)
+                .map(cluster_label)
+        })
+        .collect()
+}
+
+/// 集群编号：0 → A，25 → Z，26 → AA
+fn cluster_label(mut index: usize) -> String {
+    let mut label = Vec::new();
+    loop {
+        label.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    label.reverse();
+    String::from_utf8(label).unwrap_or_default()
+}
+
+/// 64位FNV-1a
+fn fnv1a64(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// 32位MurmurHash3（x86）
+fn murmur3_32(data: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    let mut hash = seed;
+    let chunks = data.chunks_exact(4);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        let k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])
+            .wrapping_mul(C1)
+            .rotate_left(15)
+            .wrapping_mul(C2);
+        hash = (hash ^ k)
+            .rotate_left(13)
+            .wrapping_mul(5)
+            .wrapping_add(0xe654_6b64);
+    }
+    if !tail.is_empty() {
+        let k = tail
+            .iter()
+            .rev()
+            .fold(0u32, |k, &b| (k << 8) | b as u32)
+            .wrapping_mul(C1)
+            .rotate_left(15)
+            .wrapping_mul(C2);
+        hash ^= k;
+    }
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^ (hash >> 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashes_match_reference_values() {
+        assert_eq!(murmur3_32(b"", 0), 0);
+        assert_eq!(murmur3_32(b"hello", 0), 0x248b_fa47);
+        assert_eq!(
+            murmur3_32(b"The quick brown fox jumps over the lazy dog", 0),
+            0x2e4f_f723
+        );
+        // base64 "YWJj\n" 的mmh3
+        assert_eq!(favicon_hash(b"abc"), murmur3_32(b"YWJj\n", 0) as i32);
+        let long = format!("{}\n{}\n", "A".repeat(76), "A".repeat(4));
+        assert_eq!(
+            favicon_hash(&[0u8; 60]),
+            murmur3_32(long.as_bytes(), 0) as i32
+        );
+    }
+
+    #[test]
+    fn test_similar_pages_share_a_cluster() {
+        let login = |token: &str| {
+            format!(
+                "<html><head><title>登录 - 统一认证</title></head><body><form action=/login>\
+                 <input name=user><input name=pass><input type=hidden name=csrf value={}>\
+                 <button>登录</button></form><p>版权所有 2024 某公司 信息中心</p></body></html>",
+                token
+            )
+        };
+        let a = simhash(&login("839201")).unwrap();
+        let b = simhash(&login("112358")).unwrap();
+        let other = simhash("<html><title>Welcome to nginx!</title><body>If you see this page, the nginx web server is successfully installed</body></html>").unwrap();
+        assert_eq!(a, b);
+        assert!((a ^ other).count_ones() > DEFAULT_CLUSTER_DISTANCE);
+        assert_eq!(simhash("<>  </>"), None);
+
+        let fp = |sha: &str, simhash: Option<u64>, favicon: Option<i32>| Fingerprint {
+            sha256: sha.to_string(),
+            simhash,
+            favicon,
+        };
+        let clusters = assign_clusters(
+            &[
+                fp("x", Some(a), None),
+                fp("y", Some(other), Some(7)),
+                fp("z", Some(b), None),
+                fp("", None, Some(7)),
+                fp("w", Some(a ^ 0xff), None),
+                fp("x", None, None),
+            ],
+            DEFAULT_CLUSTER_DISTANCE,
+        );
+        let labels: Vec<&str> = clusters
+            .iter()
+            .map(|c| c.as_deref().unwrap_or(""))
+            .collect();
+        assert_eq!(labels, ["A", "B", "A", "B", "", "A"]);
+        assert_eq!(cluster_label(25), "Z");
+        assert_eq!(cluster_label(26), "AA");
+        assert_eq!(cluster_label(27), "AB");
+    }
+}
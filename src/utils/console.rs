@@ -0,0 +1,188 @@
+// src/utils/console.rs
+use std::sync::OnceLock;
+
+static ASCII: OnceLock<bool> = OnceLock::new();
+
+static VERBOSE: OnceLock<bool> = OnceLock::new();
+
+/// 输出中使用的状态符号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icon {
+    /// 成功 ✅
+    Ok,
+    /// 失败 ❌
+    Fail,
+    /// 警告 ⚠️
+    Warn,
+    /// 开始扫描 🔍
+    Scan,
+    /// 配置 ⚙️
+    Config,
+    /// 结果列表 📋
+    List,
+    /// 统计 📊
+    Stats,
+}
+
+impl Icon {
+    /// 符号文本
+    ///
+    /// 终端中只占一列宽度的emoji（⚠️、⚙️）自带一个补齐空格，
+    /// 调用方统一在符号后再加一个空格即可对齐。
+    pub fn as_str(self) -> &'static str {
+        if ascii_mode() {
+            match self {
+                Icon::Ok => "[OK]",
+                Icon::Fail => "[FAIL]",
+                Icon::Warn => "[WARN]",
+                Icon::Scan => "[SCAN]",
+                Icon::Config => "[CONF]",
+                Icon::List => "[LIST]",
+                Icon::Stats => "[STAT]",
+            }
+        } else {
+            match self {
+                Icon::Ok => "✅",
+                Icon::Fail => "❌",
+                Icon::Warn => "⚠️ ",
+                Icon::Scan => "🔍",
+                Icon::Config => "⚙️ ",
+                Icon::List => "📋",
+                Icon::Stats => "📊",
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Icon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 检测控制台能力并设置全局符号集（仅首次调用生效，应在程序启动时调用）
+///
+/// Windows下会先尝试把控制台输出代码页切换为UTF-8。
+///
+/// # 参数
+/// * `force_ascii` - 强制使用ASCII符号集（`--ascii`）
+///
+/// # 返回
+/// * `bool` - 是否使用ASCII符号集
+pub fn init(force_ascii: bool) -> bool {
+    let ascii = force_ascii || {
+        let utf8_console = enable_utf8_console();
+        !supports_unicode(|name| std::env::var(name).ok(), utf8_console)
+    };
+    *ASCII.get_or_init(|| ascii)
+}
+
+/// 是否使用ASCII符号集，未初始化时为否
+pub fn ascii_mode() -> bool {
+    ASCII.get().copied().unwrap_or(false)
+}
+
+/// 设置是否输出详细信息（`--verbose`，仅首次设置生效，应在程序启动时调用）
+pub fn set_verbose(verbose: bool) {
+    let _ = VERBOSE.set(verbose);
+}
+
+/// 是否输出详细信息（如导出速度），未设置时为否
+pub fn verbose() -> bool {
+    VERBOSE.get().copied().unwrap_or(false)
+}
+
+/// 进度条填充字符
+pub fn progress_chars() -> &'static str {
+    if ascii_mode() { "#>-" } else { "█▓▒░ " }
+}
+
+/// 判断终端能否正确显示UTF-8及emoji
+///
+/// # 参数
+/// * `env` - 读取环境变量
+/// * `utf8_console` - 控制台输出代码页是否为UTF-8（仅Windows有意义）
+fn supports_unicode(env: impl Fn(&str) -> Option<String>, utf8_console: bool) -> bool {
+    let non_empty = |name: &str| env(name).filter(|v| !v.is_empty());
+
+    if cfg!(windows) {
+        // 旧版conhost即使切换到UTF-8也无法显示emoji，只信任现代终端
+        return utf8_console
+            && (non_empty("WT_SESSION").is_some()
+                || non_empty("TERM_PROGRAM").is_some()
+                || non_empty("TERM").is_some_and(|t| t.contains("xterm")));
+    }
+
+    if non_empty("TERM").is_some_and(|t| t == "dumb" || t == "linux") {
+        return false;
+    }
+    // 按POSIX规则取生效的字符集，均未设置时为C（ASCII）
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| non_empty(name))
+        .is_some_and(|locale| {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+#[cfg(windows)]
+fn enable_utf8_console() -> bool {
+    const CP_UTF8: u32 = 65001;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetConsoleOutputCP() -> u32;
+        fn SetConsoleOutputCP(code_page: u32) -> i32;
+    }
+
+    // SAFETY: 两个函数都不涉及指针，失败时只返回0
+    unsafe { GetConsoleOutputCP() == CP_UTF8 || SetConsoleOutputCP(CP_UTF8) != 0 }
+}
+
+#[cfg(not(windows))]
+fn enable_utf8_console() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_unix_locale_detection() {
+        assert!(supports_unicode(env(&[("LANG", "zh_CN.UTF-8")]), true));
+        assert!(supports_unicode(
+            env(&[("LC_ALL", "en_US.utf8"), ("LANG", "C")]),
+            true
+        ));
+        // LC_ALL 优先于 LANG
+        assert!(!supports_unicode(
+            env(&[("LC_ALL", "C"), ("LANG", "zh_CN.UTF-8")]),
+            true
+        ));
+        assert!(!supports_unicode(env(&[("LANG", "zh_CN.GBK")]), true));
+        assert!(!supports_unicode(env(&[]), true));
+        assert!(!supports_unicode(
+            env(&[("TERM", "linux"), ("LANG", "en_US.UTF-8")]),
+            true
+        ));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_windows_console_detection() {
+        assert!(supports_unicode(env(&[("WT_SESSION", "1")]), true));
+        assert!(!supports_unicode(env(&[("WT_SESSION", "1")]), false));
+        assert!(!supports_unicode(env(&[]), true));
+    }
+}
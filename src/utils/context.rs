@@ -0,0 +1,344 @@
+// src/utils/context.rs
+use super::adaptive::AdaptiveController;
+use super::console::Icon;
+use super::control::ControlState;
+use super::pause::PauseGate;
+use super::run_dir::RunDir;
+use super::stats::{Outcome, StatsSnapshot};
+use super::targets::{TargetOverrides, TargetSet};
+use super::timing::Throttle;
+use super::transcript::Tape;
+use super::{ExcelOptions, ScanProgress};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
+
+/// 命令行运行的根取消令牌，收到中断（Ctrl+C）时取消
+static INTERRUPT: LazyLock<CancellationToken> = LazyLock::new(CancellationToken::new);
+
+/// 是否已经收到过中断
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// 处理一次中断（Ctrl+C）
+///
+/// 第一次中断取消全部命令行运行：不再分发新的探测，进行中的探测计为未完成，
+/// 已完成的结果照常导出。再次中断时返回 `true`，由调用方直接退出。
+pub fn interrupt() -> bool {
+    if INTERRUPTED.swap(true, Ordering::SeqCst) {
+        return true;
+    }
+    eprintln!(
+        "{} 已中断，正在停止扫描并保存已完成的结果（再按一次 Ctrl+C 立即退出）",
+        Icon::Warn
+    );
+    INTERRUPT.cancel();
+    false
+}
+
+/// 监听 Ctrl+C 信号并按 [`interrupt`] 处理
+///
+/// 注册后 Ctrl+C 不再直接终止进程，只应在命令行扫描开始前调用一次。
+pub fn watch_interrupt() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if interrupt() {
+                std::process::exit(130);
+            }
+        }
+    });
+}
+
+/// 扫描运行上下文
+///
+/// 命令行与守护进程共用同一套扫描函数，差异由上下文决定：
+/// 是否显示进度条和监听按键、结果是否实时推送给外部、由谁来取消扫描。
+///
+/// 取消按层级进行：整次运行一个令牌，每个主机一个子令牌。
+/// 取消运行时全部主机随之取消；只取消某个主机时其余主机照常扫描。
+#[derive(Clone)]
+pub struct ScanContext {
+    /// 是否为命令行交互运行（显示进度条、监听按键）
+    pub interactive: bool,
+    /// 分发暂停开关
+    pub pause: PauseGate,
+    cancel: CancellationToken,
+    hosts: Arc<Mutex<HashMap<String, CancellationToken>>>,
+    results: Option<UnboundedSender<serde_json::Value>>,
+    progress: Arc<Mutex<Option<ScanProgress>>>,
+    run_dir: Option<Arc<RunDir>>,
+    throttle: Arc<Throttle>,
+    control: Option<Arc<ControlState>>,
+    tape: Tape,
+    overrides: Arc<HashMap<String, TargetOverrides>>,
+    adaptive: Option<Arc<AdaptiveController>>,
+}
+
+impl ScanContext {
+    /// 命令行运行的上下文（运行令牌随中断取消，见 [`interrupt`]）
+    pub fn cli() -> Self {
+        let mut ctx = Self::new(true, None);
+        ctx.cancel = INTERRUPT.child_token();
+        ctx
+    }
+
+    /// 后台运行的上下文（不绘制进度条，结果推送到 `results`）
+    pub fn background(results: UnboundedSender<serde_json::Value>) -> Self {
+        Self::new(false, Some(results))
+    }
+
+    fn new(interactive: bool, results: Option<UnboundedSender<serde_json::Value>>) -> Self {
+        Self {
+            interactive,
+            pause: PauseGate::new(),
+            cancel: CancellationToken::new(),
+            hosts: Arc::new(Mutex::new(HashMap::new())),
+            results,
+            progress: Arc::new(Mutex::new(None)),
+            run_dir: None,
+            throttle: Arc::new(Throttle::default()),
+            control: None,
+            tape: Tape::Live,
+            overrides: Arc::new(HashMap::new()),
+            adaptive: None,
+        }
+    }
+
+    /// 指定本次运行的工作目录（为 `None` 时沿用按模块平铺的旧目录结构）
+    pub fn with_run_dir(mut self, run_dir: Option<Arc<RunDir>>) -> Self {
+        self.run_dir = run_dir;
+        self
+    }
+
+    /// 本次运行的工作目录
+    pub fn run_dir(&self) -> Option<&Arc<RunDir>> {
+        self.run_dir.as_ref()
+    }
+
+    /// 指定探测节流（由 `--timing` 等时序参数决定）
+    pub fn with_throttle(mut self, throttle: Throttle) -> Self {
+        self.throttle = Arc::new(throttle);
+        // 控制通道调整速率时作用于扫描实际使用的节流器
+        if let Some(ref control) = self.control {
+            control.set_throttle(self.throttle.clone());
+        }
+        self
+    }
+
+    /// 开启控制通道所需的状态（记录已发现的结果、共享节流器），须在扫描开始前调用
+    pub fn with_control(mut self) -> Self {
+        let control = ControlState::default();
+        control.set_throttle(self.throttle.clone());
+        self.control = Some(Arc::new(control));
+        self
+    }
+
+    /// 指定探测的录制或回放（由 `--record`、`--replay` 决定）
+    pub fn with_tape(mut self, tape: Tape) -> Self {
+        self.tape = tape;
+        self
+    }
+
+    /// 探测的录制或回放，探测器据此决定访问网络还是读取录制
+    pub fn tape(&self) -> &Tape {
+        &self.tape
+    }
+
+    /// 指定目标行上的参数覆盖（由目标文件中的 `timeout=`、`count=` 等决定）
+    pub fn with_target_overrides(mut self, targets: &TargetSet) -> Self {
+        self.overrides = Arc::new(targets.overridden().collect());
+        self
+    }
+
+    /// 某个主机的参数覆盖，探测器据此调整超时及次数（没有时为空）
+    pub fn target_overrides(&self, host: &str) -> TargetOverrides {
+        self.overrides.get(host).copied().unwrap_or_default()
+    }
+
+    /// 开启自适应并发（`-c auto-adaptive`），须在 [`with_throttle`](Self::with_throttle) 之后调用
+    ///
+    /// 之后创建的进度条把结论交给控制器，节流器在每次探测发起前取得控制器的并发许可。
+    pub fn with_adaptive(mut self, controller: Option<AdaptiveController>) -> Self {
+        if let Some(controller) = controller {
+            self.throttle.set_adaptive(controller.limiter());
+            self.adaptive = Some(Arc::new(controller));
+        }
+        self
+    }
+
+    /// 自适应并发控制器（未开启时为 `None`）
+    pub fn adaptive(&self) -> Option<&Arc<AdaptiveController>> {
+        self.adaptive.as_ref()
+    }
+
+    /// 控制通道的状态（未开启时为 `None`）
+    pub fn control(&self) -> Option<&Arc<ControlState>> {
+        self.control.as_ref()
+    }
+
+    /// 探测节流，每次探测发起前等待
+    pub fn throttle(&self) -> &Throttle {
+        &self.throttle
+    }
+
+    /// 导出文件时使用的选项（写入运行目录或平铺目录）
+    pub fn excel_options(&self) -> ExcelOptions {
+        ExcelOptions {
+            run_dir: self.run_dir.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// 不推送结果的副本（用于扫描内部的辅助阶段，如端口扫描前的存活探测）
+    ///
+    /// 辅助阶段的结果也不记为已发现，暂停、取消及速率调整仍与原上下文共享。
+    pub fn without_results(&self) -> Self {
+        Self {
+            results: None,
+            control: None,
+            ..self.clone()
+        }
+    }
+
+    /// 创建本阶段的进度条，后台运行时不绘制
+    ///
+    /// # 参数
+    /// * `total` - 总任务数
+    pub fn new_progress(&self, total: u64) -> ScanProgress {
+        let mut progress = ScanProgress::new(total);
+        if let Some(ref controller) = self.adaptive {
+            progress = progress.with_adaptive(controller.clone());
+        }
+        if !self.interactive {
+            progress.set_hidden(true);
+        }
+        *self.progress.lock().unwrap() = Some(progress.clone());
+        progress
+    }
+
+    /// 当前阶段的进度条（尚未开始时为 `None`）
+    pub fn current_progress(&self) -> Option<ScanProgress> {
+        self.progress.lock().unwrap().clone()
+    }
+
+    /// 当前阶段的统计快照（尚未开始时为 `None`）
+    pub fn stats(&self) -> Option<StatsSnapshot> {
+        self.progress.lock().unwrap().as_ref().map(|p| p.snapshot())
+    }
+
+    /// 当前阶段的进度
+    ///
+    /// # 返回
+    /// * `(已完成, 总数)` - 尚未开始时为 `(0, 0)`
+    pub fn progress(&self) -> (u64, u64) {
+        self.progress
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|p| (p.position(), p.length()))
+            .unwrap_or((0, 0))
+    }
+
+    /// 请求取消扫描：不再分发新的探测，进行中的探测立即放弃并计为未完成
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+        // 暂停中的分发需要放行才能退出
+        self.pause.release();
+    }
+
+    /// 是否已请求取消
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// 本次运行的取消令牌（供工作池、交互界面等共享）
+    pub fn token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    /// 某个主机的取消令牌（运行令牌的子令牌，首次使用时创建）
+    ///
+    /// # 参数
+    /// * `host` - 主机（IP地址）
+    pub fn host_token(&self, host: &str) -> CancellationToken {
+        self.hosts
+            .lock()
+            .unwrap()
+            .entry(host.to_string())
+            .or_insert_with(|| self.cancel.child_token())
+            .clone()
+    }
+
+    /// 放弃某个主机：该主机尚未开始的探测直接跳过，进行中的探测立即放弃
+    ///
+    /// # 参数
+    /// * `host` - 主机（IP地址）
+    pub fn cancel_host(&self, host: &str) {
+        self.host_token(host).cancel();
+    }
+
+    /// 推送一条结果（无接收方时忽略），开启控制通道时同时记为已发现的结果
+    ///
+    /// 适用于只推送有效结果（服务可用、名称存在等）的模块。
+    pub fn emit<T: Serialize>(&self, result: &T) {
+        self.emit_with_outcome(result, Outcome::Succeeded);
+    }
+
+    /// 推送一条结果，只有成功的结果才记为已发现（适用于推送全部探测结果的模块）
+    ///
+    /// # 参数
+    /// * `result` - 结果
+    /// * `outcome` - 结果的结论
+    pub fn emit_with_outcome<T: Serialize>(&self, result: &T, outcome: Outcome) {
+        if self.results.is_none() && self.control.is_none() {
+            return;
+        }
+        let Ok(value) = serde_json::to_value(result) else {
+            return;
+        };
+        if outcome == Outcome::Succeeded
+            && let Some(ref control) = self.control
+        {
+            control.record_finding(value.clone());
+        }
+        if let Some(ref tx) = self.results {
+            let _ = tx.send(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn test_background_context_emits_and_cancels() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let ctx = ScanContext::background(tx);
+        assert!(!ctx.interactive);
+
+        ctx.emit(&serde_json::json!({"ip": "10.0.0.1"}));
+        ctx.without_results()
+            .emit(&serde_json::json!({"ip": "10.0.0.2"}));
+        assert_eq!(rx.try_recv().unwrap()["ip"], "10.0.0.1");
+        assert!(rx.try_recv().is_err());
+
+        let progress = ctx.new_progress(10);
+        progress.inc(3);
+        assert_eq!(ctx.progress(), (3, 10));
+
+        ctx.cancel_host("10.0.0.1");
+        assert!(ctx.host_token("10.0.0.1").is_cancelled());
+        assert!(!ctx.host_token("10.0.0.2").is_cancelled());
+        assert!(!ctx.is_cancelled());
+
+        ctx.pause.pause();
+        ctx.cancel();
+        assert!(ctx.is_cancelled());
+        assert!(ctx.without_results().host_token("10.0.0.2").is_cancelled());
+        assert!(!ctx.pause.is_paused());
+    }
+}
@@ -0,0 +1,612 @@
+// src/utils/control.rs
+use super::context::ScanContext;
+use super::run_dir::RunDir;
+use super::timing::Throttle;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use std::error::Error;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// 控制通道套接字文件名（位于运行目录下，仅Unix）
+pub const CONTROL_SOCKET_NAME: &str = "control.sock";
+
+/// 最多保留的已发现结果数（更早的只计数）
+const MAX_FINDINGS: usize = 1000;
+
+/// 单条请求的最大长度
+const MAX_REQUEST_BYTES: u64 = 4096;
+
+/// 控制通道的请求（每行一个JSON对象，以 `cmd` 区分）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "kebab-case")]
+pub enum ControlRequest {
+    /// 查询进度、统计及当前速率
+    Status,
+    /// 查询已发现的结果（最近的 `limit` 个）
+    Findings {
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// 调整速率限制（每秒探测数，0为不限）
+    SetRate { rate: u32 },
+    /// 暂停分发
+    Pause,
+    /// 恢复分发
+    Resume,
+    /// 停止扫描（已有结果照常导出）
+    Stop,
+}
+
+/// 运行中扫描供控制通道查询和调整的状态，随上下文克隆共享
+#[derive(Debug, Default)]
+pub struct ControlState {
+    throttle: Mutex<Option<Arc<Throttle>>>,
+    findings: Mutex<VecDeque<Value>>,
+    found: AtomicU64,
+}
+
+impl ControlState {
+    /// 登记扫描实际使用的节流器
+    pub fn set_throttle(&self, throttle: Arc<Throttle>) {
+        *self.throttle.lock().unwrap() = Some(throttle);
+    }
+
+    /// 记录一个已发现的结果
+    pub fn record_finding(&self, value: Value) {
+        self.found.fetch_add(1, Ordering::Relaxed);
+        let mut findings = self.findings.lock().unwrap();
+        if findings.len() == MAX_FINDINGS {
+            findings.pop_front();
+        }
+        findings.push_back(value);
+    }
+
+    /// 已发现的结果总数
+    pub fn found(&self) -> u64 {
+        self.found.load(Ordering::Relaxed)
+    }
+
+    /// 最近的 `limit` 个已发现结果（按发现顺序）
+    fn recent_findings(&self, limit: usize) -> Vec<Value> {
+        let findings = self.findings.lock().unwrap();
+        let skip = findings.len().saturating_sub(limit);
+        findings.iter().skip(skip).cloned().collect()
+    }
+
+    fn throttle(&self) -> Option<Arc<Throttle>> {
+        self.throttle.lock().unwrap().clone()
+    }
+}
+
+/// 本次运行的描述（状态应答中返回）
+#[derive(Debug, Clone)]
+struct RunInfo {
+    id: String,
+    module: String,
+    started: Instant,
+}
+
+/// 处理一条请求
+///
+/// # 参数
+/// * `ctx` - 扫描上下文（须已调用 [`ScanContext::with_control`]）
+/// * `info` - 本次运行的描述
+/// * `request` - 请求
+///
+/// # 返回
+/// * `Value` - 应答（`ok` 为 `false` 时 `error` 为原因）
+fn handle(ctx: &ScanContext, info: &RunInfo, request: ControlRequest) -> Value {
+    let Some(control) = ctx.control() else {
+        return json!({ "ok": false, "error": "本次运行未开启控制通道" });
+    };
+    match request {
+        ControlRequest::Status => {
+            let (completed, total) = ctx.progress();
+            let state = if ctx.is_cancelled() {
+                "stopping"
+            } else if ctx.pause.is_paused() {
+                "paused"
+            } else {
+                "running"
+            };
+            json!({
+                "ok": true,
+                "run_id": info.id,
+                "module": info.module,
+                "state": state,
+                "elapsed_secs": info.started.elapsed().as_secs(),
+                "paused_secs": ctx.pause.paused_duration().as_secs(),
+                "progress": { "completed": completed, "total": total },
+                "stats": ctx.stats(),
+                "rate": control.throttle().and_then(|t| t.rate()),
+                "findings": control.found(),
+            })
+        }
+        ControlRequest::Findings { limit } => {
+            let findings = control.recent_findings(limit.unwrap_or(MAX_FINDINGS));
+            json!({ "ok": true, "total": control.found(), "findings": findings })
+        }
+        ControlRequest::SetRate { rate } => match control.throttle() {
+            Some(throttle) => {
+                throttle.set_rate(rate);
+                json!({ "ok": true, "rate": throttle.rate() })
+            }
+            None => json!({ "ok": false, "error": "扫描尚未开始" }),
+        },
+        ControlRequest::Pause | ControlRequest::Resume => {
+            let paused = request == ControlRequest::Pause;
+            match ctx.current_progress() {
+                Some(progress) => ctx.pause.set_paused_with_progress(paused, &progress),
+                None if paused => ctx.pause.pause(),
+                None => ctx.pause.resume(),
+            }
+            json!({ "ok": true, "paused": ctx.pause.is_paused() })
+        }
+        ControlRequest::Stop => {
+            ctx.cancel();
+            json!({ "ok": true })
+        }
+    }
+}
+
+/// 逐行读取请求并应答，直到对端关闭连接
+async fn serve_connection<S>(stream: S, ctx: ScanContext, info: Arc<RunInfo>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+    loop {
+        let mut line = String::new();
+        // 限制单行长度，避免异常客户端占用内存
+        match (&mut reader)
+            .take(MAX_REQUEST_BYTES)
+            .read_line(&mut line)
+            .await
+        {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<ControlRequest>(line.trim()) {
+            Ok(request) => handle(&ctx, &info, request),
+            Err(e) => json!({ "ok": false, "error": format!("无效的请求: {}", e) }),
+        };
+        let mut out = response.to_string();
+        out.push('\n');
+        if writer.write_all(out.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// 控制通道服务端，丢弃时停止监听并删除套接字
+pub struct ControlServer {
+    task: tokio::task::JoinHandle<()>,
+    #[cfg(unix)]
+    socket: PathBuf,
+    #[cfg(unix)]
+    run_dir: Arc<RunDir>,
+}
+
+impl ControlServer {
+    /// 为一次运行开启控制通道
+    ///
+    /// Unix下在运行目录中创建 `control.sock`，权限为 0600，且只接受同一用户的连接；
+    /// Windows下创建仅限本机、当前用户连接的命名管道 `\\.\pipe\gxtools-<运行ID>`。
+    ///
+    /// # 参数
+    /// * `ctx` - 扫描上下文（须已调用 [`ScanContext::with_control`]）
+    /// * `run_dir` - 运行目录
+    /// * `run_id` - 运行ID
+    /// * `module` - 模块名称
+    ///
+    /// # 返回
+    /// * `Ok(ControlServer)` - 服务端句柄
+    /// * `Err` - 无法创建套接字或命名管道
+    pub fn start(
+        ctx: &ScanContext,
+        run_dir: &Arc<RunDir>,
+        run_id: &str,
+        module: &str,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let info = Arc::new(RunInfo {
+            id: run_id.to_string(),
+            module: module.to_string(),
+            started: Instant::now(),
+        });
+        #[cfg(unix)]
+        {
+            let socket = run_dir.ensure()?.join(CONTROL_SOCKET_NAME);
+            let task = unix::listen(&socket, ctx.clone(), info)?;
+            Ok(Self {
+                task,
+                socket,
+                run_dir: run_dir.clone(),
+            })
+        }
+        #[cfg(windows)]
+        {
+            let _ = run_dir;
+            let task = windows::listen(&pipe_name(run_id), ctx.clone(), info)?;
+            Ok(Self { task })
+        }
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.task.abort();
+        #[cfg(unix)]
+        {
+            std::fs::remove_file(&self.socket).ok();
+            // 只为控制通道创建的运行目录（没有其他产物）不保留
+            if self.run_dir.exists() {
+                std::fs::remove_dir(self.run_dir.path()).ok();
+            }
+        }
+    }
+}
+
+/// 命名管道名称
+#[cfg(windows)]
+fn pipe_name(run_id: &str) -> String {
+    format!(r"\\.\pipe\gxtools-{}", run_id)
+}
+
+/// 按运行ID查找控制套接字：运行目录名以 `_<短ID>` 结尾，也可直接给出运行目录名
+#[cfg(unix)]
+fn find_socket(run_id: &str) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let runs = super::output_root().join(super::run_dir::RUNS_DIR_NAME);
+    let direct = runs.join(run_id).join(CONTROL_SOCKET_NAME);
+    if direct.exists() {
+        return Ok(direct);
+    }
+    let suffix = format!("_{}", run_id.rsplit('-').next().unwrap_or(run_id));
+    let mut candidates: Vec<PathBuf> = std::fs::read_dir(&runs)
+        .map_err(|e| format!("无法读取运行目录 {}: {}", runs.display(), e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().ends_with(&suffix))
+        .map(|entry| entry.path().join(CONTROL_SOCKET_NAME))
+        .filter(|socket| socket.exists())
+        .collect();
+    // 同一短ID有多个时取最近的运行（目录名以时间戳开头）
+    candidates.sort();
+    candidates.pop().ok_or_else(|| {
+        format!(
+            "找不到运行中的扫描 {}（运行目录: {}）",
+            run_id,
+            runs.display()
+        )
+        .into()
+    })
+}
+
+/// 向运行中的扫描发送一条请求
+///
+/// # 参数
+/// * `run_id` - 运行ID（扫描开始时打印）或运行目录名
+/// * `request` - 请求
+///
+/// # 返回
+/// * `Ok(Value)` - 应答（`ok` 为 `true`）
+/// * `Err` - 连接失败或请求被拒绝
+pub async fn send(
+    run_id: &str,
+    request: &ControlRequest,
+) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    #[cfg(unix)]
+    let stream = {
+        let socket = find_socket(run_id)?;
+        tokio::net::UnixStream::connect(&socket)
+            .await
+            .map_err(|e| format!("无法连接控制通道 {}: {}", socket.display(), e))?
+    };
+    #[cfg(windows)]
+    let stream = tokio::net::windows::named_pipe::ClientOptions::new()
+        .open(pipe_name(run_id))
+        .map_err(|e| format!("无法连接运行 {} 的控制通道: {}", run_id, e))?;
+    exchange(stream, request).await
+}
+
+/// 在已建立的连接上发送请求并读取应答
+async fn exchange<S>(
+    stream: S,
+    request: &ControlRequest,
+) -> Result<Value, Box<dyn Error + Send + Sync>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    let mut response = String::new();
+    BufReader::new(reader).read_line(&mut response).await?;
+    if response.is_empty() {
+        return Err("控制通道已关闭（扫描可能已结束）".into());
+    }
+    let response: Value = serde_json::from_str(&response)?;
+    if response["ok"] != true {
+        let error = response["error"].as_str().unwrap_or("未知错误");
+        return Err(error.to_string().into());
+    }
+    Ok(response)
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::{CONTROL_SOCKET_NAME, RunInfo, serve_connection};
+    use crate::utils::context::ScanContext;
+    use std::error::Error;
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::path::Path;
+    use std::sync::Arc;
+    use tokio::net::UnixListener;
+
+    /// 在 `socket` 上监听，只接受与本进程同一用户的连接
+    pub(super) fn listen(
+        socket: &Path,
+        ctx: ScanContext,
+        info: Arc<RunInfo>,
+    ) -> Result<tokio::task::JoinHandle<()>, Box<dyn Error + Send + Sync>> {
+        // 上次异常退出留下的套接字文件
+        std::fs::remove_file(socket).ok();
+        let listener = bind_private(socket)
+            .map_err(|e| format!("无法创建控制套接字 {}: {}", socket.display(), e))?;
+        // SAFETY: geteuid 没有前置条件且总是成功
+        let owner = unsafe { libc::geteuid() };
+        Ok(tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                // 按对端凭据再检查一次
+                if !stream.peer_cred().is_ok_and(|cred| cred.uid() == owner) {
+                    continue;
+                }
+                tokio::spawn(serve_connection(stream, ctx.clone(), info.clone()));
+            }
+        }))
+    }
+
+    /// 创建权限为 0600 的套接字
+    ///
+    /// 先在只有本用户能进入的临时目录（0700）中绑定并收紧权限，再移到 `socket`，
+    /// 套接字出现在运行目录中时权限已经收紧，其他用户没有连入的窗口。
+    fn bind_private(socket: &Path) -> std::io::Result<UnixListener> {
+        let staging = socket.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::remove_dir_all(&staging).ok();
+        std::fs::DirBuilder::new().mode(0o700).create(&staging)?;
+        let staged = staging.join(CONTROL_SOCKET_NAME);
+        let bound = UnixListener::bind(&staged).and_then(|listener| {
+            std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+            std::fs::rename(&staged, socket)?;
+            Ok(listener)
+        });
+        std::fs::remove_dir_all(&staging).ok();
+        bound
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::{RunInfo, serve_connection};
+    use crate::utils::context::ScanContext;
+    use std::error::Error;
+    use std::ffi::c_void;
+    use std::io;
+    use std::ptr::null_mut;
+    use std::sync::Arc;
+    use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+    use windows_sys::Win32::Foundation::{CloseHandle, LocalFree};
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertSidToStringSidW, ConvertStringSecurityDescriptorToSecurityDescriptorW,
+        SDDL_REVISION_1,
+    };
+    use windows_sys::Win32::Security::{
+        GetTokenInformation, PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES, TOKEN_QUERY, TOKEN_USER,
+        TokenUser,
+    };
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+    /// 在命名管道 `name` 上监听
+    ///
+    /// 拒绝远程客户端；管道的DACL只含当前用户一项（不沿用默认安全描述符中的
+    /// 管理员、系统账户等），其他用户无法连接。
+    pub(super) fn listen(
+        name: &str,
+        ctx: ScanContext,
+        info: Arc<RunInfo>,
+    ) -> Result<tokio::task::JoinHandle<()>, Box<dyn Error + Send + Sync>> {
+        let security = OwnerOnly::new()
+            .map_err(|e| format!("无法创建控制管道 {} 的安全描述符: {}", name, e))?;
+        let mut server = security
+            .create(ServerOptions::new().first_pipe_instance(true), name)
+            .map_err(|e| format!("无法创建控制管道 {}: {}", name, e))?;
+        let name = name.to_string();
+        Ok(tokio::spawn(async move {
+            loop {
+                if server.connect().await.is_err() {
+                    break;
+                }
+                // 先创建下一个实例再处理本次连接，避免客户端在间隙中连接失败
+                let next = match security.create(&mut ServerOptions::new(), &name) {
+                    Ok(next) => next,
+                    Err(_) => break,
+                };
+                let connected = std::mem::replace(&mut server, next);
+                tokio::spawn(serve_connection(connected, ctx.clone(), info.clone()));
+            }
+        }))
+    }
+
+    /// 只允许当前用户访问的安全描述符
+    struct OwnerOnly(PSECURITY_DESCRIPTOR);
+
+    // SAFETY: 安全描述符创建后只读，释放前一直有效
+    unsafe impl Send for OwnerOnly {}
+    unsafe impl Sync for OwnerOnly {}
+
+    impl OwnerOnly {
+        fn new() -> io::Result<Self> {
+            let sddl: Vec<u16> = format!("D:P(A;;GA;;;{})", current_user_sid()?)
+                .encode_utf16()
+                .chain(std::iter::once(0))
+                .collect();
+            let mut descriptor = null_mut();
+            // SAFETY: sddl 以 NUL 结尾；成功时 descriptor 由 Drop 中的 LocalFree 释放
+            let ok = unsafe {
+                ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                    sddl.as_ptr(),
+                    SDDL_REVISION_1,
+                    &mut descriptor,
+                    null_mut(),
+                )
+            };
+            if ok == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self(descriptor))
+        }
+
+        /// 以该安全描述符创建一个管道实例（拒绝远程客户端）
+        fn create(&self, options: &mut ServerOptions, name: &str) -> io::Result<NamedPipeServer> {
+            let mut attributes = SECURITY_ATTRIBUTES {
+                nLength: size_of::<SECURITY_ATTRIBUTES>() as u32,
+                lpSecurityDescriptor: self.0,
+                bInheritHandle: 0,
+            };
+            // SAFETY: attributes 及其指向的安全描述符在调用期间有效
+            unsafe {
+                options
+                    .reject_remote_clients(true)
+                    .create_with_security_attributes_raw(
+                        name,
+                        (&mut attributes as *mut SECURITY_ATTRIBUTES).cast::<c_void>(),
+                    )
+            }
+        }
+    }
+
+    impl Drop for OwnerOnly {
+        fn drop(&mut self) {
+            // SAFETY: 由 ConvertStringSecurityDescriptorToSecurityDescriptorW 分配
+            unsafe { LocalFree(self.0) };
+        }
+    }
+
+    /// 当前进程用户的SID（如 `S-1-5-21-…`）
+    fn current_user_sid() -> io::Result<String> {
+        // SAFETY: 各调用的输出参数均指向有效内存；令牌句柄及SID字符串用完即释放
+        unsafe {
+            let mut token = null_mut();
+            if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut len = 0;
+            GetTokenInformation(token, TokenUser, null_mut(), 0, &mut len);
+            // 按8字节对齐分配，TOKEN_USER 中含指针
+            let mut buffer = vec![0u64; (len as usize).div_ceil(8)];
+            let ok =
+                GetTokenInformation(token, TokenUser, buffer.as_mut_ptr().cast(), len, &mut len);
+            let error = io::Error::last_os_error();
+            CloseHandle(token);
+            if ok == 0 {
+                return Err(error);
+            }
+            let user = &*buffer.as_ptr().cast::<TOKEN_USER>();
+            let mut text = null_mut();
+            if ConvertSidToStringSidW(user.User.Sid, &mut text) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let len = (0..).take_while(|&i| *text.add(i) != 0).count();
+            let sid = String::from_utf16_lossy(std::slice::from_raw_parts(text, len));
+            LocalFree(text.cast());
+            Ok(sid)
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_control_round_trip() {
+        let root = std::env::temp_dir().join(format!("gxr_control_{}", std::process::id()));
+        let run_dir = Arc::new(RunDir::new(&root, "20260101000000-ab12", "net ping"));
+        let ctx = ScanContext::cli()
+            .with_run_dir(Some(run_dir.clone()))
+            .with_control();
+        let server =
+            ControlServer::start(&ctx, &run_dir, "20260101000000-ab12", "net ping").unwrap();
+        let socket = run_dir.path().join(CONTROL_SOCKET_NAME);
+        let mode = std::fs::metadata(&socket).unwrap().permissions();
+        assert_eq!(
+            std::os::unix::fs::PermissionsExt::mode(&mode) & 0o777,
+            0o600
+        );
+        // 绑定用的临时目录已删除
+        let entries: Vec<_> = std::fs::read_dir(run_dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, [CONTROL_SOCKET_NAME]);
+
+        let request = |request: ControlRequest| {
+            let socket = socket.clone();
+            async move {
+                let stream = tokio::net::UnixStream::connect(&socket).await.unwrap();
+                exchange(stream, &request).await
+            }
+        };
+
+        let progress = ctx.new_progress(10);
+        progress.stats().dispatch();
+        progress.record(crate::utils::stats::Outcome::Succeeded);
+        ctx.emit(&json!({ "ip": "10.0.0.1" }));
+        ctx.emit_with_outcome(
+            &json!({ "ip": "10.0.0.2" }),
+            crate::utils::stats::Outcome::Failed,
+        );
+
+        let status = request(ControlRequest::Status).await.unwrap();
+        assert_eq!(status["state"], "running");
+        assert_eq!(status["progress"]["completed"], 1);
+        assert_eq!(status["findings"], 1);
+        assert!(status["rate"].is_null());
+
+        let findings = request(ControlRequest::Findings { limit: Some(5) })
+            .await
+            .unwrap();
+        assert_eq!(findings["findings"][0]["ip"], "10.0.0.1");
+
+        let rate = request(ControlRequest::SetRate { rate: 50 }).await.unwrap();
+        assert_eq!(rate["rate"], 50.0);
+        assert_eq!(ctx.throttle().rate(), Some(50.0));
+
+        request(ControlRequest::Pause).await.unwrap();
+        assert!(ctx.pause.is_paused());
+        request(ControlRequest::Resume).await.unwrap();
+        assert!(!ctx.pause.is_paused());
+        request(ControlRequest::Stop).await.unwrap();
+        assert!(ctx.is_cancelled());
+
+        let stream = tokio::net::UnixStream::connect(&socket).await.unwrap();
+        let (reader, mut writer) = tokio::io::split(stream);
+        writer.write_all(b"{\"cmd\":\"reboot\"}\n").await.unwrap();
+        let mut line = String::new();
+        BufReader::new(reader).read_line(&mut line).await.unwrap();
+        assert!(line.contains("无效的请求"));
+
+        drop(server);
+        assert!(!socket.exists());
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,836 @@
+// src/utils/dns.rs
+use super::console::Icon;
+use super::secure_dns::{SecureTransport, SecureUpstream};
+use super::tls::parse_cert_pin;
+use super::{config_file, load_config_section};
+use futures::FutureExt;
+use futures::future::{BoxFuture, Shared};
+use hickory_resolver::TokioAsyncResolver;
+use hickory_resolver::config::{
+    NameServerConfig, NameServerConfigGroup, Protocol, ResolverConfig, ResolverOpts,
+};
+use hickory_resolver::error::ResolveErrorKind;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::time::{Instant, timeout};
+
+/// 配置文件中的段落名
+pub const CONFIG_SECTION: &str = "dns";
+
+/// DNS服务器的默认端口
+const DNS_PORT: u16 = 53;
+
+static CONFIG: OnceLock<DnsConfig> = OnceLock::new();
+static RESOLVER: OnceLock<Resolver> = OnceLock::new();
+
+/// 解析参数（可在配置文件的 `dns` 段落中调整，`--dns-server`、`--dns` 等优先于配置文件）
+///
+/// ```yaml
+/// dns:
+///   servers: ["10.0.0.53", "10.0.0.54:5353"]
+///   timeout_ms: 2000
+///   negative_ttl_secs: 60
+///   upstream: https://doh.internal/dns-query
+///   allow_fallback: true
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DnsConfig {
+    /// 上游DNS服务器（为空时使用系统配置）
+    pub servers: Vec<String>,
+    /// 单次查询超时（毫秒）
+    pub timeout_ms: u64,
+    /// 否定应答没有携带SOA时的缓存时间（秒）
+    pub negative_ttl_secs: u64,
+    /// 肯定应答的最长缓存时间（秒），超过时按该值缓存
+    pub max_ttl_secs: u64,
+    /// 加密上游（`dot://主机[:端口]` 或 `https://查询地址`），设置后不再直接查询 `servers`
+    pub upstream: Option<String>,
+    /// 加密上游连接或握手失败时回退到明文DNS（`servers` 或系统配置）
+    pub allow_fallback: bool,
+    /// 加密上游证书的SHA-256指纹，指定后只接受这些证书
+    pub pins: Vec<String>,
+}
+
+impl Default for DnsConfig {
+    fn default() -> Self {
+        Self {
+            servers: Vec::new(),
+            timeout_ms: 2000,
+            negative_ttl_secs: 60,
+            max_ttl_secs: 3600,
+            upstream: None,
+            allow_fallback: false,
+            pins: Vec::new(),
+        }
+    }
+}
+
+impl DnsConfig {
+    /// 从配置文件读取解析参数，未配置的项使用默认值
+    pub fn load() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        load_config_section(&config_file(), CONFIG_SECTION)
+    }
+
+    /// 解析上游服务器地址（未写端口时使用53）
+    pub fn server_addrs(&self) -> Result<Vec<SocketAddr>, Box<dyn Error + Send + Sync>> {
+        self.servers
+            .iter()
+            .map(|s| {
+                s.parse::<SocketAddr>()
+                    .or_else(|_| s.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, DNS_PORT)))
+                    .map_err(|_| format!("无效的DNS服务器地址: {}", s).into())
+            })
+            .collect()
+    }
+
+    /// 解析加密上游（未设置时为 `None`）
+    pub fn secure_upstream(&self) -> Result<Option<SecureUpstream>, Box<dyn Error + Send + Sync>> {
+        Ok(self
+            .upstream
+            .as_deref()
+            .map(SecureUpstream::parse)
+            .transpose()?)
+    }
+
+    /// 解析加密上游的证书指纹
+    pub fn cert_pins(&self) -> Result<Vec<[u8; 32]>, Box<dyn Error + Send + Sync>> {
+        Ok(self
+            .pins
+            .iter()
+            .map(|pin| parse_cert_pin(pin))
+            .collect::<Result<_, _>>()?)
+    }
+}
+
+/// 一次DNS查询
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DnsQuery {
+    /// 正向解析（A/AAAA）
+    Ip(String),
+    /// 反向解析（PTR）
+    Ptr(IpAddr),
+    /// 服务定位（SRV）
+    Srv(String),
+}
+
+impl fmt::Display for DnsQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnsQuery::Ip(name) => write!(f, "{}", name),
+            DnsQuery::Ptr(ip) => write!(f, "PTR {}", ip),
+            DnsQuery::Srv(name) => write!(f, "SRV {}", name),
+        }
+    }
+}
+
+/// 查询得到的记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DnsRecords {
+    /// 正向解析得到的地址
+    Ips(Vec<IpAddr>),
+    /// 反向解析得到的主机名（不含末尾的点）
+    Names(Vec<String>),
+    /// 服务定位记录（按优先级、权重排序）
+    Srv(Vec<SrvTarget>),
+}
+
+/// 一条SRV记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SrvTarget {
+    /// 优先级（越小越优先）
+    pub priority: u16,
+    /// 同一优先级内的权重（越大越优先）
+    pub weight: u16,
+    /// 服务端口
+    pub port: u16,
+    /// 提供服务的主机名（不含末尾的点）
+    pub target: String,
+}
+
+/// 上游返回的应答
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsAnswer {
+    /// 记录
+    pub records: DnsRecords,
+    /// 记录的剩余TTL
+    pub ttl: Duration,
+}
+
+/// 查询失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DnsError {
+    /// 域名不存在或没有对应记录（可缓存）
+    NotFound {
+        /// 上游给出的否定缓存时间（应答携带SOA时）
+        negative_ttl: Option<Duration>,
+    },
+    /// 查询超时
+    Timeout,
+    /// 其他错误（网络不可达、上游拒绝等）
+    Other(String),
+}
+
+impl fmt::Display for DnsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnsError::NotFound { .. } => f.write_str("没有找到记录"),
+            DnsError::Timeout => f.write_str("查询超时"),
+            DnsError::Other(e) => f.write_str(e),
+        }
+    }
+}
+
+impl Error for DnsError {}
+
+/// 向上游发送DNS查询的方式
+///
+/// [`Resolver`] 只通过该接口访问网络，测试时可替换为返回预设应答的实现。
+pub trait DnsTransport: Send + Sync {
+    /// 发送一次查询（超时由调用方控制）
+    fn query(&self, query: &DnsQuery) -> BoxFuture<'_, Result<DnsAnswer, DnsError>>;
+
+    /// 已从加密上游回退到明文DNS时的原因（只有允许回退的加密上游会回退）
+    fn fallback_reason(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// 基于hickory的上游查询（自身不缓存，缓存由 [`Resolver`] 统一管理）
+pub struct HickoryTransport {
+    resolver: TokioAsyncResolver,
+}
+
+impl HickoryTransport {
+    /// 创建上游查询
+    ///
+    /// # 参数
+    /// * `servers` - 上游DNS服务器，为空时读取系统配置
+    pub fn new(servers: &[SocketAddr]) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let (config, mut opts) = if servers.is_empty() {
+            hickory_resolver::system_conf::read_system_conf()
+                .map_err(|e| format!("无法读取系统DNS配置: {}", e))?
+        } else {
+            let group: Vec<NameServerConfig> = servers
+                .iter()
+                .flat_map(|&addr| {
+                    [
+                        NameServerConfig::new(addr, Protocol::Udp),
+                        NameServerConfig::new(addr, Protocol::Tcp),
+                    ]
+                })
+                .collect();
+            (
+                ResolverConfig::from_parts(None, Vec::new(), NameServerConfigGroup::from(group)),
+                ResolverOpts::default(),
+            )
+        };
+        opts.cache_size = 0;
+        Ok(Self {
+            resolver: TokioAsyncResolver::tokio(config, opts),
+        })
+    }
+}
+
+impl DnsTransport for HickoryTransport {
+    fn query(&self, query: &DnsQuery) -> BoxFuture<'_, Result<DnsAnswer, DnsError>> {
+        let query = query.clone();
+        async move {
+            let (records, valid_until) = match query {
+                DnsQuery::Ip(ref name) => {
+                    let lookup = self.resolver.lookup_ip(name.as_str()).await;
+                    let lookup = lookup.map_err(from_resolve_error)?;
+                    (
+                        DnsRecords::Ips(lookup.iter().collect()),
+                        lookup.valid_until(),
+                    )
+                }
+                DnsQuery::Ptr(ip) => {
+                    let lookup = self.resolver.reverse_lookup(ip).await;
+                    let lookup = lookup.map_err(from_resolve_error)?;
+                    let names = lookup
+                        .iter()
+                        .map(|name| name.to_utf8().trim_end_matches('.').to_string())
+                        .collect();
+                    (DnsRecords::Names(names), lookup.valid_until())
+                }
+                DnsQuery::Srv(ref name) => {
+                    let lookup = self.resolver.srv_lookup(name.as_str()).await;
+                    let lookup = lookup.map_err(from_resolve_error)?;
+                    let mut targets: Vec<SrvTarget> = lookup
+                        .iter()
+                        .map(|srv| SrvTarget {
+                            priority: srv.priority(),
+                            weight: srv.weight(),
+                            port: srv.port(),
+                            target: srv.target().to_utf8().trim_end_matches('.').to_string(),
+                        })
+                        .collect();
+                    sort_srv(&mut targets);
+                    (DnsRecords::Srv(targets), lookup.as_lookup().valid_until())
+                }
+            };
+            Ok(DnsAnswer {
+                records,
+                ttl: valid_until.saturating_duration_since(std::time::Instant::now()),
+            })
+        }
+        .boxed()
+    }
+}
+
+/// SRV记录按优先级升序、权重降序排列（同权重按主机名）
+pub fn sort_srv(targets: &mut [SrvTarget]) {
+    targets.sort_by(|a, b| {
+        (a.priority, std::cmp::Reverse(a.weight), &a.target).cmp(&(
+            b.priority,
+            std::cmp::Reverse(b.weight),
+            &b.target,
+        ))
+    });
+}
+
+fn from_resolve_error(e: hickory_resolver::error::ResolveError) -> DnsError {
+    match e.kind() {
+        ResolveErrorKind::NoRecordsFound { negative_ttl, .. } => DnsError::NotFound {
+            negative_ttl: negative_ttl.map(|ttl| Duration::from_secs(ttl.into())),
+        },
+        ResolveErrorKind::Timeout => DnsError::Timeout,
+        _ => DnsError::Other(e.to_string()),
+    }
+}
+
+/// 解析统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsStats {
+    /// 解析请求数
+    pub queries: u64,
+    /// 命中缓存数（含等待同一查询的请求）
+    pub cache_hits: u64,
+    /// 实际发往上游的查询数
+    pub upstream: u64,
+    /// 失败的解析请求数
+    pub failures: u64,
+}
+
+type Lookup = Result<DnsRecords, DnsError>;
+
+/// 带缓存的异步DNS解析器
+///
+/// 可在各模块间共享（克隆只复制引用）：
+/// - 按TTL缓存肯定应答，按SOA（或配置的默认值）缓存否定应答
+/// - 同一查询正在进行时，后来的请求等待同一个结果，不重复发往上游
+/// - 每次上游查询受超时限制，超时及其他错误不缓存
+#[derive(Clone)]
+pub struct Resolver {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    transport: Box<dyn DnsTransport>,
+    config: DnsConfig,
+    cache: Mutex<HashMap<DnsQuery, (Lookup, Instant)>>,
+    in_flight: Mutex<HashMap<DnsQuery, Shared<BoxFuture<'static, Lookup>>>>,
+    queries: AtomicU64,
+    cache_hits: AtomicU64,
+    upstream: AtomicU64,
+    failures: AtomicU64,
+}
+
+impl Resolver {
+    /// 使用指定的上游查询方式创建解析器
+    ///
+    /// # 参数
+    /// * `transport` - 上游查询方式
+    /// * `config` - 超时及缓存参数（`servers` 由上游查询方式使用，这里忽略）
+    pub fn new(transport: impl DnsTransport + 'static, config: DnsConfig) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                transport: Box::new(transport),
+                config,
+                cache: Mutex::new(HashMap::new()),
+                in_flight: Mutex::new(HashMap::new()),
+                queries: AtomicU64::new(0),
+                cache_hits: AtomicU64::new(0),
+                upstream: AtomicU64::new(0),
+                failures: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// 按配置创建访问真实上游的解析器
+    ///
+    /// # 参数
+    /// * `config` - 解析参数
+    pub fn from_config(config: DnsConfig) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let plain = HickoryTransport::new(&config.server_addrs()?);
+        let Some(upstream) = config.secure_upstream()? else {
+            return Ok(Self::new(plain?, config));
+        };
+        let fallback: Option<Box<dyn DnsTransport>> = match plain {
+            Ok(plain) if config.allow_fallback => Some(Box::new(plain)),
+            Ok(_) => None,
+            Err(e) if config.allow_fallback => return Err(e),
+            Err(_) => None,
+        };
+        let transport = SecureTransport::new(upstream, &config.cert_pins()?, fallback)?;
+        Ok(Self::new(transport, config))
+    }
+
+    /// 正向解析主机名
+    ///
+    /// # 参数
+    /// * `host` - 主机名（不区分大小写）
+    pub async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>, DnsError> {
+        let name = host.trim_end_matches('.').to_ascii_lowercase();
+        match self.resolve(DnsQuery::Ip(name)).await? {
+            DnsRecords::Ips(ips) => Ok(ips),
+            _ => Err(DnsError::Other("应答类型不匹配".to_string())),
+        }
+    }
+
+    /// 反向解析IP地址
+    ///
+    /// # 参数
+    /// * `ip` - IP地址
+    pub async fn reverse(&self, ip: IpAddr) -> Result<Vec<String>, DnsError> {
+        match self.resolve(DnsQuery::Ptr(ip)).await? {
+            DnsRecords::Names(names) => Ok(names),
+            _ => Err(DnsError::Other("应答类型不匹配".to_string())),
+        }
+    }
+
+    /// 查询服务定位记录
+    ///
+    /// # 参数
+    /// * `name` - 服务名称（如 `_ldap._tcp.dc._msdcs.corp.local`，不区分大小写）
+    pub async fn lookup_srv(&self, name: &str) -> Result<Vec<SrvTarget>, DnsError> {
+        let name = name.trim_end_matches('.').to_ascii_lowercase();
+        match self.resolve(DnsQuery::Srv(name)).await? {
+            DnsRecords::Srv(targets) => Ok(targets),
+            _ => Err(DnsError::Other("应答类型不匹配".to_string())),
+        }
+    }
+
+    /// 已从加密上游回退到明文DNS时的原因
+    pub fn fallback_reason(&self) -> Option<&str> {
+        self.inner.transport.fallback_reason()
+    }
+
+    /// 当前的解析统计
+    pub fn stats(&self) -> DnsStats {
+        let inner = &self.inner;
+        DnsStats {
+            queries: inner.queries.load(Ordering::Relaxed),
+            cache_hits: inner.cache_hits.load(Ordering::Relaxed),
+            upstream: inner.upstream.load(Ordering::Relaxed),
+            failures: inner.failures.load(Ordering::Relaxed),
+        }
+    }
+
+    async fn resolve(&self, query: DnsQuery) -> Lookup {
+        let inner = &self.inner;
+        inner.queries.fetch_add(1, Ordering::Relaxed);
+
+        let pending = {
+            let cached = inner.cache.lock().unwrap().get(&query).cloned();
+            match cached {
+                Some((result, expires)) if Instant::now() < expires => {
+                    inner.cache_hits.fetch_add(1, Ordering::Relaxed);
+                    return inner.count_failure(result);
+                }
+                _ => {}
+            }
+
+            let mut in_flight = inner.in_flight.lock().unwrap();
+            match in_flight.get(&query) {
+                Some(pending) => {
+                    inner.cache_hits.fetch_add(1, Ordering::Relaxed);
+                    pending.clone()
+                }
+                None => {
+                    let query_key = query.clone();
+                    let resolver = self.clone();
+                    let pending = async move { resolver.query_upstream(query_key).await }
+                        .boxed()
+                        .shared();
+                    in_flight.insert(query, pending.clone());
+                    pending
+                }
+            }
+        };
+
+        inner.count_failure(pending.await)
+    }
+
+    /// 向上游查询并写入缓存（同一查询只执行一次，由所有等待者共享）
+    async fn query_upstream(&self, query: DnsQuery) -> Lookup {
+        let inner = &self.inner;
+        inner.upstream.fetch_add(1, Ordering::Relaxed);
+        let limit = Duration::from_millis(inner.config.timeout_ms);
+        let result = match timeout(limit, inner.transport.query(&query)).await {
+            Ok(result) => result,
+            Err(_) => Err(DnsError::Timeout),
+        };
+
+        let (lookup, ttl) = match result {
+            Ok(answer) => {
+                let max_ttl = Duration::from_secs(inner.config.max_ttl_secs);
+                (Ok(answer.records), answer.ttl.min(max_ttl))
+            }
+            Err(DnsError::NotFound { negative_ttl }) => {
+                let ttl =
+                    negative_ttl.unwrap_or(Duration::from_secs(inner.config.negative_ttl_secs));
+                (Err(DnsError::NotFound { negative_ttl }), ttl)
+            }
+            Err(e) => (Err(e), Duration::ZERO),
+        };
+
+        if !ttl.is_zero() {
+            inner
+                .cache
+                .lock()
+                .unwrap()
+                .insert(query.clone(), (lookup.clone(), Instant::now() + ttl));
+        }
+        inner.in_flight.lock().unwrap().remove(&query);
+        lookup
+    }
+}
+
+impl Inner {
+    fn count_failure(&self, result: Lookup) -> Lookup {
+        if result.is_err() {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+}
+
+/// 设置全局解析器使用的参数（仅首次调用生效，应在程序启动时调用）
+///
+/// 解析器在第一次使用时才创建，不做DNS查询的命令不受系统DNS配置影响。
+///
+/// # 参数
+/// * `servers` - 命令行指定的上游服务器，非空时覆盖配置文件
+/// * `upstream` - 命令行指定的加密上游（`--dns`），覆盖配置文件
+/// * `allow_fallback` - 加密上游握手失败时回退到明文DNS（`--dns-allow-fallback`）
+/// * `pins` - 加密上游证书的SHA-256指纹（`--dns-pin`），非空时覆盖配置文件
+pub fn init(
+    servers: &[String],
+    upstream: Option<&str>,
+    allow_fallback: bool,
+    pins: &[String],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut config = DnsConfig::load()?;
+    if !servers.is_empty() {
+        config.servers = servers.to_vec();
+    }
+    if let Some(upstream) = upstream {
+        config.upstream = Some(upstream.to_string());
+    }
+    config.allow_fallback |= allow_fallback;
+    if !pins.is_empty() {
+        config.pins = pins.to_vec();
+    }
+    config.server_addrs()?;
+    if config.secure_upstream()?.is_none() && (allow_fallback || !pins.is_empty()) {
+        return Err("--dns-allow-fallback 及 --dns-pin 需要指定加密DNS上游（--dns 或配置文件中的 dns.upstream）".into());
+    }
+    config.cert_pins()?;
+    let _ = CONFIG.set(config);
+    Ok(())
+}
+
+/// 全局共享的解析器
+///
+/// 未调用 [`init`] 时按配置文件创建；无法读取系统DNS配置时使用hickory的默认上游。
+pub fn resolver() -> &'static Resolver {
+    RESOLVER.get_or_init(|| {
+        let config = CONFIG
+            .get()
+            .cloned()
+            .unwrap_or_else(|| DnsConfig::load().unwrap_or_default());
+        Resolver::from_config(config.clone()).unwrap_or_else(|e| {
+            eprintln!("{} {}，使用默认DNS服务器", Icon::Warn, e);
+            let transport = HickoryTransport {
+                resolver: TokioAsyncResolver::tokio(
+                    ResolverConfig::default(),
+                    ResolverOpts::default(),
+                ),
+            };
+            Resolver::new(transport, config)
+        })
+    })
+}
+
+/// 本次运行的解析方式及加密上游是否回退（写入运行元数据，使用系统DNS配置时不记录）
+pub fn meta_entries() -> Vec<(String, String)> {
+    let Some(config) = CONFIG.get() else {
+        return Vec::new();
+    };
+    let mut entries = Vec::new();
+    match config.secure_upstream() {
+        Ok(Some(upstream)) => {
+            let pinned = if config.pins.is_empty() {
+                ""
+            } else {
+                "（校验证书指纹）"
+            };
+            entries.push((
+                "dns_resolver".to_string(),
+                format!("{}{}", upstream, pinned),
+            ));
+            let fallback = match RESOLVER.get().and_then(Resolver::fallback_reason) {
+                Some(reason) => format!("已回退到明文DNS（{}）", reason),
+                None if config.allow_fallback => "允许回退，未发生".to_string(),
+                None => "不允许回退".to_string(),
+            };
+            entries.push(("dns_fallback".to_string(), fallback));
+        }
+        _ if !config.servers.is_empty() => {
+            entries.push((
+                "dns_resolver".to_string(),
+                format!("明文DNS {}", config.servers.join(", ")),
+            ));
+        }
+        _ => {}
+    }
+    entries
+}
+
+/// 系统DNS配置中的本地域及搜索域（不含末尾的点，无法读取系统配置时为空）
+pub fn search_domains() -> Vec<String> {
+    let Ok((config, _)) = hickory_resolver::system_conf::read_system_conf() else {
+        return Vec::new();
+    };
+    let mut domains: Vec<String> = Vec::new();
+    for name in config.domain().into_iter().chain(config.search()) {
+        let domain = name.to_utf8().trim_end_matches('.').to_ascii_lowercase();
+        if !domain.is_empty() && !domains.contains(&domain) {
+            domains.push(domain);
+        }
+    }
+    domains
+}
+
+/// 全局解析器的统计（尚未使用过解析器时为 `None`）
+pub fn stats() -> Option<DnsStats> {
+    RESOLVER
+        .get()
+        .map(Resolver::stats)
+        .filter(|s| s.queries > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// 按名称返回预设应答的上游，记录查询次数
+    #[derive(Default)]
+    struct StubTransport {
+        answers: HashMap<DnsQuery, Result<DnsAnswer, DnsError>>,
+        latency: Duration,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl StubTransport {
+        fn with(mut self, query: DnsQuery, answer: Result<DnsAnswer, DnsError>) -> Self {
+            self.answers.insert(query, answer);
+            self
+        }
+    }
+
+    impl DnsTransport for StubTransport {
+        fn query(&self, query: &DnsQuery) -> BoxFuture<'_, Result<DnsAnswer, DnsError>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let answer = self
+                .answers
+                .get(query)
+                .cloned()
+                .unwrap_or(Err(DnsError::Other("refused".to_string())));
+            async move {
+                tokio::time::sleep(self.latency).await;
+                answer
+            }
+            .boxed()
+        }
+    }
+
+    fn ip_answer(ip: &str, ttl_secs: u64) -> Result<DnsAnswer, DnsError> {
+        Ok(DnsAnswer {
+            records: DnsRecords::Ips(vec![ip.parse().unwrap()]),
+            ttl: Duration::from_secs(ttl_secs),
+        })
+    }
+
+    fn host(name: &str) -> DnsQuery {
+        DnsQuery::Ip(name.to_string())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_positive_cache_respects_ttl() {
+        let stub = StubTransport::default().with(host("db.corp"), ip_answer("10.0.0.5", 30));
+        let calls = stub.calls.clone();
+        let resolver = Resolver::new(stub, DnsConfig::default());
+
+        let ip: IpAddr = "10.0.0.5".parse().unwrap();
+        assert_eq!(resolver.lookup_ip("DB.corp.").await.unwrap(), [ip]);
+        assert_eq!(resolver.lookup_ip("db.corp").await.unwrap(), [ip]);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        tokio::time::advance(Duration::from_secs(31)).await;
+        resolver.lookup_ip("db.corp").await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            resolver.stats(),
+            DnsStats {
+                queries: 3,
+                cache_hits: 1,
+                upstream: 2,
+                failures: 0
+            }
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_negative_answers_are_cached_errors_are_not() {
+        let stub = StubTransport::default()
+            .with(
+                host("gone.corp"),
+                Err(DnsError::NotFound {
+                    negative_ttl: Some(Duration::from_secs(5)),
+                }),
+            )
+            .with(
+                host("nosoa.corp"),
+                Err(DnsError::NotFound { negative_ttl: None }),
+            );
+        let calls = stub.calls.clone();
+        let config = DnsConfig {
+            negative_ttl_secs: 60,
+            ..Default::default()
+        };
+        let resolver = Resolver::new(stub, config);
+
+        for _ in 0..2 {
+            assert!(resolver.lookup_ip("gone.corp").await.is_err());
+            assert!(resolver.lookup_ip("nosoa.corp").await.is_err());
+            // 上游拒绝（未配置的名称）不缓存
+            assert!(resolver.lookup_ip("flaky.corp").await.is_err());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        // SOA给出的5秒过期，没有SOA的按配置缓存60秒
+        tokio::time::advance(Duration::from_secs(6)).await;
+        resolver.lookup_ip("gone.corp").await.unwrap_err();
+        resolver.lookup_ip("nosoa.corp").await.unwrap_err();
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+        assert_eq!(resolver.stats().failures, 8);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_concurrent_lookups_share_one_query() {
+        let stub = StubTransport {
+            latency: Duration::from_millis(200),
+            ..Default::default()
+        }
+        .with(
+            DnsQuery::Ptr("10.0.0.5".parse().unwrap()),
+            Ok(DnsAnswer {
+                records: DnsRecords::Names(vec!["db.corp".to_string()]),
+                ttl: Duration::from_secs(60),
+            }),
+        );
+        let calls = stub.calls.clone();
+        let resolver = Resolver::new(stub, DnsConfig::default());
+
+        let ip = "10.0.0.5".parse().unwrap();
+        let lookups = (0..5).map(|_| resolver.reverse(ip));
+        let results = futures::future::join_all(lookups).await;
+
+        assert!(
+            results
+                .iter()
+                .all(|r| r.as_deref() == Ok(&["db.corp".to_string()][..]))
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(resolver.stats().cache_hits, 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_upstream_times_out() {
+        let stub = StubTransport {
+            latency: Duration::from_secs(10),
+            ..Default::default()
+        }
+        .with(host("slow.corp"), ip_answer("10.0.0.9", 60));
+        let config = DnsConfig {
+            timeout_ms: 500,
+            ..Default::default()
+        };
+        let resolver = Resolver::new(stub, config);
+
+        let start = Instant::now();
+        assert_eq!(
+            resolver.lookup_ip("slow.corp").await,
+            Err(DnsError::Timeout)
+        );
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_server_addrs_default_port() {
+        let config = DnsConfig {
+            servers: vec!["10.0.0.53".to_string(), "[::1]:5353".to_string()],
+            ..Default::default()
+        };
+        let addrs = config.server_addrs().unwrap();
+        assert_eq!(addrs[0], "10.0.0.53:53".parse().unwrap());
+        assert_eq!(addrs[1], "[::1]:5353".parse().unwrap());
+
+        let config = DnsConfig {
+            servers: vec!["dns.corp".to_string()],
+            ..Default::default()
+        };
+        assert!(config.server_addrs().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_srv_lookup_is_cached_separately() {
+        let name = "_ldap._tcp.dc._msdcs.corp.local";
+        let dc = SrvTarget {
+            priority: 0,
+            weight: 100,
+            port: 389,
+            target: "dc01.corp.local".to_string(),
+        };
+        let stub = StubTransport::default().with(
+            DnsQuery::Srv(name.to_string()),
+            Ok(DnsAnswer {
+                records: DnsRecords::Srv(vec![dc.clone()]),
+                ttl: Duration::from_secs(60),
+            }),
+        );
+        let calls = stub.calls.clone();
+        let resolver = Resolver::new(stub, DnsConfig::default());
+
+        assert_eq!(
+            resolver
+                .lookup_srv("_LDAP._tcp.dc._msdcs.corp.local.")
+                .await,
+            Ok(vec![dc.clone()])
+        );
+        assert_eq!(resolver.lookup_srv(name).await, Ok(vec![dc]));
+        // 同名的A记录查询不会命中SRV缓存
+        assert!(resolver.lookup_ip(name).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,147 @@
+// src/utils/dualstack.rs
+use super::ExcelSheet;
+use super::run_dir::SummaryItem;
+use super::targets::TargetSet;
+use serde::Serialize;
+use std::net::IpAddr;
+
+/// 差异摘要中最多列出的主机数
+const MAX_LISTED: usize = 5;
+
+/// 双栈主机：同时解析到IPv4和IPv6地址的主机名，两个地址族的结果并列
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DualStackHost {
+    /// 主机名
+    pub hostname: String,
+    /// 解析得到的IPv4地址
+    pub ipv4: Vec<String>,
+    /// 解析得到的IPv6地址
+    pub ipv6: Vec<String>,
+    /// 是否有IPv4地址可达
+    pub ipv4_alive: bool,
+    /// 是否有IPv6地址可达
+    pub ipv6_alive: bool,
+    /// 两个地址族结论不一致时的说明（一致时为空）
+    pub discrepancy: String,
+}
+
+impl DualStackHost {
+    /// 两个地址族的结论是否不一致（如IPv6存活而IPv4无响应）
+    pub fn is_discrepant(&self) -> bool {
+        self.ipv4_alive != self.ipv6_alive
+    }
+}
+
+/// 按主机名对照双栈主机两个地址族的结果
+///
+/// 只有同时解析到IPv4和IPv6地址的主机名才会列出，同一主机名按一行汇总。
+///
+/// # 参数
+/// * `targets` - 目标集合（含主机名与IP的对应关系）
+/// * `alive` - 某个IP是否可达（如Ping存活、有端口应答）
+pub fn correlate(targets: &TargetSet, alive: impl Fn(&str) -> bool) -> Vec<DualStackHost> {
+    targets
+        .hostnames()
+        .filter_map(|(hostname, ips)| {
+            let (ipv6, ipv4): (Vec<String>, Vec<String>) = ips
+                .into_iter()
+                .partition(|ip| ip.parse::<IpAddr>().is_ok_and(|ip| ip.is_ipv6()));
+            if ipv4.is_empty() || ipv6.is_empty() {
+                return None;
+            }
+            let ipv4_alive = ipv4.iter().any(|ip| alive(ip));
+            let ipv6_alive = ipv6.iter().any(|ip| alive(ip));
+            let discrepancy = match (ipv4_alive, ipv6_alive) {
+                (true, false) => "仅IPv4可达",
+                (false, true) => "仅IPv6可达",
+                _ => "",
+            };
+            Some(DualStackHost {
+                hostname: hostname.to_string(),
+                ipv4,
+                ipv6,
+                ipv4_alive,
+                ipv6_alive,
+                discrepancy: discrepancy.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// 统计摘要中的双栈主机情况（没有双栈主机时为空）
+///
+/// 两个地址族结论不一致的主机单独列出，通常意味着其中一个地址族的防护策略不同。
+pub fn summary_items(hosts: &[DualStackHost]) -> Vec<SummaryItem> {
+    if hosts.is_empty() {
+        return Vec::new();
+    }
+    let discrepant: Vec<&DualStackHost> = hosts.iter().filter(|h| h.is_discrepant()).collect();
+    let mut items = vec![(
+        "双栈主机".to_string(),
+        format!("{} 个，结论不一致 {} 个", hosts.len(), discrepant.len()),
+    )];
+    if !discrepant.is_empty() {
+        let mut listed: Vec<String> = discrepant
+            .iter()
+            .take(MAX_LISTED)
+            .map(|h| format!("{}（{}）", h.hostname, h.discrepancy))
+            .collect();
+        if discrepant.len() > MAX_LISTED {
+            listed.push(format!("等 {} 个", discrepant.len()));
+        }
+        items.push(("双栈差异".to_string(), listed.join("，")));
+    }
+    items
+}
+
+/// 生成双栈主机表（每个主机名一行）
+pub fn dual_stack_sheet(hosts: &[DualStackHost]) -> ExcelSheet {
+    let reach = |alive: bool| if alive { "可达" } else { "不可达" }.to_string();
+    ExcelSheet {
+        name: "双栈主机".to_string(),
+        headers: ["主机名", "IPv4地址", "IPv4", "IPv6地址", "IPv6", "差异"]
+            .map(String::from)
+            .to_vec(),
+        rows: hosts
+            .iter()
+            .map(|h| {
+                vec![
+                    h.hostname.clone(),
+                    h.ipv4.join(", "),
+                    reach(h.ipv4_alive),
+                    h.ipv6.join(", "),
+                    reach(h.ipv6_alive),
+                    h.discrepancy.clone(),
+                ]
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correlate_dual_stack_hosts() {
+        let mut targets = TargetSet::default();
+        targets.add("www.corp", vec!["10.0.0.1".into(), "fd00::1".into()]);
+        targets.add("db.corp", vec!["10.0.0.2".into(), "fd00::2".into()]);
+        targets.add("v4only.corp", vec!["10.0.0.3".into()]);
+        targets.add("10.0.0.4", vec!["10.0.0.4".into()]);
+
+        let alive = ["fd00::1", "10.0.0.2", "fd00::2", "10.0.0.3"];
+        let hosts = correlate(&targets, |ip| alive.contains(&ip));
+        assert_eq!(hosts.len(), 2);
+        assert_eq!(hosts[0].ipv4, ["10.0.0.1"]);
+        assert_eq!(hosts[0].ipv6, ["fd00::1"]);
+        assert!(hosts[0].is_discrepant());
+        assert!(!hosts[1].is_discrepant());
+
+        let items = summary_items(&hosts);
+        assert_eq!(items[0].1, "2 个，结论不一致 1 个");
+        assert_eq!(items[1].1, "www.corp（仅IPv6可达）");
+        assert_eq!(dual_stack_sheet(&hosts).rows[0][5], "仅IPv6可达");
+        assert!(summary_items(&[]).is_empty());
+    }
+}
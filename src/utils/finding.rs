@@ -0,0 +1,634 @@
+// src/utils/finding.rs
+use super::console::Icon;
+use super::output::OutputKind;
+use super::output_file_path;
+use super::provenance::Provenance;
+use super::redact::{redactor, save_export_mapping};
+use super::run_dir::RunDir;
+use super::salvage::salvage;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+static NO_DEDUP: OnceLock<bool> = OnceLock::new();
+
+/// 标准化发现列表的文件名（不含扩展名）
+pub const VM_FINDINGS_FILE_STEM: &str = "findings_vm";
+
+/// 风险等级
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// 信息
+    Info,
+    /// 低危
+    Low,
+    /// 中危
+    Medium,
+    /// 高危
+    High,
+    /// 严重
+    Critical,
+}
+
+/// 风险等级对照表：(等级, 中文名称, CVSS下限, CVSS上限)
+///
+/// 各模块统一按此表定级，导出时给出对应的分值区间。
+pub const SEVERITY_TABLE: &[(Severity, &str, f32, f32)] = &[
+    (Severity::Info, "信息", 0.0, 0.0),
+    (Severity::Low, "低危", 0.1, 3.9),
+    (Severity::Medium, "中危", 4.0, 6.9),
+    (Severity::High, "高危", 7.0, 8.9),
+    (Severity::Critical, "严重", 9.0, 10.0),
+];
+
+impl Severity {
+    fn entry(self) -> &'static (Severity, &'static str, f32, f32) {
+        SEVERITY_TABLE
+            .iter()
+            .find(|(s, ..)| *s == self)
+            .expect("风险等级对照表缺少条目")
+    }
+
+    /// 中文名称
+    pub fn label(self) -> &'static str {
+        self.entry().1
+    }
+
+    /// 对应的CVSS分值区间
+    pub fn cvss_range(self) -> (f32, f32) {
+        let (_, _, low, high) = *self.entry();
+        (low, high)
+    }
+
+    /// 按CVSS分值定级
+    pub fn from_cvss(score: f32) -> Self {
+        SEVERITY_TABLE
+            .iter()
+            .rev()
+            .find(|(_, _, low, _)| score >= *low)
+            .map(|(s, ..)| *s)
+            .unwrap_or(Severity::Info)
+    }
+
+    /// 按中文或英文名称定级（如 "高危"、"high"）
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label.trim();
+        SEVERITY_TABLE
+            .iter()
+            .find(|(s, name, ..)| *name == label || s.to_string().eq_ignore_ascii_case(label))
+            .map(|(s, ..)| *s)
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Info => "info",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Critical => "critical",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 检查参数：参数名 -> 值（按参数名排序）
+pub type FindingParams = BTreeMap<String, String>;
+
+/// 模块产生的一条发现
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// 产生发现的模块（如 "pentest mail"）
+    pub module: String,
+    /// 资产（IP或主机名）
+    pub asset: String,
+    /// 端口（主机级发现为空）
+    pub port: Option<u16>,
+    /// 协议（如 tcp）
+    pub protocol: String,
+    /// 检查项ID（同一检查项在各次运行中保持不变，如 "open-port"）
+    pub check_id: String,
+    /// 标题
+    pub title: String,
+    /// 风险等级
+    pub severity: Severity,
+    /// 证据
+    pub evidence: String,
+    /// 修复建议
+    pub remediation: String,
+    /// 复查时重新执行该检查所需的参数（如中继测试使用的地址），多数检查为空
+    pub params: FindingParams,
+    /// 合并重复发现后检出该问题的全部来源（`模块:检查项ID`），未合并时为空
+    pub sources: Vec<String>,
+    /// 得出该发现的观测
+    pub observed: Provenance,
+}
+
+impl Finding {
+    /// 来源标识：`模块:检查项ID`（如 `pentest portscan:script:actuator`）
+    pub fn source(&self) -> String {
+        format!("{}:{}", self.module, self.check_id)
+    }
+
+    /// 规范化的发现指纹，与资产、端口一起判定不同来源报告的是否为同一问题
+    ///
+    /// 规则：
+    /// - 只看标题，检查项ID因模块而异，不参与比较
+    /// - 全角字符转为半角，字母转为小写
+    /// - 去掉标题中重复的资产及端口写法（如 `10.0.0.1`、`445/tcp`、`:445`）
+    /// - 只保留字母、数字及汉字，空白、标点及分隔符的差异忽略
+    pub fn fingerprint(&self) -> String {
+        let mut title: String = self
+            .title
+            .chars()
+            .map(|c| match c {
+                '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+                '\u{3000}' => ' ',
+                c => c,
+            })
+            .collect::<String>()
+            .to_lowercase();
+        if !self.asset.is_empty() {
+            title = title.replace(&self.asset.to_lowercase(), " ");
+        }
+        if let Some(port) = self.port {
+            for noise in [
+                format!("{}/{}", port, self.protocol.to_lowercase()),
+                format!(":{}", port),
+                format!("端口{}", port),
+                format!("port {}", port),
+            ] {
+                title = title.replace(&noise, " ");
+            }
+        }
+        title.chars().filter(|c| c.is_alphanumeric()).collect()
+    }
+
+    /// 稳定的发现ID：资产、端口及检查项的哈希，重复导入时平台据此去重
+    pub fn id(&self) -> String {
+        let port = self.port.map(|p| p.to_string()).unwrap_or_default();
+        let key = format!(
+            "{}|{}|{}|{}",
+            self.asset, port, self.protocol, self.check_id
+        );
+        hex::encode(&Sha256::digest(key.as_bytes())[..8])
+    }
+
+    /// 转换为漏洞管理平台的导入格式
+    ///
+    /// # 参数
+    /// * `first_seen` - 首次发现时间（RFC3339）
+    pub fn to_vm(&self, first_seen: &str) -> VmFinding {
+        let (low, high) = self.severity.cvss_range();
+        VmFinding {
+            id: self.id(),
+            asset: self.asset.clone(),
+            port: self.port,
+            protocol: self.protocol.clone(),
+            title: self.title.clone(),
+            severity: self.severity,
+            cvss_range: format!("{:.1}-{:.1}", low, high),
+            evidence: self.evidence.clone(),
+            remediation: self.remediation.clone(),
+            first_seen: first_seen.to_string(),
+            module: self.module.clone(),
+            check_id: self.check_id.clone(),
+            params: self.params.clone(),
+            sources: self.sources.clone(),
+            observed: self.observed.clone(),
+        }
+    }
+}
+
+/// 设置是否合并重复发现（`--no-dedup` 时关闭，用于排查各检测之间的重叠）
+pub fn set_dedup(enabled: bool) {
+    let _ = NO_DEDUP.set(!enabled);
+}
+
+/// 是否合并重复发现，未设置时为是
+pub fn dedup_enabled() -> bool {
+    !NO_DEDUP.get().copied().unwrap_or(false)
+}
+
+/// 合并同一资产、端口上指纹相同的发现（见 [`Finding::fingerprint`]）
+///
+/// 合并后保留风险等级最高的一条（等级相同时取先出现的），
+/// 证据及修复建议取各条中最详细的，`sources` 列出全部来源。
+///
+/// # 参数
+/// * `findings` - 各来源的发现（按出现顺序）
+///
+/// # 返回
+/// * 合并后的发现，保持首次出现的顺序
+pub fn dedup(findings: Vec<Finding>) -> Vec<Finding> {
+    let mut merged: Vec<Finding> = Vec::with_capacity(findings.len());
+    let mut index: HashMap<(String, Option<u16>, String), usize> = HashMap::new();
+    for finding in findings {
+        let key = (finding.asset.clone(), finding.port, finding.fingerprint());
+        let Some(&i) = index.get(&key) else {
+            index.insert(key, merged.len());
+            merged.push(finding);
+            continue;
+        };
+        let kept = &mut merged[i];
+        if kept.sources.is_empty() {
+            kept.sources.push(kept.source());
+        }
+        let source = finding.source();
+        if !kept.sources.contains(&source) {
+            kept.sources.push(source);
+        }
+        let evidence = richer(&kept.evidence, &finding.evidence).clone();
+        let remediation = richer(&kept.remediation, &finding.remediation).clone();
+        if finding.severity > kept.severity {
+            let sources = std::mem::take(&mut kept.sources);
+            *kept = Finding { sources, ..finding };
+        }
+        kept.evidence = evidence;
+        kept.remediation = remediation;
+    }
+    merged
+}
+
+/// 两段文本中更详细的一段（字符数多的，相同时取前者）
+fn richer<'a>(a: &'a String, b: &'a String) -> &'a String {
+    if b.chars().count() > a.chars().count() {
+        b
+    } else {
+        a
+    }
+}
+
+/// 按 `--no-dedup` 的设置合并重复发现，有合并时提示
+///
+/// # 参数
+/// * `findings` - 模块自身及脚本等各来源的发现
+pub fn consolidate(findings: Vec<Finding>) -> Vec<Finding> {
+    if !dedup_enabled() {
+        return findings;
+    }
+    let total = findings.len();
+    let merged = dedup(findings);
+    if merged.len() < total {
+        println!(
+            "{} {} 条重复发现已合并（同一资产、端口上的同一问题，来源见 sources 列）",
+            Icon::List,
+            total - merged.len()
+        );
+    }
+    merged
+}
+
+/// 漏洞管理平台导入格式的一行
+///
+/// `module`、`check_id`、`params` 供 `report reverify` 重新执行检查，
+/// 早期版本导出的文件没有这些列，读取时为空。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VmFinding {
+    /// 稳定的发现ID
+    pub id: String,
+    /// 资产
+    pub asset: String,
+    /// 端口
+    pub port: Option<u16>,
+    /// 协议
+    pub protocol: String,
+    /// 标题
+    pub title: String,
+    /// 风险等级
+    pub severity: Severity,
+    /// 风险等级对应的CVSS分值区间
+    pub cvss_range: String,
+    /// 证据
+    pub evidence: String,
+    /// 修复建议
+    pub remediation: String,
+    /// 首次发现时间（RFC3339）
+    pub first_seen: String,
+    /// 产生发现的模块
+    #[serde(default)]
+    pub module: String,
+    /// 检查项ID
+    #[serde(default)]
+    pub check_id: String,
+    /// 检查参数
+    #[serde(default)]
+    pub params: FindingParams,
+    /// 合并重复发现后的全部来源
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    /// 观测时间及探测方式（早期版本导出的文件没有，读取时为空）
+    #[serde(flatten)]
+    pub observed: Provenance,
+}
+
+/// CSV的列顺序（与 [`VmFinding`] 的字段一致）
+const VM_CSV_HEADERS: &[&str] = &[
+    "id",
+    "asset",
+    "port",
+    "protocol",
+    "title",
+    "severity",
+    "cvss_range",
+    "evidence",
+    "remediation",
+    "first_seen",
+    "module",
+    "check_id",
+    "params",
+    "sources",
+    "observed_at",
+    "probe",
+];
+
+/// 标准化发现列表的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FindingFormat {
+    /// JSON数组
+    VmJson,
+    /// CSV（UTF-8带BOM，Excel可直接打开）
+    VmCsv,
+}
+
+impl FindingFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::VmJson => "json",
+            Self::VmCsv => "csv",
+        }
+    }
+}
+
+/// 渲染为CSV
+fn to_csv(rows: &[VmFinding]) -> String {
+    let mut out = String::from("\u{feff}");
+    out.push_str(&VM_CSV_HEADERS.join(","));
+    out.push_str("\r\n");
+    for r in rows {
+        let cells = [
+            r.id.clone(),
+            r.asset.clone(),
+            r.port.map(|p| p.to_string()).unwrap_or_default(),
+            r.protocol.clone(),
+            r.title.clone(),
+            r.severity.to_string(),
+            r.cvss_range.clone(),
+            r.evidence.clone(),
+            r.remediation.clone(),
+            r.first_seen.clone(),
+            r.module.clone(),
+            r.check_id.clone(),
+            params_cell(&r.params),
+            r.sources.join(";"),
+            r.observed.observed_at.clone(),
+            r.observed.probe.clone(),
+        ];
+        let line: Vec<String> = cells.iter().map(|c| csv_cell(c)).collect();
+        out.push_str(&line.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// 检查参数在表格中的写法（JSON对象，没有参数时为空）
+pub fn params_cell(params: &FindingParams) -> String {
+    if params.is_empty() {
+        String::new()
+    } else {
+        serde_json::to_string(params).unwrap_or_default()
+    }
+}
+
+/// 转义CSV单元格：含分隔符、引号或换行时加引号，以公式字符开头时加单引号前缀
+pub fn csv_cell(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// 按指定格式写出标准化发现列表
+///
+/// 运行目录下为 `findings_vm.<扩展名>`，平铺目录下为 `<前缀>_findings_vm_<时间戳>.<扩展名>`。
+///
+/// # 参数
+/// * `findings` - 发现列表
+/// * `format` - 导出格式
+/// * `first_seen` - 首次发现时间（RFC3339，通常为扫描开始时间）
+/// * `kind` - 平铺输出时的模块子目录及文件名前缀
+/// * `run_dir` - 本次运行的工作目录
+///
+/// # 返回
+/// * `Ok(PathBuf)` - 写入的文件路径（写入失败时为系统临时目录下的转存文件）
+pub fn write_findings(
+    findings: &[Finding],
+    format: FindingFormat,
+    first_seen: &str,
+    kind: OutputKind,
+    run_dir: Option<&RunDir>,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let mut rows: Vec<VmFinding> = findings.iter().map(|f| f.to_vm(first_seen)).collect();
+    if let Some(r) = redactor() {
+        for row in &mut rows {
+            row.asset = r.text(&row.asset);
+            row.title = r.text(&row.title);
+            for value in row.params.values_mut() {
+                *value = r.text(value);
+            }
+            row.evidence = if r.keep_evidence() {
+                r.text(&row.evidence)
+            } else {
+                String::new()
+            };
+        }
+    }
+    let content = match format {
+        FindingFormat::VmJson => serde_json::to_string_pretty(&rows)?,
+        FindingFormat::VmCsv => to_csv(&rows),
+    };
+    let written = output_file_path(VM_FINDINGS_FILE_STEM, format.extension(), kind, run_dir)
+        .and_then(|path| {
+            fs::write(&path, &content)?;
+            Ok(path)
+        });
+    let path = match written {
+        Ok(path) => path,
+        Err(e) => {
+            let prefix = format!("{}_{}", kind.prefix, VM_FINDINGS_FILE_STEM);
+            return salvage(
+                &prefix,
+                format.extension(),
+                content.as_bytes(),
+                &e.to_string(),
+            );
+        }
+    };
+    save_export_mapping()?;
+    if let Some(run) = run_dir {
+        run.record(&path, "vm", rows.len())?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(port: Option<u16>) -> Finding {
+        Finding {
+            module: "pentest portscan".to_string(),
+            asset: "10.0.0.1".to_string(),
+            port,
+            protocol: "tcp".to_string(),
+            check_id: "open-port".to_string(),
+            title: "开放端口 22/tcp".to_string(),
+            severity: Severity::Info,
+            evidence: "SSH-2.0-OpenSSH_8.9, \"ssh-banner\"".to_string(),
+            remediation: "=关闭不需要的服务".to_string(),
+            params: FindingParams::new(),
+            sources: Vec::new(),
+            observed: Provenance {
+                observed_at: "2024-01-02T10:00:05.120+08:00".to_string(),
+                probe: "tcp-connect:22".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_severity_table_is_consistent() {
+        assert_eq!(Severity::High.label(), "高危");
+        assert_eq!(Severity::Medium.cvss_range(), (4.0, 6.9));
+        assert_eq!(Severity::from_cvss(9.8), Severity::Critical);
+        assert_eq!(Severity::from_cvss(7.0), Severity::High);
+        assert_eq!(Severity::from_cvss(0.0), Severity::Info);
+        assert_eq!(Severity::from_label("中危"), Some(Severity::Medium));
+        assert_eq!(Severity::from_label("LOW"), Some(Severity::Low));
+        assert_eq!(Severity::from_label("unknown"), None);
+        // 对照表覆盖全部等级且区间不重叠
+        for pair in SEVERITY_TABLE.windows(2) {
+            assert!(pair[0].0 < pair[1].0);
+            assert!(pair[0].3 < pair[1].2);
+        }
+    }
+
+    #[test]
+    fn test_finding_id_is_stable() {
+        let a = sample(Some(22));
+        assert_eq!(a.id(), sample(Some(22)).id());
+        assert_eq!(a.id().len(), 16);
+        assert_ne!(a.id(), sample(Some(80)).id());
+        assert_ne!(a.id(), sample(None).id());
+        // 标题、证据变化不影响ID
+        let mut b = sample(Some(22));
+        b.evidence.clear();
+        assert_eq!(a.id(), b.id());
+    }
+
+    fn titled(module: &str, check_id: &str, title: &str) -> Finding {
+        Finding {
+            module: module.to_string(),
+            check_id: check_id.to_string(),
+            title: title.to_string(),
+            port: Some(8080),
+            evidence: String::new(),
+            remediation: String::new(),
+            ..sample(Some(8080))
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_formatting_noise() {
+        let base = titled("a", "x", "Spring Boot Actuator exposed: /actuator/env");
+        for near in [
+            "spring-boot actuator EXPOSED (/actuator/env/)",
+            "Spring Boot Actuator exposed - 10.0.0.1:8080 /actuator/env",
+            "Spring Boot Actuator exposed（/actuator/env） 8080/tcp",
+        ] {
+            assert_eq!(
+                titled("b", "y", near).fingerprint(),
+                base.fingerprint(),
+                "{}",
+                near
+            );
+        }
+        for different in [
+            "Spring Boot Actuator exposed: /actuator/heapdump",
+            "Spring Boot Actuator exposed: /actuator/env 8443/tcp",
+        ] {
+            assert_ne!(
+                titled("b", "y", different).fingerprint(),
+                base.fingerprint()
+            );
+        }
+    }
+
+    #[test]
+    fn test_dedup_merges_sources_and_keeps_strongest() {
+        let mut module = titled("pentest portscan", "actuator", "Actuator 暴露 /env");
+        module.evidence = "HTTP 200".to_string();
+        let mut script = titled("pentest portscan", "script:actuator", "actuator暴露: /env");
+        script.severity = Severity::High;
+        script.evidence = "HTTP 200, 包含 spring.datasource.password".to_string();
+        script.remediation = "限制管理端点的访问".to_string();
+        let other_port = Finding {
+            port: Some(9090),
+            ..module.clone()
+        };
+
+        let merged = dedup(vec![module.clone(), other_port, script.clone(), module]);
+        assert_eq!(merged.len(), 2);
+        let f = &merged[0];
+        assert_eq!(f.severity, Severity::High);
+        assert_eq!(f.check_id, "script:actuator");
+        assert_eq!(f.evidence, script.evidence);
+        assert_eq!(f.remediation, script.remediation);
+        assert_eq!(
+            f.sources,
+            [
+                "pentest portscan:actuator",
+                "pentest portscan:script:actuator"
+            ]
+        );
+        assert!(merged[1].sources.is_empty());
+    }
+
+    #[test]
+    fn test_vm_csv_escapes_cells() {
+        let rows = vec![sample(Some(22)).to_vm("2024-01-02T10:00:00+08:00")];
+        let csv = to_csv(&rows);
+        let lines: Vec<&str> = csv.trim_start_matches('\u{feff}').lines().collect();
+        assert_eq!(lines[0], VM_CSV_HEADERS.join(","));
+        assert!(lines[1].contains(",22,tcp,"));
+        assert!(lines[1].contains("\"SSH-2.0-OpenSSH_8.9, \"\"ssh-banner\"\"\""));
+        assert!(lines[1].contains(",'=关闭不需要的服务,"));
+        assert!(lines[1].contains(",info,0.0-0.0,"));
+        assert!(lines[1].ends_with(
+            ",pentest portscan,open-port,,,2024-01-02T10:00:05.120+08:00,tcp-connect:22"
+        ));
+
+        let mut relay = sample(Some(25));
+        relay
+            .params
+            .insert("relay_to".to_string(), "a@example.net".to_string());
+        let csv = to_csv(&[relay.to_vm("2024-01-02T10:00:00+08:00")]);
+        assert!(csv.contains(",\"{\"\"relay_to\"\":\"\"a@example.net\"\"}\",,"));
+    }
+}
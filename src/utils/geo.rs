@@ -0,0 +1,372 @@
+// src/utils/geo.rs
+use super::console::Icon;
+use super::{ExcelSheet, config_dir};
+use clap::Args;
+use maxminddb::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// 未指定 `--mmdb` 时在配置目录中查找的数据库文件
+pub const DEFAULT_MMDB_FILES: &[&str] = &["GeoLite2-City.mmdb", "GeoLite2-ASN.mmdb"];
+
+/// 内网及保留地址的地区标记
+pub const PRIVATE_LABEL: &str = "内网";
+
+/// 地理位置及ASN参数
+#[derive(Args, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeoArgs {
+    /// 查询有响应主机的国家、地区、ASN及组织（只读取本地MaxMind格式数据库，不访问外部接口）
+    #[arg(long, env = "GXTOOLS_ENRICH_GEO")]
+    pub enrich_geo: bool,
+
+    /// MaxMind格式数据库文件（可重复指定，如城市库和ASN库），默认读取配置目录下的 GeoLite2-City.mmdb、GeoLite2-ASN.mmdb
+    #[arg(long, value_name = "PATH", requires = "enrich_geo")]
+    pub mmdb: Vec<PathBuf>,
+}
+
+/// 单个IP的地理位置及ASN信息
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GeoInfo {
+    /// 国家（内网及保留地址为“内网”）
+    pub country: String,
+    /// 省份/城市
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub region: String,
+    /// 自治系统号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn: Option<u32>,
+    /// 自治系统所属组织
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub org: String,
+}
+
+impl GeoInfo {
+    /// 导出Excel时的列名
+    pub const HEADERS: [&'static str; 4] = ["国家", "省份/城市", "ASN", "组织"];
+
+    /// 内网及保留地址的信息
+    pub fn private() -> Self {
+        Self {
+            country: PRIVATE_LABEL.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 导出Excel时的单元格（没有信息时为空）
+    pub fn cells(geo: Option<&GeoInfo>) -> [String; 4] {
+        match geo {
+            Some(g) => [
+                g.country.clone(),
+                g.region.clone(),
+                g.asn.map(|n| format!("AS{}", n)).unwrap_or_default(),
+                g.org.clone(),
+            ],
+            None => Default::default(),
+        }
+    }
+}
+
+/// 地理位置数据来源
+///
+/// 查询只通过该接口进行，测试时可替换为返回预设信息的实现。
+pub trait GeoSource: Send + Sync {
+    /// 查询公网IP，数据库中没有该地址时返回 `None`
+    fn lookup(&self, ip: IpAddr) -> Option<GeoInfo>;
+}
+
+/// 各类MaxMind数据库中可能出现的字段（城市库、国家库、ASN库字段互不冲突）
+#[derive(Deserialize)]
+struct MmdbRecord<'a> {
+    #[serde(borrow)]
+    country: Option<MmdbNames<'a>>,
+    #[serde(borrow)]
+    subdivisions: Option<Vec<MmdbNames<'a>>>,
+    #[serde(borrow)]
+    city: Option<MmdbNames<'a>>,
+    autonomous_system_number: Option<u32>,
+    autonomous_system_organization: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct MmdbNames<'a> {
+    #[serde(borrow)]
+    names: Option<BTreeMap<&'a str, &'a str>>,
+}
+
+impl MmdbNames<'_> {
+    /// 优先取中文名称
+    fn name(&self) -> Option<String> {
+        let names = self.names.as_ref()?;
+        names
+            .get("zh-CN")
+            .or_else(|| names.get("en"))
+            .map(|s| s.to_string())
+    }
+}
+
+/// 读取本地MaxMind格式数据库（文件一次性读入内存）
+pub struct MmdbSource {
+    readers: Vec<Reader<Vec<u8>>>,
+}
+
+impl MmdbSource {
+    /// 打开数据库文件，缺失或无法解析的文件打印警告后跳过
+    ///
+    /// # 参数
+    /// * `paths` - 数据库文件路径
+    ///
+    /// # 返回
+    /// * `Some(MmdbSource)` - 至少有一个数据库可用
+    /// * `None` - 没有可用的数据库
+    pub fn open(paths: &[PathBuf]) -> Option<Self> {
+        let readers: Vec<_> = paths
+            .iter()
+            .filter_map(|path| match Reader::open_readfile(path) {
+                Ok(reader) => Some(reader),
+                Err(e) => {
+                    eprintln!(
+                        "{} 无法读取地理位置数据库 {}: {}",
+                        Icon::Warn,
+                        path.display(),
+                        e
+                    );
+                    None
+                }
+            })
+            .collect();
+        (!readers.is_empty()).then_some(Self { readers })
+    }
+}
+
+impl GeoSource for MmdbSource {
+    fn lookup(&self, ip: IpAddr) -> Option<GeoInfo> {
+        let mut info = GeoInfo::default();
+        let mut found = false;
+        // 城市库和ASN库分别提供一部分字段，合并各库的结果
+        for reader in &self.readers {
+            // 未收录该地址或记录无法解析时跳过这个库
+            let Ok(record) = reader.lookup::<MmdbRecord>(ip) else {
+                continue;
+            };
+            found = true;
+            if let Some(country) = record.country.as_ref().and_then(MmdbNames::name) {
+                info.country = country;
+            }
+            let region: Vec<String> = record
+                .subdivisions
+                .iter()
+                .flatten()
+                .chain(record.city.as_ref())
+                .filter_map(MmdbNames::name)
+                .collect();
+            if !region.is_empty() {
+                info.region = region.join(" ");
+            }
+            if record.autonomous_system_number.is_some() {
+                info.asn = record.autonomous_system_number;
+            }
+            if let Some(org) = record.autonomous_system_organization {
+                info.org = org.to_string();
+            }
+        }
+        found.then_some(info)
+    }
+}
+
+/// 地理位置查询（数据库只打开一次，克隆后可在多个任务间共享）
+#[derive(Clone)]
+pub struct GeoLookup {
+    source: Arc<dyn GeoSource>,
+}
+
+impl GeoLookup {
+    /// 使用指定数据来源
+    pub fn new(source: impl GeoSource + 'static) -> Self {
+        Self {
+            source: Arc::new(source),
+        }
+    }
+
+    /// 按参数打开数据库，未开启 `--enrich-geo` 或没有可用数据库时返回 `None`
+    ///
+    /// # 参数
+    /// * `args` - 地理位置参数
+    pub fn open(args: &GeoArgs) -> Option<Self> {
+        if !args.enrich_geo {
+            return None;
+        }
+        let paths: Vec<PathBuf> = if args.mmdb.is_empty() {
+            DEFAULT_MMDB_FILES
+                .iter()
+                .map(|name| config_dir().join(name))
+                .filter(|path| path.exists())
+                .collect()
+        } else {
+            args.mmdb.clone()
+        };
+        match MmdbSource::open(&paths) {
+            Some(source) => Some(Self::new(source)),
+            None => {
+                eprintln!(
+                    "{} 没有可用的地理位置数据库，已跳过地理位置查询（可用 --mmdb 指定，或放在 {} 下）",
+                    Icon::Warn,
+                    config_dir().display()
+                );
+                None
+            }
+        }
+    }
+
+    /// 查询单个IP，内网及保留地址直接标记为“内网”
+    ///
+    /// # 参数
+    /// * `ip` - IP地址
+    ///
+    /// # 返回
+    /// * `Some(GeoInfo)` - 查询结果
+    /// * `None` - 地址无效或数据库中没有该地址
+    pub fn lookup(&self, ip: &str) -> Option<GeoInfo> {
+        let ip: IpAddr = ip.parse().ok()?;
+        if is_reserved(ip) {
+            return Some(GeoInfo::private());
+        }
+        self.source.lookup(ip)
+    }
+}
+
+/// 是否为内网或保留地址（不在公网路由，数据库中不会有记录）
+pub fn is_reserved(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_documentation()
+                || v4.is_multicast()
+                // 运营商级NAT 100.64.0.0/10、基准测试 198.18.0.0/15、保留 240.0.0.0/4
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 198 && (b == 18 || b == 19))
+                || a >= 240
+        }
+        IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                // 唯一本地地址 fc00::/7、链路本地地址 fe80::/10
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+/// 同一ASN下的国家及主机
+type AsnHosts<'a> = (String, BTreeSet<&'a str>);
+
+/// 按ASN汇总主机（同一IP只计一次，按主机数降序）
+///
+/// # 参数
+/// * `hosts` - (IP, 地理位置信息)
+pub fn asn_rollup_sheet<'a>(hosts: impl IntoIterator<Item = (&'a str, &'a GeoInfo)>) -> ExcelSheet {
+    // (ASN, 组织) -> (国家, 主机)
+    let mut groups: HashMap<(Option<u32>, String), AsnHosts> = HashMap::new();
+    for (ip, geo) in hosts {
+        // 内网地址没有ASN，单独汇总为一行
+        let key = (geo.asn, geo.org.clone());
+        groups
+            .entry(key)
+            .or_insert_with(|| (geo.country.clone(), BTreeSet::new()))
+            .1
+            .insert(ip);
+    }
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    groups.sort_by(|a, b| b.1.1.len().cmp(&a.1.1.len()).then_with(|| a.0.cmp(&b.0)));
+
+    ExcelSheet {
+        name: "ASN汇总".to_string(),
+        headers: ["ASN", "组织", "国家", "主机数", "主机"]
+            .map(String::from)
+            .to_vec(),
+        rows: groups
+            .into_iter()
+            .map(|((asn, org), (country, ips))| {
+                vec![
+                    asn.map(|n| format!("AS{}", n)).unwrap_or_default(),
+                    org,
+                    country,
+                    ips.len().to_string(),
+                    ips.into_iter().collect::<Vec<_>>().join(", "),
+                ]
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 只认识固定地址的数据来源
+    struct FixedSource;
+
+    impl GeoSource for FixedSource {
+        fn lookup(&self, ip: IpAddr) -> Option<GeoInfo> {
+            (ip.to_string() == "8.8.8.8").then(|| GeoInfo {
+                country: "美国".to_string(),
+                region: String::new(),
+                asn: Some(15169),
+                org: "GOOGLE".to_string(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_reserved_ranges_are_labeled_private() {
+        let geo = GeoLookup::new(FixedSource);
+        for ip in [
+            "10.1.2.3",
+            "192.168.1.1",
+            "100.64.0.1",
+            "127.0.0.1",
+            "fd00::1",
+        ] {
+            assert_eq!(geo.lookup(ip), Some(GeoInfo::private()), "{}", ip);
+        }
+        assert_eq!(geo.lookup("8.8.8.8").unwrap().asn, Some(15169));
+        assert_eq!(geo.lookup("1.1.1.1"), None);
+        assert_eq!(geo.lookup("not-an-ip"), None);
+    }
+
+    #[test]
+    fn test_missing_database_disables_enrichment() {
+        let args = GeoArgs {
+            enrich_geo: true,
+            mmdb: vec![std::env::temp_dir().join("gxr_missing.mmdb")],
+        };
+        assert!(GeoLookup::open(&args).is_none());
+        assert!(GeoLookup::open(&GeoArgs::default()).is_none());
+    }
+
+    #[test]
+    fn test_asn_rollup_counts_hosts_once() {
+        let google = GeoLookup::new(FixedSource).lookup("8.8.8.8").unwrap();
+        let private = GeoInfo::private();
+        let sheet = asn_rollup_sheet([
+            ("8.8.8.8", &google),
+            ("8.8.8.8", &google),
+            ("8.8.4.4", &google),
+            ("10.0.0.1", &private),
+        ]);
+        assert_eq!(sheet.rows.len(), 2);
+        assert_eq!(sheet.rows[0][..4], ["AS15169", "GOOGLE", "美国", "2"]);
+        assert_eq!(sheet.rows[1][2], PRIVATE_LABEL);
+        assert_eq!(GeoInfo::cells(None), ["", "", "", ""]);
+    }
+}
@@ -0,0 +1,254 @@
+// src/utils/http_pool.rs
+use super::identity::identity;
+use super::run_dir::SummaryItem;
+use super::timing::Throttle;
+use super::tls::insecure_client_config;
+use clap::Args;
+use reqwest::header::USER_AGENT;
+use reqwest::{Client, IntoUrl, Response};
+use std::error::Error;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio_rustls::rustls::ClientConfig;
+use tower::util::MapRequestLayer;
+
+/// 默认每个目标保持的空闲连接数
+pub const DEFAULT_POOL_SIZE: usize = 4;
+
+/// 默认空闲连接的保持时间（秒）
+pub const DEFAULT_POOL_IDLE_SECS: u64 = 30;
+
+/// 连接复用参数（同一目标的多次请求复用keep-alive连接）
+#[derive(Args, Debug, Clone)]
+pub struct HttpPoolArgs {
+    /// 每个目标最多保持的空闲连接数（0 表示每个请求都新建连接）
+    #[arg(long, default_value_t = DEFAULT_POOL_SIZE, value_name = "NUM")]
+    pub pool_size: usize,
+
+    /// 空闲连接的保持时间（秒），超时后关闭
+    #[arg(long, default_value_t = DEFAULT_POOL_IDLE_SECS, value_name = "SECS")]
+    pub pool_idle: u64,
+}
+
+impl Default for HttpPoolArgs {
+    fn default() -> Self {
+        Self {
+            pool_size: DEFAULT_POOL_SIZE,
+            pool_idle: DEFAULT_POOL_IDLE_SECS,
+        }
+    }
+}
+
+/// 连接复用统计
+#[derive(Debug, Default)]
+pub struct PoolStats {
+    requests: AtomicU64,
+    connections: AtomicU64,
+}
+
+impl PoolStats {
+    /// 发出的请求数
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// 新建的连接数（含连接失败的尝试）
+    pub fn connections(&self) -> u64 {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    /// 复用已有连接的请求占比（没有请求时为0）
+    pub fn reuse_rate(&self) -> f64 {
+        let requests = self.requests();
+        let reused = requests.saturating_sub(self.connections());
+        (reused as f64 / requests.max(1) as f64) * 100.0
+    }
+
+    /// 统计摘要中的连接复用情况（没有请求时为 `None`）
+    pub fn summary_item(&self) -> Option<SummaryItem> {
+        (self.requests() > 0).then(|| {
+            (
+                "连接复用".to_string(),
+                format!(
+                    "请求 {} 次，新建连接 {} 次，复用率 {:.1}%",
+                    self.requests(),
+                    self.connections(),
+                    self.reuse_rate()
+                ),
+            )
+        })
+    }
+}
+
+/// 按目标划分的HTTP连接池
+///
+/// 每个目标（主机:端口）使用单独的客户端，同一目标的先后请求复用keep-alive连接；
+/// 目标处理完毕或因出错放弃时丢弃其客户端，连接随之关闭，不会留给其他目标。
+/// 证书不做校验、不跟随跳转，系统代理（`HTTP_PROXY` 等环境变量）对每个客户端同样生效。
+#[derive(Debug)]
+pub struct HttpPool {
+    tls: ClientConfig,
+    timeout: Duration,
+    user_agent: Arc<str>,
+    args: HttpPoolArgs,
+    stats: Arc<PoolStats>,
+}
+
+impl HttpPool {
+    /// 创建连接池
+    ///
+    /// # 参数
+    /// * `timeout` - 单个请求的超时
+    /// * `user_agent` - 未指定 `--user-agent` 时请求使用的User-Agent
+    /// * `args` - 连接复用参数
+    ///
+    /// # 返回
+    /// * `Err` - 无法按参数创建客户端（在发出请求之前报错）
+    pub fn new(
+        timeout: Duration,
+        user_agent: &str,
+        args: &HttpPoolArgs,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let pool = Self {
+            tls: insecure_client_config(&[b"http/1.1"]),
+            timeout,
+            user_agent: user_agent.into(),
+            args: args.clone(),
+            stats: Arc::new(PoolStats::default()),
+        };
+        pool.build_client()
+            .map_err(|e| format!("无法创建HTTP客户端: {}", e))?;
+        Ok(pool)
+    }
+
+    fn build_client(&self) -> reqwest::Result<Client> {
+        let stats = self.stats.clone();
+        Client::builder()
+            .use_preconfigured_tls(self.tls.clone())
+            .timeout(self.timeout)
+            .redirect(reqwest::redirect::Policy::none())
+            .user_agent(&*self.user_agent)
+            .default_headers(identity().header_map())
+            .pool_max_idle_per_host(self.args.pool_size)
+            .pool_idle_timeout(Duration::from_secs(self.args.pool_idle.max(1)))
+            // 只有需要新连接时才会调用连接器
+            .connector_layer(MapRequestLayer::new(move |dst| {
+                stats.connections.fetch_add(1, Ordering::Relaxed);
+                dst
+            }))
+            .build()
+    }
+
+    /// 获取某个目标的客户端
+    ///
+    /// # 参数
+    /// * `host` - 目标地址（按主机节流）
+    /// * `throttle` - 全局节流器，每个请求发出前取得许可
+    pub fn target<'a>(&self, host: &str, throttle: &'a Throttle) -> TargetClient<'a> {
+        TargetClient {
+            client: self.build_client().expect("客户端参数已在创建连接池时校验"),
+            host: host.to_string(),
+            throttle,
+            stats: self.stats.clone(),
+            user_agent: self.user_agent.clone(),
+        }
+    }
+
+    /// 连接复用统计
+    pub fn stats(&self) -> &PoolStats {
+        &self.stats
+    }
+}
+
+/// 单个目标的客户端，丢弃时关闭其全部连接
+pub struct TargetClient<'a> {
+    client: Client,
+    host: String,
+    throttle: &'a Throttle,
+    stats: Arc<PoolStats>,
+    user_agent: Arc<str>,
+}
+
+impl TargetClient<'_> {
+    /// 发出GET请求（取得节流许可后发出，收到响应头后释放许可）
+    ///
+    /// User-Agent按请求取自探测标识（`--randomize-ua` 时每个请求轮换），附加的请求头在客户端上设置。
+    pub async fn get<U: IntoUrl>(&self, url: U) -> reqwest::Result<Response> {
+        let _permit = self.throttle.acquire(&self.host).await;
+        self.stats.requests.fetch_add(1, Ordering::Relaxed);
+        self.client
+            .get(url)
+            .header(USER_AGENT, identity().user_agent(&self.user_agent))
+            .send()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 支持keep-alive的本地服务，每个请求返回 "ok"
+    async fn serve_keep_alive() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 1024];
+                    while let Ok(n) = stream.read(&mut chunk).await {
+                        if n == 0 {
+                            break;
+                        }
+                        buf.extend_from_slice(&chunk[..n]);
+                        while let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                            buf.drain(..end + 4);
+                            let response = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+                            if stream.write_all(response).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    async fn fetch_three(args: &HttpPoolArgs, port: u16) -> Arc<PoolStats> {
+        let pool = HttpPool::new(Duration::from_secs(5), "test", args).unwrap();
+        let throttle = Throttle::default();
+        let client = pool.target("127.0.0.1", &throttle);
+        for path in ["/", "/a", "/b"] {
+            let response = client
+                .get(format!("http://127.0.0.1:{}{}", port, path))
+                .await
+                .unwrap();
+            assert_eq!(response.text().await.unwrap(), "ok");
+        }
+        pool.stats.clone()
+    }
+
+    #[tokio::test]
+    async fn test_sequential_requests_reuse_connection() {
+        let port = serve_keep_alive().await;
+        let stats = fetch_three(&HttpPoolArgs::default(), port).await;
+        assert_eq!((stats.requests(), stats.connections()), (3, 1));
+        assert_eq!(
+            stats.summary_item().unwrap().1,
+            "请求 3 次，新建连接 1 次，复用率 66.7%"
+        );
+
+        let no_reuse = HttpPoolArgs {
+            pool_size: 0,
+            ..HttpPoolArgs::default()
+        };
+        let stats = fetch_three(&no_reuse, port).await;
+        assert_eq!((stats.requests(), stats.connections()), (3, 3));
+        assert_eq!(stats.reuse_rate(), 0.0);
+    }
+}
@@ -0,0 +1,232 @@
+// src/utils/identity.rs
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 未指定 `--user-agent` 时HTTP请求使用的User-Agent
+pub const DEFAULT_USER_AGENT: &str = concat!("gxtools/", env!("CARGO_PKG_VERSION"));
+
+/// `--randomize-ua` 轮换使用的常见浏览器User-Agent
+pub const UA_POOL: &[&str] = &[
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:125.0) Gecko/20100101 Firefox/125.0",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Safari/605.1.15",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36 Edg/124.0.0.0",
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/123.0.0.0 Safari/537.36",
+];
+
+/// ICMP标记最多使用的字节数（系统ping的 `-p` 填充模式最多16字节）
+pub const ICMP_MARKER_MAX_BYTES: usize = 16;
+
+/// 探测标识：让蓝队按User-Agent或标记放行本次扫描，或在需要时混入正常流量
+///
+/// 由全局参数 `--user-agent`、`--http-header`、`--tcp-banner-probe`、`--icmp-marker`、
+/// `--randomize-ua` 决定，分别作用于共用的HTTP客户端、端口扫描的协议探测及系统ping，
+/// 指定的标识同时写入运行元数据（见 [`meta_entries`](Self::meta_entries)）。
+#[derive(Debug, Default)]
+pub struct ProbeIdentity {
+    /// 固定的User-Agent
+    pub user_agent: Option<String>,
+    /// 每个请求轮换内置的User-Agent
+    pub randomize_ua: bool,
+    /// 每个HTTP请求附加的请求头
+    pub headers: Vec<(String, String)>,
+    /// 替换端口扫描中对静默端口发送的HTTP探测报文
+    pub tcp_probe: Option<Vec<u8>>,
+    /// 写入ICMP回显请求填充数据的标记
+    pub icmp_marker: Option<String>,
+}
+
+static IDENTITY: OnceLock<ProbeIdentity> = OnceLock::new();
+
+/// 轮换User-Agent的计数
+static NEXT_UA: AtomicUsize = AtomicUsize::new(0);
+
+/// 设置本次运行的探测标识（仅首次设置生效，应在程序启动时调用）
+pub fn set_identity(identity: ProbeIdentity) {
+    let _ = IDENTITY.set(identity);
+}
+
+/// 本次运行的探测标识，未设置时为默认（User-Agent为 [`DEFAULT_USER_AGENT`]，不附加标记）
+pub fn identity() -> &'static ProbeIdentity {
+    IDENTITY.get_or_init(ProbeIdentity::default)
+}
+
+/// 解析 `--http-header "名称: 值"`
+///
+/// # 返回
+/// * `Err(String)` - 缺少 `:`，或名称、值不是合法的请求头
+pub fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| format!("请求头格式应为 名称: 值: {}", s))?;
+    let (name, value) = (name.trim(), value.trim());
+    HeaderName::from_bytes(name.as_bytes()).map_err(|_| format!("无效的请求头名称: {}", name))?;
+    HeaderValue::from_str(value).map_err(|_| format!("无效的请求头值: {}", value))?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// 解析十六进制报文（允许空格、`0x` 前缀及 `\x` 分隔，如 `48454c4c4f0d0a`、`\x48\x45`）
+///
+/// # 返回
+/// * `Err(String)` - 为空、含非十六进制字符或位数为奇数
+pub fn parse_hex(s: &str) -> Result<Vec<u8>, String> {
+    let digits: String = s
+        .trim()
+        .trim_start_matches("0x")
+        .replace("\\x", "")
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("无效的十六进制报文: {}", s));
+    }
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return Err(format!("十六进制报文须为偶数位且不能为空: {}", s));
+    }
+    Ok((0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).expect("已检查为十六进制"))
+        .collect())
+}
+
+/// 十六进制报文参数（如 `--tcp-banner-probe`），格式见 [`parse_hex`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexPayload(pub Vec<u8>);
+
+impl FromStr for HexPayload {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_hex(s).map(Self)
+    }
+}
+
+impl ProbeIdentity {
+    /// 本次请求使用的User-Agent：轮换时依次取内置列表，否则为指定值或 `default`
+    ///
+    /// # 参数
+    /// * `default` - 未指定 `--user-agent` 时使用的值（模块自身的默认值）
+    pub fn user_agent<'a>(&'a self, default: &'a str) -> &'a str {
+        if self.randomize_ua {
+            let i = NEXT_UA.fetch_add(1, Ordering::Relaxed);
+            return UA_POOL[i % UA_POOL.len()];
+        }
+        self.user_agent.as_deref().unwrap_or(default)
+    }
+
+    /// 附加的请求头（已在解析参数时校验）
+    pub fn header_map(&self) -> HeaderMap {
+        self.headers
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::from_bytes(name.as_bytes()).ok()?,
+                    HeaderValue::from_str(value).ok()?,
+                ))
+            })
+            .collect()
+    }
+
+    /// 原始HTTP请求中的User-Agent及附加请求头行（每行以 `\r\n` 结尾）
+    pub fn raw_header_lines(&self) -> String {
+        let mut lines = format!("User-Agent: {}\r\n", self.user_agent(DEFAULT_USER_AGENT));
+        for (name, value) in &self.headers {
+            lines.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        lines
+    }
+
+    /// 系统ping的填充模式（标记前 [`ICMP_MARKER_MAX_BYTES`] 字节的十六进制），未指定时为 `None`
+    pub fn icmp_pattern(&self) -> Option<String> {
+        let marker = self.icmp_marker.as_deref()?;
+        let hex: String = marker
+            .bytes()
+            .take(ICMP_MARKER_MAX_BYTES)
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        (!hex.is_empty()).then_some(hex)
+    }
+
+    /// 写入运行元数据的标识：(名称, 值)，只包含指定了的项
+    pub fn meta_entries(&self) -> Vec<(String, String)> {
+        let mut entries = Vec::new();
+        if self.randomize_ua {
+            entries.push((
+                "user_agent".to_string(),
+                format!("随机轮换（内置 {} 个）", UA_POOL.len()),
+            ));
+        } else if let Some(ref ua) = self.user_agent {
+            entries.push(("user_agent".to_string(), ua.clone()));
+        }
+        if !self.headers.is_empty() {
+            let headers: Vec<String> = self
+                .headers
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect();
+            entries.push(("http_header".to_string(), headers.join("; ")));
+        }
+        if let Some(ref probe) = self.tcp_probe {
+            let hex: String = probe.iter().map(|b| format!("{:02x}", b)).collect();
+            entries.push(("tcp_probe".to_string(), hex));
+        }
+        if let Some(ref marker) = self.icmp_marker {
+            entries.push(("icmp_marker".to_string(), marker.clone()));
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header_and_hex() {
+        assert_eq!(
+            parse_header("X-Pentest-ID: GX-2024-017"),
+            Ok(("X-Pentest-ID".to_string(), "GX-2024-017".to_string()))
+        );
+        assert!(parse_header("X-Pentest-ID").is_err());
+        assert!(parse_header("bad name: x").is_err());
+
+        assert_eq!(parse_hex("48454c4c4f0d0a"), Ok(b"HELLO\r\n".to_vec()));
+        assert_eq!(parse_hex("0x48 45"), Ok(b"HE".to_vec()));
+        assert_eq!(parse_hex("\\x48\\x45"), Ok(b"HE".to_vec()));
+        assert!(parse_hex("484").is_err());
+        assert!(parse_hex("zz").is_err());
+        assert!(parse_hex("4é").is_err());
+    }
+
+    #[test]
+    fn test_identity_user_agent_and_meta() {
+        let fixed = ProbeIdentity {
+            user_agent: Some("GX-Scanner/1.0 (GX-2024-017)".to_string()),
+            headers: vec![("X-Pentest-ID".to_string(), "GX-2024-017".to_string())],
+            icmp_marker: Some("GX-2024-017-MARKER-LONG".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(fixed.user_agent("default"), "GX-Scanner/1.0 (GX-2024-017)");
+        assert_eq!(
+            fixed.raw_header_lines(),
+            "User-Agent: GX-Scanner/1.0 (GX-2024-017)\r\nX-Pentest-ID: GX-2024-017\r\n"
+        );
+        assert_eq!(fixed.header_map().len(), 1);
+        assert_eq!(
+            fixed.icmp_pattern().unwrap().len(),
+            ICMP_MARKER_MAX_BYTES * 2
+        );
+        let keys: Vec<String> = fixed.meta_entries().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, ["user_agent", "http_header", "icmp_marker"]);
+
+        let random = ProbeIdentity {
+            randomize_ua: true,
+            ..Default::default()
+        };
+        let first = random.user_agent("default").to_string();
+        assert_ne!(random.user_agent("default"), first);
+        assert_eq!(ProbeIdentity::default().user_agent("default"), "default");
+    }
+}
@@ -0,0 +1,402 @@
+// src/utils/iface.rs
+use super::process::{CommandOutcome, output_with_timeout};
+use serde::Serialize;
+use std::io;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// 读取系统ARP表、网卡信息等外部命令的时限
+const ARP_COMMAND_LIMIT: Duration = Duration::from_secs(10);
+
+/// 常见网卡厂商的OUI（MAC前3字节），未收录的厂商显示为空
+const OUI_VENDORS: &[(&str, &str)] = &[
+    ("00:05:69", "VMware"),
+    ("00:0c:29", "VMware"),
+    ("00:1c:14", "VMware"),
+    ("00:50:56", "VMware"),
+    ("08:00:27", "VirtualBox"),
+    ("52:54:00", "QEMU/KVM"),
+    ("00:15:5d", "Microsoft Hyper-V"),
+    ("00:1c:42", "Parallels"),
+    ("00:16:3e", "Xen"),
+    ("b8:27:eb", "Raspberry Pi"),
+    ("dc:a6:32", "Raspberry Pi"),
+    ("e4:5f:01", "Raspberry Pi"),
+    ("d8:3a:dd", "Raspberry Pi"),
+    ("28:cd:c1", "Raspberry Pi"),
+    ("00:00:0c", "Cisco"),
+    ("00:e0:fc", "Huawei"),
+    ("00:0f:e2", "H3C"),
+    ("00:74:9c", "Ruijie"),
+    ("00:e0:4c", "Realtek"),
+    ("00:1b:21", "Intel"),
+    ("00:25:90", "Supermicro"),
+    ("00:14:22", "Dell"),
+    ("00:03:93", "Apple"),
+    ("44:19:b6", "Hikvision"),
+    ("3c:ef:8c", "Dahua"),
+    ("50:c7:bf", "TP-Link"),
+];
+
+/// 本机的一个IPv4网卡地址
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Interface {
+    /// 网卡名称
+    pub name: String,
+    /// IPv4地址
+    pub addr: Ipv4Addr,
+    /// 前缀长度
+    pub prefix: u8,
+    /// MAC地址（小写冒号分隔，无法获取时为 `None`）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mac: Option<String>,
+}
+
+impl Interface {
+    /// 网卡所在网段的网络地址
+    pub fn network(&self) -> Ipv4Addr {
+        Ipv4Addr::from(u32::from(self.addr) & prefix_mask(self.prefix))
+    }
+
+    /// 网卡所在网段（如 `192.168.1.0/24`）
+    pub fn cidr(&self) -> String {
+        format!("{}/{}", self.network(), self.prefix)
+    }
+
+    /// 是否与该网卡在同一网段
+    pub fn contains(&self, ip: Ipv4Addr) -> bool {
+        u32::from(ip) & prefix_mask(self.prefix) == u32::from(self.network())
+    }
+}
+
+/// 前缀长度对应的掩码
+fn prefix_mask(prefix: u8) -> u32 {
+    u32::MAX
+        .checked_shl(32 - u32::from(prefix.min(32)))
+        .unwrap_or(0)
+}
+
+/// ARP表中的一项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neighbor {
+    /// IP地址
+    pub ip: Ipv4Addr,
+    /// MAC地址（小写冒号分隔）
+    pub mac: String,
+}
+
+/// 列出本机已启用的IPv4网卡（不含回环网卡）
+///
+/// # 返回
+/// * `Err` - 系统接口调用失败或当前系统不支持
+#[cfg(unix)]
+pub fn list_interfaces() -> io::Result<Vec<Interface>> {
+    use std::ffi::CStr;
+
+    let mut interfaces = Vec::new();
+    // SAFETY: getifaddrs 分配的链表只在本函数内读取，结束前由 freeifaddrs 释放；
+    // 只在 sa_family 为 AF_INET 时把地址按 sockaddr_in 读取
+    unsafe {
+        let mut head: *mut libc::ifaddrs = std::ptr::null_mut();
+        if libc::getifaddrs(&mut head) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut cursor = head;
+        while !cursor.is_null() {
+            let entry = &*cursor;
+            cursor = entry.ifa_next;
+            let flags = entry.ifa_flags as libc::c_int;
+            if entry.ifa_addr.is_null()
+                || entry.ifa_netmask.is_null()
+                || i32::from((*entry.ifa_addr).sa_family) != libc::AF_INET
+                || flags & libc::IFF_UP == 0
+                || flags & libc::IFF_LOOPBACK != 0
+            {
+                continue;
+            }
+            let addr = &*(entry.ifa_addr as *const libc::sockaddr_in);
+            let mask = &*(entry.ifa_netmask as *const libc::sockaddr_in);
+            let name = CStr::from_ptr(entry.ifa_name).to_string_lossy().to_string();
+            interfaces.push(Interface {
+                mac: read_mac(&name),
+                name,
+                addr: Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr)),
+                prefix: u32::from_be(mask.sin_addr.s_addr).count_ones() as u8,
+            });
+        }
+        libc::freeifaddrs(head);
+    }
+    Ok(interfaces)
+}
+
+/// 列出本机已启用的IPv4网卡（解析 `ipconfig /all` 的输出）
+#[cfg(windows)]
+pub fn list_interfaces() -> io::Result<Vec<Interface>> {
+    use std::io::Read;
+    use std::process::Stdio;
+    use std::time::Instant;
+
+    let mut child = std::process::Command::new("ipconfig")
+        .arg("/all")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // 另起线程读取输出，避免管道写满导致子进程阻塞
+    let stdout = child.stdout.take();
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = stdout {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    });
+    let deadline = Instant::now() + ARP_COMMAND_LIMIT;
+    while child.try_wait()?.is_none() {
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("执行 ipconfig 超时（{}秒）", ARP_COMMAND_LIMIT.as_secs()),
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    let output = reader.join().unwrap_or_default();
+    Ok(parse_ipconfig(&decode_output(&output)))
+}
+
+/// 列出本机已启用的IPv4网卡
+#[cfg(not(any(unix, windows)))]
+pub fn list_interfaces() -> io::Result<Vec<Interface>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "当前系统不支持枚举网卡",
+    ))
+}
+
+/// 网卡的MAC地址
+#[cfg(unix)]
+fn read_mac(name: &str) -> Option<String> {
+    if cfg!(target_os = "linux") {
+        let mac = std::fs::read_to_string(format!("/sys/class/net/{}/address", name)).ok()?;
+        normalize_mac(mac.trim())
+    } else {
+        None
+    }
+}
+
+/// 读取系统ARP表（Linux读取 `/proc/net/arp`，其他系统执行 `arp -a`）
+///
+/// 只返回已解析出MAC的条目。
+pub async fn neighbor_table() -> io::Result<Vec<Neighbor>> {
+    if cfg!(target_os = "linux")
+        && let Ok(text) = tokio::fs::read_to_string("/proc/net/arp").await
+    {
+        return Ok(parse_proc_net_arp(&text));
+    }
+    let mut cmd = Command::new("arp");
+    cmd.arg(if cfg!(windows) { "-a" } else { "-an" });
+    match output_with_timeout(&mut cmd, ARP_COMMAND_LIMIT).await? {
+        CommandOutcome::Finished(output) => Ok(parse_arp_output(&decode_output(&output.stdout))),
+        CommandOutcome::TimedOut => Err(io::Error::new(io::ErrorKind::TimedOut, "读取ARP表超时")),
+    }
+}
+
+/// 命令输出按UTF-8解码，失败时按GBK（Windows中文版）解码
+fn decode_output(output: &[u8]) -> String {
+    match std::str::from_utf8(output) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::GBK.decode(output).0.to_string(),
+    }
+}
+
+/// 解析 `/proc/net/arp`（跳过未完成解析的条目）
+fn parse_proc_net_arp(text: &str) -> Vec<Neighbor> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let ip = fields.first()?.parse().ok()?;
+            // 标志位 0x2 表示已完成解析
+            let flags = u32::from_str_radix(fields.get(2)?.trim_start_matches("0x"), 16).ok()?;
+            if flags & 0x2 == 0 {
+                return None;
+            }
+            Some(Neighbor {
+                ip,
+                mac: normalize_mac(fields.get(3)?)?,
+            })
+        })
+        .collect()
+}
+
+/// 解析 `arp -a` / `arp -an` 的输出
+///
+/// 兼容BSD/macOS（`? (10.0.0.1) at 0:c:29:ab:cd:ef on en0`）及Windows（`10.0.0.1  00-0c-29-ab-cd-ef  动态`）。
+fn parse_arp_output(text: &str) -> Vec<Neighbor> {
+    text.lines()
+        .filter_map(|line| {
+            let tokens: Vec<&str> = line
+                .split_whitespace()
+                .map(|t| t.trim_matches(['(', ')']))
+                .collect();
+            // Windows的 "接口: 10.0.0.5 --- 0xb" 行没有MAC，不会被当作条目
+            let ip = tokens.iter().find_map(|t| t.parse::<Ipv4Addr>().ok())?;
+            let mac = tokens.iter().find_map(|t| normalize_mac(t))?;
+            Some(Neighbor { ip, mac })
+        })
+        .collect()
+}
+
+/// 解析 `ipconfig /all` 的输出（兼容中英文版）
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_ipconfig(text: &str) -> Vec<Interface> {
+    let mut interfaces = Vec::new();
+    let mut name = String::new();
+    let mut mac = None;
+    let mut addr: Option<Ipv4Addr> = None;
+    for line in text.lines() {
+        if !line.starts_with(' ') && line.trim_end().ends_with(':') {
+            // 网卡段落开始，如 "以太网适配器 以太网:"、"Ethernet adapter Ethernet:"
+            name = line
+                .trim_end()
+                .trim_end_matches(':')
+                .rsplit_once(' ')
+                .map_or(line.trim(), |(_, n)| n)
+                .to_string();
+            mac = None;
+            addr = None;
+            continue;
+        }
+        let Some((key, value)) = line.split_once(" : ") else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if key.starts_with("物理地址") || key.starts_with("Physical Address") {
+            mac = normalize_mac(value);
+        } else if key.contains("IPv4") {
+            // 值形如 "192.168.1.5(首选)" 或 "192.168.1.5(Preferred)"
+            addr = value.split('(').next().and_then(|v| v.trim().parse().ok());
+        } else if (key.starts_with("子网掩码") || key.starts_with("Subnet Mask"))
+            && let (Some(ip), Ok(mask)) = (addr.take(), value.parse::<Ipv4Addr>())
+            && !ip.is_loopback()
+        {
+            interfaces.push(Interface {
+                name: name.clone(),
+                addr: ip,
+                prefix: u32::from(mask).count_ones() as u8,
+                mac: mac.clone(),
+            });
+        }
+    }
+    interfaces
+}
+
+/// 统一MAC地址格式（小写冒号分隔，补齐前导0）；全0及格式不符的返回 `None`
+pub fn normalize_mac(mac: &str) -> Option<String> {
+    let parts: Vec<&str> = mac.split([':', '-']).collect();
+    if parts.len() != 6 {
+        return None;
+    }
+    let bytes: Vec<u8> = parts
+        .iter()
+        .map(|p| {
+            if p.is_empty() || p.len() > 2 {
+                None
+            } else {
+                u8::from_str_radix(p, 16).ok()
+            }
+        })
+        .collect::<Option<_>>()?;
+    if bytes.iter().all(|&b| b == 0) {
+        return None;
+    }
+    Some(
+        bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(":"),
+    )
+}
+
+/// MAC地址对应的厂商
+///
+/// # 返回
+/// * 内置表中收录的厂商名；本地管理的地址（常见于随机MAC）返回 "本地管理地址"；其余为 `None`
+pub fn mac_vendor(mac: &str) -> Option<&'static str> {
+    let prefix = mac.get(..8)?;
+    if let Some((_, vendor)) = OUI_VENDORS.iter().find(|(oui, _)| *oui == prefix) {
+        return Some(vendor);
+    }
+    let first = u8::from_str_radix(mac.get(..2)?, 16).ok()?;
+    (first & 0x02 != 0).then_some("本地管理地址")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_arp_tables() {
+        let proc = "IP address       HW type     Flags       HW address            Mask     Device\n\
+                    192.168.1.1      0x1         0x2         00:0c:29:AB:CD:EF     *        eth0\n\
+                    192.168.1.7      0x1         0x0         00:00:00:00:00:00     *        eth0\n";
+        assert_eq!(
+            parse_proc_net_arp(proc),
+            [Neighbor {
+                ip: "192.168.1.1".parse().unwrap(),
+                mac: "00:0c:29:ab:cd:ef".to_string()
+            }]
+        );
+
+        let bsd = "? (10.0.0.1) at 0:c:29:ab:cd:ef on en0 ifscope [ethernet]\n\
+                   ? (10.0.0.9) at (incomplete) on en0 ifscope [ethernet]\n";
+        let windows = "\n接口: 10.0.0.5 --- 0xb\n  Internet 地址         物理地址              类型\n  \
+                       10.0.0.2              b8-27-eb-01-02-03     动态\n";
+        let parsed: Vec<Neighbor> = parse_arp_output(bsd)
+            .into_iter()
+            .chain(parse_arp_output(windows))
+            .collect();
+        let pairs: Vec<(String, &str)> = parsed
+            .iter()
+            .map(|n| (n.ip.to_string(), n.mac.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("10.0.0.1".to_string(), "00:0c:29:ab:cd:ef"),
+                ("10.0.0.2".to_string(), "b8:27:eb:01:02:03")
+            ]
+        );
+        assert_eq!(mac_vendor("b8:27:eb:01:02:03"), Some("Raspberry Pi"));
+        assert_eq!(mac_vendor("52:54:00:12:34:56"), Some("QEMU/KVM"));
+        assert_eq!(mac_vendor("da:a1:19:00:00:01"), Some("本地管理地址"));
+        assert_eq!(mac_vendor("00:11:22:33:44:55"), None);
+    }
+
+    #[test]
+    fn test_parse_ipconfig_and_subnets() {
+        let text = "Windows IP 配置\n\n以太网适配器 以太网:\n\n   \
+                    物理地址. . . . . . . . . . . . . : 00-15-5D-01-02-03\n   \
+                    IPv4 地址 . . . . . . . . . . . . : 192.168.10.25(首选) \n   \
+                    子网掩码  . . . . . . . . . . . . : 255.255.254.0\n\n\
+                    Ethernet adapter VPN:\n\n   \
+                    Physical Address. . . . . . . . . : 00-50-56-C0-00-08\n   \
+                    IPv4 Address. . . . . . . . . . . : 10.8.0.6(Preferred) \n   \
+                    Subnet Mask . . . . . . . . . . . : 255.255.255.252\n";
+        let interfaces = parse_ipconfig(text);
+        assert_eq!(interfaces.len(), 2);
+        assert_eq!(interfaces[0].name, "以太网");
+        assert_eq!(interfaces[0].mac.as_deref(), Some("00:15:5d:01:02:03"));
+        assert_eq!(interfaces[0].cidr(), "192.168.10.0/23");
+        assert!(interfaces[0].contains("192.168.11.200".parse().unwrap()));
+        assert!(!interfaces[0].contains("192.168.12.1".parse().unwrap()));
+        assert_eq!(interfaces[1].name, "VPN");
+        assert_eq!(interfaces[1].cidr(), "10.8.0.4/30");
+        assert_eq!(prefix_mask(0), 0);
+    }
+}
@@ -0,0 +1,248 @@
+// src/utils/integrity.rs
+use super::run_dir::{MANIFEST_FILE_NAME, RunDir, SIGNATURE_FILE_NAME};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+use zeroize::Zeroizing;
+
+/// 流式计算文件的SHA256（十六进制），不把整个文件读入内存
+pub fn file_sha256(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// 读取Base64编码的32字节密钥
+fn read_key(path: &Path, what: &str) -> Result<[u8; 32], Box<dyn Error + Send + Sync>> {
+    let text = Zeroizing::new(
+        fs::read_to_string(path)
+            .map_err(|e| format!("无法读取{} {}: {}", what, path.display(), e))?,
+    );
+    let bytes = Zeroizing::new(
+        BASE64
+            .decode(text.trim())
+            .map_err(|_| format!("{}不是有效的Base64: {}", what, path.display()))?,
+    );
+    bytes.as_slice().try_into().map_err(|_| {
+        format!(
+            "{}应为32字节的Ed25519密钥（Base64编码）: {}",
+            what,
+            path.display()
+        )
+        .into()
+    })
+}
+
+/// 读取签名私钥文件（Base64编码的32字节Ed25519私钥）
+pub fn load_signing_key(path: &Path) -> Result<SigningKey, Box<dyn Error + Send + Sync>> {
+    let seed = Zeroizing::new(read_key(path, "签名私钥")?);
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// 读取签名公钥文件（Base64编码的32字节Ed25519公钥）
+pub fn load_verifying_key(path: &Path) -> Result<VerifyingKey, Box<dyn Error + Send + Sync>> {
+    VerifyingKey::from_bytes(&read_key(path, "签名公钥")?)
+        .map_err(|_| format!("签名公钥无效: {}", path.display()).into())
+}
+
+static SIGNING_KEY: OnceLock<SigningKey> = OnceLock::new();
+
+/// 设置全局签名私钥（启动时由 `--sign-key` 设置一次）
+pub fn set_signing_key(key: SigningKey) {
+    let _ = SIGNING_KEY.set(key);
+}
+
+/// 对产物索引签名，未设置签名私钥时返回 `None`
+///
+/// # 返回
+/// * `Some(String)` - Base64编码的签名
+pub fn sign_manifest(data: &[u8]) -> Option<String> {
+    SIGNING_KEY
+        .get()
+        .map(|key| BASE64.encode(key.sign(data).to_bytes()))
+}
+
+/// 单个产物的校验结论
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArtifactCheck {
+    /// 与索引一致
+    Ok,
+    /// 文件缺失或无法读取
+    Missing(String),
+    /// 内容与索引记录的SHA256不一致
+    Mismatch {
+        /// 索引记录的SHA256
+        expected: String,
+        /// 重新计算的SHA256
+        actual: String,
+    },
+}
+
+/// 产物索引签名的校验结论
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureCheck {
+    /// 签名有效
+    Valid,
+    /// 签名无效（索引被修改或公钥不匹配）
+    Invalid,
+    /// 指定了公钥但运行目录没有签名
+    Missing,
+    /// 有签名但未指定公钥
+    Unchecked,
+    /// 没有签名，也未指定公钥
+    Unsigned,
+}
+
+impl fmt::Display for SignatureCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            SignatureCheck::Valid => "签名有效",
+            SignatureCheck::Invalid => "签名无效（产物索引已被修改，或公钥不匹配）",
+            SignatureCheck::Missing => "运行目录没有签名",
+            SignatureCheck::Unchecked => "有签名，未指定 --public-key，未校验",
+            SignatureCheck::Unsigned => "未签名",
+        };
+        f.write_str(text)
+    }
+}
+
+/// 运行目录的校验结果
+#[derive(Debug, Clone)]
+pub struct VerifyReport {
+    /// 各产物的文件名及校验结论（按索引顺序）
+    pub artifacts: Vec<(String, ArtifactCheck)>,
+    /// 产物索引签名的校验结论
+    pub signature: SignatureCheck,
+}
+
+impl VerifyReport {
+    /// 不一致的产物数
+    pub fn failures(&self) -> usize {
+        self.artifacts
+            .iter()
+            .filter(|(_, check)| *check != ArtifactCheck::Ok)
+            .count()
+    }
+
+    /// 全部产物一致且签名没有问题
+    pub fn passed(&self) -> bool {
+        self.failures() == 0
+            && !matches!(
+                self.signature,
+                SignatureCheck::Invalid | SignatureCheck::Missing
+            )
+    }
+}
+
+/// 重新计算运行目录中各产物的SHA256并与索引比对，指定公钥时同时校验索引签名
+///
+/// # 参数
+/// * `dir` - 运行目录
+/// * `public_key` - 签名公钥
+pub fn verify_run(
+    dir: &Path,
+    public_key: Option<&VerifyingKey>,
+) -> Result<VerifyReport, Box<dyn Error + Send + Sync>> {
+    let manifest = RunDir::load_manifest(dir)?;
+    let artifacts = manifest
+        .artifacts
+        .iter()
+        .map(|artifact| {
+            let check = match file_sha256(&dir.join(&artifact.file)) {
+                Err(e) => ArtifactCheck::Missing(e.to_string()),
+                Ok(actual) if actual.eq_ignore_ascii_case(&artifact.sha256) => ArtifactCheck::Ok,
+                Ok(actual) => ArtifactCheck::Mismatch {
+                    expected: artifact.sha256.clone(),
+                    actual,
+                },
+            };
+            (artifact.file.clone(), check)
+        })
+        .collect();
+
+    let signature = fs::read_to_string(dir.join(SIGNATURE_FILE_NAME)).ok();
+    let signature = match (public_key, signature) {
+        (None, None) => SignatureCheck::Unsigned,
+        (None, Some(_)) => SignatureCheck::Unchecked,
+        (Some(_), None) => SignatureCheck::Missing,
+        (Some(key), Some(encoded)) => {
+            let data = fs::read(dir.join(MANIFEST_FILE_NAME))?;
+            let valid = BASE64
+                .decode(encoded.trim())
+                .ok()
+                .and_then(|b| Signature::from_slice(&b).ok())
+                .is_some_and(|sig| key.verify(&data, &sig).is_ok());
+            if valid {
+                SignatureCheck::Valid
+            } else {
+                SignatureCheck::Invalid
+            }
+        }
+    };
+
+    Ok(VerifyReport {
+        artifacts,
+        signature,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_names_the_modified_artifact() {
+        let root = std::env::temp_dir().join(format!("gxr_verify_{}", std::process::id()));
+        let run = RunDir::new(&root, "20240102100000-cd34", "net ping");
+        run.write_json("hosts.json", "json", &["10.0.0.1"], 1)
+            .unwrap();
+        run.write_json("ports.json", "json", &["10.0.0.1:22"], 1)
+            .unwrap();
+
+        // 用测试密钥对索引签名（全局密钥只能设置一次，这里直接签名）
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let manifest = run.path().join(MANIFEST_FILE_NAME);
+        let signature = BASE64.encode(key.sign(&fs::read(&manifest).unwrap()).to_bytes());
+        fs::write(run.path().join(SIGNATURE_FILE_NAME), signature).unwrap();
+
+        let clean = verify_run(run.path(), Some(&key.verifying_key())).unwrap();
+        assert!(clean.passed());
+        assert_eq!(clean.signature, SignatureCheck::Valid);
+
+        fs::write(run.path().join("ports.json"), "[]").unwrap();
+        let tampered = verify_run(run.path(), None).unwrap();
+        assert_eq!(tampered.signature, SignatureCheck::Unchecked);
+        assert_eq!(tampered.failures(), 1);
+        assert_eq!(tampered.artifacts[0].1, ArtifactCheck::Ok);
+        assert_eq!(tampered.artifacts[1].0, "ports.json");
+        assert!(matches!(
+            tampered.artifacts[1].1,
+            ArtifactCheck::Mismatch { .. }
+        ));
+
+        // 修改索引本身会使签名失效
+        let text = fs::read_to_string(&manifest).unwrap();
+        fs::write(&manifest, text.replace("net ping", "net http")).unwrap();
+        let other = SigningKey::from_bytes(&[8; 32]);
+        assert_eq!(
+            verify_run(run.path(), Some(&key.verifying_key()))
+                .unwrap()
+                .signature,
+            SignatureCheck::Invalid
+        );
+        assert_eq!(
+            verify_run(run.path(), Some(&other.verifying_key()))
+                .unwrap()
+                .signature,
+            SignatureCheck::Invalid
+        );
+        fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,282 @@
+// src/utils/knock.rs
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::OnceCell;
+use tokio::time::{Instant, sleep_until, timeout};
+
+/// 默认的敲门间隔（毫秒）
+pub const DEFAULT_KNOCK_DELAY_MS: u64 = 200;
+
+/// 敲门参数（目标端口受端口敲门保护时使用）
+#[derive(Args, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KnockArgs {
+    /// 探测每个主机前先按顺序敲门（如 7000,8000,9000:udp，协议默认TCP）
+    #[arg(long, value_name = "PORT[:tcp|udp],...")]
+    pub knock: Option<KnockSequence>,
+
+    /// 相邻两次敲门之间的间隔（毫秒），最后一次敲门后同样等待该间隔再开始探测
+    #[arg(
+        long,
+        default_value_t = DEFAULT_KNOCK_DELAY_MS,
+        value_name = "MS",
+        requires = "knock"
+    )]
+    pub knock_delay: u64,
+}
+
+impl Default for KnockArgs {
+    fn default() -> Self {
+        Self {
+            knock: None,
+            knock_delay: DEFAULT_KNOCK_DELAY_MS,
+        }
+    }
+}
+
+impl KnockArgs {
+    /// 按参数创建敲门器，未指定 `--knock` 时返回 `None`
+    pub fn knocker(&self) -> Option<Knocker> {
+        self.knock
+            .clone()
+            .map(|sequence| Knocker::new(sequence, Duration::from_millis(self.knock_delay)))
+    }
+}
+
+/// 敲门使用的协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KnockProtocol {
+    /// 发起TCP连接（发出SYN即可，不等待连接结果）
+    Tcp,
+    /// 发送一个空的UDP数据报
+    Udp,
+}
+
+/// 单次敲门
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Knock {
+    /// 端口
+    pub port: u16,
+    /// 协议
+    pub protocol: KnockProtocol,
+}
+
+impl fmt::Display for Knock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.protocol {
+            KnockProtocol::Tcp => write!(f, "{}", self.port),
+            KnockProtocol::Udp => write!(f, "{}:udp", self.port),
+        }
+    }
+}
+
+/// 敲门序列
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnockSequence(pub Vec<Knock>);
+
+impl FromStr for KnockSequence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut knocks = Vec::new();
+        for item in s.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            let (port, protocol) = match item.split_once(':') {
+                Some((port, protocol)) => {
+                    let protocol = match protocol.trim().to_ascii_lowercase().as_str() {
+                        "tcp" => KnockProtocol::Tcp,
+                        "udp" => KnockProtocol::Udp,
+                        _ => return Err(format!("敲门协议只支持 tcp 或 udp: {}", item)),
+                    };
+                    (port, protocol)
+                }
+                None => (item, KnockProtocol::Tcp),
+            };
+            let port = match port.trim().parse::<u16>() {
+                Ok(port) if port > 0 => port,
+                _ => return Err(format!("无效的敲门端口: {}", item)),
+            };
+            knocks.push(Knock { port, protocol });
+        }
+        if knocks.is_empty() {
+            return Err("敲门序列不能为空".to_string());
+        }
+        Ok(Self(knocks))
+    }
+}
+
+impl fmt::Display for KnockSequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let items: Vec<String> = self.0.iter().map(Knock::to_string).collect();
+        f.write_str(&items.join(","))
+    }
+}
+
+impl Serialize for KnockSequence {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for KnockSequence {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 发送一次敲门，错误（如连接被拒绝）不影响后续敲门
+async fn send_knock(ip: IpAddr, knock: Knock, wait: Duration) {
+    let addr = SocketAddr::new(ip, knock.port);
+    match knock.protocol {
+        KnockProtocol::Tcp => {
+            let _ = timeout(wait, TcpStream::connect(addr)).await;
+        }
+        KnockProtocol::Udp => {
+            let bind: SocketAddr = if ip.is_ipv4() {
+                ([0, 0, 0, 0], 0).into()
+            } else {
+                ([0u16; 8], 0).into()
+            };
+            if let Ok(socket) = UdpSocket::bind(bind).await {
+                let _ = socket.send_to(&[], addr).await;
+            }
+        }
+    }
+}
+
+/// 按主机执行敲门的预探测钩子
+///
+/// 每个主机在第一次探测前敲门一次，同一主机的其他探测等待敲门完成后再发起。
+#[derive(Debug)]
+pub struct Knocker {
+    sequence: KnockSequence,
+    delay: Duration,
+    hosts: Mutex<HashMap<String, Arc<OnceCell<bool>>>>,
+    silent: bool,
+}
+
+impl Knocker {
+    /// 创建敲门器
+    ///
+    /// # 参数
+    /// * `sequence` - 敲门序列
+    /// * `delay` - 相邻两次敲门之间的间隔
+    pub fn new(sequence: KnockSequence, delay: Duration) -> Self {
+        Self {
+            sequence,
+            delay,
+            hosts: Mutex::new(HashMap::new()),
+            silent: false,
+        }
+    }
+
+    /// 只标记不发送（回放录制时使用，结果中的敲门标记与录制时一致）
+    pub fn silent(mut self) -> Self {
+        self.silent = true;
+        self
+    }
+
+    /// 敲门序列
+    pub fn sequence(&self) -> &KnockSequence {
+        &self.sequence
+    }
+
+    /// 探测主机前调用：尚未敲门时按序列敲门，已敲过时立即返回
+    ///
+    /// # 返回
+    /// * `bool` - 是否对该主机敲过门（IP无效时为 `false`）
+    pub async fn before_probe(&self, ip: &str) -> bool {
+        let cell = self
+            .hosts
+            .lock()
+            .unwrap()
+            .entry(ip.to_string())
+            .or_default()
+            .clone();
+        *cell
+            .get_or_init(|| async {
+                let Ok(addr) = ip.parse::<IpAddr>() else {
+                    return false;
+                };
+                if self.silent {
+                    return true;
+                }
+                for knock in &self.sequence.0 {
+                    let next = Instant::now() + self.delay;
+                    send_knock(addr, *knock, self.delay).await;
+                    sleep_until(next).await;
+                }
+                true
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_knock_sequence() {
+        let sequence: KnockSequence = "7000, 8000:TCP ,9000:udp".parse().unwrap();
+        assert_eq!(
+            sequence.0,
+            vec![
+                Knock {
+                    port: 7000,
+                    protocol: KnockProtocol::Tcp
+                },
+                Knock {
+                    port: 8000,
+                    protocol: KnockProtocol::Tcp
+                },
+                Knock {
+                    port: 9000,
+                    protocol: KnockProtocol::Udp
+                },
+            ]
+        );
+        assert_eq!(sequence.to_string(), "7000,8000,9000:udp");
+        assert!("".parse::<KnockSequence>().is_err());
+        assert!("0".parse::<KnockSequence>().is_err());
+        assert!("7000:icmp".parse::<KnockSequence>().is_err());
+        assert!("70000".parse::<KnockSequence>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_knocks_each_host_once_in_order() {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let sequence: KnockSequence = format!("{}:udp,{}:udp", port, port).parse().unwrap();
+        let knocker = Knocker::new(sequence, Duration::from_millis(10));
+
+        let (a, b) = tokio::join!(
+            knocker.before_probe("127.0.0.1"),
+            knocker.before_probe("127.0.0.1")
+        );
+        assert!(a && b);
+        assert!(knocker.before_probe("127.0.0.1").await);
+        assert!(!knocker.before_probe("not-an-ip").await);
+
+        // 并发的两次探测只敲一次门：正好收到序列中的两个数据报
+        let mut buf = [0u8; 16];
+        for _ in 0..2 {
+            timeout(Duration::from_secs(1), socket.recv_from(&mut buf))
+                .await
+                .unwrap()
+                .unwrap();
+        }
+        assert!(
+            timeout(Duration::from_millis(100), socket.recv_from(&mut buf))
+                .await
+                .is_err()
+        );
+    }
+}
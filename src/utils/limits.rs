@@ -0,0 +1,327 @@
+// src/utils/limits.rs
+use super::adaptive::{DEFAULT_MAX, DEFAULT_MIN};
+use super::console::Icon;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// 为标准输入输出、日志、Excel写入等保留的文件描述符数量
+const FD_HEADROOM: u64 = 64;
+
+/// 无法获取上限时（如Windows）使用的文件描述符上限
+#[cfg(not(unix))]
+const FALLBACK_FD_LIMIT: u64 = 8192;
+
+/// 自动模式下TCP连接扫描的并发上限
+const AUTO_CONNECT_MAX: usize = 1000;
+
+/// 自动模式下ICMP（ping子进程）扫描的并发上限
+const AUTO_ICMP_MAX: usize = 256;
+
+/// 并发参数：固定值、自动或自适应
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConcurrencySpec {
+    /// 根据文件描述符上限、目标数量和扫描类型自动选择
+    Auto,
+    /// 用户指定的并发数
+    Fixed(usize),
+    /// 扫描过程中按出错率在上下限之间调整（见 [`AdaptiveController`](super::adaptive::AdaptiveController)）
+    Adaptive {
+        /// 下限（也是初始并发）
+        min: usize,
+        /// 上限
+        max: usize,
+    },
+}
+
+impl FromStr for ConcurrencySpec {
+    type Err = String;
+
+    /// 接受正整数、`auto`、`auto-adaptive` 或 `auto-adaptive:下限-上限`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("auto") {
+            return Ok(Self::Auto);
+        }
+        let lower = s.trim().to_ascii_lowercase();
+        if let Some(rest) = lower.strip_prefix("auto-adaptive") {
+            let (min, max) = match rest.strip_prefix(':') {
+                None if rest.is_empty() => (DEFAULT_MIN, DEFAULT_MAX),
+                Some(range) => range
+                    .split_once('-')
+                    .and_then(|(min, max)| Some((min.parse().ok()?, max.parse().ok()?)))
+                    .ok_or_else(|| format!("无效的自适应并发范围 \"{}\"，应为 下限-上限", range))?,
+                None => return Err(format!("无效的并发数 \"{}\"", s)),
+            };
+            if min == 0 || min > max {
+                return Err(format!(
+                    "自适应并发的下限须大于0且不超过上限: {}-{}",
+                    min, max
+                ));
+            }
+            return Ok(Self::Adaptive { min, max });
+        }
+        match s.trim().parse::<usize>() {
+            Ok(0) => Err("并发数必须大于0".to_string()),
+            Ok(n) => Ok(Self::Fixed(n)),
+            Err(_) => Err(format!(
+                "无效的并发数 \"{}\"，应为正整数、auto 或 auto-adaptive",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ConcurrencySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Fixed(n) => write!(f, "{}", n),
+            Self::Adaptive { min, max } => write!(f, "auto-adaptive:{}-{}", min, max),
+        }
+    }
+}
+
+/// 解析不支持自适应并发的模块的 `-c`（只接受正整数或 `auto`）
+pub fn parse_fixed_concurrency(s: &str) -> Result<ConcurrencySpec, String> {
+    match s.parse()? {
+        ConcurrencySpec::Adaptive { .. } => {
+            Err("该模块不支持 auto-adaptive（仅 net ping、pentest portscan 支持）".to_string())
+        }
+        spec => Ok(spec),
+    }
+}
+
+/// 序列化为与命令行一致的字符串形式（如 `"200"`、`"auto"`、`"auto-adaptive:50-2000"`）
+impl Serialize for ConcurrencySpec {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ConcurrencySpec {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 扫描类型（决定每个并发任务占用的文件描述符数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanKind {
+    /// TCP连接扫描：每个任务一个套接字
+    Connect,
+    /// ICMP扫描：每个任务一个ping子进程及其输出管道
+    Icmp,
+}
+
+impl ScanKind {
+    fn fds_per_task(self) -> u64 {
+        match self {
+            Self::Connect => 1,
+            Self::Icmp => 3,
+        }
+    }
+}
+
+/// 实际生效的并发数及其选择原因
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectiveConcurrency {
+    /// 生效的并发数
+    pub value: usize,
+    /// 选择原因
+    pub reason: String,
+}
+
+static FD_LIMIT: OnceLock<u64> = OnceLock::new();
+
+/// 获取进程可用的文件描述符上限
+///
+/// 首次调用时会尝试将软上限提升到硬上限，结果在进程内缓存。
+pub fn fd_limit() -> u64 {
+    *FD_LIMIT.get_or_init(raise_fd_limit)
+}
+
+#[cfg(unix)]
+fn raise_fd_limit() -> u64 {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit/setrlimit 只读写传入的结构体
+    unsafe {
+        if libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) != 0 {
+            return 1024;
+        }
+        if limit.rlim_cur < limit.rlim_max {
+            let mut raised = limit;
+            raised.rlim_cur = limit.rlim_max;
+            // macOS 不接受超过 OPEN_MAX 的软上限
+            if cfg!(target_os = "macos") {
+                raised.rlim_cur = raised.rlim_cur.min(10240);
+            }
+            if libc::setrlimit(libc::RLIMIT_NOFILE, &raised) == 0 {
+                limit = raised;
+            }
+        }
+    }
+    // rlim_t 在部分平台上不是 u64
+    #[allow(clippy::unnecessary_cast)]
+    let soft = limit.rlim_cur as u64;
+    soft
+}
+
+#[cfg(not(unix))]
+fn raise_fd_limit() -> u64 {
+    FALLBACK_FD_LIMIT
+}
+
+/// 根据文件描述符上限计算实际并发数
+///
+/// 固定值超过上限允许的并发时会被下调；自动模式按扫描类型取上限，
+/// 且不超过目标数量。自适应模式的结果为工作池的并发（即调整的上限），
+/// 同样不超过文件描述符上限及目标数量。
+///
+/// # 参数
+/// * `spec` - 用户指定的并发参数
+/// * `targets` - 任务总数
+/// * `kind` - 扫描类型
+/// * `fd_limit` - 文件描述符上限
+///
+/// # 返回
+/// * `EffectiveConcurrency` - 生效的并发数及原因
+pub fn resolve_concurrency(
+    spec: ConcurrencySpec,
+    targets: usize,
+    kind: ScanKind,
+    fd_limit: u64,
+) -> EffectiveConcurrency {
+    let budget = (fd_limit.saturating_sub(FD_HEADROOM) / kind.fds_per_task()).max(1) as usize;
+
+    match spec {
+        ConcurrencySpec::Fixed(n) if n > budget => EffectiveConcurrency {
+            value: budget,
+            reason: format!(
+                "请求的并发 {} 超过文件描述符上限 {} 所允许的 {}，已自动下调",
+                n, fd_limit, budget
+            ),
+        },
+        ConcurrencySpec::Fixed(n) => EffectiveConcurrency {
+            value: n,
+            reason: "用户指定".to_string(),
+        },
+        ConcurrencySpec::Auto => {
+            let (kind_max, kind_name) = match kind {
+                ScanKind::Connect => (AUTO_CONNECT_MAX, "TCP连接"),
+                ScanKind::Icmp => (AUTO_ICMP_MAX, "ICMP"),
+            };
+            let value = budget.min(kind_max).min(targets.max(1));
+            EffectiveConcurrency {
+                value,
+                reason: format!(
+                    "自动选择: {}扫描, 文件描述符上限 {}, 任务数 {}",
+                    kind_name, fd_limit, targets
+                ),
+            }
+        }
+        ConcurrencySpec::Adaptive { min, max } => {
+            let value = budget.min(max).min(targets.max(1));
+            EffectiveConcurrency {
+                value,
+                reason: format!(
+                    "自适应: 从 {} 开始按出错率在 {}~{} 之间调整",
+                    min.min(value),
+                    min.min(value),
+                    value
+                ),
+            }
+        }
+    }
+}
+
+/// 按当前进程的文件描述符上限计算并发数，下调时打印警告
+///
+/// 参数同 [`resolve_concurrency`]
+pub fn effective_concurrency(
+    spec: ConcurrencySpec,
+    targets: usize,
+    kind: ScanKind,
+) -> EffectiveConcurrency {
+    let effective = resolve_concurrency(spec, targets, kind, fd_limit());
+    if matches!(spec, ConcurrencySpec::Fixed(n) if n != effective.value) {
+        eprintln!("{} {}", Icon::Warn, effective.reason);
+    }
+    effective
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_concurrency_spec() {
+        assert_eq!("auto".parse(), Ok(ConcurrencySpec::Auto));
+        assert_eq!("AUTO".parse(), Ok(ConcurrencySpec::Auto));
+        assert_eq!("200".parse(), Ok(ConcurrencySpec::Fixed(200)));
+        assert!("0".parse::<ConcurrencySpec>().is_err());
+        assert!("many".parse::<ConcurrencySpec>().is_err());
+        assert_eq!(
+            "auto-adaptive".parse(),
+            Ok(ConcurrencySpec::Adaptive {
+                min: DEFAULT_MIN,
+                max: DEFAULT_MAX
+            })
+        );
+        let adaptive = ConcurrencySpec::Adaptive { min: 20, max: 300 };
+        assert_eq!(adaptive.to_string().parse(), Ok(adaptive));
+        assert!("auto-adaptive:300-20".parse::<ConcurrencySpec>().is_err());
+        assert!("auto-adaptivex".parse::<ConcurrencySpec>().is_err());
+        assert!(parse_fixed_concurrency("auto-adaptive").is_err());
+        assert_eq!(parse_fixed_concurrency("auto"), Ok(ConcurrencySpec::Auto));
+
+        let json = serde_json::to_string(&ConcurrencySpec::Fixed(50)).unwrap();
+        assert_eq!(json, "\"50\"");
+        assert_eq!(
+            serde_json::from_str::<ConcurrencySpec>("\"auto\"").unwrap(),
+            ConcurrencySpec::Auto
+        );
+    }
+
+    #[test]
+    fn test_fixed_concurrency_clamped_to_fd_limit() {
+        let e = resolve_concurrency(
+            ConcurrencySpec::Fixed(5000),
+            10_000,
+            ScanKind::Connect,
+            1024,
+        );
+        assert_eq!(e.value, 960);
+
+        let e = resolve_concurrency(ConcurrencySpec::Fixed(500), 10_000, ScanKind::Icmp, 1024);
+        assert_eq!(e.value, 320);
+
+        let e = resolve_concurrency(ConcurrencySpec::Fixed(50), 10_000, ScanKind::Connect, 1024);
+        assert_eq!(e.value, 50);
+        assert_eq!(e.reason, "用户指定");
+    }
+
+    #[test]
+    fn test_auto_concurrency() {
+        let e = resolve_concurrency(ConcurrencySpec::Auto, 100_000, ScanKind::Connect, 65536);
+        assert_eq!(e.value, AUTO_CONNECT_MAX);
+
+        let e = resolve_concurrency(ConcurrencySpec::Auto, 100_000, ScanKind::Icmp, 65536);
+        assert_eq!(e.value, AUTO_ICMP_MAX);
+
+        let e = resolve_concurrency(ConcurrencySpec::Auto, 20, ScanKind::Connect, 65536);
+        assert_eq!(e.value, 20);
+
+        let e = resolve_concurrency(ConcurrencySpec::Auto, 100_000, ScanKind::Connect, 256);
+        assert_eq!(e.value, 192);
+    }
+
+    #[test]
+    fn test_fd_limit_detected() {
+        assert!(fd_limit() > 0);
+    }
+}
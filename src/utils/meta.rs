@@ -0,0 +1,223 @@
+// src/utils/meta.rs
+use super::console::Icon;
+use super::dns;
+use super::run_dir::SummaryItem;
+use super::{Language, config_file, language, load_config_section};
+use chrono::{DateTime, Datelike, Local, Weekday};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::sync::OnceLock;
+
+/// 配置文件中的段落名
+pub const CONFIG_SECTION: &str = "meta";
+
+/// 运行元数据：名称 -> 值（项目名称、测试人员、授权文件编号等）
+pub type RunMeta = BTreeMap<String, String>;
+
+/// 有固定位置的常用名称：(名称, 别名, 显示名称)
+///
+/// 显示时按此顺序排在前面，其余名称按字母顺序排在后面。
+pub const WELL_KNOWN_KEYS: &[(&str, &[&str], &str)] = &[
+    ("project", &["项目"], "项目名称"),
+    ("tester", &["测试人员"], "测试人员"),
+    ("authorization", &["auth", "授权"], "授权文件编号"),
+    ("window", &["扫描窗口"], "扫描窗口"),
+    ("user_agent", &[], "User-Agent"),
+    ("http_header", &[], "附加请求头"),
+    ("tcp_probe", &[], "TCP探测报文"),
+    ("icmp_marker", &[], "ICMP标记"),
+    ("dns_resolver", &[], "DNS解析方式"),
+    ("dns_fallback", &[], "DNS回退"),
+];
+
+static META: OnceLock<RunMeta> = OnceLock::new();
+
+/// 名称的规范写法（常用名称的别名换成名称本身，如 `auth` -> `authorization`）
+pub fn canonical_key(key: &str) -> String {
+    let lower = key.to_lowercase();
+    WELL_KNOWN_KEYS
+        .iter()
+        .find(|(name, aliases, _)| *name == lower || aliases.contains(&key))
+        .map(|(name, ..)| name.to_string())
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// 名称的显示写法（常用名称为中文名称，其余原样显示）
+pub fn label(key: &str) -> &str {
+    WELL_KNOWN_KEYS
+        .iter()
+        .find(|(name, ..)| *name == key)
+        .map_or(key, |(.., label)| label)
+}
+
+/// 解析 `--meta KEY=VALUE`
+///
+/// # 返回
+/// * `Ok((名称, 值))` - 名称为规范写法
+/// * `Err(String)` - 缺少 `=` 或名称为空
+pub fn parse_meta(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("元数据格式应为 名称=值: {}", s))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!("元数据名称不能为空: {}", s));
+    }
+    Ok((canonical_key(key), value.trim().to_string()))
+}
+
+/// 合并配置文件的 `meta` 段落与命令行的 `--meta`（同名时以命令行为准）
+///
+/// ```yaml
+/// meta:
+///   project: 某单位等保测评
+///   tester: 张三
+/// ```
+///
+/// # 参数
+/// * `args` - 命令行的 `--meta`
+/// * `window` - 扫描窗口（`--window`），未用 `--meta window=` 指定时记为窗口
+/// * `identity` - 探测标识（见 [`ProbeIdentity::meta_entries`](super::identity::ProbeIdentity::meta_entries)），以实际使用的为准
+///
+/// # 返回
+/// * `Err` - 配置文件格式错误
+pub fn resolve(
+    args: &[(String, String)],
+    window: Option<&str>,
+    identity: Vec<(String, String)>,
+) -> Result<RunMeta, Box<dyn Error + Send + Sync>> {
+    let config: RunMeta = load_config_section(&config_file(), CONFIG_SECTION)?;
+    let mut meta: RunMeta = config
+        .into_iter()
+        .map(|(key, value)| (canonical_key(&key), value))
+        .collect();
+    meta.extend(args.iter().cloned());
+    if let Some(window) = window {
+        meta.entry("window".to_string())
+            .or_insert_with(|| window.to_string());
+    }
+    meta.extend(identity);
+    meta.retain(|_, value| !value.is_empty());
+    Ok(meta)
+}
+
+/// 设置本次运行的元数据（仅首次设置生效，应在程序启动时调用）
+pub fn set_meta(meta: RunMeta) {
+    let _ = META.set(meta);
+}
+
+/// 本次运行的元数据，未设置时为空
+pub fn meta() -> &'static RunMeta {
+    static EMPTY: RunMeta = BTreeMap::new();
+    META.get().unwrap_or(&EMPTY)
+}
+
+/// 写入报告及历史记录的元数据：启动时设置的元数据加上运行中才能确定的项（如加密DNS是否回退）
+pub fn current() -> RunMeta {
+    let mut meta = meta().clone();
+    meta.extend(dns::meta_entries());
+    meta
+}
+
+/// 按显示顺序排列的 (显示名称, 值)：常用名称在前
+pub fn ordered(meta: &RunMeta) -> Vec<(&str, &str)> {
+    let known = WELL_KNOWN_KEYS
+        .iter()
+        .filter_map(|(name, .., label)| meta.get(*name).map(|v| (*label, v.as_str())));
+    let others = meta
+        .iter()
+        .filter(|(key, _)| !WELL_KNOWN_KEYS.iter().any(|(name, ..)| name == key))
+        .map(|(key, value)| (key.as_str(), value.as_str()));
+    known.chain(others).collect()
+}
+
+/// 元数据在统计摘要中的各项（排在摘要最前面）
+pub fn summary_items(meta: &RunMeta) -> Vec<SummaryItem> {
+    ordered(meta)
+        .into_iter()
+        .map(|(label, value)| (label.to_string(), value.to_string()))
+        .collect()
+}
+
+/// 按界面语言格式化带星期的时间，如 `2024-03-08 星期五 14:30:00`
+pub fn format_timestamp(time: DateTime<Local>) -> String {
+    let weekday = match language() {
+        Language::En => time.format("%a").to_string(),
+        Language::Zh => weekday_zh(time.weekday()).to_string(),
+    };
+    format!(
+        "{} {} {}",
+        time.format("%Y-%m-%d"),
+        weekday,
+        time.format("%H:%M:%S")
+    )
+}
+
+fn weekday_zh(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "星期一",
+        Weekday::Tue => "星期二",
+        Weekday::Wed => "星期三",
+        Weekday::Thu => "星期四",
+        Weekday::Fri => "星期五",
+        Weekday::Sat => "星期六",
+        Weekday::Sun => "星期日",
+    }
+}
+
+/// 打印运行开始的横幅：模块、带星期的开始时间及元数据
+///
+/// # 参数
+/// * `module` - 模块名称
+/// * `started_at` - 开始时间
+pub fn print_banner(module: &str, started_at: DateTime<Local>) {
+    println!(
+        "{} {} · {}",
+        Icon::List,
+        module,
+        format_timestamp(started_at)
+    );
+    let items = ordered(meta());
+    if !items.is_empty() {
+        let line: Vec<String> = items
+            .iter()
+            .map(|(label, value)| format!("{}: {}", label, value))
+            .collect();
+        println!("   {}", line.join(" | "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_well_known_keys_come_first() {
+        let args: Vec<(String, String)> = ["客户=某单位", "auth=GX-2024-017", "project=XX"]
+            .iter()
+            .map(|s| parse_meta(s).unwrap())
+            .collect();
+        let mut meta: RunMeta = args.into_iter().collect();
+        meta.insert("tester".to_string(), "张三".to_string());
+        assert!(meta.contains_key("authorization"));
+        assert_eq!(
+            ordered(&meta),
+            [
+                ("项目名称", "XX"),
+                ("测试人员", "张三"),
+                ("授权文件编号", "GX-2024-017"),
+                ("客户", "某单位"),
+            ]
+        );
+        assert!(parse_meta("=x").is_err());
+        assert!(parse_meta("project").is_err());
+    }
+
+    #[test]
+    fn test_timestamp_has_weekday() {
+        let time = Local.with_ymd_and_hms(2024, 3, 8, 14, 30, 0).unwrap();
+        assert_eq!(weekday_zh(time.weekday()), "星期五");
+        assert!(format_timestamp(time).ends_with(" 14:30:00"));
+    }
+}
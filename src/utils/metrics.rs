@@ -0,0 +1,276 @@
+// src/utils/metrics.rs
+use super::format_duration;
+use chrono::{DateTime, Local};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// 单个探测任务的计时
+///
+/// 由工作池在任务开始和结束时记录，随结果一起交给调用方，业务逻辑中不另行计时。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeTiming {
+    /// 任务开始时间
+    pub started: Instant,
+    /// 任务结束时间
+    pub finished: Instant,
+    /// 尝试次数（含重试）
+    pub attempts: u32,
+    /// 超时次数（探测超过硬性时限或连接未完成即被放弃）
+    pub timeouts: u32,
+    /// 完成的连接数（成功或被拒绝）
+    pub connects: u32,
+    /// 完成的连接的总耗时
+    pub connect_time: Duration,
+    /// 首探超时后进入重探的次数（两段式探测，见 `--timeout-retry`）
+    pub retransmits: u32,
+}
+
+impl ProbeTiming {
+    /// 合并同一任务的后一轮探测（如两段式探测的重探），得到整个任务的计时
+    ///
+    /// # 参数
+    /// * `later` - 后一轮探测的计时
+    pub fn merge(self, later: ProbeTiming) -> ProbeTiming {
+        ProbeTiming {
+            started: self.started.min(later.started),
+            finished: self.finished.max(later.finished),
+            attempts: self.attempts + later.attempts,
+            timeouts: self.timeouts + later.timeouts,
+            connects: self.connects + later.connects,
+            connect_time: self.connect_time + later.connect_time,
+            retransmits: self.retransmits + later.retransmits,
+        }
+    }
+}
+
+/// 单个主机的扫描指标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostMetrics {
+    /// 探测次数（含重试）
+    pub probes: u32,
+    /// 首次探测开始时间
+    pub first_probe: Instant,
+    /// 最后一次探测结束时间
+    pub last_probe: Instant,
+    /// 完成的连接数
+    pub connects: u32,
+    /// 完成的连接的总耗时
+    pub connect_time: Duration,
+    /// 超时次数
+    pub timeouts: u32,
+    /// 进入重探的端口数
+    pub retransmits: u32,
+}
+
+impl HostMetrics {
+    /// 主机耗时（首次探测开始到最后一次探测结束）
+    pub fn wall_time(&self) -> Duration {
+        self.last_probe.saturating_duration_since(self.first_probe)
+    }
+
+    /// 平均连接延迟
+    pub fn avg_connect(&self) -> Option<Duration> {
+        (self.connects > 0).then(|| self.connect_time / self.connects)
+    }
+}
+
+/// 扫描指标收集器（按主机汇总探测计时，并记录各阶段耗时）
+#[derive(Debug, Clone)]
+pub struct ScanMetrics {
+    origin: Instant,
+    origin_wall: DateTime<Local>,
+    hosts: BTreeMap<String, HostMetrics>,
+    phases: Vec<(String, Duration)>,
+}
+
+impl Default for ScanMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScanMetrics {
+    /// 创建收集器，以当前时刻为换算墙上时间的基准
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            origin_wall: Local::now(),
+            hosts: BTreeMap::new(),
+            phases: Vec::new(),
+        }
+    }
+
+    /// 汇总一个探测任务的计时
+    ///
+    /// # 参数
+    /// * `ip` - 主机
+    /// * `timing` - 探测计时
+    pub fn record(&mut self, ip: &str, timing: &ProbeTiming) {
+        let host = self
+            .hosts
+            .entry(ip.to_string())
+            .or_insert_with(|| HostMetrics {
+                probes: 0,
+                first_probe: timing.started,
+                last_probe: timing.finished,
+                connects: 0,
+                connect_time: Duration::ZERO,
+                timeouts: 0,
+                retransmits: 0,
+            });
+        host.probes += timing.attempts;
+        host.first_probe = host.first_probe.min(timing.started);
+        host.last_probe = host.last_probe.max(timing.finished);
+        host.connects += timing.connects;
+        host.connect_time += timing.connect_time;
+        host.timeouts += timing.timeouts;
+        host.retransmits += timing.retransmits;
+    }
+
+    /// 记录一个阶段的耗时（同名阶段累加）
+    ///
+    /// # 参数
+    /// * `name` - 阶段名称（如 "端口扫描"）
+    /// * `elapsed` - 耗时
+    pub fn record_phase(&mut self, name: &str, elapsed: Duration) {
+        match self.phases.iter_mut().find(|(n, _)| n == name) {
+            Some((_, total)) => *total += elapsed,
+            None => self.phases.push((name.to_string(), elapsed)),
+        }
+    }
+
+    /// 各主机的指标
+    pub fn hosts(&self) -> &BTreeMap<String, HostMetrics> {
+        &self.hosts
+    }
+
+    /// 是否没有任何主机指标
+    pub fn is_empty(&self) -> bool {
+        self.hosts.is_empty()
+    }
+
+    /// 耗时最长的主机
+    ///
+    /// # 参数
+    /// * `n` - 最多返回的数量
+    pub fn slowest(&self, n: usize) -> Vec<(&str, &HostMetrics)> {
+        let mut hosts: Vec<(&str, &HostMetrics)> =
+            self.hosts.iter().map(|(ip, m)| (ip.as_str(), m)).collect();
+        hosts.sort_by(|a, b| b.1.wall_time().cmp(&a.1.wall_time()).then(a.0.cmp(b.0)));
+        hosts.truncate(n);
+        hosts
+    }
+
+    /// 把计时中的时刻换算为墙上时间
+    pub fn wall_clock(&self, instant: Instant) -> DateTime<Local> {
+        match instant.checked_duration_since(self.origin) {
+            Some(after) => self.origin_wall + after,
+            None => self.origin_wall - self.origin.duration_since(instant),
+        }
+    }
+
+    /// 各阶段耗时的摘要（如 "存活探测 12s, 端口扫描 5m 3s"），没有记录阶段时返回 `None`
+    pub fn phase_summary(&self) -> Option<String> {
+        (!self.phases.is_empty()).then(|| {
+            self.phases
+                .iter()
+                .map(|(name, elapsed)| format!("{} {}", name, format_duration(*elapsed)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+    }
+
+    /// 主机汇总表中追加的列名
+    pub const HOST_HEADERS: [&'static str; 7] = [
+        "探测次数",
+        "首次探测",
+        "末次探测",
+        "主机耗时",
+        "平均连接延迟",
+        "超时次数",
+        "重探端口数",
+    ];
+
+    /// 主机汇总表中追加的单元格（与 [`Self::HOST_HEADERS`] 对应）
+    ///
+    /// # 参数
+    /// * `ip` - 主机
+    pub fn host_cells(&self, ip: &str) -> Vec<String> {
+        let Some(m) = self.hosts.get(ip) else {
+            return vec![String::new(); Self::HOST_HEADERS.len()];
+        };
+        let time = |instant| self.wall_clock(instant).format("%H:%M:%S").to_string();
+        vec![
+            m.probes.to_string(),
+            time(m.first_probe),
+            time(m.last_probe),
+            format_duration(m.wall_time()),
+            m.avg_connect().map(format_duration).unwrap_or_default(),
+            m.timeouts.to_string(),
+            m.retransmits.to_string(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(start: Instant, from_ms: u64, to_ms: u64, connect_ms: u64) -> ProbeTiming {
+        ProbeTiming {
+            started: start + Duration::from_millis(from_ms),
+            finished: start + Duration::from_millis(to_ms),
+            attempts: 1,
+            timeouts: u32::from(connect_ms == 0),
+            connects: u32::from(connect_ms > 0),
+            connect_time: Duration::from_millis(connect_ms),
+            retransmits: 0,
+        }
+    }
+
+    #[test]
+    fn test_host_metrics_are_aggregated() {
+        let mut metrics = ScanMetrics::new();
+        let t0 = Instant::now();
+        metrics.record("10.0.0.1", &timing(t0, 100, 300, 20));
+        metrics.record("10.0.0.1", &timing(t0, 50, 200, 40));
+        // 首探超时后重探成功的端口，两轮计时合并为一个任务
+        let first = timing(t0, 400, 600, 0);
+        let retry = ProbeTiming {
+            retransmits: 1,
+            ..timing(t0, 900, 3400, 0)
+        };
+        metrics.record("10.0.0.1", &first.merge(retry));
+        metrics.record("10.0.0.2", &timing(t0, 0, 90, 10));
+
+        let host = metrics.hosts()["10.0.0.1"];
+        assert_eq!(host.probes, 4);
+        assert_eq!(host.timeouts, 2);
+        assert_eq!(host.retransmits, 1);
+        assert_eq!(host.wall_time(), Duration::from_millis(3350));
+        assert_eq!(host.avg_connect(), Some(Duration::from_millis(30)));
+
+        let slowest: Vec<&str> = metrics.slowest(10).into_iter().map(|(ip, _)| ip).collect();
+        assert_eq!(slowest, ["10.0.0.1", "10.0.0.2"]);
+        assert_eq!(metrics.slowest(1).len(), 1);
+
+        let cells = metrics.host_cells("10.0.0.1");
+        assert_eq!(cells.len(), ScanMetrics::HOST_HEADERS.len());
+        assert_eq!(cells[0], "4");
+        assert_eq!(cells[5], "2");
+        assert_eq!(cells[6], "1");
+        assert!(metrics.host_cells("10.0.0.9").iter().all(String::is_empty));
+    }
+
+    #[test]
+    fn test_phase_summary() {
+        let mut metrics = ScanMetrics::new();
+        assert_eq!(metrics.phase_summary(), None);
+        metrics.record_phase("存活探测", Duration::from_millis(1500));
+        metrics.record_phase("端口扫描", Duration::from_secs(20));
+        metrics.record_phase("存活探测", Duration::from_millis(500));
+        let summary = metrics.phase_summary().unwrap();
+        assert!(summary.starts_with("存活探测 2"));
+        assert!(summary.contains(", 端口扫描 20"));
+    }
+}
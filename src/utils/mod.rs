@@ -0,0 +1,2372 @@
+pub mod adaptive;
+pub mod assets;
+pub mod blocking;
+pub mod body_grep;
+pub mod cluster;
+pub mod console;
+pub mod context;
+pub mod control;
+pub mod dns;
+pub mod dualstack;
+pub mod finding;
+pub mod geo;
+pub mod http_pool;
+pub mod identity;
+pub mod iface;
+pub mod integrity;
+pub mod knock;
+pub mod limits;
+pub mod meta;
+pub mod metrics;
+pub mod output;
+pub mod pause;
+pub mod plan;
+pub mod pool;
+pub mod process;
+pub mod provenance;
+pub mod quic;
+pub mod redact;
+pub mod run_dir;
+pub mod salvage;
+pub mod sample;
+pub mod scope;
+pub mod script;
+pub mod secret;
+pub mod secure_dns;
+pub mod snapshot;
+pub mod stats;
+pub mod targets;
+pub mod timing;
+pub mod tls;
+pub mod transcript;
+pub mod verify;
+pub mod window;
+
+use crate::error::{GxError, PortErrorReason, PortSpecError};
+use adaptive::AdaptiveController;
+use blocking::BlockingStage;
+use chrono::Local;
+use console::Icon;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use output::{OutputKind, reserve_unique_path};
+use redact::{Redactor, redactor, save_export_mapping};
+use run_dir::RunDir;
+use rust_xlsxwriter::ColNum;
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use stats::{Outcome, ScanStats, StatsSnapshot};
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// 扫描进度控制结构体
+///
+/// 封装了进度条功能，支持线程安全的进度更新和消息输出。
+/// 每个进度条带有本阶段的 [`ScanStats`]，经 [`ScanProgress::record`] 记录的任务同时推进进度条。
+/// 开启自适应并发时，记录的结论同时交给控制器。
+#[derive(Clone)]
+pub struct ScanProgress {
+    pb: Arc<ProgressBar>,
+    stats: Arc<ScanStats>,
+    adaptive: Option<Arc<AdaptiveController>>,
+}
+
+impl ScanProgress {
+    /// 创建新的进度条
+    ///
+    /// # 参数
+    /// * `total` - 总任务数
+    ///
+    /// # 示例
+    /// ```ignore
+    /// let progress = ScanProgress::new(100);
+    /// ```
+    pub fn new(total: u64) -> Self {
+        let pb = ProgressBar::new(total);
+        pb.set_style(
+            ProgressStyle::with_template(
+                "[{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({percent}%) [ETA: {eta}] {msg}",
+            )
+            .unwrap()
+            .progress_chars(console::progress_chars()),
+        );
+        Self {
+            pb: Arc::new(pb),
+            stats: Arc::new(ScanStats::default()),
+            adaptive: None,
+        }
+    }
+
+    /// 把记录的结论交给自适应并发控制器
+    pub fn with_adaptive(mut self, controller: Arc<AdaptiveController>) -> Self {
+        self.adaptive = Some(controller);
+        self
+    }
+
+    /// 本阶段的扫描统计
+    pub fn stats(&self) -> &ScanStats {
+        &self.stats
+    }
+
+    /// 记录一个任务的结论，进度条位置取自统计中的已完成数
+    pub fn record(&self, outcome: Outcome) {
+        let completed = self.stats.complete(outcome);
+        self.pb.set_position(completed);
+        if let Some(ref controller) = self.adaptive {
+            controller.observe(outcome, self);
+        }
+    }
+
+    /// 本阶段的统计快照
+    pub fn snapshot(&self) -> StatsSnapshot {
+        self.stats.snapshot()
+    }
+
+    /// 进度增加指定数量
+    ///
+    /// # 参数
+    /// * `delta` - 增加的数量，默认为1
+    pub fn inc(&self, delta: u64) {
+        self.pb.inc(delta);
+    }
+
+    /// 在进度条上方输出信息（不会破坏进度条显示）
+    ///
+    /// # 参数
+    /// * `msg` - 要输出的消息
+    pub fn println<S: AsRef<str>>(&self, msg: S) {
+        self.pb.println(msg.as_ref());
+    }
+
+    /// 设置进度条消息
+    ///
+    /// # 参数
+    /// * `msg` - 消息内容
+    pub fn set_message<S: Into<String>>(&self, msg: S) {
+        self.pb.set_message(msg.into());
+    }
+
+    /// 已完成的任务数
+    pub fn position(&self) -> u64 {
+        self.pb.position()
+    }
+
+    /// 总任务数
+    pub fn length(&self) -> u64 {
+        self.pb.length().unwrap_or(0)
+    }
+
+    /// 增加总任务数（任务由上一阶段陆续产生、总数事先未知时使用）
+    ///
+    /// # 参数
+    /// * `delta` - 增加的数量
+    pub fn inc_length(&self, delta: u64) {
+        self.pb.inc_length(delta);
+    }
+
+    /// 重新开始估算剩余时间（如暂停结束后）
+    pub fn reset_eta(&self) {
+        self.pb.reset_eta();
+    }
+
+    /// 隐藏或恢复进度条绘制（交互界面接管终端时使用）
+    ///
+    /// # 参数
+    /// * `hidden` - 是否隐藏
+    pub fn set_hidden(&self, hidden: bool) {
+        let target = if hidden {
+            ProgressDrawTarget::hidden()
+        } else {
+            ProgressDrawTarget::stderr()
+        };
+        self.pb.set_draw_target(target);
+    }
+
+    /// 加入多进度条组（与各主机的进度条一同绘制）
+    ///
+    /// # 参数
+    /// * `multi` - 多进度条组
+    pub fn attach(&self, multi: &MultiProgress) {
+        multi.add(ProgressBar::clone(&self.pb));
+    }
+
+    /// 完成并关闭进度条
+    pub fn finish(&self) {
+        self.pb.finish_with_message("✅ 扫描完成");
+    }
+
+    /// 完成进度条并显示自定义消息
+    ///
+    /// # 参数
+    /// * `msg` - 完成时显示的消息
+    pub fn finish_with_message<S: Into<String>>(&self, msg: S) {
+        self.pb.finish_with_message(msg.into());
+    }
+}
+
+/// 确保输出目录存在，如不存在则创建
+///
+/// # 参数
+/// * `path` - 目录路径
+///
+/// # 返回
+/// * `Ok(PathBuf)` - 目录的完整路径
+/// * `Err` - 创建失败时返回错误
+///
+/// # 示例
+/// ```ignore
+/// let output_dir = ensure_output_dir("output/scan")?;
+/// ```
+pub fn ensure_output_dir(path: &str) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let output_dir = PathBuf::from(path);
+    if !output_dir.exists() {
+        fs::create_dir_all(&output_dir).map_err(|e| format!("创建目录失败 {}: {}", path, e))?;
+    }
+    Ok(output_dir)
+}
+
+/// 确定导出文件的路径
+///
+/// 有运行目录时为 `<运行目录>/<名称>.<扩展名>`，
+/// 否则为 `<输出根目录>/<子目录>/<前缀>_<名称>_<时间戳>.<扩展名>`
+/// （同一秒内已有同名文件时追加序号，见 [`reserve_unique_path`]）。
+/// 目录不存在时自动创建，写入后的登记由调用方负责。
+///
+/// # 参数
+/// * `name` - 文件名称（如 "findings_vm"）
+/// * `ext` - 扩展名
+/// * `kind` - 平铺输出时的模块子目录及文件名前缀
+/// * `run_dir` - 本次运行的工作目录
+pub fn output_file_path(
+    name: &str,
+    ext: &str,
+    kind: OutputKind,
+    run_dir: Option<&RunDir>,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    Ok(match run_dir {
+        Some(run) => run.ensure()?.join(format!("{}.{}", name, ext)),
+        None => {
+            let dir = ensure_output_dir(&output_root().join(kind.subdir).to_string_lossy())?;
+            let stem = format!(
+                "{}_{}_{}",
+                kind.prefix,
+                name,
+                Local::now().format("%Y%m%d_%H%M%S")
+            );
+            reserve_unique_path(&dir, &stem, ext)?
+        }
+    })
+}
+
+/// 检查文件是否存在
+///
+/// # 参数
+/// * `file_path` - 文件路径
+///
+/// # 返回
+/// * `true` - 文件存在
+/// * `false` - 文件不存在
+#[inline]
+pub fn check_file_exists(file_path: &Path) -> bool {
+    file_path.exists() && file_path.is_file()
+}
+
+/// 创建Excel模板文件（仅包含表头）
+///
+/// # 参数
+/// * `path` - Excel文件路径
+/// * `headers` - 表头列表
+///
+/// # 返回
+/// * `Ok(())` - 创建成功
+/// * `Err(XlsxError)` - 创建失败
+///
+/// # 示例
+/// ```ignore
+/// create_excel_template(
+///     "output/template.xlsx",
+///     vec!["IP地址".to_string(), "端口".to_string(), "状态".to_string()]
+/// )?;
+/// ```
+pub fn create_excel_template<P: AsRef<Path>>(
+    path: P,
+    headers: Vec<String>,
+) -> Result<(), XlsxError> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+
+    // 创建表头格式（加粗）
+    let header_format = Format::new().set_bold();
+
+    // 写入表头
+    for (col_num, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(0, col_num as u16, header, &header_format)?;
+    }
+
+    workbook.save(path.as_ref())?;
+    Ok(())
+}
+
+/// 解析目标IP地址字符串，支持多种格式
+///
+/// 支持的格式：
+/// - 单个IP: `192.168.1.1`
+/// - 多个IP（逗号分隔）: `192.168.1.1,192.168.1.2`
+/// - IP范围: `192.168.1.1-10`
+/// - CIDR: `192.168.1.0/24`
+/// - 按段通配: `192.168.*.*`、`10.0.1-5.1`（最后一段为 `*` 时与CIDR一致，不含 .0 和 .255）
+///
+/// # 参数
+/// * `targets` - 目标字符串
+///
+/// # 返回
+/// * `Ok(Vec<String>)` - 解析后的IP地址列表（重叠的写法只保留一次，按地址数值排序）
+/// * `Err` - 解析失败时返回错误信息
+///
+/// # 示例
+/// ```ignore
+/// let ips = parse_targets("192.168.1.0/24,10.0.0.1-5")?;
+/// ```
+pub fn parse_targets(targets: &str) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+    Ok(parse_targets_iter(targets)?
+        .map(|ip| ip.to_string())
+        .collect())
+}
+
+/// 按地址顺序逐个产生目标IP，不预先展开（适用于 /8 这样的大网段）
+///
+/// 格式同 [`parse_targets`]。重叠或相邻的写法先合并为不相交的区间，
+/// 每个IP只产生一次，按地址数值排序（与 [`parse_targets`] 相同）。
+///
+/// # 参数
+/// * `targets` - 目标字符串
+///
+/// # 返回
+/// * `Ok(迭代器)` - 去重后的IP地址
+/// * `Err` - 解析失败时返回错误信息（与 [`parse_targets`] 相同）
+///
+/// # 示例
+/// ```ignore
+/// let ips = parse_targets_iter("10.0.0.0/24,10.0.0.5")?;
+/// assert_eq!(ips.count(), 254);
+/// ```
+pub fn parse_targets_iter(
+    targets: &str,
+) -> Result<impl Iterator<Item = Ipv4Addr> + Send + 'static, Box<dyn Error + Send + Sync>> {
+    let ranges = merge_ranges(parse_target_ranges(targets, false)?);
+    Ok(ranges
+        .into_iter()
+        .flat_map(|(start, end)| (start..=end).map(Ipv4Addr::from)))
+}
+
+/// 目标去重后的IP数，不展开任何地址（用于预先设置进度条总数）
+///
+/// # 参数
+/// * `targets` - 目标字符串，格式同 [`parse_targets`]
+///
+/// # 返回
+/// * `Ok(u64)` - 与 [`parse_targets_iter`] 产生的IP数相同
+/// * `Err` - 解析失败
+pub fn count_targets(targets: &str) -> Result<u64, Box<dyn Error + Send + Sync>> {
+    let ranges = merge_ranges(parse_target_ranges(targets, false)?);
+    Ok(ranges
+        .iter()
+        .map(|&(start, end)| u64::from(end - start) + 1)
+        .sum())
+}
+
+/// 排除地址（`--exclude`）的区间，不展开任何地址
+///
+/// 格式同 [`parse_targets`]，但CIDR及最后一段为 `*` 的写法包含网络地址和广播地址：
+/// 排除 `10.0.0.0/24` 时，以范围写入目标的 10.0.0.0 和 10.0.0.255 同样被排除。
+///
+/// # 返回
+/// * `Ok(Vec<(起始, 结束)>)` - 按地址排序、互不相交的闭区间
+/// * `Err` - 解析失败
+pub fn parse_exclude_ranges(
+    targets: &str,
+) -> Result<Vec<(u32, u32)>, Box<dyn Error + Send + Sync>> {
+    Ok(merge_ranges(parse_target_ranges(targets, true)?))
+}
+
+/// 把目标字符串解析为闭区间（按输入顺序，未去重）
+///
+/// `whole_networks` 为真时CIDR及最后一段为 `*` 的写法包含网络地址和广播地址。
+fn parse_target_ranges(
+    targets: &str,
+    whole_networks: bool,
+) -> Result<Vec<(u32, u32)>, Box<dyn Error + Send + Sync>> {
+    let mut ranges = Vec::new();
+
+    for target in targets.split(',') {
+        let target = target.trim();
+
+        if target.is_empty() {
+            continue;
+        }
+
+        if target.contains('/') {
+            // CIDR格式：192.168.1.0/24
+            ranges.push(parse_cidr(target, whole_networks)?);
+        } else if let Some(octets) = octet_pattern(target) {
+            // 按段通配：192.168.*.*、10.0.1-5.1
+            ranges.extend(parse_octet_pattern(target, &octets, whole_networks)?);
+        } else if target.contains('-') {
+            // IP范围格式：192.168.1.1-10
+            ranges.push(parse_ip_range(target)?);
+        } else {
+            // 单个IP地址
+            let ip = Ipv4Addr::from_str(target).map_err(|_| format!("无效的IP地址: {}", target))?;
+            ranges.push((u32::from(ip), u32::from(ip)));
+        }
+    }
+
+    if ranges.is_empty() {
+        return Err("未解析到任何有效的IP地址".into());
+    }
+
+    Ok(ranges)
+}
+
+/// IP地址按数值排序，无法解析的排在最后按文本排序
+pub fn ip_sort_key(ip: &str) -> (u8, std::net::IpAddr, String) {
+    match ip.parse() {
+        Ok(addr) => (0, addr, String::new()),
+        Err(_) => (1, std::net::IpAddr::from([0u8; 4]), ip.to_string()),
+    }
+}
+
+/// 合并重叠或相邻的闭区间，返回按地址排序、互不相交的区间
+fn merge_ranges(mut ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// 从闭区间中减去另一组闭区间（两组均按地址排序、互不相交），结果同样按地址排序
+fn subtract_ranges(ranges: &[(u32, u32)], removed: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut result = Vec::new();
+    for &(start, end) in ranges {
+        let (mut start, end) = (u64::from(start), u64::from(end));
+        let first = removed.partition_point(|&(_, e)| u64::from(e) < start);
+        for &(rs, re) in &removed[first..] {
+            if u64::from(rs) > end {
+                break;
+            }
+            if u64::from(rs) > start {
+                result.push((start as u32, rs - 1));
+            }
+            start = start.max(u64::from(re) + 1);
+        }
+        if start <= end {
+            result.push((start as u32, end as u32));
+        }
+    }
+    result
+}
+
+/// 是否为按段写法（四段，前三段中有 `*` 或范围，或最后一段为 `*`），是则返回各段
+fn octet_pattern(target: &str) -> Option<Vec<&str>> {
+    let octets: Vec<&str> = target.split('.').collect();
+    let pattern = octets.len() == 4
+        && (octets[3] == "*" || octets[..3].iter().any(|o| *o == "*" || o.contains('-')));
+    pattern.then_some(octets)
+}
+
+/// 从按段写法解析地址区间（每个前三段组合对应最后一段的一个区间）
+///
+/// # 参数
+/// * `target` - 原始写法（用于错误信息）
+/// * `octets` - 四段，每段为数字、`*` 或 `a-b`
+/// * `whole_networks` - 最后一段为 `*` 时是否包含 .0 和 .255
+///
+/// # 返回
+/// * `Ok(Vec<(起始, 结束)>)` - 地址闭区间
+/// * `Err` - 某段无效
+fn parse_octet_pattern(
+    target: &str,
+    octets: &[&str],
+    whole_networks: bool,
+) -> Result<Vec<(u32, u32)>, Box<dyn Error + Send + Sync>> {
+    let mut bounds = [(0u8, 0u8); 4];
+    for (i, octet) in octets.iter().enumerate() {
+        let octet = octet.trim();
+        bounds[i] = if octet == "*" {
+            // 最后一段通配时与CIDR一致，不含网络地址和广播地址
+            if i == 3 && !whole_networks {
+                (1, 254)
+            } else {
+                (0, 255)
+            }
+        } else {
+            let (low, high) = octet.split_once('-').unwrap_or((octet, octet));
+            let parse = |s: &str| {
+                s.trim()
+                    .parse::<u8>()
+                    .map_err(|_| format!("无效的IP地址段: {}（{}）", s.trim(), target))
+            };
+            let (low, high) = (parse(low)?, parse(high)?);
+            if high < low {
+                return Err(format!("IP地址段范围无效: {}（{}）", octet, target).into());
+            }
+            (low, high)
+        };
+    }
+
+    let mut ranges = Vec::new();
+    for a in bounds[0].0..=bounds[0].1 {
+        for b in bounds[1].0..=bounds[1].1 {
+            for c in bounds[2].0..=bounds[2].1 {
+                let start = Ipv4Addr::new(a, b, c, bounds[3].0);
+                let end = Ipv4Addr::new(a, b, c, bounds[3].1);
+                ranges.push((u32::from(start), u32::from(end)));
+            }
+        }
+    }
+    Ok(ranges)
+}
+
+/// 从CIDR格式解析主机地址区间
+///
+/// # 参数
+/// * `cidr` - CIDR格式字符串，如 "192.168.1.0/24"
+/// * `whole_network` - 是否包含网络地址和广播地址（用于排除整个网段）
+///
+/// # 返回
+/// * `Ok((起始, 结束))` - 主机地址闭区间（不包含网络地址和广播地址；/32 为该地址本身，/31 为点对点链路的两个地址）
+/// * `Err` - 解析失败
+fn parse_cidr(cidr: &str, whole_network: bool) -> Result<(u32, u32), Box<dyn Error + Send + Sync>> {
+    // 分割IP和子网掩码
+    let parts: Vec<&str> = cidr.split('/').collect();
+    if parts.len() != 2 {
+        return Err(format!("无效的CIDR格式: {}", cidr).into());
+    }
+
+    let ip_str = parts[0];
+    let prefix_len_str = parts[1];
+
+    // 解析IP和子网掩码长度
+    let ip = Ipv4Addr::from_str(ip_str).map_err(|_| format!("CIDR中的IP地址无效: {}", ip_str))?;
+    let prefix_len: u8 = prefix_len_str
+        .parse()
+        .map_err(|_| format!("无效的子网掩码长度: {}", prefix_len_str))?;
+
+    if prefix_len > 32 {
+        return Err("子网掩码长度不能超过32".into());
+    }
+
+    // 将IP转换为u32整数（方便计算）
+    let ip_int = u32::from(ip);
+
+    // /32 即单个主机
+    if prefix_len == 32 {
+        return Ok((ip_int, ip_int));
+    }
+
+    // 计算子网掩码的整数形式
+    let mask = if prefix_len == 0 {
+        0u32
+    } else {
+        u32::MAX << (32 - prefix_len)
+    };
+
+    // 计算网络地址（IP & 子网掩码）
+    let network_int = ip_int & mask;
+    // 计算广播地址（网络地址 | 反掩码）
+    let broadcast_int = network_int | !mask;
+
+    // /31 为点对点链路（RFC 3021），两个地址都是主机
+    if prefix_len == 31 || whole_network {
+        return Ok((network_int, broadcast_int));
+    }
+
+    // 避免溢出：/30 及更大的网段至少有两个主机地址
+    if broadcast_int
+        .checked_sub(network_int)
+        .is_none_or(|span| span < 2)
+    {
+        return Err(format!("CIDR {} 没有可用的主机IP", cidr).into());
+    }
+
+    // 网络地址+1 到 广播地址-1（可用IP范围）
+    Ok((network_int + 1, broadcast_int - 1))
+}
+
+/// 从IP范围格式解析地址区间
+///
+/// # 参数
+/// * `range_str` - IP范围字符串，如 "192.168.1.1-10" 或跨网段的 "192.168.1.200-192.168.2.50"
+///
+/// # 返回
+/// * `Ok((起始, 结束))` - 地址闭区间
+/// * `Err` - 解析失败
+fn parse_ip_range(range_str: &str) -> Result<(u32, u32), Box<dyn Error + Send + Sync>> {
+    let dash_pos = range_str
+        .rfind('-')
+        .ok_or_else(|| format!("无效的IP范围格式: {}", range_str))?;
+
+    let (base, end) = range_str.split_at(dash_pos);
+    let base_ip =
+        Ipv4Addr::from_str(base.trim()).map_err(|_| format!("无效的起始IP地址: {}", base))?;
+
+    // 结束值为完整IP，或只写最后一段
+    let end_part = end[1..].trim();
+    let end_ip = if end_part.contains('.') {
+        Ipv4Addr::from_str(end_part).map_err(|_| format!("无效的结束IP地址: {}", end_part))?
+    } else {
+        let last = end_part
+            .parse::<u32>()
+            .map_err(|_| format!("IP范围结束值无效: {}", end_part))?;
+        let last =
+            u8::try_from(last).map_err(|_| format!("IP范围结束值({})超出范围（0-255）", last))?;
+        let [a, b, c, _] = base_ip.octets();
+        Ipv4Addr::new(a, b, c, last)
+    };
+
+    // 转换为u32整数，跨网段时按地址顺序遍历
+    let start = u32::from(base_ip);
+    let end = u32::from(end_ip);
+    if end < start {
+        return Err(format!("IP范围结束值({})必须大于或等于起始值({})", end_ip, base_ip).into());
+    }
+
+    Ok((start, end))
+}
+
+/// 默认输出根目录
+pub const DEFAULT_OUTPUT_ROOT: &str = "output";
+
+static OUTPUT_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// 设置全局输出根目录（仅首次设置生效，应在程序启动时调用）
+pub fn set_output_root(path: PathBuf) {
+    let _ = OUTPUT_ROOT.set(path);
+}
+
+/// 获取全局输出根目录，未设置时为 [`DEFAULT_OUTPUT_ROOT`]
+pub fn output_root() -> &'static Path {
+    OUTPUT_ROOT.get_or_init(|| PathBuf::from(DEFAULT_OUTPUT_ROOT))
+}
+
+static FLAT_OUTPUT: OnceLock<bool> = OnceLock::new();
+
+/// 设置是否使用按模块平铺的旧输出结构（仅首次设置生效，应在程序启动时调用）
+pub fn set_flat_output(flat: bool) {
+    let _ = FLAT_OUTPUT.set(flat);
+}
+
+/// 是否使用按模块平铺的旧输出结构（`<输出根目录>/<模块>/`），默认每次运行单独建目录
+pub fn flat_output() -> bool {
+    *FLAT_OUTPUT.get_or_init(|| false)
+}
+
+static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 设置全局配置目录（仅首次设置生效，应在程序启动时调用）
+pub fn set_config_dir(path: PathBuf) {
+    let _ = CONFIG_DIR.set(path);
+}
+
+/// 获取全局配置目录（保存配置档等），未设置时为系统的用户配置目录
+///
+/// Windows下为 `%APPDATA%\gxtools`，其他系统为 `$XDG_CONFIG_HOME/gxtools`
+/// 或 `~/.config/gxtools`，均无法确定时为当前目录下的 `.gxtools`。
+pub fn config_dir() -> &'static Path {
+    CONFIG_DIR.get_or_init(default_config_dir)
+}
+
+fn default_config_dir() -> PathBuf {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+    };
+    base.map(|b| b.join("gxtools"))
+        .unwrap_or_else(|| PathBuf::from(".gxtools"))
+}
+
+/// 配置文件名（位于配置目录下）
+pub const CONFIG_FILE_NAME: &str = "config.yaml";
+
+/// 配置文件路径
+pub fn config_file() -> PathBuf {
+    config_dir().join(CONFIG_FILE_NAME)
+}
+
+/// 读取配置文件中的一个段落
+///
+/// # 参数
+/// * `path` - 配置文件路径
+/// * `key` - 段落名（如 "honeypot"）
+///
+/// # 返回
+/// * `Ok(T)` - 段落内容，文件或段落不存在时为默认值
+/// * `Err` - 文件无法读取或格式错误
+pub fn load_config_section<T: serde::de::DeserializeOwned + Default>(
+    path: &Path,
+    key: &str,
+) -> Result<T, Box<dyn Error + Send + Sync>> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("无法读取配置文件 {}: {}", path.display(), e))?;
+    let root: serde_yaml::Value = serde_yaml::from_str(&content)
+        .map_err(|e| format!("配置文件 {} 格式错误: {}", path.display(), e))?;
+    match root.get(key) {
+        Some(section) if !section.is_null() => {
+            serde_yaml::from_value(section.clone()).map_err(|e| {
+                format!("配置文件 {} 中的 {} 段落无效: {}", path.display(), key, e).into()
+            })
+        }
+        _ => Ok(T::default()),
+    }
+}
+
+/// Excel导出选项
+#[derive(Debug, Clone)]
+pub struct ExcelOptions {
+    /// 输出根目录（默认为全局输出根目录）
+    pub output_root: PathBuf,
+    /// 是否对单元格内容做安全处理（默认开启，仅对可信数据关闭）
+    pub sanitize: bool,
+    /// 追加在结果表之后的工作表（如主机汇总）
+    pub extra_sheets: Vec<ExcelSheet>,
+    /// 本次运行的工作目录（设置后文件直接写入运行目录并登记到产物索引）
+    pub run_dir: Option<Arc<RunDir>>,
+    /// 指定文件名（默认为 `<前缀>_<时间戳>.xlsx`，如中间结果快照）
+    pub file_name: Option<String>,
+    /// 不打印保存路径（由调用方自行输出）
+    pub quiet: bool,
+}
+
+/// 附加工作表
+#[derive(Debug, Clone)]
+pub struct ExcelSheet {
+    /// 工作表名称
+    pub name: String,
+    /// 表头
+    pub headers: Vec<String>,
+    /// 数据行
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Default for ExcelOptions {
+    fn default() -> Self {
+        Self {
+            output_root: output_root().to_path_buf(),
+            sanitize: true,
+            extra_sheets: Vec::new(),
+            run_dir: None,
+            file_name: None,
+            quiet: false,
+        }
+    }
+}
+
+/// 运行元数据工作表名称
+pub const META_SHEET_NAME: &str = "项目信息";
+
+/// Excel单元格最大字符数
+pub const EXCEL_MAX_CELL_CHARS: usize = 32_767;
+
+/// 单个工作表最多写入的数据行数（Excel行数上限1048576减去表头行）
+pub const EXCEL_MAX_DATA_ROWS: usize = 1_048_575;
+
+/// 超长单元格截断后追加的标记
+const TRUNCATED_MARKER: &str = "…[已截断]";
+
+/// 对写入表格的单元格内容做安全处理
+///
+/// - 去除控制字符（保留制表符和换行）
+/// - 以 `= + - @ \t` 开头的内容前加 `'`，防止被当作公式执行
+/// - 超过Excel单元格上限（32767字符）时截断并追加截断标记
+///
+/// # 参数
+/// * `value` - 原始内容
+///
+/// # 返回
+/// * `String` - 处理后的内容
+///
+/// # 示例
+/// ```ignore
+/// assert_eq!(sanitize_cell("=1+1"), "'=1+1");
+/// ```
+pub fn sanitize_cell(value: &str) -> String {
+    sanitized(value).into_owned()
+}
+
+/// 同 [`sanitize_cell`]，无需处理时不复制（导出大量单元格时使用）
+fn sanitized(value: &str) -> Cow<'_, str> {
+    let clean = !value.starts_with(['=', '+', '-', '@', '\t'])
+        && !value
+            .chars()
+            .any(|c| c.is_control() && c != '\t' && c != '\n')
+        && value.len() <= EXCEL_MAX_CELL_CHARS;
+    if clean {
+        return Cow::Borrowed(value);
+    }
+
+    let mut cleaned: String = value
+        .chars()
+        .filter(|&c| !c.is_control() || c == '\t' || c == '\n')
+        .collect();
+
+    if cleaned.starts_with(['=', '+', '-', '@', '\t']) {
+        cleaned.insert(0, '\'');
+    }
+
+    if cleaned.chars().count() > EXCEL_MAX_CELL_CHARS {
+        let keep = EXCEL_MAX_CELL_CHARS - TRUNCATED_MARKER.chars().count();
+        cleaned = cleaned.chars().take(keep).collect();
+        cleaned.push_str(TRUNCATED_MARKER);
+    }
+
+    Cow::Owned(cleaned)
+}
+
+/// 紧凑存储的表格数据行
+///
+/// 全部单元格的文本连续存放在一个字符串中，只记录每个单元格和每行的结束位置，
+/// 导出几十万行结果时不必为每行、每个单元格单独分配内存。
+#[derive(Debug, Clone, Default)]
+pub struct PackedRows {
+    text: String,
+    cell_ends: Vec<usize>,
+    row_ends: Vec<usize>,
+}
+
+impl PackedRows {
+    /// 行数
+    pub fn len(&self) -> usize {
+        self.row_ends.len()
+    }
+
+    /// 是否没有数据行
+    pub fn is_empty(&self) -> bool {
+        self.row_ends.is_empty()
+    }
+
+    /// 追加一行，由 `write` 逐个写入单元格
+    ///
+    /// # 参数
+    /// * `headers` - 表头（脱敏时按列名决定处理方式）
+    /// * `redaction` - 导出脱敏及各列是否保留（未开启脱敏时为 `None`）
+    /// * `write` - 写入本行的单元格
+    pub fn push_row(
+        &mut self,
+        headers: &[&str],
+        redaction: Option<(&Redactor, &[bool])>,
+        write: impl FnOnce(&mut RowWriter),
+    ) {
+        let mut row = RowWriter {
+            rows: self,
+            headers,
+            redaction,
+            col: 0,
+            scratch: String::new(),
+        };
+        write(&mut row);
+        self.row_ends.push(self.cell_ends.len());
+    }
+
+    /// 第 `index` 行的单元格
+    pub fn row(&self, index: usize) -> impl Iterator<Item = &str> {
+        let first = index.checked_sub(1).map_or(0, |i| self.row_ends[i]);
+        let mut start = first.checked_sub(1).map_or(0, |i| self.cell_ends[i]);
+        self.cell_ends[first..self.row_ends[index]]
+            .iter()
+            .map(move |&end| {
+                let cell = &self.text[start..end];
+                start = end;
+                cell
+            })
+    }
+
+    /// 全部数据行
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = &str>> {
+        (0..self.len()).map(|i| self.row(i))
+    }
+}
+
+/// 逐个写入一行中的单元格（导出时的行映射函数使用）
+///
+/// 单元格按 [`fmt::Display`] 写入，数字等不必先转为字符串；开启导出脱敏时写入即脱敏。
+pub struct RowWriter<'a> {
+    rows: &'a mut PackedRows,
+    headers: &'a [&'a str],
+    redaction: Option<(&'a Redactor, &'a [bool])>,
+    col: usize,
+    scratch: String,
+}
+
+impl RowWriter<'_> {
+    /// 写入下一个单元格
+    pub fn cell(&mut self, value: impl fmt::Display) -> &mut Self {
+        let col = self.col;
+        self.col += 1;
+        let text = &mut self.rows.text;
+        match self.redaction {
+            None => {
+                let _ = write!(text, "{}", value);
+            }
+            Some((redactor, keep)) => {
+                if !keep.get(col).copied().unwrap_or(true) {
+                    return self;
+                }
+                self.scratch.clear();
+                let _ = write!(self.scratch, "{}", value);
+                text.push_str(&redactor.cell(self.headers.get(col).copied(), &self.scratch));
+            }
+        }
+        self.rows.cell_ends.push(text.len());
+        self
+    }
+
+    /// 依次写入多个单元格
+    pub fn cells<I>(&mut self, values: I) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: fmt::Display,
+    {
+        for value in values {
+            self.cell(value);
+        }
+        self
+    }
+}
+
+/// 将数据保存到Excel文件
+///
+/// 使用默认选项（输出到 output 目录，单元格内容做安全处理），
+/// 见 [`save_to_excel_with_options`]
+///
+/// # 类型参数
+/// * `T` - 数据项类型
+/// * `F` - 行映射函数类型
+///
+/// # 参数
+/// * `data` - 要保存的数据切片
+/// * `headers` - 表头列表
+/// * `row_mapper` - 将数据项映射为字符串向量的函数
+/// * `kind` - 输出子目录及文件名前缀
+///
+/// # 返回
+/// * `Ok(String)` - 保存的文件路径
+/// * `Err` - 保存失败
+///
+/// # 示例
+/// ```ignore
+/// save_to_excel(
+///     &results,
+///     &["IP", "状态"],
+///     |r| vec![r.ip.clone(), r.status.clone()],
+///     OutputKind::PING,
+/// )?;
+/// ```
+pub fn save_to_excel<T, F>(
+    data: &[T],
+    headers: &[&str],
+    row_mapper: F,
+    kind: OutputKind,
+) -> Result<String, Box<dyn Error + Send + Sync>>
+where
+    F: Fn(&T) -> Vec<String>,
+{
+    save_to_excel_with_options(data, headers, row_mapper, kind, &ExcelOptions::default())
+}
+
+/// 按指定选项将数据保存到Excel文件
+///
+/// 默认文件名为 `<前缀>_<时间戳>.xlsx`，同一秒内已有同名文件时追加序号，不会覆盖已有结果；
+/// 通过 `file_name` 指定的文件名（如中间结果快照）按原样覆盖写入。
+///
+/// 超过单个工作表行数上限的表格依次续写到后续工作表（见 [`write_split_sheets`]）。
+///
+/// 写入失败（磁盘已满、目录无权限等）时，全部工作表转存为
+/// 系统临时目录下的CSV文件（见 [`salvage::salvage_sheets`]），返回转存的结果表路径，
+/// 扫描结果不会因导出失败而丢失。
+///
+/// # 参数
+/// * `options` - 导出选项（输出根目录、是否做单元格安全处理、附加工作表、运行目录）
+///
+/// 其余参数同 [`save_to_excel`]
+pub fn save_to_excel_with_options<T, F>(
+    data: &[T],
+    headers: &[&str],
+    row_mapper: F,
+    kind: OutputKind,
+    options: &ExcelOptions,
+) -> Result<String, Box<dyn Error + Send + Sync>>
+where
+    F: Fn(&T) -> Vec<String>,
+{
+    ExcelExport::prepare(data, headers, row_mapper, kind, options).run()
+}
+
+/// Excel导出阶段
+///
+/// 准备时按脱敏规则整理好全部工作表（结果表逐个单元格写入 [`PackedRows`]），
+/// 生成和写出文件在执行时进行；扫描中途导出时通过 [`run_stage`](blocking::run_stage)
+/// 放到阻塞线程池，不占用异步运行时。
+///
+/// 工作表以常量内存模式写出：逐行写入临时文件，生成文件所需内存与行数无关。
+pub struct ExcelExport {
+    sheets: Vec<ResultSheet>,
+    kind: OutputKind,
+    options: ExcelOptions,
+}
+
+/// 整理好的结果表（名称为 `None` 时使用默认名称）
+struct ResultSheet {
+    name: Option<String>,
+    headers: Vec<String>,
+    rows: PackedRows,
+}
+
+impl ResultSheet {
+    /// 按脱敏规则整理一组结果
+    fn pack<T, F>(name: Option<&str>, headers: &[&str], data: &[T], row_writer: &F) -> Self
+    where
+        F: Fn(&T, &mut RowWriter),
+    {
+        let redactor = redactor();
+        let keep = redactor.map(|r| r.kept_columns(headers));
+        let redaction = redactor.zip(keep.as_deref());
+        let mut rows = PackedRows::default();
+        for item in data {
+            rows.push_row(headers, redaction, |row| row_writer(item, row));
+        }
+        let headers = headers
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| keep.as_ref().is_none_or(|k| k[*i]))
+            .map(|(_, h)| h.to_string())
+            .collect();
+        Self {
+            name: name.map(str::to_string),
+            headers,
+            rows,
+        }
+    }
+}
+
+impl ExcelExport {
+    /// 准备导出（参数同 [`save_to_excel_with_options`]）
+    pub fn prepare<T, F>(
+        data: &[T],
+        headers: &[&str],
+        row_mapper: F,
+        kind: OutputKind,
+        options: &ExcelOptions,
+    ) -> Self
+    where
+        F: Fn(&T) -> Vec<String>,
+    {
+        Self::prepare_with(
+            data,
+            headers,
+            |item, row| {
+                row.cells(row_mapper(item));
+            },
+            kind,
+            options,
+        )
+    }
+
+    /// 准备导出，行映射函数逐个写入单元格（结果行数很多时使用，不为每行生成字符串向量）
+    ///
+    /// # 示例
+    /// ```ignore
+    /// ExcelExport::prepare_with(
+    ///     &results,
+    ///     &["IP", "端口"],
+    ///     |r, row| {
+    ///         row.cell(&r.ip).cell(r.port);
+    ///     },
+    ///     OutputKind::PORTSCAN,
+    ///     &options,
+    /// );
+    /// ```
+    pub fn prepare_with<T, F>(
+        data: &[T],
+        headers: &[&str],
+        row_writer: F,
+        kind: OutputKind,
+        options: &ExcelOptions,
+    ) -> Self
+    where
+        F: Fn(&T, &mut RowWriter),
+    {
+        let sheet = ResultSheet::pack(None, headers, data, &row_writer);
+        Self::assemble(vec![sheet], kind, options)
+    }
+
+    /// 准备导出，结果分组写入同一工作簿的多个工作表（如存活、未存活主机分表）
+    ///
+    /// 每组为 `(工作表名称, 表头, 数据)`，按顺序写在附加工作表之前；行映射函数同 [`prepare_with`](Self::prepare_with)。
+    ///
+    /// # 示例
+    /// ```ignore
+    /// ExcelExport::prepare_groups(
+    ///     &[("存活", &headers, &alive), ("未存活", &headers, &dead)],
+    ///     |r, row| {
+    ///         row.cell(&r.ip).cell(&r.status);
+    ///     },
+    ///     OutputKind::PING,
+    ///     &options,
+    /// );
+    /// ```
+    pub fn prepare_groups<T, F>(
+        groups: &[(&str, &[&str], &[T])],
+        row_writer: F,
+        kind: OutputKind,
+        options: &ExcelOptions,
+    ) -> Self
+    where
+        F: Fn(&T, &mut RowWriter),
+    {
+        let sheets = groups
+            .iter()
+            .map(|(name, headers, data)| ResultSheet::pack(Some(name), headers, data, &row_writer))
+            .collect();
+        Self::assemble(sheets, kind, options)
+    }
+
+    /// 按脱敏规则整理附加工作表
+    fn assemble(sheets: Vec<ResultSheet>, kind: OutputKind, options: &ExcelOptions) -> Self {
+        let redactor = redactor();
+        let extra_sheets = options
+            .extra_sheets
+            .iter()
+            .map(|sheet| match redactor {
+                Some(r) => {
+                    let headers: Vec<&str> = sheet.headers.iter().map(String::as_str).collect();
+                    let (headers, rows) = r.table(&headers, sheet.rows.iter().cloned());
+                    ExcelSheet {
+                        name: sheet.name.clone(),
+                        headers,
+                        rows,
+                    }
+                }
+                None => sheet.clone(),
+            })
+            .collect();
+        Self {
+            sheets,
+            kind,
+            options: ExcelOptions {
+                extra_sheets,
+                ..options.clone()
+            },
+        }
+    }
+}
+
+impl BlockingStage for ExcelExport {
+    type Output = String;
+    const NAME: &'static str = "Excel导出";
+
+    /// 写出文件，失败时转存（见 [`save_to_excel_with_options`]）
+    fn run(self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let Self {
+            sheets,
+            kind,
+            options,
+        } = self;
+        let started = Instant::now();
+        let total_rows: usize = sheets.iter().map(|sheet| sheet.rows.len()).sum();
+        let mut filepath = None;
+        let filepath = match write_workbook(&sheets, kind, &options, &mut filepath) {
+            Ok(path) => path,
+            Err(e) => {
+                let results = sheets.into_iter().map(|sheet| ExcelSheet {
+                    name: sheet.name.unwrap_or_else(|| kind.prefix.to_string()),
+                    headers: sheet.headers,
+                    rows: sheet
+                        .rows
+                        .rows()
+                        .map(|row| row.map(str::to_string).collect())
+                        .collect(),
+                });
+                let sheets: Vec<ExcelSheet> = results.chain(options.extra_sheets).collect();
+                let salvaged = salvage::salvage_sheets(
+                    kind.prefix,
+                    &sheets,
+                    &e.to_string(),
+                    filepath.as_deref(),
+                )?;
+                return Ok(salvaged[0].to_string_lossy().to_string());
+            }
+        };
+        save_export_mapping()?;
+        if let Some(ref run) = options.run_dir {
+            run.record(&filepath, "xlsx", total_rows)?;
+        }
+        if !options.quiet {
+            println!("{} 结果已保存至: {}", Icon::Ok, filepath.display());
+        }
+        if console::verbose() {
+            let elapsed = started.elapsed();
+            println!(
+                "   导出 {} 行，耗时 {}（{:.0} 行/秒）",
+                total_rows,
+                format_duration(elapsed),
+                total_rows as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+            );
+        }
+        Ok(filepath.to_string_lossy().to_string())
+    }
+}
+
+/// 写出Excel文件（前面的工作表为结果表，其余为附加工作表）
+///
+/// `filepath` 在确定文件路径后立即设置，写入中途失败时调用方据此清理未完成的文件。
+fn write_workbook(
+    sheets: &[ResultSheet],
+    kind: OutputKind,
+    options: &ExcelOptions,
+    filepath: &mut Option<PathBuf>,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let output_dir = match options.run_dir {
+        Some(ref run) => run.ensure()?.to_path_buf(),
+        None => ensure_output_dir(&options.output_root.join(kind.subdir).to_string_lossy())?,
+    };
+
+    let path = match options.file_name {
+        Some(ref name) => output_dir.join(name),
+        None => {
+            let stem = format!("{}_{}", kind.prefix, Local::now().format("%Y%m%d_%H%M%S"));
+            reserve_unique_path(&output_dir, &stem, "xlsx")?
+        }
+    };
+    *filepath = Some(path.clone());
+
+    let mut workbook = Workbook::new();
+    // 未分组的结果表使用默认名称
+    for sheet in sheets {
+        write_split_sheets(
+            &mut workbook,
+            sheet.name.as_deref(),
+            &sheet.headers,
+            sheet.rows.rows(),
+            options.sanitize,
+            EXCEL_MAX_DATA_ROWS,
+        )?;
+    }
+    for sheet in &options.extra_sheets {
+        write_split_sheets(
+            &mut workbook,
+            Some(&sheet.name),
+            &sheet.headers,
+            sheet.rows.iter(),
+            options.sanitize,
+            EXCEL_MAX_DATA_ROWS,
+        )?;
+    }
+    // 运行元数据（项目名称、授权文件编号等）单独一个工作表，交付的文件可追溯到项目
+    let run_meta = meta::current();
+    let run_meta = meta::ordered(&run_meta);
+    if !run_meta.is_empty() {
+        let worksheet = workbook.add_worksheet_with_constant_memory();
+        worksheet.set_name(META_SHEET_NAME)?;
+        write_sheet(
+            worksheet,
+            &["项目", "内容"],
+            run_meta.iter().map(|(label, value)| [*label, *value]),
+            options.sanitize,
+        )?;
+    }
+    workbook.save(&path)?;
+    Ok(path)
+}
+
+/// 将表格写入指定路径的Excel文件（单元格内容做安全处理，不经过导出脱敏）
+///
+/// # 参数
+/// * `path` - 文件路径
+/// * `headers` - 表头
+/// * `rows` - 数据行
+pub fn save_table_to_excel(
+    path: &Path,
+    headers: &[&str],
+    rows: impl Iterator<Item = Vec<String>>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut workbook = Workbook::new();
+    write_split_sheets(
+        &mut workbook,
+        None,
+        headers,
+        rows,
+        true,
+        EXCEL_MAX_DATA_ROWS,
+    )?;
+    workbook.save(path)?;
+    Ok(())
+}
+
+/// 写入一个表格，超过 `max_rows` 行时依次续写到新的工作表（每个工作表都带表头）
+///
+/// 续写的工作表名称为 `名称(2)`、`名称(3)`……，未指定名称时使用默认名称。
+///
+/// # 参数
+/// * `workbook` - 工作簿
+/// * `name` - 工作表名称（为 `None` 时使用默认名称）
+/// * `headers` - 表头
+/// * `rows` - 数据行
+/// * `sanitize` - 是否对单元格内容做安全处理
+/// * `max_rows` - 每个工作表最多写入的数据行数
+pub fn write_split_sheets<H, R>(
+    workbook: &mut Workbook,
+    name: Option<&str>,
+    headers: &[H],
+    rows: impl Iterator<Item = R>,
+    sanitize: bool,
+    max_rows: usize,
+) -> Result<(), XlsxError>
+where
+    H: AsRef<str>,
+    R: IntoIterator,
+    R::Item: AsRef<str>,
+{
+    let mut rows = rows.peekable();
+    let mut part = 1;
+    loop {
+        let worksheet = workbook.add_worksheet_with_constant_memory();
+        if let Some(name) = name {
+            worksheet.set_name(split_sheet_name(name, part))?;
+        }
+        write_sheet(
+            worksheet,
+            headers,
+            rows.by_ref().take(max_rows.max(1)),
+            sanitize,
+        )?;
+        if rows.peek().is_none() {
+            return Ok(());
+        }
+        part += 1;
+    }
+}
+
+/// 续写工作表的名称（第一个为原名；工作表名称最长31个字符，续写时截短原名）
+///
+/// # 参数
+/// * `name` - 原名
+/// * `part` - 第几个工作表（从1开始）
+pub fn split_sheet_name(name: &str, part: usize) -> String {
+    if part == 1 {
+        return name.to_string();
+    }
+    let suffix = format!("({})", part);
+    let base: String = name.chars().take(31 - suffix.chars().count()).collect();
+    format!("{}{}", base, suffix)
+}
+
+/// 向工作表逐行写入表头和数据行
+fn write_sheet<H, R>(
+    worksheet: &mut rust_xlsxwriter::Worksheet,
+    headers: &[H],
+    rows: impl Iterator<Item = R>,
+    sanitize: bool,
+) -> Result<(), XlsxError>
+where
+    H: AsRef<str>,
+    R: IntoIterator,
+    R::Item: AsRef<str>,
+{
+    // 表头格式
+    let header_format = Format::new().set_bold();
+
+    // 写入表头
+    for (col, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(
+            0,
+            ColNum::from(col as u16),
+            header.as_ref(),
+            &header_format,
+        )?;
+    }
+
+    // 写入数据
+    for (i, row_data) in rows.enumerate() {
+        for (j, value) in row_data.into_iter().enumerate() {
+            let value = value.as_ref();
+            let value = if sanitize {
+                sanitized(value)
+            } else {
+                Cow::Borrowed(value)
+            };
+            worksheet.write_string((i + 1) as u32, ColNum::from(j as u16), value)?;
+        }
+    }
+    Ok(())
+}
+
+/// 把有序的端口列表合并为连续的范围（如 `[1, 2, 3, 5]` 为 `[(1, 3), (5, 5)]`）
+///
+/// # 参数
+/// * `ports` - 已排序去重的端口列表
+pub fn port_ranges(ports: &[u16]) -> Vec<(u16, u16)> {
+    let mut ranges: Vec<(u16, u16)> = Vec::new();
+    for &port in ports {
+        match ranges.last_mut() {
+            Some((_, end)) if end.checked_add(1) == Some(port) => *end = port,
+            _ => ranges.push((port, port)),
+        }
+    }
+    ranges
+}
+
+/// 单个端口范围的写法（如 `80`、`8000-9000`）
+pub fn format_port_range((start, end): (u16, u16)) -> String {
+    if start == end {
+        start.to_string()
+    } else {
+        format!("{}-{}", start, end)
+    }
+}
+
+/// 把端口列表压缩为范围写法（如 `1-1024,3389`，即 nmap 及 -p 参数的写法）
+///
+/// # 参数
+/// * `ports` - 已排序去重的端口列表
+pub fn compress_ports(ports: &[u16]) -> String {
+    port_ranges(ports)
+        .into_iter()
+        .map(format_port_range)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// 解析端口字符串（宽松模式），支持单个端口、范围、排除项和混合格式
+///
+/// 支持的格式：
+/// - 单个端口: `80`
+/// - 多个端口: `80,443,8080`
+/// - 端口范围: `80-90`
+/// - 开放式范围: `8000-`（至65535）
+/// - 全部端口: `all`（1-65535）
+/// - 排除项: `!135-139`、`!445`、`!all`（以 `!` 开头）
+/// - 混合格式: `all,!135-139,!445`
+///
+/// 先合并全部包含项，再减去排除项，结果排序去重。
+/// 无效项会打印警告并跳过，需要在出错时立即失败请使用 [`parse_ports_strict`]
+///
+/// # 参数
+/// * `port_str` - 端口字符串
+///
+/// # 返回
+/// * `(Vec<u16>, usize)` - 解析后的端口列表（已排序去重）及被跳过的无效项数量
+///
+/// # 示例
+/// ```ignore
+/// let (ports, skipped) = parse_ports("22,80-443,8080");
+/// ```
+pub fn parse_ports(port_str: &str) -> (Vec<u16>, usize) {
+    let (ports, errors) = resolve_port_spec(port_str);
+    for e in &errors {
+        eprintln!("⚠️  端口参数无效 {}", e);
+    }
+    (ports, errors.len())
+}
+
+/// 解析端口字符串（严格模式），任一项无效即返回错误
+///
+/// 语法与 [`parse_ports`] 相同，错误中会列出全部无效项及其位置和原因
+/// （非数字、超出范围、范围反转、端口0）；空输入或排除后无剩余端口同样视为错误
+///
+/// # 参数
+/// * `port_str` - 端口字符串
+///
+/// # 返回
+/// * `Ok(Vec<u16>)` - 解析后的端口列表（已排序去重）
+/// * `Err(GxError::InvalidPorts)` - 存在无效项
+///
+/// # 示例
+/// ```ignore
+/// let ports = parse_ports_strict("all,!135-139,!445")?;
+/// ```
+pub fn parse_ports_strict(port_str: &str) -> Result<Vec<u16>, GxError> {
+    let (ports, mut errors) = resolve_port_spec(port_str);
+
+    if errors.is_empty() && ports.is_empty() {
+        let has_inclusion = port_str
+            .split(',')
+            .map(str::trim)
+            .any(|t| !t.is_empty() && !t.starts_with('!'));
+        errors.push(PortSpecError {
+            position: 0,
+            token: String::new(),
+            reason: if has_inclusion {
+                PortErrorReason::AllExcluded
+            } else {
+                PortErrorReason::Empty
+            },
+        });
+    }
+
+    if !errors.is_empty() {
+        return Err(GxError::InvalidPorts(errors));
+    }
+
+    Ok(ports)
+}
+
+/// 解析端口描述：先合并包含项，再减去排除项
+///
+/// # 返回
+/// * `(Vec<u16>, Vec<PortSpecError>)` - 有效端口（已排序去重）及全部无效项
+fn resolve_port_spec(port_str: &str) -> (Vec<u16>, Vec<PortSpecError>) {
+    let mut included = vec![false; u16::MAX as usize + 1];
+    let mut excluded = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, part) in port_str.split(',').enumerate() {
+        let part = part.trim();
+
+        if part.is_empty() {
+            continue;
+        }
+
+        let (is_exclusion, token) = match part.strip_prefix('!') {
+            Some(rest) => (true, rest.trim()),
+            None => (false, part),
+        };
+
+        match parse_port_token(token) {
+            Ok((start, end)) if is_exclusion => excluded.push((start, end)),
+            Ok((start, end)) => included[start as usize..=end as usize].fill(true),
+            Err(reason) => errors.push(PortSpecError {
+                position: index + 1,
+                token: part.to_string(),
+                reason,
+            }),
+        }
+    }
+
+    for (start, end) in excluded {
+        included[start as usize..=end as usize].fill(false);
+    }
+
+    let ports = (1..=u16::MAX).filter(|&p| included[p as usize]).collect();
+    (ports, errors)
+}
+
+/// 解析单个端口参数项（单个端口、范围、开放式范围或 `all`）
+///
+/// # 返回
+/// * `Ok((start, end))` - 端口闭区间，单个端口时 start == end
+/// * `Err(PortErrorReason)` - 无效原因
+fn parse_port_token(token: &str) -> Result<(u16, u16), PortErrorReason> {
+    if token.eq_ignore_ascii_case("all") {
+        return Ok((1, u16::MAX));
+    }
+
+    match token.split_once('-') {
+        Some((start_str, end_str)) => {
+            let start = parse_port_number(start_str.trim())?;
+            let end = match end_str.trim() {
+                // 开放式范围：8000- 表示 8000-65535
+                "" => u16::MAX,
+                s => parse_port_number(s)?,
+            };
+            if start > end {
+                return Err(PortErrorReason::ReversedRange);
+            }
+            Ok((start, end))
+        }
+        None => {
+            let port = parse_port_number(token)?;
+            Ok((port, port))
+        }
+    }
+}
+
+/// 解析单个端口号，区分非数字、超出范围和端口0
+fn parse_port_number(s: &str) -> Result<u16, PortErrorReason> {
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+        return Err(PortErrorReason::NonNumeric);
+    }
+    match s.parse::<u32>() {
+        Ok(0) => Err(PortErrorReason::ZeroPort),
+        Ok(n) if n <= u16::MAX as u32 => Ok(n as u16),
+        _ => Err(PortErrorReason::OutOfRange),
+    }
+}
+
+/// 格式化字节大小为人类可读格式
+///
+/// 整数值不带小数（如 "1 KB"），非整数值保留两位小数（如 "1.50 KB"）
+///
+/// # 参数
+/// * `bytes` - 字节数
+///
+/// # 返回
+/// * `String` - 格式化后的字符串，如 "1.50 MB"
+///
+/// # 示例
+/// ```ignore
+/// let size = format_bytes(1048576); // "1 MB"
+/// ```
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit_index = 0;
+
+    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit_index += 1;
+    }
+
+    if size.fract() == 0.0 {
+        format!("{} {}", size as u64, UNITS[unit_index])
+    } else {
+        format!("{:.2} {}", size, UNITS[unit_index])
+    }
+}
+
+/// 界面语言（影响时长等格式化输出）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Language {
+    /// 英文单位（1h 3m 5s）
+    #[default]
+    En,
+    /// 中文单位（1小时3分）
+    Zh,
+}
+
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+/// 设置全局界面语言（仅首次设置生效，应在程序启动时调用）
+pub fn set_language(lang: Language) {
+    let _ = LANGUAGE.set(lang);
+}
+
+/// 获取全局界面语言，未设置时为默认值
+pub fn language() -> Language {
+    LANGUAGE.get().copied().unwrap_or_default()
+}
+
+/// 按全局界面语言格式化持续时间
+///
+/// 规则见 [`format_duration_with`]
+///
+/// # 参数
+/// * `duration` - 持续时间
+///
+/// # 返回
+/// * `String` - 格式化后的字符串，如 "750ms"、"1.2s"、"1h 23m 45s"
+pub fn format_duration(duration: Duration) -> String {
+    format_duration_with(duration, language())
+}
+
+/// 格式化扫描耗时，有暂停时注明暂停时长
+///
+/// # 参数
+/// * `elapsed` - 总耗时
+/// * `paused` - 其中的暂停时长
+///
+/// # 返回
+/// * `String` - 如 "1m 5s" 或 "1m 5s（其中暂停 30s）"
+pub fn format_elapsed(elapsed: Duration, paused: Duration) -> String {
+    if paused.is_zero() {
+        format_duration(elapsed)
+    } else {
+        format!(
+            "{}（其中暂停 {}）",
+            format_duration(elapsed),
+            format_duration(paused)
+        )
+    }
+}
+
+/// 按指定语言格式化持续时间
+///
+/// 舍入规则：
+/// - 先四舍五入到毫秒，不足1秒显示毫秒（999.4ms → "999ms"，999.5ms → "1s"）
+/// - 1秒到10秒之间保留一位小数，小数为0时省略（1.25s → "1.3s"，2.0s → "2s"）
+/// - 10秒及以上四舍五入到整秒，满60秒进位为分钟（59.9s → "1m 0s"）
+/// - 中文风格在小时级别省略秒（"1小时3分"）
+///
+/// # 参数
+/// * `duration` - 持续时间
+/// * `lang` - 语言
+pub fn format_duration_with(duration: Duration, lang: Language) -> String {
+    let millis = (duration.as_micros() + 500) / 1000;
+
+    if millis < 1000 {
+        return match lang {
+            Language::En => format!("{}ms", millis),
+            Language::Zh => format!("{}毫秒", millis),
+        };
+    }
+
+    if millis < 9_950 {
+        let tenths = (millis + 50) / 100;
+        let value = if tenths.is_multiple_of(10) {
+            format!("{}", tenths / 10)
+        } else {
+            format!("{}.{}", tenths / 10, tenths % 10)
+        };
+        return match lang {
+            Language::En => format!("{}s", value),
+            Language::Zh => format!("{}秒", value),
+        };
+    }
+
+    let seconds = (millis + 500) / 1000;
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    let secs = seconds % 60;
+
+    match lang {
+        Language::En => {
+            if hours > 0 {
+                format!("{}h {}m {}s", hours, minutes, secs)
+            } else if minutes > 0 {
+                format!("{}m {}s", minutes, secs)
+            } else {
+                format!("{}s", secs)
+            }
+        }
+        Language::Zh => {
+            if hours > 0 {
+                format!("{}小时{}分", hours, minutes)
+            } else if minutes > 0 {
+                format!("{}分{}秒", minutes, secs)
+            } else {
+                format!("{}秒", secs)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_single_ip() {
+        let result = parse_targets("192.168.1.1").unwrap();
+        assert_eq!(result, vec!["192.168.1.1"]);
+    }
+
+    #[test]
+    fn test_parse_ip_range() {
+        let result = parse_targets("192.168.1.1-3").unwrap();
+        assert_eq!(result, vec!["192.168.1.1", "192.168.1.2", "192.168.1.3"]);
+        assert_eq!(
+            parse_targets("10.0.0.5-10.0.0.5").unwrap(),
+            vec!["10.0.0.5"]
+        );
+    }
+
+    #[test]
+    fn test_parse_targets_dedupes_and_sorts() {
+        let ips = parse_targets("192.168.1.10,192.168.1.0/28,192.168.1.5-10,192.168.1.9").unwrap();
+        let expected: Vec<String> = (1..=14).map(|i| format!("192.168.1.{}", i)).collect();
+        assert_eq!(ips, expected);
+        // 按数值而非字符串排序
+        assert_eq!(
+            parse_targets("10.0.0.10,10.0.0.9,10.0.0.100").unwrap(),
+            vec!["10.0.0.9", "10.0.0.10", "10.0.0.100"]
+        );
+    }
+
+    #[test]
+    fn test_parse_targets_iter_merges_overlaps() {
+        let ips: Vec<Ipv4Addr> = parse_targets_iter("10.0.0.5,10.0.0.0/29,10.0.0.7-10.0.0.8")
+            .unwrap()
+            .collect();
+        let expected: Vec<Ipv4Addr> = (1..=8).map(|i| Ipv4Addr::new(10, 0, 0, i)).collect();
+        assert_eq!(ips, expected);
+        assert_eq!(
+            count_targets("10.0.0.5,10.0.0.0/29,10.0.0.7-10.0.0.8").unwrap(),
+            8
+        );
+
+        // /8 只计数不展开，迭代器按需产生
+        assert_eq!(count_targets("10.0.0.0/8,10.1.2.3").unwrap(), (1 << 24) - 2);
+        let mut big = parse_targets_iter("10.0.0.0/8").unwrap();
+        assert_eq!(big.next(), Some(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(
+            count_targets("0.0.0.0/0,255.255.255.255").unwrap(),
+            u64::from(u32::MAX)
+        );
+        assert!(parse_targets_iter("10.0.0.1,bad").is_err());
+    }
+
+    #[test]
+    fn test_parse_octet_wildcards() {
+        let ips = parse_targets("192.168.1.*").unwrap();
+        assert_eq!(ips.len(), 254);
+        assert_eq!(ips[0], "192.168.1.1");
+        assert_eq!(ips[253], "192.168.1.254");
+        assert_eq!(count_targets("192.168.*.*").unwrap(), 256 * 254);
+        assert_eq!(
+            parse_targets("10.0.1-3.1").unwrap(),
+            vec!["10.0.1.1", "10.0.2.1", "10.0.3.1"]
+        );
+        // 与其他写法混用
+        assert_eq!(
+            parse_targets("10.0.0.*,10.0.1.0/30,10.0.2.5")
+                .unwrap()
+                .len(),
+            254 + 2 + 1
+        );
+        assert!(parse_targets("10.0.300.*").is_err());
+        assert!(parse_targets("10.0.5-1.1").is_err());
+    }
+
+    #[test]
+    fn test_parse_ip_range_across_octets() {
+        let result = parse_targets("192.168.1.254-192.168.2.1").unwrap();
+        assert_eq!(
+            result,
+            vec![
+                "192.168.1.254",
+                "192.168.1.255",
+                "192.168.2.0",
+                "192.168.2.1"
+            ]
+        );
+        assert_eq!(
+            parse_targets("192.168.1.200-192.168.2.50").unwrap().len(),
+            107
+        );
+    }
+
+    #[test]
+    fn test_parse_ip_range_errors() {
+        // 结束值小于起始值
+        let err = parse_targets("192.168.2.1-192.168.1.9").unwrap_err();
+        assert!(err.to_string().contains("必须大于或等于"), "{}", err);
+        assert!(parse_targets("192.168.1.9-3").is_err());
+        // 最后一段超过255
+        let err = parse_targets("192.168.1.250-260").unwrap_err();
+        assert!(err.to_string().contains("超出范围"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_cidr() {
+        let result = parse_targets("192.168.1.0/30").unwrap();
+        assert_eq!(result, vec!["192.168.1.1", "192.168.1.2"]);
+        assert_eq!(parse_targets("10.0.0.7/32").unwrap(), vec!["10.0.0.7"]);
+        assert_eq!(parse_targets("0.0.0.0/32").unwrap(), vec!["0.0.0.0"]);
+        assert_eq!(
+            parse_targets("10.1.2.3/31").unwrap(),
+            vec!["10.1.2.2", "10.1.2.3"]
+        );
+        assert_eq!(
+            parse_targets("255.255.255.254/31").unwrap(),
+            vec!["255.255.255.254", "255.255.255.255"]
+        );
+    }
+
+    #[test]
+    fn test_parse_exclude_ranges_cover_whole_networks() {
+        let ip = |s: &str| u32::from(Ipv4Addr::from_str(s).unwrap());
+        let ranges = parse_exclude_ranges("10.0.1.*,10.0.0.0/24,192.168.0.5").unwrap();
+        assert_eq!(
+            ranges,
+            vec![
+                (ip("10.0.0.0"), ip("10.0.1.255")),
+                (ip("192.168.0.5"), ip("192.168.0.5"))
+            ]
+        );
+        assert!(parse_exclude_ranges("10.0.0.0/33").is_err());
+    }
+
+    #[test]
+    fn test_subtract_ranges() {
+        let ranges = [(0, 9), (20, 29), (u32::MAX - 1, u32::MAX)];
+        let removed = [(0, 2), (5, 5), (9, 21), (u32::MAX, u32::MAX)];
+        assert_eq!(
+            subtract_ranges(&ranges, &removed),
+            vec![(3, 4), (6, 8), (22, 29), (u32::MAX - 1, u32::MAX - 1)]
+        );
+        assert_eq!(subtract_ranges(&ranges, &[]), ranges);
+        assert!(subtract_ranges(&ranges, &[(0, u32::MAX)]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_ports() {
+        let (result, skipped) = parse_ports("22,80-82,443");
+        assert_eq!(result, vec![22, 80, 81, 82, 443]);
+        assert_eq!(skipped, 0);
+    }
+
+    #[test]
+    fn test_parse_ports_skipped_count() {
+        let (result, skipped) = parse_ports("22,abc,0,80-70");
+        assert_eq!(result, vec![22]);
+        assert_eq!(skipped, 3);
+    }
+
+    fn strict_reasons(input: &str) -> Vec<(usize, PortErrorReason)> {
+        match parse_ports_strict(input) {
+            Err(GxError::InvalidPorts(errors)) => {
+                errors.iter().map(|e| (e.position, e.reason)).collect()
+            }
+            other => panic!("期望解析失败: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_ports_strict_valid() {
+        assert_eq!(
+            parse_ports_strict("443, 22,80-82").unwrap(),
+            vec![22, 80, 81, 82, 443]
+        );
+        assert_eq!(parse_ports_strict("65535").unwrap(), vec![65535]);
+    }
+
+    #[test]
+    fn test_parse_ports_strict_errors() {
+        assert_eq!(strict_reasons("0"), vec![(1, PortErrorReason::ZeroPort)]);
+        assert_eq!(
+            strict_reasons("65536"),
+            vec![(1, PortErrorReason::OutOfRange)]
+        );
+        assert_eq!(
+            strict_reasons("80-70"),
+            vec![(1, PortErrorReason::ReversedRange)]
+        );
+        assert_eq!(
+            strict_reasons("abc"),
+            vec![(1, PortErrorReason::NonNumeric)]
+        );
+        assert_eq!(strict_reasons(""), vec![(0, PortErrorReason::Empty)]);
+        assert_eq!(strict_reasons(" , "), vec![(0, PortErrorReason::Empty)]);
+    }
+
+    #[test]
+    fn test_parse_ports_strict_reports_every_token() {
+        assert_eq!(
+            strict_reasons("22,abc,443,0-10,99999"),
+            vec![
+                (2, PortErrorReason::NonNumeric),
+                (4, PortErrorReason::ZeroPort),
+                (5, PortErrorReason::OutOfRange),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_ports_all_and_open_ended() {
+        assert_eq!(parse_ports_strict("all").unwrap().len(), 65535);
+        assert_eq!(parse_ports_strict("ALL").unwrap().len(), 65535);
+        assert_eq!(
+            parse_ports_strict("65530-").unwrap(),
+            vec![65530, 65531, 65532, 65533, 65534, 65535]
+        );
+    }
+
+    #[test]
+    fn test_parse_ports_exclusions() {
+        let ports = parse_ports_strict("all,!135-139,!445").unwrap();
+        assert_eq!(ports.len(), 65535 - 5 - 1);
+        assert!(!ports.contains(&135) && !ports.contains(&139) && !ports.contains(&445));
+        assert!(ports.contains(&134) && ports.contains(&140));
+
+        // 排除项的位置不影响结果：先包含后排除
+        assert_eq!(parse_ports_strict("!81,80-82").unwrap(), vec![80, 82]);
+    }
+
+    #[test]
+    fn test_parse_ports_exclude_not_included() {
+        assert_eq!(parse_ports_strict("22,80,!443").unwrap(), vec![22, 80]);
+    }
+
+    #[test]
+    fn test_parse_ports_overlapping_exclusions() {
+        assert_eq!(
+            parse_ports_strict("1-10,!2-5,!4-8,!8").unwrap(),
+            vec![1, 9, 10]
+        );
+    }
+
+    #[test]
+    fn test_parse_ports_exclude_all() {
+        assert_eq!(
+            strict_reasons("22,80,!all"),
+            vec![(0, PortErrorReason::AllExcluded)]
+        );
+        assert_eq!(strict_reasons("!all"), vec![(0, PortErrorReason::Empty)]);
+        assert_eq!(parse_ports("22,!all"), (vec![], 0));
+    }
+
+    #[test]
+    fn test_parse_ports_invalid_exclusion() {
+        assert_eq!(
+            strict_reasons("all,!abc"),
+            vec![(2, PortErrorReason::NonNumeric)]
+        );
+        assert_eq!(strict_reasons("!0"), vec![(1, PortErrorReason::ZeroPort)]);
+    }
+
+    #[test]
+    fn test_sanitize_cell_formula_prefix() {
+        assert_eq!(
+            sanitize_cell("=HYPERLINK(\"http://x\")"),
+            "'=HYPERLINK(\"http://x\")"
+        );
+        assert_eq!(sanitize_cell("@SUM(A1:A2)"), "'@SUM(A1:A2)");
+        assert_eq!(sanitize_cell("+1"), "'+1");
+        assert_eq!(sanitize_cell("-1"), "'-1");
+        assert_eq!(sanitize_cell("\tcmd"), "'\tcmd");
+        assert_eq!(sanitize_cell("SSH-2.0-OpenSSH_8.9"), "SSH-2.0-OpenSSH_8.9");
+    }
+
+    #[test]
+    fn test_sanitize_cell_control_chars() {
+        assert_eq!(sanitize_cell("a\x00b\x07c\r\nd"), "abc\nd");
+        // 去除控制字符后以公式字符开头同样需要转义
+        assert_eq!(sanitize_cell("\x00\x1b=cmd"), "'=cmd");
+    }
+
+    #[test]
+    fn test_sanitize_cell_truncation() {
+        let long = "A".repeat(EXCEL_MAX_CELL_CHARS + 100);
+        let result = sanitize_cell(&long);
+        assert_eq!(result.chars().count(), EXCEL_MAX_CELL_CHARS);
+        assert!(result.ends_with(TRUNCATED_MARKER));
+
+        let exact = "中".repeat(EXCEL_MAX_CELL_CHARS);
+        assert_eq!(sanitize_cell(&exact), exact);
+    }
+
+    #[test]
+    fn test_exports_in_the_same_second_do_not_overwrite() {
+        use calamine::{Reader, open_workbook_auto};
+
+        let root = std::env::temp_dir().join(format!("gxr_export_twice_{}", std::process::id()));
+        let options = ExcelOptions {
+            output_root: root.clone(),
+            quiet: true,
+            ..Default::default()
+        };
+        let first: Vec<String> = (0..50).map(|i| format!("10.0.0.{}", i)).collect();
+        let second: Vec<String> = (0..30).map(|i| format!("10.0.1.{}", i)).collect();
+        let export = |rows: &[String]| {
+            save_to_excel_with_options(
+                rows,
+                &["IP地址"],
+                |ip| vec![ip.clone()],
+                OutputKind::PING,
+                &options,
+            )
+            .unwrap()
+        };
+        let paths = [export(&first), export(&second)];
+
+        let read = |path: &str| -> Vec<String> {
+            let mut workbook = open_workbook_auto(path).unwrap();
+            let sheet = workbook.sheet_names()[0].clone();
+            let range = workbook.worksheet_range(&sheet).unwrap();
+            range.rows().skip(1).map(|r| r[0].to_string()).collect()
+        };
+        let contents = [read(&paths[0]), read(&paths[1])];
+        std::fs::remove_dir_all(&root).ok();
+
+        assert_ne!(paths[0], paths[1]);
+        assert!(paths.iter().all(|p| p.contains("ping_")));
+        // 同一秒内的第二个文件追加序号
+        if paths[1].ends_with("_2.xlsx") {
+            assert_eq!(paths[1].replace("_2.xlsx", ".xlsx"), paths[0]);
+        }
+        assert_eq!(contents, [first, second]);
+    }
+
+    #[test]
+    fn test_compress_ports() {
+        assert_eq!(
+            compress_ports(&[1, 2, 3, 5, 7, 8, 65535]),
+            "1-3,5,7-8,65535"
+        );
+        assert_eq!(compress_ports(&[]), "");
+        assert_eq!(port_ranges(&[22, 80, 81]), [(22, 22), (80, 81)]);
+    }
+
+    #[test]
+    fn test_large_tables_continue_on_new_sheets() {
+        use calamine::{Reader, open_workbook_auto};
+
+        let path = std::env::temp_dir().join(format!("gxr_split_{}.xlsx", std::process::id()));
+        let rows: Vec<Vec<String>> = (0..5).map(|i| vec![format!("10.0.0.{}", i)]).collect();
+        let mut workbook = Workbook::new();
+        write_split_sheets(
+            &mut workbook,
+            Some("目标"),
+            &["IP地址"],
+            rows.iter(),
+            true,
+            2,
+        )
+        .unwrap();
+        workbook.save(&path).unwrap();
+
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let names = workbook.sheet_names();
+        let read: Vec<String> = names
+            .iter()
+            .flat_map(|name| {
+                let range = workbook.worksheet_range(name).unwrap();
+                assert_eq!(range.rows().next().unwrap()[0].to_string(), "IP地址");
+                range
+                    .rows()
+                    .skip(1)
+                    .map(|r| r[0].to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(names, ["目标", "目标(2)", "目标(3)"]);
+        assert_eq!(read, rows.concat());
+        assert_eq!(split_sheet_name(&"表".repeat(40), 12).chars().count(), 31);
+    }
+
+    #[test]
+    fn test_prepare_groups_writes_one_sheet_per_group() {
+        use calamine::{Reader, open_workbook_auto};
+
+        let root = std::env::temp_dir().join(format!("gxr_groups_{}", std::process::id()));
+        let options = ExcelOptions {
+            output_root: root.clone(),
+            quiet: true,
+            ..Default::default()
+        };
+        let kind = OutputKind {
+            subdir: "groups",
+            prefix: "groups_test",
+        };
+        let alive = ["10.0.0.1".to_string(), "10.0.0.2".to_string()];
+        let dead = ["10.0.0.3".to_string()];
+        let headers = ["IP地址"];
+        let path = ExcelExport::prepare_groups(
+            &[("存活", &headers, &alive), ("未存活", &headers, &dead)],
+            |ip, row| {
+                row.cell(ip);
+            },
+            kind,
+            &options,
+        )
+        .run()
+        .unwrap();
+
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let names = workbook.sheet_names();
+        let read: Vec<Vec<String>> = names[..2]
+            .iter()
+            .map(|name| {
+                let range = workbook.worksheet_range(name).unwrap();
+                range.rows().map(|r| r[0].to_string()).collect()
+            })
+            .collect();
+        std::fs::remove_dir_all(&root).ok();
+
+        assert_eq!(names[..2], ["存活", "未存活"]);
+        assert_eq!(read[0], ["IP地址", "10.0.0.1", "10.0.0.2"]);
+        assert_eq!(read[1], ["IP地址", "10.0.0.3"]);
+    }
+
+    #[test]
+    fn test_save_to_excel_salvages_rows_when_writer_fails() {
+        // 输出根目录是一个普通文件，无法在其下创建模块目录
+        let root = std::env::temp_dir().join(format!("gxr_unwritable_{}", std::process::id()));
+        std::fs::write(&root, "").unwrap();
+        let options = ExcelOptions {
+            output_root: root.clone(),
+            extra_sheets: vec![ExcelSheet {
+                name: "主机汇总".to_string(),
+                headers: vec!["IP".to_string()],
+                rows: vec![vec!["10.0.0.1".to_string()]],
+            }],
+            ..Default::default()
+        };
+        let rows: Vec<String> = (0..50).map(|i| format!("10.0.0.{}", i)).collect();
+        let kind = OutputKind {
+            subdir: "salvage",
+            prefix: "salvage_test",
+        };
+
+        let path = save_to_excel_with_options(&rows, &["IP"], |r| vec![r.clone()], kind, &options)
+            .unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&root).ok();
+        std::fs::remove_file(&path).ok();
+        let summary = std::fs::read_dir(salvage::salvage_dir())
+            .unwrap()
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .find(|p| p.to_string_lossy().contains("salvage_test_主机汇总"))
+            .unwrap();
+        let summary_content = std::fs::read_to_string(&summary).unwrap();
+        std::fs::remove_file(&summary).ok();
+
+        assert!(path.starts_with(&*salvage::salvage_dir().to_string_lossy()));
+        assert!(path.ends_with(".csv"));
+        let lines: Vec<&str> = content.trim_start_matches('\u{feff}').lines().collect();
+        assert_eq!(lines.len(), rows.len() + 1);
+        assert_eq!(lines[0], "IP");
+        assert_eq!(&lines[1..], rows);
+        assert!(summary_content.contains("10.0.0.1"));
+    }
+
+    #[test]
+    fn test_save_to_excel_sanitizes_hostile_banners() {
+        use calamine::{Reader, open_workbook_auto};
+
+        let root = std::env::temp_dir().join(format!("gxr_export_{}", std::process::id()));
+        let options = ExcelOptions {
+            output_root: root.clone(),
+            extra_sheets: vec![ExcelSheet {
+                name: "主机汇总".to_string(),
+                headers: vec!["备注".to_string()],
+                rows: vec![vec!["=1+1".to_string()]],
+            }],
+            ..Default::default()
+        };
+        let banners = vec![
+            "=HYPERLINK(\"http://evil\",\"click\")".to_string(),
+            "@SUM(1+1)*cmd|' /C calc'!A0".to_string(),
+            "\x00\x01-2+3".to_string(),
+            "B".repeat(40_000),
+        ];
+
+        let path = save_to_excel_with_options(
+            &banners,
+            &["服务"],
+            |b| vec![b.clone()],
+            OutputKind {
+                subdir: "hostile",
+                prefix: "hostile",
+            },
+            &options,
+        )
+        .unwrap();
+
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let sheet = workbook.sheet_names()[0].clone();
+        let range = workbook.worksheet_range(&sheet).unwrap();
+        let cells: Vec<String> = range.rows().skip(1).map(|r| r[0].to_string()).collect();
+        let summary = workbook.worksheet_range("主机汇总").unwrap();
+        let summary_cell = summary.get_value((1, 0)).unwrap().to_string();
+        std::fs::remove_dir_all(&root).ok();
+
+        assert_eq!(summary_cell, "'=1+1");
+
+        assert_eq!(cells.len(), banners.len());
+        assert!(cells[0].starts_with("'="));
+        assert!(cells[1].starts_with("'@"));
+        assert_eq!(cells[2], "'-2+3");
+        assert_eq!(cells[3].chars().count(), EXCEL_MAX_CELL_CHARS);
+    }
+
+    #[test]
+    fn test_packed_rows_keep_cell_boundaries() {
+        let mut rows = PackedRows::default();
+        rows.push_row(&[], None, |row| {
+            row.cell("10.0.0.1").cell(443).cell("").cell("开放");
+        });
+        rows.push_row(&[], None, |_| {});
+        rows.push_row(&[], None, |row| {
+            row.cells(["", "a, b"]);
+        });
+
+        let collected: Vec<Vec<&str>> = rows.rows().map(|r| r.collect()).collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            collected,
+            [
+                vec!["10.0.0.1", "443", "", "开放"],
+                vec![],
+                vec!["", "a, b"]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1024), "1 KB");
+        assert_eq!(format_bytes(1048576), "1 MB");
+        assert_eq!(format_bytes(1536), "1.50 KB");
+    }
+
+    fn en(d: Duration) -> String {
+        format_duration_with(d, Language::En)
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(en(Duration::from_secs(3661)), "1h 1m 1s");
+        assert_eq!(en(Duration::from_secs(65)), "1m 5s");
+        assert_eq!(en(Duration::from_secs(30)), "30s");
+        assert_eq!(en(Duration::from_millis(750)), "750ms");
+        assert_eq!(en(Duration::from_millis(1200)), "1.2s");
+        assert_eq!(en(Duration::ZERO), "0ms");
+    }
+
+    #[test]
+    fn test_format_duration_boundaries() {
+        assert_eq!(en(Duration::from_micros(999_499)), "999ms");
+        assert_eq!(en(Duration::from_micros(999_500)), "1s");
+        assert_eq!(en(Duration::from_millis(999)), "999ms");
+        assert_eq!(en(Duration::from_millis(1000)), "1s");
+        assert_eq!(en(Duration::from_millis(9_949)), "9.9s");
+        assert_eq!(en(Duration::from_millis(9_950)), "10s");
+        assert_eq!(en(Duration::from_millis(59_499)), "59s");
+        assert_eq!(en(Duration::from_millis(59_900)), "1m 0s");
+        assert_eq!(en(Duration::from_millis(3_599_500)), "1h 0m 0s");
+    }
+
+    #[test]
+    fn test_format_duration_monotonic_units() {
+        // 每个单位边界两侧的输出都不会出现"60s"或"60m"
+        for ms in (0..7_300_000u64).step_by(97) {
+            let s = en(Duration::from_millis(ms));
+            assert!(
+                !s.contains("60s") && !s.contains("60m") && !s.contains("1000ms"),
+                "{}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_elapsed_notes_pause() {
+        assert_eq!(
+            format_elapsed(Duration::from_secs(65), Duration::ZERO),
+            "1m 5s"
+        );
+        assert_eq!(
+            format_elapsed(Duration::from_secs(65), Duration::from_secs(30)),
+            "1m 5s（其中暂停 30s）"
+        );
+    }
+
+    #[test]
+    fn test_format_duration_chinese() {
+        let zh = |d| format_duration_with(d, Language::Zh);
+        assert_eq!(zh(Duration::from_millis(750)), "750毫秒");
+        assert_eq!(zh(Duration::from_millis(1200)), "1.2秒");
+        assert_eq!(zh(Duration::from_secs(65)), "1分5秒");
+        assert_eq!(zh(Duration::from_secs(3780)), "1小时3分");
+    }
+}
@@ -0,0 +1,151 @@
+// src/utils/output.rs
+use std::fs::OpenOptions;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 同名文件已存在时最多尝试的序号
+const MAX_SUFFIX: usize = 1000;
+
+/// 模块的输出位置：平铺输出时的子目录及文件名前缀
+///
+/// 各模块的取值集中登记在这里，避免不同模块写入同一位置。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputKind {
+    /// 输出根目录下的子目录
+    pub subdir: &'static str,
+    /// 文件名前缀
+    pub prefix: &'static str,
+}
+
+impl OutputKind {
+    /// Ping扫描
+    pub const PING: Self = Self {
+        subdir: "ping",
+        prefix: "ping",
+    };
+
+    /// HTTP探测
+    pub const HTTP: Self = Self {
+        subdir: "http",
+        prefix: "http",
+    };
+
+    /// 端口扫描
+    pub const PORTSCAN: Self = Self {
+        subdir: "portscan",
+        prefix: "portscan",
+    };
+
+    /// 端口扫描交互界面中途导出的结果
+    pub const PORTSCAN_PARTIAL: Self = Self {
+        subdir: "portscan",
+        prefix: "portscan_partial",
+    };
+
+    /// 端口结果补充识别
+    pub const ENRICH: Self = Self {
+        subdir: "portscan",
+        prefix: "enrich",
+    };
+
+    /// 邮件服务检查
+    pub const MAIL: Self = Self {
+        subdir: "mail",
+        prefix: "mail",
+    };
+
+    /// 域控制器信息收集
+    pub const ADINFO: Self = Self {
+        subdir: "adinfo",
+        prefix: "adinfo",
+    };
+
+    /// 主机状态变化时间线
+    pub const TIMELINE: Self = Self {
+        subdir: "report",
+        prefix: "timeline",
+    };
+
+    /// 整改后复查报告
+    pub const REVERIFY: Self = Self {
+        subdir: "report",
+        prefix: "reverify",
+    };
+
+    /// 网络测绘
+    pub const MAP: Self = Self {
+        subdir: "map",
+        prefix: "map",
+    };
+
+    /// ARP扫描
+    pub const ARPSCAN: Self = Self {
+        subdir: "arpscan",
+        prefix: "arpscan",
+    };
+
+    /// 内网主机名字典扫描
+    pub const DNSSWEEP: Self = Self {
+        subdir: "dnssweep",
+        prefix: "dnssweep",
+    };
+}
+
+/// 在目录中占用一个尚不存在的文件名
+///
+/// 依次尝试 `<名称>.<扩展名>`、`<名称>_2.<扩展名>`、`<名称>_3.<扩展名>`……，
+/// 以独占方式创建空文件占位，同一秒内的多次导出（包括不同进程）不会互相覆盖。
+///
+/// # 参数
+/// * `dir` - 所在目录（须已存在）
+/// * `stem` - 文件名（不含扩展名，通常带时间戳）
+/// * `ext` - 扩展名
+///
+/// # 返回
+/// * `Ok(PathBuf)` - 已创建的空文件路径，由调用方写入内容
+pub fn reserve_unique_path(dir: &Path, stem: &str, ext: &str) -> io::Result<PathBuf> {
+    for n in 1..=MAX_SUFFIX {
+        let name = if n == 1 {
+            format!("{}.{}", stem, ext)
+        } else {
+            format!("{}_{}.{}", stem, n, ext)
+        };
+        let path = dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => return Ok(path),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} 下同名文件过多: {}.{}", dir.display(), stem, ext),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_unique_path_appends_suffix() {
+        let dir = std::env::temp_dir().join(format!("gxr_reserve_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let paths: Vec<PathBuf> = (0..3)
+            .map(|_| reserve_unique_path(&dir, "ping_20240102_100000", "xlsx").unwrap())
+            .collect();
+        let names: Vec<String> = paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(
+            names,
+            [
+                "ping_20240102_100000.xlsx",
+                "ping_20240102_100000_2.xlsx",
+                "ping_20240102_100000_3.xlsx"
+            ]
+        );
+    }
+}
@@ -0,0 +1,450 @@
+// src/utils/pause.rs
+use super::ScanProgress;
+use super::console::Icon;
+use super::context::interrupt;
+use super::window::window;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// 暂停时进度条显示的消息
+const PAUSED_MESSAGE: &str = "⏸  已暂停（按 p 或发送 SIGUSR1 继续）";
+
+/// 探测分发的暂停开关
+///
+/// 分发端在发起每个探测前调用 [`PauseGate::wait`]，暂停期间不再发起新的探测，
+/// 已在执行的探测不受影响。可在线程间克隆共享，并累计暂停总时长，
+/// 便于从速率和剩余时间估算中扣除。
+#[derive(Clone)]
+pub struct PauseGate {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    tx: watch::Sender<bool>,
+    clock: Mutex<PauseClock>,
+}
+
+/// 暂停原因及计时（手动暂停和窗口外暂停任一成立即暂停）
+#[derive(Default)]
+struct PauseClock {
+    manual: bool,
+    /// 处于扫描窗口外时为进度条显示的消息
+    window: Option<String>,
+    since: Option<Instant>,
+    total: Duration,
+}
+
+impl Default for PauseGate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PauseGate {
+    /// 创建处于运行状态的开关
+    pub fn new() -> Self {
+        let (tx, _) = watch::channel(false);
+        Self {
+            inner: Arc::new(Inner {
+                tx,
+                clock: Mutex::new(PauseClock::default()),
+            }),
+        }
+    }
+
+    /// 暂停分发
+    pub fn pause(&self) {
+        self.update(|clock| clock.manual = true);
+    }
+
+    /// 恢复分发（处于扫描窗口外时仍保持暂停）
+    pub fn resume(&self) {
+        self.update(|clock| clock.manual = false);
+    }
+
+    /// 解除所有暂停（包括窗口外暂停），用于取消扫描时放行等待中的分发
+    pub fn release(&self) {
+        self.update(|clock| {
+            clock.manual = false;
+            clock.window = None;
+        });
+    }
+
+    /// 切换手动暂停状态
+    ///
+    /// # 返回
+    /// * `true` - 切换后处于手动暂停状态
+    pub fn toggle(&self) -> bool {
+        let mut manual = false;
+        self.update(|clock| {
+            clock.manual = !clock.manual;
+            manual = clock.manual;
+        });
+        manual
+    }
+
+    /// 进入扫描窗口外：暂停分发，未手动暂停时在进度条显示恢复时间
+    ///
+    /// # 参数
+    /// * `message` - 进度条显示的消息
+    /// * `progress` - 扫描进度条
+    pub fn close_window(&self, message: String, progress: &ScanProgress) {
+        let mut show = false;
+        self.update(|clock| {
+            show = !clock.manual && clock.window.as_ref() != Some(&message);
+            clock.window = Some(message.clone());
+        });
+        if show {
+            progress.set_message(message);
+        }
+    }
+
+    /// 回到扫描窗口内：恢复分发（手动暂停的保持暂停）
+    pub fn open_window(&self, progress: &ScanProgress) {
+        let mut reopened = false;
+        self.update(|clock| reopened = clock.window.take().is_some() && !clock.manual);
+        if reopened {
+            progress.set_message("");
+            progress.reset_eta();
+        }
+    }
+
+    /// 修改暂停原因，并据此更新暂停状态及计时
+    fn update(&self, change: impl FnOnce(&mut PauseClock)) {
+        let mut clock = self.inner.clock.lock().unwrap();
+        change(&mut clock);
+        let paused = clock.manual || clock.window.is_some();
+        match (paused, clock.since) {
+            (true, None) => clock.since = Some(Instant::now()),
+            (false, Some(since)) => {
+                clock.total += since.elapsed();
+                clock.since = None;
+            }
+            _ => {}
+        }
+        self.inner.tx.send_replace(paused);
+    }
+
+    /// 当前是否处于暂停状态
+    pub fn is_paused(&self) -> bool {
+        *self.inner.tx.borrow()
+    }
+
+    /// 累计暂停时长（包含当前这一次尚未结束的暂停）
+    pub fn paused_duration(&self) -> Duration {
+        let clock = self.inner.clock.lock().unwrap();
+        clock.total + clock.since.map(|s| s.elapsed()).unwrap_or_default()
+    }
+
+    /// 暂停期间等待，运行状态下立即返回
+    pub async fn wait(&self) {
+        let mut rx = self.inner.tx.subscribe();
+        // 发送端由自身持有，不会被关闭
+        let _ = rx.wait_for(|paused| !*paused).await;
+    }
+
+    /// 切换暂停状态并同步进度条显示
+    fn toggle_with_progress(&self, progress: &ScanProgress) {
+        let paused = self.toggle();
+        self.show(paused, progress);
+    }
+
+    /// 设置手动暂停状态并同步进度条显示（如由控制通道暂停或继续）
+    ///
+    /// # 参数
+    /// * `paused` - 是否暂停
+    /// * `progress` - 扫描进度条
+    pub fn set_paused_with_progress(&self, paused: bool, progress: &ScanProgress) {
+        if paused {
+            self.pause();
+        } else {
+            self.resume();
+        }
+        self.show(paused, progress);
+    }
+
+    /// 按手动暂停状态更新进度条消息
+    fn show(&self, paused: bool, progress: &ScanProgress) {
+        if paused {
+            progress.set_message(PAUSED_MESSAGE);
+        } else if let Some(message) = self.inner.clock.lock().unwrap().window.clone() {
+            // 手动继续时仍在窗口外
+            progress.set_message(message);
+        } else {
+            progress.set_message("");
+            // 重新估算剩余时间，避免暂停期间拉低速率
+            progress.reset_eta();
+        }
+    }
+
+    /// 开始监听暂停/继续操作
+    ///
+    /// Unix下收到 SIGUSR1 时切换状态；`keys` 为真且标准输入是终端时，
+    /// 按 `p` 键切换状态。设置了扫描窗口（`--window`）时同时按窗口暂停和恢复。
+    /// 返回的句柄被丢弃时停止监听并恢复终端设置。
+    ///
+    /// # 参数
+    /// * `progress` - 扫描进度条（用于显示暂停状态）
+    /// * `keys` - 是否监听按键（交互界面自行处理按键时应关闭）
+    pub fn listen(&self, progress: &ScanProgress, keys: bool) -> PauseListener {
+        let stop = Arc::new(AtomicBool::new(false));
+
+        // 信号处理器在返回前注册，避免注册前收到的 SIGUSR1 按默认行为终止进程
+        #[cfg(unix)]
+        let signal_task = {
+            use tokio::signal::unix::{SignalKind, signal};
+            let gate = self.clone();
+            let progress = progress.clone();
+            let usr1 = signal(SignalKind::user_defined1());
+            tokio::spawn(async move {
+                let Ok(mut usr1) = usr1 else {
+                    return;
+                };
+                while usr1.recv().await.is_some() {
+                    gate.toggle_with_progress(&progress);
+                }
+            })
+        };
+
+        let key_thread = if keys && io::stdin().is_terminal() {
+            match KeyMode::enable() {
+                Ok(mode) => {
+                    let gate = self.clone();
+                    let progress = progress.clone();
+                    let stop = stop.clone();
+                    Some(thread::spawn(move || {
+                        watch_keys(&gate, &progress, &stop);
+                        drop(mode);
+                    }))
+                }
+                Err(e) => {
+                    eprintln!("{} 无法监听按键，暂停功能仅支持信号: {}", Icon::Warn, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // 先按当前时间设置一次，避免后台任务运行前已发起探测
+        let window_task = window().map(|w| {
+            w.apply(self, progress);
+            tokio::spawn(w.enforce(self.clone(), progress.clone()))
+        });
+
+        PauseListener {
+            gate: self.clone(),
+            stop,
+            key_thread,
+            window_task,
+            #[cfg(unix)]
+            signal_task,
+        }
+    }
+}
+
+/// 暂停监听句柄，丢弃时停止监听
+pub struct PauseListener {
+    gate: PauseGate,
+    stop: Arc<AtomicBool>,
+    key_thread: Option<thread::JoinHandle<()>>,
+    window_task: Option<tokio::task::JoinHandle<()>>,
+    #[cfg(unix)]
+    signal_task: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for PauseListener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.key_thread.take() {
+            let _ = handle.join();
+        }
+        #[cfg(unix)]
+        self.signal_task.abort();
+        // 停止按窗口暂停，之后没有监听的阶段不会一直等待窗口打开
+        if let Some(task) = self.window_task.take() {
+            task.abort();
+            self.gate.update(|clock| clock.window = None);
+        }
+    }
+}
+
+/// 按键监听循环
+fn watch_keys(gate: &PauseGate, progress: &ScanProgress, stop: &AtomicBool) {
+    while !stop.load(Ordering::SeqCst) {
+        if !event::poll(Duration::from_millis(100)).unwrap_or(false) {
+            continue;
+        }
+        let Ok(Event::Key(key)) = event::read() else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('p') | KeyCode::Char('P') => gate.toggle_with_progress(progress),
+            // 按键模式下 Ctrl+C 不再产生信号，按中断处理；再次中断时恢复终端后退出
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) && interrupt() => {
+                KeyMode::restore();
+                std::process::exit(130);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 逐键读取模式
+///
+/// Unix下只关闭行缓冲和回显（保留输出处理，进度条和日志显示不受影响），
+/// Windows下启用控制台原始输入模式。丢弃时恢复原设置。
+struct KeyMode;
+
+#[cfg(unix)]
+static SAVED_TERMIOS: Mutex<Option<libc::termios>> = Mutex::new(None);
+
+impl KeyMode {
+    #[cfg(unix)]
+    fn enable() -> io::Result<Self> {
+        use std::os::fd::AsRawFd;
+        let fd = io::stdin().as_raw_fd();
+        // SAFETY: termios 为纯数据结构，tcgetattr/tcsetattr 只读写传入的结构体
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(fd, &mut original) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = original;
+            // 同时关闭 ISIG，使 Ctrl+C 作为按键交给监听线程处理，确保退出前恢复终端
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(fd, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            *SAVED_TERMIOS.lock().unwrap() = Some(original);
+        }
+        Ok(Self)
+    }
+
+    #[cfg(unix)]
+    fn restore() {
+        use std::os::fd::AsRawFd;
+        if let Some(original) = SAVED_TERMIOS.lock().unwrap().take() {
+            // SAFETY: 恢复 enable 时保存的终端设置
+            unsafe {
+                libc::tcsetattr(io::stdin().as_raw_fd(), libc::TCSANOW, &original);
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn enable() -> io::Result<Self> {
+        crossterm::terminal::enable_raw_mode()?;
+        Ok(Self)
+    }
+
+    #[cfg(not(unix))]
+    fn restore() {
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+impl Drop for KeyMode {
+    fn drop(&mut self) {
+        Self::restore();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pause_gate_blocks_until_resumed() {
+        let gate = PauseGate::new();
+        assert!(gate.toggle());
+
+        let waiter = tokio::spawn({
+            let gate = gate.clone();
+            async move { gate.wait().await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+
+        gate.resume();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_paused_duration_accumulates() {
+        let gate = PauseGate::new();
+        assert_eq!(gate.paused_duration(), Duration::ZERO);
+
+        gate.pause();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        gate.resume();
+        let first = gate.paused_duration();
+        assert!(first >= Duration::from_millis(30));
+
+        // 运行期间不累计
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(gate.paused_duration(), first);
+
+        // 重复暂停不重置计时
+        gate.pause();
+        gate.pause();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(gate.paused_duration() >= first + Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_window_and_manual_pause_are_independent() {
+        let gate = PauseGate::new();
+        let progress = ScanProgress::new(0);
+        gate.close_window("窗口外".to_string(), &progress);
+        assert!(gate.is_paused());
+        // 窗口外的手动暂停和继续都不会恢复分发
+        assert!(gate.toggle());
+        assert!(!gate.toggle());
+        assert!(gate.is_paused());
+
+        // 手动暂停期间窗口打开，仍保持暂停
+        assert!(gate.toggle());
+        gate.open_window(&progress);
+        assert!(gate.is_paused());
+        gate.resume();
+        assert!(!gate.is_paused());
+
+        gate.close_window("窗口外".to_string(), &progress);
+        gate.release();
+        assert!(!gate.is_paused());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sigusr1_toggles_pause() {
+        let gate = PauseGate::new();
+        let progress = ScanProgress::new(0);
+        let _listener = gate.listen(&progress, false);
+
+        // SAFETY: 向自身进程发送信号
+        unsafe {
+            libc::kill(libc::getpid(), libc::SIGUSR1);
+        }
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while !gate.is_paused() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
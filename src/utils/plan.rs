@@ -0,0 +1,646 @@
+// src/utils/plan.rs
+use super::console::Icon;
+use super::run_dir::SummaryItem;
+use super::targets::{Target, TargetSet};
+use super::{
+    EXCEL_MAX_DATA_ROWS, compress_ports, format_port_range, merge_ranges, port_ranges,
+    split_sheet_name, subtract_ranges, write_sheet, write_split_sheets,
+};
+use calamine::{DataType, Reader, Xlsx, open_workbook};
+use clap::Args;
+use rust_xlsxwriter::Workbook;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::error::Error;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+
+/// 计划中目标表的名称（目标很多时续写为 `目标(2)`……）
+pub const TARGETS_SHEET_NAME: &str = "目标";
+
+/// 计划中端口表的名称（仅端口扫描）
+pub const PORTS_SHEET_NAME: &str = "端口";
+
+/// 计划中汇总表的名称
+pub const SUMMARY_SHEET_NAME: &str = "计划汇总";
+
+/// 汇总表中模块一行的名称
+const MODULE_LABEL: &str = "模块";
+
+/// 汇总表中计划指纹一行的名称
+const FINGERPRINT_LABEL: &str = "计划指纹";
+
+/// 汇总表中参数快照各行名称的前缀
+const PARAM_PREFIX: &str = "参数 ";
+
+/// 不一致时最多列出的目标数
+const DIFF_SAMPLES: usize = 5;
+
+/// 扫描计划参数
+///
+/// 大型项目开始前，客户需要签批确切的探测范围：`--dry-run --export-plan` 导出计划，
+/// 签批后以 `--from-plan` 执行，当前参数展开的范围与计划不一致时拒绝执行。
+#[derive(Args, Debug, Clone, Default)]
+pub struct PlanArgs {
+    /// 试运行时导出完整的探测范围（目标及来源写法、别名、标签、参数覆盖、端口、预计探测数）供签批
+    #[arg(long, value_name = "FILE", requires = "dry_run")]
+    pub export_plan: Option<PathBuf>,
+
+    /// 按已签批的计划执行：当前参数展开的探测范围与计划不一致时拒绝执行
+    #[arg(long, value_name = "FILE", conflicts_with = "export_plan")]
+    pub from_plan: Option<PathBuf>,
+}
+
+impl PlanArgs {
+    /// 按参数导出或核对计划（在发送任何探测之前调用）
+    ///
+    /// # 参数
+    /// * `plan` - 本次展开的探测范围
+    ///
+    /// # 返回
+    /// * `Ok(Some(SummaryItem))` - 指定了 `--from-plan` 且范围一致
+    /// * `Ok(None)` - 未指定 `--from-plan`
+    /// * `Err` - 导出失败，或范围与计划不一致
+    pub fn check(
+        &self,
+        plan: &ScanPlan,
+    ) -> Result<Option<SummaryItem>, Box<dyn Error + Send + Sync>> {
+        if let Some(ref path) = self.export_plan {
+            plan.export(path)?;
+        }
+        self.from_plan
+            .as_deref()
+            .map(|path| plan.verify(path))
+            .transpose()
+    }
+}
+
+/// 一次扫描展开后的探测范围
+pub struct ScanPlan<'a> {
+    module: &'a str,
+    targets: &'a TargetSet,
+    ports: Option<&'a [u16]>,
+    attempts: u32,
+    params: Vec<(String, String)>,
+}
+
+impl<'a> ScanPlan<'a> {
+    /// 按本次展开的目标及端口生成计划
+    ///
+    /// # 参数
+    /// * `module` - 模块名（如 "pentest portscan"）
+    /// * `targets` - 展开后的目标
+    /// * `ports` - 每个主机扫描的端口（不扫描端口的模块为 `None`）
+    /// * `attempts` - 每个探测的默认尝试次数（目标行上的 count 覆盖优先）
+    /// * `args` - 模块参数（写入汇总表作为参数快照，未设置的参数不写）
+    pub fn new(
+        module: &'a str,
+        targets: &'a TargetSet,
+        ports: Option<&'a [u16]>,
+        attempts: u32,
+        args: &impl Serialize,
+    ) -> Self {
+        Self {
+            module,
+            targets,
+            ports,
+            attempts: attempts.max(1),
+            params: param_snapshot(args),
+        }
+    }
+
+    /// 某个目标的预计探测数（端口数 × 尝试次数）
+    fn probes(&self, target: &Target) -> u64 {
+        let attempts = target.overrides.count.unwrap_or(self.attempts);
+        self.ports.map_or(1, <[u16]>::len) as u64 * u64::from(attempts)
+    }
+
+    /// 全部目标的预计探测数（按目标数计算，只逐个统计带次数覆盖的目标）
+    pub fn total_probes(&self) -> u64 {
+        let ports = self.ports.map_or(1, <[u16]>::len) as u64;
+        let default = self.targets.len() as u64 * u64::from(self.attempts);
+        self.targets
+            .overridden()
+            .filter_map(|(_, o)| o.count)
+            .fold(default, |total, count| {
+                total - u64::from(self.attempts) + u64::from(count)
+            })
+            * ports
+    }
+
+    /// 目标表的表头及逐个产生的数据行（每个目标一行，不预先展开目标）
+    fn target_rows(&self) -> (Vec<String>, impl Iterator<Item = Vec<String>> + '_) {
+        // 标签含全局标签（--tag）
+        let keys = self.targets.tag_keys();
+        let mut headers = ["IP地址", "来源写法", "别名"].map(String::from).to_vec();
+        headers.extend(keys.iter().cloned());
+        headers.push("参数覆盖".to_string());
+        if self.ports.is_some() {
+            headers.push("端口数".to_string());
+        }
+        headers.push("预计探测数".to_string());
+        let rows = self.targets.iter().map(move |target| {
+            let tags = self.targets.tags(&target.ip);
+            let mut row = vec![
+                target.ip.clone(),
+                target.sources.join("; "),
+                target.aliases().join(", "),
+            ];
+            row.extend(
+                keys.iter()
+                    .map(|k| tags.get(k).cloned().unwrap_or_default()),
+            );
+            row.push(if target.overrides.is_empty() {
+                String::new()
+            } else {
+                target.overrides.to_string()
+            });
+            if let Some(ports) = self.ports {
+                row.push(ports.len().to_string());
+            }
+            row.push(self.probes(&target).to_string());
+            row
+        });
+        (headers, rows)
+    }
+
+    /// 开始计算指纹：模块及目标表的表头
+    fn hasher(&self, headers: &[String]) -> Sha256 {
+        let mut hasher = Sha256::new();
+        hasher.update(self.module.as_bytes());
+        hasher.update(b"\n");
+        hash_row(&mut hasher, headers);
+        hasher
+    }
+
+    /// 结束计算指纹：目标行之后加上端口
+    fn finish(&self, mut hasher: Sha256) -> String {
+        if let Some(ports) = self.ports {
+            hasher.update(compress_ports(ports).as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    /// 计划指纹：目标、来源写法、标签、参数覆盖、端口及探测数的SHA-256
+    ///
+    /// 与目标表的内容一一对应，展开结果有任何不同（包括主机名解析到的IP变化）时指纹随之改变。
+    pub fn fingerprint(&self) -> String {
+        let (headers, rows) = self.target_rows();
+        let mut hasher = self.hasher(&headers);
+        for row in rows {
+            hash_row(&mut hasher, &row);
+        }
+        self.finish(hasher)
+    }
+
+    /// 汇总表各行（计划指纹一行先留空，目标表写完后补写）
+    fn summary_rows(&self) -> Vec<(String, String)> {
+        let mut summary = vec![
+            (MODULE_LABEL.to_string(), self.module.to_string()),
+            (FINGERPRINT_LABEL.to_string(), String::new()),
+            (
+                "生成时间".to_string(),
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            ),
+            ("目标数".to_string(), self.targets.len().to_string()),
+        ];
+        if let Some(ports) = self.ports {
+            summary.push(("端口数".to_string(), ports.len().to_string()));
+        }
+        summary.push(("预计探测总数".to_string(), self.total_probes().to_string()));
+        summary.extend(
+            self.params
+                .iter()
+                .map(|(key, value)| (format!("{}{}", PARAM_PREFIX, key), value.clone())),
+        );
+        summary
+    }
+
+    /// 写入计划文件：汇总、目标（及端口）表
+    ///
+    /// 目标行逐行写入（大范围不占用内存），写入的同时计算指纹，最后补写到汇总表。
+    ///
+    /// # 返回
+    /// * `Ok(String)` - 计划指纹
+    fn write(&self, path: &Path) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let mut workbook = Workbook::new();
+        let summary = self.summary_rows();
+        let fingerprint_row = summary
+            .iter()
+            .position(|(label, _)| label == FINGERPRINT_LABEL)
+            .unwrap_or_default() as u32
+            + 1;
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(SUMMARY_SHEET_NAME)?;
+        write_sheet(
+            worksheet,
+            &["项目", "内容"],
+            summary.iter().map(|(k, v)| [k, v]),
+            true,
+        )?;
+
+        let (headers, rows) = self.target_rows();
+        let mut hasher = self.hasher(&headers);
+        write_split_sheets(
+            &mut workbook,
+            Some(TARGETS_SHEET_NAME),
+            &headers,
+            rows.inspect(|row| hash_row(&mut hasher, row)),
+            true,
+            EXCEL_MAX_DATA_ROWS,
+        )?;
+        if let Some(ports) = self.ports {
+            write_split_sheets(
+                &mut workbook,
+                Some(PORTS_SHEET_NAME),
+                &["端口范围", "端口数"],
+                port_ranges(ports).into_iter().map(|range| {
+                    let count = u32::from(range.1) - u32::from(range.0) + 1;
+                    [format_port_range(range), count.to_string()]
+                }),
+                true,
+                EXCEL_MAX_DATA_ROWS,
+            )?;
+        }
+
+        let fingerprint = self.finish(hasher);
+        workbook
+            .worksheet_from_name(SUMMARY_SHEET_NAME)?
+            .write_string(fingerprint_row, 1, &fingerprint)?;
+        workbook.save(path)?;
+        Ok(fingerprint)
+    }
+
+    /// 导出计划（`--export-plan`）
+    ///
+    /// # 参数
+    /// * `path` - 计划文件路径
+    pub fn export(&self, path: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let fingerprint = self
+            .write(path)
+            .map_err(|e| format!("无法写入扫描计划 {}: {}", path.display(), e))?;
+        println!(
+            "{} 扫描计划已导出至: {}（{} 个目标，预计 {} 次探测，指纹 {}）",
+            Icon::Ok,
+            path.display(),
+            self.targets.len(),
+            self.total_probes(),
+            short(&fingerprint)
+        );
+        Ok(())
+    }
+
+    /// 核对已签批的计划（`--from-plan`），本次展开的范围与计划不一致时返回错误
+    ///
+    /// # 参数
+    /// * `path` - 计划文件路径
+    ///
+    /// # 返回
+    /// * `Ok(SummaryItem)` - 一致，返回写入统计摘要的一项
+    /// * `Err` - 无法读取计划、模块不同或展开的范围不一致
+    pub fn verify(&self, path: &Path) -> Result<SummaryItem, Box<dyn Error + Send + Sync>> {
+        let approved = ApprovedPlan::read(path)?;
+        if approved.module != self.module {
+            return Err(format!(
+                "计划 {} 属于模块 \"{}\"，不能用于 \"{}\"",
+                path.display(),
+                approved.module,
+                self.module
+            )
+            .into());
+        }
+        let fingerprint = self.fingerprint();
+        if approved.fingerprint != fingerprint {
+            // 按区间比较，不展开地址
+            let current = self.targets.ranges();
+            let current_v6: Vec<&str> = self.targets.v6_ips().collect();
+            let added = Diff::new(
+                &subtract_ranges(&current, &approved.ranges),
+                current_v6
+                    .iter()
+                    .copied()
+                    .filter(|ip| !approved.v6.contains(*ip))
+                    .collect(),
+            );
+            let removed = Diff::new(
+                &subtract_ranges(&approved.ranges, &current),
+                approved
+                    .v6
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|ip| !current_v6.contains(ip))
+                    .collect(),
+            );
+            let detail = if added.count == 0 && removed.count == 0 {
+                "目标相同，来源写法、标签、参数覆盖、端口或探测次数不同".to_string()
+            } else {
+                [("新增", &added), ("减少", &removed)]
+                    .into_iter()
+                    .filter(|(_, diff)| diff.count > 0)
+                    .map(|(what, diff)| format!("{} {} 个目标{}", what, diff.count, diff))
+                    .collect::<Vec<_>>()
+                    .join("，")
+            };
+            return Err(format!(
+                "当前参数展开的探测范围与计划 {} 不一致（计划指纹 {}，当前 {}）：{}，拒绝执行",
+                path.display(),
+                short(&approved.fingerprint),
+                short(&fingerprint),
+                detail
+            )
+            .into());
+        }
+        println!(
+            "{} 探测范围与已签批的计划一致: {}（指纹 {}）",
+            Icon::Ok,
+            path.display(),
+            short(&fingerprint)
+        );
+        Ok((
+            "扫描计划".to_string(),
+            format!("{}（指纹 {}）", path.display(), fingerprint),
+        ))
+    }
+}
+
+/// 从计划文件中读出的签批内容
+struct ApprovedPlan {
+    module: String,
+    fingerprint: String,
+    /// 计划中的IPv4目标：按地址排序、互不相交的闭区间
+    ranges: Vec<(u32, u32)>,
+    /// 计划中的其他目标（IPv6）
+    v6: HashSet<String>,
+}
+
+impl ApprovedPlan {
+    /// 读取计划文件的汇总表及目标表（含续写的目标表）
+    fn read(path: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let invalid = |what: &str| format!("扫描计划 {} 无效: {}", path.display(), what);
+        let mut workbook: Xlsx<_> = open_workbook(path)
+            .map_err(|e| format!("无法读取扫描计划 {}: {}", path.display(), e))?;
+        let summary = workbook
+            .worksheet_range(SUMMARY_SHEET_NAME)
+            .map_err(|_| invalid("缺少计划汇总表"))?;
+        let value = |label: &str| {
+            summary
+                .rows()
+                .find(|row| row.first().is_some_and(|c| *c == label))
+                .and_then(|row| row.get(1).map(|c| c.to_string()))
+        };
+        let module = value(MODULE_LABEL).ok_or_else(|| invalid("缺少模块"))?;
+        let fingerprint = value(FINGERPRINT_LABEL).ok_or_else(|| invalid("缺少计划指纹"))?;
+
+        let names = workbook.sheet_names();
+        let mut ranges: Vec<(u32, u32)> = Vec::new();
+        let mut v6 = HashSet::new();
+        for part in 1.. {
+            let name = split_sheet_name(TARGETS_SHEET_NAME, part);
+            if !names.contains(&name) {
+                break;
+            }
+            // 目标表可能有上百万行，逐个读取单元格，只取第一列
+            let mut cells = workbook
+                .worksheet_cells_reader(&name)
+                .map_err(|e| invalid(&e.to_string()))?;
+            while let Some(cell) = cells.next_cell().map_err(|e| invalid(&e.to_string()))? {
+                let (row, col) = cell.get_position();
+                if row == 0 || col != 0 {
+                    continue;
+                }
+                let Some(ip) = cell.get_value().as_string() else {
+                    continue;
+                };
+                match ip.parse::<Ipv4Addr>().map(u32::from) {
+                    // 目标表按地址排序，相邻的地址并入同一个区间
+                    Ok(ip) => match ranges.last_mut() {
+                        Some((_, end)) if end.checked_add(1) == Some(ip) => *end = ip,
+                        _ => ranges.push((ip, ip)),
+                    },
+                    Err(_) => {
+                        v6.insert(ip);
+                    }
+                }
+            }
+        }
+        Ok(Self {
+            module,
+            fingerprint,
+            ranges: merge_ranges(ranges),
+            v6,
+        })
+    }
+}
+
+/// 参数快照：模块参数中已设置的项（按参数名排序）
+fn param_snapshot(args: &impl Serialize) -> Vec<(String, String)> {
+    let Ok(Value::Object(values)) = serde_json::to_value(args) else {
+        return Vec::new();
+    };
+    values
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let text = match value {
+                Value::Null | Value::Bool(false) => return None,
+                Value::String(s) if s.is_empty() => return None,
+                Value::Array(ref items) if items.is_empty() => return None,
+                Value::String(s) => s,
+                Value::Array(items) => items
+                    .iter()
+                    .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                other => other.to_string(),
+            };
+            Some((format!("--{}", key.replace('_', "-")), text))
+        })
+        .collect()
+}
+
+/// 指纹的简写（前12位）
+fn short(fingerprint: &str) -> &str {
+    fingerprint.get(..12).unwrap_or(fingerprint)
+}
+
+/// 指纹中的一行（各列以制表符分隔）
+fn hash_row(hasher: &mut Sha256, row: &[String]) {
+    hasher.update(row.join("\t").as_bytes());
+    hasher.update(b"\n");
+}
+
+/// 计划与当前范围相差的目标：数量及前几个目标
+struct Diff {
+    count: usize,
+    samples: Vec<String>,
+}
+
+impl Diff {
+    /// 统计相差的目标
+    ///
+    /// # 参数
+    /// * `ranges` - 相差的IPv4区间
+    /// * `v6` - 相差的其他目标
+    fn new(ranges: &[(u32, u32)], v6: Vec<&str>) -> Self {
+        let count = ranges
+            .iter()
+            .map(|&(start, end)| (end - start) as usize + 1)
+            .sum::<usize>()
+            + v6.len();
+        let samples = ranges
+            .iter()
+            .flat_map(|&(start, end)| (start..=end).map(|ip| Ipv4Addr::from(ip).to_string()))
+            .chain(v6.into_iter().map(str::to_string))
+            .take(DIFF_SAMPLES)
+            .collect();
+        Self { count, samples }
+    }
+}
+
+impl std::fmt::Display for Diff {
+    /// 列出前几个目标（如 `（10.0.0.1, 10.0.0.2 等）`），没有时为空
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.samples.is_empty() {
+            return Ok(());
+        }
+        let more = if self.count > DIFF_SAMPLES {
+            " 等"
+        } else {
+            ""
+        };
+        write!(f, "（{}{}）", self.samples.join(", "), more)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::targets::{Tags, TargetOverrides};
+
+    #[derive(Serialize)]
+    struct DemoArgs {
+        ports: Option<String>,
+        timeout: u64,
+        live: bool,
+    }
+
+    fn targets(extra: &[&str]) -> TargetSet {
+        let mut set = TargetSet::default();
+        let tags = Tags::from([("系统".to_string(), "OA".to_string())]);
+        set.add_tagged("oa.example.com", vec!["10.0.0.1".to_string()], &tags);
+        set.add(
+            "10.0.0.0/31",
+            vec!["10.0.0.0".to_string(), "10.0.0.1".to_string()],
+        );
+        set.set_overrides(
+            "10.0.0.0/31",
+            TargetOverrides {
+                count: Some(2),
+                ..Default::default()
+            },
+        );
+        for ip in extra {
+            set.add(ip, vec![ip.to_string()]);
+        }
+        set
+    }
+
+    /// 按顺序读出计划文件各工作表的内容（含表头）
+    fn read_sheets(path: &Path) -> Vec<Vec<Vec<String>>> {
+        let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+        workbook
+            .worksheets()
+            .into_iter()
+            .map(|(_, range)| {
+                range
+                    .rows()
+                    .map(|row| row.iter().map(|c| c.to_string()).collect())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_round_trip_and_mismatch() {
+        let args = DemoArgs {
+            ports: Some("22,80-81".to_string()),
+            timeout: 3,
+            live: false,
+        };
+        let ports = [22, 80, 81];
+        let approved_set = targets(&[]);
+        let approved = ScanPlan::new("pentest portscan", &approved_set, Some(&ports), 1, &args);
+        // 3个端口 × 2次（参数覆盖）× 2个目标
+        assert_eq!(approved.total_probes(), 12);
+        let path = std::env::temp_dir().join(format!("gxr_plan_{}.xlsx", std::process::id()));
+        approved.export(&path).unwrap();
+        let sheets = read_sheets(&path);
+        let summary = &sheets[0];
+        assert!(summary.contains(&vec!["参数 --ports".to_string(), "22,80-81".to_string()]));
+        assert!(!summary.iter().any(|r| r[0] == "参数 --live"));
+        // 写入时计算的指纹与单独计算的一致
+        assert_eq!(summary[2], ["计划指纹".to_string(), approved.fingerprint()]);
+        let target_rows = &sheets[1];
+        // 目标按地址排序
+        assert_eq!(target_rows[1][0], "10.0.0.0");
+        assert_eq!(target_rows[2][0], "10.0.0.1");
+        assert_eq!(target_rows[2][1], "oa.example.com; 10.0.0.0/31");
+        assert_eq!(target_rows[2][3], "OA");
+        assert_eq!(sheets[2][1..], [["22", "1"], ["80-81", "2"]]);
+
+        let same_set = targets(&[]);
+        let same = ScanPlan::new("pentest portscan", &same_set, Some(&ports), 1, &args);
+        let verified = same.verify(&path);
+
+        let grown_set = targets(&["10.0.0.9"]);
+        let grown = ScanPlan::new("pentest portscan", &grown_set, Some(&ports), 1, &args);
+        let grown_err = grown.verify(&path).unwrap_err().to_string();
+        let fewer_ports = ScanPlan::new("pentest portscan", &same_set, Some(&ports[..2]), 1, &args);
+        let ports_err = fewer_ports.verify(&path).unwrap_err().to_string();
+        let other = ScanPlan::new("net ping", &same_set, None, 1, &args);
+        let module_err = other.verify(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).ok();
+
+        assert!(verified.unwrap().1.contains(&approved.fingerprint()));
+        assert!(
+            grown_err.contains("新增 1 个目标（10.0.0.9）"),
+            "{}",
+            grown_err
+        );
+        assert!(ports_err.contains("目标相同"), "{}", ports_err);
+        assert!(module_err.contains("不能用于"), "{}", module_err);
+    }
+
+    #[test]
+    fn test_plan_diff_by_ranges() {
+        let args = DemoArgs {
+            ports: None,
+            timeout: 3,
+            live: false,
+        };
+        let mut approved_set = TargetSet::default();
+        approved_set.add_ranges(
+            "10.0.0.0/20",
+            vec![(0x0a00_0000, 0x0a00_0fff)],
+            &Tags::new(),
+        );
+        let approved = ScanPlan::new("net ping", &approved_set, None, 1, &args);
+        let path = std::env::temp_dir().join(format!("gxr_plan_diff_{}.xlsx", std::process::id()));
+        approved.export(&path).unwrap();
+
+        let mut changed_set = approved_set.clone();
+        changed_set.remove_ranges(&[(0x0a00_0500, 0x0a00_05ff)]);
+        changed_set.add("::1", vec!["::1".to_string()]);
+        let changed = ScanPlan::new("net ping", &changed_set, None, 1, &args);
+        let err = changed.verify(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).ok();
+
+        assert!(err.contains("新增 1 个目标（::1）"), "{}", err);
+        assert!(
+            err.contains("减少 256 个目标（10.0.5.0, 10.0.5.1, 10.0.5.2, 10.0.5.3, 10.0.5.4 等）"),
+            "{}",
+            err
+        );
+    }
+}
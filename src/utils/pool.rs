@@ -0,0 +1,364 @@
+// src/utils/pool.rs
+use super::ScanProgress;
+use super::stats::Outcome;
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
+use std::future::Future;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+/// 有界并发执行探测任务，结果经由通道交给收集端
+///
+/// 任务按需从 `items` 中惰性取出，同一时刻最多 `concurrency` 个探测在执行；
+/// 不会为每个目标创建任务句柄，结果通过有界通道逐个交给 `collect` 处理，
+/// 因此目标数量再大也不会在分发阶段占用额外内存。
+///
+/// # 参数
+/// * `items` - 待探测的目标（可以是惰性迭代器）
+/// * `concurrency` - 最大并发数（0按1处理）
+/// * `probe` - 探测函数
+/// * `collect` - 结果收集函数（按完成顺序调用）
+///
+/// # 示例
+/// ```ignore
+/// let mut results = Vec::new();
+/// run_bounded(ips, 100, |ip| ping_host(&pinger, ip, opts), |r| results.push(r)).await;
+/// ```
+pub async fn run_bounded<I, F, Fut, C>(items: I, concurrency: usize, probe: F, mut collect: C)
+where
+    I: IntoIterator,
+    F: Fn(I::Item) -> Fut,
+    Fut: Future,
+    C: FnMut(Fut::Output),
+{
+    let (dispatch, results) = run_streamed(stream::iter(items), concurrency, probe);
+    let collector = results.for_each(|result| {
+        collect(result);
+        future::ready(())
+    });
+    tokio::join!(dispatch, collector);
+}
+
+/// 有界并发执行探测任务，结果以流的形式交给下一阶段
+///
+/// 与 [`run_bounded`] 相同，但目标来自异步流（可以是上一阶段的结果流），
+/// 结果也不交给回调，而是按完成顺序从返回的结果流中取出，
+/// 下一阶段可以边探测边消费，不必等本阶段全部完成。
+///
+/// 返回的分发任务与结果流须同时驱动（如 `tokio::join!`）。两者之间是有界通道：
+/// 下一阶段消费得慢时通道写满，本阶段随之停止分发新的探测（背压），不会无限积压结果。
+/// 全部探测结束后结果流终止。
+///
+/// # 参数
+/// * `items` - 待探测的目标流
+/// * `concurrency` - 最大并发数（0按1处理）
+/// * `probe` - 探测函数
+///
+/// # 返回
+/// * `(分发任务, 结果流)`
+pub fn run_streamed<S, F, Fut>(
+    items: S,
+    concurrency: usize,
+    mut probe: F,
+) -> (impl Future<Output = ()>, impl Stream<Item = Fut::Output>)
+where
+    S: Stream,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future,
+{
+    let concurrency = concurrency.max(1);
+    let (tx, mut rx) = mpsc::channel(concurrency * 2);
+
+    // 分发结束时闭包连同其中的发送端一起释放，结果流随之终止
+    let dispatch = items.for_each_concurrent(concurrency, move |item| {
+        let tx = tx.clone();
+        let fut = probe(item);
+        async move {
+            // 发送失败只可能是下一阶段已不再消费
+            let _ = tx.send(fut.await).await;
+        }
+    });
+    let results = stream::poll_fn(move |cx| rx.poll_recv(cx));
+
+    (dispatch, results)
+}
+
+/// 与 [`run_bounded`] 相同，并把每个任务计入进度条的扫描统计
+///
+/// 任务开始执行时计为已分发，`collect` 返回该结果的结论后计为已完成，
+/// 进度条随之推进，调用方不再自行 `inc`。
+///
+/// `cancel` 被取消后不再分发新的任务；进行中的探测与令牌竞速，
+/// 取消时立即丢弃（其持有的连接随之关闭），不调用 `collect`，计为未完成。
+/// 已收集的结果不受影响。
+///
+/// # 参数
+/// * `progress` - 本阶段的进度条（带扫描统计）
+/// * `cancel` - 本阶段的取消令牌（通常为 [`ScanContext::token`](super::context::ScanContext::token)）
+///
+/// 其余参数同 [`run_bounded`]
+pub async fn run_tracked<I, F, Fut, C>(
+    items: I,
+    concurrency: usize,
+    progress: &ScanProgress,
+    cancel: &CancellationToken,
+    probe: F,
+    collect: C,
+) where
+    I: IntoIterator,
+    F: Fn(I::Item) -> Fut,
+    Fut: Future,
+    C: FnMut(Fut::Output) -> Outcome,
+{
+    let stage = run_tracked_streamed(stream::iter(items), concurrency, progress, cancel, probe);
+    collect_tracked(stage, progress, collect).await;
+}
+
+/// 与 [`run_streamed`] 相同，并把每个任务计入进度条的扫描统计
+///
+/// 任务开始执行时计为已分发；被取消的探测在结果流中为 `None`。
+/// 结论由消费方记入进度条（见 [`collect_tracked`]），取消的语义同 [`run_tracked`]。
+///
+/// # 参数
+/// * `items` - 待探测的目标流
+/// * `concurrency` - 最大并发数（0按1处理）
+/// * `progress` - 本阶段的进度条（带扫描统计）
+/// * `cancel` - 本阶段的取消令牌
+/// * `probe` - 探测函数
+///
+/// # 返回
+/// * `(分发任务, 结果流)`
+pub fn run_tracked_streamed<'a, S, F, Fut>(
+    items: S,
+    concurrency: usize,
+    progress: &'a ScanProgress,
+    cancel: &'a CancellationToken,
+    mut probe: F,
+) -> (
+    impl Future<Output = ()> + 'a,
+    impl Stream<Item = Option<Fut::Output>> + 'a,
+)
+where
+    S: Stream + 'a,
+    F: FnMut(S::Item) -> Fut + 'a,
+    Fut: Future + 'a,
+{
+    run_streamed(
+        items.take_while(move |_| future::ready(!cancel.is_cancelled())),
+        concurrency,
+        move |item| {
+            progress.stats().dispatch();
+            let fut = probe(item);
+            async move {
+                tokio::select! {
+                    biased;
+                    _ = cancel.cancelled() => None,
+                    result = fut => Some(result),
+                }
+            }
+        },
+    )
+}
+
+/// 驱动 [`run_tracked_streamed`] 返回的阶段直到结束，逐个收集结果并记入进度条
+///
+/// # 参数
+/// * `stage` - `(分发任务, 结果流)`
+/// * `progress` - 本阶段的进度条
+/// * `collect` - 结果收集函数，返回该结果的结论（被取消的探测不调用，计为未完成）
+pub async fn collect_tracked<D, R, T, C>(stage: (D, R), progress: &ScanProgress, mut collect: C)
+where
+    D: Future<Output = ()>,
+    R: Stream<Item = Option<T>>,
+    C: FnMut(T) -> Outcome,
+{
+    let (dispatch, results) = stage;
+    let collector = results.for_each(|result| {
+        match result {
+            Some(result) => progress.record(collect(result)),
+            None => progress.record(Outcome::Cancelled),
+        }
+        future::ready(())
+    });
+    tokio::join!(dispatch, collector);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn test_run_bounded_collects_all() {
+        let mut results = Vec::new();
+        run_bounded(
+            0..1000u32,
+            16,
+            |i| async move { i * 2 },
+            |r| results.push(r),
+        )
+        .await;
+        results.sort_unstable();
+        assert_eq!(results, (0..1000u32).map(|i| i * 2).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_run_bounded_respects_concurrency() {
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let mut count = 0;
+
+        run_bounded(
+            0..200u32,
+            8,
+            |_| async {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(1)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+            },
+            |_| count += 1,
+        )
+        .await;
+
+        assert_eq!(count, 200);
+        assert!(peak.load(Ordering::SeqCst) <= 8);
+    }
+
+    #[tokio::test]
+    async fn test_run_tracked_matches_results() {
+        let progress = ScanProgress::new(100);
+        progress.set_hidden(true);
+        let mut rows = Vec::new();
+        run_tracked(
+            0..100u32,
+            8,
+            &progress,
+            &CancellationToken::new(),
+            |i| async move { i },
+            |i| {
+                // 模拟结果行：能被3整除的出错、偶数成功、其余失败，出错的不产生结果行
+                let outcome = match i {
+                    i if i % 3 == 0 => Outcome::Errored,
+                    i if i % 2 == 0 => Outcome::Succeeded,
+                    _ => Outcome::Failed,
+                };
+                if outcome != Outcome::Errored {
+                    rows.push((i, outcome));
+                }
+                outcome
+            },
+        )
+        .await;
+
+        let stats = progress.snapshot();
+        let count = |o: Outcome| rows.iter().filter(|(_, r)| *r == o).count() as u64;
+        assert_eq!(stats.dispatched, 100);
+        assert_eq!(stats.completed, 100);
+        assert_eq!(progress.position(), 100);
+        assert_eq!(stats.succeeded, count(Outcome::Succeeded));
+        assert_eq!(stats.failed, count(Outcome::Failed));
+        assert_eq!(stats.errored + rows.len() as u64, 100);
+    }
+
+    #[tokio::test]
+    async fn test_run_tracked_cancel_mid_flight() {
+        // 一半任务立即完成，另一半挂起直到被取消
+        let progress = ScanProgress::new(1000);
+        progress.set_hidden(true);
+        let cancel = CancellationToken::new();
+        let live = Arc::new(AtomicUsize::new(0));
+        let mut rows = Vec::new();
+
+        let canceller = {
+            let cancel = cancel.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                cancel.cancel();
+            })
+        };
+        let started = Instant::now();
+        run_tracked(
+            0..1000u32,
+            16,
+            &progress,
+            &cancel,
+            |i| {
+                let guard = LiveGuard::new(&live);
+                async move {
+                    if i % 2 == 1 {
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                    }
+                    drop(guard);
+                    i
+                }
+            },
+            |i| {
+                rows.push(i);
+                Outcome::Succeeded
+            },
+        )
+        .await;
+        canceller.await.unwrap();
+
+        // 运行结束时没有残留的探测，且远早于挂起探测的超时
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(live.load(Ordering::SeqCst), 0);
+
+        let stats = progress.snapshot();
+        assert!(rows.iter().all(|i| i % 2 == 0));
+        assert_eq!(stats.completed, rows.len() as u64);
+        assert_eq!(stats.succeeded, rows.len() as u64);
+        assert_eq!(stats.failed, 0);
+        assert!(stats.cancelled > 0);
+        assert_eq!(stats.completed + stats.cancelled, stats.dispatched);
+        assert!(stats.dispatched < 1000);
+        assert_eq!(progress.position(), stats.completed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_streamed_applies_backpressure() {
+        // 下一阶段消费得慢时，本阶段最多领先通道容量加并发数个结果
+        let started = AtomicUsize::new(0);
+        let (dispatch, results) = run_streamed(stream::iter(0..100u32), 4, |i| {
+            started.fetch_add(1, Ordering::SeqCst);
+            async move { i }
+        });
+        let mut consumed = 0;
+        let mut lead = 0;
+        let consumer = results.for_each(|_| {
+            consumed += 1;
+            lead = lead.max(started.load(Ordering::SeqCst) - consumed);
+            tokio::time::sleep(Duration::from_millis(10))
+        });
+        tokio::join!(dispatch, consumer);
+
+        assert_eq!(consumed, 100);
+        assert!(lead <= 4 * 2 + 4, "{}", lead);
+    }
+
+    /// 统计存活的探测（创建时加一，丢弃时减一）
+    struct LiveGuard(Arc<AtomicUsize>);
+
+    impl LiveGuard {
+        fn new(live: &Arc<AtomicUsize>) -> Self {
+            live.fetch_add(1, Ordering::SeqCst);
+            Self(live.clone())
+        }
+    }
+
+    impl Drop for LiveGuard {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_bounded_zero_concurrency() {
+        let mut count = 0;
+        run_bounded(0..10u32, 0, |i| async move { i }, |_| count += 1).await;
+        assert_eq!(count, 10);
+    }
+}
@@ -0,0 +1,113 @@
+// src/utils/process.rs
+use std::io;
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+/// 外部命令执行结果
+#[derive(Debug)]
+pub enum CommandOutcome {
+    /// 命令在时限内结束
+    Finished(Output),
+    /// 超过时限，子进程已被终止
+    TimedOut,
+}
+
+/// 在硬性时限内执行外部命令并收集输出
+///
+/// 超时后显式调用 `kill()` 终止子进程并等待其退出，
+/// 不依赖命令自身的超时参数（部分环境下 ping 的 `-w` 会失效）。
+///
+/// # 参数
+/// * `cmd` - 待执行的命令
+/// * `limit` - 硬性时限
+///
+/// # 返回
+/// * `Ok(CommandOutcome)` - 命令结束或超时
+/// * `Err` - 命令无法启动或读取输出失败
+pub async fn output_with_timeout(cmd: &mut Command, limit: Duration) -> io::Result<CommandOutcome> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let mut stdout_pipe = child.stdout.take();
+    let mut stderr_pipe = child.stderr.take();
+
+    let finished = {
+        let wait = async {
+            let mut stdout = Vec::new();
+            let mut stderr = Vec::new();
+            // 同时读取两个管道，避免任一管道写满导致子进程阻塞
+            let read_stdout = async {
+                match stdout_pipe {
+                    Some(ref mut pipe) => pipe.read_to_end(&mut stdout).await.map(|_| ()),
+                    None => Ok(()),
+                }
+            };
+            let read_stderr = async {
+                match stderr_pipe {
+                    Some(ref mut pipe) => pipe.read_to_end(&mut stderr).await.map(|_| ()),
+                    None => Ok(()),
+                }
+            };
+            tokio::try_join!(read_stdout, read_stderr)?;
+            let status = child.wait().await?;
+            Ok::<_, io::Error>(Output {
+                status,
+                stdout,
+                stderr,
+            })
+        };
+        timeout(limit, wait).await
+    };
+
+    match finished {
+        Ok(output) => Ok(CommandOutcome::Finished(output?)),
+        Err(_) => {
+            // 终止并回收子进程，避免残留僵尸进程
+            let _ = child.kill().await;
+            Ok(CommandOutcome::TimedOut)
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_output_with_timeout_finished() {
+        let outcome = output_with_timeout(
+            Command::new("sh").args(["-c", "echo hello"]),
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+        match outcome {
+            CommandOutcome::Finished(out) => {
+                assert!(out.status.success());
+                assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "hello");
+            }
+            CommandOutcome::TimedOut => panic!("命令不应超时"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_output_with_timeout_kills_hung_child() {
+        let start = Instant::now();
+        let outcome = output_with_timeout(
+            Command::new("sh").args(["-c", "sleep 1000"]),
+            Duration::from_millis(200),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(outcome, CommandOutcome::TimedOut));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,113 @@
+// src/utils/provenance.rs
+use super::format_duration;
+use chrono::{DateTime, FixedOffset, Local, SecondsFormat};
+use serde::{Deserialize, Serialize};
+
+/// 结果的观测来源：何时、由哪种探测得出结论
+///
+/// 事后与客户自己的日志对照时使用。各模块的结果及发现都带有这两项，JSON中展开为
+/// `observed_at`、`probe` 字段；Excel、CSV中固定为最后两列，不影响按位置引用已有列的表格。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Provenance {
+    /// 观测时间（RFC3339，本地时区，带偏移）
+    pub observed_at: String,
+    /// 得出结论的探测（如 `icmp`、`tcp-connect:3389`、`http-get:/actuator`）
+    pub probe: String,
+}
+
+impl Provenance {
+    /// Excel中的列名
+    pub const HEADERS: [&'static str; 2] = ["观测时间", "探测方式"];
+
+    /// 此刻得出的观测
+    ///
+    /// # 参数
+    /// * `probe` - 探测方式
+    pub fn now(probe: impl Into<String>) -> Self {
+        Self {
+            observed_at: timestamp(Local::now()),
+            probe: probe.into(),
+        }
+    }
+
+    /// 对应 [`HEADERS`](Self::HEADERS) 的单元格
+    pub fn cells(&self) -> [&str; 2] {
+        [&self.observed_at, &self.probe]
+    }
+}
+
+/// 观测时间的写法（RFC3339，本地时区，精确到毫秒）
+pub fn timestamp(time: DateTime<Local>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, false)
+}
+
+/// 说明一批基线结果是多久以前观测的（比较基线时提示基线的陈旧程度）
+///
+/// # 参数
+/// * `observed` - 各结果的观测时间（无法解析的忽略，早期版本的结果没有观测时间）
+/// * `now` - 当前时间
+///
+/// # 返回
+/// * `Some(说明)` - 如 `观测于 2024-01-02 10:00:00 ~ 10:20:00，距今 3天2小时`
+/// * `None` - 没有可用的观测时间
+pub fn describe_staleness<'a>(
+    observed: impl IntoIterator<Item = &'a str>,
+    now: DateTime<Local>,
+) -> Option<String> {
+    let times: Vec<DateTime<FixedOffset>> = observed
+        .into_iter()
+        .filter_map(|t| DateTime::parse_from_rfc3339(t).ok())
+        .collect();
+    let first = times.iter().min()?.with_timezone(&Local);
+    let last = times.iter().max()?.with_timezone(&Local);
+    let range = if last.date_naive() == first.date_naive() && last != first {
+        format!(
+            "{} ~ {}",
+            first.format("%Y-%m-%d %H:%M:%S"),
+            last.format("%H:%M:%S")
+        )
+    } else if last != first {
+        format!(
+            "{} ~ {}",
+            first.format("%Y-%m-%d %H:%M:%S"),
+            last.format("%Y-%m-%d %H:%M:%S")
+        )
+    } else {
+        first.format("%Y-%m-%d %H:%M:%S").to_string()
+    };
+    let age = (now - last).to_std().unwrap_or_default();
+    Some(format!("观测于 {}，距今 {}", range, format_age(age)))
+}
+
+/// 距今的时长：不足一天按 [`format_duration`]，否则按天及小时
+fn format_age(age: std::time::Duration) -> String {
+    let hours = age.as_secs() / 3600;
+    if hours < 24 {
+        format_duration(age)
+    } else {
+        format!("{}天{}小时", hours / 24, hours % 24)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_staleness_of_baseline_observations() {
+        let at = |h, m| timestamp(Local.with_ymd_and_hms(2024, 1, 2, h, m, 0).unwrap());
+        let now = Local.with_ymd_and_hms(2024, 1, 5, 12, 20, 0).unwrap();
+        let (early, late) = (at(10, 0), at(10, 20));
+        assert_eq!(
+            describe_staleness([late.as_str(), "", early.as_str()], now).as_deref(),
+            Some("观测于 2024-01-02 10:00:00 ~ 10:20:00，距今 3天2小时")
+        );
+        assert_eq!(describe_staleness(["旧版本"], now), None);
+
+        let observed = Provenance::now("tcp-connect:3389");
+        assert!(DateTime::parse_from_rfc3339(&observed.observed_at).is_ok());
+        assert_eq!(observed.cells()[1], "tcp-connect:3389");
+    }
+}
@@ -0,0 +1,76 @@
+// src/utils/quic.rs
+use crate::utils::tls::insecure_client_config;
+use quinn::crypto::rustls::{HandshakeData, QuicClientConfig};
+use quinn::{ClientConfig, Endpoint};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// QUIC握手探测器（不校验证书，握手完成即关闭连接）
+///
+/// 所有探测共用一个本地UDP端点，IPv6端点在本机不支持时不创建。
+pub struct QuicProber {
+    v4: Endpoint,
+    v6: Option<Endpoint>,
+}
+
+impl QuicProber {
+    /// 创建探测器
+    ///
+    /// # 参数
+    /// * `alpn` - 握手时提供的应用层协议（如 `b"h3"`）
+    pub fn new(alpn: &[&[u8]]) -> io::Result<Self> {
+        let crypto = QuicClientConfig::try_from(insecure_client_config(alpn))
+            .map_err(|e| io::Error::other(format!("QUIC配置无效: {}", e)))?;
+        let config = ClientConfig::new(Arc::new(crypto));
+        let bind = |addr: &str| -> io::Result<Endpoint> {
+            let mut endpoint = Endpoint::client(addr.parse().expect("本地地址格式固定"))?;
+            endpoint.set_default_client_config(config.clone());
+            Ok(endpoint)
+        };
+        Ok(Self {
+            v4: bind("0.0.0.0:0")?,
+            v6: bind("[::]:0").ok(),
+        })
+    }
+
+    /// 进行一次QUIC握手
+    ///
+    /// # 参数
+    /// * `addr` - 目标地址（UDP）
+    /// * `host` - 服务器名称或IP
+    /// * `timeout` - 握手超时
+    ///
+    /// # 返回
+    /// * `Ok(Some(协议))` - 握手成功，服务端选择的应用层协议
+    /// * `Err` - 超时、被拒绝或协议不匹配
+    pub async fn handshake(
+        &self,
+        addr: SocketAddr,
+        host: &str,
+        timeout: Duration,
+    ) -> io::Result<Option<String>> {
+        let endpoint = if addr.is_ipv6() {
+            self.v6
+                .as_ref()
+                .ok_or_else(|| io::Error::other("本机不支持IPv6 UDP"))?
+        } else {
+            &self.v4
+        };
+        let connecting = endpoint
+            .connect(addr, host)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        let connection = tokio::time::timeout(timeout, connecting)
+            .await
+            .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))?
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        let protocol = connection
+            .handshake_data()
+            .and_then(|data| data.downcast::<HandshakeData>().ok())
+            .and_then(|data| data.protocol)
+            .map(|p| String::from_utf8_lossy(&p).to_string());
+        connection.close(0u32.into(), b"");
+        Ok(protocol)
+    }
+}
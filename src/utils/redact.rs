@@ -0,0 +1,449 @@
+// src/utils/redact.rs
+use regex::Regex;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, OnceLock};
+
+/// 默认的对照表文件名（位于输出根目录下）
+pub const DEFAULT_MAPPING_FILE_NAME: &str = "redaction_map.csv";
+
+/// 对照表的表头
+const MAPPING_HEADERS: &str = "类型,替换值,原始值";
+
+/// 化名中哈希部分的十六进制位数
+const PSEUDONYM_HEX_CHARS: usize = 6;
+
+/// 属于证据的字段（JSON键），默认删除
+const EVIDENCE_KEYS: &[&str] = &[
+    "banner",
+    "evidence",
+    "auth_evidence",
+    "title",
+    "server",
+    "matches",
+    "excerpts",
+    "alt_svc",
+];
+
+/// 属于证据的列（Excel表头），默认删除
+const EVIDENCE_HEADERS: &[&str] = &[
+    "Banner",
+    "标题",
+    "Server",
+    "证据",
+    "推测依据",
+    "匿名查询结果",
+    "签名检查应答",
+    "Alt-Svc",
+];
+
+/// 内容为主机名的字段（JSON键）
+const HOSTNAME_KEYS: &[&str] = &["hostname", "dns_host_name", "domain", "aliases", "ptr"];
+
+/// 内容为主机名的列（Excel表头）
+const HOSTNAME_HEADERS: &[&str] = &["主机名", "域", "别名"];
+
+static IPV4: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:\d{1,3}\.){3}\d{1,3}\b").expect("IPv4正则有效"));
+
+/// URL中的主机部分（域名或方括号内的IPv6）
+static URL_HOST: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b([a-z][a-z0-9+.-]*://)(\[[0-9a-f:.]+\]|[a-z0-9_-]+(?:\.[a-z0-9_-]+)*)")
+        .expect("URL正则有效")
+});
+
+/// 对照表中的一项
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MappingEntry {
+    kind: &'static str,
+    original: String,
+}
+
+/// 结果脱敏
+///
+/// IP地址按所属网段替换为化名（10.0.0.0/8 → `NET-A-xxxxxx`），主机名替换为 `HOST-xxxxxxxx`，
+/// 化名由盐值和原始值的哈希决定：同一次项目的多份报告使用相同盐值时化名一致。
+/// 证据类字段（Banner、标题等）默认删除。所有替换都记入对照表，另行保存以便日后还原。
+#[derive(Debug)]
+pub struct Redactor {
+    salt: String,
+    keep_evidence: bool,
+    mapping_file: Option<PathBuf>,
+    mapping: Mutex<BTreeMap<String, MappingEntry>>,
+}
+
+impl Redactor {
+    /// 创建脱敏器
+    ///
+    /// # 参数
+    /// * `salt` - 盐值（同一项目的各份报告应使用相同的盐值）
+    /// * `keep_evidence` - 是否保留证据类字段
+    pub fn new(salt: &str, keep_evidence: bool) -> Self {
+        Self {
+            salt: salt.to_string(),
+            keep_evidence,
+            mapping_file: None,
+            mapping: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// 指定对照表文件：已有的对照表会被读入，之后每次保存都包含其中的全部条目
+    ///
+    /// # 返回
+    /// * `Err` - 对照表存在但无法读取
+    pub fn with_mapping_file(
+        mut self,
+        path: PathBuf,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        if path.exists() {
+            let text = fs::read_to_string(&path)
+                .map_err(|e| format!("无法读取对照表 {}: {}", path.display(), e))?;
+            let mut mapping = self.mapping.lock().unwrap();
+            for line in text.trim_start_matches('\u{feff}').lines().skip(1) {
+                let mut cells = line.splitn(3, ',');
+                let (Some(kind), Some(pseudonym), Some(original)) =
+                    (cells.next(), cells.next(), cells.next())
+                else {
+                    continue;
+                };
+                let kind = if kind == "IP" { "IP" } else { "主机名" };
+                mapping.insert(
+                    pseudonym.to_string(),
+                    MappingEntry {
+                        kind,
+                        original: original.to_string(),
+                    },
+                );
+            }
+        }
+        self.mapping_file = Some(path);
+        Ok(self)
+    }
+
+    /// 是否保留证据类字段
+    pub fn keep_evidence(&self) -> bool {
+        self.keep_evidence
+    }
+
+    /// 对照表文件
+    pub fn mapping_file(&self) -> Option<&Path> {
+        self.mapping_file.as_deref()
+    }
+
+    /// IP地址的化名
+    pub fn ip(&self, ip: IpAddr) -> String {
+        let net = match ip {
+            IpAddr::V4(v4) if v4.octets()[0] == 10 => "A",
+            IpAddr::V4(v4) if v4.octets()[0] == 172 && (16..32).contains(&v4.octets()[1]) => "B",
+            IpAddr::V4(v4) if v4.octets()[..2] == [192, 168] => "C",
+            IpAddr::V4(v4) if v4.is_loopback() => "L",
+            IpAddr::V4(_) => "X",
+            IpAddr::V6(_) => "6",
+        };
+        let pseudonym = format!(
+            "NET-{}-{}",
+            net,
+            self.digest(&ip.to_string(), PSEUDONYM_HEX_CHARS)
+        );
+        self.remember(&pseudonym, "IP", ip.to_string());
+        pseudonym
+    }
+
+    /// 主机名的化名（不区分大小写，忽略末尾的点）
+    pub fn hostname(&self, name: &str) -> String {
+        let name = name.trim().trim_end_matches('.');
+        // 空值及 "-" 等占位符原样保留
+        if !name.chars().any(char::is_alphanumeric) {
+            return name.to_string();
+        }
+        if let Ok(ip) = name.parse::<IpAddr>() {
+            return self.ip(ip);
+        }
+        let name = name.to_ascii_lowercase();
+        let pseudonym = format!("HOST-{}", self.digest(&name, PSEUDONYM_HEX_CHARS + 2));
+        self.remember(&pseudonym, "主机名", name);
+        pseudonym
+    }
+
+    /// 替换文本中出现的IP地址及URL中的主机名
+    pub fn text(&self, text: &str) -> String {
+        if let Ok(ip) = text.trim().parse::<IpAddr>() {
+            return self.ip(ip);
+        }
+        let text = URL_HOST.replace_all(text, |caps: &regex::Captures| {
+            let host = &caps[2];
+            let replaced = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+                Ok(ip) => self.ip(ip),
+                Err(_) if host.contains('.') => self.hostname(host),
+                Err(_) => host.to_string(),
+            };
+            format!("{}{}", &caps[1], replaced)
+        });
+        IPV4.replace_all(&text, |caps: &regex::Captures| {
+            match caps[0].parse::<IpAddr>() {
+                Ok(ip) => self.ip(ip),
+                Err(_) => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+    }
+
+    /// 脱敏一条JSON结果（删除证据字段，替换IP及主机名）
+    pub fn value(&self, value: &Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .filter(|(key, _)| self.keep_evidence || !EVIDENCE_KEYS.contains(&key.as_str()))
+                    .map(|(key, v)| {
+                        let v = if HOSTNAME_KEYS.contains(&key.as_str()) {
+                            self.hostnames(v)
+                        } else {
+                            self.value(v)
+                        };
+                        (key.clone(), v)
+                    })
+                    .collect::<Map<String, Value>>(),
+            ),
+            Value::Array(items) => Value::Array(items.iter().map(|v| self.value(v)).collect()),
+            Value::String(s) => Value::String(self.text(s)),
+            other => other.clone(),
+        }
+    }
+
+    /// 脱敏一张表格（删除证据列，替换IP及主机名）
+    ///
+    /// # 参数
+    /// * `headers` - 表头
+    /// * `rows` - 数据行
+    ///
+    /// # 返回
+    /// * `(表头, 数据行)` - 脱敏后的表格
+    pub fn table(
+        &self,
+        headers: &[&str],
+        rows: impl Iterator<Item = Vec<String>>,
+    ) -> (Vec<String>, Vec<Vec<String>>) {
+        let keep = self.kept_columns(headers);
+        let kept_headers = headers
+            .iter()
+            .zip(&keep)
+            .filter(|(_, k)| **k)
+            .map(|(h, _)| h.to_string())
+            .collect();
+        let rows = rows
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(i, _)| keep.get(*i).copied().unwrap_or(true))
+                    .map(|(i, cell)| self.cell(headers.get(i).copied(), cell))
+                    .collect()
+            })
+            .collect();
+        (kept_headers, rows)
+    }
+
+    /// 各列是否保留（不保留证据时删除证据列）
+    pub fn kept_columns(&self, headers: &[&str]) -> Vec<bool> {
+        headers
+            .iter()
+            .map(|h| self.keep_evidence || !EVIDENCE_HEADERS.contains(h))
+            .collect()
+    }
+
+    /// 脱敏一个单元格：主机名列逐个替换主机名，其余列替换其中的IP及主机名
+    ///
+    /// # 参数
+    /// * `header` - 所在列的表头
+    /// * `value` - 单元格内容
+    pub fn cell(&self, header: Option<&str>, value: &str) -> String {
+        if header.is_some_and(|h| HOSTNAME_HEADERS.contains(&h)) {
+            value
+                .split(", ")
+                .map(|name| self.hostname(name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        } else {
+            self.text(value)
+        }
+    }
+
+    /// 对照表（CSV，带BOM便于Excel打开）
+    pub fn mapping_csv(&self) -> String {
+        let mut out = format!("\u{feff}{}\r\n", MAPPING_HEADERS);
+        for (pseudonym, entry) in self.mapping.lock().unwrap().iter() {
+            out.push_str(&format!(
+                "{},{},{}\r\n",
+                entry.kind, pseudonym, entry.original
+            ));
+        }
+        out
+    }
+
+    /// 把对照表写入指定的对照表文件（未指定时不写）
+    ///
+    /// # 返回
+    /// * `Ok(Some(路径))` - 写入的文件
+    pub fn save_mapping(&self) -> Result<Option<PathBuf>, Box<dyn Error + Send + Sync>> {
+        let Some(ref path) = self.mapping_file else {
+            return Ok(None);
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.mapping_csv())
+            .map_err(|e| format!("无法写入对照表 {}: {}", path.display(), e))?;
+        Ok(Some(path.clone()))
+    }
+
+    /// 主机名字段：字符串或字符串数组
+    fn hostnames(&self, value: &Value) -> Value {
+        match value {
+            Value::String(s) => Value::String(self.hostname(s)),
+            Value::Array(items) => Value::Array(items.iter().map(|v| self.hostnames(v)).collect()),
+            other => self.value(other),
+        }
+    }
+
+    fn digest(&self, value: &str, chars: usize) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.salt.as_bytes());
+        hasher.update([0]);
+        hasher.update(value.as_bytes());
+        hex::encode(hasher.finalize())[..chars].to_string()
+    }
+
+    fn remember(&self, pseudonym: &str, kind: &'static str, original: String) {
+        self.mapping
+            .lock()
+            .unwrap()
+            .entry(pseudonym.to_string())
+            .or_insert(MappingEntry { kind, original });
+    }
+}
+
+static REDACTOR: OnceLock<Redactor> = OnceLock::new();
+
+/// 启用导出脱敏（仅首次设置生效，应在程序启动时调用）
+pub fn set_redactor(redactor: Redactor) {
+    let _ = REDACTOR.set(redactor);
+}
+
+/// 导出脱敏器，未开启 `--redact-output` 时为 `None`
+pub fn redactor() -> Option<&'static Redactor> {
+    REDACTOR.get()
+}
+
+/// 导出脱敏开启时更新对照表文件（每次导出后调用）
+pub fn save_export_mapping() -> Result<(), Box<dyn Error + Send + Sync>> {
+    if let Some(r) = redactor() {
+        r.save_mapping()?;
+    }
+    Ok(())
+}
+
+/// 要写出的JSON内容（开启导出脱敏时先脱敏）
+pub fn export_json<T: serde::Serialize + ?Sized>(
+    value: &T,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    Ok(match redactor() {
+        Some(r) => serde_json::to_string_pretty(&r.value(&serde_json::to_value(value)?))?,
+        None => serde_json::to_string_pretty(value)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_pseudonyms_are_stable_per_salt() {
+        let a = Redactor::new("engagement-1", false);
+        let b = Redactor::new("engagement-1", false);
+        let c = Redactor::new("engagement-2", false);
+        let ip: IpAddr = "10.1.2.3".parse().unwrap();
+        assert!(a.ip(ip).starts_with("NET-A-"));
+        assert_eq!(a.ip(ip), b.ip(ip));
+        assert_ne!(a.ip(ip), c.ip(ip));
+        assert!(a.ip("192.168.1.1".parse().unwrap()).starts_with("NET-C-"));
+        assert!(a.ip("172.20.0.1".parse().unwrap()).starts_with("NET-B-"));
+        assert_eq!(
+            a.hostname("DC01.Corp.Local."),
+            a.hostname("dc01.corp.local")
+        );
+        assert!(a.hostname("dc01.corp.local").starts_with("HOST-"));
+    }
+
+    #[test]
+    fn test_redacts_values_tables_and_records_mapping() {
+        let r = Redactor::new("s", false);
+        let row = json!({
+            "ip": "10.0.0.5",
+            "port": 443,
+            "url": "https://portal.corp.local:443/login?next=10.0.0.6",
+            "banner": "SSH-2.0-OpenSSH_8.0",
+            "aliases": ["web01.corp.local"],
+            "nested": {"egress": "203.0.113.9", "title": "内部门户"},
+        });
+        let out = r.value(&row);
+        let ip = r.ip("10.0.0.5".parse().unwrap());
+        assert_eq!(out["ip"], ip.as_str());
+        assert_eq!(out["port"], 443);
+        assert_eq!(
+            out["url"],
+            format!(
+                "https://{}:443/login?next={}",
+                r.hostname("portal.corp.local"),
+                r.ip("10.0.0.6".parse().unwrap())
+            )
+        );
+        assert!(out.get("banner").is_none() && out["nested"].get("title").is_none());
+        assert_eq!(out["aliases"][0], r.hostname("web01.corp.local"));
+        assert!(
+            out["nested"]["egress"]
+                .as_str()
+                .unwrap()
+                .starts_with("NET-X-")
+        );
+
+        let (headers, rows) = r.table(
+            &["IP地址", "Banner", "别名"],
+            vec![vec![
+                "10.0.0.5".to_string(),
+                "nginx".to_string(),
+                "a.local, b.local".to_string(),
+            ]]
+            .into_iter(),
+        );
+        assert_eq!(headers, ["IP地址", "别名"]);
+        assert_eq!(rows[0][0], ip);
+        assert_eq!(
+            rows[0][1],
+            format!("{}, {}", r.hostname("a.local"), r.hostname("b.local"))
+        );
+
+        let keep = Redactor::new("s", true);
+        assert_eq!(keep.value(&row)["banner"], "SSH-2.0-OpenSSH_8.0");
+
+        let csv = r.mapping_csv();
+        assert!(csv.contains(&format!("IP,{},10.0.0.5\r\n", ip)));
+        assert!(csv.contains(",portal.corp.local\r\n"));
+
+        // 对照表可读回，之后的条目与已有条目合并
+        let file = std::env::temp_dir().join(format!("gxr_redact_map_{}.csv", std::process::id()));
+        fs::write(&file, &csv).unwrap();
+        let reloaded = Redactor::new("s", false)
+            .with_mapping_file(file.clone())
+            .unwrap();
+        reloaded.ip("10.9.9.9".parse().unwrap());
+        reloaded.save_mapping().unwrap();
+        let saved = fs::read_to_string(&file).unwrap();
+        fs::remove_file(&file).ok();
+        assert!(saved.contains(",10.0.0.5\r\n") && saved.contains(",10.9.9.9\r\n"));
+    }
+}
@@ -0,0 +1,301 @@
+// src/utils/run_dir.rs
+use super::integrity::{file_sha256, sign_manifest};
+use super::meta::{RunMeta, meta};
+use super::redact::{export_json, save_export_mapping};
+use super::{flat_output, output_root};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// 运行目录所在的子目录（位于输出根目录下）
+pub const RUNS_DIR_NAME: &str = "runs";
+
+/// 产物索引文件名
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// 产物索引签名文件名（开启 `--sign-key` 时写入，内容为Base64编码的Ed25519签名）
+pub const SIGNATURE_FILE_NAME: &str = "manifest.sig";
+
+/// 统计摘要文件名（内容为 `[名称, 值]` 列表）
+pub const SUMMARY_FILE_NAME: &str = "summary.json";
+
+/// 主机结果文件名
+pub const HOSTS_FILE_NAME: &str = "hosts.json";
+
+/// 端口结果文件名
+pub const PORTS_FILE_NAME: &str = "ports.json";
+
+/// 各目标主机扫描结论文件名（含没有结果的主机）
+pub const HOST_STATUS_FILE_NAME: &str = "host_status.json";
+
+/// 双栈主机对照文件名（同一主机名的IPv4、IPv6结果并列）
+pub const DUAL_STACK_FILE_NAME: &str = "dual_stack.json";
+
+/// 检测发现文件名（如疑似蜜罐主机）
+pub const FINDINGS_FILE_NAME: &str = "findings.json";
+
+/// 目标参数覆盖文件名（目标文件中带 `timeout=` 等参数的行）
+pub const TARGET_OVERRIDES_FILE_NAME: &str = "target_overrides.json";
+
+/// 自适应并发随时间的变化文件名（`-c auto-adaptive`）
+pub const CONCURRENCY_FILE_NAME: &str = "concurrency.json";
+
+/// 统计摘要中的一项：(名称, 值)
+pub type SummaryItem = (String, String);
+
+/// 产物索引（`manifest.json`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunManifest {
+    /// 运行ID（与历史记录一致）
+    pub id: String,
+    /// 模块名称（如 "pentest portscan"）
+    pub module: String,
+    /// 开始时间（RFC3339）
+    pub started_at: String,
+    /// 运行元数据（项目名称、测试人员、授权文件编号等，见 `--meta`）
+    #[serde(default, skip_serializing_if = "RunMeta::is_empty")]
+    pub meta: RunMeta,
+    /// 本次运行生成的产物
+    pub artifacts: Vec<Artifact>,
+}
+
+/// 单个产物
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Artifact {
+    /// 相对运行目录的文件名
+    pub file: String,
+    /// 产物类型（xlsx、json、txt、log、checkpoint 等）
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// 数据行数
+    pub rows: usize,
+    /// 文件内容的SHA256（十六进制）
+    pub sha256: String,
+}
+
+/// 单次运行的工作目录
+///
+/// 目录在第一次写入产物时才创建，没有产物的运行不会留下空目录。
+/// 每登记一个产物都会重写 `manifest.json`，运行中断时索引也与已有文件一致。
+#[derive(Debug)]
+pub struct RunDir {
+    path: PathBuf,
+    manifest: Mutex<RunManifest>,
+}
+
+impl RunDir {
+    /// 为一次运行分配目录：`<root>/runs/<时间戳>_<模块>_<短ID>/`
+    ///
+    /// # 参数
+    /// * `root` - 输出根目录
+    /// * `id` - 运行ID（末尾的短ID用于区分同一秒内的运行）
+    /// * `module` - 模块名称（如 "net ping"，目录名只取最后一段）
+    pub fn new(root: &Path, id: &str, module: &str) -> Self {
+        let now = Local::now();
+        let short_id = id.rsplit('-').next().unwrap_or(id);
+        let module_name = module.rsplit(' ').next().unwrap_or(module);
+        let name = format!(
+            "{}_{}_{}",
+            now.format("%Y%m%d_%H%M%S"),
+            module_name,
+            short_id
+        );
+        Self {
+            path: root.join(RUNS_DIR_NAME).join(name),
+            manifest: Mutex::new(RunManifest {
+                id: id.to_string(),
+                module: module.to_string(),
+                started_at: now.to_rfc3339(),
+                meta: meta().clone(),
+                artifacts: Vec::new(),
+            }),
+        }
+    }
+
+    /// 按全局设置为一次运行分配目录，使用平铺输出结构时返回 `None`
+    ///
+    /// # 参数
+    /// * `id` - 运行ID
+    /// * `module` - 模块名称
+    pub fn allocate(id: &str, module: &str) -> Option<Arc<Self>> {
+        Self::allocate_in(output_root(), id, module)
+    }
+
+    /// 在指定输出根目录下为一次运行分配目录，使用平铺输出结构时返回 `None`
+    ///
+    /// # 参数
+    /// * `root` - 输出根目录
+    /// * `id` - 运行ID
+    /// * `module` - 模块名称
+    pub fn allocate_in(root: &Path, id: &str, module: &str) -> Option<Arc<Self>> {
+        (!flat_output()).then(|| Arc::new(Self::new(root, id, module)))
+    }
+
+    /// 运行目录路径（可能尚未创建）
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 是否已生成过产物
+    pub fn exists(&self) -> bool {
+        self.path.is_dir()
+    }
+
+    /// 确保运行目录存在并返回其路径
+    pub fn ensure(&self) -> io::Result<&Path> {
+        fs::create_dir_all(&self.path)?;
+        Ok(&self.path)
+    }
+
+    /// 登记一个已写入运行目录的产物并更新索引
+    ///
+    /// # 参数
+    /// * `file` - 产物路径（需位于运行目录下）
+    /// * `kind` - 产物类型
+    /// * `rows` - 数据行数
+    pub fn record(
+        &self,
+        file: &Path,
+        kind: &str,
+        rows: usize,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let name = file
+            .strip_prefix(&self.path)
+            .map_err(|_| format!("产物不在运行目录下: {}", file.display()))?
+            .to_string_lossy()
+            .replace('\\', "/");
+        let sha256 = file_sha256(file)?;
+
+        let mut manifest = self.manifest.lock().unwrap();
+        // 同名文件被重写时替换原有条目
+        manifest.artifacts.retain(|a| a.file != name);
+        manifest.artifacts.push(Artifact {
+            file: name,
+            kind: kind.to_string(),
+            rows,
+            sha256,
+        });
+        self.save_manifest(&manifest)
+    }
+
+    /// 删除运行目录下的产物文件并从索引中移除（如被替换的中间结果）
+    ///
+    /// # 参数
+    /// * `file` - 产物路径（需位于运行目录下）
+    pub fn remove(&self, file: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let name = file
+            .strip_prefix(&self.path)
+            .map_err(|_| format!("产物不在运行目录下: {}", file.display()))?
+            .to_string_lossy()
+            .replace('\\', "/");
+        if file.exists() {
+            fs::remove_file(file)?;
+        }
+
+        let mut manifest = self.manifest.lock().unwrap();
+        manifest.artifacts.retain(|a| a.file != name);
+        self.save_manifest(&manifest)
+    }
+
+    /// 重写产物索引，设置了签名私钥时同时更新签名
+    fn save_manifest(&self, manifest: &RunManifest) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let data = serde_json::to_string_pretty(manifest)?;
+        let dir = self.ensure()?;
+        fs::write(dir.join(MANIFEST_FILE_NAME), &data)?;
+        if let Some(signature) = sign_manifest(data.as_bytes()) {
+            fs::write(dir.join(SIGNATURE_FILE_NAME), signature)?;
+        }
+        Ok(())
+    }
+
+    /// 写入JSON产物并登记（供 `report view` 等工具读取）
+    ///
+    /// # 参数
+    /// * `file` - 相对运行目录的文件名
+    /// * `kind` - 产物类型（结果列表为 "json"，统计摘要为 "summary"）
+    /// * `value` - 写入的内容
+    /// * `rows` - 数据行数
+    ///
+    /// # 返回
+    /// * `Ok(PathBuf)` - 写入的文件路径
+    pub fn write_json<T: Serialize + ?Sized>(
+        &self,
+        file: &str,
+        kind: &str,
+        value: &T,
+        rows: usize,
+    ) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+        let path = self.ensure()?.join(file);
+        fs::write(&path, export_json(value)?)?;
+        save_export_mapping()?;
+        self.record(&path, kind, rows)?;
+        Ok(path)
+    }
+
+    /// 写入本次运行的统计摘要
+    ///
+    /// # 参数
+    /// * `items` - 按显示顺序排列的 (名称, 值)
+    pub fn write_summary(&self, items: &[SummaryItem]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.write_json(SUMMARY_FILE_NAME, "summary", items, items.len())?;
+        Ok(())
+    }
+
+    /// 读取已有运行目录的产物索引
+    ///
+    /// # 参数
+    /// * `dir` - 运行目录
+    pub fn load_manifest(dir: &Path) -> Result<RunManifest, Box<dyn Error + Send + Sync>> {
+        let path = dir.join(MANIFEST_FILE_NAME);
+        let data = fs::read_to_string(&path)
+            .map_err(|e| format!("无法读取产物索引 {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&data)
+            .map_err(|e| format!("产物索引格式错误 {}: {}", path.display(), e))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn test_run_dir_is_lazy_and_indexes_artifacts() {
+        let root = std::env::temp_dir().join(format!("gxr_runs_{}", std::process::id()));
+        let run = RunDir::new(&root, "20240102100000-ab12", "pentest portscan");
+        let name = run
+            .path()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        assert!(name.ends_with("_portscan_ab12"));
+        assert!(!run.exists());
+
+        let file = run.ensure().unwrap().join("alive.txt");
+        fs::write(&file, "10.0.0.1\n").unwrap();
+        run.record(&file, "txt", 1).unwrap();
+        fs::write(&file, "10.0.0.1\n10.0.0.2\n").unwrap();
+        run.record(&file, "txt", 2).unwrap();
+        assert!(run.record(&root.join("outside.txt"), "txt", 0).is_err());
+        run.write_json(HOSTS_FILE_NAME, "json", &["10.0.0.1"], 1)
+            .unwrap();
+
+        let manifest = RunDir::load_manifest(run.path()).unwrap();
+        fs::remove_dir_all(&root).ok();
+
+        assert_eq!(manifest.id, "20240102100000-ab12");
+        assert_eq!(manifest.artifacts.len(), 2);
+        assert_eq!(manifest.artifacts[1].kind, "json");
+        assert_eq!(manifest.artifacts[0].file, "alive.txt");
+        assert_eq!(manifest.artifacts[0].rows, 2);
+        assert_eq!(
+            manifest.artifacts[0].sha256,
+            hex::encode(Sha256::digest(b"10.0.0.1\n10.0.0.2\n"))
+        );
+    }
+}
@@ -0,0 +1,106 @@
+// src/utils/salvage.rs
+use super::ExcelSheet;
+use super::console::Icon;
+use super::finding::csv_cell;
+use super::output::reserve_unique_path;
+use super::redact::save_export_mapping;
+use chrono::Local;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 转存目录名（位于系统临时目录下）
+pub const SALVAGE_DIR_NAME: &str = "gxtools-salvage";
+
+/// 导出失败时转存数据的目录
+pub fn salvage_dir() -> PathBuf {
+    std::env::temp_dir().join(SALVAGE_DIR_NAME)
+}
+
+/// 导出失败时把数据转存到系统临时目录，并打印转存位置
+///
+/// 文件名为 `<前缀>_<时间戳>.<扩展名>`，同名时追加序号。
+/// 开启导出脱敏时随后保存脱敏映射，映射同样写不进去时只提示，已转存的数据不受影响。
+///
+/// # 参数
+/// * `prefix` - 文件名前缀（如模块名）
+/// * `ext` - 扩展名
+/// * `content` - 文件内容
+/// * `error` - 导出失败的原因（打印在转存位置之前）
+///
+/// # 返回
+/// * `Ok(PathBuf)` - 转存的文件路径
+/// * `Err` - 转存同样失败（错误信息包含原始失败原因）
+pub fn salvage(
+    prefix: &str,
+    ext: &str,
+    content: &[u8],
+    error: &str,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let dir = salvage_dir();
+    let written = fs::create_dir_all(&dir).and_then(|_| {
+        let stem = format!("{}_{}", prefix, Local::now().format("%Y%m%d_%H%M%S"));
+        let path = reserve_unique_path(&dir, &stem, ext)?;
+        fs::write(&path, content)?;
+        Ok(path)
+    });
+    match written {
+        Ok(path) => {
+            println!(
+                "{} 导出失败（{}），数据已转存至: {}",
+                Icon::Warn,
+                error,
+                path.display()
+            );
+            if let Err(e) = save_export_mapping() {
+                println!("{} 无法保存脱敏映射: {}", Icon::Warn, e);
+            }
+            Ok(path)
+        }
+        Err(e) => Err(format!("{}；转存到 {} 同样失败: {}", error, dir.display(), e).into()),
+    }
+}
+
+/// Excel导出失败时把全部工作表转存为CSV（每个工作表一个文件）
+///
+/// # 参数
+/// * `prefix` - 文件名前缀
+/// * `sheets` - 要转存的工作表（第一个为结果表，文件名不带工作表名）
+/// * `error` - Excel导出失败的原因
+/// * `partial` - 导出失败前已创建的Excel文件（存在时删除，避免留下空文件）
+///
+/// # 返回
+/// * `Ok(Vec<PathBuf>)` - 各工作表转存的文件路径
+pub fn salvage_sheets(
+    prefix: &str,
+    sheets: &[ExcelSheet],
+    error: &str,
+    partial: Option<&Path>,
+) -> Result<Vec<PathBuf>, Box<dyn Error + Send + Sync>> {
+    if let Some(path) = partial {
+        fs::remove_file(path).ok();
+    }
+    sheets
+        .iter()
+        .enumerate()
+        .map(|(i, sheet)| {
+            let prefix = if i == 0 {
+                prefix.to_string()
+            } else {
+                format!("{}_{}", prefix, sheet.name)
+            };
+            salvage(&prefix, "csv", to_csv(sheet).as_bytes(), error)
+        })
+        .collect()
+}
+
+/// 渲染为CSV（UTF-8带BOM，Excel可直接打开）
+fn to_csv(sheet: &ExcelSheet) -> String {
+    let mut out = String::from("\u{feff}");
+    for line in std::iter::once(&sheet.headers).chain(&sheet.rows) {
+        let cells: Vec<String> = line.iter().map(|c| csv_cell(c)).collect();
+        out.push_str(&cells.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
@@ -0,0 +1,571 @@
+// src/utils/sample.rs
+use super::console::Icon;
+use super::run_dir::SummaryItem;
+use super::targets::{TargetSet, is_hostname, split_specs};
+use super::{ExcelSheet, merge_ranges, parse_target_ranges};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+
+/// 95%置信水平对应的正态分位数
+const Z_95: f64 = 1.96;
+
+/// 随机抽样参数（目标空间过大、无法全量扫描时使用）
+#[derive(Args, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SampleArgs {
+    /// 只扫描目标空间中均匀随机抽取的样本（如 1000 或 5%），统计结果按样本外推为估算值
+    #[arg(long, value_name = "N|PCT%", conflicts_with_all = ["target_xlsx", "target_file"])]
+    pub sample: Option<SampleSize>,
+
+    /// 抽样的随机种子（默认随机生成；目标、样本大小和种子相同时抽到相同的样本）
+    #[arg(long, value_name = "SEED", requires = "sample")]
+    pub sample_seed: Option<u64>,
+
+    /// 只扫描展开后的前 N 个目标（快速检查用，与 --sample 不能同时使用）
+    #[arg(long, value_name = "N", conflicts_with = "sample")]
+    pub limit: Option<usize>,
+}
+
+impl SampleArgs {
+    /// 按 `--limit` 只保留前 N 个目标（按地址顺序），未指定时不变
+    ///
+    /// # 参数
+    /// * `targets` - 去重后的目标集合
+    pub fn apply_limit(&self, targets: &mut TargetSet) {
+        let Some(limit) = self.limit else {
+            return;
+        };
+        let total = targets.len();
+        if total <= limit {
+            return;
+        }
+        targets.truncate(limit);
+        println!(
+            "{} 已从 {} 个目标中取前 {} 个（--limit）",
+            Icon::List,
+            total,
+            targets.len()
+        );
+    }
+}
+
+/// 打乱探测顺序的参数（顺序扫描容易被IDS/IPS识别）
+#[derive(Args, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RandomizeArgs {
+    /// 打乱目标的探测顺序（端口扫描同时打乱端口顺序），结果仍按IP排序输出
+    #[arg(long, env = "GXTOOLS_RANDOMIZE")]
+    #[serde(default)]
+    pub randomize: bool,
+
+    /// 打乱顺序的随机种子（默认随机生成；目标和种子相同时探测顺序相同）
+    #[arg(long, value_name = "SEED", requires = "randomize")]
+    pub seed: Option<u64>,
+}
+
+impl RandomizeArgs {
+    /// 按参数创建洗牌器并打印种子，未开启 `--randomize` 时为 `None`
+    pub fn shuffler(&self) -> Option<Shuffler> {
+        if !self.randomize {
+            return None;
+        }
+        let seed = self.seed.unwrap_or_else(random_seed);
+        println!(
+            "{} 随机探测顺序，随机种子 {}（--seed {} 可复现）",
+            Icon::Config,
+            seed,
+            seed
+        );
+        Some(Shuffler::new(seed))
+    }
+}
+
+/// 按种子可复现地打乱顺序
+pub struct Shuffler(SplitMix64);
+
+impl Shuffler {
+    /// 使用指定种子创建
+    pub fn new(seed: u64) -> Self {
+        Self(SplitMix64(seed))
+    }
+
+    /// 原地打乱（Fisher–Yates）
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.0.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// 样本大小
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleSize {
+    /// 固定数量
+    Count(u64),
+    /// 目标空间的百分比
+    Percent(f64),
+}
+
+impl SampleSize {
+    /// 按目标空间大小换算样本数（至少1个，不超过目标空间）
+    pub fn count(&self, population: u64) -> u64 {
+        let n = match *self {
+            SampleSize::Count(n) => n,
+            SampleSize::Percent(pct) => (population as f64 * pct / 100.0).ceil() as u64,
+        };
+        n.clamp(1, population.max(1))
+    }
+}
+
+impl FromStr for SampleSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(pct) = s.strip_suffix('%') {
+            match pct.trim().parse::<f64>() {
+                Ok(pct) if pct > 0.0 && pct <= 100.0 => Ok(SampleSize::Percent(pct)),
+                _ => Err(format!("抽样比例应在 0% 到 100% 之间: {}", s)),
+            }
+        } else {
+            match s.parse::<u64>() {
+                Ok(n) if n > 0 => Ok(SampleSize::Count(n)),
+                _ => Err(format!("样本大小应为正整数或百分比（如 1000、5%）: {}", s)),
+            }
+        }
+    }
+}
+
+impl fmt::Display for SampleSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SampleSize::Count(n) => write!(f, "{}", n),
+            SampleSize::Percent(pct) => write!(f, "{}%", pct),
+        }
+    }
+}
+
+impl Serialize for SampleSize {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SampleSize {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 目标空间：合并后互不重叠的IP区间，不展开为地址列表
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TargetSpace {
+    /// 按起始地址排序的闭区间
+    ranges: Vec<(u32, u32)>,
+}
+
+impl TargetSpace {
+    /// 解析目标字符串（与 -t 的IP写法相同：IP、范围、网段及按段通配，不支持主机名）
+    ///
+    /// 网段与 -t 一样不含网络地址和广播地址，重叠的部分只计一次。
+    pub fn parse(targets: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        if let Some(host) = split_specs(targets).find(|spec| is_hostname(spec)) {
+            return Err(format!("抽样只支持IP、范围及网段: {}", host).into());
+        }
+        Ok(Self {
+            ranges: merge_ranges(parse_target_ranges(targets, false)?),
+        })
+    }
+
+    /// 目标空间中的地址数
+    pub fn len(&self) -> u64 {
+        self.ranges
+            .iter()
+            .map(|(start, end)| (end - start) as u64 + 1)
+            .sum()
+    }
+
+    /// 目标空间是否为空
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// 第 `index` 个地址（按地址顺序）
+    fn nth(&self, mut index: u64) -> Option<Ipv4Addr> {
+        for (start, end) in &self.ranges {
+            let size = (end - start) as u64 + 1;
+            if index < size {
+                return Some(Ipv4Addr::from(start + index as u32));
+            }
+            index -= size;
+        }
+        None
+    }
+
+    /// 不放回地均匀抽取 `count` 个地址（按地址排序）
+    ///
+    /// 使用 Floyd 抽样算法，只记录抽中的序号，内存与样本大小成正比。
+    ///
+    /// # 参数
+    /// * `count` - 样本数（超过目标空间时取全部）
+    /// * `seed` - 随机种子
+    pub fn sample(&self, count: u64, seed: u64) -> Vec<Ipv4Addr> {
+        let population = self.len();
+        let count = count.min(population);
+        let mut rng = SplitMix64(seed);
+        let mut chosen = HashSet::with_capacity(count as usize);
+        for j in population - count..population {
+            let pick = rng.below(j + 1);
+            if !chosen.insert(pick) {
+                chosen.insert(j);
+            }
+        }
+        let mut indices: Vec<u64> = chosen.into_iter().collect();
+        indices.sort_unstable();
+        indices.into_iter().filter_map(|i| self.nth(i)).collect()
+    }
+}
+
+/// SplitMix64 伪随机数生成器（相同种子产生相同序列，用于可复现的抽样）
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// `[0, bound)` 内的均匀随机数（拒绝采样，避免取模偏差）
+    fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let x = self.next();
+            if x < zone {
+                return x % bound;
+            }
+        }
+    }
+}
+
+/// 本次运行的抽样
+#[derive(Debug, Clone)]
+pub struct Sample {
+    /// 抽样参数
+    pub size: SampleSize,
+    /// 随机种子
+    pub seed: u64,
+    /// 目标空间的地址数
+    pub population: u64,
+    /// 抽中的地址
+    pub ips: Vec<Ipv4Addr>,
+}
+
+impl Sample {
+    /// 按参数抽样，未指定 `--sample` 时返回 `None`
+    ///
+    /// # 参数
+    /// * `target` - -t 参数的值
+    /// * `args` - 抽样参数
+    pub fn draw(
+        target: Option<&str>,
+        args: &SampleArgs,
+    ) -> Result<Option<Self>, Box<dyn Error + Send + Sync>> {
+        let Some(size) = args.sample else {
+            return Ok(None);
+        };
+        let target = target.ok_or("抽样需要用 -t 指定目标空间")?;
+        let space = TargetSpace::parse(target)?;
+        let seed = args.sample_seed.unwrap_or_else(random_seed);
+        let population = space.len();
+        let ips = space.sample(size.count(population), seed);
+        let sample = Self {
+            size,
+            seed,
+            population,
+            ips,
+        };
+        println!(
+            "{} 抽样扫描: 目标空间 {} 个IP，抽取 {} 个（{:.2}%），随机种子 {}（--sample-seed {} 可复现）",
+            Icon::Config,
+            sample.population,
+            sample.ips.len(),
+            sample.fraction() * 100.0,
+            sample.seed,
+            sample.seed
+        );
+        Ok(Some(sample))
+    }
+
+    /// 样本作为 -t 参数的值
+    pub fn spec(&self) -> String {
+        self.ips
+            .iter()
+            .map(Ipv4Addr::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// 抽样比例
+    pub fn fraction(&self) -> f64 {
+        self.ips.len() as f64 / self.population.max(1) as f64
+    }
+
+    /// 按样本中命中的数量估算整个目标空间
+    ///
+    /// # 参数
+    /// * `hits` - 样本中命中的数量（如存活主机数）
+    /// * `observed` - 实际完成探测的样本数（取消时少于样本大小）
+    pub fn estimate(&self, hits: usize, observed: usize) -> Estimate {
+        Estimate::new(hits as u64, observed as u64, self.population)
+    }
+
+    /// 摘要项：标明结果来自抽样，并列出各项估算
+    ///
+    /// # 参数
+    /// * `estimates` - (名称, 估算) 列表
+    pub fn summary_items(&self, estimates: &[(&str, Estimate)]) -> Vec<SummaryItem> {
+        let mut items = vec![(
+            "抽样".to_string(),
+            format!(
+                "{} / {} 个IP（{:.2}%），种子 {}，以下为估算值",
+                self.ips.len(),
+                self.population,
+                self.fraction() * 100.0,
+                self.seed
+            ),
+        )];
+        items.extend(
+            estimates
+                .iter()
+                .map(|(name, estimate)| (format!("估算{}", name), estimate.to_string())),
+        );
+        items
+    }
+
+    /// 抽样说明工作表，避免把抽样结果误当作全量扫描
+    ///
+    /// # 参数
+    /// * `estimates` - (名称, 估算) 列表
+    pub fn sheet(&self, estimates: &[(&str, Estimate)]) -> ExcelSheet {
+        let mut rows = vec![
+            vec![
+                "扫描方式".to_string(),
+                "抽样扫描（非全量，统计为估算值）".to_string(),
+            ],
+            vec!["目标空间".to_string(), format!("{} 个IP", self.population)],
+            vec!["样本大小".to_string(), format!("{} 个IP", self.ips.len())],
+            vec![
+                "抽样比例".to_string(),
+                format!("{:.4}%", self.fraction() * 100.0),
+            ],
+            vec!["随机种子".to_string(), self.seed.to_string()],
+            vec![
+                "复现参数".to_string(),
+                format!("--sample {} --sample-seed {}", self.size, self.seed),
+            ],
+        ];
+        rows.extend(
+            estimates
+                .iter()
+                .map(|(name, estimate)| vec![format!("估算{}", name), estimate.to_string()]),
+        );
+        ExcelSheet {
+            name: "抽样说明".to_string(),
+            headers: vec!["项目".to_string(), "值".to_string()],
+            rows,
+        }
+    }
+}
+
+/// 默认随机种子
+fn random_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+        ^ std::process::id() as u64
+}
+
+/// 按样本外推的估算值及其95%置信区间
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    /// 样本中命中的数量
+    pub hits: u64,
+    /// 样本大小
+    pub observed: u64,
+    /// 估算值
+    pub value: f64,
+    /// 置信区间下限
+    pub low: f64,
+    /// 置信区间上限
+    pub high: f64,
+}
+
+impl Estimate {
+    /// 用 Wilson 区间估算比例，并按有限总体修正后换算到整个目标空间
+    ///
+    /// # 参数
+    /// * `hits` - 样本中命中的数量
+    /// * `observed` - 样本大小
+    /// * `population` - 目标空间大小
+    pub fn new(hits: u64, observed: u64, population: u64) -> Self {
+        let population_f = population as f64;
+        if observed == 0 {
+            return Self {
+                hits,
+                observed,
+                value: 0.0,
+                low: 0.0,
+                high: population_f,
+            };
+        }
+        let p = hits as f64 / observed as f64;
+        let (low, high) = if observed >= population {
+            // 样本即全部目标，没有抽样误差
+            (p, p)
+        } else {
+            // 不放回抽样的方差按 (N-n)/(N-1) 缩小，等效于放大样本
+            let n = observed as f64 * (population_f - 1.0) / (population_f - observed as f64);
+            let z2 = Z_95 * Z_95;
+            let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+            let half = Z_95 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+            ((center - half).max(0.0), (center + half).min(1.0))
+        };
+        Self {
+            hits,
+            observed,
+            value: p * population_f,
+            low: low * population_f,
+            high: high * population_f,
+        }
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "约 {:.0} 个（95%置信区间 {:.0}~{:.0}，样本命中 {}/{}）",
+            self.value, self.low, self.high, self.hits, self.observed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_is_uniform_and_reproducible() {
+        // 重叠的写法只计一次，网段不含网络地址和广播地址
+        let space = TargetSpace::parse("10.0.0.0/8, 10.1.0.5, 192.168.1.1-10").unwrap();
+        assert_eq!(space.len(), (1 << 24) - 2 + 10);
+        assert_eq!(space.nth(0), Some(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(
+            space.nth((1 << 24) - 2),
+            Some(Ipv4Addr::new(192, 168, 1, 1))
+        );
+
+        let sample = space.sample(1000, 42);
+        assert_eq!(sample.len(), 1000);
+        assert_eq!(sample, space.sample(1000, 42));
+        assert_ne!(sample, space.sample(1000, 43));
+        assert!(sample.windows(2).all(|w| w[0] < w[1]));
+
+        let small = TargetSpace::parse("192.168.1.0/29").unwrap();
+        assert_eq!(small.sample(100, 1).len(), 6);
+
+        assert_eq!("5%".parse(), Ok(SampleSize::Percent(5.0)));
+        assert_eq!("1000".parse(), Ok(SampleSize::Count(1000)));
+        assert_eq!(SampleSize::Percent(5.0).count(254), 13);
+        assert!("0".parse::<SampleSize>().is_err());
+        assert!("150%".parse::<SampleSize>().is_err());
+        assert!(TargetSpace::parse("example.com").is_err());
+    }
+
+    #[test]
+    fn test_target_space_matches_target_syntax() {
+        let space = |targets: &str| {
+            let space = TargetSpace::parse(targets).unwrap();
+            (0..space.len())
+                .filter_map(|i| space.nth(i))
+                .map(|ip| ip.to_string())
+                .collect::<Vec<_>>()
+        };
+        // 与 -t 展开的地址相同
+        for targets in ["10.0.0.0/31", "10.0.0.200-10.0.1.50", "10.0.*.1"] {
+            assert_eq!(
+                space(targets),
+                crate::utils::parse_targets(targets).unwrap(),
+                "{}",
+                targets
+            );
+        }
+        assert_eq!(space("10.0.0.0/31"), ["10.0.0.0", "10.0.0.1"]);
+        assert_eq!(
+            TargetSpace::parse("10.0.0.200-10.0.1.50").unwrap().len(),
+            107
+        );
+        assert_eq!(TargetSpace::parse("10.0.*.1").unwrap().len(), 256);
+        assert_eq!(
+            TargetSpace::parse("10.0.0.0/31")
+                .unwrap()
+                .sample(5, 1)
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_shuffle_is_reproducible_permutation() {
+        let mut a: Vec<u32> = (0..100).collect();
+        let mut b = a.clone();
+        Shuffler::new(7).shuffle(&mut a);
+        Shuffler::new(7).shuffle(&mut b);
+        assert_eq!(a, b);
+        assert_ne!(a, (0..100).collect::<Vec<_>>());
+        a.sort_unstable();
+        assert_eq!(a, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_limit_keeps_first_targets() {
+        let mut set = TargetSet::default();
+        set.add(
+            "10.0.0.0/29",
+            crate::utils::parse_targets("10.0.0.0/29").unwrap(),
+        );
+        let args = SampleArgs {
+            limit: Some(2),
+            ..Default::default()
+        };
+        args.apply_limit(&mut set);
+        assert_eq!(set.ips(), ["10.0.0.1", "10.0.0.2"]);
+        assert_eq!(set.spec_ips("10.0.0.0/29").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_estimate_interval_covers_the_rate() {
+        let estimate = Estimate::new(50, 1000, 1_000_000);
+        assert_eq!(estimate.value, 50_000.0);
+        assert!(estimate.low < 50_000.0 && estimate.high > 50_000.0);
+        assert!(estimate.low > 35_000.0 && estimate.high < 70_000.0);
+
+        // 没有命中时下限为0，上限仍然大于0
+        let none = Estimate::new(0, 1000, 1_000_000);
+        assert_eq!(none.low, 0.0);
+        assert!(none.high > 0.0);
+
+        // 全量时没有抽样误差
+        let full = Estimate::new(10, 254, 254);
+        assert_eq!((full.low, full.high), (10.0, 10.0));
+    }
+}
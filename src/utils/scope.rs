@@ -0,0 +1,481 @@
+// src/utils/scope.rs
+use super::console::Icon;
+use super::targets::{TargetSet, is_hostname};
+use super::{config_file, load_config_section};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// 配置文件中的段落名
+pub const CONFIG_SECTION: &str = "scope";
+
+/// 终端中最多列出的范围外目标数
+const MAX_LISTED_VIOLATIONS: usize = 20;
+
+static SETTINGS: OnceLock<ScopeSettings> = OnceLock::new();
+
+/// 授权范围设置（可在配置文件的 `scope` 段落中设置，命令行参数优先）
+///
+/// ```yaml
+/// scope:
+///   file: /etc/gxtools/scope.txt
+///   strict: true
+///   acknowledge_active_scan: true
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScopeConfig {
+    /// 授权范围文件
+    pub file: Option<PathBuf>,
+    /// 存在范围外目标时中止运行
+    pub strict: bool,
+    /// 预先确认主动攻击类模块（用于自动化运行）
+    pub acknowledge_active_scan: bool,
+}
+
+impl ScopeConfig {
+    /// 从配置文件读取授权范围设置
+    pub fn load() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        load_config_section(&config_file(), CONFIG_SECTION)
+    }
+}
+
+/// 授权范围：网段与域名
+///
+/// 范围文件每行一项，`#` 之后为注释：
+/// - `10.0.0.0/8`、`192.168.1.10` —— 授权的网段或地址
+/// - `corp.example.com`、`*.corp.example.com` —— 授权的域名（含全部子域名）
+///
+/// 核对规则：
+/// - 列出了网段时，目标地址必须落在某个网段内（主机名按解析后的地址核对）；
+/// - 列出了域名时，以主机名写出的目标必须属于某个域名；
+/// - 只列出域名时，直接以IP写出的目标无法核对，视为范围外。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scope {
+    /// (网络地址, 掩码)
+    networks: Vec<(u32, u32)>,
+    /// 小写、不含 `*.` 前缀及末尾的点
+    domains: Vec<String>,
+}
+
+/// 范围外的目标
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeViolation {
+    /// 目标地址
+    pub ip: String,
+    /// 指向该地址的原始写法
+    pub sources: Vec<String>,
+    /// 原因
+    pub reason: String,
+}
+
+impl Scope {
+    /// 解析范围文件内容
+    ///
+    /// # 参数
+    /// * `content` - 文件内容
+    ///
+    /// # 返回
+    /// * `Ok(Scope)` - 授权范围
+    /// * `Err` - 存在无法识别的行，或文件中没有任何条目
+    pub fn parse(content: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut scope = Scope::default();
+        for (i, line) in content.lines().enumerate() {
+            let entry = line.split('#').next().unwrap_or_default().trim();
+            if entry.is_empty() {
+                continue;
+            }
+            scope
+                .add(entry)
+                .map_err(|e| format!("授权范围第{}行无效: {}", i + 1, e))?;
+        }
+        if scope.is_empty() {
+            return Err("授权范围文件中没有任何网段或域名".into());
+        }
+        Ok(scope)
+    }
+
+    /// 读取范围文件
+    ///
+    /// # 参数
+    /// * `path` - 文件路径
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("无法读取授权范围文件 {}: {}", path.display(), e))?;
+        Self::parse(&content).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    fn add(&mut self, entry: &str) -> Result<(), String> {
+        if let Some((ip, prefix)) = entry.split_once('/') {
+            let ip = Ipv4Addr::from_str(ip).map_err(|_| format!("网段地址无效: {}", entry))?;
+            let prefix: u32 = prefix
+                .parse()
+                .ok()
+                .filter(|p| *p <= 32)
+                .ok_or_else(|| format!("掩码长度无效: {}", entry))?;
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            self.networks.push((u32::from(ip) & mask, mask));
+        } else if let Ok(ip) = Ipv4Addr::from_str(entry) {
+            self.networks.push((u32::from(ip), u32::MAX));
+        } else {
+            let domain = entry.strip_prefix("*.").unwrap_or(entry);
+            let domain = domain.strip_suffix('.').unwrap_or(domain);
+            if !is_hostname(domain) {
+                return Err(format!("既不是网段也不是域名: {}", entry));
+            }
+            self.domains.push(domain.to_ascii_lowercase());
+        }
+        Ok(())
+    }
+
+    /// 是否没有任何条目
+    pub fn is_empty(&self) -> bool {
+        self.networks.is_empty() && self.domains.is_empty()
+    }
+
+    /// 网段数与域名数
+    pub fn counts(&self) -> (usize, usize) {
+        (self.networks.len(), self.domains.len())
+    }
+
+    /// 地址是否落在授权网段内
+    pub fn contains_ip(&self, ip: Ipv4Addr) -> bool {
+        let ip = u32::from(ip);
+        self.networks
+            .iter()
+            .any(|(network, mask)| ip & mask == *network)
+    }
+
+    /// 主机名是否属于授权域名
+    pub fn contains_host(&self, name: &str) -> bool {
+        let name = name.strip_suffix('.').unwrap_or(name).to_ascii_lowercase();
+        self.domains.iter().any(|d| {
+            name == *d
+                || name
+                    .strip_suffix(d.as_str())
+                    .is_some_and(|rest| rest.ends_with('.'))
+        })
+    }
+
+    /// 核对一个目标（主机名须已解析），在范围内时返回 `None`
+    ///
+    /// # 参数
+    /// * `ip` - 目标地址
+    /// * `sources` - 指向该地址的原始写法
+    ///
+    /// # 返回
+    /// * 范围外的原因
+    pub fn check(&self, ip: &str, sources: &[String]) -> Option<String> {
+        let hostnames: Vec<&String> = sources.iter().filter(|s| is_hostname(s)).collect();
+        if !self.networks.is_empty() {
+            let inside = Ipv4Addr::from_str(ip).is_ok_and(|ip| self.contains_ip(ip));
+            if !inside {
+                return Some(match hostnames.first() {
+                    Some(name) => format!("主机名 {} 解析到授权网段以外的地址", name),
+                    None => "地址不在授权网段内".to_string(),
+                });
+            }
+        }
+        if !self.domains.is_empty() {
+            if let Some(name) = hostnames.iter().find(|n| !self.contains_host(n)) {
+                return Some(format!("主机名 {} 不属于授权域名", name));
+            }
+            if self.networks.is_empty() && hostnames.is_empty() {
+                return Some("授权范围只包含域名，无法核对IP写法的目标".to_string());
+            }
+        }
+        None
+    }
+
+    /// 核对目标集合中的每个目标
+    pub fn violations(&self, set: &TargetSet) -> Vec<ScopeViolation> {
+        set.iter()
+            .filter_map(|t| {
+                self.check(&t.ip, &t.sources).map(|reason| ScopeViolation {
+                    ip: t.ip,
+                    sources: t.sources,
+                    reason,
+                })
+            })
+            .collect()
+    }
+}
+
+/// 本次运行生效的授权范围设置
+#[derive(Debug, Clone, Default)]
+pub struct ScopeSettings {
+    /// 授权范围（未指定范围文件时为 `None`，不做核对）
+    pub scope: Option<Scope>,
+    /// 存在范围外目标时中止运行
+    pub strict: bool,
+    /// 已确认执行主动攻击类模块
+    pub active_scan_acknowledged: bool,
+}
+
+/// 按命令行参数与配置文件初始化授权范围设置（命令行优先）
+///
+/// # 参数
+/// * `file` - `--scope-file`
+/// * `strict` - `--strict-scope`
+/// * `acknowledged` - `--i-understand-active-scan`
+pub fn init(
+    file: Option<&Path>,
+    strict: bool,
+    acknowledged: bool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let config = ScopeConfig::load()?;
+    let scope = match file.or(config.file.as_deref()) {
+        Some(path) => Some(Scope::load(path)?),
+        None => None,
+    };
+    let _ = SETTINGS.set(ScopeSettings {
+        scope,
+        strict: strict || config.strict,
+        active_scan_acknowledged: acknowledged || config.acknowledge_active_scan,
+    });
+    Ok(())
+}
+
+/// 当前的授权范围设置（未初始化时不做核对）
+pub fn settings() -> &'static ScopeSettings {
+    SETTINGS.get_or_init(ScopeSettings::default)
+}
+
+/// 在发出任何探测之前核对目标集合，排除范围外的目标
+///
+/// 严格模式下存在范围外目标即返回错误；全部目标均在范围外时同样返回错误。
+///
+/// # 参数
+/// * `set` - 已解析主机名的目标集合
+pub fn enforce(set: &mut TargetSet) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let settings = settings();
+    let Some(ref scope) = settings.scope else {
+        return Ok(());
+    };
+    let violations = scope.violations(set);
+    let excluded = settle(&violations, set.len(), settings.strict)?;
+    set.retain(|t| !excluded.contains(&t.ip));
+    Ok(())
+}
+
+/// 核对导入的地址（如已有扫描结果中的主机），返回应排除的地址
+///
+/// # 参数
+/// * `ips` - 去重后的地址
+pub fn enforce_ips(ips: &[String]) -> Result<HashSet<String>, Box<dyn Error + Send + Sync>> {
+    let settings = settings();
+    let Some(ref scope) = settings.scope else {
+        return Ok(HashSet::new());
+    };
+    let violations: Vec<ScopeViolation> = ips
+        .iter()
+        .filter_map(|ip| {
+            let sources = [ip.clone()];
+            scope.check(ip, &sources).map(|reason| ScopeViolation {
+                ip: ip.clone(),
+                sources: sources.to_vec(),
+                reason,
+            })
+        })
+        .collect();
+    settle(&violations, ips.len(), settings.strict)
+}
+
+/// 输出核对结果，并按严格模式决定中止还是排除
+fn settle(
+    violations: &[ScopeViolation],
+    total: usize,
+    strict: bool,
+) -> Result<HashSet<String>, Box<dyn Error + Send + Sync>> {
+    if violations.is_empty() {
+        println!("{} 授权范围核对通过: {} 个目标", Icon::Ok, total);
+        return Ok(HashSet::new());
+    }
+
+    println!(
+        "{} {} 个目标不在授权范围内{}:",
+        Icon::Warn,
+        violations.len(),
+        if strict { "" } else { "，已排除" }
+    );
+    for v in violations.iter().take(MAX_LISTED_VIOLATIONS) {
+        let named: Vec<&str> = v
+            .sources
+            .iter()
+            .filter(|s| **s != v.ip)
+            .map(String::as_str)
+            .collect();
+        let alias = if named.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", named.join(", "))
+        };
+        println!("   {}{} => {}", v.ip, alias, v.reason);
+    }
+    if violations.len() > MAX_LISTED_VIOLATIONS {
+        println!(
+            "   ... 另有 {} 个",
+            violations.len() - MAX_LISTED_VIOLATIONS
+        );
+    }
+
+    if strict {
+        return Err(format!(
+            "存在 {} 个授权范围外的目标，已按 --strict-scope 中止（未发出任何探测）",
+            violations.len()
+        )
+        .into());
+    }
+    if violations.len() == total {
+        return Err("全部目标均不在授权范围内".into());
+    }
+    Ok(violations.iter().map(|v| v.ip.clone()).collect())
+}
+
+/// 运行主动攻击类模块（如爆破、漏洞验证）前确认已获授权
+///
+/// 已通过 `--i-understand-active-scan` 或配置文件确认时直接通过；
+/// 终端中要求输入 yes 确认，非交互运行时返回错误。
+///
+/// # 参数
+/// * `module` - 模块名（如 "pentest brute"）
+pub fn require_active_scan_ack(module: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if settings().active_scan_acknowledged {
+        return Ok(());
+    }
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return Err(format!(
+            "{} 会发送主动攻击性流量，非交互运行时需指定 --i-understand-active-scan 或在配置文件中设置 scope.acknowledge_active_scan",
+            module
+        )
+        .into());
+    }
+    eprint!(
+        "{} {} 会发送主动攻击性流量，确认目标已获书面授权？输入 yes 继续: ",
+        Icon::Warn,
+        module
+    );
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin.lock().read_line(&mut answer)?;
+    if is_confirmed(&answer) {
+        Ok(())
+    } else {
+        Err("未确认授权，已取消运行".into())
+    }
+}
+
+/// 确认输入必须完整写出 yes
+fn is_confirmed(answer: &str) -> bool {
+    answer.trim().eq_ignore_ascii_case("yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCOPE: &str = "\
+# 项目授权范围
+10.0.0.0/24
+192.168.1.10   # 跳板机
+*.corp.example.com
+";
+
+    #[test]
+    fn test_parse_scope() {
+        let scope = Scope::parse(SCOPE).unwrap();
+        assert_eq!(scope.counts(), (2, 1));
+        assert!(scope.contains_ip("10.0.0.255".parse().unwrap()));
+        assert!(scope.contains_ip("192.168.1.10".parse().unwrap()));
+        assert!(!scope.contains_ip("10.0.1.1".parse().unwrap()));
+        assert!(!scope.contains_ip("192.168.1.11".parse().unwrap()));
+        assert!(scope.contains_host("corp.example.com"));
+        assert!(scope.contains_host("WWW.Corp.Example.com."));
+        assert!(!scope.contains_host("evilcorp.example.com"));
+
+        let err = Scope::parse("10.0.0.0/24\n10.0.0.0/33\n").unwrap_err();
+        assert!(err.to_string().contains("第2行"));
+        assert!(Scope::parse("# 只有注释\n\n").is_err());
+        assert!(
+            Scope::parse("0.0.0.0/0")
+                .unwrap()
+                .contains_ip(Ipv4Addr::BROADCAST)
+        );
+    }
+
+    #[test]
+    fn test_hostname_resolving_outside_scope_is_rejected() {
+        let scope = Scope::parse(SCOPE).unwrap();
+        let mut set = TargetSet::default();
+        set.add("10.0.0.0/30", vec!["10.0.0.1".into(), "10.0.0.2".into()]);
+        set.add("app.corp.example.com", vec!["10.0.0.2".into()]);
+        // 域名在授权范围内，但解析到了范围外的地址
+        set.add("mail.corp.example.com", vec!["203.0.113.5".into()]);
+        set.add("wiki.other.com", vec!["10.0.0.3".into()]);
+        set.add("172.16.0.1", vec!["172.16.0.1".into()]);
+
+        let violations = scope.violations(&set);
+        let rejected: Vec<(&str, &str)> = violations
+            .iter()
+            .map(|v| (v.ip.as_str(), v.reason.as_str()))
+            .collect();
+        assert_eq!(
+            rejected,
+            [
+                ("10.0.0.3", "主机名 wiki.other.com 不属于授权域名"),
+                ("172.16.0.1", "地址不在授权网段内"),
+                (
+                    "203.0.113.5",
+                    "主机名 mail.corp.example.com 解析到授权网段以外的地址"
+                ),
+            ]
+        );
+
+        let excluded: HashSet<&str> = rejected.iter().map(|(ip, _)| *ip).collect();
+        set.retain(|t| !excluded.contains(t.ip.as_str()));
+        assert_eq!(set.ips(), ["10.0.0.1", "10.0.0.2"]);
+        assert_eq!(
+            set.spec_ips("mail.corp.example.com").unwrap(),
+            [] as [String; 0]
+        );
+    }
+
+    #[test]
+    fn test_domain_only_scope() {
+        let scope = Scope::parse("corp.example.com").unwrap();
+        assert_eq!(
+            scope.check("203.0.113.5", &["a.corp.example.com".into()]),
+            None
+        );
+        assert!(scope.check("10.0.0.1", &["10.0.0.1".into()]).is_some());
+    }
+
+    #[test]
+    fn test_strict_scope_aborts() {
+        let violations = vec![ScopeViolation {
+            ip: "10.0.1.1".into(),
+            sources: vec!["10.0.1.1".into()],
+            reason: "地址不在授权网段内".into(),
+        }];
+        assert!(settle(&violations, 3, true).is_err());
+        assert!(settle(&violations, 1, false).is_err());
+        let excluded = settle(&violations, 3, false).unwrap();
+        assert!(excluded.contains("10.0.1.1"));
+        assert!(settle(&[], 3, true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_is_confirmed() {
+        assert!(is_confirmed("yes\n"));
+        assert!(is_confirmed(" YES "));
+        assert!(!is_confirmed("y"));
+        assert!(!is_confirmed(""));
+    }
+}
@@ -0,0 +1,829 @@
+// src/utils/script.rs
+use super::blocking::BlockingStage;
+use super::console::Icon;
+use super::context::ScanContext;
+use super::finding::{Finding, FindingParams, Severity};
+use super::identity::{DEFAULT_USER_AGENT, identity};
+use super::provenance::Provenance;
+use super::run_dir::SummaryItem;
+use super::targets::{Tags, TargetSet};
+use super::tls::insecure_client_config;
+use clap::Args;
+use reqwest::Client;
+use reqwest::header::USER_AGENT;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::runtime::Handle;
+
+/// 默认每次调用脚本函数的执行时限（毫秒）
+pub const DEFAULT_SCRIPT_TIMEOUT_MS: u64 = 1000;
+
+/// `http_get` 读取的响应正文上限，超出部分截断
+pub const MAX_FETCH_BYTES: usize = 256 * 1024;
+
+/// 逐条打印的脚本错误数，其余只计入统计
+const MAX_ERRORS_SHOWN: usize = 5;
+
+/// 返回值中表示派生发现的键
+const FINDINGS_KEY: &str = "findings";
+
+/// 脚本参数
+#[derive(Args, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptArgs {
+    /// 结果处理脚本（rhai），可定义 on_host_result、on_port_result、on_finding，
+    /// 返回的字段作为附加列导出，返回的 findings 作为派生发现（示例见 examples/scripts）
+    #[arg(long, value_name = "FILE", env = "GXTOOLS_SCRIPT")]
+    pub script: Option<PathBuf>,
+
+    /// 每次调用脚本函数的执行时限（毫秒），超时的调用被终止并计为出错
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value_t = DEFAULT_SCRIPT_TIMEOUT_MS,
+        requires = "script"
+    )]
+    pub script_timeout: u64,
+}
+
+/// 脚本函数（调用点）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookPoint {
+    /// 每个主机的结果（Ping扫描）
+    HostResult,
+    /// 每个开放端口（端口扫描）
+    PortResult,
+    /// 模块产生的每条发现（不含脚本派生的发现）
+    Finding,
+}
+
+impl HookPoint {
+    /// 脚本中的函数名
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::HostResult => "on_host_result",
+            Self::PortResult => "on_port_result",
+            Self::Finding => "on_finding",
+        }
+    }
+}
+
+impl fmt::Display for HookPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// 可交给脚本处理的结果行
+pub trait ScriptRow: Serialize + Send + 'static {
+    /// 资产（派生发现未指定 asset 时使用）
+    fn asset(&self) -> &str;
+
+    /// 端口（派生发现未指定 port 时使用）
+    fn port(&self) -> Option<u16> {
+        None
+    }
+
+    /// 是否调用脚本（如端口扫描只处理开放端口）
+    fn scripted(&self) -> bool {
+        true
+    }
+
+    /// 脚本返回的字段合并到的位置（与目标标签一样作为附加列导出）
+    fn fields_mut(&mut self) -> &mut Tags;
+
+    /// 结果行的观测（派生发现沿用，没有时以调用脚本的时间为准）
+    fn observed(&self) -> Option<&Provenance> {
+        None
+    }
+}
+
+/// 脚本返回的派生发现，只有 title 必填
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ScriptFinding {
+    asset: Option<String>,
+    port: Option<u16>,
+    protocol: Option<String>,
+    check_id: Option<String>,
+    title: String,
+    severity: Option<String>,
+    evidence: String,
+    remediation: String,
+}
+
+/// 一次调用的返回值
+#[derive(Debug, Default)]
+struct HookOutput {
+    fields: Tags,
+    findings: Vec<ScriptFinding>,
+}
+
+/// 脚本调用统计
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptStats {
+    /// 调用次数
+    pub calls: u64,
+    /// 出错次数（含超时）
+    pub errors: u64,
+    /// 超时次数
+    pub timeouts: u64,
+    /// 返回了附加字段的结果行数
+    pub rows_updated: u64,
+    /// 派生发现数
+    pub derived: u64,
+}
+
+/// 脚本可用的HTTP请求
+///
+/// 只允许访问本次扫描的目标，每个请求发出前取得扫描的全局节流许可；
+/// 不跟随跳转，脚本无法借助重定向访问目标以外的地址。
+pub struct ScriptFetch {
+    ctx: ScanContext,
+    runtime: Handle,
+    targets: TargetSet,
+    client: Client,
+}
+
+impl ScriptFetch {
+    /// 创建请求助手，须在异步运行时中调用
+    ///
+    /// # 参数
+    /// * `ctx` - 扫描上下文（使用其节流器）
+    /// * `targets` - 本次扫描的目标（按区间保存，不展开为IP列表）
+    ///
+    /// # 返回
+    /// * `Err` - 不在异步运行时中或无法创建HTTP客户端
+    pub fn new(
+        ctx: &ScanContext,
+        targets: &TargetSet,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let runtime =
+            Handle::try_current().map_err(|e| format!("脚本请求需要异步运行时: {}", e))?;
+        let client = Client::builder()
+            .use_preconfigured_tls(insecure_client_config(&[b"http/1.1"]))
+            .redirect(reqwest::redirect::Policy::none())
+            .default_headers(identity().header_map())
+            .build()
+            .map_err(|e| format!("无法创建HTTP客户端: {}", e))?;
+        Ok(Self {
+            ctx: ctx.clone(),
+            runtime,
+            targets: targets.clone(),
+            client,
+        })
+    }
+
+    /// 检查地址是否允许访问，返回主机名
+    fn check(&self, url: &str) -> Result<(reqwest::Url, String), String> {
+        let url = reqwest::Url::parse(url).map_err(|e| format!("无效的地址 {}: {}", url, e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("只允许 http/https 地址: {}", url));
+        }
+        // IPv6地址在URL中带方括号
+        let host = match url.host_str() {
+            Some(host) => host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string(),
+            None => return Err(format!("地址缺少主机: {}", url)),
+        };
+        if !self.targets.contains(&host) {
+            return Err(format!("{} 不在本次扫描的目标中，不允许访问", host));
+        }
+        Ok((url, host))
+    }
+
+    /// 发出GET请求（阻塞当前线程，只能在阻塞线程池中调用）
+    ///
+    /// # 参数
+    /// * `url` - 请求地址
+    /// * `budget` - 剩余的执行时间（含等待节流许可的时间）
+    ///
+    /// # 返回
+    /// * `Ok((状态码, 正文))` - 正文超过 [`MAX_FETCH_BYTES`] 时截断
+    fn get(&self, url: &str, budget: Duration) -> Result<(u16, String), String> {
+        let (url, host) = self.check(url)?;
+        let request = async {
+            let _permit = self.ctx.throttle().acquire(&host).await;
+            let mut response = self
+                .client
+                .get(url)
+                .header(USER_AGENT, identity().user_agent(DEFAULT_USER_AGENT))
+                .send()
+                .await
+                .map_err(|e| format!("请求失败: {}", e))?;
+            let status = response.status().as_u16();
+            let mut body = Vec::new();
+            while body.len() < MAX_FETCH_BYTES
+                && let Some(chunk) = response
+                    .chunk()
+                    .await
+                    .map_err(|e| format!("读取响应失败: {}", e))?
+            {
+                body.extend_from_slice(&chunk);
+            }
+            body.truncate(MAX_FETCH_BYTES);
+            Ok((status, String::from_utf8_lossy(&body).into_owned()))
+        };
+        self.runtime
+            .block_on(async { tokio::time::timeout(budget, request).await })
+            .map_err(|_| "请求超出脚本执行时限".to_string())?
+    }
+}
+
+/// 结果处理脚本
+///
+/// 脚本在受限的引擎中执行：不能导入模块、读写文件或访问网络（只有 `http_get`），
+/// 调用深度、字符串及集合大小均有上限，每次调用超过时限即被终止。
+/// 出错或超时的调用不影响扫描结果，只计入统计。
+pub struct ScriptHook {
+    engine: Engine,
+    ast: AST,
+    name: String,
+    module: String,
+    timeout: Duration,
+    deadline: Arc<Mutex<Option<Instant>>>,
+    stats: ScriptStats,
+}
+
+impl ScriptHook {
+    /// 按参数加载脚本（未指定 `--script` 时返回 `None`）
+    ///
+    /// # 参数
+    /// * `args` - 脚本参数
+    /// * `module` - 派生发现所属的模块（如 "pentest portscan"）
+    /// * `fetch` - 脚本可用的HTTP请求（为 `None` 时脚本中没有 `http_get`）
+    ///
+    /// # 返回
+    /// * `Err` - 无法读取或编译脚本（在扫描开始前报错）
+    pub fn load(
+        args: &ScriptArgs,
+        module: &str,
+        fetch: Option<ScriptFetch>,
+    ) -> Result<Option<Self>, Box<dyn Error + Send + Sync>> {
+        let Some(ref path) = args.script else {
+            return Ok(None);
+        };
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("无法读取脚本 {}: {}", path.display(), e))?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        let timeout = Duration::from_millis(args.script_timeout.max(1));
+        Self::compile(&source, &name, module, timeout, fetch).map(Some)
+    }
+
+    /// 编译脚本并执行其顶层语句
+    ///
+    /// # 参数
+    /// * `source` - 脚本内容
+    /// * `name` - 脚本名称（用于提示及派生发现的检查项ID）
+    /// * `module` - 派生发现所属的模块
+    /// * `timeout` - 每次调用的执行时限
+    /// * `fetch` - 脚本可用的HTTP请求
+    ///
+    /// # 返回
+    /// * `Err` - 编译失败、顶层语句出错或没有定义任何脚本函数
+    pub fn compile(
+        source: &str,
+        name: &str,
+        module: &str,
+        timeout: Duration,
+        fetch: Option<ScriptFetch>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let deadline = Arc::new(Mutex::new(None));
+        let engine = sandboxed_engine(name, &deadline, fetch);
+        let ast = engine
+            .compile(source)
+            .map_err(|e| format!("脚本 {} 编译失败: {}", name, e))?;
+
+        let hook = Self {
+            engine,
+            ast,
+            name: name.to_string(),
+            module: module.to_string(),
+            timeout,
+            deadline,
+            stats: ScriptStats::default(),
+        };
+        if ![
+            HookPoint::HostResult,
+            HookPoint::PortResult,
+            HookPoint::Finding,
+        ]
+        .into_iter()
+        .any(|point| hook.has(point))
+        {
+            return Err(format!(
+                "脚本 {} 没有定义 on_host_result、on_port_result 或 on_finding",
+                name
+            )
+            .into());
+        }
+        // 顶层语句（如常量、提示信息）只执行一次
+        hook.arm();
+        let result = hook.engine.run_ast(&hook.ast);
+        hook.disarm();
+        result.map_err(|e| format!("脚本 {} 执行出错: {}", name, e))?;
+        Ok(hook)
+    }
+
+    /// 脚本是否定义了某个函数
+    pub fn has(&self, point: HookPoint) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|f| f.name == point.name() && f.params.len() == 1)
+    }
+
+    /// 调用统计
+    pub fn stats(&self) -> ScriptStats {
+        self.stats
+    }
+
+    /// 统计摘要中的脚本调用情况
+    pub fn summary_item(&self) -> SummaryItem {
+        let s = &self.stats;
+        let mut value = format!("{}: 调用 {} 次", self.name, s.calls);
+        if s.errors > 0 {
+            value.push_str(&format!("，出错 {} 次", s.errors));
+            if s.timeouts > 0 {
+                value.push_str(&format!("（超时 {} 次）", s.timeouts));
+            }
+        }
+        value.push_str(&format!(
+            "，附加字段 {} 行，派生发现 {} 个",
+            s.rows_updated, s.derived
+        ));
+        ("脚本".to_string(), value)
+    }
+
+    /// 对结果行调用脚本，返回的字段合并到结果行
+    ///
+    /// # 参数
+    /// * `point` - 调用的脚本函数
+    /// * `rows` - 结果行
+    ///
+    /// # 返回
+    /// * 脚本派生的发现
+    pub fn apply_rows<T: ScriptRow>(&mut self, point: HookPoint, rows: &mut [T]) -> Vec<Finding> {
+        if !self.has(point) {
+            return Vec::new();
+        }
+        let mut derived = Vec::new();
+        for row in rows.iter_mut().filter(|r| r.scripted()) {
+            let location = (row.asset().to_string(), row.port());
+            let Some(output) = self.call(point, &*row, &location) else {
+                continue;
+            };
+            derived.extend(self.derive(output.findings, &location, row.observed()));
+            if !output.fields.is_empty() {
+                self.stats.rows_updated += 1;
+                row.fields_mut().extend(output.fields);
+            }
+        }
+        derived
+    }
+
+    /// 对发现调用脚本
+    ///
+    /// # 参数
+    /// * `findings` - 模块产生的发现
+    ///
+    /// # 返回
+    /// * 脚本派生的发现（返回的其他字段对发现没有意义，忽略）
+    pub fn apply_findings(&mut self, findings: &[Finding]) -> Vec<Finding> {
+        if !self.has(HookPoint::Finding) {
+            return Vec::new();
+        }
+        let mut derived = Vec::new();
+        for finding in findings {
+            let value = serde_json::json!({
+                "module": finding.module,
+                "asset": finding.asset,
+                "port": finding.port,
+                "protocol": finding.protocol,
+                "check_id": finding.check_id,
+                "title": finding.title,
+                "severity": finding.severity.to_string(),
+                "evidence": finding.evidence,
+                "remediation": finding.remediation,
+            });
+            let location = (finding.asset.clone(), finding.port);
+            if let Some(output) = self.call(HookPoint::Finding, &value, &location) {
+                derived.extend(self.derive(output.findings, &location, Some(&finding.observed)));
+            }
+        }
+        derived
+    }
+
+    /// 调用一次脚本函数，出错时打印提示并返回 `None`
+    fn call<T: Serialize + ?Sized>(
+        &mut self,
+        point: HookPoint,
+        value: &T,
+        location: &(String, Option<u16>),
+    ) -> Option<HookOutput> {
+        self.stats.calls += 1;
+        let result = rhai::serde::to_dynamic(value).and_then(|arg| {
+            self.arm();
+            let result = self.engine.call_fn_with_options::<Dynamic>(
+                CallFnOptions::new().eval_ast(false),
+                &mut Scope::new(),
+                &self.ast,
+                point.name(),
+                (arg,),
+            );
+            self.disarm();
+            result
+        });
+        let error = match result {
+            Ok(value) => match parse_output(value) {
+                Ok(output) => return Some(output),
+                Err(e) => e,
+            },
+            Err(e) if matches!(e.unwrap_inner(), EvalAltResult::ErrorTerminated(..)) => {
+                self.stats.timeouts += 1;
+                format!("超过执行时限 {}ms", self.timeout.as_millis())
+            }
+            Err(e) => e.to_string(),
+        };
+        self.stats.errors += 1;
+        if self.stats.errors as usize <= MAX_ERRORS_SHOWN {
+            let target = match location.1 {
+                Some(port) => format!("{}:{}", location.0, port),
+                None => location.0.clone(),
+            };
+            println!(
+                "{} 脚本 {} 处理 {} 出错: {}",
+                Icon::Warn,
+                point,
+                target,
+                error
+            );
+        }
+        None
+    }
+
+    /// 将脚本返回的派生发现补全为模块的发现（缺少标题的忽略）
+    ///
+    /// # 参数
+    /// * `findings` - 脚本返回的派生发现
+    /// * `location` - 调用脚本的资产及端口
+    /// * `observed` - 调用脚本的结果行或发现的观测
+    fn derive(
+        &mut self,
+        findings: Vec<ScriptFinding>,
+        location: &(String, Option<u16>),
+        observed: Option<&Provenance>,
+    ) -> Vec<Finding> {
+        let stem = Path::new(&self.name)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let observed = observed
+            .cloned()
+            .unwrap_or_else(|| Provenance::now(format!("script:{}", stem)));
+        let derived: Vec<Finding> = findings
+            .into_iter()
+            .filter(|f| !f.title.trim().is_empty())
+            .map(|f| Finding {
+                module: self.module.clone(),
+                asset: f.asset.unwrap_or_else(|| location.0.clone()),
+                port: f.port.or(location.1),
+                protocol: f.protocol.unwrap_or_else(|| "tcp".to_string()),
+                check_id: f.check_id.unwrap_or_else(|| format!("script:{}", stem)),
+                title: f.title,
+                severity: f
+                    .severity
+                    .as_deref()
+                    .and_then(Severity::from_label)
+                    .unwrap_or(Severity::Info),
+                evidence: f.evidence,
+                remediation: f.remediation,
+                params: FindingParams::new(),
+                sources: Vec::new(),
+                observed: observed.clone(),
+            })
+            .collect();
+        self.stats.derived += derived.len() as u64;
+        derived
+    }
+
+    /// 开始计时，超过时限后引擎在下一次检查时终止脚本
+    fn arm(&self) {
+        *self.deadline.lock().unwrap() = Some(Instant::now() + self.timeout);
+    }
+
+    fn disarm(&self) {
+        *self.deadline.lock().unwrap() = None;
+    }
+}
+
+/// 创建受限的脚本引擎
+fn sandboxed_engine(
+    name: &str,
+    deadline: &Arc<Mutex<Option<Instant>>>,
+    fetch: Option<ScriptFetch>,
+) -> Engine {
+    let mut engine = Engine::new();
+    // 不允许 import 任何模块，也不允许 eval 拼接的代码
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(1024 * 1024)
+        .set_max_array_size(10_000)
+        .set_max_map_size(10_000);
+
+    let clock = deadline.clone();
+    engine.on_progress(move |ops| {
+        // 每隔一批操作检查一次，避免频繁读取时钟
+        if ops % 256 != 0 {
+            return None;
+        }
+        clock
+            .lock()
+            .unwrap()
+            .is_some_and(|d| Instant::now() >= d)
+            .then(|| Dynamic::from("timeout"))
+    });
+    let prefix = format!("📜 [{}]", name);
+    engine.on_print(move |text| println!("{} {}", prefix, text));
+
+    if let Some(fetch) = fetch {
+        let clock = deadline.clone();
+        engine.register_fn(
+            "http_get",
+            move |url: &str| -> Result<Map, Box<EvalAltResult>> {
+                let budget = clock
+                    .lock()
+                    .unwrap()
+                    .map(|d| d.saturating_duration_since(Instant::now()))
+                    .unwrap_or_default();
+                if budget.is_zero() {
+                    return Err("超出脚本执行时限".into());
+                }
+                let (status, body) = fetch.get(url, budget)?;
+                let mut response = Map::new();
+                response.insert("status".into(), (status as i64).into());
+                response.insert("body".into(), body.into());
+                Ok(response)
+            },
+        );
+    }
+    engine
+}
+
+/// 解析脚本函数的返回值（`()` 表示没有附加内容）
+fn parse_output(value: Dynamic) -> Result<HookOutput, String> {
+    if value.is_unit() {
+        return Ok(HookOutput::default());
+    }
+    let type_name = value.type_name();
+    let map = value
+        .try_cast::<Map>()
+        .ok_or_else(|| format!("返回值须为对象映射或 ()，实际为 {}", type_name))?;
+    let mut output = HookOutput::default();
+    for (key, value) in map {
+        if key == FINDINGS_KEY {
+            output.findings = rhai::serde::from_dynamic(&value)
+                .map_err(|e| format!("findings 格式不正确: {}", e))?;
+        } else if !value.is_unit() {
+            output.fields.insert(key.to_string(), value.to_string());
+        }
+    }
+    Ok(output)
+}
+
+/// 打印脚本派生的发现
+pub fn print_derived(findings: &[Finding]) {
+    if findings.is_empty() {
+        return;
+    }
+    println!("\n{} 脚本派生的发现:", Icon::List);
+    for f in findings {
+        let asset = match f.port {
+            Some(port) => format!("{}:{}", f.asset, port),
+            None => f.asset.clone(),
+        };
+        println!("   [{}] {} {}", f.severity.label(), asset, f.title);
+    }
+}
+
+/// 脚本处理阶段（放到阻塞线程池执行，见 [`super::blocking::run_stage`]）
+///
+/// 先对结果行调用脚本，再对模块的发现及结果行派生的发现调用 `on_finding`；
+/// 产出处理后的脚本（供统计）、结果行及全部派生的发现。
+pub struct ScriptStage<T> {
+    hook: ScriptHook,
+    point: HookPoint,
+    rows: Vec<T>,
+    findings: Vec<Finding>,
+}
+
+impl<T: ScriptRow> ScriptStage<T> {
+    /// 创建处理阶段
+    ///
+    /// # 参数
+    /// * `hook` - 已加载的脚本
+    /// * `point` - 结果行调用的脚本函数
+    /// * `rows` - 结果行
+    /// * `findings` - 模块产生的发现（交给 `on_finding`）
+    pub fn new(hook: ScriptHook, point: HookPoint, rows: Vec<T>, findings: Vec<Finding>) -> Self {
+        Self {
+            hook,
+            point,
+            rows,
+            findings,
+        }
+    }
+}
+
+impl<T: ScriptRow> BlockingStage for ScriptStage<T> {
+    type Output = (ScriptHook, Vec<T>, Vec<Finding>);
+    const NAME: &'static str = "脚本处理";
+
+    fn run(mut self) -> Result<Self::Output, Box<dyn Error + Send + Sync>> {
+        let mut derived = self.hook.apply_rows(self.point, &mut self.rows);
+        self.findings.extend(derived.iter().cloned());
+        derived.extend(self.hook.apply_findings(&self.findings));
+        Ok((self.hook, self.rows, derived))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Port {
+        ip: String,
+        port: u16,
+        status: String,
+        tags: Tags,
+    }
+
+    impl ScriptRow for Port {
+        fn asset(&self) -> &str {
+            &self.ip
+        }
+
+        fn port(&self) -> Option<u16> {
+            Some(self.port)
+        }
+
+        fn scripted(&self) -> bool {
+            self.status == "开放"
+        }
+
+        fn fields_mut(&mut self) -> &mut Tags {
+            &mut self.tags
+        }
+    }
+
+    fn port(port: u16, status: &str) -> Port {
+        Port {
+            ip: "10.0.0.1".to_string(),
+            port,
+            status: status.to_string(),
+            tags: Tags::new(),
+        }
+    }
+
+    fn hook(source: &str) -> ScriptHook {
+        ScriptHook::compile(
+            source,
+            "test.rhai",
+            "pentest portscan",
+            Duration::from_millis(200),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_hook_points_merge_fields_and_derive_findings() {
+        let script = hook(include_str!("../../examples/scripts/risky_ports.rhai"));
+        let rows = vec![port(445, "开放"), port(80, "开放"), port(23, "关闭")];
+        let base = vec![Finding {
+            module: "pentest portscan".to_string(),
+            asset: "10.0.0.1".to_string(),
+            port: Some(445),
+            protocol: "tcp".to_string(),
+            check_id: "open-port".to_string(),
+            title: "开放端口 445/tcp".to_string(),
+            severity: Severity::Info,
+            evidence: String::new(),
+            remediation: String::new(),
+            params: FindingParams::new(),
+            sources: Vec::new(),
+            observed: Provenance::default(),
+        }];
+        let (script, rows, derived) = ScriptStage::new(script, HookPoint::PortResult, rows, base)
+            .run()
+            .unwrap();
+
+        // 字段只加到脚本处理过的开放端口
+        assert_eq!(rows[0].tags["服务分类"], "文件共享");
+        assert_eq!(rows[1].tags["服务分类"], "Web");
+        assert!(rows[2].tags.is_empty());
+
+        // on_port_result 派生一条，on_finding 对其再派生一条
+        assert_eq!(derived.len(), 2);
+        assert_eq!(derived[0].check_id, "smb-exposed");
+        assert_eq!(derived[0].asset, "10.0.0.1");
+        assert_eq!(derived[0].port, Some(445));
+        assert_eq!(derived[0].severity, Severity::Medium);
+        assert_eq!(derived[1].check_id, "script:test");
+        assert_eq!(derived[1].severity, Severity::High);
+        let stats = script.stats();
+        assert_eq!((stats.calls, stats.errors, stats.rows_updated), (4, 0, 2));
+
+        // 另一个示例脚本用到 http_get，未提供时同样可以编译
+        let web = hook(include_str!("../../examples/scripts/web_title.rhai"));
+        assert!(web.has(HookPoint::HostResult) && !web.has(HookPoint::Finding));
+    }
+
+    #[test]
+    fn test_runaway_and_sandboxed_calls_are_contained() {
+        let mut script = hook(
+            r#"
+            fn on_host_result(r) {
+                if r.ip == "10.0.0.1" { loop {} }
+                if r.ip == "10.0.0.2" { import "fs" as fs; return fs::read("/etc/passwd"); }
+                if r.ip == "10.0.0.3" { return http_get("http://10.0.0.3/"); }
+                #{ checked: true }
+            }
+            "#,
+        );
+        #[derive(Serialize)]
+        struct Host {
+            ip: String,
+            tags: Tags,
+        }
+        impl ScriptRow for Host {
+            fn asset(&self) -> &str {
+                &self.ip
+            }
+            fn fields_mut(&mut self) -> &mut Tags {
+                &mut self.tags
+            }
+        }
+        let mut hosts: Vec<Host> = (1..=4)
+            .map(|i| Host {
+                ip: format!("10.0.0.{}", i),
+                tags: Tags::new(),
+            })
+            .collect();
+
+        let start = Instant::now();
+        assert!(
+            script
+                .apply_rows(HookPoint::HostResult, &mut hosts)
+                .is_empty()
+        );
+        assert!(start.elapsed() < Duration::from_secs(2));
+        // 死循环超时终止，import 与未开放的 http_get 出错，其余主机照常处理
+        assert_eq!(hosts[3].tags["checked"], "true");
+        assert!(hosts[..3].iter().all(|h| h.tags.is_empty()));
+        let stats = script.stats();
+        assert_eq!((stats.calls, stats.errors, stats.timeouts), (4, 3, 1));
+
+        assert!(
+            ScriptHook::compile(
+                "let x = 1;",
+                "x.rhai",
+                "net ping",
+                Duration::from_millis(10),
+                None
+            )
+            .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_only_allows_scan_targets() {
+        let mut targets = TargetSet::default();
+        targets.add_ranges(
+            "10.0.0.0/30",
+            vec![(0x0a00_0001, 0x0a00_0002)],
+            &Tags::new(),
+        );
+        targets.add("localhost", vec!["::1".to_string()]);
+        let fetch = ScriptFetch::new(&ScanContext::cli(), &targets).unwrap();
+        assert!(fetch.check("http://10.0.0.1:8080/x").is_ok());
+        assert_eq!(fetch.check("http://[::1]/").unwrap().1, "::1");
+        assert!(fetch.check("http://localhost/").is_ok());
+        assert!(fetch.check("http://10.0.0.3/").is_err());
+        assert!(fetch.check("file:///etc/passwd").is_err());
+    }
+}
@@ -0,0 +1,405 @@
+// src/utils/secret.rs
+use clap::{Args, Command};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use zeroize::Zeroize;
+
+/// 日志、调试输出中代替敏感值的占位符
+pub const REDACTED: &str = "******";
+
+/// 命令行中表示"交互输入"的参数值
+pub const PROMPT_VALUE: &str = "-";
+
+static SHOW_SECRETS: OnceLock<bool> = OnceLock::new();
+
+/// 设置报告中是否显示明文（仅首次设置生效，应在程序启动时调用）
+pub fn set_show_secrets(show: bool) {
+    let _ = SHOW_SECRETS.set(show);
+}
+
+/// 报告中是否显示明文，默认不显示
+pub fn show_secrets() -> bool {
+    *SHOW_SECRETS.get_or_init(|| false)
+}
+
+/// 敏感值包装
+///
+/// `Debug`/`Display` 永远只输出占位符；序列化（写入JSON、Excel等报告）时按
+/// [`show_secrets`] 策略输出部分遮盖的值；释放时清零内存。取明文必须显式调用 [`Secret::expose`]。
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// 取出明文（只应在真正使用凭据时调用）
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// 报告中显示的值：默认部分遮盖，开启 `--show-secrets` 时为明文
+    pub fn for_report(&self) -> String {
+        if show_secrets() {
+            self.0.clone()
+        } else {
+            mask(&self.0)
+        }
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({})", REDACTED)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl FromStr for Secret {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(s))
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.for_report())
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self)
+    }
+}
+
+/// 遮盖敏感值，只保留首尾字符，如 `password` -> `p*****d`
+///
+/// # 参数
+/// * `value` - 原始值
+///
+/// # 返回
+/// * `String` - 遮盖后的值（不超过3个字符时全部遮盖）
+pub fn mask(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= 3 {
+        return REDACTED.to_string();
+    }
+    format!("{}*****{}", chars[0], chars[chars.len() - 1])
+}
+
+/// 密码输入参数（三种来源互斥）
+///
+/// 优先使用 `--password -`（交互输入）、`--password-file` 或 `--password-env`，
+/// 直接写在命令行上的密码会出现在进程列表和shell历史中。
+#[derive(Args, Debug, Clone, Default)]
+pub struct PasswordArgs {
+    /// 密码（为 - 时交互输入且不回显）
+    #[arg(
+        long,
+        env = "GXTOOLS_PASSWORD",
+        hide_env_values = true,
+        value_name = "PASSWORD|-",
+        group = "password_source"
+    )]
+    pub password: Option<Secret>,
+
+    /// 从文件读取密码（取第一行）
+    #[arg(long, value_name = "FILE", group = "password_source")]
+    pub password_file: Option<PathBuf>,
+
+    /// 从指定环境变量读取密码
+    #[arg(long, value_name = "VAR", group = "password_source")]
+    pub password_env: Option<String>,
+}
+
+impl PasswordArgs {
+    /// 按参数取得密码
+    ///
+    /// # 返回
+    /// * `Ok(Some(Secret))` - 取得的密码
+    /// * `Ok(None)` - 未指定任何来源
+    /// * `Err` - 文件或环境变量读取失败、交互输入被取消
+    pub fn resolve(&self) -> Result<Option<Secret>, Box<dyn Error + Send + Sync>> {
+        if let Some(ref password) = self.password {
+            if password.expose() == PROMPT_VALUE {
+                return prompt_secret("请输入密码: ").map(Some);
+            }
+            return Ok(Some(password.clone()));
+        }
+        if let Some(ref path) = self.password_file {
+            let mut data = std::fs::read_to_string(path)
+                .map_err(|e| format!("读取密码文件 {} 失败: {}", path.display(), e))?;
+            let secret = Secret::new(data.lines().next().unwrap_or_default());
+            data.zeroize();
+            return Ok(Some(secret));
+        }
+        if let Some(ref var) = self.password_env {
+            let value = std::env::var(var).map_err(|_| format!("环境变量 {} 未设置", var))?;
+            return Ok(Some(Secret::new(value)));
+        }
+        Ok(None)
+    }
+}
+
+/// 交互读取敏感值，终端中不回显；标准输入不是终端时读取一行
+///
+/// # 参数
+/// * `prompt` - 提示文字（输出到标准错误）
+pub fn prompt_secret(prompt: &str) -> Result<Secret, Box<dyn Error + Send + Sync>> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        let mut line = String::new();
+        stdin.lock().read_line(&mut line)?;
+        let secret = Secret::new(line.trim_end_matches(['\r', '\n']));
+        line.zeroize();
+        return Ok(secret);
+    }
+
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+    terminal::enable_raw_mode()?;
+    let result = read_hidden();
+    terminal::disable_raw_mode()?;
+    eprintln!();
+    result
+}
+
+fn read_hidden() -> Result<Secret, Box<dyn Error + Send + Sync>> {
+    let mut value = Secret::default();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(value),
+            KeyCode::Esc => return Err("已取消输入".into()),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Err("已取消输入".into());
+            }
+            KeyCode::Backspace => {
+                value.0.pop();
+            }
+            KeyCode::Char(c) => value.0.push(c),
+            _ => {}
+        }
+    }
+}
+
+/// 收集命令树中所有敏感参数（`hide_env_values`）的长名
+///
+/// # 参数
+/// * `cmd` - 根命令
+pub fn sensitive_args(cmd: &Command) -> Vec<String> {
+    let mut names: Vec<String> = cmd
+        .get_arguments()
+        .filter(|a| a.is_hide_env_values_set())
+        .filter_map(|a| a.get_long().map(str::to_string))
+        .collect();
+    for sub in cmd.get_subcommands() {
+        names.extend(sensitive_args(sub));
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// 遮盖命令行中敏感参数的值（用于历史记录等会落盘的地方）
+///
+/// 支持 `--name value` 和 `--name=value` 两种写法，值为 `-`（交互输入）时保留。
+///
+/// # 参数
+/// * `argv` - 命令行参数
+/// * `sensitive` - 敏感参数的长名（不含 `--`）
+///
+/// # 返回
+/// * `String` - 以空格连接的遮盖后命令行
+pub fn redact_command_line(argv: &[String], sensitive: &[String]) -> String {
+    let is_sensitive = |flag: &str| {
+        flag.strip_prefix("--")
+            .is_some_and(|name| sensitive.iter().any(|s| s == name))
+    };
+    let mut out = Vec::with_capacity(argv.len());
+    let mut redact_next = false;
+    for arg in argv {
+        if redact_next {
+            redact_next = false;
+            out.push(if arg == PROMPT_VALUE {
+                arg.clone()
+            } else {
+                REDACTED.to_string()
+            });
+            continue;
+        }
+        match arg.split_once('=') {
+            Some((flag, value)) if is_sensitive(flag) && value != PROMPT_VALUE => {
+                out.push(format!("{}={}", flag, REDACTED));
+            }
+            _ => {
+                redact_next = is_sensitive(arg);
+                out.push(arg.clone());
+            }
+        }
+    }
+    out.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser, Debug)]
+    struct TestCli {
+        #[arg(long)]
+        username: String,
+        #[command(flatten)]
+        password: PasswordArgs,
+    }
+
+    #[test]
+    fn test_secret_never_prints_plaintext() {
+        let secret = Secret::new("Sup3rS3cret!");
+        assert_eq!(secret.to_string(), REDACTED);
+        assert!(!format!("{:?}", secret).contains("Sup3r"));
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"S*****!\"");
+        assert_eq!(mask("abc"), REDACTED);
+        assert_eq!(mask("password"), "p*****d");
+    }
+
+    #[test]
+    fn test_password_sources() {
+        let cli = TestCli::parse_from(["t", "--username", "admin", "--password", "hunter22"]);
+        assert_eq!(
+            cli.password.resolve().unwrap().unwrap().expose(),
+            "hunter22"
+        );
+        assert!(!format!("{:?}", cli).contains("hunter22"));
+
+        let file = std::env::temp_dir().join(format!("gxr_pw_{}", std::process::id()));
+        std::fs::write(&file, "from-file\nignored\n").unwrap();
+        let cli = TestCli::parse_from([
+            "t",
+            "--username",
+            "admin",
+            "--password-file",
+            file.to_str().unwrap(),
+        ]);
+        let resolved = cli.password.resolve();
+        std::fs::remove_file(&file).ok();
+        assert_eq!(resolved.unwrap().unwrap().expose(), "from-file");
+
+        let cli =
+            TestCli::parse_from(["t", "--username", "a", "--password-env", "GXR_NO_SUCH_VAR"]);
+        assert!(cli.password.resolve().is_err());
+
+        assert!(
+            TestCli::try_parse_from([
+                "t",
+                "--username",
+                "a",
+                "--password",
+                "x",
+                "--password-env",
+                "V"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_reports_never_contain_plaintext() {
+        use crate::utils::output::OutputKind;
+        use crate::utils::{ExcelOptions, save_to_excel_with_options};
+        use calamine::{Reader, open_workbook_auto};
+
+        #[derive(Serialize)]
+        struct Credential {
+            username: String,
+            password: Secret,
+        }
+        let found = vec![Credential {
+            username: "admin".to_string(),
+            password: Secret::new("hunter22"),
+        }];
+
+        let json = serde_json::to_string(&found).unwrap();
+        assert!(json.contains("admin") && json.contains("h*****2"));
+        assert!(!json.contains("hunter22"));
+
+        let root = std::env::temp_dir().join(format!("gxr_secret_{}", std::process::id()));
+        let options = ExcelOptions {
+            output_root: root.clone(),
+            ..Default::default()
+        };
+        let path = save_to_excel_with_options(
+            &found,
+            &["用户名", "密码"],
+            |c| vec![c.username.clone(), c.password.for_report()],
+            OutputKind {
+                subdir: "brute",
+                prefix: "brute",
+            },
+            &options,
+        )
+        .unwrap();
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let sheet = workbook.sheet_names()[0].clone();
+        let cells: Vec<String> = workbook
+            .worksheet_range(&sheet)
+            .unwrap()
+            .cells()
+            .map(|(_, _, c)| c.to_string())
+            .collect();
+        std::fs::remove_dir_all(&root).ok();
+        assert!(cells.contains(&"admin".to_string()));
+        assert!(cells.iter().all(|c| !c.contains("hunter22")));
+    }
+
+    #[test]
+    fn test_redact_command_line() {
+        let argv: Vec<String> = [
+            "gxtools",
+            "serve",
+            "--token",
+            "abc123",
+            "--password=hunter22",
+            "--password-file",
+            "pw.txt",
+            "--password",
+            "-",
+        ]
+        .map(String::from)
+        .to_vec();
+        let sensitive = sensitive_args(&<TestCli as clap::CommandFactory>::command());
+        assert_eq!(sensitive, ["password"]);
+        assert_eq!(
+            redact_command_line(&argv, &["token".to_string(), "password".to_string()]),
+            "gxtools serve --token ****** --password=****** --password-file pw.txt --password -"
+        );
+    }
+}
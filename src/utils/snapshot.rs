@@ -0,0 +1,428 @@
+// src/utils/snapshot.rs
+use super::blocking::{BlockingStage, run_stage};
+use super::console::Icon;
+use super::output::OutputKind;
+use super::redact::{export_json, save_export_mapping};
+use super::run_dir::RunDir;
+use super::{ScanProgress, ensure_output_dir, output_root};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::{MissedTickBehavior, interval};
+
+/// 中间结果文件名前缀
+pub const SNAPSHOT_PREFIX: &str = "partial";
+
+/// 中间结果快照参数
+#[derive(Args, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapshotArgs {
+    /// 每隔指定分钟写一次中间结果（partial_<n>.json，开启 -o 时另写 partial_<n>.xlsx）
+    #[arg(long, value_name = "MINS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub snapshot_every: Option<u64>,
+
+    /// 保留最近几份中间结果
+    #[arg(
+        long,
+        default_value = "3",
+        value_name = "K",
+        requires = "snapshot_every"
+    )]
+    pub snapshot_keep: usize,
+
+    /// 扫描结束后保留中间结果（默认由最终结果替换）
+    #[arg(long, requires = "snapshot_every")]
+    pub keep_snapshots: bool,
+}
+
+impl Default for SnapshotArgs {
+    fn default() -> Self {
+        Self {
+            snapshot_every: None,
+            snapshot_keep: 3,
+            keep_snapshots: false,
+        }
+    }
+}
+
+/// 扫描过程中不断累积的结果
+///
+/// 探测结果逐个追加，快照任务可随时克隆当前内容，不影响分发。
+/// 克隆只复制引用。
+#[derive(Debug)]
+pub struct ResultCollector<T> {
+    inner: Arc<RwLock<Vec<T>>>,
+}
+
+impl<T> Clone for ResultCollector<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Default for ResultCollector<T> {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+}
+
+impl<T> ResultCollector<T> {
+    /// 创建空的结果集
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加一条结果
+    pub fn push(&self, result: T) {
+        self.inner.write().unwrap().push(result);
+    }
+
+    /// 当前结果数
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().len()
+    }
+
+    /// 是否还没有结果
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: Clone> ResultCollector<T> {
+    /// 当前结果的副本
+    pub fn snapshot(&self) -> Vec<T> {
+        self.inner.read().unwrap().clone()
+    }
+
+    /// 取出全部结果（仍有其他引用时返回副本）
+    pub fn into_vec(self) -> Vec<T> {
+        match Arc::try_unwrap(self.inner) {
+            Ok(lock) => lock.into_inner().unwrap(),
+            Err(inner) => inner.read().unwrap().clone(),
+        }
+    }
+}
+
+/// 快照写入函数：参数为当前结果及快照序号，返回写入的文件
+pub type SnapshotWriter<T> =
+    Box<dyn FnMut(&[T], usize) -> Result<Vec<PathBuf>, Box<dyn Error + Send + Sync>> + Send>;
+
+/// 定期写中间结果的后台任务
+pub struct Snapshotter {
+    stop: oneshot::Sender<()>,
+    handle: JoinHandle<VecDeque<Vec<PathBuf>>>,
+    run_dir: Option<Arc<RunDir>>,
+}
+
+impl Snapshotter {
+    /// 按参数启动快照任务，未开启 `--snapshot-every` 时返回 `None`
+    ///
+    /// 每个周期克隆一次当前结果交给 `write`，只保留最近 `--snapshot-keep` 份，
+    /// 更早的文件（及其在运行目录索引中的条目）被删除。没有新结果的周期不写文件。
+    ///
+    /// # 参数
+    /// * `args` - 快照参数
+    /// * `results` - 扫描中累积的结果
+    /// * `run_dir` - 本次运行的工作目录（删除旧快照时同步更新产物索引）
+    /// * `progress` - 进度条（用于输出信息）
+    /// * `write` - 写入一份快照
+    pub fn start<T>(
+        args: &SnapshotArgs,
+        results: &ResultCollector<T>,
+        run_dir: Option<Arc<RunDir>>,
+        progress: &ScanProgress,
+        write: SnapshotWriter<T>,
+    ) -> Option<Self>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        let every = Duration::from_secs(args.snapshot_every? * 60);
+        let (stop, stopped) = oneshot::channel();
+        let handle = tokio::spawn(snapshot_loop(
+            every,
+            args.snapshot_keep.max(1),
+            results.clone(),
+            run_dir.clone(),
+            progress.clone(),
+            write,
+            stopped,
+        ));
+        Some(Self {
+            stop,
+            handle,
+            run_dir,
+        })
+    }
+
+    /// 停止快照任务
+    ///
+    /// # 参数
+    /// * `keep` - 是否保留已写入的中间结果（否则删除，由最终结果替换）
+    ///
+    /// # 返回
+    /// * `usize` - 保留的快照份数
+    pub async fn finish(self, keep: bool) -> usize {
+        let _ = self.stop.send(());
+        let snapshots = self.handle.await.unwrap_or_default();
+        if keep {
+            return snapshots.len();
+        }
+        for files in snapshots {
+            remove_snapshot(&files, self.run_dir.as_deref());
+        }
+        0
+    }
+}
+
+/// 写一份快照的阶段（写入函数随阶段移入阻塞线程，写完后交还给快照任务）
+struct SnapshotWrite<T> {
+    write: SnapshotWriter<T>,
+    rows: Vec<T>,
+    n: usize,
+}
+
+type SnapshotWritten<T> = (
+    SnapshotWriter<T>,
+    Vec<T>,
+    Result<Vec<PathBuf>, Box<dyn Error + Send + Sync>>,
+);
+
+impl<T: Send + 'static> BlockingStage for SnapshotWrite<T> {
+    type Output = SnapshotWritten<T>;
+    const NAME: &'static str = "中间结果写入";
+
+    fn run(mut self) -> Result<SnapshotWritten<T>, Box<dyn Error + Send + Sync>> {
+        let written = (self.write)(&self.rows, self.n);
+        Ok((self.write, self.rows, written))
+    }
+}
+
+async fn snapshot_loop<T: Clone + Send + 'static>(
+    every: Duration,
+    keep: usize,
+    results: ResultCollector<T>,
+    run_dir: Option<Arc<RunDir>>,
+    progress: ScanProgress,
+    mut write: SnapshotWriter<T>,
+    mut stopped: oneshot::Receiver<()>,
+) -> VecDeque<Vec<PathBuf>> {
+    let mut ticker = interval(every);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // 第一次触发是立即的，跳过
+    ticker.tick().await;
+
+    let mut written: VecDeque<Vec<PathBuf>> = VecDeque::new();
+    let mut last_len = 0;
+    let mut n = 0;
+    loop {
+        tokio::select! {
+            _ = &mut stopped => break,
+            _ = ticker.tick() => {}
+        }
+
+        let rows = results.snapshot();
+        if rows.len() == last_len {
+            continue;
+        }
+        last_len = rows.len();
+        n += 1;
+        // Excel生成等写入工作在阻塞线程中进行，不拖慢进行中的探测
+        let (writer, rows, written_files) = match run_stage(SnapshotWrite { write, rows, n }).await
+        {
+            Ok(done) => done,
+            Err(e) => {
+                progress.println(format!("{} 写入中间结果失败: {}", Icon::Warn, e));
+                break;
+            }
+        };
+        write = writer;
+        match written_files {
+            Ok(files) => {
+                progress.println(format!(
+                    "📸 已写入第 {} 份中间结果（{} 条）: {}",
+                    n,
+                    rows.len(),
+                    files
+                        .iter()
+                        .map(|f| f.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+                written.push_back(files);
+                while written.len() > keep {
+                    if let Some(old) = written.pop_front() {
+                        remove_snapshot(&old, run_dir.as_deref());
+                    }
+                }
+            }
+            Err(e) => progress.println(format!("{} 写入中间结果失败: {}", Icon::Warn, e)),
+        }
+    }
+    written
+}
+
+/// 删除一份快照的文件（尽力而为）
+fn remove_snapshot(files: &[PathBuf], run_dir: Option<&RunDir>) {
+    for file in files {
+        let removed = match run_dir {
+            Some(run) => run.remove(file),
+            None => fs::remove_file(file).map_err(Into::into),
+        };
+        if let Err(e) = removed {
+            eprintln!("{} 删除中间结果 {} 失败: {}", Icon::Warn, file.display(), e);
+        }
+    }
+}
+
+/// 快照文件名：运行目录下为 `partial_<n>.<扩展名>`，平铺目录下加模块前缀避免与其他运行冲突
+///
+/// # 参数
+/// * `prefix` - 模块的文件名前缀（如 "ping"）
+/// * `n` - 快照序号
+/// * `ext` - 扩展名
+/// * `flat` - 是否写入按模块平铺的目录
+pub fn snapshot_file_name(prefix: &str, n: usize, ext: &str, flat: bool) -> String {
+    if flat {
+        format!("{}_{}_{}.{}", prefix, SNAPSHOT_PREFIX, n, ext)
+    } else {
+        format!("{}_{}.{}", SNAPSHOT_PREFIX, n, ext)
+    }
+}
+
+/// 把一份结果写成JSON快照（运行目录中登记为 "snapshot" 类型，`report view` 不展示）
+///
+/// # 参数
+/// * `rows` - 当前结果
+/// * `n` - 快照序号
+/// * `kind` - 平铺输出时的模块子目录及文件名前缀
+/// * `run_dir` - 本次运行的工作目录
+pub fn write_json_snapshot<T: Serialize>(
+    rows: &[T],
+    n: usize,
+    kind: OutputKind,
+    run_dir: Option<&RunDir>,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    match run_dir {
+        Some(run) => run.write_json(
+            &snapshot_file_name(kind.prefix, n, "json", false),
+            "snapshot",
+            rows,
+            rows.len(),
+        ),
+        None => {
+            let dir = ensure_output_dir(&output_root().join(kind.subdir).to_string_lossy())?;
+            let path = dir.join(snapshot_file_name(kind.prefix, n, "json", true));
+            fs::write(&path, export_json(rows)?)?;
+            save_export_mapping()?;
+            Ok(path)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn hidden_progress() -> ScanProgress {
+        let progress = ScanProgress::new(0);
+        progress.set_hidden(true);
+        progress
+    }
+
+    #[test]
+    fn test_collector_snapshot_is_independent() {
+        let results = ResultCollector::new();
+        results.push(1);
+        let snapshot = results.snapshot();
+        results.clone().push(2);
+        assert_eq!(snapshot, [1]);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results.into_vec(), [1, 2]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_snapshots_are_pruned_and_replaced() {
+        let root = std::env::temp_dir().join(format!("gxr_snapshot_{}", std::process::id()));
+        let run = Arc::new(RunDir::new(&root, "20240102100000-ab12", "net ping"));
+        let args = SnapshotArgs {
+            snapshot_every: Some(1),
+            snapshot_keep: 2,
+            keep_snapshots: false,
+        };
+        let results = ResultCollector::new();
+        let sizes = Arc::new(Mutex::new(Vec::new()));
+
+        let writer_run = run.clone();
+        let writer_sizes = sizes.clone();
+        let snapshotter = Snapshotter::start(
+            &args,
+            &results,
+            Some(run.clone()),
+            &hidden_progress(),
+            Box::new(move |rows: &[u32], n| {
+                writer_sizes.lock().unwrap().push(rows.len());
+                Ok(vec![write_json_snapshot(
+                    rows,
+                    n,
+                    OutputKind::PING,
+                    Some(&writer_run),
+                )?])
+            }),
+        )
+        .unwrap();
+
+        // 结果在两次快照之间到达
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        for i in 0..4 {
+            results.push(i);
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        }
+        // 没有新结果的周期不写文件
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        tokio::task::yield_now().await;
+
+        assert_eq!(*sizes.lock().unwrap(), [1, 2, 3, 4]);
+        let files = |run: &RunDir| -> Vec<String> {
+            RunDir::load_manifest(run.path())
+                .unwrap()
+                .artifacts
+                .into_iter()
+                .map(|a| a.file)
+                .collect()
+        };
+        assert_eq!(files(&run), ["partial_3.json", "partial_4.json"]);
+        assert!(!run.path().join("partial_1.json").exists());
+
+        assert_eq!(snapshotter.finish(false).await, 0);
+        let remaining = files(&run);
+        fs::remove_dir_all(&root).ok();
+        assert!(remaining.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_args_disabled_by_default() {
+        let args = SnapshotArgs::default();
+        assert!(args.snapshot_every.is_none());
+        let results: ResultCollector<u32> = ResultCollector::new();
+        let started = Snapshotter::start(
+            &args,
+            &results,
+            None,
+            &hidden_progress(),
+            Box::new(|_: &[u32], _| Ok(Vec::new())),
+        );
+        assert!(started.is_none());
+    }
+}
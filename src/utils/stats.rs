@@ -0,0 +1,206 @@
+// src/utils/stats.rs
+use super::run_dir::SummaryItem;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 单个任务的结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// 得到了期望的结果（主机存活、端口开放、服务可用等）
+    Succeeded,
+    /// 探测正常完成但结果为否定（无回复、端口关闭、名称不存在等）
+    Failed,
+    /// 探测本身出错（无法执行、检查中断等）
+    Errored,
+    /// 探测进行中被取消（扫描中止、主机被放弃等），结果未知，记为未完成
+    Cancelled,
+}
+
+/// 扫描统计
+///
+/// 各计数器为原子量，由工作池在分发和收集结果时累加（见 [`run_tracked`](super::pool::run_tracked)），
+/// 终端统计、运行目录的 `summary.json` 及进度条位置都以此为准，不再各自计数。
+#[derive(Debug, Default)]
+pub struct ScanStats {
+    dispatched: AtomicU64,
+    completed: AtomicU64,
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    errored: AtomicU64,
+    cancelled: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl ScanStats {
+    /// 分发了一个任务
+    pub fn dispatch(&self) {
+        self.dispatched.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 结束了一个任务（被取消的任务只计为未完成，不计入已完成）
+    ///
+    /// # 返回
+    /// * `u64` - 已完成的任务数
+    pub fn complete(&self, outcome: Outcome) -> u64 {
+        self.counter(outcome).fetch_add(1, Ordering::Relaxed);
+        if outcome == Outcome::Cancelled {
+            return self.completed.load(Ordering::Relaxed);
+        }
+        self.completed.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// 改变一个已完成任务的结论（如复核推翻了首次探测的结果）
+    pub fn reclassify(&self, from: Outcome, to: Outcome) {
+        if from != to {
+            self.counter(from).fetch_sub(1, Ordering::Relaxed);
+            self.counter(to).fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 累加发送的字节数
+    pub fn add_sent(&self, bytes: u64) {
+        self.bytes_sent.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// 累加接收的字节数
+    pub fn add_received(&self, bytes: u64) {
+        self.bytes_received.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn counter(&self, outcome: Outcome) -> &AtomicU64 {
+        match outcome {
+            Outcome::Succeeded => &self.succeeded,
+            Outcome::Failed => &self.failed,
+            Outcome::Errored => &self.errored,
+            Outcome::Cancelled => &self.cancelled,
+        }
+    }
+
+    /// 当前各计数器的值（调试构建中检查计数是否自洽）
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let snapshot = StatsSnapshot {
+            dispatched: self.dispatched.load(Ordering::Relaxed),
+            completed: self.completed.load(Ordering::Relaxed),
+            succeeded: self.succeeded.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            errored: self.errored.load(Ordering::Relaxed),
+            cancelled: self.cancelled.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+        };
+        snapshot.check();
+        snapshot
+    }
+}
+
+/// 扫描统计在某一时刻的值
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct StatsSnapshot {
+    /// 已分发的任务数
+    pub dispatched: u64,
+    /// 已完成的任务数
+    pub completed: u64,
+    /// 成功数
+    pub succeeded: u64,
+    /// 失败数
+    pub failed: u64,
+    /// 出错数
+    pub errored: u64,
+    /// 探测中被取消的任务数（未完成）
+    pub cancelled: u64,
+    /// 发送的字节数
+    pub bytes_sent: u64,
+    /// 接收的字节数
+    pub bytes_received: u64,
+}
+
+impl StatsSnapshot {
+    /// 检查计数是否自洽（仅调试构建）
+    ///
+    /// 统计在收集端逐个结果累加，结束后应满足 完成 = 成功 + 失败 + 出错 且 完成 + 未完成 ≤ 分发。
+    pub fn check(&self) {
+        debug_assert_eq!(
+            self.completed,
+            self.succeeded + self.failed + self.errored,
+            "统计不一致: {:?}",
+            self
+        );
+        debug_assert!(
+            self.completed + self.cancelled <= self.dispatched,
+            "统计不一致: {:?}",
+            self
+        );
+    }
+
+    /// 数量占已完成任务数的百分比（没有任务时为0）
+    pub fn percent(&self, count: u64) -> f64 {
+        (count as f64 / self.completed.max(1) as f64) * 100.0
+    }
+
+    /// 统计摘要中的任务计数
+    ///
+    /// # 参数
+    /// * `unit` - 任务的单位（如 "个IP"、"个端口"）
+    pub fn summary_items(&self, unit: &str) -> Vec<SummaryItem> {
+        let mut items = vec![(
+            "任务".to_string(),
+            format!(
+                "完成 {} / {} {}（成功 {}，失败 {}，出错 {}）",
+                self.completed, self.dispatched, unit, self.succeeded, self.failed, self.errored
+            ),
+        )];
+        if self.cancelled > 0 {
+            items.push((
+                "未完成".to_string(),
+                format!("{} {}（探测中被取消）", self.cancelled, unit),
+            ));
+        }
+        // 只列出有计数的方向（如HTTP探测只统计接收的正文）
+        let traffic: Vec<String> = [("发送", self.bytes_sent), ("接收", self.bytes_received)]
+            .into_iter()
+            .filter(|(_, bytes)| *bytes > 0)
+            .map(|(name, bytes)| format!("{} {}", name, format_bytes(bytes)))
+            .collect();
+        if !traffic.is_empty() {
+            items.push(("流量".to_string(), traffic.join("，")));
+        }
+        items
+    }
+}
+
+/// 按 B/KB/MB 显示字节数
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1 << 20 => format!("{:.1}MB", b as f64 / (1 << 20) as f64),
+        b if b >= 1 << 10 => format!("{:.1}KB", b as f64 / (1 << 10) as f64),
+        b => format!("{}B", b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_counts_and_reclassify() {
+        let stats = ScanStats::default();
+        for outcome in [Outcome::Succeeded, Outcome::Failed, Outcome::Failed] {
+            stats.dispatch();
+            stats.complete(outcome);
+        }
+        stats.dispatch();
+        stats.reclassify(Outcome::Failed, Outcome::Succeeded);
+        stats.add_received(2048);
+        stats.dispatch();
+        assert_eq!(stats.complete(Outcome::Cancelled), 3);
+
+        let s = stats.snapshot();
+        assert_eq!((s.dispatched, s.completed, s.cancelled), (5, 3, 1));
+        assert_eq!((s.succeeded, s.failed, s.errored), (2, 1, 0));
+        let items = s.summary_items("个IP");
+        assert_eq!(items[0].1, "完成 3 / 5 个IP（成功 2，失败 1，出错 0）");
+        assert_eq!(items[1].1, "1 个IP（探测中被取消）");
+        assert_eq!(items[2].1, "接收 2.0KB");
+    }
+}
@@ -0,0 +1,1687 @@
+// src/utils/targets.rs
+use super::console::Icon;
+use super::dns::{self, Resolver};
+use super::run_dir::SummaryItem;
+use super::scope;
+use super::{
+    count_targets, merge_ranges, parse_exclude_ranges, parse_target_ranges, subtract_ranges,
+};
+use calamine::{Data, Reader, open_workbook_auto};
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::io::{IsTerminal, Read};
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 额外的目标来源参数（与 -t 合并使用）
+#[derive(Args, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TargetSourceArgs {
+    /// 从Excel资产清单导入目标（单元格支持与 -t 相同的格式）
+    #[arg(long, value_name = "FILE")]
+    pub target_xlsx: Option<PathBuf>,
+
+    /// 从文本文件导入目标（每行一个，格式与 -t 相同，# 之后为注释）
+    ///
+    /// `IP 名称...` 形式的行把后面的名称记为该IP的别名，如 net dnssweep --alive-file 的输出。
+    /// 行尾可带 `名称=值` 形式的参数覆盖该行目标的全局参数，如 `10.8.0.0/24 timeout=5 count=5 rate=50`
+    /// （timeout 为秒，可写 300ms；count 为探测次数；rate 为该行目标合计每秒最多探测数）。
+    #[arg(short = 'f', long, value_name = "FILE")]
+    pub target_file: Option<PathBuf>,
+
+    /// 导入时读取的列名（默认第一列）
+    #[arg(long, value_name = "COLUMN", requires = "target_xlsx")]
+    pub column: Option<String>,
+
+    /// 导入时读取的工作表名（默认第一个工作表）
+    #[arg(long, value_name = "SHEET", requires = "target_xlsx")]
+    pub sheet: Option<String>,
+
+    /// 导入时作为标签带入结果的列（如 业务系统,责任人），按首行表头查找
+    #[arg(
+        long,
+        value_name = "COLUMNS",
+        value_delimiter = ',',
+        requires = "target_xlsx"
+    )]
+    #[serde(default)]
+    pub tag_columns: Vec<String>,
+
+    /// 附加到每条结果的标签，可重复指定（如 --tag 项目=2024Q3渗透）
+    #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = parse_tag)]
+    #[serde(default)]
+    pub tags: Vec<(String, String)>,
+
+    /// 从目标中排除的地址（格式与 -t 相同：单个IP、范围、CIDR，多个用逗号隔开；CIDR排除整个网段，含网络地址和广播地址）
+    #[arg(long, env = "GXTOOLS_EXCLUDE", value_name = "TARGETS")]
+    pub exclude: Option<String>,
+
+    /// 主机名解析时使用的地址族（4、6 或 both）
+    ///
+    /// 为 both 时同时解析到IPv4和IPv6地址的主机名按双栈主机对照两个地址族的结果。
+    #[arg(long, value_enum, default_value_t, value_name = "VERSION")]
+    #[serde(default)]
+    pub ip_version: IpVersion,
+
+    /// 不解析主机名：目标中出现非IP写法时直接报错（适用于禁止对外DNS查询的环境）
+    #[arg(long, env = "GXTOOLS_NO_RESOLVE")]
+    #[serde(default)]
+    pub no_resolve: bool,
+
+    /// 目标数超过该值时提示（如误写 `10.*.*.*`），仍继续扫描，默认 65536
+    #[arg(long, env = "GXTOOLS_MAX_TARGETS", value_name = "N")]
+    pub max_targets: Option<usize>,
+}
+
+/// 目标数提示阈值的默认值（一个 /16 网段）
+pub const DEFAULT_MAX_TARGETS: usize = 65536;
+
+/// 主机名解析时使用的地址族
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+pub enum IpVersion {
+    /// 只使用IPv4地址
+    #[value(name = "4")]
+    #[serde(rename = "4")]
+    V4,
+    /// 只使用IPv6地址
+    #[value(name = "6")]
+    #[serde(rename = "6")]
+    V6,
+    /// 同时使用IPv4和IPv6地址
+    #[default]
+    #[serde(rename = "both")]
+    Both,
+}
+
+impl IpVersion {
+    /// 是否接受该地址
+    pub fn accepts(self, ip: &IpAddr) -> bool {
+        match self {
+            IpVersion::V4 => ip.is_ipv4(),
+            IpVersion::V6 => ip.is_ipv6(),
+            IpVersion::Both => true,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            IpVersion::V4 => "IPv4",
+            IpVersion::V6 => "IPv6",
+            IpVersion::Both => "IP",
+        }
+    }
+}
+
+impl TargetSourceArgs {
+    /// 是否指定了目标文件（`--target-xlsx` 或 `--target-file`）
+    pub fn has_files(&self) -> bool {
+        self.target_xlsx.is_some() || self.target_file.is_some()
+    }
+}
+
+/// 目标标签：列名 -> 值（按列名排序，导出时列顺序固定）
+pub type Tags = BTreeMap<String, String>;
+
+/// 解析 `KEY=VALUE` 形式的标签
+fn parse_tag(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("标签格式应为 名称=值: {}", s))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!("标签名称不能为空: {}", s));
+    }
+    Ok((key.to_string(), value.trim().to_string()))
+}
+
+/// 合并标签：同名标签的值不同时以 "; " 连接
+///
+/// # 参数
+/// * `into` - 合并到的标签
+/// * `tags` - 新的标签
+pub fn merge_tags(into: &mut Tags, tags: &Tags) {
+    for (key, value) in tags {
+        match into.get_mut(key) {
+            Some(existing) => {
+                if !existing.split("; ").any(|v| v == value) {
+                    existing.push_str("; ");
+                    existing.push_str(value);
+                }
+            }
+            None => {
+                into.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// 结果中出现过的全部标签名（导出时作为附加列，按名称排序）
+pub fn tag_keys<'a>(tags: impl IntoIterator<Item = &'a Tags>) -> Vec<String> {
+    tags.into_iter()
+        .flat_map(|t| t.keys().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// 目标行上的参数覆盖，优先于全局的 `--timeout`、`-n` 及速率参数
+///
+/// 同一IP被多行指向时，每项参数以最先出现的一行为准。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetOverrides {
+    /// 超时（毫秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// 探测次数（ping的次数；端口扫描为硬性超时后的总尝试次数）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+    /// 该行目标合计每秒最多发起的探测数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate: Option<u32>,
+}
+
+/// 支持的参数名
+const OVERRIDE_KEYS: &str = "timeout、count、rate";
+
+/// 超时覆盖的上限（秒）
+const MAX_OVERRIDE_TIMEOUT_SECS: u64 = 600;
+
+impl TargetOverrides {
+    /// 解析行尾的 `名称=值` 参数
+    ///
+    /// # 参数
+    /// * `items` - 各项参数
+    ///
+    /// # 返回
+    /// * `Err(String)` - 未知参数、重复参数或取值无效
+    pub fn parse<'a>(items: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut overrides = Self::default();
+        for item in items {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("参数格式应为 名称=值: {}", item))?;
+            let duplicate = match key {
+                "timeout" => overrides
+                    .timeout_ms
+                    .replace(parse_timeout_ms(value)?)
+                    .is_some(),
+                "count" => overrides
+                    .count
+                    .replace(parse_positive(key, value)?)
+                    .is_some(),
+                "rate" => overrides
+                    .rate
+                    .replace(parse_positive(key, value)?)
+                    .is_some(),
+                _ => {
+                    return Err(format!("未知参数 {}（支持 {}）", key, OVERRIDE_KEYS));
+                }
+            };
+            if duplicate {
+                return Err(format!("参数 {} 重复指定", key));
+            }
+        }
+        Ok(overrides)
+    }
+
+    /// 是否没有任何覆盖
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 覆盖的超时
+    pub fn timeout(&self) -> Option<std::time::Duration> {
+        self.timeout_ms.map(std::time::Duration::from_millis)
+    }
+
+    /// 合并另一行的覆盖（已有的参数保持不变）
+    fn fill_from(&mut self, other: &Self) {
+        self.timeout_ms = self.timeout_ms.or(other.timeout_ms);
+        self.count = self.count.or(other.count);
+        self.rate = self.rate.or(other.rate);
+    }
+}
+
+impl std::fmt::Display for TargetOverrides {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(ms) = self.timeout_ms {
+            parts.push(if ms % 1000 == 0 {
+                format!("超时={}秒", ms / 1000)
+            } else {
+                format!("超时={}ms", ms)
+            });
+        }
+        if let Some(count) = self.count {
+            parts.push(format!("次数={}", count));
+        }
+        if let Some(rate) = self.rate {
+            parts.push(format!("速率={}/秒", rate));
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// 解析超时：秒（可带小数或 s 后缀）或带 ms 后缀的毫秒
+fn parse_timeout_ms(value: &str) -> Result<u64, String> {
+    let invalid = || format!("timeout 取值无效: {}（如 5、0.5、300ms）", value);
+    let ms = match value.strip_suffix("ms") {
+        Some(ms) => ms.parse::<u64>().map_err(|_| invalid())?,
+        None => {
+            let secs: f64 = value
+                .strip_suffix('s')
+                .unwrap_or(value)
+                .parse()
+                .map_err(|_| invalid())?;
+            if !secs.is_finite() || secs < 0.0 {
+                return Err(invalid());
+            }
+            (secs * 1000.0).round() as u64
+        }
+    };
+    if ms == 0 || ms > MAX_OVERRIDE_TIMEOUT_SECS * 1000 {
+        return Err(format!(
+            "timeout 应在 1ms 到 {} 秒之间: {}",
+            MAX_OVERRIDE_TIMEOUT_SECS, value
+        ));
+    }
+    Ok(ms)
+}
+
+/// 解析正整数参数
+fn parse_positive(key: &str, value: &str) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("{} 应为正整数: {}", key, value)),
+    }
+}
+
+/// Excel目标导入结果
+#[derive(Debug, Default)]
+pub struct XlsxImportReport {
+    /// 读取的数据行数（不含表头）
+    pub rows: usize,
+    /// 成功解析的行数
+    pub parsed: usize,
+    /// 跳过的行（行号, 原因）
+    pub skipped: Vec<(usize, String)>,
+    /// 解析得到的IP数（各单元格分别去重，不展开地址）
+    pub targets: u64,
+    /// 成功解析的单元格中的原始写法（逗号分隔的每一项）及所在行的标签
+    pub specs: Vec<(String, Tags)>,
+    /// 单元格中带参数覆盖的原始写法
+    pub overrides: Vec<(String, TargetOverrides)>,
+}
+
+/// 文本目标文件的内容
+#[derive(Debug, Default)]
+pub struct TargetFile {
+    /// 每行的第一项（格式与 -t 相同）
+    pub specs: Vec<String>,
+    /// 行内IP之后的名称：(名称, IP)
+    pub aliases: Vec<(String, String)>,
+    /// 带参数覆盖的行：(原始写法, 覆盖)
+    pub overrides: Vec<(String, TargetOverrides)>,
+}
+
+/// 读取文本目标文件
+///
+/// # 参数
+/// * `path` - 文件路径
+///
+/// # 返回
+/// * `Ok(TargetFile)` - 各行的目标写法、别名及参数覆盖
+/// * `Err` - 文件无法读取，或某行的参数覆盖无效（错误中带行号）
+pub fn read_targets_file(path: &Path) -> Result<TargetFile, Box<dyn Error + Send + Sync>> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("无法读取目标文件 {}: {}", path.display(), e))?;
+    parse_target_lines(&text, &format!("目标文件 {}", path.display()))
+}
+
+/// 从标准输入读取目标（`-t -`），格式同文本目标文件，一行也可写多个逗号隔开的目标
+///
+/// # 返回
+/// * `Ok(TargetFile)` - 各行的目标写法、别名及参数覆盖
+/// * `Err` - 标准输入是终端（没有通过管道传入目标）、读取失败、没有任何目标或某行无效
+pub fn read_targets_stdin() -> Result<TargetFile, Box<dyn Error + Send + Sync>> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Err(
+            "-t - 从标准输入读取目标，请通过管道传入（如 cat scope.txt | gxtools net ping -t -）"
+                .into(),
+        );
+    }
+    let mut text = String::new();
+    stdin
+        .read_to_string(&mut text)
+        .map_err(|e| format!("无法读取标准输入: {}", e))?;
+    let file = parse_target_lines(&text, "标准输入")?;
+    if file.specs.is_empty() {
+        return Err("标准输入中没有任何目标".into());
+    }
+    Ok(file)
+}
+
+/// 按行解析目标文本（# 之后为注释）
+///
+/// # 参数
+/// * `text` - 文本内容
+/// * `source` - 来源描述，用于错误信息（如 `目标文件 scope.txt`）
+fn parse_target_lines(
+    text: &str,
+    source: &str,
+) -> Result<TargetFile, Box<dyn Error + Send + Sync>> {
+    let mut file = TargetFile::default();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut items = line.split_whitespace();
+        let Some(spec) = items.next() else {
+            continue;
+        };
+        let (params, names): (Vec<&str>, Vec<&str>) = items.partition(|item| item.contains('='));
+        let overrides = TargetOverrides::parse(params)
+            .map_err(|e| format!("{} 第{}行: {}", source, i + 1, e))?;
+        // 只有单个IP的行才带别名
+        if spec.parse::<IpAddr>().is_ok() {
+            file.aliases.extend(
+                names
+                    .iter()
+                    .map(|name| (name.to_string(), spec.to_string())),
+            );
+        }
+        for spec in split_specs(spec) {
+            // 主机名留到解析阶段，其他写法在此校验以便指出行号
+            if !is_hostname(&spec)
+                && let Err(e) = count_targets(&spec)
+            {
+                return Err(format!("{} 第{}行: {}", source, i + 1, e).into());
+            }
+            if !overrides.is_empty() {
+                file.overrides.push((spec.clone(), overrides));
+            }
+            file.specs.push(spec);
+        }
+    }
+    Ok(file)
+}
+
+/// 规范化后的单个目标
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    /// IP地址
+    pub ip: String,
+    /// 指向该IP的全部原始写法（按首次出现的顺序）
+    pub sources: Vec<String>,
+    /// 各写法携带的标签（合并后）
+    pub tags: Tags,
+    /// 目标行上的参数覆盖（合并后）
+    pub overrides: TargetOverrides,
+}
+
+impl Target {
+    /// 别名：除IP本身以外指向该主机的写法
+    ///
+    /// 只有主机名，或同一IP被多个写法（如重叠的网段）指向时才记录，
+    /// 单个网段展开的IP不带别名。
+    pub fn aliases(&self) -> Vec<String> {
+        let named: Vec<String> = self
+            .sources
+            .iter()
+            .filter(|s| **s != self.ip)
+            .cloned()
+            .collect();
+        if self.sources.len() > 1 || named.iter().any(|s| is_hostname(s)) {
+            named
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// 按IP规范化的目标集合
+///
+/// 同一主机无论以主机名、IP还是重叠的网段多次出现，都只探测一次；
+/// 原始写法与IP的对应关系保留下来，按写法取回结果时同一个探测结果对所有别名都有效。
+///
+/// IPv4写法按区间保存，不展开为逐个IP（/8 这样的大网段也只占几个区间）；
+/// 来源、标签及参数覆盖按区间边界切分的地址段记录，查询或逐个产生目标时才生成 [`Target`]。
+#[derive(Debug, Clone, Default)]
+pub struct TargetSet {
+    specs: Vec<SpecEntry>,
+    spec_index: HashMap<String, usize>,
+    removed: Vec<(u32, u32)>,
+    removed_v6: HashSet<String>,
+    global_tags: Tags,
+    overrides: Vec<(String, TargetOverrides)>,
+    index: OnceLock<SegmentIndex>,
+}
+
+/// 一个原始写法指向的地址
+#[derive(Debug, Clone, Default)]
+struct SpecEntry {
+    spec: String,
+    /// IPv4地址：按地址排序、互不相交的闭区间
+    ranges: Vec<(u32, u32)>,
+    /// 其他地址（IPv6，按首次出现的顺序）
+    others: Vec<String>,
+    tags: Tags,
+}
+
+/// 一段地址共同的来源写法（按首次出现的顺序）、标签及参数覆盖
+#[derive(Debug, Clone, Default)]
+struct Origin {
+    specs: Vec<usize>,
+    tags: Tags,
+    overrides: TargetOverrides,
+}
+
+/// 按来源切分的地址段（集合变化后重新生成）
+#[derive(Debug, Clone, Default)]
+struct SegmentIndex {
+    /// 按地址排序、互不相交的IPv4区间，区间内的地址来源相同
+    v4: Vec<(u32, u32, Origin)>,
+    /// IPv6地址及其来源（按首次出现的顺序）
+    v6: Vec<(String, Origin)>,
+    v6_pos: HashMap<String, usize>,
+    len: usize,
+}
+
+impl TargetSet {
+    /// 添加一个原始写法及其展开（或解析）得到的IP
+    ///
+    /// # 参数
+    /// * `spec` - 原始写法（如 `localhost`、`10.0.0.0/24`）
+    /// * `ips` - 该写法对应的IP地址
+    pub fn add(&mut self, spec: &str, ips: Vec<String>) {
+        self.add_tagged(spec, ips, &Tags::new());
+    }
+
+    /// 添加一个带标签的原始写法，标签随IP保留（主机名解析后同样有效）
+    ///
+    /// # 参数
+    /// * `spec` - 原始写法
+    /// * `ips` - 该写法对应的IP地址
+    /// * `tags` - 该写法携带的标签（如资产清单中的业务系统、责任人）
+    pub fn add_tagged(&mut self, spec: &str, ips: Vec<String>, tags: &Tags) {
+        let mut ranges = Vec::new();
+        let mut others = Vec::new();
+        for ip in ips {
+            match ip.parse::<Ipv4Addr>() {
+                Ok(v4) => ranges.push((u32::from(v4), u32::from(v4))),
+                Err(_) => others.push(ip),
+            }
+        }
+        self.insert(spec, ranges, others, tags);
+    }
+
+    /// 添加一个IPv4写法的地址区间，不展开为逐个IP
+    ///
+    /// # 参数
+    /// * `spec` - 原始写法（如 `10.0.0.0/8`）
+    /// * `ranges` - 该写法对应的闭区间（可重叠）
+    /// * `tags` - 该写法携带的标签
+    pub fn add_ranges(&mut self, spec: &str, ranges: Vec<(u32, u32)>, tags: &Tags) {
+        self.insert(spec, ranges, Vec::new(), tags);
+    }
+
+    fn insert(&mut self, spec: &str, ranges: Vec<(u32, u32)>, others: Vec<String>, tags: &Tags) {
+        let ranges = merge_ranges(ranges);
+        // 重新加入的地址不再视为已移除
+        if !self.removed.is_empty() {
+            self.removed = subtract_ranges(&self.removed, &ranges);
+        }
+        for ip in &others {
+            self.removed_v6.remove(ip);
+        }
+        let i = *self.spec_index.entry(spec.to_string()).or_insert_with(|| {
+            self.specs.push(SpecEntry {
+                spec: spec.to_string(),
+                ..Default::default()
+            });
+            self.specs.len() - 1
+        });
+        let entry = &mut self.specs[i];
+        entry.ranges.extend(ranges);
+        entry.ranges = merge_ranges(std::mem::take(&mut entry.ranges));
+        for ip in others {
+            if !entry.others.contains(&ip) {
+                entry.others.push(ip);
+            }
+        }
+        merge_tags(&mut entry.tags, tags);
+        self.index = OnceLock::new();
+    }
+
+    /// 地址段索引（首次使用时按当前的写法、移除的地址及参数覆盖生成）
+    fn index(&self) -> &SegmentIndex {
+        self.index.get_or_init(|| self.build_index())
+    }
+
+    fn build_index(&self) -> SegmentIndex {
+        // 参数覆盖按设置的顺序合并，同一参数以先设置的为准
+        let overrides: Vec<(usize, TargetOverrides)> = self
+            .overrides
+            .iter()
+            .filter_map(|(spec, o)| Some((*self.spec_index.get(spec)?, *o)))
+            .collect();
+        let origin = |specs: Vec<usize>| {
+            let mut tags = Tags::new();
+            for &i in &specs {
+                merge_tags(&mut tags, &self.specs[i].tags);
+            }
+            let mut merged = TargetOverrides::default();
+            for (i, o) in &overrides {
+                if specs.contains(i) {
+                    merged.fill_from(o);
+                }
+            }
+            Origin {
+                specs,
+                tags,
+                overrides: merged,
+            }
+        };
+
+        // 各写法区间的起止把地址空间切成若干段，同一段内的地址由同一组写法指向
+        let mut bounds: Vec<(u64, bool, usize)> = Vec::new();
+        for (i, entry) in self.specs.iter().enumerate() {
+            for &(start, end) in &entry.ranges {
+                bounds.push((u64::from(start), true, i));
+                bounds.push((u64::from(end) + 1, false, i));
+            }
+        }
+        bounds.sort_unstable();
+        let mut index = SegmentIndex::default();
+        let mut active: BTreeSet<usize> = BTreeSet::new();
+        let mut k = 0;
+        while k < bounds.len() {
+            let pos = bounds[k].0;
+            while let Some(&(_, open, i)) = bounds.get(k).filter(|b| b.0 == pos) {
+                if open {
+                    active.insert(i);
+                } else {
+                    active.remove(&i);
+                }
+                k += 1;
+            }
+            let Some(&(next, ..)) = bounds.get(k) else {
+                break;
+            };
+            if active.is_empty() {
+                continue;
+            }
+            let segment = origin(active.iter().copied().collect());
+            for (start, end) in subtract_ranges(&[(pos as u32, (next - 1) as u32)], &self.removed) {
+                index.len += (end - start) as usize + 1;
+                index.v4.push((start, end, segment.clone()));
+            }
+        }
+
+        let mut v6_specs: Vec<(String, Vec<usize>)> = Vec::new();
+        for (i, entry) in self.specs.iter().enumerate() {
+            for ip in entry
+                .others
+                .iter()
+                .filter(|ip| !self.removed_v6.contains(*ip))
+            {
+                match index.v6_pos.get(ip) {
+                    Some(&pos) => v6_specs[pos].1.push(i),
+                    None => {
+                        index.v6_pos.insert(ip.clone(), v6_specs.len());
+                        v6_specs.push((ip.clone(), vec![i]));
+                    }
+                }
+            }
+        }
+        index.len += v6_specs.len();
+        index.v6 = v6_specs
+            .into_iter()
+            .map(|(ip, specs)| (ip, origin(specs)))
+            .collect();
+        index
+    }
+
+    fn target(&self, ip: String, origin: &Origin) -> Target {
+        Target {
+            ip,
+            sources: origin
+                .specs
+                .iter()
+                .map(|&i| self.specs[i].spec.clone())
+                .collect(),
+            tags: origin.tags.clone(),
+            overrides: origin.overrides,
+        }
+    }
+
+    /// 某个IP所在地址段的来源（不在集合中时为 `None`）
+    fn origin(&self, ip: &str) -> Option<&Origin> {
+        let index = self.index();
+        match ip.parse::<Ipv4Addr>() {
+            Ok(v4) => {
+                let v4 = u32::from(v4);
+                let i = index.v4.partition_point(|&(_, end, _)| end < v4);
+                index
+                    .v4
+                    .get(i)
+                    .filter(|&&(start, _, _)| start <= v4)
+                    .map(|(_, _, origin)| origin)
+            }
+            Err(_) => index.v6_pos.get(ip).map(|&i| &index.v6[i].1),
+        }
+    }
+
+    /// 逐个产生去重后的目标，不预先展开（IPv4按地址顺序，IPv6在后，按首次出现的顺序）
+    pub fn iter(&self) -> impl Iterator<Item = Target> + '_ {
+        let index = self.index();
+        let v4 = index.v4.iter().flat_map(move |(start, end, origin)| {
+            (*start..=*end).map(move |ip| self.target(Ipv4Addr::from(ip).to_string(), origin))
+        });
+        let v6 = index
+            .v6
+            .iter()
+            .map(move |(ip, origin)| self.target(ip.clone(), origin));
+        v4.chain(v6)
+    }
+
+    /// 逐个产生去重后的IP地址，顺序同 [`iter`](Self::iter)
+    pub fn iter_ips(&self) -> impl Iterator<Item = String> + Send + '_ {
+        let index = self.index();
+        index
+            .v4
+            .iter()
+            .flat_map(|&(start, end, _)| (start..=end).map(|ip| Ipv4Addr::from(ip).to_string()))
+            .chain(index.v6.iter().map(|(ip, _)| ip.clone()))
+    }
+
+    /// 去重后的IP地址列表，每个IP只出现一次（会展开全部地址，大范围请用 [`iter_ips`](Self::iter_ips)）
+    pub fn ips(&self) -> Vec<String> {
+        self.iter_ips().collect()
+    }
+
+    /// 去重后的IPv4目标：按地址排序、互不相交的闭区间（不展开地址）
+    pub fn ranges(&self) -> Vec<(u32, u32)> {
+        merge_ranges(self.index().v4.iter().map(|&(s, e, _)| (s, e)).collect())
+    }
+
+    /// 去重后的IPv6目标（按首次出现的顺序）
+    pub fn v6_ips(&self) -> impl Iterator<Item = &str> {
+        self.index().v6.iter().map(|(ip, _)| ip.as_str())
+    }
+
+    /// 某个IP的目标记录（不在集合中时为 `None`）
+    pub fn get(&self, ip: &str) -> Option<Target> {
+        self.origin(ip)
+            .map(|origin| self.target(ip.to_string(), origin))
+    }
+
+    /// 主机是否在集合中：IP按区间查找，主机名须以原始写法出现在目标中且仍有地址未被移除
+    pub fn contains(&self, host: &str) -> bool {
+        if self.origin(host).is_some() {
+            return true;
+        }
+        is_hostname(host)
+            && self.entry(host).is_some_and(|entry| {
+                !self.member_ranges(entry).is_empty() || self.member_others(entry).next().is_some()
+            })
+    }
+
+    /// 某个IP的别名（不在集合中时为空）
+    pub fn aliases(&self, ip: &str) -> Vec<String> {
+        self.get(ip).map(|t| t.aliases()).unwrap_or_default()
+    }
+
+    /// 设置附加到每个目标的标签（`--tag`）
+    pub fn set_global_tags(&mut self, tags: Tags) {
+        self.global_tags = tags;
+    }
+
+    /// 全部目标出现过的标签名（含全局标签，按名称排序，不展开地址）
+    pub fn tag_keys(&self) -> Vec<String> {
+        let index = self.index();
+        let origins = index
+            .v4
+            .iter()
+            .map(|(_, _, origin)| origin)
+            .chain(index.v6.iter().map(|(_, origin)| origin));
+        tag_keys(std::iter::once(&self.global_tags).chain(origins.map(|origin| &origin.tags)))
+    }
+
+    /// 某个IP的标签：全局标签加上各写法携带的标签（同名时以后者为准）
+    pub fn tags(&self, ip: &str) -> Tags {
+        let mut tags = self.global_tags.clone();
+        if let Some(origin) = self.origin(ip) {
+            tags.extend(origin.tags.clone());
+        }
+        tags
+    }
+
+    /// 为某个原始写法指向的目标设置参数覆盖（已有的参数以先设置的为准）
+    ///
+    /// # 参数
+    /// * `spec` - 原始写法
+    /// * `overrides` - 该写法所在行的参数覆盖
+    pub fn set_overrides(&mut self, spec: &str, overrides: TargetOverrides) {
+        if !self.spec_index.contains_key(spec) {
+            return;
+        }
+        self.overrides.push((spec.to_string(), overrides));
+        self.index = OnceLock::new();
+    }
+
+    /// 某个IP的参数覆盖（没有时为空）
+    pub fn overrides(&self, ip: &str) -> TargetOverrides {
+        self.origin(ip)
+            .map(|origin| origin.overrides)
+            .unwrap_or_default()
+    }
+
+    /// 带参数覆盖的目标及其覆盖（只展开覆盖行指向的地址）
+    pub fn overridden(&self) -> impl Iterator<Item = (String, TargetOverrides)> + '_ {
+        let index = self.index();
+        let v4 = index
+            .v4
+            .iter()
+            .filter(|(_, _, origin)| !origin.overrides.is_empty())
+            .flat_map(|(start, end, origin)| {
+                (*start..=*end).map(|ip| (Ipv4Addr::from(ip).to_string(), origin.overrides))
+            });
+        let v6 = index
+            .v6
+            .iter()
+            .filter(|(_, origin)| !origin.overrides.is_empty())
+            .map(|(ip, origin)| (ip.clone(), origin.overrides));
+        v4.chain(v6)
+    }
+
+    /// 带参数覆盖的原始写法（按出现顺序）及其目标数
+    pub fn override_specs(&self) -> Vec<OverrideSpec> {
+        self.overrides
+            .iter()
+            .map(|(spec, overrides)| OverrideSpec {
+                spec: spec.clone(),
+                hosts: self.entry(spec).map_or(0, |entry| {
+                    range_len(&self.member_ranges(entry)) + self.member_others(entry).count()
+                }),
+                overrides: *overrides,
+            })
+            .collect()
+    }
+
+    fn entry(&self, spec: &str) -> Option<&SpecEntry> {
+        self.spec_index.get(spec).map(|&i| &self.specs[i])
+    }
+
+    /// 写法的IPv4区间中仍在集合里的部分
+    fn member_ranges(&self, entry: &SpecEntry) -> Vec<(u32, u32)> {
+        let outside = subtract_ranges(&entry.ranges, &self.ranges());
+        subtract_ranges(&entry.ranges, &outside)
+    }
+
+    /// 写法的其他地址中仍在集合里的部分
+    fn member_others<'s>(&'s self, entry: &'s SpecEntry) -> impl Iterator<Item = &'s String> {
+        let index = self.index();
+        entry
+            .others
+            .iter()
+            .filter(|ip| index.v6_pos.contains_key(*ip))
+    }
+
+    /// 原始写法对应的IP（用于把结果按用户输入的写法取回）
+    pub fn spec_ips(&self, spec: &str) -> Option<Vec<String>> {
+        let entry = self.entry(spec)?;
+        Some(
+            self.member_ranges(entry)
+                .into_iter()
+                .flat_map(|(start, end)| (start..=end).map(|ip| Ipv4Addr::from(ip).to_string()))
+                .chain(self.member_others(entry).cloned())
+                .collect(),
+        )
+    }
+
+    /// 以主机名写入的目标及其解析得到的IP（按首次出现的顺序）
+    pub fn hostnames(&self) -> impl Iterator<Item = (&str, Vec<String>)> {
+        self.specs
+            .iter()
+            .filter(|entry| is_hostname(&entry.spec))
+            .map(|entry| {
+                let ips = self.spec_ips(&entry.spec).unwrap_or_default();
+                (entry.spec.as_str(), ips)
+            })
+    }
+
+    /// 只保留满足条件的目标（如排除授权范围外的目标），原始写法对应的IP同步移除
+    pub fn retain(&mut self, mut keep: impl FnMut(&Target) -> bool) {
+        let mut removed: Vec<(u32, u32)> = Vec::new();
+        let mut removed_v6 = Vec::new();
+        for target in self.iter() {
+            if keep(&target) {
+                continue;
+            }
+            match target.ip.parse::<Ipv4Addr>() {
+                // 目标按地址顺序产生，相邻的地址并入同一个区间
+                Ok(ip) => match removed.last_mut() {
+                    Some((_, end)) if *end + 1 == u32::from(ip) => *end = u32::from(ip),
+                    _ => removed.push((u32::from(ip), u32::from(ip))),
+                },
+                Err(_) => removed_v6.push(target.ip),
+            }
+        }
+        self.removed_v6.extend(removed_v6);
+        self.remove_ranges(&removed);
+    }
+
+    /// 移除落在区间内的IPv4目标（如 `--exclude`），不展开区间
+    ///
+    /// # 参数
+    /// * `ranges` - 按地址排序、互不相交的闭区间
+    pub fn remove_ranges(&mut self, ranges: &[(u32, u32)]) {
+        let mut removed = std::mem::take(&mut self.removed);
+        removed.extend_from_slice(ranges);
+        self.removed = merge_ranges(removed);
+        self.index = OnceLock::new();
+    }
+
+    /// 只保留前 `n` 个目标（顺序同 [`iter`](Self::iter)）
+    pub fn truncate(&mut self, n: usize) {
+        let mut left = n;
+        let mut removed = Vec::new();
+        for &(start, end, _) in &self.index().v4 {
+            let size = (end - start) as usize + 1;
+            if left >= size {
+                left -= size;
+            } else {
+                removed.push((start + left as u32, end));
+                left = 0;
+            }
+        }
+        let removed_v6: Vec<String> = self
+            .index()
+            .v6
+            .iter()
+            .skip(left)
+            .map(|(ip, _)| ip.clone())
+            .collect();
+        self.removed_v6.extend(removed_v6);
+        self.remove_ranges(&removed);
+    }
+
+    /// 去重后的目标数
+    pub fn len(&self) -> usize {
+        self.index().len
+    }
+
+    /// 是否没有任何目标
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 被合并掉的重复数（各写法展开的IP总数减去去重后的数量）
+    pub fn merged(&self) -> usize {
+        let index = self.index();
+        let v4: usize = index
+            .v4
+            .iter()
+            .map(|(start, end, origin)| ((end - start) as usize + 1) * (origin.specs.len() - 1))
+            .sum();
+        let v6: usize = index.v6.iter().map(|(_, o)| o.specs.len() - 1).sum();
+        v4 + v6
+    }
+}
+
+/// 区间内的地址总数
+fn range_len(ranges: &[(u32, u32)]) -> usize {
+    ranges.iter().map(|&(s, e)| (e - s) as usize + 1).sum()
+}
+
+/// 带参数覆盖的一行目标（打印在扫描计划中，并写入运行目录）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OverrideSpec {
+    /// 原始写法
+    pub spec: String,
+    /// 指向的目标数（排除授权范围外的目标后）
+    pub hosts: usize,
+    /// 参数覆盖
+    #[serde(flatten)]
+    pub overrides: TargetOverrides,
+}
+
+/// 打印带参数覆盖的目标行（扫描开始前及 `--dry-run` 时显示）
+///
+/// # 参数
+/// * `lines` - 带参数覆盖的目标行，见 [`TargetSet::override_specs`]
+pub fn print_overrides(lines: &[OverrideSpec]) {
+    if lines.is_empty() {
+        return;
+    }
+    println!("{} 目标参数覆盖（优先于全局参数）:", Icon::Config);
+    for line in lines {
+        println!(
+            "   {}（{} 个IP）: {}",
+            line.spec, line.hosts, line.overrides
+        );
+    }
+}
+
+/// 参数覆盖在统计摘要中的一项（没有覆盖时为 `None`）
+///
+/// # 参数
+/// * `lines` - 带参数覆盖的目标行
+pub fn overrides_summary_item(lines: &[OverrideSpec]) -> Option<SummaryItem> {
+    if lines.is_empty() {
+        return None;
+    }
+    let hosts: usize = lines.iter().map(|l| l.hosts).sum();
+    Some((
+        "目标参数覆盖".to_string(),
+        format!("{} 行（{} 个IP）", lines.len(), hosts),
+    ))
+}
+
+/// 汇总 -t 与其他来源的目标，解析主机名后按IP去重（IPv4按地址排序，IPv6在后）
+///
+/// 主机名通过全局共享的解析器（[`dns::resolver`]）解析。
+/// 指定了授权范围时，范围外的目标在此排除（见 [`scope::enforce`]）。
+///
+/// # 参数
+/// * `target` - -t 参数的值
+/// * `sources` - 额外的目标来源
+///
+/// # 返回
+/// * `Ok(TargetSet)` - 按IP规范化的目标集合
+/// * `Err` - 解析失败或未得到任何目标
+pub async fn collect_targets(
+    target: Option<&str>,
+    sources: &TargetSourceArgs,
+) -> Result<TargetSet, Box<dyn Error + Send + Sync>> {
+    let mut specs: Vec<(String, Tags)> = Vec::new();
+    let mut overrides = Vec::new();
+
+    let mut aliases = Vec::new();
+    if target == Some("-") {
+        let stdin = read_targets_stdin()?;
+        println!("{} 从标准输入读取 {} 个目标", Icon::List, stdin.specs.len());
+        specs.extend(stdin.specs.into_iter().map(|spec| (spec, Tags::new())));
+        aliases = stdin.aliases;
+        overrides.extend(stdin.overrides);
+    } else if let Some(target) = target {
+        specs.extend(split_specs(target).map(|spec| (spec, Tags::new())));
+    }
+
+    if let Some(ref path) = sources.target_xlsx {
+        let report = read_targets_xlsx(
+            path,
+            sources.column.as_deref(),
+            sources.sheet.as_deref(),
+            &sources.tag_columns,
+        )?;
+        print_import_report(path, &report);
+        specs.extend(report.specs);
+        overrides.extend(report.overrides);
+    }
+
+    if let Some(ref path) = sources.target_file {
+        let file = read_targets_file(path)?;
+        println!(
+            "{} 从 {} 导入 {} 个目标",
+            Icon::List,
+            path.display(),
+            file.specs.len()
+        );
+        specs.extend(file.specs.into_iter().map(|spec| (spec, Tags::new())));
+        aliases.extend(file.aliases);
+        overrides.extend(file.overrides);
+    }
+
+    // 只有出现主机名时才创建解析器，--no-resolve 时不解析
+    let needs_dns = !sources.no_resolve && specs.iter().any(|(s, _)| is_hostname(s));
+    let mut set = resolve_specs(&specs, needs_dns.then(dns::resolver), sources.ip_version).await?;
+    for (name, ip) in aliases {
+        set.add(&name, vec![ip]);
+    }
+    set.set_global_tags(sources.tags.iter().cloned().collect());
+    for (spec, o) in overrides {
+        set.set_overrides(&spec, o);
+    }
+    // 展开及去重的数量在排除和授权范围核对之前统计，之后移除的目标另有提示
+    if set.merged() > 0 {
+        println!(
+            "{} 目标展开为 {} 个IP，去重后 {} 个（同一IP只探测一次，其他写法记为别名）",
+            Icon::List,
+            set.len() + set.merged(),
+            set.len()
+        );
+    }
+
+    // 排除的地址不探测，全部被排除时与没有目标一样报错；
+    // 按区间比对，不展开排除的网段，网段的网络地址和广播地址同样排除
+    if let Some(ref exclude) = sources.exclude {
+        let excluded =
+            parse_exclude_ranges(exclude).map_err(|e| format!("--exclude 参数无效: {}", e))?;
+        let before = set.len();
+        set.remove_ranges(&excluded);
+        println!("{} 已排除 {} 个目标", Icon::List, before - set.len());
+    }
+
+    if set.is_empty() {
+        return Err("未解析到任何有效的IP地址".into());
+    }
+    let max_targets = sources.max_targets.unwrap_or(DEFAULT_MAX_TARGETS);
+    if set.len() > max_targets {
+        println!(
+            "{} 目标展开后共 {} 个IP，超过 {}（--max-targets），请确认范围无误",
+            Icon::Warn,
+            set.len(),
+            max_targets
+        );
+    }
+    // 主机名解析之后再核对，解析到范围外地址的主机名同样会被拦下
+    scope::enforce(&mut set)?;
+
+    Ok(set)
+}
+
+/// 展开或解析每个原始写法，并按IP规范化
+///
+/// IP、范围及网段按区间加入，不展开；主机名通过 `resolver` 解析，
+/// 只保留 `ip_version` 指定地址族的地址（IPv4在前）。
+///
+/// # 参数
+/// * `specs` - 原始写法（每项不含逗号）及其携带的标签
+/// * `resolver` - 主机名解析器，为 `None` 时只接受IP写法
+/// * `ip_version` - 主机名解析时使用的地址族
+pub async fn resolve_specs(
+    specs: &[(String, Tags)],
+    resolver: Option<&Resolver>,
+    ip_version: IpVersion,
+) -> Result<TargetSet, Box<dyn Error + Send + Sync>> {
+    let mut set = TargetSet::default();
+    for (spec, tags) in specs {
+        if !is_hostname(spec) {
+            set.add_ranges(spec, parse_target_ranges(spec, false)?, tags);
+            continue;
+        }
+        let ips = match resolver {
+            Some(resolver) => {
+                let addrs = resolver
+                    .lookup_ip(spec)
+                    .await
+                    .map_err(|e| format!("无法解析主机名 {}: {}", spec, e))?;
+                let mut addrs: Vec<IpAddr> = addrs
+                    .iter()
+                    .copied()
+                    .filter(|ip| ip_version.accepts(ip))
+                    .collect();
+                if addrs.is_empty() {
+                    return Err(format!("主机名 {} 没有{}地址", spec, ip_version.label()).into());
+                }
+                addrs.sort_by_key(|ip| ip.is_ipv6());
+                addrs.iter().map(IpAddr::to_string).collect()
+            }
+            None => {
+                return Err(
+                    format!("已禁用主机名解析（--no-resolve），目标 {} 不是IP地址", spec).into(),
+                );
+            }
+        };
+        set.add_tagged(spec, ips, tags);
+    }
+    Ok(set)
+}
+
+/// 终端输出中跟在IP后的别名（没有别名时为空）
+///
+/// # 示例
+/// ```ignore
+/// assert_eq!(alias_suffix(&["localhost".to_string()]), " (localhost)");
+/// ```
+pub fn alias_suffix(aliases: &[String]) -> String {
+    if aliases.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", aliases.join(", "))
+    }
+}
+
+/// 按逗号拆分目标字符串，去掉空项
+pub fn split_specs(targets: &str) -> impl Iterator<Item = String> + '_ {
+    targets
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// 是否形如主机名（字母、数字、连字符组成的标签，至少含一个字母）
+pub fn is_hostname(s: &str) -> bool {
+    let s = s.strip_suffix('.').unwrap_or(s);
+    !s.is_empty()
+        && s.len() <= 253
+        && s.chars().any(|c| c.is_ascii_alphabetic())
+        && s.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// 从Excel文件读取目标列表
+///
+/// 指定列名时按首行表头查找该列；未指定时读取第一列，若首行第一列本身
+/// 就是合法目标则视为无表头。每个单元格按 [`parse_targets`](super::parse_targets) 的格式解析，
+/// 空单元格和无效单元格会被跳过并记录原因。
+///
+/// # 参数
+/// * `path` - Excel文件路径
+/// * `column` - 列名（可选）
+/// * `sheet` - 工作表名（可选）
+/// * `tag_columns` - 作为标签读取的列名（需要首行表头）
+///
+/// # 返回
+/// * `Ok(XlsxImportReport)` - 导入结果
+/// * `Err` - 文件无法打开、工作表或列不存在
+pub fn read_targets_xlsx(
+    path: &Path,
+    column: Option<&str>,
+    sheet: Option<&str>,
+    tag_columns: &[String],
+) -> Result<XlsxImportReport, Box<dyn Error + Send + Sync>> {
+    let mut workbook = open_workbook_auto(path)
+        .map_err(|e| format!("无法打开Excel文件 {}: {}", path.display(), e))?;
+
+    let sheet_name = match sheet {
+        Some(name) => name.to_string(),
+        None => workbook
+            .sheet_names()
+            .first()
+            .cloned()
+            .ok_or_else(|| format!("Excel文件 {} 中没有工作表", path.display()))?,
+    };
+
+    let range = workbook
+        .worksheet_range(&sheet_name)
+        .map_err(|e| format!("无法读取工作表 {}: {}", sheet_name, e))?;
+
+    let rows: Vec<&[Data]> = range.rows().collect();
+    let (col_index, data_start) = locate_column(&rows, column)?;
+    if !tag_columns.is_empty() && data_start == 0 {
+        return Err("指定 --tag-columns 时Excel首行必须为表头".into());
+    }
+    let tag_indexes = tag_columns
+        .iter()
+        .map(|name| Ok((name.clone(), find_column(&rows, name)?)))
+        .collect::<Result<Vec<_>, Box<dyn Error + Send + Sync>>>()?;
+
+    let mut report = XlsxImportReport::default();
+    for (i, row) in rows.iter().enumerate().skip(data_start) {
+        // Excel行号从1开始
+        let row_no = i + 1;
+        report.rows += 1;
+
+        let cell = row
+            .get(col_index)
+            .map(|c| c.to_string())
+            .unwrap_or_default();
+        let cell = cell.trim();
+
+        if cell.is_empty() {
+            report.skipped.push((row_no, "空单元格".to_string()));
+            continue;
+        }
+        // 单元格中目标之后可带参数覆盖，如 10.8.0.0/24 timeout=5
+        let (cell, params) = cell.split_once(char::is_whitespace).unwrap_or((cell, ""));
+        let overrides = TargetOverrides::parse(params.split_whitespace())
+            .map_err(|e| format!("Excel文件 {} 第{}行: {}", path.display(), row_no, e))?;
+
+        match count_targets(cell) {
+            Ok(count) => {
+                report.parsed += 1;
+                // 空单元格不产生标签
+                let tags: Tags = tag_indexes
+                    .iter()
+                    .filter_map(|(name, index)| {
+                        let value = row.get(*index)?.to_string().trim().to_string();
+                        (!value.is_empty()).then(|| (name.clone(), value))
+                    })
+                    .collect();
+                report.targets += count;
+                report
+                    .specs
+                    .extend(split_specs(cell).map(|spec| (spec, tags.clone())));
+                if !overrides.is_empty() {
+                    report
+                        .overrides
+                        .extend(split_specs(cell).map(|spec| (spec, overrides)));
+                }
+            }
+            Err(e) => report.skipped.push((row_no, e.to_string())),
+        }
+    }
+
+    Ok(report)
+}
+
+/// 确定目标所在列及数据起始行
+///
+/// # 返回
+/// * `(列索引, 数据起始行索引)`
+fn locate_column(
+    rows: &[&[Data]],
+    column: Option<&str>,
+) -> Result<(usize, usize), Box<dyn Error + Send + Sync>> {
+    let header = rows.first().copied().unwrap_or(&[]);
+
+    match column {
+        Some(name) => Ok((find_column(rows, name)?, 1)),
+        None => {
+            let first = header.first().map(|c| c.to_string()).unwrap_or_default();
+            let has_header = count_targets(first.trim()).is_err();
+            Ok((0, if has_header { 1 } else { 0 }))
+        }
+    }
+}
+
+/// 按首行表头查找列
+fn find_column(rows: &[&[Data]], name: &str) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let header = rows.first().copied().unwrap_or(&[]);
+    header
+        .iter()
+        .position(|c| c.to_string().trim() == name)
+        .ok_or_else(|| {
+            let available: Vec<String> = header
+                .iter()
+                .map(|c| c.to_string())
+                .filter(|s| !s.trim().is_empty())
+                .collect();
+            format!("未找到列 \"{}\"，可用列: {}", name, available.join(", ")).into()
+        })
+}
+
+/// 打印Excel导入统计（最多列出10条跳过原因）
+fn print_import_report(path: &Path, report: &XlsxImportReport) {
+    println!(
+        "{} 已导入 {}: 读取 {} 行, 解析 {} 行, 跳过 {} 行, 得到 {} 个IP",
+        Icon::List,
+        path.display(),
+        report.rows,
+        report.parsed,
+        report.skipped.len(),
+        report.targets
+    );
+    for (row, reason) in report.skipped.iter().take(10) {
+        println!("   {} 第{}行: {}", Icon::Warn, row, reason);
+    }
+    if report.skipped.len() > 10 {
+        println!("   ... 另有 {} 行被跳过", report.skipped.len() - 10);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::dns::{DnsAnswer, DnsConfig, DnsError, DnsQuery, DnsRecords, DnsTransport};
+    use futures::FutureExt;
+    use futures::future::BoxFuture;
+    use rust_xlsxwriter::Workbook;
+    use std::time::Duration;
+
+    fn write_xlsx(name: &str, rows: &[&[&str]]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("gxr_{}_{}.xlsx", name, std::process::id()));
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        for (r, row) in rows.iter().enumerate() {
+            for (c, value) in row.iter().enumerate() {
+                worksheet.write_string(r as u32, c as u16, *value).unwrap();
+            }
+        }
+        workbook.save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_read_targets_xlsx_named_column() {
+        let path = write_xlsx(
+            "named",
+            &[
+                &["资产名称", "IP地址"],
+                &["网关", "192.168.1.1"],
+                &["网段", "10.0.0.0/30"],
+                &["无效", "abc"],
+                &["空", ""],
+            ],
+        );
+        let report = read_targets_xlsx(&path, Some("IP地址"), None, &[]).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(report.rows, 4);
+        assert_eq!(report.parsed, 2);
+        assert_eq!(report.skipped.len(), 2);
+        assert_eq!(report.skipped[0].0, 4);
+        assert_eq!(report.targets, 3);
+    }
+
+    #[test]
+    fn test_read_targets_xlsx_first_column_without_header() {
+        let path = write_xlsx("noheader", &[&["192.168.1.1"], &["192.168.1.2-3"]]);
+        let report = read_targets_xlsx(&path, None, None, &[]).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(report.rows, 2);
+        assert_eq!(report.targets, 3);
+    }
+
+    #[test]
+    fn test_read_targets_xlsx_missing_column() {
+        let path = write_xlsx("missing", &[&["IP"], &["192.168.1.1"]]);
+        let result = read_targets_xlsx(&path, Some("IP地址"), None, &[]);
+        std::fs::remove_file(&path).ok();
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_collect_targets_merges_and_dedupes() {
+        let path = write_xlsx("merge", &[&["IP地址"], &["192.168.1.2"], &["192.168.1.5"]]);
+        let sources = TargetSourceArgs {
+            target_xlsx: Some(path.clone()),
+            ..Default::default()
+        };
+        let set = collect_targets(Some("192.168.1.1-2"), &sources)
+            .await
+            .unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(set.ips(), vec!["192.168.1.1", "192.168.1.2", "192.168.1.5"]);
+        assert_eq!(set.merged(), 1);
+        assert_eq!(set.aliases("192.168.1.2"), vec!["192.168.1.1-2"]);
+        // 只来自一个网段的IP不带别名
+        assert!(set.aliases("192.168.1.1").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_collect_targets_merges_overlapping_specs() {
+        let set = collect_targets(
+            Some("192.168.1.0/29,192.168.1.5-10,192.168.1.6"),
+            &TargetSourceArgs::default(),
+        )
+        .await
+        .unwrap();
+        let expected: Vec<String> = (1..=10).map(|i| format!("192.168.1.{}", i)).collect();
+        assert_eq!(set.ips(), expected);
+        // 展开 6 + 6 + 1 个，去重后 10 个
+        assert_eq!(set.merged(), 3);
+        assert_eq!(
+            set.aliases("192.168.1.6"),
+            vec!["192.168.1.0/29", "192.168.1.5-10"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect_targets_applies_exclude() {
+        let sources = TargetSourceArgs {
+            exclude: Some("10.0.0.1,10.0.0.4-5".to_string()),
+            ..Default::default()
+        };
+        let set = collect_targets(Some("10.0.0.0/29"), &sources)
+            .await
+            .unwrap();
+        assert_eq!(set.ips(), vec!["10.0.0.2", "10.0.0.3", "10.0.0.6"]);
+
+        let sources = TargetSourceArgs {
+            exclude: Some("10.0.0.0/24".to_string()),
+            ..Default::default()
+        };
+        // 网段的网络地址和广播地址以范围写入目标时同样被排除
+        let set = collect_targets(Some("10.0.0.0-10.0.1.0"), &sources)
+            .await
+            .unwrap();
+        assert_eq!(set.ips(), vec!["10.0.1.0"]);
+        let sources = TargetSourceArgs {
+            exclude: Some("10.0.0.*,10.0.0.0/8".to_string()),
+            ..Default::default()
+        };
+        let err = collect_targets(Some("10.0.0.0/29"), &sources)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "未解析到任何有效的IP地址");
+    }
+
+    #[tokio::test]
+    async fn test_large_ranges_stay_unexpanded() {
+        let sources = TargetSourceArgs {
+            exclude: Some("10.0.0.0/16".to_string()),
+            max_targets: Some(usize::MAX),
+            ..Default::default()
+        };
+        let mut set = collect_targets(Some("10.0.0.0/8,10.1.2.3"), &sources)
+            .await
+            .unwrap();
+        // 10.0.0.0 本身不在 /8 展开的范围内
+        assert_eq!(set.len(), (1 << 24) - 2 - 65535);
+        assert_eq!(set.merged(), 1);
+        assert_eq!(set.aliases("10.1.2.3"), vec!["10.0.0.0/8"]);
+        assert!(set.get("10.0.0.9").is_none());
+        assert_eq!(set.iter_ips().next().unwrap(), "10.1.0.0");
+        assert_eq!(set.spec_ips("10.1.2.3").unwrap(), ["10.1.2.3"]);
+
+        set.truncate(3);
+        assert_eq!(set.ips(), ["10.1.0.0", "10.1.0.1", "10.1.0.2"]);
+        assert_eq!(set.spec_ips("10.0.0.0/8").unwrap().len(), 3);
+        assert_eq!(set.merged(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_target_file_overrides() {
+        let path = std::env::temp_dir().join(format!("gxr_overrides_{}.txt", std::process::id()));
+        std::fs::write(
+            &path,
+            "10.0.0.1 gw timeout=300ms\n10.0.0.0/30 timeout=5 count=5 rate=50 # 卫星链路\n",
+        )
+        .unwrap();
+        let sources = TargetSourceArgs {
+            target_file: Some(path.clone()),
+            ..Default::default()
+        };
+        let set = collect_targets(Some("10.0.0.9"), &sources).await.unwrap();
+        // 同一IP以先出现的一行为准，其余参数取自后面的行
+        let first = set.overrides("10.0.0.1");
+        assert_eq!(first.timeout_ms, Some(300));
+        assert_eq!((first.count, first.rate), (Some(5), Some(50)));
+        assert_eq!(set.overrides("10.0.0.2").timeout_ms, Some(5000));
+        assert!(set.overrides("10.0.0.9").is_empty());
+        assert_eq!(set.aliases("10.0.0.1"), vec!["10.0.0.0/30", "gw"]);
+        let lines = set.override_specs();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].hosts, 2);
+        assert_eq!(
+            lines[1].overrides.to_string(),
+            "超时=5秒, 次数=5, 速率=50/秒"
+        );
+
+        std::fs::write(&path, "10.0.0.1\n\n10.0.0.2 timeout=0\n").unwrap();
+        let err = read_targets_file(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).ok();
+        assert!(err.contains("第3行"), "{}", err);
+
+        std::fs::write(&path, "# 范围\n10.0.0.1\ngw.corp.local\n10.0.0.300\n").unwrap();
+        let err = read_targets_file(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).ok();
+        assert!(err.contains("第4行"), "{}", err);
+    }
+
+    #[test]
+    fn test_target_lines_accept_commas_and_comments() {
+        let file = parse_target_lines(
+            "# prod\n10.0.0.1,10.0.0.2\n\n10.0.1.0/30 # 网段\n",
+            "标准输入",
+        )
+        .unwrap();
+        assert_eq!(file.specs, ["10.0.0.1", "10.0.0.2", "10.0.1.0/30"]);
+        let err = parse_target_lines("10.0.0.1\n10.0.0.300\n", "标准输入").unwrap_err();
+        assert_eq!(err.to_string(), "标准输入 第2行: 无效的IP地址: 10.0.0.300");
+    }
+
+    #[test]
+    fn test_parse_overrides() {
+        let o = TargetOverrides::parse(["timeout=0.5", "count=2"]).unwrap();
+        assert_eq!(o.timeout(), Some(std::time::Duration::from_millis(500)));
+        assert_eq!(o.count, Some(2));
+        assert!(TargetOverrides::parse(["timeout=2s"]).is_ok());
+        assert!(TargetOverrides::parse(["count=0"]).is_err());
+        assert!(TargetOverrides::parse(["rate=-1"]).is_err());
+        assert!(TargetOverrides::parse(["timeout=abc"]).is_err());
+        assert!(TargetOverrides::parse(["timeout=1", "timeout=2"]).is_err());
+        let err = TargetOverrides::parse(["retries=3"]).unwrap_err();
+        assert!(err.contains("未知参数 retries"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_tag() {
+        assert_eq!(
+            parse_tag("项目=2024Q3渗透").unwrap(),
+            ("项目".to_string(), "2024Q3渗透".to_string())
+        );
+        assert_eq!(parse_tag("备注=a=b").unwrap().1, "a=b");
+        assert!(parse_tag("项目").is_err());
+        assert!(parse_tag("=值").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tag_columns_follow_targets() {
+        let path = write_xlsx(
+            "tags",
+            &[
+                &["IP地址", "业务系统", "责任人"],
+                &["192.168.1.1-2", "OA", "张三"],
+                &["192.168.1.2", "邮件", ""],
+            ],
+        );
+        let sources = TargetSourceArgs {
+            target_xlsx: Some(path.clone()),
+            column: Some("IP地址".to_string()),
+            tag_columns: vec!["业务系统".to_string(), "责任人".to_string()],
+            tags: vec![
+                ("项目".to_string(), "2024Q3渗透".to_string()),
+                ("责任人".to_string(), "李四".to_string()),
+            ],
+            ..Default::default()
+        };
+        let set = collect_targets(None, &sources).await.unwrap();
+        let missing = read_targets_xlsx(&path, Some("IP地址"), None, &["部门".to_string()]);
+        std::fs::remove_file(&path).ok();
+        assert!(missing.is_err());
+
+        let tags = set.tags("192.168.1.1");
+        assert_eq!(tags["业务系统"], "OA");
+        assert_eq!(tags["责任人"], "张三");
+        assert_eq!(tags["项目"], "2024Q3渗透");
+        // 同一IP出现在多行时标签合并，空单元格不覆盖
+        let tags = set.tags("192.168.1.2");
+        assert_eq!(tags["业务系统"], "OA; 邮件");
+        assert_eq!(tags["责任人"], "张三");
+        // 未在清单中的IP只有全局标签
+        assert_eq!(set.tags("10.0.0.1").len(), 2);
+        assert_eq!(
+            tag_keys([&set.tags("192.168.1.1")]),
+            ["业务系统", "责任人", "项目"]
+        );
+    }
+
+    /// 只认识固定主机名的解析器
+    struct HostsTransport(Vec<(&'static str, &'static str)>);
+
+    impl DnsTransport for HostsTransport {
+        fn query(&self, query: &DnsQuery) -> BoxFuture<'_, Result<DnsAnswer, DnsError>> {
+            let ips: Vec<IpAddr> = self
+                .0
+                .iter()
+                .filter(|(name, _)| matches!(query, DnsQuery::Ip(q) if q == name))
+                .map(|(_, ip)| ip.parse().unwrap())
+                .collect();
+            let answer = if ips.is_empty() {
+                Err(DnsError::NotFound { negative_ttl: None })
+            } else {
+                Ok(DnsAnswer {
+                    records: DnsRecords::Ips(ips),
+                    ttl: Duration::from_secs(60),
+                })
+            };
+            async move { answer }.boxed()
+        }
+    }
+
+    fn specs(targets: &str) -> Vec<(String, Tags)> {
+        split_specs(targets).map(|s| (s, Tags::new())).collect()
+    }
+
+    #[tokio::test]
+    async fn test_hostname_and_ip_specs_share_one_target() {
+        let resolver = Resolver::new(
+            HostsTransport(vec![("localhost", "127.0.0.1"), ("localhost", "::1")]),
+            DnsConfig::default(),
+        );
+        let set = resolve_specs(
+            &specs("localhost,127.0.0.1,127.0.0.1/32"),
+            Some(&resolver),
+            IpVersion::V4,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(set.ips(), vec!["127.0.0.1"]);
+        assert_eq!(set.aliases("127.0.0.1"), vec!["localhost", "127.0.0.1/32"]);
+        // 每种写法都能取回同一个IP
+        for spec in ["localhost", "127.0.0.1", "127.0.0.1/32"] {
+            assert_eq!(set.spec_ips(spec).unwrap(), ["127.0.0.1"]);
+        }
+
+        // 单独的主机名也记为别名
+        let set = resolve_specs(&specs("localhost"), Some(&resolver), IpVersion::V4)
+            .await
+            .unwrap();
+        assert_eq!(set.aliases("127.0.0.1"), vec!["localhost"]);
+
+        assert!(
+            resolve_specs(&specs("missing.corp"), Some(&resolver), IpVersion::V4)
+                .await
+                .is_err()
+        );
+        // 主机名解析为IP后标签仍然保留
+        let tagged = [(
+            "localhost".to_string(),
+            Tags::from([("业务系统".to_string(), "OA".to_string())]),
+        )];
+        let set = resolve_specs(&tagged, Some(&resolver), IpVersion::V4)
+            .await
+            .unwrap();
+        assert_eq!(set.tags("127.0.0.1")["业务系统"], "OA");
+
+        // 同时使用两个地址族时IPv4在前，只用IPv6时不含IPv4地址
+        let set = resolve_specs(&specs("localhost"), Some(&resolver), IpVersion::Both)
+            .await
+            .unwrap();
+        assert_eq!(set.ips(), vec!["127.0.0.1", "::1"]);
+        let set = resolve_specs(&specs("localhost"), Some(&resolver), IpVersion::V6)
+            .await
+            .unwrap();
+        assert_eq!(set.ips(), vec!["::1"]);
+
+        // 不允许解析时主机名视为无效目标
+        let err = resolve_specs(&specs("10.0.0.1,localhost"), None, IpVersion::V4)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("--no-resolve"));
+    }
+
+    #[test]
+    fn test_is_hostname() {
+        assert!(is_hostname("localhost"));
+        assert!(is_hostname("gw-01.corp.local."));
+        assert!(!is_hostname("10.0.0.1"));
+        assert!(!is_hostname("-bad.corp"));
+        assert!(!is_hostname("a..b"));
+        assert!(!is_hostname("主机"));
+    }
+}
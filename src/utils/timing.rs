@@ -0,0 +1,559 @@
+// src/utils/timing.rs
+use super::adaptive::{AdaptiveLimiter, AdaptiveSlot};
+use super::limits::ConcurrencySpec;
+use super::run_dir::SummaryItem;
+use super::targets::TargetSet;
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::{Instant, sleep_until};
+
+/// 时序模板（参考 nmap 的 -T 选项）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimingTemplate {
+    /// 同一时刻只有一个探测，探测间隔数秒且随机抖动
+    Paranoid,
+    /// 低并发、限速，每个主机同时只有一个探测
+    Sneaky,
+    /// 默认参数
+    #[default]
+    Normal,
+    /// 提高并发、缩短超时，适用于网络质量好且不要求隐蔽的场景
+    Aggressive,
+}
+
+impl fmt::Display for TimingTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Paranoid => "paranoid",
+            Self::Sneaky => "sneaky",
+            Self::Normal => "normal",
+            Self::Aggressive => "aggressive",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 时序参数
+///
+/// 模板给出并发、速率、单主机并行数、探测间隔和重试次数的整套取值，
+/// 显式给出的参数（包括各模块自身的 -c、-T）覆盖模板中的对应项。
+#[derive(Args, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimingArgs {
+    /// 时序模板：paranoid（最慢、最隐蔽）| sneaky | normal（默认）| aggressive
+    #[arg(long, env = "GXTOOLS_TIMING", value_enum, value_name = "TEMPLATE")]
+    pub timing: Option<TimingTemplate>,
+
+    /// 每秒最多发起的探测数
+    #[arg(long, value_name = "PPS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_rate: Option<u32>,
+
+    /// 每个主机同时进行的最大探测数
+    #[arg(long, value_name = "NUM", value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_host_parallelism: Option<u64>,
+
+    /// 相邻两次探测的最小间隔（毫秒）
+    #[arg(long, value_name = "MS")]
+    pub scan_delay: Option<u64>,
+
+    /// 探测无应答时的重试次数
+    #[arg(long, value_name = "NUM")]
+    pub retries: Option<u32>,
+
+    /// 自适应并发（-c auto-adaptive）的下限，也是初始并发，默认50
+    #[arg(long = "min", value_name = "NUM", value_parser = clap::value_parser!(u64).range(1..))]
+    pub adaptive_min: Option<u64>,
+
+    /// 自适应并发（-c auto-adaptive）的上限，默认2000
+    #[arg(long = "max", value_name = "NUM", value_parser = clap::value_parser!(u64).range(1..))]
+    pub adaptive_max: Option<u64>,
+}
+
+/// 模块在 normal 模板下的默认参数
+#[derive(Debug, Clone, Copy)]
+pub struct TimingDefaults {
+    /// 并发数
+    pub concurrency: usize,
+    /// 超时（秒）
+    pub timeout_secs: u64,
+    /// 重试次数
+    pub retries: u32,
+}
+
+/// 生效的时序参数（打印在扫描开始时，并写入历史记录和运行目录的统计摘要）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timing {
+    /// 使用的模板
+    pub template: TimingTemplate,
+    /// 并发参数（实际并发还受文件描述符上限和任务数限制）
+    pub concurrency: ConcurrencySpec,
+    /// 超时（秒）
+    pub timeout_secs: u64,
+    /// 每秒最多发起的探测数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rate: Option<u32>,
+    /// 每个主机同时进行的最大探测数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_parallelism: Option<usize>,
+    /// 相邻两次探测的最小间隔（毫秒）
+    pub scan_delay_ms: u64,
+    /// 探测间隔是否随机抖动（±50%）
+    pub jitter: bool,
+    /// 重试次数
+    pub retries: u32,
+}
+
+impl TimingTemplate {
+    /// 模板对应的整套参数
+    ///
+    /// # 参数
+    /// * `defaults` - 模块在 normal 模板下的默认参数
+    pub fn preset(self, defaults: TimingDefaults) -> Timing {
+        let normal = Timing {
+            template: self,
+            concurrency: ConcurrencySpec::Fixed(defaults.concurrency),
+            timeout_secs: defaults.timeout_secs,
+            max_rate: None,
+            host_parallelism: None,
+            scan_delay_ms: 0,
+            jitter: false,
+            retries: defaults.retries,
+        };
+        match self {
+            Self::Paranoid => Timing {
+                concurrency: ConcurrencySpec::Fixed(1),
+                timeout_secs: defaults.timeout_secs * 2,
+                host_parallelism: Some(1),
+                scan_delay_ms: 5000,
+                jitter: true,
+                retries: defaults.retries.max(1),
+                ..normal
+            },
+            Self::Sneaky => Timing {
+                concurrency: ConcurrencySpec::Fixed(5),
+                timeout_secs: defaults.timeout_secs * 2,
+                max_rate: Some(10),
+                host_parallelism: Some(1),
+                scan_delay_ms: 500,
+                jitter: true,
+                retries: defaults.retries.max(1),
+                ..normal
+            },
+            Self::Normal => normal,
+            Self::Aggressive => Timing {
+                concurrency: ConcurrencySpec::Fixed(defaults.concurrency * 4),
+                timeout_secs: (defaults.timeout_secs / 2).max(1),
+                retries: defaults.retries.min(1),
+                ..normal
+            },
+        }
+    }
+}
+
+impl Timing {
+    /// 按模板及显式参数得到生效的时序参数
+    ///
+    /// # 参数
+    /// * `args` - 时序参数
+    /// * `defaults` - 模块在 normal 模板下的默认参数
+    /// * `concurrency` - 模块的 -c 参数（显式给出时覆盖模板）
+    /// * `timeout_secs` - 模块的 -T 参数（显式给出时覆盖模板）
+    /// * `retries` - 模块自身的重试参数（如 ping 的 -n，优先于 --retries）
+    pub fn resolve(
+        args: &TimingArgs,
+        defaults: TimingDefaults,
+        concurrency: Option<ConcurrencySpec>,
+        timeout_secs: Option<u64>,
+        retries: Option<u32>,
+    ) -> Self {
+        let mut timing = args.timing.unwrap_or_default().preset(defaults);
+        if let Some(concurrency) = concurrency {
+            timing.concurrency = concurrency;
+        }
+        // --min、--max 只对自适应并发生效
+        if let ConcurrencySpec::Adaptive { min, max } = &mut timing.concurrency {
+            if let Some(n) = args.adaptive_min {
+                *min = n as usize;
+            }
+            if let Some(n) = args.adaptive_max {
+                *max = n as usize;
+            }
+            *max = (*max).max(*min);
+        }
+        if let Some(timeout_secs) = timeout_secs {
+            timing.timeout_secs = timeout_secs;
+        }
+        if let Some(rate) = args.max_rate {
+            timing.max_rate = Some(rate);
+        }
+        if let Some(n) = args.max_host_parallelism {
+            timing.host_parallelism = Some(n as usize);
+        }
+        if let Some(delay) = args.scan_delay {
+            timing.scan_delay_ms = delay;
+        }
+        if let Some(retries) = retries.or(args.retries) {
+            timing.retries = retries;
+        }
+        timing
+    }
+
+    /// 相邻两次探测的最小间隔（速率限制与探测间隔取较大者）
+    pub fn probe_interval(&self) -> Duration {
+        let delay = Duration::from_millis(self.scan_delay_ms);
+        let rate = self
+            .max_rate
+            .map(|r| Duration::from_secs(1) / r.max(1))
+            .unwrap_or_default();
+        delay.max(rate)
+    }
+
+    /// 写入统计摘要的条目
+    pub fn summary_items(&self) -> Vec<SummaryItem> {
+        vec![(
+            "时序模板".to_string(),
+            format!("{}（{}）", self.template, self),
+        )]
+    }
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "并发={}, 超时={}秒, 速率={}, 单主机并行={}, 探测间隔={}ms{}, 重试={}次",
+            self.concurrency,
+            self.timeout_secs,
+            self.max_rate
+                .map(|r| format!("{}/秒", r))
+                .unwrap_or_else(|| "不限".to_string()),
+            self.host_parallelism
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| "不限".to_string()),
+            self.scan_delay_ms,
+            if self.jitter { "±50%" } else { "" },
+            self.retries
+        )
+    }
+}
+
+/// 探测节流：控制探测发起的间隔及每个主机的并行数
+///
+/// 并发数仍由工作池限制，节流只在每次探测发起前等待。
+/// 间隔按预约方式分配，多个探测同时等待时依次错开。
+/// 间隔可在扫描过程中调整（如通过控制通道降低速率），从下一次预约起生效。
+/// 目标文件中带 `rate=` 的行另有一组共享的间隔，该行的目标同时受两者限制。
+/// 开启自适应并发时，间隔等待结束后还须取得并发许可，速率限制与自适应并发以更严格者为准。
+#[derive(Debug, Default)]
+pub struct Throttle {
+    /// 探测间隔（纳秒，0为不限）
+    interval: AtomicU64,
+    jitter: bool,
+    next: tokio::sync::Mutex<Option<Instant>>,
+    host_limit: Option<usize>,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+    groups: HashMap<String, Arc<RateGroup>>,
+    adaptive: OnceLock<Arc<AdaptiveLimiter>>,
+    seed: AtomicU64,
+}
+
+/// 一组主机共享的速率限制
+#[derive(Debug)]
+struct RateGroup {
+    interval: Duration,
+    next: tokio::sync::Mutex<Option<Instant>>,
+}
+
+/// 探测许可，释放后同一主机的下一个探测才能发起
+pub struct ProbePermit {
+    _host: Option<OwnedSemaphorePermit>,
+    _adaptive: Option<AdaptiveSlot>,
+}
+
+impl Throttle {
+    /// 按时序参数创建节流器
+    pub fn new(timing: &Timing) -> Self {
+        Self {
+            interval: AtomicU64::new(timing.probe_interval().as_nanos() as u64),
+            jitter: timing.jitter,
+            host_limit: timing.host_parallelism,
+            seed: AtomicU64::new(
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_nanos() as u64)
+                    .unwrap_or(0)
+                    ^ std::process::id() as u64,
+            ),
+            ..Default::default()
+        }
+    }
+
+    /// 按时序参数及目标行上的 `rate=` 覆盖创建节流器
+    ///
+    /// # 参数
+    /// * `timing` - 时序参数
+    /// * `targets` - 目标集合
+    pub fn for_targets(timing: &Timing, targets: &TargetSet) -> Self {
+        let mut throttle = Self::new(timing);
+        for line in targets.override_specs() {
+            if let Some(rate) = line.overrides.rate {
+                let hosts = targets.spec_ips(&line.spec).unwrap_or_default();
+                throttle.limit_hosts(hosts, rate);
+            }
+        }
+        throttle
+    }
+
+    /// 限制一组主机合计的探测速率（已被其他组限制的主机保持不变）
+    ///
+    /// # 参数
+    /// * `hosts` - 主机
+    /// * `rate` - 这些主机合计每秒最多发起的探测数
+    pub fn limit_hosts(&mut self, hosts: impl IntoIterator<Item = String>, rate: u32) {
+        let group = Arc::new(RateGroup {
+            interval: Duration::from_secs(1) / rate.max(1),
+            next: tokio::sync::Mutex::new(None),
+        });
+        for host in hosts {
+            self.groups.entry(host).or_insert_with(|| group.clone());
+        }
+    }
+
+    /// 探测发起前须取得自适应并发的许可（仅首次设置生效）
+    pub fn set_adaptive(&self, limiter: Arc<AdaptiveLimiter>) {
+        let _ = self.adaptive.set(limiter);
+    }
+
+    /// 是否不做任何限制
+    pub fn is_unlimited(&self) -> bool {
+        self.interval().is_zero()
+            && self.host_limit.is_none()
+            && self.groups.is_empty()
+            && self.adaptive.get().is_none()
+    }
+
+    /// 当前的探测间隔
+    fn interval(&self) -> Duration {
+        Duration::from_nanos(self.interval.load(Ordering::Relaxed))
+    }
+
+    /// 调整速率限制
+    ///
+    /// # 参数
+    /// * `rate` - 每秒最多发起的探测数（0为不限）
+    pub fn set_rate(&self, rate: u32) {
+        let interval = match rate {
+            0 => Duration::ZERO,
+            r => Duration::from_secs(1) / r,
+        };
+        self.interval
+            .store(interval.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// 当前的速率限制（每秒探测数，不限时为 `None`）
+    pub fn rate(&self) -> Option<f64> {
+        let interval = self.interval();
+        (!interval.is_zero()).then(|| 1.0 / interval.as_secs_f64())
+    }
+
+    /// 等待发起一次探测
+    ///
+    /// # 参数
+    /// * `host` - 探测的主机（用于限制单主机并行数）
+    pub async fn acquire(&self, host: &str) -> ProbePermit {
+        let host_permit = match self.host_limit {
+            Some(limit) => {
+                let semaphore = self
+                    .hosts
+                    .lock()
+                    .unwrap()
+                    .entry(host.to_string())
+                    .or_insert_with(|| Arc::new(Semaphore::new(limit.max(1))))
+                    .clone();
+                semaphore.acquire_owned().await.ok()
+            }
+            None => None,
+        };
+
+        // 先在所属的组内预约，再占用全局的间隔，避免全局的间隔空等
+        if let Some(group) = self.groups.get(host) {
+            let start = {
+                let mut next = group.next.lock().await;
+                let now = Instant::now();
+                let start = next.map_or(now, |n| n.max(now));
+                *next = Some(start + group.interval);
+                start
+            };
+            sleep_until(start).await;
+        }
+
+        if !self.interval().is_zero() {
+            let start = {
+                let mut next = self.next.lock().await;
+                let now = Instant::now();
+                let start = next.map_or(now, |n| n.max(now));
+                *next = Some(start + self.next_gap());
+                start
+            };
+            sleep_until(start).await;
+        }
+
+        // 间隔等待结束后再占用并发许可，许可只计实际进行中的探测
+        let adaptive = match self.adaptive.get() {
+            Some(limiter) => Some(limiter.acquire().await),
+            None => None,
+        };
+
+        ProbePermit {
+            _host: host_permit,
+            _adaptive: adaptive,
+        }
+    }
+
+    /// 下一次间隔（开启抖动时在 50%~150% 之间随机）
+    fn next_gap(&self) -> Duration {
+        let interval = self.interval();
+        if !self.jitter {
+            return interval;
+        }
+        // xorshift，只用于错开探测时间
+        let mut x = self.seed.load(Ordering::Relaxed) | 1;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.seed.store(x, Ordering::Relaxed);
+        interval.mul_f64(0.5 + (x % 1000) as f64 / 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFAULTS: TimingDefaults = TimingDefaults {
+        concurrency: 100,
+        timeout_secs: 2,
+        retries: 2,
+    };
+
+    #[test]
+    fn test_explicit_flags_override_template() {
+        let args = TimingArgs {
+            timing: Some(TimingTemplate::Paranoid),
+            scan_delay: Some(2000),
+            ..Default::default()
+        };
+        let timing = Timing::resolve(&args, DEFAULTS, None, Some(1), None);
+        assert_eq!(timing.concurrency, ConcurrencySpec::Fixed(1));
+        assert_eq!(timing.host_parallelism, Some(1));
+        assert_eq!(timing.timeout_secs, 1);
+        assert_eq!(timing.scan_delay_ms, 2000);
+        assert!(timing.jitter);
+
+        let timing = Timing::resolve(&TimingArgs::default(), DEFAULTS, None, None, Some(0));
+        assert_eq!(timing.template, TimingTemplate::Normal);
+        assert_eq!(timing.concurrency, ConcurrencySpec::Fixed(100));
+        assert_eq!(timing.retries, 0);
+        assert!(timing.probe_interval().is_zero());
+
+        let args = TimingArgs {
+            timing: Some(TimingTemplate::Aggressive),
+            ..Default::default()
+        };
+        let timing = Timing::resolve(&args, DEFAULTS, None, None, None);
+        assert_eq!(timing.concurrency, ConcurrencySpec::Fixed(400));
+        assert_eq!(timing.timeout_secs, 1);
+    }
+
+    #[test]
+    fn test_probe_interval_uses_slower_limit() {
+        let mut timing = TimingTemplate::Normal.preset(DEFAULTS);
+        timing.max_rate = Some(4);
+        assert_eq!(timing.probe_interval(), Duration::from_millis(250));
+        timing.scan_delay_ms = 1000;
+        assert_eq!(timing.probe_interval(), Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttle_spaces_probes_and_limits_hosts() {
+        let mut timing = TimingTemplate::Normal.preset(DEFAULTS);
+        timing.scan_delay_ms = 1000;
+        timing.host_parallelism = Some(1);
+        let throttle = Arc::new(Throttle::new(&timing));
+        assert!(!throttle.is_unlimited());
+
+        let start = Instant::now();
+        let first = throttle.acquire("10.0.0.1").await;
+        let _other = throttle.acquire("10.0.0.2").await;
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        // 同一主机要等上一个探测结束
+        let waiting = {
+            let throttle = throttle.clone();
+            tokio::spawn(async move {
+                let _permit = throttle.acquire("10.0.0.1").await;
+                Instant::now()
+            })
+        };
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        drop(first);
+        let acquired = waiting.await.unwrap();
+        assert_eq!(acquired - start, Duration::from_secs(6));
+
+        assert!(Throttle::default().is_unlimited());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_group_limits_only_its_hosts() {
+        let mut throttle = Throttle::default();
+        throttle.limit_hosts(["10.0.0.1".to_string(), "10.0.0.2".to_string()], 2);
+        // 先设置的组优先
+        throttle.limit_hosts(["10.0.0.2".to_string()], 100);
+        assert!(!throttle.is_unlimited());
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            throttle.acquire("10.0.0.9").await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        throttle.acquire("10.0.0.1").await;
+        throttle.acquire("10.0.0.2").await;
+        throttle.acquire("10.0.0.1").await;
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttle_rate_can_change_mid_scan() {
+        let throttle = Throttle::default();
+        assert_eq!(throttle.rate(), None);
+
+        throttle.set_rate(2);
+        assert_eq!(throttle.rate(), Some(2.0));
+        let start = Instant::now();
+        for _ in 0..3 {
+            throttle.acquire("10.0.0.1").await;
+        }
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        throttle.set_rate(0);
+        assert!(throttle.is_unlimited());
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        let timing = TimingTemplate::Paranoid.preset(DEFAULTS);
+        let throttle = Throttle::new(&timing);
+        for _ in 0..100 {
+            let gap = throttle.next_gap();
+            assert!(gap >= Duration::from_millis(2500) && gap < Duration::from_millis(7500));
+        }
+    }
+}
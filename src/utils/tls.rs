@@ -0,0 +1,251 @@
+// src/utils/tls.rs
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::io;
+use std::sync::{Arc, OnceLock};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::TlsConnector;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{self, CryptoProvider};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{
+    self, CertificateError, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+
+static CONNECTOR: OnceLock<TlsConnector> = OnceLock::new();
+
+/// 接受任意证书的校验器
+///
+/// 探测的目的是读取服务信息，自签名、过期或主机名不符的证书在内网中很常见，不应导致探测失败；
+/// 握手签名仍按正常流程校验。
+#[derive(Debug)]
+struct AcceptAnyCert(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// 只接受指定证书的校验器
+///
+/// 用于使用自签名证书的内网服务：证书（DER编码）的SHA-256指纹与任一指定值相同即通过，
+/// 不再校验证书链、有效期及主机名；握手签名仍按正常流程校验。
+#[derive(Debug)]
+struct PinnedCert {
+    inner: AcceptAnyCert,
+    pins: Vec<[u8; 32]>,
+}
+
+impl ServerCertVerifier for PinnedCert {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let digest: [u8; 32] = Sha256::digest(end_entity.as_ref()).into();
+        if self.pins.contains(&digest) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// 校验证书的TLS客户端配置
+///
+/// # 参数
+/// * `pins` - 证书的SHA-256指纹，为空时按内置的根证书校验证书链及主机名，否则只接受这些证书
+/// * `alpn` - 握手时提供的应用层协议，为空时不使用ALPN
+pub fn verified_client_config(pins: &[[u8; 32]], alpn: &[&[u8]]) -> ClientConfig {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .expect("默认的TLS版本应当受支持");
+    let mut config = if pins.is_empty() {
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        builder.with_root_certificates(roots).with_no_client_auth()
+    } else {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(PinnedCert {
+                inner: AcceptAnyCert(provider),
+                pins: pins.to_vec(),
+            }))
+            .with_no_client_auth()
+    };
+    config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+    config
+}
+
+/// 解析证书指纹
+///
+/// # 参数
+/// * `s` - 证书（DER编码）SHA-256摘要的十六进制写法，可用冒号分隔（如 `openssl x509 -noout -fingerprint -sha256` 输出中等号后的部分）
+pub fn parse_cert_pin(s: &str) -> Result<[u8; 32], String> {
+    let digits: String = s.chars().filter(|c| *c != ':').collect();
+    hex::decode(digits.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| format!("证书指纹应为64位十六进制的SHA-256摘要: {}", s))
+}
+
+/// 错误是否由证书校验失败引起（沿错误链查找，含 `io::Error` 包装的TLS错误）
+pub fn is_certificate_error(e: &(dyn Error + 'static)) -> bool {
+    let mut current = Some(e);
+    while let Some(err) = current {
+        if let Some(rustls::Error::InvalidCertificate(_)) = err.downcast_ref::<rustls::Error>() {
+            return true;
+        }
+        if let Some(io) = err.downcast_ref::<io::Error>()
+            && let Some(inner) = io.get_ref()
+            && is_certificate_error(inner)
+        {
+            return true;
+        }
+        current = err.source();
+    }
+    false
+}
+
+/// 不校验证书的TLS客户端配置
+///
+/// # 参数
+/// * `alpn` - 握手时提供的应用层协议（如 `b"h2"`），为空时不使用ALPN
+pub fn insecure_client_config(alpn: &[&[u8]]) -> ClientConfig {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let mut config = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .expect("默认的TLS版本应当受支持")
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AcceptAnyCert(provider)))
+        .with_no_client_auth();
+    config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+    config
+}
+
+/// 不校验证书的TLS客户端（全局共享）
+fn connector() -> &'static TlsConnector {
+    CONNECTOR.get_or_init(|| TlsConnector::from(Arc::new(insecure_client_config(&[]))))
+}
+
+/// 服务器名称（IP不发送SNI）
+fn server_name(host: &str) -> io::Result<ServerName<'static>> {
+    ServerName::try_from(host.to_string()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("无效的服务器名称: {}", host),
+        )
+    })
+}
+
+/// 在已建立的连接上进行TLS握手（不校验证书）
+///
+/// # 参数
+/// * `stream` - 已建立的连接
+/// * `host` - 服务器名称或IP（用于SNI，IP不发送SNI）
+///
+/// # 返回
+/// * `Ok(TlsStream)` - 握手完成的连接
+/// * `Err` - 名称无效或握手失败
+pub async fn connect_insecure<S>(stream: S, host: &str) -> io::Result<TlsStream<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    connector().connect(server_name(host)?, stream).await
+}
+
+/// 在已建立的连接上以ALPN握手，返回服务端选择的协议（不校验证书，握手后即断开）
+///
+/// # 参数
+/// * `stream` - 已建立的连接
+/// * `host` - 服务器名称或IP
+/// * `alpn` - 提供的协议，按优先级排列（如 `[b"h2", b"http/1.1"]`）
+///
+/// # 返回
+/// * `Ok(Some(协议))` - 服务端选择的协议
+/// * `Ok(None)` - 服务端不支持ALPN
+/// * `Err` - 握手失败
+pub async fn negotiate_alpn<S>(stream: S, host: &str, alpn: &[&[u8]]) -> io::Result<Option<String>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let connector = TlsConnector::from(Arc::new(insecure_client_config(alpn)));
+    let tls = connector.connect(server_name(host)?, stream).await?;
+    Ok(tls
+        .get_ref()
+        .1
+        .alpn_protocol()
+        .map(|p| String::from_utf8_lossy(p).to_string()))
+}
@@ -0,0 +1,937 @@
+// src/utils/transcript.rs
+use super::console::Icon;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use clap::Args;
+use regex::bytes::Regex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::task::{Context, Poll, Waker};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// 录制目录中的说明文件
+pub const TRANSCRIPT_META_FILE: &str = "transcript.json";
+
+/// 录制格式版本
+const TRANSCRIPT_VERSION: u32 = 1;
+
+/// 系统ping的录制类别
+pub const PING_KIND: &str = "ping";
+
+/// TCP连接的录制类别
+pub const TCP_KIND: &str = "tcp";
+
+static IPV4: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:\d{1,3}\.){3}\d{1,3}\b").expect("IPv4正则有效"));
+
+/// 录制与回放参数
+#[derive(Args, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscriptArgs {
+    /// 将每次探测的原始输入输出（ping程序输出、端口收发的数据）录制到目录，用于复现解析及分类问题
+    #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// 录制时将地址替换为 198.18.0.0/15、2001:db8::/32 中的固定替代地址，便于分享（替代的目标见 transcript.json）
+    #[arg(long, requires = "record")]
+    pub record_redact: bool,
+
+    /// 从录制目录读取探测结果，不访问网络（目标须与录制时相同，录制中没有的探测按超时处理）
+    #[arg(long, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
+}
+
+/// 录制目录的说明
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TranscriptMeta {
+    version: u32,
+    module: String,
+    created_at: String,
+    redacted: bool,
+    /// 录制到的目标（脱敏录制时为替代地址，回放时以此作为目标）
+    targets: BTreeSet<String>,
+    probes: u64,
+}
+
+/// 录制中的一条记录
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry<T> {
+    key: String,
+    seq: u32,
+    #[serde(flatten)]
+    data: T,
+}
+
+/// 探测录制与回放
+///
+/// 录制时探测照常进行，原始输入输出按“类别 + 目标 + 序号”写入录制目录；
+/// 回放时探测器按同样的键从录制中读取，不访问网络。同一目标的多次探测（重试、复核）
+/// 依次编号，回放时按相同顺序取出，解析和分类逻辑与实际探测完全一致。
+#[derive(Debug, Clone, Default)]
+pub enum Tape {
+    /// 实际探测，不录制
+    #[default]
+    Live,
+    /// 实际探测并录制
+    Record(Arc<Recorder>),
+    /// 从录制中回放
+    Replay(Arc<Player>),
+}
+
+impl Tape {
+    /// 按参数打开录制或回放
+    ///
+    /// # 参数
+    /// * `args` - 录制与回放参数
+    /// * `module` - 当前模块（如 "net ping"），回放时须与录制时一致
+    ///
+    /// # 返回
+    /// * `Err` - 无法创建录制目录，或回放目录无效
+    pub fn open(args: &TranscriptArgs, module: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        if let Some(ref dir) = args.replay {
+            let player = Player::open(dir, module)?;
+            println!(
+                "{} 回放模式: 从 {} 读取 {} 个探测记录，不访问网络",
+                Icon::Config,
+                dir.display(),
+                player.len()
+            );
+            return Ok(Self::Replay(Arc::new(player)));
+        }
+        if let Some(ref dir) = args.record {
+            let recorder = Recorder::create(dir, module, args.record_redact)?;
+            println!(
+                "{} 录制模式: 原始探测数据写入 {}{}",
+                Icon::Config,
+                dir.display(),
+                if args.record_redact {
+                    "（地址已替换）"
+                } else {
+                    ""
+                }
+            );
+            return Ok(Self::Record(Arc::new(recorder)));
+        }
+        Ok(Self::Live)
+    }
+
+    /// 是否为回放（不访问网络）
+    pub fn is_replay(&self) -> bool {
+        matches!(self, Self::Replay(_))
+    }
+
+    /// 结束录制或回放：写出录制说明，回放时提示录制中缺少的探测
+    pub fn finish(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self {
+            Self::Live => {}
+            Self::Record(recorder) => {
+                let (dir, probes) = recorder.finish()?;
+                println!("{} 已录制 {} 次探测至: {}", Icon::Ok, probes, dir.display());
+            }
+            Self::Replay(player) => {
+                let missing = player.missing.load(Ordering::Relaxed);
+                if missing > 0 {
+                    println!(
+                        "{} 回放时有 {} 次探测在录制中找不到，已按超时处理（目标或参数与录制时不同？）",
+                        Icon::Warn,
+                        missing
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 建立连接：录制时记录连接结果及之后收发的数据，回放时返回录制的数据流
+    ///
+    /// 回放时录制中没有该连接，或录制时连接未完成（被调用方超时放弃），返回的future不会完成，
+    /// 与实际探测一样由调用方的超时结束。
+    ///
+    /// # 参数
+    /// * `host` - 目标地址
+    /// * `suffix` - 键的其余部分（如 ":80"）
+    /// * `connect` - 实际建立连接的future（回放时不执行）
+    pub async fn connect<S, F>(
+        &self,
+        host: &str,
+        suffix: &str,
+        connect: F,
+    ) -> io::Result<TapeStream<S>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+        F: Future<Output = io::Result<S>>,
+    {
+        match self {
+            Self::Live => connect.await.map(TapeStream::Live),
+            Self::Record(recorder) => {
+                let take = recorder.begin(TCP_KIND, host, suffix);
+                let mut pending = PendingConnect {
+                    recorder,
+                    take: Some(take.clone()),
+                };
+                let result = connect.await;
+                pending.take = None;
+                match result {
+                    Ok(stream) => Ok(TapeStream::Record(RecordingStream {
+                        inner: stream,
+                        recorder: recorder.clone(),
+                        take,
+                        host: host.to_string(),
+                        events: Vec::new(),
+                    })),
+                    Err(e) => {
+                        let failed = TcpTranscript::Failed {
+                            error: IoErrorRecord::from(&e),
+                        };
+                        recorder.save(TCP_KIND, &take, &failed);
+                        Err(e)
+                    }
+                }
+            }
+            Self::Replay(player) => match player.take::<TcpTranscript>(TCP_KIND, host, suffix) {
+                Some(TcpTranscript::Connected { events }) => {
+                    Ok(TapeStream::Replay(ReplayStream::new(events)))
+                }
+                Some(TcpTranscript::Failed { error }) => Err(error.into()),
+                Some(TcpTranscript::Pending) | None => std::future::pending().await,
+            },
+        }
+    }
+}
+
+/// 一次录制的键
+#[derive(Debug, Clone)]
+pub struct Take {
+    key: String,
+    seq: u32,
+}
+
+/// 录制写入器
+#[derive(Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+    meta: Mutex<TranscriptMeta>,
+    redact: bool,
+    seqs: Mutex<HashMap<(&'static str, String), u32>>,
+    writers: Mutex<HashMap<&'static str, BufWriter<File>>>,
+}
+
+impl Recorder {
+    /// 创建录制目录（已有的同名录制文件会被覆盖）
+    ///
+    /// # 参数
+    /// * `dir` - 录制目录
+    /// * `module` - 当前模块
+    /// * `redact` - 是否替换地址
+    pub fn create(
+        dir: &Path,
+        module: &str,
+        redact: bool,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("无法创建录制目录 {}: {}", dir.display(), e))?;
+        for kind in [PING_KIND, TCP_KIND] {
+            let path = dir.join(format!("{}.jsonl", kind));
+            if path.exists() {
+                fs::remove_file(&path)
+                    .map_err(|e| format!("无法覆盖录制文件 {}: {}", path.display(), e))?;
+            }
+        }
+        let recorder = Self {
+            dir: dir.to_path_buf(),
+            meta: Mutex::new(TranscriptMeta {
+                version: TRANSCRIPT_VERSION,
+                module: module.to_string(),
+                created_at: chrono::Local::now().to_rfc3339(),
+                redacted: redact,
+                ..Default::default()
+            }),
+            redact,
+            seqs: Mutex::new(HashMap::new()),
+            writers: Mutex::new(HashMap::new()),
+        };
+        recorder.write_meta()?;
+        Ok(recorder)
+    }
+
+    /// 为一次探测分配键（同一目标的探测依次编号）
+    ///
+    /// # 参数
+    /// * `kind` - 录制类别
+    /// * `host` - 目标地址（脱敏录制时替换）
+    /// * `suffix` - 键的其余部分
+    pub fn begin(&self, kind: &'static str, host: &str, suffix: &str) -> Take {
+        let host = self.host(host);
+        let key = format!("{}{}", host, suffix);
+        let mut seqs = self.seqs.lock().unwrap();
+        let seq = seqs.entry((kind, key.clone())).or_insert(0);
+        let take = Take { key, seq: *seq };
+        *seq += 1;
+        self.meta.lock().unwrap().targets.insert(host);
+        take
+    }
+
+    /// 写入一条记录（写入失败只提示一次，不影响扫描）
+    pub fn save<T: Serialize>(&self, kind: &'static str, take: &Take, data: &T) {
+        let entry = Entry {
+            key: take.key.clone(),
+            seq: take.seq,
+            data,
+        };
+        let Ok(line) = serde_json::to_string(&entry) else {
+            return;
+        };
+        let mut writers = self.writers.lock().unwrap();
+        let writer = match writers.get_mut(kind) {
+            Some(writer) => writer,
+            None => {
+                let path = self.dir.join(format!("{}.jsonl", kind));
+                match File::create(&path) {
+                    Ok(file) => writers.entry(kind).or_insert(BufWriter::new(file)),
+                    Err(e) => {
+                        eprintln!("{} 无法写入录制文件 {}: {}", Icon::Warn, path.display(), e);
+                        return;
+                    }
+                }
+            }
+        };
+        if writeln!(writer, "{}", line).is_ok() {
+            self.meta.lock().unwrap().probes += 1;
+        }
+    }
+
+    /// 分配键并写入一条记录
+    pub fn append<T: Serialize>(&self, kind: &'static str, host: &str, suffix: &str, data: &T) {
+        let take = self.begin(kind, host, suffix);
+        self.save(kind, &take, data);
+    }
+
+    /// 要录制的原始数据（脱敏录制时替换其中的地址）
+    ///
+    /// # 参数
+    /// * `host` - 本次探测的目标（IPv6目标以原文替换）
+    /// * `data` - 原始数据
+    pub fn payload(&self, host: &str, data: &[u8]) -> Vec<u8> {
+        if !self.redact {
+            return data.to_vec();
+        }
+        let data = IPV4.replace_all(
+            data,
+            |caps: &regex::bytes::Captures| match std::str::from_utf8(&caps[0])
+                .ok()
+                .and_then(|s| s.parse().ok())
+            {
+                Some(ip) => substitute(ip).to_string().into_bytes(),
+                None => caps[0].to_vec(),
+            },
+        );
+        match host.parse::<IpAddr>() {
+            Ok(ip @ IpAddr::V6(_)) => replace_bytes(
+                &data,
+                host.as_bytes(),
+                substitute(ip).to_string().as_bytes(),
+            ),
+            _ => data.into_owned(),
+        }
+    }
+
+    /// 目标地址（脱敏录制时为替代地址，主机名原样保留）
+    fn host(&self, host: &str) -> String {
+        match host.parse::<IpAddr>() {
+            Ok(ip) if self.redact => substitute(ip).to_string(),
+            _ => host.to_string(),
+        }
+    }
+
+    fn write_meta(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let path = self.dir.join(TRANSCRIPT_META_FILE);
+        let text = serde_json::to_string_pretty(&*self.meta.lock().unwrap())?;
+        fs::write(&path, text).map_err(|e| format!("无法写入 {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    /// 写出缓冲的记录及录制说明
+    fn finish(&self) -> Result<(PathBuf, u64), Box<dyn Error + Send + Sync>> {
+        for writer in self.writers.lock().unwrap().values_mut() {
+            writer
+                .flush()
+                .map_err(|e| format!("无法写入录制文件: {}", e))?;
+        }
+        self.write_meta()?;
+        Ok((self.dir.clone(), self.meta.lock().unwrap().probes))
+    }
+}
+
+/// 录制读取器
+#[derive(Debug)]
+pub struct Player {
+    entries: Mutex<HashMap<(String, String), VecDeque<serde_json::Value>>>,
+    missing: AtomicU64,
+}
+
+impl Player {
+    /// 读取录制目录
+    ///
+    /// # 参数
+    /// * `dir` - 录制目录
+    /// * `module` - 当前模块，须与录制时一致
+    pub fn open(dir: &Path, module: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let meta_path = dir.join(TRANSCRIPT_META_FILE);
+        let meta: TranscriptMeta = serde_json::from_str(
+            &fs::read_to_string(&meta_path)
+                .map_err(|e| format!("无法读取录制说明 {}: {}", meta_path.display(), e))?,
+        )
+        .map_err(|e| format!("录制说明格式不正确 {}: {}", meta_path.display(), e))?;
+        if meta.version > TRANSCRIPT_VERSION {
+            return Err(format!(
+                "录制格式版本 {} 高于当前支持的版本，请升级后回放",
+                meta.version
+            )
+            .into());
+        }
+        if meta.module != module {
+            return Err(format!("录制来自 {}，不能在 {} 中回放", meta.module, module).into());
+        }
+
+        let mut entries: HashMap<(String, String), Vec<(u32, serde_json::Value)>> = HashMap::new();
+        for kind in [PING_KIND, TCP_KIND] {
+            let path = dir.join(format!("{}.jsonl", kind));
+            let Ok(file) = File::open(&path) else {
+                continue;
+            };
+            for (n, line) in BufReader::new(file).lines().enumerate() {
+                let line = line.map_err(|e| format!("无法读取 {}: {}", path.display(), e))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry: Entry<serde_json::Value> = serde_json::from_str(&line)
+                    .map_err(|e| format!("{} 第 {} 行格式不正确: {}", path.display(), n + 1, e))?;
+                entries
+                    .entry((kind.to_string(), entry.key))
+                    .or_default()
+                    .push((entry.seq, entry.data));
+            }
+        }
+        // 记录按完成顺序写入，按序号还原探测顺序
+        let entries = entries
+            .into_iter()
+            .map(|(key, mut list)| {
+                list.sort_by_key(|(seq, _)| *seq);
+                (key, list.into_iter().map(|(_, data)| data).collect())
+            })
+            .collect();
+        Ok(Self {
+            entries: Mutex::new(entries),
+            missing: AtomicU64::new(0),
+        })
+    }
+
+    /// 录制中的探测数
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap()
+            .values()
+            .map(VecDeque::len)
+            .sum()
+    }
+
+    /// 录制是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 按顺序取出某个目标的下一条记录（没有时计入缺失数）
+    ///
+    /// # 参数
+    /// * `kind` - 录制类别
+    /// * `host` - 目标地址
+    /// * `suffix` - 键的其余部分
+    pub fn take<T: DeserializeOwned>(&self, kind: &str, host: &str, suffix: &str) -> Option<T> {
+        let key = (kind.to_string(), format!("{}{}", host, suffix));
+        let data = self
+            .entries
+            .lock()
+            .unwrap()
+            .get_mut(&key)
+            .and_then(VecDeque::pop_front)
+            .and_then(|data| serde_json::from_value(data).ok());
+        if data.is_none() {
+            self.missing.fetch_add(1, Ordering::Relaxed);
+        }
+        data
+    }
+}
+
+/// 地址的替代地址（由原地址的哈希决定，同一地址在各次录制中一致）
+fn substitute(ip: IpAddr) -> IpAddr {
+    let digest = Sha256::digest(ip.to_string().as_bytes());
+    match ip {
+        IpAddr::V4(_) => {
+            // 198.18.0.0/15
+            let n = u32::from_be_bytes([0, digest[0] & 0x01, digest[1], digest[2]]);
+            IpAddr::V4(Ipv4Addr::from(u32::from(Ipv4Addr::new(198, 18, 0, 0)) + n))
+        }
+        IpAddr::V6(_) => {
+            let mut octets = [0u8; 16];
+            octets[..4].copy_from_slice(&[0x20, 0x01, 0x0d, 0xb8]);
+            octets[8..].copy_from_slice(&digest[..8]);
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+    }
+}
+
+/// 替换字节串中的全部子串
+fn replace_bytes(data: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut rest = data;
+    while let Some(pos) = rest.windows(from.len()).position(|w| w == from) {
+        out.extend_from_slice(&rest[..pos]);
+        out.extend_from_slice(to);
+        rest = &rest[pos + from.len()..];
+    }
+    out.extend_from_slice(rest);
+    out
+}
+
+/// 字节串按base64写入录制
+pub mod base64_bytes {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        BASE64.decode(text).map_err(serde::de::Error::custom)
+    }
+}
+
+/// IO错误（按类型还原，端口扫描据此区分关闭、不可达等）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IoErrorRecord {
+    kind: String,
+    message: String,
+}
+
+/// 录制时保留的IO错误类型，其他类型回放为 `Other`
+const IO_ERROR_KINDS: &[io::ErrorKind] = &[
+    io::ErrorKind::ConnectionRefused,
+    io::ErrorKind::ConnectionReset,
+    io::ErrorKind::ConnectionAborted,
+    io::ErrorKind::NotConnected,
+    io::ErrorKind::HostUnreachable,
+    io::ErrorKind::NetworkUnreachable,
+    io::ErrorKind::NetworkDown,
+    io::ErrorKind::AddrInUse,
+    io::ErrorKind::AddrNotAvailable,
+    io::ErrorKind::PermissionDenied,
+    io::ErrorKind::BrokenPipe,
+    io::ErrorKind::TimedOut,
+    io::ErrorKind::UnexpectedEof,
+    io::ErrorKind::InvalidInput,
+    io::ErrorKind::InvalidData,
+];
+
+impl From<&io::Error> for IoErrorRecord {
+    fn from(e: &io::Error) -> Self {
+        Self {
+            kind: format!("{:?}", e.kind()),
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<IoErrorRecord> for io::Error {
+    fn from(record: IoErrorRecord) -> Self {
+        let kind = IO_ERROR_KINDS
+            .iter()
+            .copied()
+            .find(|k| format!("{:?}", k) == record.kind)
+            .unwrap_or(io::ErrorKind::Other);
+        io::Error::new(kind, record.message)
+    }
+}
+
+/// 一次TCP连接的录制
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "connect", rename_all = "snake_case")]
+pub enum TcpTranscript {
+    /// 连接成功，之后依次收发的数据
+    Connected { events: Vec<StreamEvent> },
+    /// 连接失败
+    Failed { error: IoErrorRecord },
+    /// 连接未完成即被放弃（超时）
+    Pending,
+}
+
+/// 数据流上的一次收发
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// 收到数据
+    Read {
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
+    },
+    /// 对端关闭连接
+    Eof,
+    /// 发出数据
+    Write {
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
+    },
+    /// 读写出错
+    Error { error: IoErrorRecord },
+}
+
+/// 连接未完成时（调用方超时放弃）记录为 [`TcpTranscript::Pending`]
+struct PendingConnect<'a> {
+    recorder: &'a Recorder,
+    take: Option<Take>,
+}
+
+impl Drop for PendingConnect<'_> {
+    fn drop(&mut self) {
+        if let Some(ref take) = self.take {
+            self.recorder.save(TCP_KIND, take, &TcpTranscript::Pending);
+        }
+    }
+}
+
+/// 录制收发数据的数据流，关闭时写入录制
+pub struct RecordingStream<S> {
+    inner: S,
+    recorder: Arc<Recorder>,
+    take: Take,
+    host: String,
+    events: Vec<StreamEvent>,
+}
+
+impl<S> Drop for RecordingStream<S> {
+    fn drop(&mut self) {
+        let events = std::mem::take(&mut self.events);
+        self.recorder
+            .save(TCP_KIND, &self.take, &TcpTranscript::Connected { events });
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for RecordingStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let this = &mut *self;
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+        match poll {
+            Poll::Ready(Ok(())) if buf.filled().len() == filled => {
+                if buf.remaining() > 0 {
+                    this.events.push(StreamEvent::Eof);
+                }
+            }
+            Poll::Ready(Ok(())) => {
+                let data = this.recorder.payload(&this.host, &buf.filled()[filled..]);
+                this.events.push(StreamEvent::Read { data });
+            }
+            Poll::Ready(Err(ref e)) => this.events.push(StreamEvent::Error { error: e.into() }),
+            Poll::Pending => {}
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for RecordingStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+        match poll {
+            Poll::Ready(Ok(n)) => {
+                let data = this.recorder.payload(&this.host, &buf[..n]);
+                this.events.push(StreamEvent::Write { data });
+            }
+            Poll::Ready(Err(ref e)) => this.events.push(StreamEvent::Error { error: e.into() }),
+            Poll::Pending => {}
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// 按录制回放的数据流
+///
+/// 读取时只返回排在下一个的收到数据；录制中下一步是发出数据（实际探测时读取一直等待，
+/// 直到探测方超时后发出请求），读取保持等待，发出后再继续。录制结束后读取不再完成。
+pub struct ReplayStream {
+    events: VecDeque<StreamEvent>,
+    /// 读取的剩余部分（调用方缓冲区小于录制的数据块时）
+    partial: Vec<u8>,
+    reader: Option<Waker>,
+}
+
+impl ReplayStream {
+    fn new(events: Vec<StreamEvent>) -> Self {
+        Self {
+            events: events.into(),
+            partial: Vec::new(),
+            reader: None,
+        }
+    }
+}
+
+impl AsyncRead for ReplayStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        if this.partial.is_empty() {
+            match this.events.front() {
+                Some(StreamEvent::Read { .. }) => {
+                    let Some(StreamEvent::Read { data }) = this.events.pop_front() else {
+                        unreachable!();
+                    };
+                    this.partial = data;
+                }
+                Some(StreamEvent::Eof) => return Poll::Ready(Ok(())),
+                Some(StreamEvent::Error { error }) => {
+                    let error = error.clone();
+                    this.events.pop_front();
+                    return Poll::Ready(Err(error.into()));
+                }
+                Some(StreamEvent::Write { .. }) | None => {
+                    this.reader = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+        let n = this.partial.len().min(buf.remaining());
+        buf.put_slice(&this.partial[..n]);
+        this.partial.drain(..n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for ReplayStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let n = match this.events.front() {
+            Some(StreamEvent::Write { data }) => {
+                let n = data.len().min(buf.len());
+                this.events.pop_front();
+                n
+            }
+            Some(StreamEvent::Error { error }) if this.partial.is_empty() => {
+                let error = error.clone();
+                this.events.pop_front();
+                return Poll::Ready(Err(error.into()));
+            }
+            // 录制中没有对应的发出（探测内容有变化），照常接受
+            _ => buf.len(),
+        };
+        if let Some(waker) = this.reader.take() {
+            waker.wake();
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// 实际、录制中或回放的数据流
+pub enum TapeStream<S> {
+    /// 实际的数据流
+    Live(S),
+    /// 录制中的数据流
+    Record(RecordingStream<S>),
+    /// 回放的数据流
+    Replay(ReplayStream),
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for TapeStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Live(s) => Pin::new(s).poll_read(cx, buf),
+            Self::Record(s) => Pin::new(s).poll_read(cx, buf),
+            Self::Replay(s) => Pin::new(s).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for TapeStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Live(s) => Pin::new(s).poll_write(cx, buf),
+            Self::Record(s) => Pin::new(s).poll_write(cx, buf),
+            Self::Replay(s) => Pin::new(s).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Live(s) => Pin::new(s).poll_flush(cx),
+            Self::Record(s) => Pin::new(s).poll_flush(cx),
+            Self::Replay(s) => Pin::new(s).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Live(s) => Pin::new(s).poll_shutdown(cx),
+            Self::Record(s) => Pin::new(s).poll_shutdown(cx),
+            Self::Replay(s) => Pin::new(s).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gxr_tape_{}_{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        dir
+    }
+
+    /// 先读banner（超时）、发请求、再读应答的探测
+    async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> (Vec<u8>, Vec<u8>) {
+        let mut buf = [0u8; 64];
+        let banner =
+            match tokio::time::timeout(Duration::from_millis(50), stream.read(&mut buf)).await {
+                Ok(Ok(n)) => buf[..n].to_vec(),
+                _ => Vec::new(),
+            };
+        stream.write_all(b"GET / HTTP/1.0\r\n\r\n").await.unwrap();
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply).await.unwrap();
+        (banner, reply)
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay_is_identical() {
+        let dir = temp_dir("roundtrip");
+        let args = TranscriptArgs {
+            record: Some(dir.clone()),
+            ..Default::default()
+        };
+        let tape = Tape::open(&args, "pentest portscan").unwrap();
+
+        // 对端在收到请求后才应答，随后关闭连接
+        let (client, mut server) = tokio::io::duplex(1024);
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let _ = server.read(&mut buf).await;
+            server
+                .write_all(b"HTTP/1.0 200 OK\r\n\r\n10.1.2.3")
+                .await
+                .unwrap();
+        });
+        let mut stream = tape
+            .connect("10.0.0.1", ":80", async { Ok(client) })
+            .await
+            .unwrap();
+        let live = exchange(&mut stream).await;
+        drop(stream);
+        let refused = tape
+            .connect("10.0.0.1", ":81", async {
+                Err::<tokio::io::DuplexStream, _>(io::Error::from(io::ErrorKind::ConnectionRefused))
+            })
+            .await;
+        assert!(refused.is_err());
+        tape.finish().unwrap();
+
+        let args = TranscriptArgs {
+            replay: Some(dir.clone()),
+            ..Default::default()
+        };
+        let tape = Tape::open(&args, "pentest portscan").unwrap();
+        let never =
+            async { Err::<tokio::io::DuplexStream, _>(io::Error::other("回放时不应连接")) };
+        let mut stream = tape.connect("10.0.0.1", ":80", never).await.unwrap();
+        assert_eq!(exchange(&mut stream).await, live);
+        let never =
+            async { Err::<tokio::io::DuplexStream, _>(io::Error::other("回放时不应连接")) };
+        let refused = tape.connect("10.0.0.1", ":81", never).await.err().unwrap();
+        assert_eq!(refused.kind(), io::ErrorKind::ConnectionRefused);
+        // 录制中没有的连接不会完成
+        let never =
+            async { Err::<tokio::io::DuplexStream, _>(io::Error::other("回放时不应连接")) };
+        let missing = tokio::time::timeout(
+            Duration::from_millis(20),
+            tape.connect("10.0.0.2", ":80", never),
+        )
+        .await;
+        assert!(missing.is_err());
+
+        assert!(Tape::open(&args, "net ping").is_err());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_redacted_recording_replaces_addresses() {
+        let dir = temp_dir("redact");
+        let recorder = Recorder::create(&dir, "net ping", true).unwrap();
+        let take = recorder.begin(PING_KIND, "10.0.0.1", "");
+        let masked = substitute("10.0.0.1".parse().unwrap()).to_string();
+        assert_eq!(take.key, masked);
+        assert!(masked.starts_with("198.1"));
+
+        let payload = recorder.payload(
+            "10.0.0.1",
+            b"64 bytes from 10.0.0.1: ttl=64 via 192.168.1.1",
+        );
+        let text = String::from_utf8(payload).unwrap();
+        assert!(!text.contains("10.0.0.1") && !text.contains("192.168.1.1"));
+        assert!(text.contains(&masked));
+
+        let v6 = recorder.payload("fd00::1", b"from fd00::1: icmp_seq=1");
+        assert!(!String::from_utf8(v6).unwrap().contains("fd00::1"));
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,194 @@
+// src/utils/verify.rs
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// 复核参数（ping、portscan 共用）
+///
+/// 单次探测的结果并不可靠：偶发丢包会把存活主机判为失败，SYN代理会让被过滤的端口显示为开放。
+/// 开启 `--verify` 后，主扫描结束时挑出可疑的结果，用更多尝试次数和更长超时再探测一遍。
+#[derive(Args, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerifyArgs {
+    /// 主扫描结束后复核可疑结果，结果标注置信度（已复核/未复核）及结论是否改变
+    #[arg(long, env = "GXTOOLS_VERIFY")]
+    pub verify: bool,
+
+    /// 只复核这些类别（多个用逗号隔开，默认全部）
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "KIND")]
+    pub verify_only: Vec<VerifyKind>,
+
+    /// 基线运行目录（目录名或其前缀）：基线中存活/开放、本次失败/关闭的结果会被复核
+    #[arg(long, value_name = "RUN")]
+    pub verify_baseline: Option<String>,
+
+    /// 复核时的尝试次数，默认3
+    #[arg(long, value_name = "COUNT")]
+    pub verify_attempts: Option<u32>,
+
+    /// 复核时的超时倍数（相对主扫描的超时），默认2
+    #[arg(long, value_name = "N")]
+    pub verify_timeout_factor: Option<u32>,
+
+    /// 开放端口数不超过该值的主机，其开放端口会被复核（仅端口扫描），默认2
+    #[arg(long, value_name = "NUM")]
+    pub verify_sparse_ports: Option<usize>,
+}
+
+/// 复核时的默认尝试次数
+pub const DEFAULT_VERIFY_ATTEMPTS: u32 = 3;
+
+/// 复核时的默认超时倍数
+pub const DEFAULT_VERIFY_TIMEOUT_FACTOR: u32 = 2;
+
+/// 开放端口数不超过该值的主机视为"开放端口很少"
+pub const DEFAULT_VERIFY_SPARSE_PORTS: usize = 2;
+
+impl VerifyArgs {
+    /// 是否复核某一类结果
+    pub fn wants(&self, kind: VerifyKind) -> bool {
+        self.verify && (self.verify_only.is_empty() || self.verify_only.contains(&kind))
+    }
+
+    /// 复核时的尝试次数（至少1次）
+    pub fn attempts(&self) -> u32 {
+        self.verify_attempts
+            .unwrap_or(DEFAULT_VERIFY_ATTEMPTS)
+            .max(1)
+    }
+
+    /// 复核时的超时
+    ///
+    /// # 参数
+    /// * `timeout` - 主扫描的超时
+    pub fn timeout(&self, timeout: Duration) -> Duration {
+        timeout
+            * self
+                .verify_timeout_factor
+                .unwrap_or(DEFAULT_VERIFY_TIMEOUT_FACTOR)
+                .max(1)
+    }
+
+    /// "开放端口很少"的阈值
+    pub fn sparse_ports(&self) -> usize {
+        self.verify_sparse_ports
+            .unwrap_or(DEFAULT_VERIFY_SPARSE_PORTS)
+    }
+}
+
+/// 需要复核的结果类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifyKind {
+    /// 基线中存活（开放）、本次失败（关闭）的主机或端口（需指定 --verify-baseline）
+    Baseline,
+    /// 开放端口很少的主机上的开放端口（仅端口扫描）
+    Sparse,
+    /// 扫描期间结果反复的目标（重试后才成功、探测超时后重试）
+    Flapped,
+}
+
+/// 结果的置信度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confidence {
+    /// 经过复核
+    Verified,
+    /// 只有主扫描的一次结论
+    Unverified,
+}
+
+impl fmt::Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Confidence::Verified => "已复核",
+            Confidence::Unverified => "未复核",
+        })
+    }
+}
+
+impl Serialize for Confidence {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// 单个结果的复核标注
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Verification {
+    /// 置信度
+    pub confidence: Confidence,
+    /// 复核后结论是否改变
+    pub changed: bool,
+}
+
+impl Verification {
+    /// Excel表头
+    pub const HEADERS: [&'static str; 2] = ["置信度", "复核结论"];
+
+    /// 未复核的结果
+    pub const UNVERIFIED: Self = Self {
+        confidence: Confidence::Unverified,
+        changed: false,
+    };
+
+    /// 复核过的结果
+    ///
+    /// # 参数
+    /// * `changed` - 复核后结论是否改变
+    pub fn verified(changed: bool) -> Self {
+        Self {
+            confidence: Confidence::Verified,
+            changed,
+        }
+    }
+
+    /// Excel中的两列：置信度、结论是否改变
+    pub fn cells(verification: Option<&Self>) -> [String; 2] {
+        match verification {
+            Some(v) => [
+                v.confidence.to_string(),
+                match (v.confidence, v.changed) {
+                    (Confidence::Unverified, _) => String::new(),
+                    (_, true) => "已改变".to_string(),
+                    (_, false) => "未改变".to_string(),
+                },
+            ],
+            None => [String::new(), String::new()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_kinds_and_defaults() {
+        let mut args = VerifyArgs::default();
+        assert!(!args.wants(VerifyKind::Flapped));
+
+        args.verify = true;
+        assert!(args.wants(VerifyKind::Baseline) && args.wants(VerifyKind::Sparse));
+        assert_eq!(args.attempts(), 3);
+        assert_eq!(args.timeout(Duration::from_secs(3)), Duration::from_secs(6));
+
+        args.verify_only = vec![VerifyKind::Sparse];
+        args.verify_attempts = Some(0);
+        assert!(!args.wants(VerifyKind::Baseline) && args.wants(VerifyKind::Sparse));
+        assert_eq!(args.attempts(), 1);
+
+        assert_eq!(
+            Verification::cells(Some(&Verification::verified(true))),
+            ["已复核", "已改变"]
+        );
+        assert_eq!(
+            Verification::cells(Some(&Verification::UNVERIFIED)),
+            ["未复核", ""]
+        );
+        assert_eq!(
+            serde_json::to_value(Verification::verified(false)).unwrap(),
+            serde_json::json!({"confidence": "已复核", "changed": false})
+        );
+    }
+}
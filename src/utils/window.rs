@@ -0,0 +1,262 @@
+// src/utils/window.rs
+use super::ScanProgress;
+use super::pause::PauseGate;
+use chrono::{FixedOffset, Local, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use std::error::Error;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// 窗口状态的最长复查间隔（系统时间被调整或本地时区切换夏令时后也能及时纠正）
+const RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 没有夏令时的常用时区（名称不区分大小写）-> 与UTC的偏移（秒）
+const FIXED_ZONES: &[(&str, i32)] = &[
+    ("UTC", 0),
+    ("Etc/UTC", 0),
+    ("GMT", 0),
+    ("Asia/Shanghai", 8 * 3600),
+    ("Asia/Chongqing", 8 * 3600),
+    ("Asia/Urumqi", 6 * 3600),
+    ("Asia/Hong_Kong", 8 * 3600),
+    ("Asia/Macau", 8 * 3600),
+    ("Asia/Taipei", 8 * 3600),
+    ("Asia/Singapore", 8 * 3600),
+    ("Asia/Kuala_Lumpur", 8 * 3600),
+    ("Asia/Manila", 8 * 3600),
+    ("Asia/Tokyo", 9 * 3600),
+    ("Asia/Seoul", 9 * 3600),
+    ("Asia/Bangkok", 7 * 3600),
+    ("Asia/Jakarta", 7 * 3600),
+    ("Asia/Ho_Chi_Minh", 7 * 3600),
+    ("Asia/Kolkata", 5 * 3600 + 1800),
+    ("Asia/Calcutta", 5 * 3600 + 1800),
+    ("Asia/Dubai", 4 * 3600),
+    ("Europe/Moscow", 3 * 3600),
+];
+
+/// 允许扫描的时间窗口
+///
+/// 起始时间晚于结束时间时窗口跨越午夜（如 `22:00-06:00`）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanWindow {
+    /// 窗口开始时间（含）
+    pub start: NaiveTime,
+    /// 窗口结束时间（不含）
+    pub end: NaiveTime,
+    /// 窗口所在时区，`None` 表示本机时区
+    pub offset: Option<FixedOffset>,
+}
+
+impl ScanWindow {
+    /// 解析窗口
+    ///
+    /// # 参数
+    /// * `spec` - 窗口（如 `00:00-06:00`，结束时间可写作 `24:00`）
+    /// * `timezone` - 时区（固定偏移如 `+08:00`、`UTC+8`，或没有夏令时的时区名如 `Asia/Shanghai`），为空时使用本机时区
+    pub fn parse(spec: &str, timezone: Option<&str>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let (start, end_text) = spec
+            .split_once('-')
+            .ok_or_else(|| format!("扫描窗口格式应为 HH:MM-HH:MM: {}", spec))?;
+        let start = parse_time(start)?;
+        let end = parse_time(end_text)?;
+        // 只有 00:00-24:00 表示全天
+        if start == end && end_text.trim() != "24:00" {
+            return Err(format!("扫描窗口的开始和结束时间相同: {}", spec).into());
+        }
+        let offset = timezone.map(parse_timezone).transpose()?;
+        Ok(Self { start, end, offset })
+    }
+
+    /// 窗口所在时区的当前时间
+    pub fn now(&self) -> NaiveDateTime {
+        match self.offset {
+            Some(offset) => Utc::now().with_timezone(&offset).naive_local(),
+            None => Local::now().naive_local(),
+        }
+    }
+
+    /// 指定时间是否在窗口内
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start == self.end {
+            true
+        } else if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// 距离窗口下一次打开或关闭的时长
+    pub fn until_change(&self, now: NaiveDateTime) -> Duration {
+        let next = |time: NaiveTime| {
+            let today = now.date().and_time(time);
+            if today > now {
+                today
+            } else {
+                today + TimeDelta::days(1)
+            }
+        };
+        (next(self.start).min(next(self.end)) - now)
+            .to_std()
+            .unwrap_or_default()
+    }
+
+    /// 窗口外暂停时进度条显示的消息
+    pub fn paused_message(&self) -> String {
+        format!("⏸  窗口外暂停，将于 {} 恢复", self.start.format("%H:%M"))
+    }
+
+    /// 按当前时间暂停或恢复分发
+    ///
+    /// 在窗口外时暂停开关并在进度条显示恢复时间，回到窗口内时恢复（手动暂停的保持暂停）。
+    ///
+    /// # 返回
+    /// 下一次需要复查的等待时长
+    pub fn apply(&self, gate: &PauseGate, progress: &ScanProgress) -> Duration {
+        let now = self.now();
+        if self.contains(now.time()) {
+            gate.open_window(progress);
+        } else {
+            gate.close_window(self.paused_message(), progress);
+        }
+        self.until_change(now).min(RECHECK_INTERVAL)
+    }
+
+    /// 按窗口暂停和恢复分发，直到任务被取消
+    ///
+    /// 窗口外的时长与手动暂停一样计入暂停时长，不影响速率统计。
+    ///
+    /// # 参数
+    /// * `gate` - 暂停开关
+    /// * `progress` - 扫描进度条
+    pub async fn enforce(self, gate: PauseGate, progress: ScanProgress) {
+        loop {
+            let wait = self.apply(&gate, &progress);
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// 解析 `HH:MM`（`24:00` 视为午夜）
+fn parse_time(text: &str) -> Result<NaiveTime, Box<dyn Error + Send + Sync>> {
+    let text = text.trim();
+    if text == "24:00" {
+        return Ok(NaiveTime::MIN);
+    }
+    NaiveTime::parse_from_str(text, "%H:%M").map_err(|_| format!("无效的时间: {}", text).into())
+}
+
+/// 解析时区：`+08:00`、`+0800`、`+8`、`UTC+8`、`GMT-03:30` 或没有夏令时的时区名
+///
+/// 有夏令时的时区（如 `Europe/Berlin`）不支持，请改用本机时区或固定偏移。
+pub fn parse_timezone(text: &str) -> Result<FixedOffset, Box<dyn Error + Send + Sync>> {
+    let text = text.trim();
+    if let Some(&(_, seconds)) = FIXED_ZONES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(text))
+    {
+        return Ok(FixedOffset::east_opt(seconds).expect("偏移在有效范围内"));
+    }
+    let unsupported = || {
+        format!(
+            "不支持的时区: {}（请使用固定偏移如 +08:00，或 Asia/Shanghai 等没有夏令时的时区）",
+            text
+        )
+    };
+    let upper = text.to_ascii_uppercase();
+    let offset = upper
+        .strip_prefix("UTC")
+        .or_else(|| upper.strip_prefix("GMT"))
+        .unwrap_or(&upper);
+    let (sign, rest) = match offset.as_bytes().first() {
+        Some(b'+') => (1, &offset[1..]),
+        Some(b'-') => (-1, &offset[1..]),
+        _ => return Err(unsupported().into()),
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    let (Ok(hours), Ok(minutes)) = (hours.parse::<i32>(), minutes.parse::<i32>()) else {
+        return Err(unsupported().into());
+    };
+    if hours > 14 || minutes >= 60 {
+        return Err(unsupported().into());
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(|| unsupported().into())
+}
+
+static WINDOW: OnceLock<ScanWindow> = OnceLock::new();
+
+/// 设置全局扫描窗口（启动时由 `--window` 设置一次）
+pub fn set_window(window: ScanWindow) {
+    let _ = WINDOW.set(window);
+}
+
+/// 全局扫描窗口（未设置时不限制扫描时间）
+pub fn window() -> Option<ScanWindow> {
+    WINDOW.get().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("2024-03-01 {}", time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_window_spanning_midnight() {
+        let night = ScanWindow::parse("22:00-06:00", Some("Asia/Shanghai")).unwrap();
+        assert_eq!(night.offset, FixedOffset::east_opt(8 * 3600));
+        assert!(night.contains(at("23:30").time()));
+        assert!(night.contains(at("00:00").time()));
+        assert!(!night.contains(at("06:00").time()));
+        assert!(!night.contains(at("12:00").time()));
+        assert_eq!(
+            night.until_change(at("12:00")),
+            Duration::from_secs(10 * 3600)
+        );
+        assert_eq!(
+            night.until_change(at("23:00")),
+            Duration::from_secs(7 * 3600)
+        );
+
+        let early = ScanWindow::parse("00:00-06:00", None).unwrap();
+        assert!(early.contains(at("00:00").time()));
+        assert!(!early.contains(at("23:59").time()));
+        // 窗口刚关闭时下一次变化是次日开始
+        assert_eq!(
+            early.until_change(at("06:00")),
+            Duration::from_secs(18 * 3600)
+        );
+        assert_eq!(early.paused_message(), "⏸  窗口外暂停，将于 00:00 恢复");
+
+        let evening = ScanWindow::parse("18:00-24:00", None).unwrap();
+        assert!(evening.contains(at("23:59").time()));
+        assert!(!evening.contains(at("00:00").time()));
+
+        assert!(
+            ScanWindow::parse("00:00-24:00", None)
+                .unwrap()
+                .contains(at("13:00").time())
+        );
+        assert!(ScanWindow::parse("06:00-06:00", None).is_err());
+        assert!(ScanWindow::parse("0600", None).is_err());
+        assert!(ScanWindow::parse("25:00-06:00", None).is_err());
+    }
+
+    #[test]
+    fn test_parse_fixed_offset_timezones() {
+        let hours = |h: i32, m: i32| FixedOffset::east_opt(h * 3600 + m * 60).unwrap();
+        assert_eq!(parse_timezone("+08:00").unwrap(), hours(8, 0));
+        assert_eq!(parse_timezone("UTC+8").unwrap(), hours(8, 0));
+        assert_eq!(parse_timezone("gmt-0330").unwrap(), hours(-3, -30));
+        assert_eq!(parse_timezone("asia/kolkata").unwrap(), hours(5, 30));
+        assert_eq!(parse_timezone("UTC").unwrap(), hours(0, 0));
+        assert!(parse_timezone("Europe/Berlin").is_err());
+        assert!(parse_timezone("+15").is_err());
+    }
+}